@@ -0,0 +1,181 @@
+//! The locations of the artifacts written by `forc build`.
+//!
+//! These allow plugins and other tools to find the compiler's output for a package without
+//! hard-coding `forc`'s output layout.
+
+use crate::{PackageManifestFile, SWAY_BIN_HASH_SUFFIX, SWAY_BIN_ROOT_SUFFIX};
+use forc_util::default_output_directory;
+use std::path::{Path, PathBuf};
+
+/// The name of the directory within the output directory holding intermediate artifacts
+/// requested via the build profile's `emit-*` options.
+pub const EMIT_DIR_NAME: &str = "emit";
+
+/// The paths of the artifacts written for a single built package.
+#[derive(Clone, Debug)]
+pub struct ArtifactPaths {
+    output_dir: PathBuf,
+    pkg_name: String,
+}
+
+impl ArtifactPaths {
+    /// The artifacts for the package named `pkg_name`, written to `output_dir`.
+    pub fn new(output_dir: &Path, pkg_name: &str) -> Self {
+        Self {
+            output_dir: output_dir.to_path_buf(),
+            pkg_name: pkg_name.to_string(),
+        }
+    }
+
+    /// The artifacts for the given package built with the named build profile, at the location
+    /// `forc build` uses when no `--output-directory` is given.
+    pub fn from_manifest(manifest: &PackageManifestFile, profile_name: &str) -> Self {
+        let output_dir = default_output_directory(manifest.dir()).join(profile_name);
+        Self::new(&output_dir, &manifest.project.name)
+    }
+
+    /// The directory containing all of the artifacts.
+    pub fn output_dir(&self) -> &Path {
+        &self.output_dir
+    }
+
+    /// The bytecode binary.
+    pub fn bytecode(&self) -> PathBuf {
+        self.output_dir.join(&self.pkg_name).with_extension("bin")
+    }
+
+    /// The JSON ABI.  Only written for programs with an ABI.
+    pub fn abi(&self) -> PathBuf {
+        self.output_dir
+            .join(format!("{}-abi", self.pkg_name))
+            .with_extension("json")
+    }
+
+    /// The JSON initial storage slots.  Only written for contracts.
+    pub fn storage_slots(&self) -> PathBuf {
+        self.output_dir
+            .join(format!("{}-storage_slots", self.pkg_name))
+            .with_extension("json")
+    }
+
+    /// The JSON storage layout, giving the slots of each storage field.  Only written for
+    /// contracts.
+    pub fn storage_layout(&self) -> PathBuf {
+        self.output_dir
+            .join(format!("{}-storage_layout", self.pkg_name))
+            .with_extension("json")
+    }
+
+    /// The hash of the bytecode.  Only written for scripts.
+    pub fn bytecode_hash(&self) -> PathBuf {
+        self.output_dir
+            .join(format!("{}{SWAY_BIN_HASH_SUFFIX}", self.pkg_name))
+    }
+
+    /// The root of the bytecode.  Only written for predicates.
+    pub fn bytecode_root(&self) -> PathBuf {
+        self.output_dir
+            .join(format!("{}{SWAY_BIN_ROOT_SUFFIX}", self.pkg_name))
+    }
+
+    /// The JSON description of how the package was built.  See [crate::BuildInfo].
+    pub fn build_info(&self) -> PathBuf {
+        self.output_dir
+            .join(format!("{}-build-info", self.pkg_name))
+            .with_extension("json")
+    }
+
+    /// The JSON source map, from each instruction of the bytecode to the source it was compiled
+    /// from.  See [sway_core::source_map::SourceMap].
+    pub fn source_map(&self) -> PathBuf {
+        self.output_dir
+            .join(format!("{}-source-map", self.pkg_name))
+            .with_extension("json")
+    }
+
+    /// The JSON bytecode size and estimated gas of each function.  See [crate::FunctionCost].
+    pub fn function_costs(&self) -> PathBuf {
+        self.output_dir
+            .join(format!("{}-function-costs", self.pkg_name))
+            .with_extension("json")
+    }
+
+    /// The directory holding the artifacts requested via the `emit-*` build profile options.
+    pub fn emit_dir(&self) -> PathBuf {
+        self.output_dir.join(EMIT_DIR_NAME)
+    }
+
+    /// The optimized IR.  Only written with the `emit-ir` build profile option.
+    pub fn ir(&self) -> PathBuf {
+        self.emit_dir().join("final.ir")
+    }
+
+    /// The finalized ASM.  Only written with the `emit-asm` build profile option.
+    pub fn asm(&self) -> PathBuf {
+        self.emit_dir().join("final.asm")
+    }
+
+    /// The listing of the final bytecode.  Only written with the `emit-bytecode` build profile
+    /// option.
+    pub fn bytecode_listing(&self) -> PathBuf {
+        self.emit_dir().join("final.bytecode")
+    }
+
+    /// The index page of the IR explorer.  Only written with the `emit-explorer` build profile
+    /// option.
+    pub fn explorer_index(&self) -> PathBuf {
+        self.emit_dir().join("explorer").join("index.html")
+    }
+
+    /// The directory holding the IR written before and after passes named by the
+    /// `emit-before-passes` and `emit-after-passes` build profile options.
+    pub fn pass_dumps_dir(&self) -> PathBuf {
+        self.emit_dir().join("passes")
+    }
+}
+
+#[test]
+fn test_artifact_paths_layout() {
+    let paths = ArtifactPaths::new(Path::new("out/debug"), "counter");
+    let artifacts = [
+        (paths.bytecode(), "counter.bin"),
+        (paths.abi(), "counter-abi.json"),
+        (paths.storage_slots(), "counter-storage_slots.json"),
+        (paths.storage_layout(), "counter-storage_layout.json"),
+        (paths.bytecode_hash(), "counter-bin-hash"),
+        (paths.bytecode_root(), "counter-bin-root"),
+        (paths.build_info(), "counter-build-info.json"),
+        (paths.source_map(), "counter-source-map.json"),
+        (paths.function_costs(), "counter-function-costs.json"),
+        (paths.emit_dir(), "emit"),
+        (paths.ir(), "emit/final.ir"),
+        (paths.asm(), "emit/final.asm"),
+        (paths.bytecode_listing(), "emit/final.bytecode"),
+        (paths.explorer_index(), "emit/explorer/index.html"),
+        (paths.pass_dumps_dir(), "emit/passes"),
+    ];
+    assert_eq!(paths.output_dir(), Path::new("out/debug"));
+    for (path, expected) in artifacts {
+        assert_eq!(path, Path::new("out/debug").join(expected));
+    }
+}
+
+#[test]
+fn test_artifact_paths_from_manifest() {
+    let dir = std::env::temp_dir().join(format!("forc-artifacts-test-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(
+        dir.join("Forc.toml"),
+        "[project]\nauthors = [\"Fuel Labs\"]\nentry = \"main.sw\"\nimplicit-std = false\nlicense = \"Apache-2.0\"\nname = \"counter\"\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("src/main.sw"), "script;\n\nfn main() {}\n").unwrap();
+
+    let manifest = PackageManifestFile::from_dir(&dir).unwrap();
+    let paths = ArtifactPaths::from_manifest(&manifest, "release");
+    assert_eq!(paths.output_dir(), dir.join("out").join("release"));
+    assert_eq!(paths.bytecode(), dir.join("out/release/counter.bin"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -4,11 +4,13 @@
 //! The project should consist of one or more Sway modules under a `src` directory. It may also
 //! declare a set of forc package dependencies within its manifest.
 
+pub mod artifacts;
 pub mod lock;
 pub mod manifest;
 mod pkg;
 pub mod source;
 
+pub use artifacts::ArtifactPaths;
 pub use lock::Lock;
 pub use manifest::{
     BuildProfile, PackageManifest, PackageManifestFile, WorkspaceManifest, WorkspaceManifestFile,
@@ -9,7 +9,10 @@ use std::{
     sync::Arc,
 };
 
-use sway_core::{fuel_prelude::fuel_tx, language::parsed::TreeType, parse_tree_type, BuildTarget};
+use sway_core::{
+    fuel_prelude::fuel_tx, language::parsed::TreeType, parse_tree_type, AbiEncoding, BuildTarget,
+    OptLevel,
+};
 pub use sway_types::ConfigTimeConstant;
 use sway_utils::constants;
 
@@ -199,10 +202,11 @@ pub struct DependencyDetails {
     pub(crate) tag: Option<String>,
     pub(crate) package: Option<String>,
     pub(crate) rev: Option<String>,
+    pub(crate) registry: Option<String>,
 }
 
 /// Parameters to pass through to the `sway_core::BuildConfig` during compilation.
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "kebab-case")]
 pub struct BuildProfile {
     pub print_ast: bool,
@@ -210,11 +214,57 @@ pub struct BuildProfile {
     pub print_ir: bool,
     pub print_finalized_asm: bool,
     pub print_intermediate_asm: bool,
+    #[serde(default)]
+    pub print_ir_memory: bool,
+    #[serde(default)]
+    pub print_pass_statistics: bool,
     pub terse: bool,
     pub time_phases: bool,
     pub include_tests: bool,
     pub json_abi_with_callpaths: bool,
     pub error_on_warnings: bool,
+    #[serde(default)]
+    pub emit_ir: bool,
+    #[serde(default)]
+    pub emit_asm: bool,
+    #[serde(default)]
+    pub emit_bytecode: bool,
+    #[serde(default)]
+    pub emit_before_passes: Vec<String>,
+    #[serde(default)]
+    pub emit_after_passes: Vec<String>,
+    #[serde(default)]
+    pub emit_explorer: bool,
+    #[serde(default)]
+    pub opt_level: OptLevel,
+    /// An explicit list of IR passes to run in place of the `opt-level` preset.  Each entry may be a
+    /// comma separated pipeline, which may name a level such as `O1` for its passes.
+    #[serde(default)]
+    pub passes: Option<Vec<String>>,
+    /// Instrument the program with coverage counters, for use by `forc test --coverage`.
+    #[serde(default)]
+    pub instrument_coverage: bool,
+    /// Lower the gas checkpoints marked by `__gas_checkpoint` to logs of the remaining gas.
+    #[serde(default)]
+    pub gas_checkpoints: bool,
+    /// A file of rewrite rules for the `rewrite` IR pass, relative to the manifest directory.
+    #[serde(default)]
+    pub rewrite_rules: Option<PathBuf>,
+    /// Values for `configurable` constants, by name, to compile in as constants rather than leave
+    /// patchable.
+    #[serde(default)]
+    pub freeze_configurables: BTreeMap<String, toml::Value>,
+    /// The encoding of a contract's ABI method arguments in call data.
+    #[serde(default)]
+    pub abi_encoding: AbiEncoding,
+    /// The number of changes each iterative IR pass may make to a function, bounding the time
+    /// spent optimizing very large functions.
+    #[serde(default)]
+    pub optimization_fuel: Option<u64>,
+    /// Cache the optimized IR of each function within the output directory, reusing it while the
+    /// function and those it calls are unchanged.
+    #[serde(default)]
+    pub incremental: bool,
 }
 
 impl Dependency {
@@ -603,11 +653,28 @@ impl BuildProfile {
             print_ir: false,
             print_finalized_asm: false,
             print_intermediate_asm: false,
+            print_ir_memory: false,
+            print_pass_statistics: false,
             terse: false,
             time_phases: false,
             include_tests: false,
             json_abi_with_callpaths: false,
             error_on_warnings: false,
+            emit_ir: false,
+            emit_asm: false,
+            emit_bytecode: false,
+            emit_before_passes: vec![],
+            emit_after_passes: vec![],
+            emit_explorer: false,
+            opt_level: OptLevel::Opt1,
+            passes: None,
+            instrument_coverage: false,
+            gas_checkpoints: false,
+            rewrite_rules: None,
+            freeze_configurables: BTreeMap::new(),
+            abi_encoding: AbiEncoding::Default,
+            optimization_fuel: None,
+            incremental: false,
         }
     }
 
@@ -618,11 +685,28 @@ impl BuildProfile {
             print_ir: false,
             print_finalized_asm: false,
             print_intermediate_asm: false,
+            print_ir_memory: false,
+            print_pass_statistics: false,
             terse: false,
             time_phases: false,
             include_tests: false,
             json_abi_with_callpaths: false,
             error_on_warnings: false,
+            emit_ir: false,
+            emit_asm: false,
+            emit_bytecode: false,
+            emit_before_passes: vec![],
+            emit_after_passes: vec![],
+            emit_explorer: false,
+            opt_level: OptLevel::Opt1,
+            passes: None,
+            instrument_coverage: false,
+            gas_checkpoints: false,
+            rewrite_rules: None,
+            freeze_configurables: BTreeMap::new(),
+            abi_encoding: AbiEncoding::Default,
+            optimization_fuel: None,
+            incremental: false,
         }
     }
 }
@@ -1,4 +1,5 @@
 use crate::{
+    artifacts::ArtifactPaths,
     lock::Lock,
     manifest::{
         BuildProfile, ConfigTimeConstant, Dependency, ManifestFile, MemberManifestFiles,
@@ -24,6 +25,7 @@ use std::{
     fmt,
     fs::{self, File},
     hash::{Hash, Hasher},
+    ops::ControlFlow,
     path::{Path, PathBuf},
     str::FromStr,
 };
@@ -45,8 +47,10 @@ use sway_core::{
     },
     semantic_analysis::namespace,
     source_map::SourceMap,
+    storage_layout::StorageLayout,
     transform::AttributeKind,
-    BuildTarget, CompileResult, Engines, FinalizedEntry, TypeEngine,
+    AbiEncoding, BuildTarget, CompileResult, CoveragePoint, EmittedArtifact, Engines,
+    FinalizedEntry, FunctionSize, ModuleCache, OptLevel, TypeEngine,
 };
 use sway_error::{error::CompileError, warning::CompileWarning};
 use sway_types::{Ident, Span, Spanned};
@@ -104,6 +108,8 @@ pub struct BuiltPackage {
     pub descriptor: PackageDescriptor,
     pub program_abi: ProgramABI,
     pub storage_slots: Vec<StorageSlot>,
+    /// The slots of each storage field.  Empty for programs other than contracts.
+    pub storage_layout: StorageLayout,
     pub warnings: Vec<CompileWarning>,
     source_map: SourceMap,
     pub tree_type: TreeType,
@@ -114,6 +120,78 @@ pub struct BuiltPackage {
     ///
     /// For non-contract members, this is always `None`.
     pub bytecode_without_tests: Option<BuiltPackageBytecode>,
+    /// Intermediate compiler artifacts requested via the build profile's `emit-*` options.
+    pub emitted: Vec<EmittedArtifact>,
+    /// The coverage counters inserted when built with the `instrument-coverage` profile option.
+    pub coverage_points: Vec<CoveragePoint>,
+    /// The coverage counters of `bytecode_without_tests`, which are logged by the contract
+    /// deployed for the tests rather than by the tests themselves.
+    pub contract_coverage_points: Vec<CoveragePoint>,
+    /// The size of the bytecode generated for each function.
+    pub function_sizes: Vec<FunctionSize>,
+    /// The size of the data section at the end of the bytecode.
+    pub data_section_size: u64,
+    /// How the package was built, for reproducing the build.
+    pub build_info: BuildInfo,
+}
+
+/// Describes how a package was built, so that the build may be reproduced and the result checked
+/// against the deployed bytecode.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildInfo {
+    /// The version of the compiler used.
+    pub compiler_version: String,
+    pub build_target: BuildTarget,
+    /// The IR passes run over the program, in order.
+    pub pass_pipeline: Vec<String>,
+    /// The build profile, less the options which only affect diagnostic output.
+    pub build_profile: BuildProfile,
+    /// The hash of the lock file describing the package graph.
+    pub lock_hash: String,
+    /// The packages the package depends on, directly or not, each as `<name> <source>`.
+    pub dependencies: Vec<String>,
+    /// A hash of the compiler version, build target, pass pipeline, build profile, lock file and
+    /// dependencies.  Builds with the same fingerprint from the same source are expected to
+    /// produce identical output.
+    pub fingerprint: String,
+    /// The hash of the bytecode.
+    pub bytecode_hash: String,
+    /// The hash of the JSON ABI, if the program has an ABI.
+    pub abi_hash: Option<String>,
+}
+
+/// The bytecode size and estimated gas of a function, as written to
+/// [ArtifactPaths::function_costs].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionCost {
+    /// The name of the function in the IR, which differs between generic instantiations.
+    pub name: String,
+    /// The source file declaring the function, if known.
+    pub path: Option<PathBuf>,
+    /// The byte offsets of the function declaration within its source file.
+    pub span_start: usize,
+    pub span_end: usize,
+    /// The size of the function's bytecode.
+    pub size_in_bytes: u64,
+    /// The gas spent by a call to the function according to `sway_ir::estimate_gas`.
+    pub estimated_gas: u64,
+}
+
+impl From<&FunctionSize> for FunctionCost {
+    fn from(function: &FunctionSize) -> Self {
+        FunctionCost {
+            name: function.name.clone(),
+            path: function
+                .span
+                .as_ref()
+                .and_then(|span| span.path())
+                .map(|path| path.to_path_buf()),
+            span_start: function.span.as_ref().map_or(0, |span| span.start()),
+            span_end: function.span.as_ref().map_or(0, |span| span.end()),
+            size_in_bytes: function.size_in_bytes,
+            estimated_gas: function.estimated_gas,
+        }
+    }
 }
 
 /// The package descriptors that a `BuiltPackage` holds so that the source used for building the
@@ -145,6 +223,7 @@ pub struct PkgEntry {
 pub enum PkgEntryKind {
     Main,
     Test(PkgTestEntry),
+    Bench(PkgBenchEntry),
 }
 
 /// The possible conditions for a test result to be considered "passing".
@@ -161,6 +240,12 @@ pub struct PkgTestEntry {
     pub span: Span,
 }
 
+/// Data specific to the bench entry point.
+#[derive(Debug, Clone)]
+pub struct PkgBenchEntry {
+    pub span: Span,
+}
+
 /// The result of successfully compiling a workspace.
 ///
 /// This is a map from each member package name to its associated built package.
@@ -180,9 +265,15 @@ pub struct CompiledPackage {
     pub tree_type: TreeType,
     pub program_abi: ProgramABI,
     pub storage_slots: Vec<StorageSlot>,
+    pub storage_layout: StorageLayout,
     pub bytecode: BuiltPackageBytecode,
     pub namespace: namespace::Root,
     pub warnings: Vec<CompileWarning>,
+    pub emitted: Vec<EmittedArtifact>,
+    pub coverage_points: Vec<CoveragePoint>,
+    pub function_sizes: Vec<FunctionSize>,
+    pub data_section_size: u64,
+    pub pass_pipeline: Vec<String>,
 }
 
 /// Compiled contract dependency parts relevant to calculating a contract's ID.
@@ -259,6 +350,28 @@ pub struct PrintOpts {
     pub intermediate_asm: bool,
     /// Print the generated Sway IR (Intermediate Representation).
     pub ir: bool,
+    /// Print the memory used by the IR before, during and after optimization.
+    pub ir_memory: bool,
+    /// Print the time taken and changes made by each optimization pass.
+    pub pass_statistics: bool,
+}
+
+/// Intermediate compiler artifacts to write to the `emit` directory within the output directory.
+#[derive(Default, Clone)]
+pub struct EmitOpts {
+    /// Emit the Sway IR once all optimisation passes have run.
+    pub ir: bool,
+    /// Emit the finalized ASM.
+    pub asm: bool,
+    /// Emit a textual listing of the final bytecode.
+    pub bytecode: bool,
+    /// Emit the Sway IR as it is immediately before each run of the named passes.
+    pub before_passes: Vec<String>,
+    /// Emit the Sway IR as it is immediately after each run of the named passes.
+    pub after_passes: Vec<String>,
+    /// Emit the IR explorer, a set of HTML pages showing each function's control flow graph,
+    /// dominator tree and IR after every pass, linked to the source and the ASM.
+    pub explorer: bool,
 }
 
 #[derive(Default, Clone)]
@@ -275,11 +388,12 @@ type ConstName = String;
 type ConstInjectionMap = HashMap<Pinned, Vec<(ConstName, ConfigTimeConstant)>>;
 
 /// The set of options provided to the `build` functions.
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct BuildOpts {
     pub pkg: PkgOpts,
     pub print: PrintOpts,
     pub minify: MinifyOpts,
+    pub emit: EmitOpts,
     /// If set, outputs a binary file representing the script bytes.
     pub binary_outfile: Option<String>,
     /// If set, outputs source file mapping in JSON format
@@ -299,6 +413,17 @@ pub struct BuildOpts {
     pub error_on_warnings: bool,
     /// Include all test functions within the build.
     pub tests: bool,
+    /// Instrument the built members with coverage counters.
+    pub instrument_coverage: bool,
+    /// Compile everything afresh, even if the build profile enables `incremental` compilation.
+    pub disable_incremental: bool,
+    /// Run only the IR passes required to produce code, whatever the build profile's `opt-level`,
+    /// e.g., so that variables remain in memory for a debugger.
+    pub unoptimized: bool,
+    /// If set, the storage layout of each contract built is checked against a previous storage
+    /// layout artifact, either the given file or the contract's artifact within the given
+    /// directory.  The build fails if they're incompatible.
+    pub check_storage_layout: Option<PathBuf>,
     /// List of constants to inject for each package.
     pub const_inject_map: ConstInjectionMap,
     /// The set of options to filter by member project kind.
@@ -306,14 +431,18 @@ pub struct BuildOpts {
 }
 
 /// The set of options to filter type of projects to build in a workspace.
+#[derive(Clone)]
 pub struct MemberFilter {
     pub build_contracts: bool,
     pub build_scripts: bool,
     pub build_predicates: bool,
     pub build_libraries: bool,
+    /// The names of the members to build.  If empty, all members are built.
+    pub members: Vec<String>,
 }
 
 /// Contains the lexed, parsed, and typed compilation stages of a program.
+#[derive(Clone)]
 pub struct Programs {
     pub lexed: LexedProgram,
     pub parsed: ParseProgram,
@@ -327,6 +456,7 @@ impl Default for MemberFilter {
             build_scripts: true,
             build_predicates: true,
             build_libraries: true,
+            members: vec![],
         }
     }
 }
@@ -339,6 +469,7 @@ impl MemberFilter {
             build_scripts: true,
             build_predicates: false,
             build_libraries: false,
+            members: vec![],
         }
     }
 
@@ -349,6 +480,7 @@ impl MemberFilter {
             build_scripts: false,
             build_predicates: false,
             build_libraries: false,
+            members: vec![],
         }
     }
 
@@ -364,6 +496,9 @@ impl MemberFilter {
             .into_iter()
             .filter(|&node_ix| {
                 let pkg = &graph[node_ix];
+                if !self.members.is_empty() && !self.members.contains(&pkg.name) {
+                    return false;
+                }
                 let pkg_manifest = &manifest_map[&pkg.id()];
                 let program_type = pkg_manifest.program_type();
                 // Since parser cannot recover for program type detection, for the scenerios that
@@ -413,6 +548,69 @@ impl Edge {
     }
 }
 
+impl BuildInfo {
+    /// Describe the build of `node` within `plan` made with the current compiler.
+    pub fn new(
+        build_target: BuildTarget,
+        pass_pipeline: Vec<String>,
+        build_profile: &BuildProfile,
+        plan: &BuildPlan,
+        node: NodeIx,
+        bytecode: &[u8],
+        program_abi: &ProgramABI,
+    ) -> Result<Self> {
+        let compiler_version = env!("CARGO_PKG_VERSION").to_string();
+        let build_profile = BuildProfile {
+            print_ast: false,
+            print_dca_graph: false,
+            print_ir: false,
+            print_finalized_asm: false,
+            print_intermediate_asm: false,
+            print_ir_memory: false,
+            print_pass_statistics: false,
+            terse: false,
+            time_phases: false,
+            incremental: false,
+            ..build_profile.clone()
+        };
+        let graph = plan.graph();
+        let lock_hash = format!(
+            "0x{}",
+            fuel_crypto::Hasher::hash(serde_json::to_vec(&Lock::from_graph(graph))?)
+        );
+        let mut dependencies = Dfs::new(graph, node)
+            .iter(graph)
+            .filter(|dep_node| *dep_node != node)
+            .map(|dep_node| format!("{} {}", graph[dep_node].name, graph[dep_node].source))
+            .collect::<Vec<_>>();
+        dependencies.sort();
+        let fingerprint = fuel_crypto::Hasher::hash(serde_json::to_vec(&(
+            &compiler_version,
+            build_target,
+            &pass_pipeline,
+            &build_profile,
+            &lock_hash,
+            &dependencies,
+        ))?);
+        let abi_json = match program_abi {
+            ProgramABI::Fuel(abi) if !abi.functions.is_empty() => Some(serde_json::to_vec(abi)?),
+            ProgramABI::Evm(abi) if !abi.is_empty() => Some(serde_json::to_vec(abi)?),
+            _ => None,
+        };
+        Ok(Self {
+            compiler_version,
+            build_target,
+            pass_pipeline,
+            build_profile,
+            lock_hash,
+            dependencies,
+            fingerprint: format!("0x{fingerprint}"),
+            bytecode_hash: format!("0x{}", fuel_crypto::Hasher::hash(bytecode)),
+            abi_hash: abi_json.map(|json| format!("0x{}", fuel_crypto::Hasher::hash(json))),
+        })
+    }
+}
+
 impl BuiltPackage {
     /// Writes bytecode of the BuiltPackage to the given `path`.
     pub fn write_bytecode(&self, path: &Path) -> Result<()> {
@@ -420,6 +618,11 @@ impl BuiltPackage {
         Ok(())
     }
 
+    /// The mapping from the package's bytecode to its source code.
+    pub fn source_map(&self) -> &SourceMap {
+        &self.source_map
+    }
+
     /// Writes debug_info (source_map) of the BuiltPackage to the given `path`.
     pub fn write_debug_info(&self, path: &Path) -> Result<()> {
         let source_map_json =
@@ -439,12 +642,10 @@ impl BuiltPackage {
             fs::create_dir_all(output_dir)?;
         }
         // Place build artifacts into the output directory.
-        let bin_path = output_dir.join(pkg_name).with_extension("bin");
+        let paths = ArtifactPaths::new(output_dir, pkg_name);
+        self.write_bytecode(&paths.bytecode())?;
 
-        self.write_bytecode(&bin_path)?;
-
-        let program_abi_stem = format!("{pkg_name}-abi");
-        let program_abi_path = output_dir.join(program_abi_stem).with_extension("json");
+        let program_abi_path = paths.abi();
         match &self.program_abi {
             ProgramABI::Fuel(program_abi) => {
                 if !program_abi.functions.is_empty() {
@@ -477,37 +678,61 @@ impl BuiltPackage {
         match self.tree_type {
             TreeType::Contract => {
                 // For contracts, emit a JSON file with all the initialized storage slots.
-                let storage_slots_stem = format!("{pkg_name}-storage_slots");
-                let storage_slots_path = output_dir.join(storage_slots_stem).with_extension("json");
-                let storage_slots_file = File::create(storage_slots_path)?;
+                let storage_slots_file = File::create(paths.storage_slots())?;
                 let res = if minify.json_storage_slots {
                     serde_json::to_writer(&storage_slots_file, &self.storage_slots)
                 } else {
                     serde_json::to_writer_pretty(&storage_slots_file, &self.storage_slots)
                 };
-
                 res?;
+
+                // Along with the layout of the storage fields, for checking upgrades against.
+                let storage_layout_file = File::create(paths.storage_layout())?;
+                serde_json::to_writer_pretty(&storage_layout_file, &self.storage_layout)?;
             }
             TreeType::Predicate => {
                 // Get the root hash of the bytecode for predicates and store the result in a file in the output directory
                 let root = format!("0x{}", Contract::root_from_code(&self.bytecode.bytes));
-                let root_file_name = format!("{}{}", &pkg_name, SWAY_BIN_ROOT_SUFFIX);
-                let root_path = output_dir.join(root_file_name);
-                fs::write(root_path, &root)?;
+                fs::write(paths.bytecode_root(), &root)?;
                 info!("      Predicate root: {}", root);
             }
             TreeType::Script => {
                 // hash the bytecode for scripts and store the result in a file in the output directory
                 let bytecode_hash =
                     format!("0x{}", fuel_crypto::Hasher::hash(&self.bytecode.bytes));
-                let hash_file_name = format!("{}{}", &pkg_name, SWAY_BIN_HASH_SUFFIX);
-                let hash_path = output_dir.join(hash_file_name);
-                fs::write(hash_path, &bytecode_hash)?;
+                fs::write(paths.bytecode_hash(), &bytecode_hash)?;
                 info!("      Bytecode hash: {}", bytecode_hash);
             }
             _ => (),
         }
 
+        self.write_emitted(&paths.emit_dir())?;
+
+        self.write_debug_info(&paths.source_map())?;
+
+        let build_info_file = File::create(paths.build_info())?;
+        serde_json::to_writer_pretty(&build_info_file, &self.build_info)?;
+
+        let function_costs = self
+            .function_sizes
+            .iter()
+            .map(FunctionCost::from)
+            .collect::<Vec<_>>();
+        let function_costs_file = File::create(paths.function_costs())?;
+        serde_json::to_writer_pretty(&function_costs_file, &function_costs)?;
+
+        Ok(())
+    }
+
+    /// Writes any intermediate artifacts captured during compilation to `emit_dir`.
+    pub fn write_emitted(&self, emit_dir: &Path) -> Result<()> {
+        for artifact in &self.emitted {
+            let path = emit_dir.join(&artifact.path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, &artifact.contents)?;
+        }
         Ok(())
     }
 }
@@ -1497,7 +1722,48 @@ pub fn sway_build_config(
     .print_finalized_asm(build_profile.print_finalized_asm)
     .print_intermediate_asm(build_profile.print_intermediate_asm)
     .print_ir(build_profile.print_ir)
-    .include_tests(build_profile.include_tests);
+    .print_ir_memory(build_profile.print_ir_memory)
+    .print_pass_statistics(build_profile.print_pass_statistics)
+    .include_tests(build_profile.include_tests)
+    .instrument_coverage(build_profile.instrument_coverage)
+    .gas_checkpoints(build_profile.gas_checkpoints)
+    .rewrite_rules(
+        build_profile
+            .rewrite_rules
+            .as_ref()
+            .map(|path| manifest_dir.join(path)),
+    )
+    .abi_encoding(build_profile.abi_encoding)
+    .optimization_fuel(build_profile.optimization_fuel)
+    .freeze_configurables(
+        build_profile
+            .freeze_configurables
+            .iter()
+            .map(|(name, value)| {
+                // Strings are passed through unquoted, everything else as its TOML literal.
+                let value = match value {
+                    toml::Value::String(string) => string.clone(),
+                    value => value.to_string(),
+                };
+                (name.clone(), value)
+            })
+            .collect(),
+    )
+    .ir_cache_dir(
+        build_profile
+            .incremental
+            .then(|| default_output_directory(manifest_dir).join("cache")),
+    )
+    .opt_level(build_profile.opt_level)
+    .passes(build_profile.passes.clone())
+    .emit(sway_core::EmitConfig {
+        ir: build_profile.emit_ir,
+        asm: build_profile.emit_asm,
+        before_passes: build_profile.emit_before_passes.clone(),
+        after_passes: build_profile.emit_after_passes.clone(),
+        explorer: build_profile.emit_explorer,
+        function: None,
+    });
     Ok(build_config)
 }
 
@@ -1742,6 +2008,7 @@ pub fn compile(
     }
 
     let storage_slots = typed_program.storage_slots.clone();
+    let storage_layout = typed_program.storage_layout.clone();
     let tree_type = typed_program.kind.tree_type();
 
     let namespace = typed_program.root.namespace.clone().into();
@@ -1764,6 +2031,7 @@ pub fn compile(
                     &mut JsonAbiContext {
                         program: typed_program,
                         json_abi_with_callpaths: profile.json_abi_with_callpaths,
+                        ir_abi: asm_res.value.as_ref().map(|asm| &asm.0.ir_abi),
                     },
                     engines.te(),
                     engines.de(),
@@ -1800,6 +2068,26 @@ pub fn compile(
         .as_ref()
         .map(|asm| asm.0.entries.clone())
         .unwrap_or_default();
+    let mut emitted = asm_res
+        .value
+        .as_ref()
+        .map(|asm| asm.0.emitted.clone())
+        .unwrap_or_default();
+    let coverage_points = asm_res
+        .value
+        .as_ref()
+        .map(|asm| asm.0.coverage_points.clone())
+        .unwrap_or_default();
+    let function_sizes = asm_res
+        .value
+        .as_ref()
+        .map(|asm| asm.0.function_sizes.clone())
+        .unwrap_or_default();
+    let pass_pipeline = asm_res
+        .value
+        .as_ref()
+        .map(|asm| asm.0.pass_pipeline.clone())
+        .unwrap_or_default();
     let decl_engine = engines.de();
     let entries = entries
         .iter()
@@ -1835,6 +2123,14 @@ pub fn compile(
         }
     }
 
+    if profile.emit_bytecode {
+        emitted.push(EmittedArtifact {
+            path: PathBuf::from("final.bytecode"),
+            contents: bytecode_listing(&compiled.bytecode),
+        });
+    }
+
+    let data_section_size = compiled.data_section_size;
     let bytecode = BuiltPackageBytecode {
         bytes: compiled.bytecode,
         entries,
@@ -1843,14 +2139,35 @@ pub fn compile(
         source_map: source_map.clone(),
         program_abi,
         storage_slots,
+        storage_layout,
         tree_type,
         bytecode,
         namespace,
         warnings: bc_res.warnings,
+        emitted,
+        coverage_points,
+        function_sizes,
+        data_section_size,
+        pass_pipeline,
     };
     Ok(compiled_package)
 }
 
+/// Produce a human readable listing of the given bytecode, one instruction per line prefixed with
+/// its byte offset.
+fn bytecode_listing(bytes: &[u8]) -> String {
+    // Fuel VM instructions are all 4 bytes wide.
+    const INSTRUCTION_SIZE: usize = 4;
+    bytes
+        .chunks(INSTRUCTION_SIZE)
+        .enumerate()
+        .map(|(idx, instr)| {
+            let hex = instr.iter().map(|b| format!("{b:02x}")).collect::<String>();
+            format!("{:08x}: {hex}\n", idx * INSTRUCTION_SIZE)
+        })
+        .collect()
+}
+
 impl PkgEntry {
     /// Returns whether this `PkgEntry` corresponds to a test.
     pub fn is_test(&self) -> bool {
@@ -1862,6 +2179,11 @@ impl PkgEntry {
         decl_engine: &DeclEngine,
     ) -> Result<Self> {
         let pkg_entry_kind = match &finalized_entry.test_decl_ref {
+            Some(bench_decl_ref) if decl_engine.get_function(bench_decl_ref).is_bench() => {
+                PkgEntryKind::Bench(PkgBenchEntry {
+                    span: bench_decl_ref.span(),
+                })
+            }
             Some(test_decl_ref) => {
                 let pkg_test_entry = PkgTestEntry::from_decl(test_decl_ref.clone(), decl_engine)?;
                 PkgEntryKind::Test(pkg_test_entry)
@@ -1884,6 +2206,14 @@ impl PkgEntryKind {
             _ => None,
         }
     }
+
+    /// Returns `Some` if the `PkgEntryKind` is `Bench`.
+    pub fn bench(&self) -> Option<&PkgBenchEntry> {
+        match self {
+            PkgEntryKind::Bench(bench) => Some(bench),
+            _ => None,
+        }
+    }
 }
 
 impl PkgTestEntry {
@@ -1931,10 +2261,14 @@ fn build_profile_from_opts(
     let BuildOpts {
         pkg,
         print,
+        emit,
         build_profile,
         release,
         time_phases,
         tests,
+        instrument_coverage,
+        disable_incremental,
+        unoptimized,
         error_on_warnings,
         ..
     } = build_options;
@@ -1974,13 +2308,31 @@ fn build_profile_from_opts(
     profile.print_ast |= print.ast;
     profile.print_dca_graph |= print.dca_graph;
     profile.print_ir |= print.ir;
+    profile.print_ir_memory |= print.ir_memory;
+    profile.print_pass_statistics |= print.pass_statistics;
     profile.print_finalized_asm |= print.finalized_asm;
     profile.print_intermediate_asm |= print.intermediate_asm;
     profile.terse |= pkg.terse;
     profile.time_phases |= time_phases;
     profile.include_tests |= tests;
+    profile.instrument_coverage |= instrument_coverage;
+    profile.incremental &= !disable_incremental;
+    if *unoptimized {
+        profile.opt_level = OptLevel::Opt0;
+        profile.passes = None;
+    }
     profile.json_abi_with_callpaths |= pkg.json_abi_with_callpaths;
     profile.error_on_warnings |= error_on_warnings;
+    profile.emit_ir |= emit.ir;
+    profile.emit_asm |= emit.asm;
+    profile.emit_bytecode |= emit.bytecode;
+    profile.emit_explorer |= emit.explorer;
+    profile
+        .emit_before_passes
+        .extend(emit.before_passes.iter().cloned());
+    profile
+        .emit_after_passes
+        .extend(emit.after_passes.iter().cloned());
 
     Ok((selected_build_profile.to_string(), profile))
 }
@@ -2002,6 +2354,7 @@ pub fn build_with_options(build_options: BuildOpts) -> Result<Built> {
         const_inject_map,
         build_target,
         member_filter,
+        check_storage_layout,
         ..
     } = &build_options;
 
@@ -2036,6 +2389,13 @@ pub fn build_with_options(build_options: BuildOpts) -> Result<Built> {
         None => build_plan.member_nodes().collect(),
     };
 
+    if let Some(name) = member_filter
+        .members
+        .iter()
+        .find(|name| build_plan.find_member_index(name).is_none())
+    {
+        bail!("No member named `{name}` was found");
+    }
     let outputs = member_filter.filter_outputs(&build_plan, outputs);
 
     // Build it!
@@ -2068,6 +2428,9 @@ pub fn build_with_options(build_options: BuildOpts) -> Result<Built> {
         if let Some(outfile) = &debug_outfile {
             built_package.write_debug_info(outfile.as_ref())?;
         }
+        if let (Some(path), TreeType::Contract) = (check_storage_layout, &built_package.tree_type) {
+            check_storage_layout_compatibility(&built_package, &pkg_manifest.project.name, path)?;
+        }
         built_package.write_output(minify.clone(), &pkg_manifest.project.name, &output_dir)?;
         built_workspace.insert(pinned.name.clone(), built_package);
     }
@@ -2083,6 +2446,36 @@ pub fn build_with_options(build_options: BuildOpts) -> Result<Built> {
     }
 }
 
+/// Check the storage layout of a built contract against a previous storage layout artifact at
+/// `path`, or within the directory at `path`.
+fn check_storage_layout_compatibility(
+    built_package: &BuiltPackage,
+    pkg_name: &str,
+    path: &Path,
+) -> Result<()> {
+    let path = match path.is_dir() {
+        true => ArtifactPaths::new(path, pkg_name).storage_layout(),
+        false => path.to_path_buf(),
+    };
+    let file = File::open(&path)
+        .map_err(|e| anyhow!("failed to open storage layout {}: {e}", path.display()))?;
+    let previous: StorageLayout = serde_json::from_reader(file)
+        .map_err(|e| anyhow!("failed to parse storage layout {}: {e}", path.display()))?;
+    let incompatibilities = built_package.storage_layout.incompatibilities(&previous);
+    if !incompatibilities.is_empty() {
+        let list = incompatibilities
+            .iter()
+            .map(|incompatibility| format!("  - {incompatibility}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        bail!(
+            "the storage layout of `{pkg_name}` is incompatible with {}:\n{list}",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
 fn print_pkg_summary_header(built_pkg: &BuiltPackage) {
     let prog_ty_str = forc_util::program_type_str(&built_pkg.tree_type);
     // The ansi_term formatters ignore the `std::fmt` right-align
@@ -2193,7 +2586,7 @@ pub fn build(
         let is_contract_dependency = is_contract_dependency(plan.graph(), node);
         // If we are building a contract and tests are enabled or we are building a contract
         // dependency, we need the tests exlcuded bytecode.
-        let bytecode_without_tests = if (include_tests
+        let (bytecode_without_tests, contract_coverage_points) = if (include_tests
             && matches!(manifest.program_type(), Ok(TreeType::Contract)))
             || is_contract_dependency
         {
@@ -2203,8 +2596,12 @@ pub fn build(
             //   1. Interpreter deployment in `forc-test`.
             //   2. Contract ID injection in `forc-pkg` if this is a contract dependency to any
             //      other pkg, so that injected contract id is not effected by the tests.
+            //
+            // The deployed contract is instrumented along with its tests, so that the coverage of
+            // the methods the tests call is collected too.
             let profile = BuildProfile {
                 include_tests: false,
+                instrument_coverage: profile.instrument_coverage && !is_contract_dependency,
                 ..profile.clone()
             };
 
@@ -2253,9 +2650,12 @@ pub fn build(
                 const_inject_map.insert(pkg.clone(), constant_declarations);
             }
 
-            Some(compiled_without_tests.bytecode)
+            (
+                Some(compiled_without_tests.bytecode),
+                compiled_without_tests.coverage_points,
+            )
         } else {
-            None
+            (None, vec![])
         };
 
         let constants = const_inject_map
@@ -2271,10 +2671,14 @@ pub fn build(
             })
             .unwrap_or_else(|| manifest.config_time_constants());
 
-        // Build all non member nodes with tests disabled by overriding the current profile.
+        // Build all non member nodes with tests and coverage disabled, their configurables left
+        // patchable and the default ABI encoding, by overriding the current profile.
         let profile = if !plan.member_nodes().any(|member| member == node) {
             BuildProfile {
                 include_tests: false,
+                instrument_coverage: false,
+                freeze_configurables: BTreeMap::new(),
+                abi_encoding: AbiEncoding::Default,
                 ..profile.clone()
             }
         } else {
@@ -2312,15 +2716,31 @@ pub fn build(
             standardize_json_abi_types(program_abi);
         }
 
+        let build_info = BuildInfo::new(
+            descriptor.target,
+            compiled.pass_pipeline,
+            &profile,
+            plan,
+            node,
+            &compiled.bytecode.bytes,
+            &compiled.program_abi,
+        )?;
         let built_pkg = BuiltPackage {
             descriptor,
             program_abi: compiled.program_abi,
             storage_slots: compiled.storage_slots,
+            storage_layout: compiled.storage_layout,
             source_map: compiled.source_map,
             tree_type: compiled.tree_type,
             bytecode: compiled.bytecode,
             warnings: compiled.warnings,
             bytecode_without_tests,
+            emitted: compiled.emitted,
+            coverage_points: compiled.coverage_points,
+            contract_coverage_points,
+            function_sizes: compiled.function_sizes,
+            data_section_size: compiled.data_section_size,
+            build_info,
         };
 
         if outputs.contains(&node) {
@@ -2471,15 +2891,118 @@ pub fn check(
     include_tests: bool,
     engines: Engines<'_>,
 ) -> anyhow::Result<Vec<CompileResult<Programs>>> {
-    let mut lib_namespace_map = Default::default();
+    check_incremental(
+        plan,
+        build_target,
+        terse_mode,
+        include_tests,
+        engines,
+        &mut CheckCache::default(),
+        |_, _| ControlFlow::Continue(()),
+    )
+}
+
+/// The packages checked by previous calls to [check_incremental], which are reused by later calls
+/// as long as neither their sources nor those of their dependencies have changed.
+///
+/// Within a package that is checked again, only the modules whose sources changed are parsed
+/// again.
+///
+/// The cached programs refer to the type and declaration engines they were checked with, so the
+/// cache must be cleared along with the engines.
+#[derive(Default)]
+pub struct CheckCache {
+    packages: HashMap<PinnedId, CachedCheck>,
+    modules: ModuleCache,
+}
+
+struct CachedCheck {
+    /// A hash of the package's source files and the fingerprints of its dependencies.
+    fingerprint: u64,
+    result: CompileResult<Programs>,
+    /// The namespace provided to dependents, for libraries.
+    namespace: Option<namespace::Module>,
+}
+
+impl fmt::Debug for CheckCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CheckCache")
+            .field("packages", &self.packages.keys().collect::<Vec<_>>())
+            .field("modules", &self.modules.len())
+            .finish()
+    }
+}
+
+impl CheckCache {
+    /// Forget all of the checked packages, so that they are checked again by the next call.
+    pub fn clear(&mut self) {
+        self.packages.clear();
+        self.modules.clear();
+    }
+
+    /// The fingerprint of the package's cached check, if it was checked successfully.
+    ///
+    /// Unless the cache is cleared in between, this only changes when the package is checked
+    /// again, so it can be used to tell whether anything derived from the check is up to date.
+    pub fn fingerprint(&self, pkg_id: &PinnedId) -> Option<u64> {
+        self.packages.get(pkg_id).map(|cached| cached.fingerprint)
+    }
+}
+
+/// Like [check], but packages which were checked successfully by a previous call with the same
+/// `cache` are reused rather than checked again, unless their sources or those of their
+/// dependencies have changed since.
+///
+/// `on_checked` is called with the result of each package as soon as it's available, in
+/// compilation order.  Checking stops early if it returns [ControlFlow::Break], in which case the
+/// results of the packages checked so far are returned.
+pub fn check_incremental(
+    plan: &BuildPlan,
+    build_target: BuildTarget,
+    terse_mode: bool,
+    include_tests: bool,
+    engines: Engines<'_>,
+    cache: &mut CheckCache,
+    mut on_checked: impl FnMut(&Pinned, &CompileResult<Programs>) -> ControlFlow<()>,
+) -> anyhow::Result<Vec<CompileResult<Programs>>> {
+    let mut lib_namespace_map = HashMap::new();
     let mut source_map = SourceMap::new();
     // During `check`, we don't compile so this stays empty.
     let compiled_contract_deps = HashMap::new();
+    let mut fingerprints = HashMap::new();
 
     let mut results = vec![];
     for &node in plan.compilation_order.iter() {
         let pkg = &plan.graph[node];
         let manifest = &plan.manifest_map()[&pkg.id()];
+
+        let mut hasher = hash_map::DefaultHasher::default();
+        (build_target, terse_mode, include_tests).hash(&mut hasher);
+        hash_package_sources(pkg, manifest, &mut hasher);
+        for dep_node in plan.graph.neighbors_directed(node, Direction::Outgoing) {
+            fingerprints.get(&dep_node).hash(&mut hasher);
+        }
+        let fingerprint = hasher.finish();
+        fingerprints.insert(node, fingerprint);
+
+        if let Some(cached) = cache
+            .packages
+            .get(&pkg.id())
+            .filter(|cached| cached.fingerprint == fingerprint)
+        {
+            if let Some(namespace) = &cached.namespace {
+                lib_namespace_map.insert(node, namespace.clone());
+            }
+            source_map.insert_dependency(manifest.dir());
+            let result = cached.result.clone();
+            let flow = on_checked(pkg, &result);
+            results.push(result);
+            if flow.is_break() {
+                return Ok(results);
+            }
+            continue;
+        }
+
         let constants = manifest.config_time_constants();
         let dep_namespace = dependency_namespace(
             &lib_namespace_map,
@@ -2495,11 +3018,20 @@ pub fn check(
             value,
             mut warnings,
             mut errors,
-        } = parse(manifest, build_target, terse_mode, include_tests, engines)?;
+        } = parse_with_cache(
+            manifest,
+            build_target,
+            terse_mode,
+            include_tests,
+            engines,
+            &mut cache.modules,
+        )?;
 
         let (lexed, parsed) = match value {
             None => {
-                results.push(CompileResult::new(None, warnings, errors));
+                let result = CompileResult::new(None, warnings, errors);
+                let _ = on_checked(pkg, &result);
+                results.push(result);
                 return Ok(results);
             }
             Some(modules) => modules,
@@ -2512,31 +3044,50 @@ pub fn check(
         let typed_program = match ast_result.value {
             None => {
                 let value = Some(Programs::new(lexed, parsed, None));
-                results.push(CompileResult::new(value, warnings, errors));
+                let result = CompileResult::new(value, warnings, errors);
+                let _ = on_checked(pkg, &result);
+                results.push(result);
                 return Ok(results);
             }
             Some(typed_program) => typed_program,
         };
 
-        if let TreeType::Library = typed_program.kind.tree_type() {
-            let mut namespace = typed_program.root.namespace.clone();
-            namespace.name = Some(Ident::new_no_span(pkg.name.clone()));
-            namespace.span = Some(
-                Span::new(
-                    manifest.entry_string()?,
-                    0,
-                    0,
-                    Some(manifest.entry_path().into()),
-                )
-                .unwrap(),
-            );
-            lib_namespace_map.insert(node, namespace);
-        }
+        let namespace = match typed_program.kind.tree_type() {
+            TreeType::Library => {
+                let mut namespace = typed_program.root.namespace.clone();
+                namespace.name = Some(Ident::new_no_span(pkg.name.clone()));
+                namespace.span = Some(
+                    Span::new(
+                        manifest.entry_string()?,
+                        0,
+                        0,
+                        Some(manifest.entry_path().into()),
+                    )
+                    .unwrap(),
+                );
+                lib_namespace_map.insert(node, namespace.clone());
+                Some(namespace)
+            }
+            _ => None,
+        };
 
         source_map.insert_dependency(manifest.dir());
 
         let value = Some(Programs::new(lexed, parsed, Some(typed_program)));
-        results.push(CompileResult::new(value, warnings, errors));
+        let result = CompileResult::new(value, warnings, errors);
+        cache.packages.insert(
+            pkg.id(),
+            CachedCheck {
+                fingerprint,
+                result: result.clone(),
+                namespace,
+            },
+        );
+        let flow = on_checked(pkg, &result);
+        results.push(result);
+        if flow.is_break() {
+            return Ok(results);
+        }
     }
 
     if results.is_empty() {
@@ -2546,6 +3097,28 @@ pub fn check(
     Ok(results)
 }
 
+/// Hash the manifest and Sway source files of the package, in a stable order.
+///
+/// Git and registry packages are identified by their pinned source, which fixes their contents,
+/// so only the files of members and path dependencies are looked at. Those are hashed by their
+/// size and modification time, so that unchanged files are not read on every check.
+fn hash_package_sources(pkg: &Pinned, manifest: &PackageManifestFile, hasher: &mut impl Hasher) {
+    match pkg.source {
+        source::Pinned::Git(_) | source::Pinned::Registry(_) => pkg.source.hash(hasher),
+        source::Pinned::Member(_) | source::Pinned::Path(_) => {
+            let mut paths = sway_utils::helpers::get_sway_files(manifest.dir().to_path_buf());
+            paths.push(manifest.path().to_path_buf());
+            paths.sort();
+            for path in paths {
+                let stamp = fs::metadata(&path)
+                    .ok()
+                    .map(|metadata| (metadata.len(), metadata.modified().ok()));
+                (path, stamp).hash(hasher);
+            }
+        }
+    }
+}
+
 /// Returns a parsed AST from the supplied [PackageManifestFile]
 pub fn parse(
     manifest: &PackageManifestFile,
@@ -2553,6 +3126,25 @@ pub fn parse(
     terse_mode: bool,
     include_tests: bool,
     engines: Engines<'_>,
+) -> anyhow::Result<CompileResult<(LexedProgram, ParseProgram)>> {
+    parse_with_cache(
+        manifest,
+        build_target,
+        terse_mode,
+        include_tests,
+        engines,
+        &mut ModuleCache::default(),
+    )
+}
+
+/// Like [parse], but reuses the modules of `cache` whose sources are unchanged.
+fn parse_with_cache(
+    manifest: &PackageManifestFile,
+    build_target: BuildTarget,
+    terse_mode: bool,
+    include_tests: bool,
+    engines: Engines<'_>,
+    cache: &mut ModuleCache,
 ) -> anyhow::Result<CompileResult<(LexedProgram, ParseProgram)>> {
     let profile = BuildProfile {
         terse: terse_mode,
@@ -2566,7 +3158,12 @@ pub fn parse(
         &profile,
     )?
     .include_tests(include_tests);
-    Ok(sway_core::parse(source, engines, Some(&sway_build_config)))
+    Ok(sway_core::parse_with_cache(
+        source,
+        engines,
+        Some(&sway_build_config),
+        cache,
+    ))
 }
 
 /// Format an error message for an absent `Forc.toml`.
@@ -2625,3 +3222,304 @@ fn test_root_pkg_order() {
         .collect();
     assert_eq!(order, vec!["test_lib", "test_contract", "test_script"])
 }
+
+#[test]
+fn test_build_info_fingerprint() {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .join("test/src/e2e_vm_tests/test_programs/should_pass/forc/workspace_building/");
+    let manifest_file = ManifestFile::from_dir(&manifest_dir).unwrap();
+    let member_manifests = manifest_file.member_manifests().unwrap();
+    let lock_path = manifest_file.lock_path().unwrap();
+    let plan =
+        BuildPlan::from_lock_and_manifests(&lock_path, &member_manifests, false, false).unwrap();
+    let node = |plan: &BuildPlan, name: &str| {
+        plan.graph()
+            .node_indices()
+            .find(|node| plan.graph()[*node].name == name)
+            .unwrap()
+    };
+    let build_info = |plan: &BuildPlan, name: &str, profile: &BuildProfile| {
+        BuildInfo::new(
+            BuildTarget::default(),
+            vec![],
+            profile,
+            plan,
+            node(plan, name),
+            &[],
+            &ProgramABI::MidenVM(()),
+        )
+        .unwrap()
+    };
+    let profile = BuildProfile::default();
+
+    // The dependencies include those of the dependencies.
+    let script = build_info(&plan, "test_script", &profile);
+    assert_eq!(
+        script.dependencies,
+        vec!["test_contract member", "test_lib member"]
+    );
+    let lib = build_info(&plan, "test_lib", &profile);
+    assert!(lib.dependencies.is_empty());
+    assert_ne!(lib.fingerprint, script.fingerprint);
+
+    // The build profile counts, but not the options which only print diagnostics.
+    let profile_o2 = BuildProfile {
+        opt_level: OptLevel::Opt2,
+        ..profile.clone()
+    };
+    assert_ne!(
+        build_info(&plan, "test_lib", &profile_o2).fingerprint,
+        lib.fingerprint
+    );
+    let profile_print_ir = BuildProfile {
+        print_ir: true,
+        time_phases: true,
+        ..profile.clone()
+    };
+    assert_eq!(build_info(&plan, "test_lib", &profile_print_ir), lib);
+
+    // As does any change to the lock file, even one which doesn't affect the package's own
+    // dependencies.
+    let mut other_plan = plan.clone();
+    let edge = other_plan
+        .graph
+        .find_edge(node(&plan, "test_script"), node(&plan, "test_contract"))
+        .unwrap();
+    other_plan.graph.remove_edge(edge);
+    let other_lib = build_info(&other_plan, "test_lib", &profile);
+    assert_eq!(other_lib.dependencies, lib.dependencies);
+    assert_ne!(other_lib.lock_hash, lib.lock_hash);
+    assert_ne!(other_lib.fingerprint, lib.fingerprint);
+}
+
+#[test]
+fn test_check_incremental_reuses_unchanged_packages() {
+    let dir = std::env::temp_dir().join(format!("forc-pkg-check-cache-{}", std::process::id()));
+    let write = |path: &str, contents: &str| {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    };
+    let manifest = |name: &str, dependencies: &str| {
+        format!(
+            "[project]\nauthors = [\"Fuel Labs <contact@fuel.sh>\"]\nentry = \"lib.sw\"\n\
+             implicit-std = false\nlicense = \"Apache-2.0\"\nname = \"{name}\"\n\n\
+             [dependencies]\n{dependencies}"
+        )
+    };
+    write("dep/Forc.toml", &manifest("dep", ""));
+    write(
+        "dep/src/lib.sw",
+        "library;\n\npub fn one() -> u64 {\n    1\n}\n",
+    );
+    write(
+        "app/Forc.toml",
+        &manifest("app", "dep = { path = \"../dep\" }\n"),
+    );
+    write("app/src/lib.sw", "library;\n\nmod helper;\n");
+    write(
+        "app/src/helper.sw",
+        "library;\n\npub fn two() -> u64 {\n    2\n}\n",
+    );
+
+    let manifest_file = ManifestFile::from_dir(&dir.join("app")).unwrap();
+    let plan = BuildPlan::from_manifests(&manifest_file.member_manifests().unwrap(), true).unwrap();
+    let type_engine = TypeEngine::default();
+    let decl_engine = DeclEngine::default();
+    let engines = Engines::new(&type_engine, &decl_engine);
+    let mut cache = CheckCache::default();
+    let check = |cache: &mut CheckCache| {
+        check_incremental(
+            &plan,
+            BuildTarget::default(),
+            true,
+            false,
+            engines,
+            cache,
+            |_, _| ControlFlow::Continue(()),
+        )
+        .unwrap()
+    };
+
+    let results = check(&mut cache);
+    assert!(results.iter().all(|result| result.errors.is_empty()));
+    assert_eq!(cache.packages.len(), 2);
+    assert_eq!(cache.modules.len(), 3);
+
+    // Mark the cached result of `dep`, so that reusing it can be told apart from checking it again.
+    let dep_id = plan.graph()[plan.compilation_order()[0]].id();
+    cache.packages.get_mut(&dep_id).unwrap().result.value = None;
+
+    // Editing `app` leaves `dep` as it was.
+    write(
+        "app/src/helper.sw",
+        "library;\n\npub fn three() -> u64 {\n    3\n}\n",
+    );
+    let results = check(&mut cache);
+    assert!(results[0].value.is_none());
+    assert!(results[1].value.is_some());
+    assert_eq!(cache.modules.len(), 3);
+
+    // Editing `dep` checks it, and so `app`, again.
+    write(
+        "dep/src/lib.sw",
+        "library;\n\npub fn four() -> u64 {\n    4\n}\n",
+    );
+    let results = check(&mut cache);
+    assert!(results.iter().all(|result| result.value.is_some()));
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_build_emits_requested_artifacts() {
+    let dir = std::env::temp_dir().join(format!("forc-pkg-emit-{}", std::process::id()));
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(
+        dir.join("Forc.toml"),
+        "[project]\nauthors = [\"Fuel Labs <contact@fuel.sh>\"]\nentry = \"main.sw\"\n\
+         implicit-std = false\nlicense = \"Apache-2.0\"\nname = \"emit\"\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("src/main.sw"),
+        "script;\n\nfn main() -> u64 {\n    42\n}\n",
+    )
+    .unwrap();
+    let build = |emit: EmitOpts| {
+        build_with_options(BuildOpts {
+            pkg: PkgOpts {
+                path: Some(dir.to_string_lossy().to_string()),
+                offline: true,
+                ..Default::default()
+            },
+            emit,
+            ..Default::default()
+        })
+    };
+
+    let built = build(EmitOpts {
+        ir: true,
+        asm: true,
+        bytecode: true,
+        before_passes: vec!["dce".to_string()],
+        after_passes: vec!["simplifycfg".to_string()],
+        explorer: false,
+    })
+    .unwrap()
+    .expect_pkg()
+    .unwrap();
+    let artifact = |path: &str| {
+        built
+            .emitted
+            .iter()
+            .find(|artifact| artifact.path == Path::new(path))
+            .map(|artifact| artifact.contents.as_str())
+            .unwrap_or_else(|| panic!("{path} was not emitted"))
+    };
+
+    assert!(artifact("final.ir").contains("script {"));
+    assert!(!artifact("final.asm").is_empty());
+
+    // One line per 4 byte instruction, prefixed with its offset.
+    let listing = artifact("final.bytecode").lines().collect::<Vec<_>>();
+    assert_eq!(listing.len(), built.bytecode.bytes.len() / 4);
+    assert_eq!(
+        listing[0],
+        format!("00000000: {}", hex::encode(&built.bytecode.bytes[..4]))
+    );
+
+    // The passes are numbered in the order they ran, and only the requested ones are captured.
+    let passes = built
+        .emitted
+        .iter()
+        .filter_map(|artifact| artifact.path.strip_prefix("passes").ok())
+        .map(|path| path.to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    assert!(passes.iter().any(|name| name.ends_with("-before-dce.ir")));
+    assert!(passes
+        .iter()
+        .any(|name| name.ends_with("-after-simplifycfg.ir")));
+    assert!(passes.iter().all(|name| {
+        name.ends_with("-before-dce.ir") || name.ends_with("-after-simplifycfg.ir")
+    }));
+    let indices = passes
+        .iter()
+        .map(|name| name.split('-').next().unwrap().parse::<usize>().unwrap())
+        .collect::<Vec<_>>();
+    assert!(indices.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(built
+        .emitted
+        .iter()
+        .filter(|artifact| artifact.path.starts_with("passes"))
+        .all(|artifact| artifact.contents.contains("script {")));
+
+    // Passes which don't exist are reported rather than silently never emitted.
+    assert!(build(EmitOpts {
+        before_passes: vec!["no-such-pass".to_string()],
+        ..Default::default()
+    })
+    .is_err());
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_build_selected_workspace_members() {
+    let dir = std::env::temp_dir().join(format!("forc-pkg-members-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("Forc.toml"),
+        "[workspace]\nmembers = [\"first\", \"second\"]\n",
+    )
+    .unwrap();
+    for name in ["first", "second"] {
+        fs::create_dir_all(dir.join(name).join("src")).unwrap();
+        fs::write(
+            dir.join(name).join("Forc.toml"),
+            format!(
+                "[project]\nauthors = [\"Fuel Labs <contact@fuel.sh>\"]\nentry = \"main.sw\"\n\
+                 implicit-std = false\nlicense = \"Apache-2.0\"\nname = \"{name}\"\n"
+            ),
+        )
+        .unwrap();
+        fs::write(
+            dir.join(name).join("src/main.sw"),
+            "script;\n\nfn main() -> u64 {\n    42\n}\n",
+        )
+        .unwrap();
+    }
+    let build = |members: &[&str]| {
+        build_with_options(BuildOpts {
+            pkg: PkgOpts {
+                path: Some(dir.to_string_lossy().to_string()),
+                offline: true,
+                ..Default::default()
+            },
+            member_filter: MemberFilter {
+                members: members.iter().map(|name| name.to_string()).collect(),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+    };
+    let built_members = |members: &[&str]| match build(members).unwrap() {
+        Built::Workspace(built) => {
+            let mut names = built.into_keys().collect::<Vec<_>>();
+            names.sort();
+            names
+        }
+        Built::Package(_) => panic!("expected a workspace build"),
+    };
+
+    // Every member is built unless some are selected.
+    assert_eq!(built_members(&[]), ["first", "second"]);
+    assert_eq!(built_members(&["second"]), ["second"]);
+
+    // Selecting a member which doesn't exist is an error rather than building nothing.
+    assert!(build(&["third"]).is_err());
+
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -10,7 +10,7 @@
 pub mod git;
 mod member;
 pub mod path;
-mod reg;
+pub mod reg;
 
 use crate::{
     manifest::{self, MemberManifestFiles, PackageManifestFile},
@@ -120,7 +120,7 @@ impl Source {
             manifest::Dependency::Simple(ref ver_str) => {
                 bail!(
                     "Unsupported dependency declaration in \"{}\": `{}` - \
-                    currently only `git` and `path` dependencies are supported",
+                    currently only `git`, `path` and `registry` dependencies are supported",
                     manifest_dir.display(),
                     ver_str
                 )
@@ -156,6 +156,18 @@ impl Source {
                     let source = git::Source { repo, reference };
                     Source::Git(source)
                 }
+                (_, Some(version), None) => {
+                    let index = match &det.registry {
+                        Some(index) => Url::parse(index)?,
+                        None => bail!(
+                            "dependencies with a `version` must also specify the index of \
+                            the `registry` in which to find it"
+                        ),
+                    };
+                    let version = semver::VersionReq::parse(version)?;
+                    let source = reg::Source { index, version };
+                    Source::Registry(source)
+                }
                 _ => {
                     bail!("unsupported set of fields for dependency: {:?}", dep);
                 }
@@ -257,7 +269,7 @@ impl Pinned {
     /// Used soley for the package lock file.
     pub fn semver(&self) -> Option<semver::Version> {
         match self {
-            Self::Registry(reg) => Some(reg.source.version.clone()),
+            Self::Registry(reg) => Some(reg.version.clone()),
             _ => None,
         }
     }
@@ -307,7 +319,7 @@ impl fmt::Display for Pinned {
             Self::Member(src) => src.fmt(f),
             Self::Path(src) => src.fmt(f),
             Self::Git(src) => src.fmt(f),
-            Self::Registry(_reg) => todo!("pkg registries not yet implemented"),
+            Self::Registry(src) => src.fmt(f),
         }
     }
 }
@@ -318,7 +330,7 @@ impl<'a> fmt::Display for DisplayCompiling<'a, Pinned> {
             Pinned::Member(_) => self.manifest_dir.display().fmt(f),
             Pinned::Path(_src) => self.manifest_dir.display().fmt(f),
             Pinned::Git(src) => src.fmt(f),
-            Pinned::Registry(_src) => todo!("registry dependencies not yet implemented"),
+            Pinned::Registry(src) => src.fmt(f),
         }
     }
 }
@@ -334,8 +346,9 @@ impl FromStr for Pinned {
             Self::Path(src)
         } else if let Ok(src) = git::Pinned::from_str(s) {
             Self::Git(src)
+        } else if let Ok(src) = reg::Pinned::from_str(s) {
+            Self::Registry(src)
         } else {
-            // TODO: Try parse registry source.
             return Err(PinnedParseError);
         };
         Ok(source)
@@ -1,40 +1,228 @@
-use crate::{manifest::PackageManifestFile, source};
-use anyhow::bail;
+//! Packages published to a registry.
+//!
+//! A registry is described by its index: a git repository containing a file for each published
+//! package, named after the package. Each line of the file is a JSON [IndexEntry] describing a
+//! single published version, giving the git repository and commit at which that version's source
+//! may be found along with a [checksum] of the source.
+//!
+//! Dependencies on registry packages specify a semver version requirement, and are pinned to the
+//! newest matching version that has not been yanked. The pinned version and its checksum are
+//! recorded in the lock file, and the checksum is verified whenever the package is loaded. Yanked
+//! versions which are already locked continue to work, with a warning.
+//!
+//! Publishing or yanking a version amounts to editing a local checkout of the index with
+//! [publish] or [set_yanked], then committing and pushing the change.
+
+use crate::{
+    manifest::{self, PackageManifestFile},
+    source::{self, git},
+};
+use anyhow::{anyhow, bail, Context, Result};
+use forc_util::user_forc_directory;
 use serde::{Deserialize, Serialize};
-use std::path::{Path, PathBuf};
+use std::{
+    cmp::Ordering,
+    collections::hash_map,
+    fmt, fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use sway_core::fuel_prelude::fuel_crypto;
+use tracing::{info, warn};
+use url::Url;
 
-/// A package from the official registry.
-#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
+/// A package from a registry.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Deserialize, Serialize)]
 pub struct Source {
-    /// The base version specified for the package.
-    pub version: semver::Version,
+    /// The URL of the registry's index repository.
+    pub index: Url,
+    /// The requirement that the selected version must meet.
+    pub version: semver::VersionReq,
 }
 
 /// A pinned instance of the registry source.
 #[derive(Clone, Debug, Eq, Hash, PartialEq, Deserialize, Serialize)]
 pub struct Pinned {
-    /// The registry package with base version.
+    /// The registry package with its version requirement.
     pub source: Source,
     /// The pinned version.
     pub version: semver::Version,
+    /// The checksum of the pinned version's source as published in the index.
+    pub checksum: String,
+}
+
+/// A single published version of a package, as found in a registry index.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct IndexEntry {
+    pub name: String,
+    pub version: semver::Version,
+    /// The git repository containing the package.
+    pub repo: Url,
+    /// The commit at which this version of the package may be found.
+    pub commit: String,
+    /// The checksum of the package source. See [checksum].
+    pub checksum: String,
+    /// Yanked versions are not selected when pinning new dependencies.
+    #[serde(default)]
+    pub yanked: bool,
+}
+
+/// Error returned upon failed parsing of `Pinned::from_str`.
+#[derive(Clone, Debug)]
+pub enum PinnedParseError {
+    Prefix,
+    Index,
+    VersionReq,
+    Version,
+    Checksum,
+}
+
+/// The name under which registry indices are checked out alongside git dependencies.
+const INDEX_CHECKOUT_NAME: &str = "registry-index";
+
+/// Files which are written by forc within fetched packages and so are excluded from checksums.
+const CHECKSUM_EXCLUDED_FILES: &[&str] = &[".forc_index"];
+
+impl Pinned {
+    pub const PREFIX: &'static str = "registry";
+
+    /// The directory into which this version of the package is fetched.
+    ///
+    /// The resulting directory is:
+    ///
+    /// ```ignore
+    /// $HOME/.forc/registry/<index_url_hash>/<name>-<version>
+    /// ```
+    pub fn local_path(&self, name: &str) -> PathBuf {
+        registry_directory(&self.source.index).join(format!("{name}-{}", self.version))
+    }
 }
 
 impl source::Pin for Source {
     type Pinned = Pinned;
-    fn pin(&self, _ctx: source::PinCtx) -> anyhow::Result<(Self::Pinned, PathBuf)> {
-        bail!("registry dependencies are not yet supported");
+    fn pin(&self, ctx: source::PinCtx) -> Result<(Self::Pinned, PathBuf)> {
+        let index_dir = fetch_index(&ctx, &self.index)?;
+        let entry = read_index(&index_dir, ctx.name())?
+            .into_iter()
+            .filter(|entry| !entry.yanked && self.version.matches(&entry.version))
+            .max_by(|a, b| a.version.cmp(&b.version))
+            .ok_or_else(|| {
+                anyhow!(
+                    "no published version of `{}` matches `{}` in registry {}",
+                    ctx.name(),
+                    self.version,
+                    self.index
+                )
+            })?;
+        let pinned = Pinned {
+            source: self.clone(),
+            version: entry.version,
+            checksum: entry.checksum,
+        };
+        let path = pinned.local_path(ctx.name());
+        Ok((pinned, path))
     }
 }
 
 impl source::Fetch for Pinned {
-    fn fetch(&self, _ctx: source::PinCtx, _local: &Path) -> anyhow::Result<PackageManifestFile> {
-        bail!("registry dependencies are not yet supported");
+    fn fetch(&self, ctx: source::PinCtx, local: &Path) -> Result<PackageManifestFile> {
+        // Co-ordinate access to the package directory using an advisory file lock.
+        let mut lock = crate::pkg::path_lock(local)?;
+        {
+            let _guard = lock.write()?;
+            if !local.exists() {
+                info!(
+                    "  {} {} {}",
+                    ansi_term::Color::Green.bold().paint("Fetching"),
+                    ansi_term::Style::new().bold().paint(ctx.name),
+                    self
+                );
+                fetch(&ctx, self, local)?;
+            }
+        }
+        let _guard = lock.read()?;
+        verify_checksum(ctx.name(), self, local)?;
+        PackageManifestFile::from_dir(local)
     }
 }
 
 impl source::DepPath for Pinned {
-    fn dep_path(&self, _name: &str) -> anyhow::Result<source::DependencyPath> {
-        bail!("registry dependencies are not yet supported");
+    fn dep_path(&self, name: &str) -> Result<source::DependencyPath> {
+        let local = self.local_path(name);
+        // Co-ordinate access to the package directory using an advisory file lock.
+        let lock = crate::pkg::path_lock(&local)?;
+        let _guard = lock.read()?;
+        let path = manifest::find_within(&local, name)
+            .ok_or_else(|| anyhow!("failed to find package `{}` in {}", name, self))?;
+        Ok(source::DependencyPath::ManifestPath(path))
+    }
+}
+
+impl fmt::Display for Pinned {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // registry+<url/to/index>?<version_req>#<version>!<checksum>
+        //
+        // Whitespace is removed from the version requirement so that the source may be used
+        // within the whitespace separated dependency lines of the lock file.
+        let version_req = self.source.version.to_string().replace(' ', "");
+        write!(
+            f,
+            "{}+{}?{}#{}!{}",
+            Self::PREFIX,
+            self.source.index,
+            version_req,
+            self.version,
+            self.checksum
+        )
+    }
+}
+
+impl FromStr for Pinned {
+    type Err = PinnedParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // registry+<url/to/index>?<version_req>#<version>!<checksum>
+        let s = s.trim();
+
+        // Check for "registry+" at the start.
+        let prefix_plus = format!("{}+", Self::PREFIX);
+        let s = s
+            .strip_prefix(&prefix_plus)
+            .ok_or(PinnedParseError::Prefix)?;
+
+        // Parse from the end, as the index URL may itself contain `?` or `#`.
+        let (s, checksum) = s.rsplit_once('!').ok_or(PinnedParseError::Checksum)?;
+        if checksum.is_empty() {
+            return Err(PinnedParseError::Checksum);
+        }
+        let (s, version) = s.rsplit_once('#').ok_or(PinnedParseError::Version)?;
+        let version = semver::Version::parse(version).map_err(|_| PinnedParseError::Version)?;
+        let (index, version_req) = s.rsplit_once('?').ok_or(PinnedParseError::VersionReq)?;
+        let version_req =
+            semver::VersionReq::parse(version_req).map_err(|_| PinnedParseError::VersionReq)?;
+        let index = Url::parse(index).map_err(|_| PinnedParseError::Index)?;
+
+        Ok(Self {
+            source: Source {
+                index,
+                version: version_req,
+            },
+            version,
+            checksum: checksum.to_string(),
+        })
+    }
+}
+
+impl Ord for Source {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // `semver::VersionReq` has no ordering of its own, so compare by string.
+        (&self.index, self.version.to_string()).cmp(&(&other.index, other.version.to_string()))
+    }
+}
+
+impl PartialOrd for Source {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
@@ -43,3 +231,448 @@ impl From<Pinned> for source::Pinned {
         Self::Registry(p)
     }
 }
+
+/// Compute the checksum of the package source within `pkg_dir`.
+///
+/// This is the SHA-256 hash of the path and contents of every file within the directory, in path
+/// order. Each path and file is preceded by its length so that the boundaries between them are
+/// unambiguous, and paths always use `/` separators so that the checksum is the same on every
+/// platform.
+pub fn checksum(pkg_dir: &Path) -> Result<String> {
+    let mut files = Vec::new();
+    for entry in walkdir::WalkDir::new(pkg_dir) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(pkg_dir)?.to_path_buf();
+        let excluded = relative.starts_with(".git")
+            || CHECKSUM_EXCLUDED_FILES
+                .iter()
+                .any(|excluded| relative == Path::new(excluded));
+        if !excluded {
+            files.push(relative);
+        }
+    }
+    files.sort();
+
+    let mut hasher = fuel_crypto::Hasher::default();
+    for file in files {
+        let contents = fs::read(pkg_dir.join(&file))
+            .with_context(|| format!("failed to read {}", pkg_dir.join(&file).display()))?;
+        let path = file
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        hasher.input((path.len() as u64).to_be_bytes());
+        hasher.input(path.as_bytes());
+        hasher.input((contents.len() as u64).to_be_bytes());
+        hasher.input(contents);
+    }
+    Ok(format!("{}", hasher.finalize()))
+}
+
+/// Publish a version of the package within `pkg_dir` by adding it to a local checkout of a
+/// registry index.
+///
+/// The package must already have been pushed to `repo` at `commit`. `pkg_dir` must be within a
+/// local clone of the repository containing that commit, from which the checksum is computed, so
+/// that uncommitted or ignored files such as build output don't affect it. The change to the index
+/// must then be committed and pushed for the version to become available.
+pub fn publish(
+    index_dir: &Path,
+    pkg_dir: &Path,
+    version: semver::Version,
+    repo: Url,
+    commit: String,
+) -> Result<IndexEntry> {
+    let manifest = PackageManifestFile::from_dir(pkg_dir)?;
+    let name = manifest.project.name.clone();
+    // The first version of a package starts a new index file.
+    let mut entries = if index_dir.join(&name).exists() {
+        read_index(index_dir, &name)?
+    } else {
+        vec![]
+    };
+    if entries.iter().any(|entry| entry.version == version) {
+        bail!("version {version} of `{name}` has already been published");
+    }
+    let entry = IndexEntry {
+        name,
+        version,
+        repo,
+        checksum: committed_checksum(pkg_dir, &commit)?,
+        commit,
+        yanked: false,
+    };
+    entries.push(entry.clone());
+    write_index(index_dir, &entry.name, &entries)?;
+    Ok(entry)
+}
+
+/// Compute the checksum of the package within `pkg_dir` as it is at `commit` of the git repository
+/// containing it, i.e. as it will be fetched from the registry.
+fn committed_checksum(pkg_dir: &Path, commit: &str) -> Result<String> {
+    let repo = git2::Repository::discover(pkg_dir)
+        .with_context(|| format!("{} is not within a git repository", pkg_dir.display()))?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow!("the git repository containing the package has no working tree"))?;
+    let relative = pkg_dir
+        .canonicalize()?
+        .strip_prefix(workdir.canonicalize()?)?
+        .to_path_buf();
+    let tree = repo
+        .revparse_single(commit)
+        .and_then(|object| object.peel_to_tree())
+        .with_context(|| format!("failed to find commit {commit}"))?;
+
+    // Check the commit out to a temporary directory, leaving the working tree and index alone.
+    let tmp = std::env::temp_dir().join(format!("forc-publish-{}", std::process::id()));
+    if tmp.exists() {
+        fs::remove_dir_all(&tmp)?;
+    }
+    let mut checkout = git2::build::CheckoutBuilder::new();
+    checkout.force().update_index(false).target_dir(&tmp);
+    let result = repo
+        .checkout_tree(tree.as_object(), Some(&mut checkout))
+        .map_err(anyhow::Error::from)
+        .and_then(|()| checksum(&tmp.join(&relative)));
+    let _ = fs::remove_dir_all(&tmp);
+    result
+}
+
+/// Set whether the given published version of a package is yanked within a local checkout of a
+/// registry index.
+pub fn set_yanked(
+    index_dir: &Path,
+    name: &str,
+    version: &semver::Version,
+    yanked: bool,
+) -> Result<()> {
+    let mut entries = read_index(index_dir, name)?;
+    let entry = entries
+        .iter_mut()
+        .find(|entry| &entry.version == version)
+        .ok_or_else(|| anyhow!("version {version} of `{name}` has not been published"))?;
+    entry.yanked = yanked;
+    write_index(index_dir, name, &entries)
+}
+
+/// Read all of the published versions of the named package from the index.
+pub fn read_index(index_dir: &Path, name: &str) -> Result<Vec<IndexEntry>> {
+    let path = index_dir.join(name);
+    let contents = fs::read_to_string(&path).map_err(|err| match err.kind() {
+        std::io::ErrorKind::NotFound => {
+            anyhow!("package `{name}` was not found in the registry index")
+        }
+        _ => anyhow!("failed to read {}: {err}", path.display()),
+    })?;
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line)
+                .with_context(|| format!("invalid registry index entry in {}", path.display()))
+        })
+        .collect()
+}
+
+fn write_index(index_dir: &Path, name: &str, entries: &[IndexEntry]) -> Result<()> {
+    let mut contents = String::new();
+    for entry in entries {
+        contents.push_str(&serde_json::to_string(entry)?);
+        contents.push('\n');
+    }
+    fs::write(index_dir.join(name), contents)?;
+    Ok(())
+}
+
+/// Check out the newest commit of the registry index, returning its location.
+///
+/// In offline mode the most recently fetched commit is used instead.
+fn fetch_index(ctx: &source::PinCtx, index: &Url) -> Result<PathBuf> {
+    let source = git::Source {
+        repo: index.clone(),
+        reference: git::Reference::DefaultBranch,
+    };
+    let head_path = registry_directory(index).join("index-head");
+    let commit_hash = if ctx.offline() {
+        fs::read_to_string(&head_path)
+            .map_err(|_| anyhow!("Unable to fetch registry index {index} in offline mode"))?
+    } else {
+        git::pin(ctx.fetch_id(), INDEX_CHECKOUT_NAME, source.clone())?.commit_hash
+    };
+    let pinned = git::Pinned {
+        source,
+        commit_hash,
+    };
+    let path = git::commit_path(INDEX_CHECKOUT_NAME, index, &pinned.commit_hash);
+    {
+        let mut lock = crate::pkg::path_lock(&path)?;
+        let _guard = lock.write()?;
+        if !path.exists() {
+            info!(
+                "  {} registry index {}",
+                ansi_term::Color::Green.bold().paint("Updating"),
+                index
+            );
+            git::fetch(ctx.fetch_id(), INDEX_CHECKOUT_NAME, &pinned)?;
+        }
+    }
+    if let Some(parent) = head_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&head_path, &pinned.commit_hash)?;
+    Ok(path)
+}
+
+/// Fetch the source of the pinned package version into `local`.
+///
+/// NOTE: This function assumes that the caller has aquired an advisory lock to co-ordinate access
+/// to the `local` path.
+fn fetch(ctx: &source::PinCtx, pinned: &Pinned, local: &Path) -> Result<()> {
+    let name = ctx.name();
+    let index_dir = fetch_index(ctx, &pinned.source.index)?;
+    let entry = read_index(&index_dir, name)?
+        .into_iter()
+        .find(|entry| entry.version == pinned.version)
+        .ok_or_else(|| {
+            anyhow!(
+                "version {} of `{name}` was not found in registry {}",
+                pinned.version,
+                pinned.source.index
+            )
+        })?;
+    if entry.yanked {
+        warn!(
+            "  version {} of `{name}` has been yanked from registry {}",
+            entry.version, pinned.source.index
+        );
+    }
+    if entry.checksum != pinned.checksum {
+        bail!(
+            "the checksum of version {} of `{name}` in registry {} does not match the lock file",
+            entry.version,
+            pinned.source.index
+        );
+    }
+
+    // Check out the package's git repository at the published commit.
+    let git_pinned = git::Pinned {
+        source: git::Source {
+            repo: entry.repo,
+            reference: git::Reference::Rev(entry.commit.clone()),
+        },
+        commit_hash: entry.commit,
+    };
+    let repo_path = git::commit_path(name, &git_pinned.source.repo, &git_pinned.commit_hash);
+    {
+        let mut lock = crate::pkg::path_lock(&repo_path)?;
+        let _guard = lock.write()?;
+        if !repo_path.exists() {
+            git::fetch(ctx.fetch_id(), name, &git_pinned)?;
+        }
+    }
+
+    // Copy just the package out of the repository.
+    let pkg_dir = manifest::find_dir_within(&repo_path, name)
+        .ok_or_else(|| anyhow!("failed to find package `{name}` in {}", git_pinned))?;
+    copy_dir(&pkg_dir, local)
+}
+
+/// Ensure the package source within `local` matches the pinned checksum.
+fn verify_checksum(name: &str, pinned: &Pinned, local: &Path) -> Result<()> {
+    let actual = checksum(local)?;
+    if actual != pinned.checksum {
+        bail!(
+            "checksum mismatch for version {} of `{name}`: expected {}, found {}. \
+            The source in {} may have been modified.",
+            pinned.version,
+            pinned.checksum,
+            actual,
+            local.display()
+        );
+    }
+    Ok(())
+}
+
+/// Copy the directory `from` to `to`, which must not exist yet.
+///
+/// The files are copied into a temporary sibling of `to` which is then renamed into place, so that
+/// a failed copy never leaves a partial package where it would be mistaken for a fetched one.
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    let name = to
+        .file_name()
+        .ok_or_else(|| anyhow!("invalid package directory {}", to.display()))?;
+    let tmp = to.with_file_name(format!(
+        "{}.tmp-{}",
+        name.to_string_lossy(),
+        std::process::id()
+    ));
+    if tmp.exists() {
+        fs::remove_dir_all(&tmp)?;
+    }
+    let copy = || -> Result<()> {
+        for entry in walkdir::WalkDir::new(from) {
+            let entry = entry?;
+            let relative = entry.path().strip_prefix(from)?;
+            let dest = tmp.join(relative);
+            if entry.file_type().is_dir() {
+                fs::create_dir_all(&dest)?;
+            } else if entry.file_type().is_file() {
+                fs::copy(entry.path(), &dest)?;
+            }
+        }
+        Ok(())
+    };
+    if let Err(err) = copy() {
+        let _ = fs::remove_dir_all(&tmp);
+        return Err(err);
+    }
+    fs::rename(&tmp, to)?;
+    Ok(())
+}
+
+/// The directory within the user's forc directory for packages from the given registry.
+fn registry_directory(index: &Url) -> PathBuf {
+    let mut hasher = hash_map::DefaultHasher::new();
+    index.hash(&mut hasher);
+    user_forc_directory()
+        .join("registry")
+        .join(format!("{:x}", hasher.finish()))
+}
+
+#[test]
+fn test_source_reg_pinned_parsing() {
+    let strings = [
+        "registry+https://github.com/foo/index?^1.2.3#1.4.0!abcdef",
+        "registry+https://github.com/foo/index?>=0.1.0,<0.3.0#0.2.1-alpha.1!0123",
+    ];
+
+    let expected = [
+        Pinned {
+            source: Source {
+                index: Url::parse("https://github.com/foo/index").unwrap(),
+                version: semver::VersionReq::parse("^1.2.3").unwrap(),
+            },
+            version: semver::Version::parse("1.4.0").unwrap(),
+            checksum: "abcdef".to_string(),
+        },
+        Pinned {
+            source: Source {
+                index: Url::parse("https://github.com/foo/index").unwrap(),
+                version: semver::VersionReq::parse(">=0.1.0, <0.3.0").unwrap(),
+            },
+            version: semver::Version::parse("0.2.1-alpha.1").unwrap(),
+            checksum: "0123".to_string(),
+        },
+    ];
+
+    for (&string, expected) in strings.iter().zip(&expected) {
+        let parsed = Pinned::from_str(string).unwrap();
+        assert_eq!(&parsed, expected);
+        let serialized = expected.to_string();
+        assert_eq!(&serialized, string);
+    }
+}
+
+#[test]
+fn test_publish_checksums_the_committed_source() {
+    let dir = std::env::temp_dir().join(format!("forc-reg-publish-test-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let (repo_dir, index_dir) = (dir.join("repo"), dir.join("index"));
+    let pkg_dir = repo_dir.join("foo");
+    fs::create_dir_all(pkg_dir.join("src")).unwrap();
+    fs::create_dir_all(&index_dir).unwrap();
+    fs::write(
+        pkg_dir.join("Forc.toml"),
+        "[project]\nauthors = [\"Fuel Labs\"]\nentry = \"main.sw\"\nlicense = \"Apache-2.0\"\nname = \"foo\"\n",
+    )
+    .unwrap();
+    fs::write(pkg_dir.join("src/main.sw"), "library foo;\n").unwrap();
+
+    // Commit the package, then build it, leaving untracked output behind.
+    let repo = git2::Repository::init(&repo_dir).unwrap();
+    let mut index = repo.index().unwrap();
+    index
+        .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+        .unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = git2::Signature::now("forc", "forc@fuel.sh").unwrap();
+    let commit = repo
+        .commit(Some("HEAD"), &signature, &signature, "foo", &tree, &[])
+        .unwrap();
+    let clean_checksum = checksum(&pkg_dir).unwrap();
+    fs::create_dir_all(pkg_dir.join("out/debug")).unwrap();
+    fs::write(pkg_dir.join("out/debug/foo.bin"), [0u8; 8]).unwrap();
+    assert_ne!(checksum(&pkg_dir).unwrap(), clean_checksum);
+
+    let entry = publish(
+        &index_dir,
+        &pkg_dir,
+        semver::Version::new(0, 1, 0),
+        Url::parse("https://github.com/foo/foo").unwrap(),
+        commit.to_string(),
+    )
+    .unwrap();
+    assert_eq!(entry.checksum, clean_checksum);
+    assert_eq!(read_index(&index_dir, "foo").unwrap(), [entry]);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_checksum_separates_paths_and_contents() {
+    let dir = std::env::temp_dir().join(format!("forc-reg-checksum-test-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let package = |name: &str, files: &[(&str, &str)]| {
+        let pkg_dir = dir.join(name);
+        for (path, contents) in files {
+            let path = pkg_dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        checksum(&pkg_dir).unwrap()
+    };
+
+    assert_ne!(package("a", &[("a", "bc")]), package("b", &[("ab", "c")]));
+    assert_eq!(
+        package("c", &[("src/main.sw", "library;\n")]),
+        package("d", &[("src/main.sw", "library;\n")])
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_publish_keeps_malformed_index() {
+    let dir = std::env::temp_dir().join(format!("forc-reg-malformed-test-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let (pkg_dir, index_dir) = (dir.join("foo"), dir.join("index"));
+    fs::create_dir_all(pkg_dir.join("src")).unwrap();
+    fs::create_dir_all(&index_dir).unwrap();
+    fs::write(
+        pkg_dir.join("Forc.toml"),
+        "[project]\nauthors = [\"Fuel Labs\"]\nentry = \"main.sw\"\nlicense = \"Apache-2.0\"\nname = \"foo\"\n",
+    )
+    .unwrap();
+    fs::write(pkg_dir.join("src/main.sw"), "library foo;\n").unwrap();
+    fs::write(index_dir.join("foo"), "not an index entry\n").unwrap();
+
+    let result = publish(
+        &index_dir,
+        &pkg_dir,
+        semver::Version::new(0, 1, 0),
+        Url::parse("https://github.com/foo/foo").unwrap(),
+        "0".repeat(40),
+    );
+    assert!(result.is_err());
+    assert_eq!(
+        fs::read_to_string(index_dir.join("foo")).unwrap(),
+        "not an index entry\n"
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
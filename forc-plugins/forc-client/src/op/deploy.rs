@@ -164,12 +164,15 @@ fn build_opts_from_cmd(cmd: &cmd::Deploy) -> pkg::BuildOpts {
             finalized_asm: cmd.print.finalized_asm,
             intermediate_asm: cmd.print.intermediate_asm,
             ir: cmd.print.ir,
+            ir_memory: cmd.print.ir_memory,
+            pass_statistics: cmd.print.pass_statistics,
         },
         time_phases: cmd.print.time_phases,
         minify: pkg::MinifyOpts {
             json_abi: cmd.minify.json_abi,
             json_storage_slots: cmd.minify.json_storage_slots,
         },
+        emit: Default::default(),
         build_profile: cmd.build_profile.build_profile.clone(),
         release: cmd.build_profile.release,
         error_on_warnings: cmd.build_profile.error_on_warnings,
@@ -177,6 +180,10 @@ fn build_opts_from_cmd(cmd: &cmd::Deploy) -> pkg::BuildOpts {
         debug_outfile: cmd.build_output.debug_file.clone(),
         build_target: BuildTarget::default(),
         tests: false,
+        instrument_coverage: false,
+        disable_incremental: false,
+        unoptimized: false,
+        check_storage_layout: None,
         const_inject_map,
         member_filter: pkg::MemberFilter::only_contracts(),
     }
@@ -160,11 +160,14 @@ fn build_opts_from_cmd(cmd: &cmd::Run) -> pkg::BuildOpts {
             finalized_asm: cmd.print.finalized_asm,
             intermediate_asm: cmd.print.intermediate_asm,
             ir: cmd.print.ir,
+            ir_memory: cmd.print.ir_memory,
+            pass_statistics: cmd.print.pass_statistics,
         },
         minify: pkg::MinifyOpts {
             json_abi: cmd.minify.json_abi,
             json_storage_slots: cmd.minify.json_storage_slots,
         },
+        emit: Default::default(),
         build_target: BuildTarget::default(),
         build_profile: cmd.build_profile.build_profile.clone(),
         release: cmd.build_profile.release,
@@ -173,6 +176,10 @@ fn build_opts_from_cmd(cmd: &cmd::Run) -> pkg::BuildOpts {
         binary_outfile: cmd.build_output.bin_file.clone(),
         debug_outfile: cmd.build_output.debug_file.clone(),
         tests: false,
+        instrument_coverage: false,
+        disable_incremental: false,
+        unoptimized: false,
+        check_storage_layout: None,
         const_inject_map,
         member_filter: pkg::MemberFilter::only_scripts(),
     }
@@ -0,0 +1,6 @@
+//! A Debug Adapter Protocol server for debugging the tests of Sway packages.
+
+pub mod protocol;
+pub mod server;
+
+pub use server::Server;
@@ -0,0 +1,22 @@
+//! A `forc` plugin for debugging Sway tests from editors supporting the Debug Adapter Protocol.
+//!
+//! Once installed and available via `PATH`, can be executed via `forc debug`.
+
+use clap::Parser;
+use std::io;
+
+#[derive(Debug, Parser)]
+#[clap(
+    name = "forc-debug",
+    about = "Forc plugin for debugging Sway tests via the Debug Adapter Protocol (DAP).",
+    version
+)]
+struct App {}
+
+fn main() -> anyhow::Result<()> {
+    App::parse();
+
+    // Messages are exchanged with the client over stdin and stdout.
+    let stdin = io::stdin();
+    forc_debug::Server::new(stdin.lock(), io::stdout()).run()
+}
@@ -0,0 +1,154 @@
+//! Reading and writing Debug Adapter Protocol messages.
+//!
+//! Each message is a JSON object preceded by a `Content-Length` header giving its length in bytes.
+
+use anyhow::{anyhow, bail, Result};
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+
+/// A request sent by the client.
+#[derive(Debug)]
+pub struct Request {
+    pub seq: i64,
+    pub command: String,
+    pub arguments: Value,
+}
+
+/// Reads requests from the client and writes responses and events to it.
+pub struct Connection<R, W> {
+    reader: R,
+    writer: W,
+    /// The sequence number of the next message sent to the client.
+    seq: i64,
+}
+
+impl<R: BufRead, W: Write> Connection<R, W> {
+    pub fn new(reader: R, writer: W) -> Self {
+        Self {
+            reader,
+            writer,
+            seq: 1,
+        }
+    }
+
+    /// Read the next request, or `None` if the client closed the connection.
+    ///
+    /// Messages other than requests are skipped.
+    pub fn read_request(&mut self) -> Result<Option<Request>> {
+        loop {
+            let message = match self.read_message()? {
+                Some(message) => message,
+                None => return Ok(None),
+            };
+            if message["type"] != "request" {
+                continue;
+            }
+            let seq = message["seq"]
+                .as_i64()
+                .ok_or_else(|| anyhow!("request is missing its sequence number"))?;
+            let command = message["command"]
+                .as_str()
+                .ok_or_else(|| anyhow!("request is missing its command"))?
+                .to_string();
+            let arguments = message.get("arguments").cloned().unwrap_or(Value::Null);
+            return Ok(Some(Request {
+                seq,
+                command,
+                arguments,
+            }));
+        }
+    }
+
+    /// Respond successfully to the given request.
+    pub fn respond(&mut self, request: &Request, body: Value) -> Result<()> {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request.seq,
+            "command": request.command,
+            "success": true,
+            "body": body,
+        }))
+    }
+
+    /// Respond to the given request with an error.
+    pub fn respond_error(&mut self, request: &Request, message: &str) -> Result<()> {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request.seq,
+            "command": request.command,
+            "success": false,
+            "message": message,
+        }))
+    }
+
+    /// Send an event to the client.
+    pub fn event(&mut self, event: &str, body: Value) -> Result<()> {
+        self.send(json!({
+            "type": "event",
+            "event": event,
+            "body": body,
+        }))
+    }
+
+    fn send(&mut self, mut message: Value) -> Result<()> {
+        message["seq"] = self.seq.into();
+        self.seq += 1;
+        let content = serde_json::to_string(&message)?;
+        write!(
+            self.writer,
+            "Content-Length: {}\r\n\r\n{content}",
+            content.len()
+        )?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn read_message(&mut self) -> Result<Option<Value>> {
+        let mut content_length = None;
+        loop {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("Content-Length") {
+                    content_length = Some(value.trim().parse::<usize>()?);
+                }
+            }
+        }
+        let content_length = match content_length {
+            Some(content_length) => content_length,
+            None => bail!("message is missing the `Content-Length` header"),
+        };
+        let mut content = vec![0; content_length];
+        self.reader.read_exact(&mut content)?;
+        Ok(Some(serde_json::from_slice(&content)?))
+    }
+}
+
+#[test]
+fn test_message_framing() {
+    let request = r#"{"seq":1,"type":"request","command":"threads"}"#;
+    let input = format!("Content-Length: {}\r\n\r\n{request}", request.len());
+    let mut output = vec![];
+    let mut connection = Connection::new(input.as_bytes(), &mut output);
+
+    let request = connection.read_request().unwrap().unwrap();
+    assert_eq!(request.seq, 1);
+    assert_eq!(request.command, "threads");
+    assert!(connection.read_request().unwrap().is_none());
+
+    connection
+        .respond(&request, json!({ "threads": [] }))
+        .unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let (header, content) = output.split_once("\r\n\r\n").unwrap();
+    assert_eq!(header, format!("Content-Length: {}", content.len()));
+    let response: Value = serde_json::from_str(content).unwrap();
+    assert_eq!(response["request_seq"], 1);
+    assert_eq!(response["success"], true);
+}
@@ -0,0 +1,320 @@
+//! A Debug Adapter Protocol server for stepping through the tests of a Sway package.
+//!
+//! The client launches a single named test within a package, which is built without optimisation
+//! and with its tests included, then executed one instruction at a time in the VM.  Breakpoints
+//! are set by file and line, and stepping moves from one source line to the next, either into,
+//! over or out of function calls.
+//!
+//! There is a single thread and a single stack frame, located at the source of the next
+//! instruction to be executed.  Its scopes are the user variables of the function being executed,
+//! as located by the compiler's debug variable metadata, and the VM's registers.
+
+use crate::protocol::{Connection, Request};
+use anyhow::{anyhow, Result};
+use forc_test::{StopReason, TestDebugger, Variable};
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, Write},
+    path::PathBuf,
+};
+
+/// The ID of the only thread reported to the client.
+const THREAD_ID: i64 = 1;
+
+/// The variables reference for the VM's registers.
+const REGISTERS_REFERENCE: i64 = 1;
+
+/// The variables reference for the user variables of the function being executed.
+const LOCALS_REFERENCE: i64 = 2;
+
+/// The names of the VM's reserved registers, by index.
+const REGISTER_NAMES: [&str; 16] = [
+    "zero", "one", "of", "pc", "ssp", "sp", "fp", "hp", "err", "ggas", "cgas", "bal", "is", "ret",
+    "retl", "flag",
+];
+
+pub struct Server<R, W> {
+    connection: Connection<R, W>,
+    debugger: Option<TestDebugger>,
+    /// Breakpoint lines by file, retained so that they may be set once a test is launched.
+    breakpoints: BTreeMap<PathBuf, Vec<usize>>,
+    /// Whether to stop before executing the first instruction of the test.
+    stop_on_entry: bool,
+}
+
+impl<R: BufRead, W: Write> Server<R, W> {
+    pub fn new(reader: R, writer: W) -> Self {
+        Self {
+            connection: Connection::new(reader, writer),
+            debugger: None,
+            breakpoints: BTreeMap::new(),
+            stop_on_entry: false,
+        }
+    }
+
+    /// Handle requests until the client disconnects.
+    pub fn run(&mut self) -> Result<()> {
+        while let Some(request) = self.connection.read_request()? {
+            let disconnect = matches!(request.command.as_str(), "disconnect" | "terminate");
+            match self.handle(&request) {
+                Ok(body) => self.connection.respond(&request, body)?,
+                Err(err) => self
+                    .connection
+                    .respond_error(&request, &format!("{err:#}"))?,
+            }
+            if disconnect {
+                break;
+            }
+            // Execution is only resumed once the response has been sent, as the client expects
+            // any resulting events to follow it.
+            if let Err(err) = self.resume_after(&request) {
+                self.output(&format!("{err:#}\n"))?;
+                self.connection.event("terminated", json!({}))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn handle(&mut self, request: &Request) -> Result<Value> {
+        let args = &request.arguments;
+        match request.command.as_str() {
+            "initialize" => Ok(json!({
+                "supportsConfigurationDoneRequest": true,
+                "supportsTerminateRequest": true,
+            })),
+            "launch" => {
+                self.launch(args)?;
+                Ok(json!({}))
+            }
+            "setBreakpoints" => self.set_breakpoints(args),
+            "configurationDone" | "disconnect" | "terminate" => Ok(json!({})),
+            "threads" => {
+                let name = self
+                    .debugger
+                    .as_ref()
+                    .map_or("test", |debugger| debugger.name());
+                Ok(json!({ "threads": [{ "id": THREAD_ID, "name": name }] }))
+            }
+            "stackTrace" => {
+                let debugger = self.debugger()?;
+                let test = debugger.name().to_string();
+                let frames = debugger
+                    .location()
+                    .into_iter()
+                    .map(|location| {
+                        let name = location.path.file_name().map(|name| name.to_string_lossy());
+                        json!({
+                            "id": 0,
+                            "name": test,
+                            "source": { "name": name, "path": location.path },
+                            "line": location.line,
+                            "column": location.column,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                Ok(json!({ "stackFrames": frames, "totalFrames": frames.len() }))
+            }
+            "scopes" => Ok(json!({
+                "scopes": [
+                    {
+                        "name": "Locals",
+                        "variablesReference": LOCALS_REFERENCE,
+                        "expensive": false,
+                    },
+                    {
+                        "name": "Registers",
+                        "variablesReference": REGISTERS_REFERENCE,
+                        "expensive": false,
+                    },
+                ]
+            })),
+            "variables" => {
+                let variables: Vec<Value> = match args["variablesReference"].as_i64() {
+                    Some(LOCALS_REFERENCE) => self
+                        .debugger()?
+                        .variables()
+                        .iter()
+                        .map(|variable| {
+                            json!({
+                                "name": variable.name,
+                                "value": format_variable(variable),
+                                "type": variable.ty,
+                                "variablesReference": 0,
+                            })
+                        })
+                        .collect(),
+                    Some(REGISTERS_REFERENCE) => self
+                        .debugger()?
+                        .registers()
+                        .iter()
+                        .enumerate()
+                        .map(|(ix, value)| {
+                            let name = match REGISTER_NAMES.get(ix) {
+                                Some(name) => format!("${name}"),
+                                None => format!("$r{ix}"),
+                            };
+                            json!({
+                                "name": name,
+                                "value": format!("{value:#x}"),
+                                "variablesReference": 0,
+                            })
+                        })
+                        .collect(),
+                    _ => vec![],
+                };
+                Ok(json!({ "variables": variables }))
+            }
+            "continue" => {
+                self.debugger()?;
+                Ok(json!({ "allThreadsContinued": true }))
+            }
+            "next" | "stepIn" | "stepOut" => {
+                self.debugger()?;
+                Ok(json!({}))
+            }
+            command => Err(anyhow!("unsupported request `{command}`")),
+        }
+    }
+
+    /// Execute the test in response to a request, once it has been responded to.
+    fn resume_after(&mut self, request: &Request) -> Result<()> {
+        let reason = match request.command.as_str() {
+            "configurationDone" if self.stop_on_entry => {
+                match self.debugger()?.step_instruction()? {
+                    Some(state) => StopReason::Finished(state),
+                    None => return self.stopped("entry"),
+                }
+            }
+            "configurationDone" | "continue" => self.debugger()?.resume()?,
+            "next" => self.debugger()?.step_over()?,
+            "stepIn" => self.debugger()?.step_line()?,
+            "stepOut" => self.debugger()?.step_out()?,
+            _ => return Ok(()),
+        };
+        match reason {
+            StopReason::Step => self.stopped("step"),
+            StopReason::Breakpoint => self.stopped("breakpoint"),
+            StopReason::Finished(state) => {
+                let receipts = self.debugger()?.receipts().to_vec();
+                for receipt in receipts {
+                    self.output(&format!("{receipt:?}\n"))?;
+                }
+                self.output(&format!("Test finished with state {state:?}\n"))?;
+                self.connection.event("terminated", json!({}))
+            }
+        }
+    }
+
+    fn launch(&mut self, args: &Value) -> Result<()> {
+        let test = args["test"]
+            .as_str()
+            .ok_or_else(|| anyhow!("the name of the `test` to debug must be provided"))?;
+        let path = args["program"].as_str().map(str::to_string);
+        self.stop_on_entry = args["stopOnEntry"].as_bool().unwrap_or(false);
+
+        let opts = forc_test::Opts {
+            pkg: forc_pkg::PkgOpts {
+                path,
+                ..Default::default()
+            },
+            unoptimized: true,
+            ..Default::default()
+        };
+        let built = forc_test::build(opts)?;
+        let mut debugger = built.debug_test(test)?;
+        for (path, lines) in &self.breakpoints {
+            debugger.set_breakpoints(path, lines);
+        }
+        self.debugger = Some(debugger);
+        self.connection.event("initialized", json!({}))
+    }
+
+    fn set_breakpoints(&mut self, args: &Value) -> Result<Value> {
+        let path = args["source"]["path"]
+            .as_str()
+            .map(PathBuf::from)
+            .ok_or_else(|| anyhow!("breakpoints may only be set within source files"))?;
+        let lines = args["breakpoints"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|breakpoint| breakpoint["line"].as_u64())
+            .map(|line| line as usize)
+            .collect::<Vec<_>>();
+        let verified = match &mut self.debugger {
+            Some(debugger) => debugger.set_breakpoints(&path, &lines),
+            None => vec![],
+        };
+        let breakpoints = lines
+            .iter()
+            .map(|line| json!({ "verified": verified.contains(line), "line": line }))
+            .collect::<Vec<_>>();
+        self.breakpoints.insert(path, lines);
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    fn stopped(&mut self, reason: &str) -> Result<()> {
+        self.connection.event(
+            "stopped",
+            json!({
+                "reason": reason,
+                "threadId": THREAD_ID,
+                "allThreadsStopped": true,
+            }),
+        )
+    }
+
+    fn output(&mut self, output: &str) -> Result<()> {
+        self.connection
+            .event("output", json!({ "category": "console", "output": output }))
+    }
+
+    fn debugger(&mut self) -> Result<&mut TestDebugger> {
+        self.debugger
+            .as_mut()
+            .ok_or_else(|| anyhow!("no test has been launched"))
+    }
+}
+
+/// Format the value of a variable for display, decoding the word sized types and showing others as
+/// hex.
+fn format_variable(variable: &Variable) -> String {
+    let bytes = match &variable.value {
+        Some(bytes) => bytes,
+        None => return "<optimized out>".to_string(),
+    };
+    let word = <[u8; 8]>::try_from(bytes.as_slice())
+        .ok()
+        .map(u64::from_be_bytes);
+    match (variable.ty.as_str(), word) {
+        ("bool", Some(word)) => (word != 0).to_string(),
+        ("u8" | "u16" | "u32" | "u64", Some(word)) => word.to_string(),
+        _ => {
+            let hex = bytes.iter().map(|b| format!("{b:02x}")).collect::<String>();
+            format!("0x{hex}")
+        }
+    }
+}
+
+#[test]
+fn test_format_variable() {
+    let variable = |ty: &str, value: Option<Vec<u8>>| Variable {
+        name: "x".to_string(),
+        ty: ty.to_string(),
+        value,
+    };
+    assert_eq!(
+        format_variable(&variable("u64", Some(42u64.to_be_bytes().to_vec()))),
+        "42"
+    );
+    assert_eq!(
+        format_variable(&variable("bool", Some(1u64.to_be_bytes().to_vec()))),
+        "true"
+    );
+    assert_eq!(
+        format_variable(&variable("b256", Some(vec![0xab; 4]))),
+        "0xabababab"
+    );
+    assert_eq!(format_variable(&variable("u64", None)), "<optimized out>");
+}
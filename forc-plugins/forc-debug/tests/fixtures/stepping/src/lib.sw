@@ -0,0 +1,17 @@
+library;
+
+#[inline(never)]
+fn double(x: u64) -> u64 {
+    let doubled = x + x;
+    doubled
+}
+
+#[test]
+fn test_double() {
+    let input = 21;
+    let output = double(input);
+    let pair = (input, output);
+    if pair.1 != 42 {
+        __revert(1);
+    }
+}
@@ -0,0 +1,132 @@
+use forc_debug::Server;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+fn fixture_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/stepping")
+}
+
+/// Frame each request as the client would.
+fn requests(requests: &[(&str, Value)]) -> Vec<u8> {
+    let mut input = String::new();
+    for (seq, (command, arguments)) in requests.iter().enumerate() {
+        let request = json!({
+            "seq": seq + 1,
+            "type": "request",
+            "command": command,
+            "arguments": arguments,
+        })
+        .to_string();
+        input.push_str(&format!(
+            "Content-Length: {}\r\n\r\n{request}",
+            request.len()
+        ));
+    }
+    input.into_bytes()
+}
+
+/// Split the server's output into its messages.
+fn messages(output: &[u8]) -> Vec<Value> {
+    let mut output = std::str::from_utf8(output).unwrap();
+    let mut messages = vec![];
+    while let Some((header, rest)) = output.split_once("\r\n\r\n") {
+        let len = header
+            .strip_prefix("Content-Length: ")
+            .unwrap()
+            .parse::<usize>()
+            .unwrap();
+        messages.push(serde_json::from_str(&rest[..len]).unwrap());
+        output = &rest[len..];
+    }
+    messages
+}
+
+fn response(messages: &[Value], request_seq: u64) -> &Value {
+    let response = messages
+        .iter()
+        .find(|message| message["type"] == "response" && message["request_seq"] == request_seq)
+        .unwrap();
+    assert_eq!(response["success"], true, "{response}");
+    &response["body"]
+}
+
+fn frame_line(messages: &[Value], request_seq: u64) -> u64 {
+    response(messages, request_seq)["stackFrames"][0]["line"]
+        .as_u64()
+        .unwrap()
+}
+
+fn variable<'a>(messages: &'a [Value], request_seq: u64, name: &str) -> &'a Value {
+    response(messages, request_seq)["variables"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|variable| variable["name"] == name)
+        .unwrap()
+}
+
+#[test]
+fn step_through_a_test() {
+    let dir = fixture_dir();
+    let source = dir.join("src/lib.sw");
+    let input = requests(&[
+        ("initialize", json!({})),
+        ("launch", json!({ "program": dir, "test": "test_double" })),
+        (
+            "setBreakpoints",
+            json!({ "source": { "path": source }, "breakpoints": [{ "line": 12 }] }),
+        ),
+        ("configurationDone", json!({})),
+        ("stackTrace", json!({ "threadId": 1 })),
+        ("variables", json!({ "variablesReference": 2 })),
+        ("stepIn", json!({ "threadId": 1 })),
+        ("stackTrace", json!({ "threadId": 1 })),
+        ("stepOut", json!({ "threadId": 1 })),
+        ("stackTrace", json!({ "threadId": 1 })),
+        ("next", json!({ "threadId": 1 })),
+        ("variables", json!({ "variablesReference": 2 })),
+        ("continue", json!({ "threadId": 1 })),
+        ("disconnect", json!({})),
+    ]);
+    let mut output = vec![];
+    Server::new(input.as_slice(), &mut output).run().unwrap();
+    let _ = std::fs::remove_dir_all(dir.join("out"));
+    let _ = std::fs::remove_file(dir.join("Forc.lock"));
+    let messages = messages(&output);
+
+    let breakpoints = &response(&messages, 3)["breakpoints"];
+    assert_eq!(breakpoints, &json!([{ "verified": true, "line": 12 }]));
+
+    // The breakpoint is hit before `output` is assigned.
+    assert_eq!(frame_line(&messages, 5), 12);
+    let input = variable(&messages, 6, "input");
+    assert_eq!(input["value"], "21");
+    assert_eq!(input["type"], "u64");
+
+    // Stepping in stops within `double`, and stepping out returns to the test.
+    assert!((4..=7).contains(&frame_line(&messages, 8)));
+    assert!((10..=17).contains(&frame_line(&messages, 10)));
+
+    // Stepping over the rest of the call's line assigns `output`.
+    assert_eq!(variable(&messages, 12, "output")["value"], "42");
+
+    let stops = messages
+        .iter()
+        .filter(|message| message["event"] == "stopped")
+        .map(|message| message["body"]["reason"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(stops, ["breakpoint", "step", "step", "step"]);
+    assert!(messages
+        .iter()
+        .any(|message| message["event"] == "terminated"));
+}
+
+#[test]
+fn stepping_requires_a_launched_test() {
+    let input = requests(&[("next", json!({ "threadId": 1 }))]);
+    let mut output = vec![];
+    Server::new(input.as_slice(), &mut output).run().unwrap();
+    let messages = messages(&output);
+    assert_eq!(messages[0]["success"], false);
+    assert_eq!(messages[0]["message"], "no test has been launched");
+}
@@ -127,8 +127,8 @@ impl Document {
                 }
             }
         }
-        // if there is another submodule we need to go a level deeper
-        if let Some((_, submodule)) = typed_submodule.module.submodules.first() {
+        // if there are further submodules we need to go a level deeper
+        for (_, submodule) in &typed_submodule.module.submodules {
             Document::from_ty_submodule(
                 decl_engine,
                 submodule,
@@ -365,7 +365,10 @@ fn create_preview(raw_attributes: Option<String>) -> Option<String> {
 
 #[cfg(test)]
 mod tests {
-    use super::ModuleInfo;
+    use super::{Document, ModuleInfo};
+    use forc_pkg::{self as pkg, manifest::ManifestFile};
+    use std::fs;
+    use sway_core::{decl_engine::DeclEngine, BuildTarget, Engines, TypeEngine};
 
     #[test]
     fn test_parent() {
@@ -382,4 +385,78 @@ mod tests {
         let project_opt = module_info.parent();
         assert_eq!(None, project_opt);
     }
+
+    #[test]
+    fn test_all_submodules_are_documented() {
+        let dir = std::env::temp_dir().join(format!("forc-doc-submodules-{}", std::process::id()));
+        fs::create_dir_all(dir.join("src/first")).unwrap();
+        let write = |path: &str, contents: &str| fs::write(dir.join(path), contents).unwrap();
+        write(
+            "Forc.toml",
+            "[project]\nauthors = [\"Fuel Labs <contact@fuel.sh>\"]\nentry = \"lib.sw\"\n\
+             implicit-std = false\nlicense = \"Apache-2.0\"\nname = \"docs\"\n",
+        );
+        write(
+            "src/lib.sw",
+            "library;\n\nmod first;\nmod second;\n\npub struct Root {\n    value: u64,\n}\n",
+        );
+        write(
+            "src/first.sw",
+            "library;\n\nmod nested;\n\npub struct First {\n    value: u64,\n}\n",
+        );
+        write(
+            "src/first/nested.sw",
+            "library;\n\npub struct Nested {\n    value: u64,\n}\n",
+        );
+        write(
+            "src/second.sw",
+            "library;\n\npub struct Second {\n    value: u64,\n}\n",
+        );
+
+        let manifest = ManifestFile::from_dir(&dir).unwrap();
+        let member_manifests = manifest.member_manifests().unwrap();
+        let lock_path = manifest.lock_path().unwrap();
+        let plan =
+            pkg::BuildPlan::from_lock_and_manifests(&lock_path, &member_manifests, false, true)
+                .unwrap();
+        let type_engine = TypeEngine::default();
+        let decl_engine = DeclEngine::default();
+        let engines = Engines::new(&type_engine, &decl_engine);
+        let compilation = pkg::check(&plan, BuildTarget::default(), true, true, engines)
+            .unwrap()
+            .pop()
+            .unwrap();
+        let typed_program = compilation
+            .value
+            .and_then(|programs| programs.typed)
+            .unwrap();
+        let documented = |no_deps: bool| {
+            Document::from_ty_program(&decl_engine, "docs", &typed_program, no_deps, false)
+                .unwrap()
+                .iter()
+                .map(|doc| {
+                    format!(
+                        "{}::{}",
+                        doc.module_info.module_prefixes.join("::"),
+                        doc.item_header.item_name.as_str()
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Every submodule is documented, not only the first at each level.
+        assert_eq!(
+            documented(false),
+            [
+                "docs::Root",
+                "docs::first::First",
+                "docs::first::nested::Nested",
+                "docs::second::Second"
+            ]
+        );
+        // `--no-deps` only documents the root module.
+        assert_eq!(documented(true), ["docs::Root"]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
@@ -4,7 +4,7 @@ mod doc;
 mod render;
 
 use crate::{
-    doc::{Document, Documentation},
+    doc::{Document, Documentation, ModuleInfo, ModulePrefix},
     render::{RenderedDocumentation, INDEX_FILENAME},
 };
 use anyhow::{bail, Result};
@@ -15,31 +15,53 @@ use forc_util::default_output_directory;
 use include_dir::{include_dir, Dir};
 use pkg::manifest::ManifestFile;
 use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
     process::Command as Process,
     sync::Arc,
-    {fs, path::PathBuf},
 };
-use sway_core::{decl_engine::DeclEngine, BuildTarget, Engines, TypeEngine};
+use sway_core::{
+    decl_engine::DeclEngine, language::ty::TyProgram, BuildTarget, Engines, TypeEngine,
+};
 
 /// Information passed to the render phase to get TypeInfo, CallPath or visibility for type anchors.
 #[derive(Clone)]
 struct RenderPlan {
     document_private_items: bool,
+    /// Whether the package being rendered is a dependency of the documented package.
+    is_dependency: bool,
+    /// The module prefixes and file names of every page that is written.
+    documented_pages: Arc<HashSet<(Vec<ModulePrefix>, String)>>,
     type_engine: Arc<TypeEngine>,
     decl_engine: Arc<DeclEngine>,
 }
 impl RenderPlan {
     fn new(
         document_private_items: bool,
+        is_dependency: bool,
+        documented_pages: Arc<HashSet<(Vec<ModulePrefix>, String)>>,
         type_engine: Arc<TypeEngine>,
         decl_engine: Arc<DeclEngine>,
     ) -> RenderPlan {
         Self {
             document_private_items,
+            is_dependency,
+            documented_pages,
             type_engine,
             decl_engine,
         }
     }
+    /// The relative path from a page within the given module to the root of the documentation.
+    fn path_to_doc_root(&self, module_info: &ModuleInfo) -> String {
+        // The documented package's modules are written directly to the root, whereas each
+        // dependency is written to a directory named after it.
+        let depth = match self.is_dependency {
+            true => module_info.depth(),
+            false => module_info.depth() - 1,
+        };
+        "../".repeat(depth)
+    }
 }
 
 /// Main method for `forc doc`.
@@ -74,62 +96,160 @@ pub fn main() -> Result<()> {
     let doc_path = out_path.join(DOC_DIR_NAME);
     fs::create_dir_all(&doc_path)?;
 
-    // compile the program and extract the docs
+    // compile the program and its dependencies
     let member_manifests = manifest.member_manifests()?;
     let lock_path = manifest.lock_path()?;
     let plan =
         pkg::BuildPlan::from_lock_and_manifests(&lock_path, &member_manifests, locked, offline)?;
-    let type_engine = TypeEngine::default();
-    let decl_engine = DeclEngine::default();
+    let type_engine = Arc::new(TypeEngine::default());
+    let decl_engine = Arc::new(DeclEngine::default());
     let engines = Engines::new(&type_engine, &decl_engine);
     let tests_enabled = true;
-    let typed_program = match pkg::check(
+    let compilations = pkg::check(
         &plan,
         BuildTarget::default(),
         silent,
         tests_enabled,
         engines,
-    )?
-    .pop()
-    .and_then(|compilation| compilation.value)
-    .and_then(|programs| programs.typed)
-    {
-        Some(typed_program) => typed_program,
-        _ => bail!("CompileResult returned None"),
+    )?;
+    // there is a result for each package in compilation order, ending with the project itself
+    let mut typed_programs = vec![];
+    for (&node, compilation) in plan.compilation_order().iter().zip(compilations) {
+        match compilation.value.and_then(|programs| programs.typed) {
+            Some(typed_program) => typed_programs.push((&plan.graph()[node].name, typed_program)),
+            None => bail!("CompileResult returned None"),
+        }
+    }
+    let typed_program = match typed_programs.pop() {
+        Some((_, typed_program)) => typed_program,
+        None => bail!("CompileResult returned None"),
     };
-    let raw_docs: Documentation = Document::from_ty_program(
-        &decl_engine,
-        project_name,
+
+    // gather the docs of the project, and unless `--no-deps` is passed, those of its dependencies
+    let mut packages = vec![(
+        false,
+        doc_path.clone(),
+        project_name.as_str(),
         &typed_program,
-        no_deps,
-        document_private_items,
-    )?;
-    let root_attributes =
-        (!typed_program.root.attributes.is_empty()).then_some(typed_program.root.attributes);
-    let program_kind = typed_program.kind;
-    // render docs to HTML
+        Document::from_ty_program(
+            &decl_engine,
+            project_name,
+            &typed_program,
+            no_deps,
+            document_private_items,
+        )?,
+    )];
+    if !no_deps {
+        // each dependency is written to a directory of its own, so that types
+        // from dependencies can be linked to
+        for (dep_name, dep_program) in &typed_programs {
+            packages.push((
+                true,
+                doc_path.join(dep_name),
+                dep_name.as_str(),
+                dep_program,
+                Document::from_ty_program(
+                    &decl_engine,
+                    dep_name,
+                    dep_program,
+                    no_deps,
+                    document_private_items,
+                )?,
+            ));
+        }
+    }
+    // type anchors may only link to the pages that are actually written
+    let documented_pages: Arc<HashSet<(Vec<ModulePrefix>, String)>> = Arc::new(
+        packages
+            .iter()
+            .flat_map(|(_, _, _, _, raw_docs)| raw_docs)
+            .map(|doc| (doc.module_info.module_prefixes.clone(), doc.html_filename()))
+            .collect(),
+    );
+
+    // render the docs to HTML and write them to the doc path
     let forc_version = pkg_manifest
         .project
         .forc_version
         .as_ref()
         .map(|ver| format!("Forc v{}.{}.{}", ver.major, ver.minor, ver.patch));
+    for (is_dependency, path, name, program, raw_docs) in packages {
+        write_docs(
+            &path,
+            name,
+            program,
+            raw_docs,
+            RenderPlan::new(
+                document_private_items,
+                is_dependency,
+                documented_pages.clone(),
+                type_engine.clone(),
+                decl_engine.clone(),
+            ),
+            match is_dependency {
+                true => None,
+                false => forc_version.clone(),
+            },
+        )?;
+    }
+
+    // check if the user wants to open the doc in the browser
+    // if opening in the browser fails, attempt to open using a file explorer
+    if open_result {
+        const BROWSER_ENV_VAR: &str = "BROWSER";
+        let path = doc_path.join(INDEX_FILENAME);
+        let default_browser_opt = std::env::var_os(BROWSER_ENV_VAR);
+        match default_browser_opt {
+            Some(def_browser) => {
+                let browser = PathBuf::from(def_browser);
+                if let Err(e) = Process::new(&browser).arg(path).status() {
+                    bail!(
+                        "Couldn't open docs with {}: {}",
+                        browser.to_string_lossy(),
+                        e
+                    );
+                }
+            }
+            None => {
+                if let Err(e) = opener::open(&path) {
+                    bail!("Couldn't open docs: {}", e);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Render the docs for a single package to HTML, and write them along with
+/// the assets they refer to into `doc_path`.
+fn write_docs(
+    doc_path: &Path,
+    project_name: &str,
+    typed_program: &TyProgram,
+    raw_docs: Documentation,
+    render_plan: RenderPlan,
+    forc_version: Option<String>,
+) -> Result<()> {
+    if raw_docs.is_empty() {
+        // nothing in the package is documentable
+        return Ok(());
+    }
+    let root_attributes =
+        (!typed_program.root.attributes.is_empty()).then(|| typed_program.root.attributes.clone());
     let rendered_docs = RenderedDocumentation::from(
         raw_docs,
-        RenderPlan::new(
-            document_private_items,
-            Arc::from(type_engine),
-            Arc::from(decl_engine),
-        ),
+        render_plan,
         root_attributes,
-        program_kind,
+        typed_program.kind.clone(),
         forc_version,
     )?;
 
     // write contents to outfile
     for doc in rendered_docs.0 {
-        let mut doc_path = doc_path.clone();
+        let mut doc_path = doc_path.to_path_buf();
         for prefix in doc.module_info.module_prefixes {
-            if &prefix != project_name {
+            if prefix != project_name {
                 doc_path.push(prefix);
             }
         }
@@ -152,30 +272,5 @@ pub fn main() -> Result<()> {
     let sway_hjs = std::include_bytes!("assets/highlight.js");
     fs::write(assets_path.join(SWAY_HJS_FILENAME), sway_hjs)?;
 
-    // check if the user wants to open the doc in the browser
-    // if opening in the browser fails, attempt to open using a file explorer
-    if open_result {
-        const BROWSER_ENV_VAR: &str = "BROWSER";
-        let path = doc_path.join(INDEX_FILENAME);
-        let default_browser_opt = std::env::var_os(BROWSER_ENV_VAR);
-        match default_browser_opt {
-            Some(def_browser) => {
-                let browser = PathBuf::from(def_browser);
-                if let Err(e) = Process::new(&browser).arg(path).status() {
-                    bail!(
-                        "Couldn't open docs with {}: {}",
-                        browser.to_string_lossy(),
-                        e
-                    );
-                }
-            }
-            None => {
-                if let Err(e) = opener::open(&path) {
-                    bail!("Couldn't open docs: {}", e);
-                }
-            }
-        }
-    }
-
     Ok(())
 }
@@ -890,12 +890,18 @@ fn render_type_anchor(
             } else {
                 let module_info = ModuleInfo::from_call_path(enum_decl.call_path);
                 let file_name = format!("enum.{}.html", decl_ref.name().clone().as_str());
-                let href = module_info.file_path_from_location(&file_name, current_module_info)?;
-                Ok(box_html! {
-                    a(class="enum", href=href) {
+                let href =
+                    type_anchor_href(&module_info, &file_name, render_plan, current_module_info)?;
+                match href {
+                    Some(href) => Ok(box_html! {
+                        a(class="enum", href=href) {
+                            : decl_ref.name().clone().as_str();
+                        }
+                    }),
+                    None => Ok(box_html! {
                         : decl_ref.name().clone().as_str();
-                    }
-                })
+                    }),
+                }
             }
         }
         TypeInfo::Struct(decl_ref) => {
@@ -907,12 +913,18 @@ fn render_type_anchor(
             } else {
                 let module_info = ModuleInfo::from_call_path(struct_decl.call_path);
                 let file_name = format!("struct.{}.html", decl_ref.name().clone().as_str());
-                let href = module_info.file_path_from_location(&file_name, current_module_info)?;
-                Ok(box_html! {
-                    a(class="struct", href=href) {
+                let href =
+                    type_anchor_href(&module_info, &file_name, render_plan, current_module_info)?;
+                match href {
+                    Some(href) => Ok(box_html! {
+                        a(class="struct", href=href) {
+                            : decl_ref.name().clone().as_str();
+                        }
+                    }),
+                    None => Ok(box_html! {
                         : decl_ref.name().clone().as_str();
-                    }
-                })
+                    }),
+                }
             }
         }
         TypeInfo::UnknownGeneric { name, .. } => Ok(box_html! {
@@ -959,6 +971,36 @@ fn render_type_anchor(
     }
 }
 
+/// Creates the path from the current module to the doc of a type declared in `module_info`.
+///
+/// Types declared in another package link to the docs of that dependency. Returns `None`
+/// if no page is written for the type, e.g. when `--no-deps` is passed.
+fn type_anchor_href(
+    module_info: &ModuleInfo,
+    file_name: &str,
+    render_plan: &RenderPlan,
+    current_module_info: &ModuleInfo,
+) -> Result<Option<String>> {
+    if !render_plan
+        .documented_pages
+        .contains(&(module_info.module_prefixes.clone(), file_name.to_owned()))
+    {
+        return Ok(None);
+    }
+    if module_info.project_name() == current_module_info.project_name() {
+        module_info
+            .file_path_from_location(file_name, current_module_info)
+            .map(Some)
+    } else {
+        Ok(Some(format!(
+            "{}{}/{}",
+            render_plan.path_to_doc_root(current_module_info),
+            module_info.module_prefixes.join("/"),
+            file_name
+        )))
+    }
+}
+
 /// Used for creating links between docs.
 #[derive(Debug, Clone, Ord, PartialOrd, Eq, PartialEq)]
 pub(crate) struct DocLink {
@@ -1602,3 +1644,92 @@ pub(crate) fn split_at_markdown_header(raw_html: &str) -> &str {
         raw_html
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::type_anchor_href;
+    use crate::{
+        doc::{ModuleInfo, ModulePrefix},
+        RenderPlan,
+    };
+    use std::{collections::HashSet, sync::Arc};
+    use sway_core::{decl_engine::DeclEngine, TypeEngine};
+
+    fn module_info(module_prefixes: &[&str]) -> ModuleInfo {
+        ModuleInfo::from_ty_module(
+            module_prefixes
+                .iter()
+                .map(|prefix| prefix.to_string())
+                .collect(),
+            None,
+        )
+    }
+
+    fn render_plan(is_dependency: bool) -> RenderPlan {
+        let page = |module_prefixes: &[&str], file_name: &str| -> (Vec<ModulePrefix>, String) {
+            (
+                module_prefixes
+                    .iter()
+                    .map(|prefix| prefix.to_string())
+                    .collect(),
+                file_name.to_string(),
+            )
+        };
+        RenderPlan::new(
+            false,
+            is_dependency,
+            Arc::new(HashSet::from([
+                page(&["project", "types"], "struct.Local.html"),
+                page(&["dep"], "struct.Foreign.html"),
+            ])),
+            Arc::new(TypeEngine::default()),
+            Arc::new(DeclEngine::default()),
+        )
+    }
+
+    #[test]
+    fn test_type_anchor_href() {
+        let href =
+            |type_module: &[&str], file_name: &str, is_dependency: bool, current: &[&str]| {
+                type_anchor_href(
+                    &module_info(type_module),
+                    file_name,
+                    &render_plan(is_dependency),
+                    &module_info(current),
+                )
+                .unwrap()
+            };
+
+        // Types declared in the same package link relative to the current module.
+        assert_eq!(
+            href(
+                &["project", "types"],
+                "struct.Local.html",
+                false,
+                &["project", "nested"]
+            )
+            .as_deref(),
+            Some("../types/struct.Local.html")
+        );
+        // Types declared in a dependency link to the directory its docs are written to.
+        assert_eq!(
+            href(
+                &["dep"],
+                "struct.Foreign.html",
+                false,
+                &["project", "nested"]
+            )
+            .as_deref(),
+            Some("../dep/struct.Foreign.html")
+        );
+        assert_eq!(
+            href(&["dep"], "struct.Foreign.html", true, &["other", "inner"]).as_deref(),
+            Some("../../dep/struct.Foreign.html")
+        );
+        // Types without a page of their own, e.g. with `--no-deps`, aren't linked.
+        assert_eq!(
+            href(&["dep"], "struct.Hidden.html", false, &["project"]),
+            None
+        );
+    }
+}
@@ -0,0 +1,358 @@
+//! Running `#[bench]` functions and comparing their gas usage against a stored baseline.
+//!
+//! Benchmarks are built and executed in exactly the same way as tests.  Gas usage is
+//! deterministic so a single run is enough to measure it, but each benchmark is executed a number
+//! of times so that statistics may be reported for the time taken.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+    time::Duration,
+};
+
+use forc_pkg as pkg;
+use fuel_vm as vm;
+use serde::{Deserialize, Serialize};
+use sway_types::Span;
+
+use crate::{exec_test, BuiltTests, PackageTests};
+
+/// The result of benchmarking a specific package.
+#[derive(Debug)]
+pub struct BenchedPackage {
+    pub built: Box<pkg::BuiltPackage>,
+    pub benches: Vec<BenchResult>,
+}
+
+/// The result of executing a single benchmark within a single package.
+#[derive(Debug)]
+pub struct BenchResult {
+    /// The name of the function.
+    pub name: String,
+    /// The span for the function declaring this benchmark.
+    pub span: Span,
+    /// The resulting state after executing the benchmark function.
+    pub state: vm::state::ProgramState,
+    /// Gas used by a single execution of this benchmark.
+    pub gas_used: u64,
+    /// The time taken by each execution of this benchmark.
+    pub durations: Vec<Duration>,
+}
+
+/// The gas used by each benchmark, saved so that later runs may be checked for regressions.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BenchBaseline {
+    /// Gas used keyed by `<package name>::<benchmark name>`.
+    pub gas_used: BTreeMap<String, u64>,
+    /// The benchmarks which reverted, keyed in the same way.
+    #[serde(default)]
+    pub reverted: BTreeSet<String>,
+}
+
+/// A benchmark's gas usage compared to its baseline.
+///
+/// The gas used is `None` for a run in which the benchmark reverted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasComparison {
+    pub baseline: Option<u64>,
+    pub current: Option<u64>,
+}
+
+impl BuiltTests {
+    /// The total number of benchmarks.
+    pub fn bench_count(&self) -> usize {
+        self.pkgs()
+            .iter()
+            .map(|pkg| bench_entries(pkg).count())
+            .sum()
+    }
+
+    /// Run all built benchmarks, executing each of them `iterations` times.
+    pub fn run_benches(self, iterations: usize) -> anyhow::Result<Vec<BenchedPackage>> {
+        self.pkgs()
+            .into_iter()
+            .map(|pkg| pkg.run_benches(iterations))
+            .collect()
+    }
+
+    pub(crate) fn pkgs(&self) -> Vec<&PackageTests> {
+        match self {
+            BuiltTests::Package(pkg) => vec![pkg],
+            BuiltTests::Workspace(workspace) => workspace.iter().collect(),
+        }
+    }
+}
+
+impl PackageTests {
+    /// Run all benchmarks for this package and collect their results.
+    fn run_benches(&self, iterations: usize) -> anyhow::Result<BenchedPackage> {
+        let pkg_with_benches = self.built_pkg_with_tests();
+        let benches = bench_entries(self)
+            .map(|(entry, bench_entry)| {
+                let offset = u32::try_from(entry.finalized.imm)
+                    .expect("bench instruction offset out of range");
+                let mut durations = Vec::with_capacity(iterations);
+                let mut outcome = None;
+                for _ in 0..iterations.max(1) {
+                    let bench_setup = self.setup()?;
+                    let (state, duration, receipts) =
+                        exec_test(&pkg_with_benches.bytecode.bytes, offset, bench_setup);
+                    let gas_used = receipts
+                        .iter()
+                        .find_map(|receipt| match receipt {
+                            fuel_tx::Receipt::ScriptResult { gas_used, .. } => Some(*gas_used),
+                            _ => None,
+                        })
+                        .ok_or_else(|| {
+                            anyhow::anyhow!("missing used gas information from bench execution")
+                        })?;
+                    durations.push(duration);
+                    outcome = Some((state, gas_used));
+                }
+                let (state, gas_used) = outcome.expect("benchmarks are executed at least once");
+                Ok(BenchResult {
+                    name: entry.finalized.fn_name.clone(),
+                    span: bench_entry.span.clone(),
+                    state,
+                    gas_used,
+                    durations,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(BenchedPackage {
+            built: Box::new(pkg_with_benches.clone()),
+            benches,
+        })
+    }
+}
+
+fn bench_entries(
+    pkg: &PackageTests,
+) -> impl Iterator<Item = (&pkg::PkgEntry, &pkg::PkgBenchEntry)> {
+    pkg.built_pkg_with_tests()
+        .bytecode
+        .entries
+        .iter()
+        .filter_map(|entry| entry.kind.bench().map(|bench| (entry, bench)))
+}
+
+impl BenchResult {
+    /// Whether or not the benchmark reverted, in which case its measurements are meaningless.
+    pub fn reverted(&self) -> bool {
+        matches!(self.state, vm::state::ProgramState::Revert(_))
+    }
+
+    /// The fastest execution time.
+    pub fn min(&self) -> Duration {
+        self.durations.iter().min().copied().unwrap_or_default()
+    }
+
+    /// The slowest execution time.
+    pub fn max(&self) -> Duration {
+        self.durations.iter().max().copied().unwrap_or_default()
+    }
+
+    /// The mean execution time.
+    pub fn mean(&self) -> Duration {
+        match self.durations.len() {
+            0 => Duration::default(),
+            len => self.durations.iter().sum::<Duration>() / len as u32,
+        }
+    }
+
+    /// The median execution time.
+    pub fn median(&self) -> Duration {
+        let mut sorted = self.durations.clone();
+        sorted.sort();
+        match sorted.len() {
+            0 => Duration::default(),
+            len if len % 2 == 0 => (sorted[len / 2 - 1] + sorted[len / 2]) / 2,
+            len => sorted[len / 2],
+        }
+    }
+
+    /// The standard deviation of the execution times.
+    pub fn std_dev(&self) -> Duration {
+        if self.durations.len() < 2 {
+            return Duration::default();
+        }
+        let mean = self.mean().as_secs_f64();
+        let variance = self
+            .durations
+            .iter()
+            .map(|duration| (duration.as_secs_f64() - mean).powi(2))
+            .sum::<f64>()
+            / (self.durations.len() - 1) as f64;
+        Duration::from_secs_f64(variance.sqrt())
+    }
+}
+
+impl BenchBaseline {
+    /// Collect the gas used by every benchmark in the given packages.
+    pub fn from_benched(pkgs: &[BenchedPackage]) -> Self {
+        let mut baseline = Self::default();
+        for pkg in pkgs {
+            for bench in &pkg.benches {
+                baseline.insert(baseline_key(pkg, bench), bench);
+            }
+        }
+        baseline
+    }
+
+    fn insert(&mut self, key: String, bench: &BenchResult) {
+        if bench.reverted() {
+            self.reverted.insert(key);
+        } else {
+            self.gas_used.insert(key, bench.gas_used);
+        }
+    }
+
+    /// Load a baseline previously written with `save`.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = fs::read_to_string(path).map_err(|e| {
+            anyhow::anyhow!("failed to read bench baseline {}: {e}", path.display())
+        })?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Write this baseline to `path` as JSON.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Compare a benchmark's gas usage to this baseline.  Returns `None` if the benchmark isn't in
+    /// the baseline.
+    pub fn compare(&self, pkg: &BenchedPackage, bench: &BenchResult) -> Option<GasComparison> {
+        self.compare_key(&baseline_key(pkg, bench), bench)
+    }
+
+    fn compare_key(&self, key: &str, bench: &BenchResult) -> Option<GasComparison> {
+        let baseline = match self.gas_used.get(key) {
+            Some(gas_used) => Some(*gas_used),
+            None if self.reverted.contains(key) => None,
+            None => return None,
+        };
+        let current = (!bench.reverted()).then_some(bench.gas_used);
+        Some(GasComparison { baseline, current })
+    }
+}
+
+impl GasComparison {
+    /// Whether more gas is used than in the baseline, or the benchmark has started reverting.
+    pub fn is_regression(&self) -> bool {
+        match (self.baseline, self.current) {
+            (Some(baseline), Some(current)) => current > baseline,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    /// The change in gas used as a percentage of the baseline, if it reverted in neither run.
+    pub fn percent_change(&self) -> Option<f64> {
+        match (self.baseline, self.current) {
+            (Some(0), Some(_)) => Some(0.0),
+            (Some(baseline), Some(current)) => {
+                Some((current as f64 - baseline as f64) * 100.0 / baseline as f64)
+            }
+            _ => None,
+        }
+    }
+}
+
+fn baseline_key(pkg: &BenchedPackage, bench: &BenchResult) -> String {
+    format!("{}::{}", pkg.built.descriptor.name, bench.name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bench(state: vm::state::ProgramState, gas_used: u64, millis: &[u64]) -> BenchResult {
+        BenchResult {
+            name: "bench".to_string(),
+            span: Span::dummy(),
+            state,
+            gas_used,
+            durations: millis.iter().copied().map(Duration::from_millis).collect(),
+        }
+    }
+
+    fn returned(gas_used: u64, millis: &[u64]) -> BenchResult {
+        bench(vm::state::ProgramState::Return(0), gas_used, millis)
+    }
+
+    fn reverted() -> BenchResult {
+        bench(vm::state::ProgramState::Revert(0), 0, &[1])
+    }
+
+    #[test]
+    fn median() {
+        assert_eq!(returned(0, &[]).median(), Duration::default());
+        assert_eq!(returned(0, &[3, 1, 2]).median(), Duration::from_millis(2));
+        assert_eq!(
+            returned(0, &[4, 1, 3, 2]).median(),
+            Duration::from_micros(2500)
+        );
+    }
+
+    #[test]
+    fn std_dev() {
+        assert_eq!(returned(0, &[5]).std_dev(), Duration::default());
+        assert_eq!(returned(0, &[7, 7, 7]).std_dev(), Duration::default());
+        // The sample variance of 2, 4, 4, 4, 5, 5, 7 and 9 is 32 / 7.
+        let std_dev = returned(0, &[2, 4, 4, 4, 5, 5, 7, 9]).std_dev();
+        assert!((std_dev.as_secs_f64() * 1000.0 - (32.0f64 / 7.0).sqrt()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn compare() {
+        let mut baseline = BenchBaseline::default();
+        baseline.insert("pkg::cheap".to_string(), &returned(100, &[1]));
+        baseline.insert("pkg::broken".to_string(), &reverted());
+
+        let comparison = baseline.compare_key("pkg::cheap", &returned(150, &[1]));
+        assert_eq!(
+            comparison,
+            Some(GasComparison {
+                baseline: Some(100),
+                current: Some(150)
+            })
+        );
+        assert!(comparison.unwrap().is_regression());
+        assert!(!baseline
+            .compare_key("pkg::cheap", &returned(100, &[1]))
+            .unwrap()
+            .is_regression());
+
+        // Starting to revert is a regression, while still reverting or no longer reverting isn't.
+        assert!(baseline
+            .compare_key("pkg::cheap", &reverted())
+            .unwrap()
+            .is_regression());
+        assert!(!baseline
+            .compare_key("pkg::broken", &reverted())
+            .unwrap()
+            .is_regression());
+        assert!(!baseline
+            .compare_key("pkg::broken", &returned(100, &[1]))
+            .unwrap()
+            .is_regression());
+
+        assert_eq!(baseline.compare_key("pkg::new", &returned(100, &[1])), None);
+    }
+
+    #[test]
+    fn percent_change() {
+        let change = |baseline, current| GasComparison { baseline, current }.percent_change();
+        assert_eq!(change(Some(200), Some(250)), Some(25.0));
+        assert_eq!(change(Some(200), Some(150)), Some(-25.0));
+        assert_eq!(change(Some(0), Some(10)), Some(0.0));
+        assert_eq!(change(Some(200), None), None);
+        assert_eq!(change(None, Some(200)), None);
+    }
+}
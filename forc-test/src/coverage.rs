@@ -0,0 +1,205 @@
+//! Line and branch coverage for tests built with coverage instrumentation.
+//!
+//! Each instrumented block and call site is attributed to the line on which its source begins.
+//! Counters are collected both from the test scripts themselves and, for contract packages, from
+//! the deployed contract whose methods the `#[test]` functions call via its ABI.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use sway_core::CoveragePoint;
+use sway_types::Span;
+
+use crate::TestedPackage;
+
+/// Line and branch coverage accumulated across the tests of one or more packages.
+#[derive(Debug, Default)]
+pub struct CoverageReport {
+    files: BTreeMap<Arc<PathBuf>, FileCoverage>,
+}
+
+#[derive(Debug)]
+struct FileCoverage {
+    src: Arc<str>,
+    /// The execution count for each line on which an instrumented block begins.
+    lines: BTreeMap<usize, u64>,
+    /// The execution count for each arm of each conditional branch, keyed by the line of the
+    /// condition, the branch ID and whether it is the true arm.
+    branches: BTreeMap<(usize, u64, bool), u64>,
+}
+
+impl CoverageReport {
+    /// Accumulate the coverage from all of the tests of the given package.
+    ///
+    /// Only source files within the package's own directory are included.
+    pub fn add_package(&mut self, pkg: &TestedPackage) {
+        let pkg_dir = pkg.built.descriptor.manifest_file.dir();
+        for point in &pkg.built.coverage_points {
+            let hits = pkg
+                .tests
+                .iter()
+                .map(|test| test.coverage.get(&point.id).copied().unwrap_or(0))
+                .sum::<u64>();
+            self.add_point(pkg_dir, point, hits);
+        }
+        for point in &pkg.built.contract_coverage_points {
+            let hits = pkg
+                .tests
+                .iter()
+                .map(|test| test.contract_coverage.get(&point.id).copied().unwrap_or(0))
+                .sum::<u64>();
+            self.add_point(pkg_dir, point, hits);
+        }
+    }
+
+    fn add_point(&mut self, pkg_dir: &Path, point: &CoveragePoint, hits: u64) {
+        if let Some(span) = &point.span {
+            if let Some(file) = self.file_coverage(pkg_dir, span) {
+                *file.lines.entry(start_line(span)).or_default() += hits;
+            }
+        }
+
+        if let Some(branch) = &point.branch {
+            if let Some(span) = branch.span.as_ref().or(point.span.as_ref()) {
+                if let Some(file) = self.file_coverage(pkg_dir, span) {
+                    let key = (start_line(span), branch.id, branch.is_true_arm);
+                    *file.branches.entry(key).or_default() += hits;
+                }
+            }
+        }
+    }
+
+    /// Whether any coverage has been collected at all.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Render the report in the `lcov` tracefile format.
+    pub fn to_lcov(&self) -> String {
+        let mut lcov = String::new();
+        for (path, file) in &self.files {
+            let _ = writeln!(lcov, "TN:");
+            let _ = writeln!(lcov, "SF:{}", path.display());
+            for ((line, id, is_true_arm), taken) in &file.branches {
+                let arm = if *is_true_arm { 0 } else { 1 };
+                let _ = writeln!(lcov, "BRDA:{line},{id},{arm},{taken}");
+            }
+            let _ = writeln!(lcov, "BRF:{}", file.branches.len());
+            let _ = writeln!(lcov, "BRH:{}", count_hit(file.branches.values()));
+            for (line, hits) in &file.lines {
+                let _ = writeln!(lcov, "DA:{line},{hits}");
+            }
+            let _ = writeln!(lcov, "LF:{}", file.lines.len());
+            let _ = writeln!(lcov, "LH:{}", count_hit(file.lines.values()));
+            let _ = writeln!(lcov, "end_of_record");
+        }
+        lcov
+    }
+
+    /// Render the report as a standalone HTML page, with a summary table followed by the annotated
+    /// source of each file.
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        html.push_str(HTML_HEADER);
+
+        let _ = writeln!(html, "<table>");
+        let _ = writeln!(
+            html,
+            "<tr><th>File</th><th>Lines</th><th>Branches</th></tr>"
+        );
+        for (idx, (path, file)) in self.files.iter().enumerate() {
+            let _ = writeln!(
+                html,
+                "<tr><td><a href=\"#file{idx}\">{}</a></td><td>{}</td><td>{}</td></tr>",
+                escape_html(&path.display().to_string()),
+                ratio(count_hit(file.lines.values()), file.lines.len()),
+                ratio(count_hit(file.branches.values()), file.branches.len()),
+            );
+        }
+        let _ = writeln!(html, "</table>");
+
+        for (idx, (path, file)) in self.files.iter().enumerate() {
+            let _ = writeln!(
+                html,
+                "<h2 id=\"file{idx}\">{}</h2>",
+                escape_html(&path.display().to_string())
+            );
+            let _ = writeln!(html, "<pre>");
+            for (line_idx, line) in file.src.lines().enumerate() {
+                let line_number = line_idx + 1;
+                let (class, hits) = match file.lines.get(&line_number) {
+                    Some(0) => ("miss", "0".to_string()),
+                    Some(hits) => ("hit", hits.to_string()),
+                    None => ("none", String::new()),
+                };
+                let _ = writeln!(
+                    html,
+                    "<span class=\"{class}\">{line_number:>5} {hits:>7} | {}</span>",
+                    escape_html(line)
+                );
+            }
+            let _ = writeln!(html, "</pre>");
+        }
+
+        html.push_str(HTML_FOOTER);
+        html
+    }
+
+    fn file_coverage(&mut self, pkg_dir: &Path, span: &Span) -> Option<&mut FileCoverage> {
+        let path = span.path().filter(|path| path.starts_with(pkg_dir))?;
+        Some(
+            self.files
+                .entry(path.clone())
+                .or_insert_with(|| FileCoverage {
+                    src: span.src().clone(),
+                    lines: BTreeMap::new(),
+                    branches: BTreeMap::new(),
+                }),
+        )
+    }
+}
+
+const HTML_HEADER: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Coverage report</title>
+<style>
+body { font-family: sans-serif; }
+pre span { display: block; }
+.hit { background-color: #dfd; }
+.miss { background-color: #fdd; }
+td, th { padding: 0 1em; text-align: left; }
+</style>
+</head>
+<body>
+<h1>Coverage report</h1>
+"#;
+
+const HTML_FOOTER: &str = "</body>\n</html>\n";
+
+fn start_line(span: &Span) -> usize {
+    span.start_pos().line_col().0
+}
+
+fn count_hit<'a>(counts: impl Iterator<Item = &'a u64>) -> usize {
+    counts.filter(|hits| **hits > 0).count()
+}
+
+fn ratio(hit: usize, total: usize) -> String {
+    if total == 0 {
+        "-".to_string()
+    } else {
+        format!("{hit}/{total} ({:.1}%)", hit as f64 * 100.0 / total as f64)
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,362 @@
+//! Executing a single test one instruction at a time, for use by debuggers.
+//!
+//! The test is run exactly as it is by `forc test`, with the VM stopping after every instruction so
+//! that its position in the script can be mapped back to the source via the package's source map.
+//! Only the test script itself is stepped through; while a contract called by the test is
+//! executing, the debugger runs until control returns to the script.
+//!
+//! Each function call grows the stack, as the callee saves the caller's registers there, so the
+//! stack pointer tells whether a step has entered or left a function.  The variables of the
+//! function being executed are found among its locals through the source map, which is only
+//! complete when the tests are built without optimisation; otherwise variables whose values were
+//! promoted to registers are shown as optimized out.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use fuel_tx as tx;
+use fuel_vm::{self as vm, fuel_asm::RegId, prelude::Instruction};
+use sway_core::source_map::SourceMap;
+
+use crate::{test_interpreter, BuiltTests, PackageTests};
+
+/// A test being executed under the control of a debugger.
+pub struct TestDebugger {
+    /// The name of the test function.
+    name: String,
+    interpreter: vm::interpreter::Interpreter<vm::storage::MemoryStorage, tx::Script>,
+    /// The transaction executing the test, until execution begins.
+    tx: Option<vm::checked_transaction::Checked<tx::Script>>,
+    source_map: SourceMap,
+    /// The contents of each source file referred to by the source map, read as required.
+    sources: HashMap<PathBuf, String>,
+    /// Indices of the instructions at which execution should stop.
+    breakpoints: BTreeSet<usize>,
+    /// The final state and receipts, once the test has finished executing.
+    finished: Option<(vm::state::ProgramState, Vec<tx::Receipt>)>,
+}
+
+/// Why execution of a test under the debugger stopped.
+#[derive(Debug)]
+pub enum StopReason {
+    /// A step was completed.
+    Step,
+    /// A breakpoint was reached.
+    Breakpoint,
+    /// The test finished executing with the given state.
+    Finished(vm::state::ProgramState),
+}
+
+/// A user variable of the function being executed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Variable {
+    pub name: String,
+    /// The Sway type of the variable, as it would be written in the source.
+    pub ty: String,
+    /// The bytes of the variable's current value, or `None` if it was optimized out.
+    pub value: Option<Vec<u8>>,
+}
+
+/// A location in a source file, with both the line and the column starting from 1.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl BuiltTests {
+    /// Prepare the test with the given name for execution under a debugger.
+    ///
+    /// Fails if no test has the given name, or if tests in more than one package do.
+    pub fn debug_test(&self, name: &str) -> anyhow::Result<TestDebugger> {
+        let mut found = self
+            .pkgs()
+            .into_iter()
+            .filter(|pkg| test_offset(pkg, name).is_some());
+        let pkg = found
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("no test named `{name}` was found"))?;
+        if found.next().is_some() {
+            anyhow::bail!("tests named `{name}` were found in more than one package");
+        }
+        pkg.debug_test(name)
+    }
+}
+
+impl PackageTests {
+    /// Prepare the test with the given name for execution under a debugger.
+    pub fn debug_test(&self, name: &str) -> anyhow::Result<TestDebugger> {
+        let offset = test_offset(self, name)
+            .ok_or_else(|| anyhow::anyhow!("no test named `{name}` was found"))?;
+        let pkg_with_tests = self.built_pkg_with_tests();
+        let test_setup = self.setup()?;
+        let (mut interpreter, tx) =
+            test_interpreter(&pkg_with_tests.bytecode.bytes, offset, test_setup);
+        interpreter.set_single_stepping(true);
+        Ok(TestDebugger {
+            name: name.to_string(),
+            interpreter,
+            tx: Some(tx),
+            source_map: pkg_with_tests.source_map().clone(),
+            sources: HashMap::new(),
+            breakpoints: BTreeSet::new(),
+            finished: None,
+        })
+    }
+}
+
+impl TestDebugger {
+    /// The name of the test function.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The current value of each of the VM's registers.
+    pub fn registers(&self) -> &[tx::Word] {
+        self.interpreter.registers()
+    }
+
+    /// The user variables of the function being executed, in declaration order.
+    ///
+    /// Where a variable is shadowed only its last declaration is included.
+    pub fn variables(&self) -> Vec<Variable> {
+        let index = match self.instruction_index() {
+            Some(index) => index,
+            None => return vec![],
+        };
+        let stack_pointer = self.stack_pointer();
+        let memory = self.interpreter.memory();
+        let mut variables: Vec<Variable> = vec![];
+        for location in self.source_map.variables_at(index) {
+            let value = location.slot.and_then(|slot| {
+                let start = stack_pointer
+                    .checked_sub(slot.locals_size)?
+                    .checked_add(slot.offset)? as usize;
+                let end = start.checked_add(slot.size as usize)?;
+                memory.get(start..end).map(<[u8]>::to_vec)
+            });
+            variables.retain(|variable| variable.name != location.name);
+            variables.push(Variable {
+                name: location.name.clone(),
+                ty: location.ty.clone(),
+                value,
+            });
+        }
+        variables
+    }
+
+    /// The receipts produced so far.
+    pub fn receipts(&self) -> &[tx::Receipt] {
+        match &self.finished {
+            Some((_, receipts)) => receipts,
+            None => self.interpreter.receipts(),
+        }
+    }
+
+    /// The final state of the VM, once the test has finished executing.
+    pub fn finished_state(&self) -> Option<vm::state::ProgramState> {
+        self.finished.as_ref().map(|(state, _)| *state)
+    }
+
+    /// The index of the next instruction of the test script to be executed.
+    ///
+    /// This is `None` before execution has begun, after it has finished, and while a contract
+    /// called by the test is executing.
+    pub fn instruction_index(&self) -> Option<usize> {
+        if self.tx.is_some() || self.finished.is_some() {
+            return None;
+        }
+        let registers = self.registers();
+        let register = |reg: RegId| registers[reg.to_u8() as usize];
+        if register(RegId::FP) != 0 {
+            return None;
+        }
+        let offset = register(RegId::PC).checked_sub(register(RegId::IS))?;
+        Some(offset as usize / Instruction::SIZE)
+    }
+
+    /// The source location of the next instruction to be executed, if it is known.
+    pub fn location(&mut self) -> Option<SourceLocation> {
+        let index = self.instruction_index()?;
+        let (path, range) = self.source_map.addr_to_span(index)?;
+        let (line, column) = line_and_column(self.source(&path)?, range.start);
+        Some(SourceLocation { path, line, column })
+    }
+
+    /// Replace the breakpoints within the given file with breakpoints on the given lines.
+    ///
+    /// Execution stops at the first instruction generated for each line.  Returns the lines for
+    /// which a breakpoint was set, which excludes those with no instructions.
+    pub fn set_breakpoints(&mut self, path: &Path, lines: &[usize]) -> Vec<usize> {
+        let mut instructions: Vec<(usize, usize)> = vec![];
+        let spans = self
+            .source_map
+            .addr_spans()
+            .filter(|(_, span_path, _)| same_file(span_path, path))
+            .collect::<Vec<_>>();
+        for (index, span_path, range) in spans {
+            if let Some(src) = self.source(&span_path) {
+                instructions.push((line_and_column(src, range.start).0, index));
+            }
+        }
+        instructions.sort();
+
+        let in_file = instructions
+            .iter()
+            .map(|(_, index)| *index)
+            .collect::<Vec<_>>();
+        self.breakpoints.retain(|index| !in_file.contains(index));
+
+        let mut set = vec![];
+        for line in lines {
+            let first = instructions
+                .iter()
+                .find(|(instruction_line, _)| instruction_line == line);
+            if let Some((_, index)) = first {
+                self.breakpoints.insert(*index);
+                set.push(*line);
+            }
+        }
+        set
+    }
+
+    /// Execute until a breakpoint is reached or the test finishes.
+    pub fn resume(&mut self) -> anyhow::Result<StopReason> {
+        loop {
+            if let Some(state) = self.step_instruction()? {
+                return Ok(StopReason::Finished(state));
+            }
+            if self.at_breakpoint() {
+                return Ok(StopReason::Breakpoint);
+            }
+        }
+    }
+
+    /// Execute until the next instruction of the test script.
+    pub fn step_instruction(&mut self) -> anyhow::Result<Option<vm::state::ProgramState>> {
+        loop {
+            if let Some(state) = self.finished_state() {
+                return Ok(Some(state));
+            }
+            let state = match self.tx.take() {
+                Some(tx) => *self.interpreter.transact(tx)?.state(),
+                None => self.interpreter.resume()?,
+            };
+            match state {
+                vm::state::ProgramState::RunProgram(_)
+                | vm::state::ProgramState::VerifyPredicate(_) => {
+                    if self.instruction_index().is_some() {
+                        return Ok(None);
+                    }
+                }
+                state => {
+                    let receipts = self.interpreter.receipts().to_vec();
+                    self.finished = Some((state, receipts));
+                }
+            }
+        }
+    }
+
+    /// Execute until the source line changes, a breakpoint is reached or the test finishes,
+    /// stepping into any function which is called.
+    ///
+    /// Instructions without a known source location are stepped over.
+    pub fn step_line(&mut self) -> anyhow::Result<StopReason> {
+        let start = self.location();
+        self.step_until(|location, _| moved(start.as_ref(), location))
+    }
+
+    /// As for [`TestDebugger::step_line`], but running any function which is called until it
+    /// returns.
+    pub fn step_over(&mut self) -> anyhow::Result<StopReason> {
+        let start = self.location();
+        let stack_pointer = self.stack_pointer();
+        self.step_until(|location, sp| sp <= stack_pointer && moved(start.as_ref(), location))
+    }
+
+    /// Execute until the function being executed returns to its caller, a breakpoint is reached
+    /// or the test finishes.
+    pub fn step_out(&mut self) -> anyhow::Result<StopReason> {
+        let stack_pointer = self.stack_pointer();
+        self.step_until(|_, sp| sp < stack_pointer)
+    }
+
+    /// Execute until an instruction with a known source location for which `stop` returns true,
+    /// given the location and the stack pointer, or until a breakpoint is reached or the test
+    /// finishes.
+    fn step_until(
+        &mut self,
+        mut stop: impl FnMut(&SourceLocation, tx::Word) -> bool,
+    ) -> anyhow::Result<StopReason> {
+        loop {
+            if let Some(state) = self.step_instruction()? {
+                return Ok(StopReason::Finished(state));
+            }
+            if self.at_breakpoint() {
+                return Ok(StopReason::Breakpoint);
+            }
+            if let Some(location) = self.location() {
+                if stop(&location, self.stack_pointer()) {
+                    return Ok(StopReason::Step);
+                }
+            }
+        }
+    }
+
+    fn stack_pointer(&self) -> tx::Word {
+        self.registers()[RegId::SP.to_u8() as usize]
+    }
+
+    fn at_breakpoint(&self) -> bool {
+        self.instruction_index()
+            .map_or(false, |index| self.breakpoints.contains(&index))
+    }
+
+    fn source(&mut self, path: &Path) -> Option<&str> {
+        if !self.sources.contains_key(path) {
+            let src = fs::read_to_string(path).ok()?;
+            self.sources.insert(path.to_path_buf(), src);
+        }
+        self.sources.get(path).map(|src| src.as_str())
+    }
+}
+
+/// The instruction offset of the entry point of the test with the given name.
+fn test_offset(pkg: &PackageTests, name: &str) -> Option<u32> {
+    pkg.built_pkg_with_tests()
+        .bytecode
+        .entries
+        .iter()
+        .filter(|entry| entry.kind.test().is_some())
+        .find(|entry| entry.finalized.fn_name == name)
+        .map(|entry| {
+            u32::try_from(entry.finalized.imm).expect("test instruction offset out of range")
+        })
+}
+
+/// Whether `location` is on a different line to `start`, if execution had a location to start from.
+fn moved(start: Option<&SourceLocation>, location: &SourceLocation) -> bool {
+    start.map_or(true, |start| {
+        start.path != location.path || start.line != location.line
+    })
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    a == b
+        || match (a.canonicalize(), b.canonicalize()) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+}
+
+/// The line and column of the given byte offset within `src`.
+fn line_and_column(src: &str, offset: usize) -> (usize, usize) {
+    let before = &src[..offset.min(src.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |ix| ix + 1) + 1;
+    (line, column)
+}
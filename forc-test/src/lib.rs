@@ -1,4 +1,17 @@
-use std::{collections::HashSet, fs, path::PathBuf, sync::Arc};
+mod bench;
+mod coverage;
+mod debug;
+
+pub use bench::{BenchBaseline, BenchResult, BenchedPackage, GasComparison};
+pub use coverage::CoverageReport;
+pub use debug::{SourceLocation, StopReason, TestDebugger, Variable};
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::PathBuf,
+    sync::Arc,
+};
 
 use forc_pkg as pkg;
 use fuel_abi_types::error_codes::ErrorSignal;
@@ -9,7 +22,7 @@ use fuel_vm::{self as vm, fuel_asm, prelude::Instruction};
 use pkg::TestPassCondition;
 use pkg::{Built, BuiltPackage};
 use rand::{Rng, SeedableRng};
-use sway_core::BuildTarget;
+use sway_core::{BuildTarget, COVERAGE_LOG_ID};
 use sway_types::Span;
 
 /// The result of a `forc test` invocation.
@@ -52,6 +65,13 @@ pub struct TestResult {
     pub logs: Vec<fuel_tx::Receipt>,
     /// Gas used while executing this test.
     pub gas_used: u64,
+    /// The number of times each coverage counter was hit, keyed by the `CoveragePoint` ID.
+    ///
+    /// Always empty unless the tests were built with coverage instrumentation.
+    pub coverage: BTreeMap<u64, u64>,
+    /// The number of times each coverage counter of the contract under test was hit by the calls
+    /// the test made to it, keyed by the ID of one of the package's `contract_coverage_points`.
+    pub contract_coverage: BTreeMap<u64, u64>,
 }
 
 const TEST_METADATA_SEED: u64 = 0x7E57u64;
@@ -104,6 +124,10 @@ pub struct Opts {
     pub error_on_warnings: bool,
     /// Output the time elapsed over each part of the compilation process.
     pub time_phases: bool,
+    /// Instrument the tests with coverage counters and collect them during execution.
+    pub coverage: bool,
+    /// Run only the IR passes required to produce code, so that the tests may be debugged.
+    pub unoptimized: bool,
 }
 
 /// The set of options provided for controlling logs printed for each test.
@@ -187,6 +211,7 @@ impl<'a> PackageTests {
                     .expect("test instruction offset out of range");
                 let name = entry.finalized.fn_name.clone();
                 let test_setup = self.setup()?;
+                let contract_id = test_setup.contract_id;
                 let (state, duration, receipts) =
                     exec_test(&pkg_with_tests.bytecode.bytes, offset, test_setup);
 
@@ -200,12 +225,32 @@ impl<'a> PackageTests {
                         anyhow::anyhow!("missing used gas information from test execution")
                     })?;
 
-                // Only retain `Log` and `LogData` receipts.
+                // Only retain `Log` and `LogData` receipts, counting and removing any coverage
+                // counters logged by the test itself or by the contract it calls.
+                let instrumented = !pkg_with_tests.coverage_points.is_empty();
+                let mut coverage = BTreeMap::new();
+                let mut contract_coverage = BTreeMap::new();
                 let logs = receipts
                     .into_iter()
-                    .filter(|receipt| {
-                        matches!(receipt, fuel_tx::Receipt::Log { .. })
-                            || matches!(receipt, fuel_tx::Receipt::LogData { .. })
+                    .filter(|receipt| match receipt {
+                        fuel_tx::Receipt::Log { id, ra, rb, .. }
+                            if instrumented
+                                && *rb == COVERAGE_LOG_ID
+                                && *id == tx::ContractId::zeroed() =>
+                        {
+                            *coverage.entry(*ra).or_default() += 1;
+                            false
+                        }
+                        fuel_tx::Receipt::Log { id, ra, rb, .. }
+                            if instrumented
+                                && *rb == COVERAGE_LOG_ID
+                                && Some(*id) == contract_id =>
+                        {
+                            *contract_coverage.entry(*ra).or_default() += 1;
+                            false
+                        }
+                        fuel_tx::Receipt::Log { .. } | fuel_tx::Receipt::LogData { .. } => true,
+                        _ => false,
                     })
                     .collect();
 
@@ -219,6 +264,8 @@ impl<'a> PackageTests {
                     condition,
                     logs,
                     gas_used,
+                    coverage,
+                    contract_coverage,
                 })
             })
             .collect::<anyhow::Result<_>>()?;
@@ -257,6 +304,7 @@ impl Opts {
             pkg: self.pkg,
             print: self.print,
             minify: self.minify,
+            emit: Default::default(),
             binary_outfile: self.binary_outfile,
             debug_outfile: self.debug_outfile,
             build_target: self.build_target,
@@ -265,6 +313,10 @@ impl Opts {
             error_on_warnings: self.error_on_warnings,
             time_phases: self.time_phases,
             tests: true,
+            instrument_coverage: self.coverage,
+            disable_incremental: false,
+            unoptimized: self.unoptimized,
+            check_storage_layout: None,
             const_inject_map,
             member_filter: Default::default(),
         }
@@ -468,6 +520,28 @@ fn exec_test(
     vm::state::ProgramState,
     std::time::Duration,
     Vec<fuel_tx::Receipt>,
+) {
+    let (mut interpreter, tx) = test_interpreter(bytecode, test_offset, test_setup);
+
+    // Execute and return the result.
+    let start = std::time::Instant::now();
+    let transition = interpreter.transact(tx).unwrap();
+    let duration = start.elapsed();
+    let state = *transition.state();
+    let receipts = transition.receipts().to_vec();
+
+    (state, duration, receipts)
+}
+
+/// Create an interpreter along with the transaction that executes the test whose entry point is at
+/// the given instruction offset as if it were a script.
+fn test_interpreter(
+    bytecode: &[u8],
+    test_offset: u32,
+    test_setup: TestSetup,
+) -> (
+    vm::interpreter::Interpreter<vm::storage::MemoryStorage, tx::Script>,
+    vm::checked_transaction::Checked<tx::Script>,
 ) {
     let storage = test_setup.storage;
     let contract_id = test_setup.contract_id;
@@ -510,15 +584,7 @@ fn exec_test(
     }
     let tx = tx.finalize_checked(block_height, &params, &GasCosts::default());
 
-    let mut interpreter =
+    let interpreter =
         vm::interpreter::Interpreter::with_storage(storage, params, GasCosts::default());
-
-    // Execute and return the result.
-    let start = std::time::Instant::now();
-    let transition = interpreter.transact(tx).unwrap();
-    let duration = start.elapsed();
-    let state = *transition.state();
-    let receipts = transition.receipts().to_vec();
-
-    (state, duration, receipts)
+    (interpreter, tx)
 }
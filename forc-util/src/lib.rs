@@ -259,6 +259,15 @@ fn format_err(err: &CompileError) {
     let mut end_pos = span.end();
 
     let friendly_str = maybe_uwuify(&format!("{err}"));
+    let secondary_labels = err
+        .secondary_labels()
+        .into_iter()
+        .filter(|(span, _)| span.start() < span.end())
+        .map(|(span, label)| {
+            let path_str = span.path().map(|path| path.to_string_lossy().into_owned());
+            (span, maybe_uwuify(&label), path_str)
+        })
+        .collect::<Vec<_>>();
     let (snippet_title, snippet_slices) = if start_pos < end_pos {
         let title = Some(Annotation {
             label: None,
@@ -268,7 +277,7 @@ fn format_err(err: &CompileError) {
 
         let (mut start, end) = err.span().line_col();
         let input = construct_window(&mut start, end, &mut start_pos, &mut end_pos, input);
-        let slices = vec![Slice {
+        let mut slices = vec![Slice {
             source: input,
             line_start: start.line,
             origin: path_str.as_deref(),
@@ -279,6 +288,24 @@ fn format_err(err: &CompileError) {
                 range: (start_pos, end_pos),
             }],
         }];
+        for (span, label, path_str) in &secondary_labels {
+            let mut start_pos = span.start();
+            let mut end_pos = span.end();
+            let (mut start, end) = span.line_col();
+            let input =
+                construct_window(&mut start, end, &mut start_pos, &mut end_pos, span.input());
+            slices.push(Slice {
+                source: input,
+                line_start: start.line,
+                origin: path_str.as_deref(),
+                fold: false,
+                annotations: vec![SourceAnnotation {
+                    label,
+                    annotation_type: AnnotationType::Note,
+                    range: (start_pos, end_pos),
+                }],
+            });
+        }
 
         (title, slices)
     } else {
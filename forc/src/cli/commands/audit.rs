@@ -0,0 +1,208 @@
+use crate::ops::forc_audit::{self, AuditReport, Severity};
+use ansi_term::Colour;
+use anyhow::{bail, Result};
+use clap::{Parser, ValueEnum};
+use std::{fs, path::PathBuf};
+use sway_core::BuildTarget;
+use tracing::info;
+
+/// Run the security analyses over the current or target project.
+///
+/// Every member of a workspace is audited, along with its dependencies if `--dependencies` is
+/// given. The project is type checked, and compiler diagnostics with security implications are reported
+/// as findings with a severity. This includes storage accesses made after calling another
+/// contract, which may allow reentrancy.
+///
+/// Contracts are also compiled to IR, and a taint analysis reports storage writes whose key may
+/// come from a caller unhashed, letting the caller overwrite any storage slot.
+///
+/// The findings may be written as JSON or SARIF for use by other tools, and `--deny` causes the
+/// command to fail when findings of a given severity are present, for gating CI.
+#[derive(Debug, Default, Parser)]
+pub struct Command {
+    /// Build target to use for code generation.
+    #[clap(value_enum, default_value_t=BuildTarget::default(), alias="target")]
+    pub build_target: BuildTarget,
+    /// Path to the project, if not specified, current working directory will be used.
+    #[clap(short, long)]
+    pub path: Option<String>,
+    /// Offline mode, prevents Forc from using the network when managing dependencies.
+    /// Meaning it will only try to use previously downloaded dependencies.
+    #[clap(long = "offline")]
+    pub offline_mode: bool,
+    /// Requires that the Forc.lock file is up-to-date. If the lock file is missing, or it
+    /// needs to be updated, Forc will exit with an error
+    #[clap(long)]
+    pub locked: bool,
+    /// Also audit the dependencies of the project's packages.
+    #[clap(long)]
+    pub dependencies: bool,
+    /// The format in which to print the findings.
+    #[clap(long, value_enum, default_value_t = Format::Human)]
+    pub format: Format,
+    /// Write the findings to the given file rather than printing them.
+    #[clap(long, short)]
+    pub output: Option<PathBuf>,
+    /// Fail if there are any findings of at least the given severity.
+    #[clap(long, value_enum)]
+    pub deny: Option<Severity>,
+}
+
+/// The formats in which the findings may be written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Findings listed with their severity and location.
+    #[default]
+    Human,
+    /// The findings as a JSON array.
+    Json,
+    /// A Static Analysis Results Interchange Format (SARIF) log.
+    Sarif,
+}
+
+pub(crate) fn exec(command: Command) -> Result<()> {
+    let format = command.format;
+    let output = command.output.clone();
+    let deny = command.deny;
+    let report = forc_audit::audit(command)?;
+
+    let contents = render(&report, format)?;
+    match &output {
+        Some(path) => fs::write(path, contents)?,
+        None if format == Format::Human => info!("{contents}"),
+        // Machine readable output goes to stdout so that it may be piped elsewhere.
+        None => println!("{contents}"),
+    }
+
+    if let Some(severity) = deny {
+        let count = report.count_at_least(severity);
+        if count > 0 {
+            bail!("{count} finding(s) of at least {severity:?} severity");
+        }
+    }
+    Ok(())
+}
+
+/// The report as it's written in the given format.
+fn render(report: &AuditReport, format: Format) -> Result<String> {
+    Ok(match format {
+        Format::Human => human_readable(report),
+        Format::Json => serde_json::to_string_pretty(&report.findings)?,
+        Format::Sarif => serde_json::to_string_pretty(&report.to_sarif())?,
+    })
+}
+
+fn human_readable(report: &AuditReport) -> String {
+    if report.findings.is_empty() {
+        return "No findings.".to_string();
+    }
+    let mut lines = vec![];
+    for finding in &report.findings {
+        let severity = match finding.severity {
+            Severity::High => Colour::Red.bold().paint("high"),
+            Severity::Medium => Colour::Yellow.bold().paint("medium"),
+            Severity::Low => Colour::Blue.bold().paint("low"),
+        };
+        let location = match &finding.path {
+            Some(path) => format!("{}:{}:{}", path.display(), finding.start.0, finding.start.1),
+            None => "<unknown location>".to_string(),
+        };
+        lines.push(format!("{severity} [{}] {}", finding.rule, finding.message));
+        lines.push(format!("  --> {location}"));
+    }
+    lines.push(format!(
+        "\n{} finding(s): {} high, {} medium, {} low",
+        report.findings.len(),
+        report.count_at_least(Severity::High),
+        report.count_at_least(Severity::Medium) - report.count_at_least(Severity::High),
+        report.count_at_least(Severity::Low) - report.count_at_least(Severity::Medium),
+    ));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+fn test_report() -> AuditReport {
+    use crate::ops::forc_audit::{Finding, EFFECT_AFTER_INTERACTION, LOSS_OF_PRECISION};
+    let finding = |rule: &forc_audit::Rule, line| Finding {
+        rule: rule.id,
+        severity: rule.severity,
+        message: rule.description.to_string(),
+        path: Some(PathBuf::from("src/main.sw")),
+        start: (line, 5),
+        end: (line, 12),
+    };
+    AuditReport {
+        findings: vec![
+            finding(&EFFECT_AFTER_INTERACTION, 10),
+            finding(&LOSS_OF_PRECISION, 20),
+        ],
+    }
+}
+
+#[test]
+fn render_human_readable_report() {
+    let rendered = render(&test_report(), Format::Human).unwrap();
+    let lines = rendered.lines().collect::<Vec<_>>();
+    assert!(lines[0].contains("high"));
+    assert!(lines[0].contains("[effect-after-interaction] Storage is accessed after"));
+    assert_eq!(lines[1], "  --> src/main.sw:10:5");
+    assert!(lines[2].contains("[loss-of-precision]"));
+    assert_eq!(lines[3], "  --> src/main.sw:20:5");
+    assert_eq!(
+        lines.last().unwrap(),
+        &"2 finding(s): 1 high, 0 medium, 1 low"
+    );
+
+    let empty = render(&AuditReport::default(), Format::Human).unwrap();
+    assert_eq!(empty, "No findings.");
+}
+
+#[test]
+fn render_json_report() {
+    let rendered = render(&test_report(), Format::Json).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+    assert_eq!(
+        json[0],
+        serde_json::json!({
+            "rule": "effect-after-interaction",
+            "severity": "high",
+            "message": forc_audit::EFFECT_AFTER_INTERACTION.description,
+            "path": "src/main.sw",
+            "start": [10, 5],
+            "end": [10, 12],
+        })
+    );
+    assert_eq!(json[1]["severity"], "low");
+}
+
+#[cfg(test)]
+const EFFECT_AFTER_INTERACTION_DESCRIPTION: &str = forc_audit::EFFECT_AFTER_INTERACTION.description;
+
+#[test]
+fn render_sarif_report() {
+    let rendered = render(&test_report(), Format::Sarif).unwrap();
+    let sarif: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+    assert_eq!(sarif["version"], "2.1.0");
+    let run = &sarif["runs"][0];
+    assert_eq!(
+        run["tool"]["driver"]["rules"].as_array().unwrap().len(),
+        forc_audit::RULES.len()
+    );
+    let results = run["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0]["ruleId"], "effect-after-interaction");
+    assert_eq!(results[0]["level"], "error");
+    assert_eq!(results[1]["level"], "note");
+    let location = &results[0]["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "src/main.sw");
+    assert_eq!(location["region"]["startLine"], 10);
+    assert_eq!(location["region"]["endColumn"], 12);
+}
+
+#[test]
+fn deny_counts_findings_at_or_above_a_severity() {
+    let report = test_report();
+    assert_eq!(report.count_at_least(Severity::High), 1);
+    assert_eq!(report.count_at_least(Severity::Medium), 1);
+    assert_eq!(report.count_at_least(Severity::Low), 2);
+}
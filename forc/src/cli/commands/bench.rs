@@ -0,0 +1,113 @@
+use crate::cli;
+use ansi_term::Colour;
+use anyhow::{bail, Result};
+use clap::Parser;
+use forc_test::{BenchBaseline, BenchedPackage};
+use std::path::PathBuf;
+use tracing::info;
+
+/// Run the Sway benchmarks for the current project.
+///
+/// Benchmarks are functions decorated with the `#[bench]` attribute. They are compiled and
+/// executed in the same way as unit tests, and the gas used by each is reported along with
+/// statistics for the time taken over a number of iterations.
+///
+/// The gas used by each benchmark may be saved to a baseline file with `--save-baseline`, and
+/// later runs compared against it with `--baseline` to catch gas regressions.
+#[derive(Debug, Parser)]
+pub struct Command {
+    #[clap(flatten)]
+    pub build: cli::shared::Build,
+    /// The number of times to execute each benchmark.
+    #[clap(long, default_value = "10")]
+    pub iterations: usize,
+    /// Write the gas used by each benchmark to the given baseline file.
+    #[clap(long)]
+    pub save_baseline: Option<PathBuf>,
+    /// Compare the gas used by each benchmark against the given baseline file.
+    #[clap(long)]
+    pub baseline: Option<PathBuf>,
+    /// Fail if any benchmark uses more gas than recorded in the baseline.
+    #[clap(long, requires = "baseline")]
+    pub fail_on_regression: bool,
+}
+
+pub(crate) fn exec(cmd: Command) -> Result<()> {
+    let baseline = cmd
+        .baseline
+        .as_deref()
+        .map(BenchBaseline::load)
+        .transpose()?;
+
+    let opts = super::test::opts_from_build(cmd.build);
+    let built_tests = forc_test::build(opts)?;
+    info!("   Running {} benchmarks", built_tests.bench_count());
+    let benched = built_tests.run_benches(cmd.iterations)?;
+
+    let (mut reverted, mut regressions) = (0, 0);
+    for pkg in &benched {
+        if benched.len() > 1 {
+            info!("\n   benched -- {}\n", pkg.built.descriptor.name);
+        }
+        let (pkg_reverted, pkg_regressions) = print_benched_pkg(pkg, baseline.as_ref());
+        reverted += pkg_reverted;
+        regressions += pkg_regressions;
+    }
+
+    if let Some(path) = &cmd.save_baseline {
+        BenchBaseline::from_benched(&benched).save(path)?;
+        info!("\n   Baseline saved to {}", path.display());
+    }
+
+    if cmd.fail_on_regression && regressions > 0 {
+        bail!("{regressions} benchmark(s) used more gas than the baseline or started reverting");
+    }
+    if reverted > 0 {
+        bail!("{reverted} benchmark(s) reverted");
+    }
+    Ok(())
+}
+
+/// Print the results for each benchmark in the package, returning the number which reverted and
+/// the number of regressions against the baseline, which include those which started reverting.
+fn print_benched_pkg(pkg: &BenchedPackage, baseline: Option<&BenchBaseline>) -> (usize, usize) {
+    let (mut reverted, mut regressions) = (0, 0);
+    for bench in &pkg.benches {
+        let comparison = baseline.and_then(|baseline| baseline.compare(pkg, bench));
+        if comparison.map_or(false, |comparison| comparison.is_regression()) {
+            regressions += 1;
+        }
+
+        if bench.reverted() {
+            reverted += 1;
+            info!(
+                "      bench {} ... {}",
+                bench.name,
+                Colour::Red.paint("REVERTED")
+            );
+            continue;
+        }
+
+        let delta = match comparison.and_then(|comparison| comparison.percent_change()) {
+            Some(change) if change > 0.0 => {
+                Colour::Red.paint(format!(" ({change:+.2}%)")).to_string()
+            }
+            Some(change) if change < 0.0 => {
+                Colour::Green.paint(format!(" ({change:+.2}%)")).to_string()
+            }
+            Some(_) => " (no change)".to_string(),
+            None => String::new(),
+        };
+        info!(
+            "      bench {} ... {} gas{delta}, time: [min {:?}, median {:?}, mean {:?} ± {:?}, max {:?}]",
+            bench.name,
+            bench.gas_used,
+            bench.min(),
+            bench.median(),
+            bench.mean(),
+            bench.std_dev(),
+            bench.max(),
+        );
+    }
+    (reverted, regressions)
+}
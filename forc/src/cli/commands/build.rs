@@ -16,16 +16,112 @@ use clap::Parser;
 ///
 /// - `contract` and `library` projects will also produce the public ABI in JSON format
 /// `<project-name>-abi.json`.
+///
+/// - All projects will also produce a description of how they were built, including the compiler
+/// version and IR pass pipeline along with hashes of the bytecode and ABI
+/// `<project-name>-build-info.json`.
+///
+/// - `contract` projects will also produce the initial storage slots
+/// `<project-name>-storage_slots.json` and the slots of each storage field
+/// `<project-name>-storage_layout.json`.
+///
+/// - All projects will also produce the bytecode size and estimated gas of each function
+/// `<project-name>-function-costs.json`.
+///
+/// - All projects will also produce the source map from the bytecode to the source
+/// `<project-name>-source-map.json`.
+///
+/// Intermediate compiler artifacts requested with the `--emit*` options are written to the
+/// `emit` directory within the output directory.
+/// `--emit explorer` writes a set of HTML pages to `emit/explorer` showing how each function was
+/// compiled, starting from `index.html`.
 #[derive(Debug, Default, Parser)]
 pub struct Command {
     #[clap(flatten)]
     pub build: cli::shared::Build,
+    #[clap(flatten)]
+    pub emit: cli::shared::Emit,
     /// Also build all tests within the project.
     #[clap(long)]
     pub tests: bool,
+    /// Only build the workspace member with the given name, along with its dependencies.
+    ///
+    /// May be given multiple times to build several members.
+    #[clap(long = "package", value_name = "MEMBER")]
+    pub packages: Vec<String>,
+    /// Check the storage layout of each contract against a previous
+    /// `<project-name>-storage_layout.json`, given either as the file or as the directory
+    /// containing it, e.g. the output directory of the deployed build.
+    ///
+    /// The build fails if a storage field has moved to other slots or changed type, as an upgraded
+    /// contract would then misread the storage it inherits.
+    #[clap(long, value_name = "PATH")]
+    pub check_storage_layout: Option<std::path::PathBuf>,
 }
 
 pub(crate) fn exec(command: Command) -> Result<()> {
     forc_build::build(command)?;
     Ok(())
 }
+
+#[test]
+fn check_storage_layout_against_a_previous_build() {
+    let dir =
+        std::env::temp_dir().join(format!("forc-check-storage-layout-{}", std::process::id()));
+    let previous = dir.join("previous");
+    let write_contract = |fields: &str| {
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(
+            dir.join("Forc.toml"),
+            "[project]\nauthors = [\"Fuel Labs <contact@fuel.sh>\"]\nentry = \"main.sw\"\n\
+             implicit-std = false\nlicense = \"Apache-2.0\"\nname = \"layout\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("src/main.sw"),
+            format!(
+                "contract;\n\nstorage {{\n{fields}}}\n\nabi Layout {{\n    fn noop();\n}}\n\n\
+                 impl Layout for Contract {{\n    fn noop() {{}}\n}}\n"
+            ),
+        )
+        .unwrap();
+    };
+    let build = |args: &[&str]| {
+        let mut command = vec!["forc-build", "--offline", "--path", dir.to_str().unwrap()];
+        command.extend_from_slice(args);
+        forc_build::build(Command::try_parse_from(command).unwrap())
+    };
+
+    write_contract("    a: u64 = 1,\n    b: bool = true,\n");
+    build(&["--output-directory", previous.to_str().unwrap()]).unwrap();
+
+    // Appending a field is compatible, whether given the layout file or its directory.
+    write_contract("    a: u64 = 1,\n    b: bool = true,\n    c: u64 = 3,\n");
+    build(&["--check-storage-layout", previous.to_str().unwrap()]).unwrap();
+    let layout_file = previous.join("layout-storage_layout.json");
+    build(&["--check-storage-layout", layout_file.to_str().unwrap()]).unwrap();
+
+    // Changing the type of a field, or removing one before others, is not.
+    write_contract("    a: u64 = 1,\n    b: u64 = 2,\n");
+    let error = build(&["--check-storage-layout", previous.to_str().unwrap()]).unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("storage field `b` has changed type from `bool` to `u64`"),
+        "{error}"
+    );
+    write_contract("    b: bool = true,\n");
+    let error = build(&["--check-storage-layout", previous.to_str().unwrap()]).unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("storage field `b` has moved to other slots"),
+        "{error}"
+    );
+
+    // A missing layout is an error rather than a pass.
+    let missing = dir.join("missing.json");
+    assert!(build(&["--check-storage-layout", missing.to_str().unwrap()]).is_err());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
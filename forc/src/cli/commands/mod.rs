@@ -1,4 +1,6 @@
 pub mod addr2line;
+pub mod audit;
+pub mod bench;
 pub mod build;
 pub mod check;
 pub mod clean;
@@ -7,6 +9,8 @@ pub mod init;
 pub mod new;
 pub mod parse_bytecode;
 pub mod plugins;
+pub mod size;
 pub mod template;
 pub mod test;
 pub mod update;
+pub mod verify_reproducible;
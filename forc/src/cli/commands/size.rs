@@ -0,0 +1,130 @@
+use crate::{
+    cli,
+    ops::forc_size::{self, PackageSize, SizeChange, SizeReport},
+};
+use ansi_term::Colour;
+use anyhow::Result;
+use clap::Parser;
+use std::path::PathBuf;
+use tracing::info;
+
+/// Report the bytecode size of the current project.
+///
+/// The size of the bytecode is broken down by function, by module and by generic instantiation,
+/// along with the size of the data section.  Code from functions which have been inlined is
+/// counted towards the functions it was inlined from, not those it was inlined into.
+///
+/// A report may be saved with `--save` and a later build compared against it with `--diff`.
+#[derive(Debug, Parser)]
+pub struct Command {
+    #[clap(flatten)]
+    pub build: cli::shared::Build,
+    /// The maximum number of functions to list for each package, largest first.
+    #[clap(long, default_value = "20")]
+    pub top: usize,
+    /// Write the size report to the given file as JSON.
+    #[clap(long)]
+    pub save: Option<PathBuf>,
+    /// Compare the sizes against a report previously written with `--save`.
+    #[clap(long)]
+    pub diff: Option<PathBuf>,
+}
+
+pub(crate) fn exec(cmd: Command) -> Result<()> {
+    let old_report = cmd.diff.as_deref().map(SizeReport::load).transpose()?;
+    let save = cmd.save.clone();
+    let top = cmd.top;
+    let report = forc_size::size(cmd)?;
+
+    for (name, size) in &report.packages {
+        match old_report
+            .as_ref()
+            .and_then(|old_report| old_report.packages.get(name))
+        {
+            Some(old_size) => print_diff(name, size, old_size),
+            None => print_size(name, size, top),
+        }
+    }
+
+    if let Some(path) = &save {
+        report.save(path)?;
+        info!("\n   Size report saved to {}", path.display());
+    }
+    Ok(())
+}
+
+fn print_size(name: &str, size: &PackageSize, top: usize) {
+    info!(
+        "\n  {}: {} bytes ({} bytes of data section)",
+        Colour::Green.bold().paint(name),
+        size.bytecode,
+        size.data_section
+    );
+
+    let mut functions = size.functions.iter().collect::<Vec<_>>();
+    functions.sort_by(|(_, a), (_, b)| b.cmp(a));
+    info!("\n    Functions:");
+    for (function, bytes) in functions.iter().take(top) {
+        info!("      {bytes:>8}  {function}");
+    }
+    if functions.len() > top {
+        info!("      ... and {} more", functions.len() - top);
+    }
+
+    info!("\n    Modules:");
+    for (module, bytes) in &size.modules {
+        info!("      {bytes:>8}  {module}");
+    }
+
+    let mut instantiations = size.generic_instantiations().peekable();
+    if instantiations.peek().is_some() {
+        info!("\n    Generic instantiations:");
+        for (declaration, declaration_size) in instantiations {
+            info!(
+                "      {:>8}  {declaration} ({} instances: {})",
+                declaration_size.size,
+                declaration_size.instances.len(),
+                declaration_size.instances.join(", ")
+            );
+        }
+    }
+}
+
+fn print_diff(name: &str, size: &PackageSize, old_size: &PackageSize) {
+    info!(
+        "\n  {}: {}",
+        Colour::Green.bold().paint(name),
+        change_description(Some(old_size.bytecode), Some(size.bytecode))
+    );
+    info!(
+        "    data section: {}",
+        change_description(Some(old_size.data_section), Some(size.data_section))
+    );
+    print_changes("Modules", &size.diff_modules(old_size));
+    print_changes("Functions", &size.diff_declarations(old_size));
+}
+
+fn print_changes(title: &str, changes: &[SizeChange]) {
+    if changes.is_empty() {
+        return;
+    }
+    info!("\n    {title}:");
+    for change in changes {
+        info!(
+            "      {}  {}",
+            change.name,
+            change_description(change.old, change.new)
+        );
+    }
+}
+
+fn change_description(old: Option<u64>, new: Option<u64>) -> String {
+    let delta = new.unwrap_or(0) as i64 - old.unwrap_or(0) as i64;
+    let delta = match delta {
+        0 => "no change".to_string(),
+        d if d > 0 => Colour::Red.paint(format!("{d:+}")).to_string(),
+        d => Colour::Green.paint(format!("{d:+}")).to_string(),
+    };
+    let bytes = |size: Option<u64>| size.map_or("-".to_string(), |size| size.to_string());
+    format!("{} -> {} bytes ({delta})", bytes(old), bytes(new))
+}
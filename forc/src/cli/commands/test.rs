@@ -3,8 +3,9 @@ use ansi_term::Colour;
 use anyhow::{bail, Result};
 use clap::Parser;
 use forc_pkg as pkg;
-use forc_test::TestedPackage;
-use forc_util::format_log_receipts;
+use forc_test::{CoverageReport, TestedPackage};
+use forc_util::{default_output_directory, format_log_receipts};
+use std::path::{Path, PathBuf};
 use tracing::info;
 
 /// Run the Sway unit tests for the current project.
@@ -24,6 +25,10 @@ use tracing::info;
 /// Upon successful compilation, test scripts are executed to their completion. A test is
 /// considered a failure in the case that a revert (`rvrt`) instruction is encountered during
 /// execution. Otherwise, it is considered a success.
+///
+/// With `--coverage` the tests are instrumented with coverage counters, and line and branch
+/// coverage for each package is written to the `coverage` directory within its output directory,
+/// as both an lcov tracefile (`lcov.info`) and an HTML report (`index.html`).
 #[derive(Debug, Parser)]
 pub struct Command {
     #[clap(flatten)]
@@ -32,6 +37,9 @@ pub struct Command {
     pub test_print: TestPrintOpts,
     /// When specified, only tests containing the given string will be executed.
     pub filter: Option<String>,
+    /// Collect line and branch coverage while running the tests.
+    #[clap(long)]
+    pub coverage: bool,
 }
 
 /// The set of options provided for controlling output of a test.
@@ -51,6 +59,9 @@ pub(crate) fn exec(cmd: Command) -> Result<()> {
     }
 
     let test_print_opts = cmd.test_print.clone();
+    let coverage_output_dir = cmd
+        .coverage
+        .then(|| cmd.build.pkg.output_directory.clone().map(PathBuf::from));
     let opts = opts_from_cmd(cmd);
     let built_tests = forc_test::build(opts)?;
     let start = std::time::Instant::now();
@@ -65,10 +76,18 @@ pub(crate) fn exec(cmd: Command) -> Result<()> {
                 let built = &pkg.built.descriptor.name;
                 info!("\n   tested -- {built}\n");
                 print_tested_pkg(&pkg, &test_print_opts)?;
+                if let Some(output_dir) = &coverage_output_dir {
+                    write_coverage(&pkg, output_dir.as_deref())?;
+                }
             }
             info!("\n   Finished in {:?}", duration);
         }
-        forc_test::Tested::Package(pkg) => print_tested_pkg(&pkg, &test_print_opts)?,
+        forc_test::Tested::Package(pkg) => {
+            print_tested_pkg(&pkg, &test_print_opts)?;
+            if let Some(output_dir) = &coverage_output_dir {
+                write_coverage(&pkg, output_dir.as_deref())?;
+            }
+        }
     };
 
     Ok(())
@@ -152,33 +171,69 @@ fn print_tested_pkg(pkg: &TestedPackage, test_print_opts: &TestPrintOpts) -> Res
     Ok(())
 }
 
+/// Write the lcov and HTML coverage reports for the given package to the `coverage` directory
+/// within its output directory.
+fn write_coverage(pkg: &TestedPackage, output_dir: Option<&Path>) -> Result<()> {
+    let mut report = CoverageReport::default();
+    report.add_package(pkg);
+    if report.is_empty() {
+        info!(
+            "   No coverage was collected for {}",
+            pkg.built.descriptor.name
+        );
+        return Ok(());
+    }
+
+    let coverage_dir = output_dir
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| default_output_directory(pkg.built.descriptor.manifest_file.dir()))
+        .join("coverage");
+    std::fs::create_dir_all(&coverage_dir)?;
+    std::fs::write(coverage_dir.join("lcov.info"), report.to_lcov())?;
+    std::fs::write(coverage_dir.join("index.html"), report.to_html())?;
+    info!("   Coverage written to {}", coverage_dir.display());
+    Ok(())
+}
+
 fn opts_from_cmd(cmd: Command) -> forc_test::Opts {
+    forc_test::Opts {
+        coverage: cmd.coverage,
+        ..opts_from_build(cmd.build)
+    }
+}
+
+/// Produce the `forc_test::Opts` for the given shared build options.
+pub(crate) fn opts_from_build(build: cli::shared::Build) -> forc_test::Opts {
     forc_test::Opts {
         pkg: pkg::PkgOpts {
-            path: cmd.build.pkg.path,
-            offline: cmd.build.pkg.offline,
-            terse: cmd.build.pkg.terse,
-            locked: cmd.build.pkg.locked,
-            output_directory: cmd.build.pkg.output_directory,
-            json_abi_with_callpaths: cmd.build.pkg.json_abi_with_callpaths,
+            path: build.pkg.path,
+            offline: build.pkg.offline,
+            terse: build.pkg.terse,
+            locked: build.pkg.locked,
+            output_directory: build.pkg.output_directory,
+            json_abi_with_callpaths: build.pkg.json_abi_with_callpaths,
         },
         print: pkg::PrintOpts {
-            ast: cmd.build.print.ast,
-            dca_graph: cmd.build.print.dca_graph,
-            finalized_asm: cmd.build.print.finalized_asm,
-            intermediate_asm: cmd.build.print.intermediate_asm,
-            ir: cmd.build.print.ir,
+            ast: build.print.ast,
+            dca_graph: build.print.dca_graph,
+            finalized_asm: build.print.finalized_asm,
+            intermediate_asm: build.print.intermediate_asm,
+            ir: build.print.ir,
+            ir_memory: build.print.ir_memory,
+            pass_statistics: build.print.pass_statistics,
         },
-        time_phases: cmd.build.print.time_phases,
+        time_phases: build.print.time_phases,
         minify: pkg::MinifyOpts {
-            json_abi: cmd.build.minify.json_abi,
-            json_storage_slots: cmd.build.minify.json_storage_slots,
+            json_abi: build.minify.json_abi,
+            json_storage_slots: build.minify.json_storage_slots,
         },
-        build_profile: cmd.build.profile.build_profile,
-        release: cmd.build.profile.release,
-        error_on_warnings: cmd.build.profile.error_on_warnings,
-        binary_outfile: cmd.build.output.bin_file,
-        debug_outfile: cmd.build.output.debug_file,
-        build_target: cmd.build.build_target,
+        build_profile: build.profile.build_profile,
+        release: build.profile.release,
+        error_on_warnings: build.profile.error_on_warnings,
+        binary_outfile: build.output.bin_file,
+        debug_outfile: build.output.debug_file,
+        build_target: build.build_target,
+        coverage: false,
+        unoptimized: false,
     }
 }
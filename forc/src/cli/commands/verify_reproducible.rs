@@ -0,0 +1,62 @@
+use crate::{cli, ops::forc_verify_reproducible};
+use ansi_term::Colour;
+use anyhow::{bail, Result};
+use clap::Parser;
+use std::path::PathBuf;
+use tracing::info;
+
+/// Check that the current or target project builds reproducibly.
+///
+/// The project is compiled twice from scratch, using exactly the dependencies in the lock file and
+/// without reusing any cached compiler output, and the bytecode, ABI and other artifacts of the two
+/// builds are compared.
+///
+/// The two builds run at once, each in a process of its own, so that they share no compiler
+/// state.
+///
+/// The output of each build is kept in the `reproducible` directory within the output directory.
+/// This includes `<project-name>-build-info.json`, which records the compiler version, IR pass
+/// pipeline, build profile and dependencies along with a fingerprint of them all, so that the
+/// build can be reproduced elsewhere, for example when verifying deployed bytecode.
+#[derive(Debug, Parser)]
+pub struct Command {
+    #[clap(flatten)]
+    pub build: cli::shared::Build,
+    /// Make just one of the compared builds, into the given directory.
+    #[clap(long, hide = true)]
+    pub build_into: Option<PathBuf>,
+}
+
+pub(crate) fn exec(cmd: Command) -> Result<()> {
+    if let Some(output_dir) = &cmd.build_into {
+        return forc_verify_reproducible::build_into(cmd.build, output_dir);
+    }
+    let report = forc_verify_reproducible::verify_reproducible(cmd)?;
+
+    for (name, build_info) in &report.build_infos {
+        info!("\n  {}", Colour::Green.bold().paint(name));
+        info!("    Compiler version: {}", build_info.compiler_version);
+        info!("    Fingerprint: {}", build_info.fingerprint);
+        info!("    Bytecode hash: {}", build_info.bytecode_hash);
+        if let Some(abi_hash) = &build_info.abi_hash {
+            info!("    ABI hash: {abi_hash}");
+        }
+    }
+
+    if !report.is_reproducible() {
+        info!("\n  The following output differs between builds:");
+        for path in &report.mismatches {
+            info!("    {}", path.display());
+        }
+        bail!(
+            "the build is not reproducible, compare the output in {} and {}",
+            report.output_dirs[0].display(),
+            report.output_dirs[1].display()
+        );
+    }
+    info!(
+        "\n  {}",
+        Colour::Green.bold().paint("The build is reproducible")
+    );
+    Ok(())
+}
@@ -1,11 +1,13 @@
 use std::str::FromStr;
 
 use self::commands::{
-    addr2line, build, check, clean, completions, init, new, parse_bytecode, plugins, template,
-    test, update,
+    addr2line, audit, bench, build, check, clean, completions, init, new, parse_bytecode, plugins,
+    size, template, test, update, verify_reproducible,
 };
 use addr2line::Command as Addr2LineCommand;
 use anyhow::{anyhow, Result};
+pub use audit::Command as AuditCommand;
+pub use bench::Command as BenchCommand;
 pub use build::Command as BuildCommand;
 pub use check::Command as CheckCommand;
 use clap::{Parser, Subcommand};
@@ -16,10 +18,12 @@ pub use init::Command as InitCommand;
 pub use new::Command as NewCommand;
 use parse_bytecode::Command as ParseBytecodeCommand;
 pub use plugins::Command as PluginsCommand;
+pub use size::Command as SizeCommand;
 pub use template::Command as TemplateCommand;
 pub use test::Command as TestCommand;
 use tracing::metadata::LevelFilter;
 pub use update::Command as UpdateCommand;
+pub use verify_reproducible::Command as VerifyReproducibleCommand;
 
 mod commands;
 mod plugin;
@@ -49,6 +53,8 @@ struct Opt {
 enum Forc {
     #[clap(name = "addr2line")]
     Addr2Line(Addr2LineCommand),
+    Audit(AuditCommand),
+    Bench(BenchCommand),
     #[clap(visible_alias = "b")]
     Build(BuildCommand),
     Check(CheckCommand),
@@ -57,9 +63,11 @@ enum Forc {
     New(NewCommand),
     Init(InitCommand),
     ParseBytecode(ParseBytecodeCommand),
+    Size(SizeCommand),
     #[clap(visible_alias = "t")]
     Test(TestCommand),
     Update(UpdateCommand),
+    VerifyReproducible(VerifyReproducibleCommand),
     Plugins(PluginsCommand),
     Template(TemplateCommand),
     /// This is a catch-all for unknown subcommands and their arguments.
@@ -87,6 +95,8 @@ pub async fn run_cli() -> Result<()> {
 
     match opt.command {
         Forc::Addr2Line(command) => addr2line::exec(command),
+        Forc::Audit(command) => audit::exec(command),
+        Forc::Bench(command) => bench::exec(command),
         Forc::Build(command) => build::exec(command),
         Forc::Check(command) => check::exec(command),
         Forc::Clean(command) => clean::exec(command),
@@ -95,8 +105,10 @@ pub async fn run_cli() -> Result<()> {
         Forc::New(command) => new::exec(command),
         Forc::ParseBytecode(command) => parse_bytecode::exec(command),
         Forc::Plugins(command) => plugins::exec(command),
+        Forc::Size(command) => size::exec(command),
         Forc::Test(command) => test::exec(command),
         Forc::Update(command) => update::exec(command).await,
+        Forc::VerifyReproducible(command) => verify_reproducible::exec(command),
         Forc::Template(command) => template::exec(command),
         Forc::Plugin(args) => {
             let output = plugin::execute_external_subcommand(args)?;
@@ -1,6 +1,6 @@
 //! Sets of arguments that are shared between commands.
 
-use clap::{Args, Parser};
+use clap::{Args, Parser, ValueEnum};
 use sway_core::BuildTarget;
 
 /// Args that can be shared between all commands that `build` a package. E.g. `build`, `test`,
@@ -74,6 +74,13 @@ pub struct Print {
     /// Print the generated Sway IR (Intermediate Representation).
     #[clap(long)]
     pub ir: bool,
+    /// Print the memory used by the IR, broken down by the kind of IR entity, before and after
+    /// optimization along with the total after each optimization pass.
+    #[clap(long)]
+    pub ir_memory: bool,
+    /// Print the time taken by each optimization pass, how often it ran and what it changed.
+    #[clap(long)]
+    pub pass_statistics: bool,
     /// Output the time elapsed over each part of the compilation process.
     #[clap(long)]
     pub time_phases: bool,
@@ -118,3 +125,35 @@ pub struct Minify {
     #[clap(long)]
     pub json_storage_slots: bool,
 }
+
+/// Options related to emitting intermediate compiler artifacts.
+#[derive(Args, Debug, Default)]
+pub struct Emit {
+    /// Comma separated list of intermediate artifacts to emit.
+    #[clap(long, value_enum, value_delimiter = ',')]
+    pub emit: Vec<EmitKind>,
+    /// Emit the IR as it is immediately before each run of the named pass.
+    ///
+    /// May be given multiple times, or as a comma separated list.
+    #[clap(long, value_delimiter = ',')]
+    pub emit_before_pass: Vec<String>,
+    /// Emit the IR as it is immediately after each run of the named pass.
+    ///
+    /// May be given multiple times, or as a comma separated list.
+    #[clap(long, value_delimiter = ',')]
+    pub emit_after_pass: Vec<String>,
+}
+
+/// The kinds of intermediate artifacts which may be passed to `--emit`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum EmitKind {
+    /// The Sway IR once all optimisation passes have run.
+    Ir,
+    /// The finalized ASM.
+    Asm,
+    /// A listing of the final bytecode.
+    Bytecode,
+    /// HTML pages showing the control flow graph, dominator tree and IR after every pass of each
+    /// function, linked to the source and the ASM.
+    Explorer,
+}
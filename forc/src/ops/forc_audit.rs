@@ -0,0 +1,306 @@
+use crate::cli::AuditCommand;
+use anyhow::{anyhow, bail, Result};
+use clap::ValueEnum;
+use forc_pkg::{self as pkg, manifest::ManifestFile};
+use serde::Serialize;
+use serde_json::json;
+use std::{collections::HashSet, path::PathBuf, sync::Arc};
+use sway_core::{
+    decl_engine::DeclEngine, ir_generation::compile_program, language::ty, CompileResult, Engines,
+    TypeEngine,
+};
+use sway_error::warning::{CompileWarning, Warning};
+use sway_ir::{compute_module_taint, Context, Value};
+use sway_types::Span;
+
+/// How serious a finding is, from the point of view of a security review.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+/// A kind of security issue detected by `forc audit`.
+pub struct Rule {
+    pub id: &'static str,
+    pub severity: Severity,
+    pub description: &'static str,
+}
+
+/// Storage written or read after calling another contract, which may allow reentrancy.
+pub const EFFECT_AFTER_INTERACTION: Rule = Rule {
+    id: "effect-after-interaction",
+    severity: Severity::High,
+    description: "Storage is accessed after an external contract call, which may allow \
+        reentrancy. Effects should precede interactions (checks-effects-interactions).",
+};
+
+/// Storage written at a key taken from the caller of a contract method, found by the IR taint
+/// analysis.
+pub const CALLER_CONTROLLED_STORAGE_KEY: Rule = Rule {
+    id: "caller-controlled-storage-key",
+    severity: Severity::High,
+    description: "Storage is written at a key supplied by the caller of a contract method, which \
+        lets the caller overwrite any storage slot.",
+};
+
+/// An `asm` block variable shadowing a reserved register.
+pub const SHADOWING_RESERVED_REGISTER: Rule = Rule {
+    id: "shadowing-reserved-register",
+    severity: Severity::Medium,
+    description: "An `asm` block variable shadows a reserved VM register.",
+};
+
+/// A value discarded without being checked.
+pub const UNUSED_RETURN_VALUE: Rule = Rule {
+    id: "unused-return-value",
+    severity: Severity::Low,
+    description: "The return value of a call is discarded without being checked.",
+};
+
+/// An integer conversion which may truncate.
+pub const LOSS_OF_PRECISION: Rule = Rule {
+    id: "loss-of-precision",
+    severity: Severity::Low,
+    description: "An integer conversion may silently lose precision.",
+};
+
+pub const RULES: &[Rule] = &[
+    EFFECT_AFTER_INTERACTION,
+    CALLER_CONTROLLED_STORAGE_KEY,
+    SHADOWING_RESERVED_REGISTER,
+    UNUSED_RETURN_VALUE,
+    LOSS_OF_PRECISION,
+];
+
+/// A single issue found in the audited package.
+#[derive(Debug, Serialize)]
+pub struct Finding {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    pub path: Option<PathBuf>,
+    /// The 1-based start and end line and column of the offending code.
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+}
+
+/// The findings for the audited packages, ordered by source location.
+#[derive(Debug, Default, Serialize)]
+pub struct AuditReport {
+    pub findings: Vec<Finding>,
+}
+
+pub fn audit(command: AuditCommand) -> Result<AuditReport> {
+    let AuditCommand {
+        build_target,
+        path,
+        offline_mode: offline,
+        locked,
+        dependencies,
+        ..
+    } = command;
+
+    let this_dir = if let Some(ref path) = path {
+        PathBuf::from(path)
+    } else {
+        std::env::current_dir()?
+    };
+    let manifest_file = ManifestFile::from_dir(&this_dir)?;
+    let member_manifests = manifest_file.member_manifests()?;
+    let lock_path = manifest_file.lock_path()?;
+    let plan =
+        pkg::BuildPlan::from_lock_and_manifests(&lock_path, &member_manifests, locked, offline)?;
+
+    let type_engine = TypeEngine::default();
+    let decl_engine = DeclEngine::default();
+    let engines = Engines::new(&type_engine, &decl_engine);
+    let terse_mode = true;
+    let tests_enabled = false;
+    let results = pkg::check(&plan, build_target, terse_mode, tests_enabled, engines)?;
+
+    // The results are in compilation order, which includes the dependencies of the members.
+    let members = plan.member_nodes().collect::<HashSet<_>>();
+    let mut findings = vec![];
+    for (node, res) in plan.compilation_order().iter().zip(&results) {
+        let name = &plan.graph()[*node].name;
+        if !res.is_ok() {
+            bail!("unable to type check `{name}`");
+        }
+        if dependencies || members.contains(node) {
+            findings.extend(audit_package(res, engines)?);
+        }
+    }
+    findings.sort_by(|a, b| (&a.path, a.start).cmp(&(&b.path, b.start)));
+    Ok(AuditReport { findings })
+}
+
+/// The findings for a single checked package.
+fn audit_package(res: &CompileResult<pkg::Programs>, engines: Engines) -> Result<Vec<Finding>> {
+    let mut findings = res
+        .warnings
+        .iter()
+        .filter_map(Finding::from_warning)
+        .collect::<Vec<_>>();
+
+    // The taint analysis runs over the IR of contracts, as they're what callers may pass data to.
+    let typed = res
+        .value
+        .as_ref()
+        .and_then(|programs| programs.typed.as_ref());
+    if let Some(typed) =
+        typed.filter(|typed| matches!(typed.kind, ty::TyProgramKind::Contract { .. }))
+    {
+        let ir = compile_program(typed, false, engines)
+            .map_err(|err| anyhow!("unable to generate IR: {err}"))?;
+        for module in ir.module_iter() {
+            let taint = compute_module_taint(&ir, module);
+            findings.extend(
+                taint
+                    .tainted_storage_writes
+                    .iter()
+                    .map(|write| Finding::from_ir(&ir, write, &CALLER_CONTROLLED_STORAGE_KEY)),
+            );
+        }
+    }
+    Ok(findings)
+}
+
+impl Finding {
+    /// The finding for the given compiler warning, if it is security relevant.
+    fn from_warning(warning: &CompileWarning) -> Option<Self> {
+        let rule = match warning.warning_content {
+            Warning::EffectAfterInteraction { .. } => &EFFECT_AFTER_INTERACTION,
+            Warning::ShadowingReservedRegister { .. } => &SHADOWING_RESERVED_REGISTER,
+            Warning::UnusedReturnValue { .. } => &UNUSED_RETURN_VALUE,
+            Warning::LossOfPrecision { .. } => &LOSS_OF_PRECISION,
+            _ => return None,
+        };
+        let (start, end) = warning.span.line_col();
+        Some(Finding {
+            rule: rule.id,
+            severity: rule.severity,
+            message: warning.to_friendly_warning_string(),
+            path: warning.path().map(|path| path.as_ref().clone()),
+            start: (start.line, start.col),
+            end: (end.line, end.col),
+        })
+    }
+
+    /// The finding for the given IR instruction, located by its span if it has one.
+    fn from_ir(ir: &Context, value: &Value, rule: &Rule) -> Self {
+        let span = ir_span(ir, value);
+        let (start, end) = span
+            .as_ref()
+            .map(|span| {
+                let (start, end) = span.line_col();
+                ((start.line, start.col), (end.line, end.col))
+            })
+            .unwrap_or_default();
+        Finding {
+            rule: rule.id,
+            severity: rule.severity,
+            message: rule.description.to_string(),
+            path: span.and_then(|span| span.path().map(|path| path.as_ref().clone())),
+            start,
+            end,
+        }
+    }
+}
+
+/// The source span recorded in the metadata of an IR value, as `sway-core` writes it.
+fn ir_span(ir: &Context, value: &Value) -> Option<Span> {
+    let md_idx = value.get_metadata(ir)?;
+    let md_idcs = match md_idx.get_content(ir).unwrap_list() {
+        Some(md_idcs) => md_idcs.to_vec(),
+        None => vec![md_idx],
+    };
+    md_idcs.iter().find_map(|md_idx| {
+        let fields = md_idx.get_content(ir).unwrap_struct("span", 3)?;
+        let path = PathBuf::from(fields[0].unwrap_index()?.get_content(ir).unwrap_string()?);
+        let start = fields[1].unwrap_integer()?;
+        let end = fields[2].unwrap_integer()?;
+        let src = std::fs::read_to_string(&path).ok()?;
+        Span::new(
+            Arc::from(src),
+            start as usize,
+            end as usize,
+            Some(Arc::new(path)),
+        )
+    })
+}
+
+impl AuditReport {
+    /// The number of findings at or above the given severity.
+    pub fn count_at_least(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|finding| finding.severity >= severity)
+            .count()
+    }
+
+    /// The report in the Static Analysis Results Interchange Format, version 2.1.0.
+    pub fn to_sarif(&self) -> serde_json::Value {
+        let rules = RULES
+            .iter()
+            .map(|rule| {
+                json!({
+                    "id": rule.id,
+                    "shortDescription": { "text": rule.description },
+                    "defaultConfiguration": { "level": sarif_level(rule.severity) },
+                })
+            })
+            .collect::<Vec<_>>();
+        let results = self
+            .findings
+            .iter()
+            .map(|finding| {
+                let uri = finding
+                    .path
+                    .as_ref()
+                    .map(|path| path.display().to_string())
+                    .unwrap_or_default();
+                json!({
+                    "ruleId": finding.rule,
+                    "level": sarif_level(finding.severity),
+                    "message": { "text": finding.message },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": { "uri": uri },
+                            "region": {
+                                "startLine": finding.start.0,
+                                "startColumn": finding.start.1,
+                                "endLine": finding.end.0,
+                                "endColumn": finding.end.1,
+                            },
+                        },
+                    }],
+                })
+            })
+            .collect::<Vec<_>>();
+        json!({
+            "version": "2.1.0",
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "forc-audit",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "rules": rules,
+                    },
+                },
+                "results": results,
+            }],
+        })
+    }
+}
+
+fn sarif_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::High => "error",
+        Severity::Medium => "warning",
+        Severity::Low => "note",
+    }
+}
@@ -1,4 +1,4 @@
-use crate::cli::BuildCommand;
+use crate::cli::{shared::EmitKind, BuildCommand};
 use anyhow::Result;
 use forc_pkg as pkg;
 
@@ -8,7 +8,7 @@ pub fn build(cmd: BuildCommand) -> Result<pkg::Built> {
     Ok(built)
 }
 
-fn opts_from_cmd(cmd: BuildCommand) -> pkg::BuildOpts {
+pub(crate) fn opts_from_cmd(cmd: BuildCommand) -> pkg::BuildOpts {
     let const_inject_map = std::collections::HashMap::new();
     pkg::BuildOpts {
         pkg: pkg::PkgOpts {
@@ -25,6 +25,16 @@ fn opts_from_cmd(cmd: BuildCommand) -> pkg::BuildOpts {
             finalized_asm: cmd.build.print.finalized_asm,
             intermediate_asm: cmd.build.print.intermediate_asm,
             ir: cmd.build.print.ir,
+            ir_memory: cmd.build.print.ir_memory,
+            pass_statistics: cmd.build.print.pass_statistics,
+        },
+        emit: pkg::EmitOpts {
+            ir: cmd.emit.emit.contains(&EmitKind::Ir),
+            asm: cmd.emit.emit.contains(&EmitKind::Asm),
+            bytecode: cmd.emit.emit.contains(&EmitKind::Bytecode),
+            explorer: cmd.emit.emit.contains(&EmitKind::Explorer),
+            before_passes: cmd.emit.emit_before_pass,
+            after_passes: cmd.emit.emit_after_pass,
         },
         time_phases: cmd.build.print.time_phases,
         minify: pkg::MinifyOpts {
@@ -38,7 +48,14 @@ fn opts_from_cmd(cmd: BuildCommand) -> pkg::BuildOpts {
         debug_outfile: cmd.build.output.debug_file,
         build_target: cmd.build.build_target,
         tests: cmd.tests,
+        instrument_coverage: false,
+        disable_incremental: false,
+        unoptimized: false,
+        check_storage_layout: cmd.check_storage_layout,
         const_inject_map,
-        member_filter: Default::default(),
+        member_filter: pkg::MemberFilter {
+            members: cmd.packages,
+            ..Default::default()
+        },
     }
 }
@@ -0,0 +1,365 @@
+use crate::cli::{BuildCommand, SizeCommand};
+use anyhow::Result;
+use forc_pkg as pkg;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path};
+use sway_core::FunctionSize;
+use sway_types::Span;
+
+/// The name used for the module of functions with no source span, such as the compiler generated
+/// contract method dispatcher.
+const UNKNOWN_MODULE: &str = "<unknown>";
+
+/// The bytecode size of each package in a build, broken down by function and module.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SizeReport {
+    pub packages: BTreeMap<String, PackageSize>,
+}
+
+/// The bytecode size of a single package.
+///
+/// The code inlined into a function is counted towards the function it was inlined from, rather
+/// than its caller, so functions which were always inlined are listed too.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PackageSize {
+    /// The total size of the bytecode, including the data section.
+    pub bytecode: u64,
+    /// The size of the data section.
+    pub data_section: u64,
+    /// The size of each function, keyed by its name in the IR.
+    pub functions: BTreeMap<String, u64>,
+    /// The size of each function declaration, keyed by `<module>:<line>`.  Generic functions
+    /// combine the sizes of all of their instantiations.
+    pub declarations: BTreeMap<String, DeclarationSize>,
+    /// The size of each module, keyed by its path relative to the package.
+    pub modules: BTreeMap<String, u64>,
+}
+
+/// The size of all of the functions generated for a single declaration.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DeclarationSize {
+    /// The IR names of the functions, of which there are several for a generic function.
+    pub instances: Vec<String>,
+    pub size: u64,
+}
+
+/// The size of an item in a previous report compared to the current one.  Either size is `None` if
+/// the item isn't in that report.
+#[derive(Debug)]
+pub struct SizeChange {
+    pub name: String,
+    pub old: Option<u64>,
+    pub new: Option<u64>,
+}
+
+pub fn size(cmd: SizeCommand) -> Result<SizeReport> {
+    let build_cmd = BuildCommand {
+        build: cmd.build,
+        ..Default::default()
+    };
+    let built = super::forc_build::build(build_cmd)?;
+    let pkgs = match &built {
+        pkg::Built::Package(built_pkg) => vec![built_pkg.as_ref()],
+        pkg::Built::Workspace(built_workspace) => built_workspace.values().collect(),
+    };
+    let packages = pkgs
+        .into_iter()
+        .map(|built_pkg| {
+            (
+                built_pkg.descriptor.name.clone(),
+                PackageSize::from_built(built_pkg),
+            )
+        })
+        .collect();
+    Ok(SizeReport { packages })
+}
+
+impl SizeReport {
+    /// Load a report previously written with `save`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("failed to read size report {}: {e}", path.display()))?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Write this report to `path` as JSON.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+impl PackageSize {
+    fn from_built(built_pkg: &pkg::BuiltPackage) -> Self {
+        Self::from_function_sizes(
+            built_pkg.bytecode.bytes.len() as u64,
+            built_pkg.data_section_size,
+            built_pkg.descriptor.manifest_file.dir(),
+            &built_pkg.function_sizes,
+        )
+    }
+
+    fn from_function_sizes(
+        bytecode: u64,
+        data_section: u64,
+        pkg_dir: &Path,
+        function_sizes: &[FunctionSize],
+    ) -> Self {
+        let mut size = PackageSize {
+            bytecode,
+            data_section,
+            ..Default::default()
+        };
+        for function in function_sizes {
+            let inlined_size = function
+                .inlined
+                .iter()
+                .map(|inlined| inlined.size_in_bytes)
+                .sum::<u64>();
+            size.add_function(
+                pkg_dir,
+                &function.name,
+                function.span.as_ref(),
+                function.size_in_bytes.saturating_sub(inlined_size),
+            );
+            for inlined in &function.inlined {
+                size.add_function(
+                    pkg_dir,
+                    &inlined.name,
+                    inlined.span.as_ref(),
+                    inlined.size_in_bytes,
+                );
+            }
+        }
+        size
+    }
+
+    /// Count `bytes` of code towards the function `name`, its declaration and its module.
+    fn add_function(&mut self, pkg_dir: &Path, name: &str, span: Option<&Span>, bytes: u64) {
+        let (module, declaration) = match span {
+            Some(span) => {
+                let module = match span.path() {
+                    Some(path) => path
+                        .strip_prefix(pkg_dir)
+                        .unwrap_or(path.as_path())
+                        .display()
+                        .to_string(),
+                    None => UNKNOWN_MODULE.to_string(),
+                };
+                let line = span.start_pos().line_col().0;
+                let declaration = format!("{module}:{line}");
+                (module, declaration)
+            }
+            None => (UNKNOWN_MODULE.to_string(), name.to_string()),
+        };
+        *self.functions.entry(name.to_string()).or_default() += bytes;
+        *self.modules.entry(module).or_default() += bytes;
+        let declaration = self.declarations.entry(declaration).or_default();
+        if !declaration
+            .instances
+            .iter()
+            .any(|instance| instance == name)
+        {
+            declaration.instances.push(name.to_string());
+        }
+        declaration.size += bytes;
+    }
+
+    /// The declarations with more than one generic instantiation.
+    pub fn generic_instantiations(&self) -> impl Iterator<Item = (&String, &DeclarationSize)> {
+        self.declarations
+            .iter()
+            .filter(|(_, declaration)| declaration.instances.len() > 1)
+    }
+
+    /// The modules whose size differs from those in `old`.
+    pub fn diff_modules(&self, old: &PackageSize) -> Vec<SizeChange> {
+        diff_sizes(&old.modules, &self.modules)
+    }
+
+    /// The function declarations whose size differs from those in `old`.
+    pub fn diff_declarations(&self, old: &PackageSize) -> Vec<SizeChange> {
+        let sizes = |size: &PackageSize| {
+            size.declarations
+                .iter()
+                .map(|(name, declaration)| (name.clone(), declaration.size))
+                .collect::<BTreeMap<_, _>>()
+        };
+        diff_sizes(&sizes(old), &sizes(self))
+    }
+}
+
+fn diff_sizes(old: &BTreeMap<String, u64>, new: &BTreeMap<String, u64>) -> Vec<SizeChange> {
+    let mut names = old.keys().chain(new.keys()).collect::<Vec<_>>();
+    names.sort();
+    names.dedup();
+    names
+        .into_iter()
+        .map(|name| SizeChange {
+            name: name.clone(),
+            old: old.get(name).copied(),
+            new: new.get(name).copied(),
+        })
+        .filter(|change| change.old != change.new)
+        .collect()
+}
+
+#[cfg(test)]
+const TEST_MAIN_SRC: &str = "fn main() {}\nfn helper() {}\nfn other() {}\n";
+
+#[cfg(test)]
+const TEST_LIB_SRC: &str = "fn id<T>() {}\n";
+
+#[cfg(test)]
+fn test_span(path: &str, src: &str, line: usize) -> Span {
+    let start = src.lines().take(line - 1).map(|line| line.len() + 1).sum();
+    let path = std::sync::Arc::new(Path::new("/pkg").join(path));
+    Span::new(std::sync::Arc::from(src), start, start + 2, Some(path)).unwrap()
+}
+
+#[cfg(test)]
+fn test_function(name: &str, span: Option<Span>, size_in_bytes: u64) -> FunctionSize {
+    FunctionSize {
+        name: name.to_string(),
+        span,
+        size_in_bytes,
+        estimated_gas: 0,
+        inlined: vec![],
+    }
+}
+
+#[cfg(test)]
+fn test_package_size(helper_size: u64, id_size: u64) -> PackageSize {
+    let helper_span = test_span("src/main.sw", TEST_MAIN_SRC, 2);
+    let inlined_helper = sway_core::InlinedSize {
+        name: "helper".to_string(),
+        span: Some(helper_span),
+        size_in_bytes: helper_size,
+    };
+    let main = FunctionSize {
+        inlined: vec![inlined_helper.clone()],
+        ..test_function(
+            "main",
+            Some(test_span("src/main.sw", TEST_MAIN_SRC, 1)),
+            40 + helper_size,
+        )
+    };
+    let other = FunctionSize {
+        inlined: vec![inlined_helper],
+        ..test_function(
+            "other",
+            Some(test_span("src/main.sw", TEST_MAIN_SRC, 3)),
+            8 + helper_size,
+        )
+    };
+    let id_span = test_span("src/lib.sw", TEST_LIB_SRC, 1);
+    PackageSize::from_function_sizes(
+        200,
+        24,
+        Path::new("/pkg"),
+        &[
+            main,
+            other,
+            test_function("id_0", Some(id_span.clone()), id_size),
+            test_function("id_1", Some(id_span), id_size),
+            test_function("__entry", None, 16),
+        ],
+    )
+}
+
+#[test]
+fn attribute_sizes_to_modules_and_declarations() {
+    let size = test_package_size(12, 8);
+    assert_eq!((size.bytecode, size.data_section), (200, 24));
+    assert_eq!(
+        size.functions.into_iter().collect::<Vec<_>>(),
+        vec![
+            ("__entry".to_string(), 16),
+            ("helper".to_string(), 24),
+            ("id_0".to_string(), 8),
+            ("id_1".to_string(), 8),
+            ("main".to_string(), 40),
+            ("other".to_string(), 8),
+        ]
+    );
+    assert_eq!(
+        size.modules.into_iter().collect::<Vec<_>>(),
+        vec![
+            (UNKNOWN_MODULE.to_string(), 16),
+            ("src/lib.sw".to_string(), 16),
+            ("src/main.sw".to_string(), 72),
+        ]
+    );
+    let declarations = size
+        .declarations
+        .iter()
+        .map(|(name, declaration)| {
+            (
+                name.as_str(),
+                declaration.instances.clone(),
+                declaration.size,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        declarations,
+        vec![
+            ("__entry", vec!["__entry".to_string()], 16),
+            (
+                "src/lib.sw:1",
+                vec!["id_0".to_string(), "id_1".to_string()],
+                16
+            ),
+            ("src/main.sw:1", vec!["main".to_string()], 40),
+            ("src/main.sw:2", vec!["helper".to_string()], 24),
+            ("src/main.sw:3", vec!["other".to_string()], 8),
+        ]
+    );
+}
+
+#[test]
+fn inlined_code_is_not_a_generic_instantiation() {
+    let size = test_package_size(12, 8);
+    let generic = size
+        .generic_instantiations()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(generic, vec!["src/lib.sw:1"]);
+}
+
+#[test]
+fn diff_modules_and_declarations() {
+    let old = test_package_size(12, 8);
+    let new = test_package_size(20, 8);
+    let changes = |changes: Vec<SizeChange>| {
+        changes
+            .into_iter()
+            .map(|change| (change.name, change.old, change.new))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        changes(new.diff_modules(&old)),
+        vec![("src/main.sw".to_string(), Some(72), Some(88))]
+    );
+    assert_eq!(
+        changes(new.diff_declarations(&old)),
+        vec![("src/main.sw:2".to_string(), Some(24), Some(40))]
+    );
+    assert!(new.diff_modules(&new).is_empty());
+
+    // Items only in one of the reports have no size in the other.
+    let mut removed = test_package_size(12, 8);
+    removed.modules.remove("src/lib.sw");
+    removed.declarations.remove("src/lib.sw:1");
+    assert_eq!(
+        changes(removed.diff_modules(&old)),
+        vec![("src/lib.sw".to_string(), Some(16), None)]
+    );
+    assert_eq!(
+        changes(old.diff_declarations(&removed)),
+        vec![("src/lib.sw:1".to_string(), None, Some(16))]
+    );
+}
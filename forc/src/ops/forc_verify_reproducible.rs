@@ -0,0 +1,139 @@
+use crate::{
+    cli::{self, BuildCommand, VerifyReproducibleCommand},
+    ops::forc_build,
+};
+use anyhow::{bail, Result};
+use forc_pkg::{self as pkg, manifest::ManifestFile, ArtifactPaths};
+use forc_util::default_output_directory;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+use sway_core::fuel_prelude::fuel_crypto;
+
+/// The name of the directory within the output directory in which the compared builds are placed.
+const VERIFY_DIR_NAME: &str = "reproducible";
+
+/// The outcome of building a project twice and comparing the output.
+pub struct ReproducibilityReport {
+    /// The directories holding the output of each of the builds.
+    pub output_dirs: [PathBuf; 2],
+    /// How each package was built, keyed by package name.
+    pub build_infos: BTreeMap<String, pkg::BuildInfo>,
+    /// The files which differ between the builds, or were only written by one of them, relative
+    /// to the output directories.
+    pub mismatches: Vec<PathBuf>,
+}
+
+impl ReproducibilityReport {
+    pub fn is_reproducible(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// The options of each of the compared builds.  They must use exactly the dependencies in the
+/// lock file, and must not reuse any cached compiler output.
+fn build_opts(build: cli::shared::Build) -> pkg::BuildOpts {
+    let mut opts = forc_build::opts_from_cmd(BuildCommand {
+        build,
+        ..Default::default()
+    });
+    opts.pkg.locked = true;
+    opts.disable_incremental = true;
+    opts
+}
+
+/// Make one of the builds compared by [verify_reproducible], into `output_dir`.
+pub fn build_into(build: cli::shared::Build, output_dir: &Path) -> Result<()> {
+    let mut opts = build_opts(build);
+    opts.pkg.output_directory = Some(output_dir.display().to_string());
+    pkg::build_with_options(opts)?;
+    Ok(())
+}
+
+pub fn verify_reproducible(cmd: VerifyReproducibleCommand) -> Result<ReproducibilityReport> {
+    let this_dir = match &cmd.build.pkg.path {
+        Some(path) => PathBuf::from(path),
+        None => std::env::current_dir()?,
+    };
+    let manifest = ManifestFile::from_dir(&this_dir)?;
+    let verify_dir = default_output_directory(manifest.dir()).join(VERIFY_DIR_NAME);
+    if verify_dir.exists() {
+        fs::remove_dir_all(&verify_dir)?;
+    }
+
+    // Fetch the locked dependencies up front, rather than have the builds race to fetch them.
+    pkg::BuildPlan::from_build_opts(&build_opts(cmd.build))?;
+
+    // Each build is made by running this command again, with the same arguments, in a process of
+    // its own.
+    let output_dirs = [verify_dir.join("first"), verify_dir.join("second")];
+    let forc = std::env::current_exe()?;
+    let builds = output_dirs
+        .iter()
+        .map(|output_dir| {
+            Command::new(&forc)
+                .args(std::env::args_os().skip(1))
+                .arg("--build-into")
+                .arg(output_dir)
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    for build in builds {
+        let output = build.wait_with_output()?;
+        if !output.status.success() {
+            bail!(
+                "failed to build the project:\n{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+    }
+
+    let mut build_infos = BTreeMap::new();
+    for name in manifest.member_manifests()?.into_keys() {
+        let path = ArtifactPaths::new(&output_dirs[0], &name).build_info();
+        if path.exists() {
+            let build_info = serde_json::from_slice(&fs::read(&path)?)?;
+            build_infos.insert(name, build_info);
+        }
+    }
+
+    let first = file_hashes(&output_dirs[0])?;
+    let second = file_hashes(&output_dirs[1])?;
+    let mut mismatches = first
+        .keys()
+        .chain(second.keys())
+        .filter(|path| first.get(*path) != second.get(*path))
+        .cloned()
+        .collect::<Vec<_>>();
+    mismatches.sort();
+    mismatches.dedup();
+
+    Ok(ReproducibilityReport {
+        output_dirs,
+        build_infos,
+        mismatches,
+    })
+}
+
+/// The hash of each file within `dir`, keyed by its path relative to `dir`.
+fn file_hashes(dir: &Path) -> Result<BTreeMap<PathBuf, String>> {
+    let mut hashes = BTreeMap::new();
+    for entry in walkdir::WalkDir::new(dir) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let hash = fuel_crypto::Hasher::hash(fs::read(entry.path())?);
+        hashes.insert(
+            entry.path().strip_prefix(dir)?.to_path_buf(),
+            hash.to_string(),
+        );
+    }
+    Ok(hashes)
+}
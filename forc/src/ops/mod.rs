@@ -1,6 +1,9 @@
+pub mod forc_audit;
 pub mod forc_build;
 pub mod forc_check;
 pub mod forc_clean;
 pub mod forc_init;
+pub mod forc_size;
 pub mod forc_template;
 pub mod forc_update;
+pub mod forc_verify_reproducible;
@@ -6,12 +6,13 @@ pub struct Annotated<T> {
     pub value: T,
 }
 
-// Attributes can have any number of arguments:
+// Attributes can have any number of arguments, each of which may be given a value:
 //
 //    #[attribute]
 //    #[attribute()]
 //    #[attribute(value)]
 //    #[attribute(value0, value1, value2)]
+//    #[attribute(name = "value")]
 
 #[derive(Clone, Debug, Serialize)]
 pub struct AttributeDecl {
@@ -55,7 +56,7 @@ pub enum AttributeHashKind {
 #[derive(Clone, Debug, Serialize)]
 pub struct Attribute {
     pub name: Ident,
-    pub args: Option<Parens<Punctuated<Ident, CommaToken>>>,
+    pub args: Option<Parens<Punctuated<AttributeArg, CommaToken>>>,
 }
 
 impl Spanned for Attribute {
@@ -66,3 +67,18 @@ impl Spanned for Attribute {
             .unwrap_or_else(|| self.name.span())
     }
 }
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AttributeArg {
+    pub name: Ident,
+    pub value: Option<(EqToken, Literal)>,
+}
+
+impl Spanned for AttributeArg {
+    fn span(&self) -> Span {
+        match &self.value {
+            Some((_, value)) => Span::join(self.name.span(), value.span()),
+            None => self.name.span(),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! Rewriting of the syntax tree by value.
+//!
+//! Implementors of [Fold] take ownership of a node and return the node to replace it with. The
+//! default methods rebuild each node from its folded children, so overriding a single method is
+//! enough to rewrite every occurrence of that kind of node wherever it appears in the tree.
+
+use crate::{priv_prelude::*, visit_mut::VisitMut, Module};
+
+/// Rewrites the modules, items, statements, expressions, types and patterns of a syntax tree,
+/// children first.
+pub trait Fold {
+    fn fold_module(&mut self, module: Module) -> Module {
+        walk_module(self, module)
+    }
+
+    fn fold_item(&mut self, item: Item) -> Item {
+        walk_item(self, item)
+    }
+
+    fn fold_statement(&mut self, statement: Statement) -> Statement {
+        walk_statement(self, statement)
+    }
+
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        walk_expr(self, expr)
+    }
+
+    fn fold_ty(&mut self, ty: Ty) -> Ty {
+        walk_ty(self, ty)
+    }
+
+    fn fold_pattern(&mut self, pattern: Pattern) -> Pattern {
+        walk_pattern(self, pattern)
+    }
+}
+
+/// Folds the children of `module`.
+pub fn walk_module<F: Fold + ?Sized>(folder: &mut F, mut module: Module) -> Module {
+    crate::visit_mut::walk_module(&mut FoldChildren(folder), &mut module);
+    module
+}
+
+/// Folds the children of `item`.
+pub fn walk_item<F: Fold + ?Sized>(folder: &mut F, mut item: Item) -> Item {
+    crate::visit_mut::walk_item(&mut FoldChildren(folder), &mut item);
+    item
+}
+
+/// Folds the children of `statement`.
+pub fn walk_statement<F: Fold + ?Sized>(folder: &mut F, mut statement: Statement) -> Statement {
+    crate::visit_mut::walk_statement(&mut FoldChildren(folder), &mut statement);
+    statement
+}
+
+/// Folds the children of `expr`.
+pub fn walk_expr<F: Fold + ?Sized>(folder: &mut F, mut expr: Expr) -> Expr {
+    crate::visit_mut::walk_expr(&mut FoldChildren(folder), &mut expr);
+    expr
+}
+
+/// Folds the children of `ty`.
+pub fn walk_ty<F: Fold + ?Sized>(folder: &mut F, mut ty: Ty) -> Ty {
+    crate::visit_mut::walk_ty(&mut FoldChildren(folder), &mut ty);
+    ty
+}
+
+/// Folds the children of `pattern`.
+pub fn walk_pattern<F: Fold + ?Sized>(folder: &mut F, mut pattern: Pattern) -> Pattern {
+    crate::visit_mut::walk_pattern(&mut FoldChildren(folder), &mut pattern);
+    pattern
+}
+
+/// Walks a node mutably, handing each foldable node found below it to the folder and putting the
+/// result back in its place.
+///
+/// The node is swapped out for a cheap placeholder while the folder owns it.
+struct FoldChildren<'a, F: ?Sized>(&'a mut F);
+
+impl<F: Fold + ?Sized> VisitMut for FoldChildren<'_, F> {
+    fn visit_item(&mut self, item: &mut Item) {
+        let placeholder = Annotated {
+            attribute_list: vec![],
+            value: ItemKind::Error(Box::new([])),
+        };
+        *item = self.0.fold_item(mem::replace(item, placeholder));
+    }
+
+    fn visit_statement(&mut self, statement: &mut Statement) {
+        let placeholder = Statement::Error(Box::new([]));
+        *statement = self.0.fold_statement(mem::replace(statement, placeholder));
+    }
+
+    fn visit_expr(&mut self, expr: &mut Expr) {
+        let placeholder = Expr::Error(Box::new([]));
+        *expr = self.0.fold_expr(mem::replace(expr, placeholder));
+    }
+
+    fn visit_ty(&mut self, ty: &mut Ty) {
+        let placeholder = Ty::Infer {
+            underscore_token: UnderscoreToken::new(Span::dummy()),
+        };
+        *ty = self.0.fold_ty(mem::replace(ty, placeholder));
+    }
+
+    fn visit_pattern(&mut self, pattern: &mut Pattern) {
+        let placeholder = Pattern::Error(Box::new([]));
+        *pattern = self.0.fold_pattern(mem::replace(pattern, placeholder));
+    }
+}
@@ -28,6 +28,9 @@ pub enum Intrinsic {
     PtrAdd,
     PtrSub,
     Smo,
+    ContextGas,
+    GlobalGas,
+    GasCheckpoint,
 }
 
 impl fmt::Display for Intrinsic {
@@ -59,6 +62,9 @@ impl fmt::Display for Intrinsic {
             Intrinsic::PtrAdd => "ptr_add",
             Intrinsic::PtrSub => "ptr_sub",
             Intrinsic::Smo => "smo",
+            Intrinsic::ContextGas => "context_gas",
+            Intrinsic::GlobalGas => "global_gas",
+            Intrinsic::GasCheckpoint => "gas_checkpoint",
         };
         write!(f, "{s}")
     }
@@ -94,6 +100,9 @@ impl Intrinsic {
             "__ptr_add" => PtrAdd,
             "__ptr_sub" => PtrSub,
             "__smo" => Smo,
+            "__context_gas" => ContextGas,
+            "__global_gas" => GlobalGas,
+            "__gas_checkpoint" => GasCheckpoint,
             _ => return None,
         })
     }
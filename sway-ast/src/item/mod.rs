@@ -36,6 +36,8 @@ pub enum ItemKind {
     Const(ItemConst),
     Storage(ItemStorage),
     Configurable(ItemConfigurable),
+    /// Tokens skipped while recovering from an item that failed to parse.
+    Error(Box<[Span]>),
 }
 
 impl Spanned for ItemKind {
@@ -52,6 +54,7 @@ impl Spanned for ItemKind {
             ItemKind::Const(item_const) => item_const.span(),
             ItemKind::Storage(item_storage) => item_storage.span(),
             ItemKind::Configurable(item_configurable) => item_configurable.span(),
+            ItemKind::Error(spans) => spans.iter().cloned().reduce(Span::join).unwrap(),
         }
     }
 }
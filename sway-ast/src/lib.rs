@@ -2,6 +2,7 @@ pub mod assignable;
 pub mod attribute;
 pub mod brackets;
 pub mod expr;
+pub mod fold;
 pub mod generics;
 pub mod intrinsics;
 mod item;
@@ -14,8 +15,12 @@ mod priv_prelude;
 pub mod punctuated;
 pub mod statement;
 pub mod submodule;
+pub mod syntax_tree;
 pub mod token;
 pub mod ty;
+pub mod visit;
+pub mod visit_mut;
+mod visitor;
 pub mod where_clause;
 
 pub use crate::{
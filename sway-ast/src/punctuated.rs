@@ -87,3 +87,30 @@ impl<'a, T, P> Iterator for PunctuatedRefIter<'a, T, P> {
         }
     }
 }
+
+impl<'a, T, P> IntoIterator for &'a mut Punctuated<T, P> {
+    type Item = &'a mut T;
+    type IntoIter = PunctuatedRefMutIter<'a, T, P>;
+    fn into_iter(self) -> PunctuatedRefMutIter<'a, T, P> {
+        PunctuatedRefMutIter {
+            value_separator_pairs: self.value_separator_pairs.iter_mut(),
+            final_value_opt: self.final_value_opt.as_deref_mut(),
+        }
+    }
+}
+
+pub struct PunctuatedRefMutIter<'a, T, P> {
+    value_separator_pairs: std::slice::IterMut<'a, (T, P)>,
+    final_value_opt: Option<&'a mut T>,
+}
+
+impl<'a, T, P> Iterator for PunctuatedRefMutIter<'a, T, P> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        match self.value_separator_pairs.next() {
+            Some((value, _separator)) => Some(value),
+            None => self.final_value_opt.take(),
+        }
+    }
+}
@@ -9,6 +9,8 @@ pub enum Statement {
         expr: Expr,
         semicolon_token_opt: Option<SemicolonToken>,
     },
+    /// Tokens skipped while recovering from a statement that failed to parse.
+    Error(Box<[Span]>),
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -33,6 +35,7 @@ impl Spanned for Statement {
                 None => expr.span(),
                 Some(semicolon_token) => Span::join(expr.span(), semicolon_token.span()),
             },
+            Statement::Error(spans) => spans.iter().cloned().reduce(Span::join).unwrap(),
         }
     }
 }
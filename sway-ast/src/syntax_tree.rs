@@ -0,0 +1,342 @@
+//! A lossless syntax tree of a source file.
+//!
+//! The syntax tree sits between the token stream and the AST. Unlike either of them it keeps
+//! every byte of the source, including whitespace, comments and delimiters, so the text of any
+//! node is exactly the text it was built from. Tools that rewrite source code can use it to
+//! compute edits that touch only the text they mean to change.
+//!
+//! The tree is built from the tokens, nesting delimited groups, and then the syntactic nodes
+//! found by the parser, such as items, are grouped with [SyntaxNode::wrap]. Trivia between
+//! those nodes stays with their parent, so leading comments belong to the enclosing node.
+
+use crate::{
+    priv_prelude::*,
+    token::{CommentedTokenStream, CommentedTokenTree, GenericTokenTree},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+    /// The whole source file.
+    Root,
+    /// The declaration of the kind of module at the start of the file, e.g. `library;`.
+    ModuleKind,
+    /// An item, including its attributes and doc comments, at the top level of the module or in
+    /// the body of an `impl`, `abi` or `trait`.
+    Item,
+    /// A delimited group, including its delimiters.
+    Group(Delimiter),
+    Whitespace,
+    Comment,
+    DocComment,
+    Ident,
+    Punct(PunctKind),
+    Literal,
+    OpenDelimiter(Delimiter),
+    CloseDelimiter(Delimiter),
+    /// Text skipped by the lexer, such as an invalid character or an unclosed block comment.
+    Unknown,
+}
+
+impl SyntaxKind {
+    /// Returns true for whitespace and comments, which don't affect the meaning of the program.
+    pub fn is_trivia(self) -> bool {
+        matches!(self, SyntaxKind::Whitespace | SyntaxKind::Comment)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SyntaxToken {
+    pub kind: SyntaxKind,
+    pub span: Span,
+}
+
+impl Spanned for SyntaxToken {
+    fn span(&self) -> Span {
+        self.span.clone()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SyntaxNode {
+    pub kind: SyntaxKind,
+    pub span: Span,
+    pub children: Vec<SyntaxElement>,
+}
+
+impl Spanned for SyntaxNode {
+    fn span(&self) -> Span {
+        self.span.clone()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl Spanned for SyntaxElement {
+    fn span(&self) -> Span {
+        match self {
+            SyntaxElement::Node(node) => node.span(),
+            SyntaxElement::Token(token) => token.span(),
+        }
+    }
+}
+
+/// Replaces the text of `span` with `new_text`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyntaxEdit {
+    pub span: Span,
+    pub new_text: String,
+}
+
+impl SyntaxNode {
+    /// Builds the syntax tree of the source that `stream` was lexed from, which must span the
+    /// whole source.
+    pub fn from_token_stream(stream: &CommentedTokenStream) -> SyntaxNode {
+        let span = stream.full_span.clone();
+        let children = elements_between(&span, &stream.token_trees, span.start(), span.end());
+        SyntaxNode {
+            kind: SyntaxKind::Root,
+            span,
+            children,
+        }
+    }
+
+    /// Groups the children which together span exactly `span` into a new node of `kind`, within
+    /// the innermost node containing them.
+    ///
+    /// Returns false, leaving the tree as it was, if `span` doesn't start and end at the
+    /// boundaries of the children of a node.
+    pub fn wrap(&mut self, kind: SyntaxKind, span: &Span) -> bool {
+        let (start, end) = (span.start(), span.end());
+        let inner = self.children.iter_mut().find_map(|child| match child {
+            SyntaxElement::Node(node)
+                if node.span.start() <= start
+                    && end <= node.span.end()
+                    && (node.span.start(), node.span.end()) != (start, end) =>
+            {
+                Some(node)
+            }
+            _ => None,
+        });
+        if let Some(node) = inner {
+            return node.wrap(kind, span);
+        }
+        let first = self
+            .children
+            .iter()
+            .position(|child| child.span().start() == start);
+        let last = self
+            .children
+            .iter()
+            .position(|child| child.span().end() == end);
+        match (first, last) {
+            (Some(first), Some(last)) if first <= last => {
+                let children = self.children.drain(first..=last).collect();
+                let node = SyntaxNode {
+                    kind,
+                    span: span_within(&self.span, start, end),
+                    children,
+                };
+                self.children.insert(first, SyntaxElement::Node(node));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the nodes of the given kind within this node, outermost first.
+    pub fn descendants(&self, kind: SyntaxKind) -> Vec<&SyntaxNode> {
+        let mut nodes = vec![];
+        for child in &self.children {
+            if let SyntaxElement::Node(node) = child {
+                if node.kind == kind {
+                    nodes.push(node);
+                }
+                nodes.extend(node.descendants(kind));
+            }
+        }
+        nodes
+    }
+
+    /// Returns the source text of the node, which includes all of its whitespace and comments.
+    pub fn text(&self) -> &str {
+        self.span.as_str()
+    }
+
+    /// Returns the tokens of the node in source order. Together they cover the node's text
+    /// without gaps.
+    pub fn tokens(&self) -> Vec<&SyntaxToken> {
+        let mut tokens = vec![];
+        collect_tokens(self, &mut tokens);
+        tokens
+    }
+
+    /// Returns the token containing the given byte offset of the source.
+    pub fn token_at_offset(&self, offset: usize) -> Option<&SyntaxToken> {
+        let mut node = self;
+        loop {
+            let child = node
+                .children
+                .iter()
+                .find(|child| child.span().start() <= offset && offset < child.span().end())?;
+            match child {
+                SyntaxElement::Node(child_node) => node = child_node,
+                SyntaxElement::Token(token) => return Some(token),
+            }
+        }
+    }
+
+    /// Returns the edits which turn the text of this tree into the text of `other`, provided the
+    /// two differ only in their trivia. The edits replace the whole trivia between each pair of
+    /// neighbouring tokens that differs, so everything else is left untouched.
+    ///
+    /// Returns `None` if the trees differ in any other token.
+    pub fn trivia_edits(&self, other: &SyntaxNode) -> Option<Vec<SyntaxEdit>> {
+        let tokens = significant_tokens(self);
+        let other_tokens = significant_tokens(other);
+        let same_tokens = tokens.len() == other_tokens.len()
+            && tokens
+                .iter()
+                .zip(&other_tokens)
+                .all(|(token, other_token)| token.span.as_str() == other_token.span.as_str());
+        if !same_tokens {
+            return None;
+        }
+        let gaps = trivia_gaps(self, &tokens);
+        let other_gaps = trivia_gaps(other, &other_tokens);
+        let edits = gaps
+            .into_iter()
+            .zip(other_gaps)
+            .filter(|(gap, other_gap)| gap.as_str() != other_gap.as_str())
+            .map(|(gap, other_gap)| SyntaxEdit {
+                span: gap,
+                new_text: other_gap.as_str().to_string(),
+            })
+            .collect();
+        Some(edits)
+    }
+}
+
+fn collect_tokens<'a>(node: &'a SyntaxNode, tokens: &mut Vec<&'a SyntaxToken>) {
+    for child in &node.children {
+        match child {
+            SyntaxElement::Node(child_node) => collect_tokens(child_node, tokens),
+            SyntaxElement::Token(token) => tokens.push(token),
+        }
+    }
+}
+
+fn significant_tokens(node: &SyntaxNode) -> Vec<&SyntaxToken> {
+    node.tokens()
+        .into_iter()
+        .filter(|token| !token.kind.is_trivia())
+        .collect()
+}
+
+/// Returns the spans of the trivia before, between and after `tokens`, some of which may be
+/// empty.
+fn trivia_gaps(node: &SyntaxNode, tokens: &[&SyntaxToken]) -> Vec<Span> {
+    let starts = std::iter::once(node.span.start()).chain(tokens.iter().map(|t| t.span.end()));
+    let ends = tokens
+        .iter()
+        .map(|t| t.span.start())
+        .chain(std::iter::once(node.span.end()));
+    starts
+        .zip(ends)
+        .map(|(start, end)| span_within(&node.span, start, end))
+        .collect()
+}
+
+fn span_within(span: &Span, start: usize, end: usize) -> Span {
+    Span::new(span.src().clone(), start, end, span.path().cloned()).unwrap()
+}
+
+/// Returns the syntax elements of the source between `start` and `end`, which holds
+/// `token_trees` separated by whitespace and any text skipped by the lexer.
+fn elements_between(
+    span: &Span,
+    token_trees: &[CommentedTokenTree],
+    start: usize,
+    end: usize,
+) -> Vec<SyntaxElement> {
+    let mut children = vec![];
+    let mut position = start;
+    for token_tree in token_trees {
+        let element = element(span, token_tree);
+        push_gap(span, &mut children, position, element.span().start());
+        position = element.span().end();
+        children.push(element);
+    }
+    push_gap(span, &mut children, position, end);
+    children
+}
+
+fn push_gap(span: &Span, children: &mut Vec<SyntaxElement>, start: usize, end: usize) {
+    if start >= end {
+        return;
+    }
+    let gap = span_within(span, start, end);
+    let kind = if gap.as_str().chars().all(char::is_whitespace) {
+        SyntaxKind::Whitespace
+    } else {
+        SyntaxKind::Unknown
+    };
+    children.push(SyntaxElement::Token(SyntaxToken { kind, span: gap }));
+}
+
+fn element(span: &Span, token_tree: &CommentedTokenTree) -> SyntaxElement {
+    let token = |kind, token_span: Span| {
+        SyntaxElement::Token(SyntaxToken {
+            kind,
+            span: token_span,
+        })
+    };
+    let tree = match token_tree {
+        CommentedTokenTree::Comment(comment) => {
+            return token(SyntaxKind::Comment, comment.span.clone())
+        }
+        CommentedTokenTree::Tree(tree) => tree,
+    };
+    match tree {
+        GenericTokenTree::Punct(punct) => token(SyntaxKind::Punct(punct.kind), punct.span()),
+        // The span of a raw identifier leaves out its `r#` prefix.
+        GenericTokenTree::Ident(ident) if ident.is_raw_ident() => token(
+            SyntaxKind::Ident,
+            span_within(span, ident.span().start() - 2, ident.span().end()),
+        ),
+        GenericTokenTree::Ident(ident) => token(SyntaxKind::Ident, ident.span()),
+        GenericTokenTree::Literal(literal) => token(SyntaxKind::Literal, literal.span()),
+        GenericTokenTree::DocComment(doc_comment) => {
+            token(SyntaxKind::DocComment, doc_comment.span())
+        }
+        GenericTokenTree::Group(group) => {
+            let delimiter = group.delimiter;
+            let inner = &group.token_stream.full_span;
+            let mut elements = vec![token(
+                SyntaxKind::OpenDelimiter(delimiter),
+                span_within(span, group.span.start(), inner.start()),
+            )];
+            elements.extend(elements_between(
+                span,
+                &group.token_stream.token_trees,
+                inner.start(),
+                inner.end(),
+            ));
+            // Unclosed groups are recovered without a closing delimiter.
+            if inner.end() < group.span.end() {
+                elements.push(token(
+                    SyntaxKind::CloseDelimiter(delimiter),
+                    span_within(span, inner.end(), group.span.end()),
+                ));
+            }
+            SyntaxElement::Node(SyntaxNode {
+                kind: SyntaxKind::Group(delimiter),
+                span: group.span(),
+                children: elements,
+            })
+        }
+    }
+}
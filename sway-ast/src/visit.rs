@@ -0,0 +1,11 @@
+//! Traversal of the syntax tree by shared reference.
+//!
+//! Implementors of [Visit] override the methods for the nodes they are interested in and call the
+//! matching `walk_*` function from within them to keep visiting the node's children.
+
+use crate::visitor::define_visitor;
+
+define_visitor!(
+    /// Visits the nodes of a syntax tree by shared reference, in source order.
+    Visit
+);
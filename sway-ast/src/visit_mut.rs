@@ -0,0 +1,11 @@
+//! Traversal of the syntax tree by mutable reference.
+//!
+//! This mirrors [crate::visit], with every method taking the node by mutable reference so that
+//! it can be edited in place.
+
+use crate::visitor::define_visitor;
+
+define_visitor!(
+    /// Visits the nodes of a syntax tree by mutable reference, in source order.
+    VisitMut, mut
+);
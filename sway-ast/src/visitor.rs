@@ -0,0 +1,893 @@
+/// Defines a visitor trait over the syntax tree along with the `walk_*` functions that its
+/// default methods use to visit the children of each node.
+///
+/// The same definition generates [crate::visit::Visit] over shared references and, when given
+/// `mut`, [crate::visit_mut::VisitMut] over mutable references, so that the two can never disagree
+/// about which nodes are reachable.
+macro_rules! define_visitor {
+    ($(#[$attr:meta])* $trait_name:ident $(, $mutability:tt)?) => {
+        use crate::{
+            attribute::Attribute,
+            expr::{
+                asm::AsmFinalExpr, ExprArrayDescriptor, ExprStructField, ExprTupleDescriptor,
+                IfCondition, IfExpr, LoopControlFlow, MatchBranch, MatchBranchKind,
+            },
+            item::{
+                item_configurable::ConfigurableField, item_impl::ItemImplItem,
+                item_storage::StorageField, item_trait::ItemTraitItem, item_use::UseTree, FnArg,
+                FnArgs,
+            },
+            path::{PathExprSegment, PathTypeSegment, QualifiedPathRoot},
+            pattern::PatternStructField,
+            priv_prelude::*,
+            ty::TyTupleDescriptor,
+            Module,
+        };
+
+        $(#[$attr])*
+        pub trait $trait_name {
+            fn visit_module(&mut self, module: &$($mutability)? Module) {
+                walk_module(self, module)
+            }
+
+            fn visit_item(&mut self, item: &$($mutability)? Item) {
+                walk_item(self, item)
+            }
+
+            fn visit_attribute_decl(&mut self, attribute_decl: &$($mutability)? AttributeDecl) {
+                walk_attribute_decl(self, attribute_decl)
+            }
+
+            fn visit_submodule(&mut self, submodule: &$($mutability)? Submodule) {
+                walk_submodule(self, submodule)
+            }
+
+            fn visit_item_use(&mut self, item_use: &$($mutability)? ItemUse) {
+                walk_item_use(self, item_use)
+            }
+
+            fn visit_use_tree(&mut self, use_tree: &$($mutability)? UseTree) {
+                walk_use_tree(self, use_tree)
+            }
+
+            fn visit_item_struct(&mut self, item_struct: &$($mutability)? ItemStruct) {
+                walk_item_struct(self, item_struct)
+            }
+
+            fn visit_item_enum(&mut self, item_enum: &$($mutability)? ItemEnum) {
+                walk_item_enum(self, item_enum)
+            }
+
+            fn visit_type_field(&mut self, type_field: &$($mutability)? TypeField) {
+                walk_type_field(self, type_field)
+            }
+
+            fn visit_item_fn(&mut self, item_fn: &$($mutability)? ItemFn) {
+                walk_item_fn(self, item_fn)
+            }
+
+            fn visit_fn_signature(&mut self, fn_signature: &$($mutability)? FnSignature) {
+                walk_fn_signature(self, fn_signature)
+            }
+
+            fn visit_fn_arg(&mut self, fn_arg: &$($mutability)? FnArg) {
+                walk_fn_arg(self, fn_arg)
+            }
+
+            fn visit_item_trait(&mut self, item_trait: &$($mutability)? ItemTrait) {
+                walk_item_trait(self, item_trait)
+            }
+
+            fn visit_traits(&mut self, traits: &$($mutability)? Traits) {
+                walk_traits(self, traits)
+            }
+
+            fn visit_item_impl(&mut self, item_impl: &$($mutability)? ItemImpl) {
+                walk_item_impl(self, item_impl)
+            }
+
+            fn visit_item_abi(&mut self, item_abi: &$($mutability)? ItemAbi) {
+                walk_item_abi(self, item_abi)
+            }
+
+            fn visit_item_const(&mut self, item_const: &$($mutability)? ItemConst) {
+                walk_item_const(self, item_const)
+            }
+
+            fn visit_item_storage(&mut self, item_storage: &$($mutability)? ItemStorage) {
+                walk_item_storage(self, item_storage)
+            }
+
+            fn visit_storage_field(&mut self, storage_field: &$($mutability)? StorageField) {
+                walk_storage_field(self, storage_field)
+            }
+
+            fn visit_item_configurable(
+                &mut self,
+                item_configurable: &$($mutability)? ItemConfigurable,
+            ) {
+                walk_item_configurable(self, item_configurable)
+            }
+
+            fn visit_configurable_field(
+                &mut self,
+                configurable_field: &$($mutability)? ConfigurableField,
+            ) {
+                walk_configurable_field(self, configurable_field)
+            }
+
+            fn visit_generic_params(&mut self, generic_params: &$($mutability)? GenericParams) {
+                walk_generic_params(self, generic_params)
+            }
+
+            fn visit_generic_args(&mut self, generic_args: &$($mutability)? GenericArgs) {
+                walk_generic_args(self, generic_args)
+            }
+
+            fn visit_where_clause(&mut self, where_clause: &$($mutability)? WhereClause) {
+                walk_where_clause(self, where_clause)
+            }
+
+            fn visit_ty(&mut self, ty: &$($mutability)? Ty) {
+                walk_ty(self, ty)
+            }
+
+            fn visit_path_type(&mut self, path_type: &$($mutability)? PathType) {
+                walk_path_type(self, path_type)
+            }
+
+            fn visit_path_expr(&mut self, path_expr: &$($mutability)? PathExpr) {
+                walk_path_expr(self, path_expr)
+            }
+
+            fn visit_code_block_contents(
+                &mut self,
+                code_block_contents: &$($mutability)? CodeBlockContents,
+            ) {
+                walk_code_block_contents(self, code_block_contents)
+            }
+
+            fn visit_statement(&mut self, statement: &$($mutability)? Statement) {
+                walk_statement(self, statement)
+            }
+
+            fn visit_statement_let(&mut self, statement_let: &$($mutability)? StatementLet) {
+                walk_statement_let(self, statement_let)
+            }
+
+            fn visit_expr(&mut self, expr: &$($mutability)? Expr) {
+                walk_expr(self, expr)
+            }
+
+            fn visit_if_expr(&mut self, if_expr: &$($mutability)? IfExpr) {
+                walk_if_expr(self, if_expr)
+            }
+
+            fn visit_match_branch(&mut self, match_branch: &$($mutability)? MatchBranch) {
+                walk_match_branch(self, match_branch)
+            }
+
+            fn visit_asm_block(&mut self, asm_block: &$($mutability)? AsmBlock) {
+                walk_asm_block(self, asm_block)
+            }
+
+            fn visit_assignable(&mut self, assignable: &$($mutability)? Assignable) {
+                walk_assignable(self, assignable)
+            }
+
+            fn visit_pattern(&mut self, pattern: &$($mutability)? Pattern) {
+                walk_pattern(self, pattern)
+            }
+
+            fn visit_literal(&mut self, _literal: &$($mutability)? Literal) {}
+
+            fn visit_ident(&mut self, _ident: &$($mutability)? Ident) {}
+        }
+
+        pub fn walk_module<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            module: &$($mutability)? Module,
+        ) {
+            for item in &$($mutability)? module.items {
+                visitor.visit_item(item);
+            }
+        }
+
+        pub fn walk_item<V: $trait_name + ?Sized>(visitor: &mut V, item: &$($mutability)? Item) {
+            walk_attribute_list(visitor, &$($mutability)? item.attribute_list);
+            match &$($mutability)? item.value {
+                ItemKind::Submodule(submodule) => visitor.visit_submodule(submodule),
+                ItemKind::Use(item_use) => visitor.visit_item_use(item_use),
+                ItemKind::Struct(item_struct) => visitor.visit_item_struct(item_struct),
+                ItemKind::Enum(item_enum) => visitor.visit_item_enum(item_enum),
+                ItemKind::Fn(item_fn) => visitor.visit_item_fn(item_fn),
+                ItemKind::Trait(item_trait) => visitor.visit_item_trait(item_trait),
+                ItemKind::Impl(item_impl) => visitor.visit_item_impl(item_impl),
+                ItemKind::Abi(item_abi) => visitor.visit_item_abi(item_abi),
+                ItemKind::Const(item_const) => visitor.visit_item_const(item_const),
+                ItemKind::Storage(item_storage) => visitor.visit_item_storage(item_storage),
+                ItemKind::Configurable(item_configurable) => {
+                    visitor.visit_item_configurable(item_configurable)
+                }
+                ItemKind::Error(_) => {}
+            }
+        }
+
+        fn walk_attribute_list<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            attribute_list: &$($mutability)? [AttributeDecl],
+        ) {
+            for attribute_decl in attribute_list {
+                visitor.visit_attribute_decl(attribute_decl);
+            }
+        }
+
+        pub fn walk_attribute_decl<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            attribute_decl: &$($mutability)? AttributeDecl,
+        ) {
+            for attribute in &$($mutability)? attribute_decl.attribute.inner {
+                let Attribute { name, args } = attribute;
+                visitor.visit_ident(name);
+                if let Some(args) = args {
+                    for AttributeArg { name, value } in &$($mutability)? args.inner {
+                        visitor.visit_ident(name);
+                        if let Some((_, value)) = value {
+                            visitor.visit_literal(value);
+                        }
+                    }
+                }
+            }
+        }
+
+        pub fn walk_submodule<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            submodule: &$($mutability)? Submodule,
+        ) {
+            visitor.visit_ident(&$($mutability)? submodule.name);
+        }
+
+        pub fn walk_item_use<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            item_use: &$($mutability)? ItemUse,
+        ) {
+            visitor.visit_use_tree(&$($mutability)? item_use.tree);
+        }
+
+        pub fn walk_use_tree<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            use_tree: &$($mutability)? UseTree,
+        ) {
+            match use_tree {
+                UseTree::Group { imports } => {
+                    for import in &$($mutability)? imports.inner {
+                        visitor.visit_use_tree(import);
+                    }
+                }
+                UseTree::Name { name } => visitor.visit_ident(name),
+                UseTree::Rename { name, alias, .. } => {
+                    visitor.visit_ident(name);
+                    visitor.visit_ident(alias);
+                }
+                UseTree::Path { prefix, suffix, .. } => {
+                    visitor.visit_ident(prefix);
+                    visitor.visit_use_tree(suffix);
+                }
+                UseTree::Glob { .. } | UseTree::Error { .. } => {}
+            }
+        }
+
+        pub fn walk_item_struct<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            item_struct: &$($mutability)? ItemStruct,
+        ) {
+            visitor.visit_ident(&$($mutability)? item_struct.name);
+            if let Some(generics) = &$($mutability)? item_struct.generics {
+                visitor.visit_generic_params(generics);
+            }
+            if let Some(where_clause) = &$($mutability)? item_struct.where_clause_opt {
+                visitor.visit_where_clause(where_clause);
+            }
+            for field in &$($mutability)? item_struct.fields.inner {
+                walk_attribute_list(visitor, &$($mutability)? field.attribute_list);
+                visitor.visit_type_field(&$($mutability)? field.value);
+            }
+        }
+
+        pub fn walk_item_enum<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            item_enum: &$($mutability)? ItemEnum,
+        ) {
+            visitor.visit_ident(&$($mutability)? item_enum.name);
+            if let Some(generics) = &$($mutability)? item_enum.generics {
+                visitor.visit_generic_params(generics);
+            }
+            if let Some(where_clause) = &$($mutability)? item_enum.where_clause_opt {
+                visitor.visit_where_clause(where_clause);
+            }
+            for variant in &$($mutability)? item_enum.fields.inner {
+                walk_attribute_list(visitor, &$($mutability)? variant.attribute_list);
+                visitor.visit_type_field(&$($mutability)? variant.value);
+            }
+        }
+
+        pub fn walk_type_field<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            type_field: &$($mutability)? TypeField,
+        ) {
+            visitor.visit_ident(&$($mutability)? type_field.name);
+            visitor.visit_ty(&$($mutability)? type_field.ty);
+        }
+
+        pub fn walk_item_fn<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            item_fn: &$($mutability)? ItemFn,
+        ) {
+            visitor.visit_fn_signature(&$($mutability)? item_fn.fn_signature);
+            visitor.visit_code_block_contents(&$($mutability)? item_fn.body.inner);
+        }
+
+        pub fn walk_fn_signature<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            fn_signature: &$($mutability)? FnSignature,
+        ) {
+            visitor.visit_ident(&$($mutability)? fn_signature.name);
+            if let Some(generics) = &$($mutability)? fn_signature.generics {
+                visitor.visit_generic_params(generics);
+            }
+            match &$($mutability)? fn_signature.arguments.inner {
+                FnArgs::Static(args) | FnArgs::NonStatic { args_opt: Some((_, args)), .. } => {
+                    for arg in args {
+                        visitor.visit_fn_arg(arg);
+                    }
+                }
+                FnArgs::NonStatic { args_opt: None, .. } => {}
+            }
+            if let Some((_, ty)) = &$($mutability)? fn_signature.return_type_opt {
+                visitor.visit_ty(ty);
+            }
+            if let Some(where_clause) = &$($mutability)? fn_signature.where_clause_opt {
+                visitor.visit_where_clause(where_clause);
+            }
+        }
+
+        pub fn walk_fn_arg<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            fn_arg: &$($mutability)? FnArg,
+        ) {
+            visitor.visit_pattern(&$($mutability)? fn_arg.pattern);
+            visitor.visit_ty(&$($mutability)? fn_arg.ty);
+        }
+
+        pub fn walk_item_trait<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            item_trait: &$($mutability)? ItemTrait,
+        ) {
+            visitor.visit_ident(&$($mutability)? item_trait.name);
+            if let Some(generics) = &$($mutability)? item_trait.generics {
+                visitor.visit_generic_params(generics);
+            }
+            if let Some(where_clause) = &$($mutability)? item_trait.where_clause_opt {
+                visitor.visit_where_clause(where_clause);
+            }
+            if let Some((_, super_traits)) = &$($mutability)? item_trait.super_traits {
+                visitor.visit_traits(super_traits);
+            }
+            walk_interface(
+                visitor,
+                &$($mutability)? item_trait.trait_items.inner,
+                &$($mutability)? item_trait.trait_defs_opt,
+            );
+        }
+
+        /// Walks the required and provided methods of a trait or an ABI.
+        fn walk_interface<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            items: &$($mutability)? [(Annotated<ItemTraitItem>, SemicolonToken)],
+            defs_opt: &$($mutability)? Option<Braces<Vec<Annotated<ItemFn>>>>,
+        ) {
+            for (item, _) in items {
+                walk_attribute_list(visitor, &$($mutability)? item.attribute_list);
+                match &$($mutability)? item.value {
+                    ItemTraitItem::Fn(fn_signature) => visitor.visit_fn_signature(fn_signature),
+                }
+            }
+            if let Some(defs) = defs_opt {
+                for item_fn in &$($mutability)? defs.inner {
+                    walk_attribute_list(visitor, &$($mutability)? item_fn.attribute_list);
+                    visitor.visit_item_fn(&$($mutability)? item_fn.value);
+                }
+            }
+        }
+
+        pub fn walk_traits<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            traits: &$($mutability)? Traits,
+        ) {
+            visitor.visit_path_type(&$($mutability)? traits.prefix);
+            for (_, path_type) in &$($mutability)? traits.suffixes {
+                visitor.visit_path_type(path_type);
+            }
+        }
+
+        pub fn walk_item_impl<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            item_impl: &$($mutability)? ItemImpl,
+        ) {
+            if let Some(generics) = &$($mutability)? item_impl.generic_params_opt {
+                visitor.visit_generic_params(generics);
+            }
+            if let Some((path_type, _)) = &$($mutability)? item_impl.trait_opt {
+                visitor.visit_path_type(path_type);
+            }
+            visitor.visit_ty(&$($mutability)? item_impl.ty);
+            if let Some(where_clause) = &$($mutability)? item_impl.where_clause_opt {
+                visitor.visit_where_clause(where_clause);
+            }
+            for item in &$($mutability)? item_impl.contents.inner {
+                walk_attribute_list(visitor, &$($mutability)? item.attribute_list);
+                match &$($mutability)? item.value {
+                    ItemImplItem::Fn(item_fn) => visitor.visit_item_fn(item_fn),
+                }
+            }
+        }
+
+        pub fn walk_item_abi<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            item_abi: &$($mutability)? ItemAbi,
+        ) {
+            visitor.visit_ident(&$($mutability)? item_abi.name);
+            if let Some((_, super_traits)) = &$($mutability)? item_abi.super_traits {
+                visitor.visit_traits(super_traits);
+            }
+            walk_interface(
+                visitor,
+                &$($mutability)? item_abi.abi_items.inner,
+                &$($mutability)? item_abi.abi_defs_opt,
+            );
+        }
+
+        pub fn walk_item_const<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            item_const: &$($mutability)? ItemConst,
+        ) {
+            visitor.visit_ident(&$($mutability)? item_const.name);
+            if let Some((_, ty)) = &$($mutability)? item_const.ty_opt {
+                visitor.visit_ty(ty);
+            }
+            if let Some(expr) = &$($mutability)? item_const.expr_opt {
+                visitor.visit_expr(expr);
+            }
+        }
+
+        pub fn walk_item_storage<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            item_storage: &$($mutability)? ItemStorage,
+        ) {
+            for field in &$($mutability)? item_storage.fields.inner {
+                walk_attribute_list(visitor, &$($mutability)? field.attribute_list);
+                visitor.visit_storage_field(&$($mutability)? field.value);
+            }
+        }
+
+        pub fn walk_storage_field<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            storage_field: &$($mutability)? StorageField,
+        ) {
+            visitor.visit_ident(&$($mutability)? storage_field.name);
+            visitor.visit_ty(&$($mutability)? storage_field.ty);
+            visitor.visit_expr(&$($mutability)? storage_field.initializer);
+        }
+
+        pub fn walk_item_configurable<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            item_configurable: &$($mutability)? ItemConfigurable,
+        ) {
+            for field in &$($mutability)? item_configurable.fields.inner {
+                walk_attribute_list(visitor, &$($mutability)? field.attribute_list);
+                visitor.visit_configurable_field(&$($mutability)? field.value);
+            }
+        }
+
+        pub fn walk_configurable_field<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            configurable_field: &$($mutability)? ConfigurableField,
+        ) {
+            visitor.visit_ident(&$($mutability)? configurable_field.name);
+            visitor.visit_ty(&$($mutability)? configurable_field.ty);
+            visitor.visit_expr(&$($mutability)? configurable_field.initializer);
+        }
+
+        pub fn walk_generic_params<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            generic_params: &$($mutability)? GenericParams,
+        ) {
+            for ident in &$($mutability)? generic_params.parameters.inner {
+                visitor.visit_ident(ident);
+            }
+        }
+
+        pub fn walk_generic_args<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            generic_args: &$($mutability)? GenericArgs,
+        ) {
+            for ty in &$($mutability)? generic_args.parameters.inner {
+                visitor.visit_ty(ty);
+            }
+        }
+
+        pub fn walk_where_clause<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            where_clause: &$($mutability)? WhereClause,
+        ) {
+            for bound in &$($mutability)? where_clause.bounds {
+                visitor.visit_ident(&$($mutability)? bound.ty_name);
+                visitor.visit_traits(&$($mutability)? bound.bounds);
+            }
+        }
+
+        pub fn walk_ty<V: $trait_name + ?Sized>(visitor: &mut V, ty: &$($mutability)? Ty) {
+            match ty {
+                Ty::Path(path_type) => visitor.visit_path_type(path_type),
+                Ty::Tuple(tuple) => {
+                    if let TyTupleDescriptor::Cons { head, tail, .. } =
+                        &$($mutability)? tuple.inner
+                    {
+                        visitor.visit_ty(head);
+                        for ty in tail {
+                            visitor.visit_ty(ty);
+                        }
+                    }
+                }
+                Ty::Array(array) => {
+                    visitor.visit_ty(&$($mutability)? array.inner.ty);
+                    visitor.visit_expr(&$($mutability)? array.inner.length);
+                }
+                Ty::Str { length, .. } => visitor.visit_expr(&$($mutability)? length.inner),
+                Ty::Infer { .. } => {}
+            }
+        }
+
+        fn walk_qualified_path_root<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            root_opt: &$($mutability)? Option<(
+                Option<AngleBrackets<QualifiedPathRoot>>,
+                DoubleColonToken,
+            )>,
+        ) {
+            if let Some((Some(root), _)) = root_opt {
+                visitor.visit_ty(&$($mutability)? root.inner.ty);
+                if let Some((_, as_trait)) = &$($mutability)? root.inner.as_trait {
+                    visitor.visit_path_type(as_trait);
+                }
+            }
+        }
+
+        pub fn walk_path_type<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            path_type: &$($mutability)? PathType,
+        ) {
+            walk_qualified_path_root(visitor, &$($mutability)? path_type.root_opt);
+            walk_path_type_segment(visitor, &$($mutability)? path_type.prefix);
+            for (_, segment) in &$($mutability)? path_type.suffix {
+                walk_path_type_segment(visitor, segment);
+            }
+        }
+
+        fn walk_path_type_segment<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            segment: &$($mutability)? PathTypeSegment,
+        ) {
+            visitor.visit_ident(&$($mutability)? segment.name);
+            if let Some((_, generic_args)) = &$($mutability)? segment.generics_opt {
+                visitor.visit_generic_args(generic_args);
+            }
+        }
+
+        pub fn walk_path_expr<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            path_expr: &$($mutability)? PathExpr,
+        ) {
+            walk_qualified_path_root(visitor, &$($mutability)? path_expr.root_opt);
+            walk_path_expr_segment(visitor, &$($mutability)? path_expr.prefix);
+            for (_, segment) in &$($mutability)? path_expr.suffix {
+                walk_path_expr_segment(visitor, segment);
+            }
+        }
+
+        fn walk_path_expr_segment<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            segment: &$($mutability)? PathExprSegment,
+        ) {
+            visitor.visit_ident(&$($mutability)? segment.name);
+            if let Some((_, generic_args)) = &$($mutability)? segment.generics_opt {
+                visitor.visit_generic_args(generic_args);
+            }
+        }
+
+        pub fn walk_code_block_contents<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            code_block_contents: &$($mutability)? CodeBlockContents,
+        ) {
+            for statement in &$($mutability)? code_block_contents.statements {
+                visitor.visit_statement(statement);
+            }
+            if let Some(expr) = &$($mutability)? code_block_contents.final_expr_opt {
+                visitor.visit_expr(expr);
+            }
+        }
+
+        pub fn walk_statement<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            statement: &$($mutability)? Statement,
+        ) {
+            match statement {
+                Statement::Let(statement_let) => visitor.visit_statement_let(statement_let),
+                Statement::Item(item) => visitor.visit_item(item),
+                Statement::Expr { expr, .. } => visitor.visit_expr(expr),
+                Statement::Error(_) => {}
+            }
+        }
+
+        pub fn walk_statement_let<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            statement_let: &$($mutability)? StatementLet,
+        ) {
+            visitor.visit_pattern(&$($mutability)? statement_let.pattern);
+            if let Some((_, ty)) = &$($mutability)? statement_let.ty_opt {
+                visitor.visit_ty(ty);
+            }
+            visitor.visit_expr(&$($mutability)? statement_let.expr);
+        }
+
+        fn walk_struct_fields<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            fields: &$($mutability)? Punctuated<ExprStructField, CommaToken>,
+        ) {
+            for field in fields {
+                visitor.visit_ident(&$($mutability)? field.field_name);
+                if let Some((_, expr)) = &$($mutability)? field.expr_opt {
+                    visitor.visit_expr(expr);
+                }
+            }
+        }
+
+        pub fn walk_expr<V: $trait_name + ?Sized>(visitor: &mut V, expr: &$($mutability)? Expr) {
+            match expr {
+                Expr::Error(_) | Expr::Break { .. } | Expr::Continue { .. } => {}
+                Expr::Path(path_expr) => visitor.visit_path_expr(path_expr),
+                Expr::Literal(literal) => visitor.visit_literal(literal),
+                Expr::AbiCast { args, .. } => {
+                    visitor.visit_path_type(&$($mutability)? args.inner.name);
+                    visitor.visit_expr(&$($mutability)? args.inner.address);
+                }
+                Expr::Struct { path, fields } => {
+                    visitor.visit_path_expr(path);
+                    walk_struct_fields(visitor, &$($mutability)? fields.inner);
+                }
+                Expr::Tuple(tuple) => {
+                    if let ExprTupleDescriptor::Cons { head, tail, .. } =
+                        &$($mutability)? tuple.inner
+                    {
+                        visitor.visit_expr(head);
+                        for expr in tail {
+                            visitor.visit_expr(expr);
+                        }
+                    }
+                }
+                Expr::Parens(parens) => visitor.visit_expr(&$($mutability)? parens.inner),
+                Expr::Block(block) => {
+                    visitor.visit_code_block_contents(&$($mutability)? block.inner)
+                }
+                Expr::Array(array) => match &$($mutability)? array.inner {
+                    ExprArrayDescriptor::Sequence(exprs) => {
+                        for expr in exprs {
+                            visitor.visit_expr(expr);
+                        }
+                    }
+                    ExprArrayDescriptor::Repeat { value, length, .. } => {
+                        visitor.visit_expr(value);
+                        visitor.visit_expr(length);
+                    }
+                },
+                Expr::Asm(asm_block) => visitor.visit_asm_block(asm_block),
+                Expr::Return { expr_opt, .. } => {
+                    if let Some(expr) = expr_opt {
+                        visitor.visit_expr(expr);
+                    }
+                }
+                Expr::If(if_expr) => visitor.visit_if_expr(if_expr),
+                Expr::Match {
+                    value, branches, ..
+                } => {
+                    visitor.visit_expr(value);
+                    for branch in &$($mutability)? branches.inner {
+                        visitor.visit_match_branch(branch);
+                    }
+                }
+                Expr::While {
+                    condition, block, ..
+                } => {
+                    visitor.visit_expr(condition);
+                    visitor.visit_code_block_contents(&$($mutability)? block.inner);
+                }
+                Expr::FuncApp { func, args } => {
+                    visitor.visit_expr(func);
+                    for arg in &$($mutability)? args.inner {
+                        visitor.visit_expr(arg);
+                    }
+                }
+                Expr::Index { target, arg } => {
+                    visitor.visit_expr(target);
+                    visitor.visit_expr(&$($mutability)? arg.inner);
+                }
+                Expr::MethodCall {
+                    target,
+                    path_seg,
+                    contract_args_opt,
+                    args,
+                    ..
+                } => {
+                    visitor.visit_expr(target);
+                    walk_path_expr_segment(visitor, path_seg);
+                    if let Some(contract_args) = contract_args_opt {
+                        walk_struct_fields(visitor, &$($mutability)? contract_args.inner);
+                    }
+                    for arg in &$($mutability)? args.inner {
+                        visitor.visit_expr(arg);
+                    }
+                }
+                Expr::FieldProjection { target, name, .. } => {
+                    visitor.visit_expr(target);
+                    visitor.visit_ident(name);
+                }
+                Expr::TupleFieldProjection { target, .. } => visitor.visit_expr(target),
+                Expr::Ref { expr, .. } | Expr::Deref { expr, .. } | Expr::Not { expr, .. } => {
+                    visitor.visit_expr(expr)
+                }
+                Expr::Mul { lhs, rhs, .. }
+                | Expr::Div { lhs, rhs, .. }
+                | Expr::Pow { lhs, rhs, .. }
+                | Expr::Modulo { lhs, rhs, .. }
+                | Expr::Add { lhs, rhs, .. }
+                | Expr::Sub { lhs, rhs, .. }
+                | Expr::Shl { lhs, rhs, .. }
+                | Expr::Shr { lhs, rhs, .. }
+                | Expr::BitAnd { lhs, rhs, .. }
+                | Expr::BitXor { lhs, rhs, .. }
+                | Expr::BitOr { lhs, rhs, .. }
+                | Expr::Equal { lhs, rhs, .. }
+                | Expr::NotEqual { lhs, rhs, .. }
+                | Expr::LessThan { lhs, rhs, .. }
+                | Expr::GreaterThan { lhs, rhs, .. }
+                | Expr::LessThanEq { lhs, rhs, .. }
+                | Expr::GreaterThanEq { lhs, rhs, .. }
+                | Expr::LogicalAnd { lhs, rhs, .. }
+                | Expr::LogicalOr { lhs, rhs, .. } => {
+                    visitor.visit_expr(lhs);
+                    visitor.visit_expr(rhs);
+                }
+                Expr::Reassignment {
+                    assignable, expr, ..
+                } => {
+                    visitor.visit_assignable(assignable);
+                    visitor.visit_expr(expr);
+                }
+            }
+        }
+
+        pub fn walk_if_expr<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            if_expr: &$($mutability)? IfExpr,
+        ) {
+            match &$($mutability)? if_expr.condition {
+                IfCondition::Expr(condition) => visitor.visit_expr(condition),
+                IfCondition::Let { lhs, rhs, .. } => {
+                    visitor.visit_pattern(lhs);
+                    visitor.visit_expr(rhs);
+                }
+            }
+            visitor.visit_code_block_contents(&$($mutability)? if_expr.then_block.inner);
+            match &$($mutability)? if_expr.else_opt {
+                Some((_, LoopControlFlow::Break(block))) => {
+                    visitor.visit_code_block_contents(&$($mutability)? block.inner)
+                }
+                Some((_, LoopControlFlow::Continue(else_if_expr))) => {
+                    visitor.visit_if_expr(else_if_expr)
+                }
+                None => {}
+            }
+        }
+
+        pub fn walk_match_branch<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            match_branch: &$($mutability)? MatchBranch,
+        ) {
+            visitor.visit_pattern(&$($mutability)? match_branch.pattern);
+            match &$($mutability)? match_branch.kind {
+                MatchBranchKind::Block { block, .. } => {
+                    visitor.visit_code_block_contents(&$($mutability)? block.inner)
+                }
+                MatchBranchKind::Expr { expr, .. } => visitor.visit_expr(expr),
+            }
+        }
+
+        pub fn walk_asm_block<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            asm_block: &$($mutability)? AsmBlock,
+        ) {
+            for register in &$($mutability)? asm_block.registers.inner {
+                visitor.visit_ident(&$($mutability)? register.register);
+                if let Some((_, value)) = &$($mutability)? register.value_opt {
+                    visitor.visit_expr(value);
+                }
+            }
+            if let Some(AsmFinalExpr { register, ty_opt }) =
+                &$($mutability)? asm_block.contents.inner.final_expr_opt
+            {
+                visitor.visit_ident(register);
+                if let Some((_, ty)) = ty_opt {
+                    visitor.visit_ty(ty);
+                }
+            }
+        }
+
+        pub fn walk_assignable<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            assignable: &$($mutability)? Assignable,
+        ) {
+            match assignable {
+                Assignable::Var(name) => visitor.visit_ident(name),
+                Assignable::Index { target, arg } => {
+                    visitor.visit_assignable(target);
+                    visitor.visit_expr(&$($mutability)? arg.inner);
+                }
+                Assignable::FieldProjection { target, name, .. } => {
+                    visitor.visit_assignable(target);
+                    visitor.visit_ident(name);
+                }
+                Assignable::TupleFieldProjection { target, .. } => visitor.visit_assignable(target),
+            }
+        }
+
+        pub fn walk_pattern<V: $trait_name + ?Sized>(
+            visitor: &mut V,
+            pattern: &$($mutability)? Pattern,
+        ) {
+            match pattern {
+                Pattern::Wildcard { .. } | Pattern::Error(_) => {}
+                Pattern::Var { name, .. } => visitor.visit_ident(name),
+                Pattern::Literal(literal) => visitor.visit_literal(literal),
+                Pattern::Constant(path) => visitor.visit_path_expr(path),
+                Pattern::Constructor { path, args } => {
+                    visitor.visit_path_expr(path);
+                    for arg in &$($mutability)? args.inner {
+                        visitor.visit_pattern(arg);
+                    }
+                }
+                Pattern::Struct { path, fields } => {
+                    visitor.visit_path_expr(path);
+                    for field in &$($mutability)? fields.inner {
+                        if let PatternStructField::Field {
+                            field_name,
+                            pattern_opt,
+                        } = field
+                        {
+                            visitor.visit_ident(field_name);
+                            if let Some((_, pattern)) = pattern_opt {
+                                visitor.visit_pattern(pattern);
+                            }
+                        }
+                    }
+                }
+                Pattern::Tuple(patterns) => {
+                    for pattern in &$($mutability)? patterns.inner {
+                        visitor.visit_pattern(pattern);
+                    }
+                }
+            }
+        }
+    };
+}
+
+pub(crate) use define_visitor;
@@ -0,0 +1,16 @@
+use std::{env, process::Command};
+
+fn main() {
+    // The cache of optimized IR is salted with the version of rustc, as a different build of the
+    // compiler may optimize differently.  Cargo rebuilds everything when rustc changes, so this
+    // only needs to run again when it does.
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .unwrap_or_default();
+    println!("cargo:rustc-env=SWAY_RUSTC_VERSION={}", version.trim());
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -1,5 +1,12 @@
+//! Generation of the Fuel JSON ABI of a program.
+//!
+//! When the [`IrAbi`] of the compiled program is given, the functions, logged types and
+//! configurables are limited to those in the IR, so the ABI describes the bytecode which was
+//! produced.  Their types are still found through the typed program, and the messages types are
+//! all taken from it, whether or not their messages remain in the bytecode.
+
 use fuel_abi_types::program_abi;
-use sway_types::integer_bits::IntegerBits;
+use sway_types::{constants::DOC_COMMENT_ATTRIBUTE_NAME, integer_bits::IntegerBits};
 
 use crate::{
     decl_engine::DeclEngine,
@@ -7,13 +14,62 @@ use crate::{
         ty::{TyConstantDeclaration, TyFunctionDeclaration, TyProgram, TyProgramKind},
         CallPath,
     },
-    transform::AttributesMap,
-    TypeArgument, TypeEngine, TypeId, TypeInfo, TypeParameter,
+    transform::{AttributeKind, AttributesMap},
+    AbiEncoding, LogId, TypeArgument, TypeEngine, TypeId, TypeInfo, TypeParameter,
 };
 
+use super::ir_abi::IrAbi;
+
+/// The name of the function attribute recording the encoding of a contract's ABI methods.
+const ABI_ENCODING_ATTRIBUTE_NAME: &str = "abi-encoding";
+
 pub struct JsonAbiContext<'a> {
     pub program: &'a TyProgram,
     pub json_abi_with_callpaths: bool,
+    /// The ABI items of the compiled IR.  When given, only the functions, logged types and
+    /// configurables present in the IR are included, so the ABI matches the bytecode.
+    pub ir_abi: Option<&'a IrAbi>,
+}
+
+impl<'a> JsonAbiContext<'a> {
+    /// The ABI methods to include, in the order they were compiled if the IR is given.
+    fn abi_entries(
+        &self,
+        abi_entries: &'a [TyFunctionDeclaration],
+    ) -> Vec<&'a TyFunctionDeclaration> {
+        match self.ir_abi {
+            Some(ir_abi) => ir_abi
+                .functions
+                .iter()
+                .filter_map(|name| abi_entries.iter().find(|decl| decl.name.as_str() == name))
+                .collect(),
+            None => abi_entries.iter().collect(),
+        }
+    }
+
+    fn logged_types(&self) -> Vec<&'a (LogId, TypeId)> {
+        let logged_types = self.program.logged_types.iter();
+        match self.ir_abi {
+            Some(ir_abi) => logged_types
+                .filter(|(log_id, _)| ir_abi.log_ids.contains(&(**log_id as u64)))
+                .collect(),
+            None => logged_types.collect(),
+        }
+    }
+
+    fn configurables(&self) -> Vec<&'a TyConstantDeclaration> {
+        let configurables = self.program.configurables.iter();
+        match self.ir_abi {
+            Some(ir_abi) => configurables
+                .filter(|decl| {
+                    ir_abi
+                        .configurables
+                        .contains(decl.call_path.suffix.as_str())
+                })
+                .collect(),
+            None => configurables.collect(),
+        }
+    }
 }
 
 pub fn generate_json_abi_program(
@@ -24,10 +80,27 @@ pub fn generate_json_abi_program(
 ) -> program_abi::ProgramABI {
     match &ctx.program.kind {
         TyProgramKind::Contract { abi_entries, .. } => {
-            let functions = abi_entries
-                .iter()
+            let mut functions = ctx
+                .abi_entries(abi_entries)
+                .into_iter()
                 .map(|x| x.generate_json_abi_function(ctx, type_engine, decl_engine, types))
-                .collect();
+                .collect::<Vec<_>>();
+            // Callers need to know how to encode the arguments and decode the results, so a
+            // non-default encoding is recorded as an attribute of every method.
+            let encoding = ctx
+                .ir_abi
+                .map_or(AbiEncoding::Default, |ir_abi| ir_abi.encoding);
+            if encoding != AbiEncoding::Default {
+                for function in &mut functions {
+                    function
+                        .attributes
+                        .get_or_insert_with(Vec::new)
+                        .push(program_abi::Attribute {
+                            name: ABI_ENCODING_ATTRIBUTE_NAME.to_string(),
+                            arguments: vec![encoding.as_str().to_string()],
+                        });
+                }
+            }
             let logged_types = generate_json_logged_types(ctx, type_engine, decl_engine, types);
             let messages_types = generate_json_messages_types(ctx, type_engine, decl_engine, types);
             let configurables = generate_json_configurables(ctx, type_engine, decl_engine, types);
@@ -75,9 +148,8 @@ fn generate_json_logged_types(
     types: &mut Vec<program_abi::TypeDeclaration>,
 ) -> Vec<program_abi::LoggedType> {
     // A list of all `program_abi::TypeDeclaration`s needed for the logged types
-    let logged_types = ctx
-        .program
-        .logged_types
+    let program_logged_types = ctx.logged_types();
+    let logged_types = program_logged_types
         .iter()
         .map(|(_, type_id)| program_abi::TypeDeclaration {
             type_id: type_id.index(),
@@ -103,8 +175,7 @@ fn generate_json_logged_types(
     types.extend(logged_types);
 
     // Generate the JSON data for the logged types
-    ctx.program
-        .logged_types
+    program_logged_types
         .iter()
         .map(|(log_id, type_id)| program_abi::LoggedType {
             log_id: **log_id as u64,
@@ -185,9 +256,8 @@ fn generate_json_configurables(
     types: &mut Vec<program_abi::TypeDeclaration>,
 ) -> Vec<program_abi::Configurable> {
     // A list of all `program_abi::TypeDeclaration`s needed for the configurables types
-    let configurables_types = ctx
-        .program
-        .configurables
+    let program_configurables = ctx.configurables();
+    let configurables_types = program_configurables
         .iter()
         .map(
             |TyConstantDeclaration {
@@ -222,8 +292,7 @@ fn generate_json_configurables(
     types.extend(configurables_types);
 
     // Generate the JSON data for the configurables types
-    ctx.program
-        .configurables
+    program_configurables
         .iter()
         .map(
             |TyConstantDeclaration {
@@ -950,6 +1019,9 @@ impl TyFunctionDeclaration {
     }
 }
 
+/// Returns the attributes of a function in the JSON ABI. The doc comments of the function are
+/// merged into a single `doc-comment` attribute with one argument per line, so that SDKs can
+/// show them as method documentation.
 fn generate_json_abi_attributes_map(
     attr_map: &AttributesMap,
 ) -> Option<Vec<program_abi::Attribute>> {
@@ -959,11 +1031,22 @@ fn generate_json_abi_attributes_map(
         Some(
             attr_map
                 .iter()
-                .flat_map(|(_attr_kind, attrs)| {
-                    attrs.iter().map(|attr| program_abi::Attribute {
-                        name: attr.name.to_string(),
-                        arguments: attr.args.iter().map(|arg| arg.to_string()).collect(),
-                    })
+                .flat_map(|(attr_kind, attrs)| match attr_kind {
+                    AttributeKind::DocComment => vec![program_abi::Attribute {
+                        name: DOC_COMMENT_ATTRIBUTE_NAME.to_string(),
+                        arguments: attr_map
+                            .doc_comment_lines()
+                            .into_iter()
+                            .map(str::to_string)
+                            .collect(),
+                    }],
+                    _ => attrs
+                        .iter()
+                        .map(|attr| program_abi::Attribute {
+                            name: attr.name.to_string(),
+                            arguments: attr.args.iter().map(|arg| arg.to_string()).collect(),
+                        })
+                        .collect(),
                 })
                 .collect(),
         )
@@ -0,0 +1,133 @@
+//! The items of a program's ABI found in its compiled IR.
+//!
+//! Only the entry functions, the log ids and the configurables are taken from the IR, so that the
+//! JSON ABI leaves out what the optimizer removed, such as a log in a branch which was folded away
+//! or a configurable frozen into a constant.  The rest of the ABI still comes from the typed
+//! program: the types are resolved through it as the IR types don't carry their Sway names, and the
+//! messages are all listed as their ids aren't kept as separate values in the IR.
+
+use std::collections::BTreeSet;
+
+use sway_ir::{ConstantValue, Context, FuelVmInstruction, Instruction};
+
+use crate::{metadata::MetadataManager, AbiEncoding};
+
+/// The items of a program's ABI which are present in its compiled IR.
+///
+/// The JSON ABI is generated from these rather than from the typed program alone, so that it
+/// always describes the bytecode which was actually produced.  The types of the items are still
+/// found through the typed program, as the IR types don't carry their Sway names.
+#[derive(Clone, Debug, Default)]
+pub struct IrAbi {
+    /// The names of the entry functions, excluding tests, in the order they were compiled.
+    pub functions: Vec<String>,
+    /// The ids of the types logged by the program.
+    pub log_ids: BTreeSet<u64>,
+    /// The names of the configurables compiled into the program.
+    pub configurables: BTreeSet<String>,
+    /// The encoding of the contract's ABI method arguments and returned data.
+    pub encoding: AbiEncoding,
+}
+
+impl IrAbi {
+    pub(crate) fn from_ir(context: &Context, md_mgr: &mut MetadataManager) -> Self {
+        let mut ir_abi = IrAbi::default();
+        for module in context.module_iter() {
+            for function in module.function_iter(context) {
+                let md = function.get_metadata(context);
+                if function.is_entry(context) && md_mgr.md_to_test_decl_index(context, md).is_none()
+                {
+                    ir_abi.functions.push(function.get_name(context).to_owned());
+                }
+
+                for (_, inst) in function.instruction_iter(context) {
+                    if let Some(Instruction::FuelVm(FuelVmInstruction::Log { log_id, .. })) =
+                        inst.get_instruction(context)
+                    {
+                        if let Some(ConstantValue::Uint(log_id)) =
+                            log_id.get_constant(context).map(|c| &c.value)
+                        {
+                            ir_abi.log_ids.insert(*log_id);
+                        }
+                    }
+                }
+            }
+
+            for (_, value) in module.global_configurable_iter(context) {
+                if let Some(name) =
+                    md_mgr.md_to_config_const_name(context, value.get_metadata(context))
+                {
+                    ir_abi.configurables.insert(name.to_string());
+                }
+            }
+        }
+        ir_abi
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sway_ir::{create_o1_pass_group, register_known_passes, PassManager};
+
+    const IR: &str = r#"
+script {
+    VERBOSE = config bool true, !1
+    LIMIT = config u64 5, !2
+
+    entry fn main() -> u64 {
+        entry():
+        cbr VERBOSE, verbose(), done()
+
+        verbose():
+        v0 = const bool true
+        v1 = const u64 0
+        log bool v0, v1
+        br done()
+
+        done():
+        v2 = const u64 42
+        v3 = const u64 1
+        log u64 v2, v3
+        ret u64 LIMIT
+    }
+}
+
+!1 = config_name "VERBOSE"
+!2 = config_name "LIMIT"
+"#;
+
+    fn ir_abi(ir: &Context) -> IrAbi {
+        IrAbi::from_ir(ir, &mut MetadataManager::default())
+    }
+
+    #[test]
+    fn items_in_the_ir() {
+        let ir = sway_ir::parser::parse(IR).unwrap();
+        let ir_abi = ir_abi(&ir);
+        assert_eq!(ir_abi.functions, vec!["main".to_string()]);
+        assert_eq!(ir_abi.log_ids, BTreeSet::from([0, 1]));
+        assert_eq!(
+            ir_abi.configurables,
+            BTreeSet::from(["LIMIT".to_string(), "VERBOSE".to_string()])
+        );
+    }
+
+    #[test]
+    fn items_removed_from_the_ir_are_left_out() {
+        // Freezing `VERBOSE` to `false` makes its configurable a constant and lets the optimizer
+        // remove the log it guards.
+        let mut ir = sway_ir::parser::parse(IR).unwrap();
+        let module = ir.module_iter().next().unwrap();
+        let values = [("VERBOSE".to_string(), "false".to_string())].into();
+        sway_ir::freeze_configurables(&mut ir, &module, &values).unwrap();
+        let mut pass_mgr = PassManager::default();
+        register_known_passes(&mut pass_mgr);
+        pass_mgr.run(&mut ir, &create_o1_pass_group(false)).unwrap();
+
+        let ir_abi = ir_abi(&ir);
+        assert_eq!(ir_abi.functions, vec!["main".to_string()]);
+        assert_eq!(ir_abi.log_ids, BTreeSet::from([1]));
+        assert_eq!(ir_abi.configurables, BTreeSet::from(["LIMIT".to_string()]));
+    }
+}
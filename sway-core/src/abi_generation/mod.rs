@@ -1,2 +1,3 @@
 pub mod evm_json_abi;
 pub mod fuel_json_abi;
+pub mod ir_abi;
@@ -709,7 +709,7 @@ impl<'ir> EvmAsmBuilder<'ir> {
     }
 
     pub(super) fn insert_block_label(&mut self, block: Block) {
-        if &block.get_label(self.context) != "entry" {
+        if block.get_label(self.context) != "entry" {
             let label = self.block_to_label(&block);
             self.cur_section
                 .as_mut()
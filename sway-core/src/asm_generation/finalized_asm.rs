@@ -4,10 +4,12 @@ use super::{
     fuel::{checks, data_section::DataSection},
     ProgramABI, ProgramKind,
 };
+use crate::abi_generation::ir_abi::IrAbi;
 use crate::asm_lang::allocated_ops::{AllocatedOp, AllocatedOpcode};
 use crate::decl_engine::DeclRefFunction;
 use crate::error::*;
-use crate::source_map::SourceMap;
+use crate::source_map::{SourceMap, VariableLocation};
+use crate::EmittedArtifact;
 
 use etk_asm::asm::Assembler;
 use sway_error::error::CompileError;
@@ -25,6 +27,18 @@ pub struct FinalizedAsm {
     pub program_kind: ProgramKind,
     pub entries: Vec<FinalizedEntry>,
     pub abi: Option<ProgramABI>,
+    /// The ABI items present in the final IR, from which the JSON ABI is generated.
+    pub ir_abi: IrAbi,
+    /// Intermediate artifacts captured along the way, as requested by the `BuildConfig`.
+    pub emitted: Vec<EmittedArtifact>,
+    /// The coverage counters inserted into the program, if requested by the `BuildConfig`.
+    pub coverage_points: Vec<CoveragePoint>,
+    /// The amount of bytecode generated for each function.
+    pub function_sizes: Vec<FunctionSize>,
+    /// Where the user variables of each function are held, which is added to the source map.
+    pub variables: Vec<VariableLocation>,
+    /// The names of the IR passes run over the program, in order.
+    pub pass_pipeline: Vec<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -40,11 +54,66 @@ pub struct FinalizedEntry {
     pub test_decl_ref: Option<DeclRefFunction>,
 }
 
+/// The amount of bytecode generated for a single function.
+///
+/// Generic functions are monomorphised and so have an entry per instantiation, each sharing the
+/// span of the declaration.  Functions which were inlined have no entry of their own, but the code
+/// inlined from them is listed in the `inlined` sizes of their callers.
+#[derive(Clone, Debug)]
+pub struct FunctionSize {
+    /// The name of the function in the IR.
+    pub name: String,
+    /// The span of the function declaration, if known.
+    pub span: Option<Span>,
+    /// The size of the function's bytecode, including the code inlined into it.
+    pub size_in_bytes: u64,
+    /// The gas spent by a call to the function according to `sway_ir::estimate_gas`.
+    pub estimated_gas: u64,
+    /// The code inlined into the function, per inlined function.
+    pub inlined: Vec<InlinedSize>,
+}
+
+/// The amount of bytecode inlined from a function into one of its callers, found from the
+/// `sway_ir::InlinedFrom` metadata of the inlined instructions.
+#[derive(Clone, Debug)]
+pub struct InlinedSize {
+    /// The name of the inlined function in the IR.
+    pub name: String,
+    /// The span of the inlined function declaration, if known.
+    pub span: Option<Span>,
+    /// The size of the bytecode inlined from the function.
+    pub size_in_bytes: u64,
+}
+
+/// A coverage counter, logged with `sway_ir::COVERAGE_LOG_ID` whenever its block is executed.
+#[derive(Clone, Debug)]
+pub struct CoveragePoint {
+    /// The value logged by the counter.
+    pub id: u64,
+    /// The source of the instrumented block, if known.
+    pub span: Option<Span>,
+    /// Set if the block is an arm of a conditional branch.
+    pub branch: Option<CoverageBranch>,
+}
+
+/// An arm of a conditional branch, as found by a [CoveragePoint].
+#[derive(Clone, Debug)]
+pub struct CoverageBranch {
+    /// Identifies the conditional branch, shared by both of its arms.
+    pub id: u64,
+    /// Whether this is the arm taken when the condition is true.
+    pub is_true_arm: bool,
+    /// The source of the condition, if known.
+    pub span: Option<Span>,
+}
+
 /// The bytecode for a sway program as well as the byte offsets of configuration-time constants in
 /// the bytecode.
 pub struct CompiledBytecode {
     pub bytecode: Vec<u8>,
     pub config_const_offsets: BTreeMap<String, u64>,
+    /// The size of the data section, which is appended to the end of the bytecode.
+    pub data_section_size: u64,
 }
 
 impl FinalizedAsm {
@@ -54,6 +123,9 @@ impl FinalizedAsm {
     ) -> CompileResult<CompiledBytecode> {
         match &self.program_section {
             InstructionSet::Fuel { ops } => {
+                for variable in &self.variables {
+                    source_map.insert_variable(variable.clone());
+                }
                 to_bytecode_mut(ops, &mut self.data_section, source_map)
             }
             InstructionSet::Evm { ops } => {
@@ -68,6 +140,7 @@ impl FinalizedAsm {
                         CompiledBytecode {
                             bytecode: assembler.take(),
                             config_const_offsets: BTreeMap::new(),
+                            data_section_size: 0,
                         },
                         vec![],
                         vec![],
@@ -78,6 +151,7 @@ impl FinalizedAsm {
                 CompiledBytecode {
                     bytecode: ops.to_bytecode().into(),
                     config_const_offsets: Default::default(),
+                    data_section_size: 0,
                 },
                 vec![],
                 vec![],
@@ -177,6 +251,7 @@ fn to_bytecode_mut(
         .collect::<BTreeMap<String, u64>>();
 
     let mut data_section = data_section.serialize_to_bytes();
+    let data_section_size = data_section.len() as u64;
 
     buf.append(&mut data_section);
 
@@ -184,6 +259,7 @@ fn to_bytecode_mut(
         CompiledBytecode {
             bytecode: buf,
             config_const_offsets: config_offsets,
+            data_section_size,
         },
         vec![],
         errors,
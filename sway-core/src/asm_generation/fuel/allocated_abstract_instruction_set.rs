@@ -510,45 +510,12 @@ impl AllocatedAbstractInstructionSet {
                     cur_basic_block = Some((cur_lab, op_idx, cur_offset));
                     furthest_offset = std::cmp::max(furthest_offset, cur_offset);
                 }
-
-                // A special case for LWDataId which may be 1 or 2 ops, depending on the source size.
-                Either::Left(AllocatedOpcode::LWDataId(_, ref data_id)) => {
-                    let has_copy_type = data_section.has_copy_type(data_id).expect(
-                        "Internal miscalculation in data section -- \
-                        data id did not match up to any actual data",
-                    );
-                    cur_offset += if has_copy_type { 1 } else { 2 };
-                }
-
-                // Another special case for the blob opcode, used for testing.
-                Either::Left(AllocatedOpcode::BLOB(ref count)) => cur_offset += count.value as u64,
-
-                // These ops will end up being exactly one op, so the cur_offset goes up one.
-                Either::Right(
-                    Jump(..) | JumpIfNotZero(..) | Call(..) | MoveAddress(..) | LoadLabel(..),
-                )
-                | Either::Left(_) => {
-                    cur_offset += 1;
-                }
-
                 Either::Right(JumpIfNotEq(_, _, lab)) => {
                     jnei_labels.insert(lab);
-                    cur_offset += 1;
-                }
-
-                Either::Right(Comment) => (),
-
-                Either::Right(DataSectionOffsetPlaceholder) => {
-                    // If the placeholder is 32 bits, this is 1. if 64, this should be 2. We use LW
-                    // to load the data, which loads a whole word, so for now this is 2.
-                    cur_offset += 2
                 }
-
-                Either::Right(PushAll(_)) | Either::Right(PopAll(_)) => unreachable!(
-                    "fix me, pushall and popall don't really belong in control flow ops \
-                        since they're not about control flow"
-                ),
+                _ => (),
             }
+            cur_offset += op_len(op, data_section);
         }
 
         // Don't forget the final block.
@@ -699,3 +666,45 @@ impl std::fmt::Display for AllocatedAbstractInstructionSet {
         )
     }
 }
+
+/// The number of final ops, each of 4 bytes, an allocated op will be realized as.
+pub(crate) fn op_len(op: &AllocatedAbstractOp, data_section: &DataSection) -> u64 {
+    use ControlFlowOp::*;
+
+    match op.opcode {
+        // Labels and comments are dropped.
+        Either::Right(Label(_) | Comment) => 0,
+
+        // A special case for LWDataId which may be 1 or 2 ops, depending on the source size.
+        Either::Left(AllocatedOpcode::LWDataId(_, ref data_id)) => {
+            let has_copy_type = data_section.has_copy_type(data_id).expect(
+                "Internal miscalculation in data section -- \
+                data id did not match up to any actual data",
+            );
+            if has_copy_type {
+                1
+            } else {
+                2
+            }
+        }
+
+        // Another special case for the blob opcode, used for testing.
+        Either::Left(AllocatedOpcode::BLOB(ref count)) => count.value as u64,
+
+        // These ops will end up being exactly one op.
+        Either::Right(
+            Jump(..) | JumpIfNotEq(..) | JumpIfNotZero(..) | Call(..) | MoveAddress(..)
+            | LoadLabel(..),
+        )
+        | Either::Left(_) => 1,
+
+        // If the placeholder is 32 bits, this is 1. if 64, this should be 2. We use LW to load the
+        // data, which loads a whole word, so for now this is 2.
+        Either::Right(DataSectionOffsetPlaceholder) => 2,
+
+        Either::Right(PushAll(_)) | Either::Right(PopAll(_)) => unreachable!(
+            "fix me, pushall and popall don't really belong in control flow ops \
+                since they're not about control flow"
+        ),
+    }
+}
@@ -132,7 +132,7 @@ impl<'ir> FuelAsmBuilder<'ir> {
     }
 
     pub(super) fn insert_block_label(&mut self, block: Block) {
-        if &block.get_label(self.context) != "entry" {
+        if block.get_label(self.context) != "entry" {
             let label = self.block_to_label(&block);
             self.cur_bytecode.push(Op::unowned_jump_label(label))
         }
@@ -213,6 +213,9 @@ impl<'ir> FuelAsmBuilder<'ir> {
                     aggregate, indices, ..
                 } => self.compile_extract_value(instr_val, aggregate, indices),
                 Instruction::FuelVm(fuel_vm_instr) => match fuel_vm_instr {
+                    // Checkpoints which weren't lowered by the `gas_checkpoints` pass are
+                    // disabled and generate no code.
+                    FuelVmInstruction::GasCheckpoint(_) => (),
                     FuelVmInstruction::GetStorageKey => {
                         check!(
                             self.compile_get_storage_key(instr_val),
@@ -12,6 +12,7 @@ use crate::{
     error::*,
     fuel_prelude::fuel_asm::GTFArgs,
     size_bytes_in_words, size_bytes_round_up_to_word_alignment,
+    source_map::{StackSlot, VariableLocation},
 };
 
 use sway_ir::*;
@@ -158,10 +159,7 @@ impl<'ir> FuelAsmBuilder<'ir> {
             )),
             _ => None,
         };
-        let comment = format!(
-            "--- start of function: {} ---",
-            function.get_name(self.context)
-        );
+        let comment = function_start_comment(function.get_name(self.context));
         self.cur_bytecode.push(match span {
             Some(span) => Op::jump_label_comment(start_label, span, comment),
             None => Op::unowned_jump_label_comment(start_label, comment),
@@ -206,6 +204,7 @@ impl<'ir> FuelAsmBuilder<'ir> {
         }
 
         self.init_locals(function);
+        self.record_debug_variables(function);
 
         // Compile instructions.
         let mut warnings = Vec::new();
@@ -216,7 +215,28 @@ impl<'ir> FuelAsmBuilder<'ir> {
         let po = sway_ir::dominator::compute_post_order(self.context, &function);
         for block in po.po_to_block.iter().rev() {
             self.insert_block_label(*block);
+
+            // Mark where the code inlined from another function starts and ends, so that its size
+            // may be attributed to that function.  Labels end any inlined code before them.
+            let mut inlined_from = None;
             for instr_val in block.instruction_iter(self.context) {
+                let instr_inlined_from = instr_val.get_inlined_from(self.context);
+                if instr_inlined_from != inlined_from {
+                    let owning_span = instr_inlined_from
+                        .as_ref()
+                        .and_then(|from| from.span.as_ref())
+                        .and_then(|span| self.md_mgr.source_span_to_span(self.context, span));
+                    self.cur_bytecode.push(Op {
+                        opcode: Either::Right(OrganizationalOp::Comment),
+                        comment: inlined_code_comment(
+                            instr_inlined_from
+                                .as_ref()
+                                .map(|from| from.function.as_str()),
+                        ),
+                        owning_span,
+                    });
+                    inlined_from = instr_inlined_from;
+                }
                 check!(
                     self.compile_instruction(&instr_val, func_is_entry),
                     return err(warnings, errors),
@@ -687,7 +707,131 @@ impl<'ir> FuelAsmBuilder<'ir> {
         }
     }
 
+    /// Note where each user variable of `function` is held, in comments which are gathered into the
+    /// source map once the final position of the function is known.  Variables which aren't held in
+    /// a local of their own, e.g., because their values were promoted to registers, are noted as
+    /// optimized out.
+    fn record_debug_variables(&mut self, function: Function) {
+        let locals_size = self.locals_ctxs.last().map_or(0, |(size, _)| *size);
+        let mut variables = Vec::new();
+        for (_block, value) in function.instruction_iter(self.context) {
+            let slot = match value.get_instruction(self.context) {
+                Some(Instruction::GetLocal(local_var)) => match self.ptr_map.get(local_var) {
+                    Some(Storage::Stack(word_offs)) => Some(StackSlot {
+                        offset: word_offs * 8,
+                        size: ir_type_size_in_bytes(
+                            self.context,
+                            &local_var.get_type(self.context),
+                        ),
+                        locals_size,
+                    }),
+                    _ => None,
+                },
+                _ => None,
+            };
+            for variable in value.get_debug_variables(self.context) {
+                variables.push((variable, slot));
+            }
+        }
+        for variable in function.get_debug_variables(self.context) {
+            variables.push((variable, None));
+        }
+        for (variable, slot) in variables {
+            self.cur_bytecode
+                .push(Op::new_comment(debug_variable_comment(&VariableLocation {
+                    name: variable.name,
+                    ty: variable.ty,
+                    opcodes: 0..0,
+                    slot,
+                })));
+        }
+    }
+
     pub(super) fn locals_base_reg(&self) -> &VirtualRegister {
         &self.locals_ctxs.last().expect("No locals").1
     }
 }
+
+const FUNCTION_START_COMMENT_PREFIX: &str = "--- start of function: ";
+const FUNCTION_START_COMMENT_SUFFIX: &str = " ---";
+
+/// The comment attached to the label at the start of each function.
+fn function_start_comment(name: &str) -> String {
+    format!("{FUNCTION_START_COMMENT_PREFIX}{name}{FUNCTION_START_COMMENT_SUFFIX}")
+}
+
+/// Recover the function name from a comment made by `function_start_comment()`.  The allocated ops
+/// don't otherwise record which function they belong to, so this is how code size is attributed to
+/// functions.
+pub(crate) fn function_name_from_start_comment(comment: &str) -> Option<&str> {
+    comment
+        .strip_prefix(FUNCTION_START_COMMENT_PREFIX)?
+        .strip_suffix(FUNCTION_START_COMMENT_SUFFIX)
+}
+
+const INLINED_CODE_COMMENT_PREFIX: &str = "--- inlined from: ";
+const INLINED_CODE_COMMENT_SUFFIX: &str = " ---";
+const INLINED_CODE_END_COMMENT: &str = "--- end of inlined code ---";
+
+/// The comment marking the start of the code inlined from the function `name`, or the end of any
+/// inlined code if `None`.  The span of the comment is that of the inlined function.
+fn inlined_code_comment(name: Option<&str>) -> String {
+    match name {
+        Some(name) => format!("{INLINED_CODE_COMMENT_PREFIX}{name}{INLINED_CODE_COMMENT_SUFFIX}"),
+        None => INLINED_CODE_END_COMMENT.to_owned(),
+    }
+}
+
+/// Recover the inlined function from a comment made by `inlined_code_comment()`, as `Some(None)`
+/// for the end of the inlined code and `None` if the comment isn't such a marker.
+pub(crate) fn inlined_function_from_comment(comment: &str) -> Option<Option<&str>> {
+    if comment == INLINED_CODE_END_COMMENT {
+        return Some(None);
+    }
+    comment
+        .strip_prefix(INLINED_CODE_COMMENT_PREFIX)?
+        .strip_suffix(INLINED_CODE_COMMENT_SUFFIX)
+        .map(Some)
+}
+
+const DEBUG_VARIABLE_COMMENT_PREFIX: &str = "debug variable ";
+
+/// The comment noting where a user variable is held, made by `record_debug_variables()`, e.g.,
+/// `debug variable 8 8 24 x: u64` for a variable at offset 8 among 24 bytes of locals.
+fn debug_variable_comment(variable: &VariableLocation) -> String {
+    let slot = match variable.slot {
+        Some(StackSlot {
+            offset,
+            size,
+            locals_size,
+        }) => format!("{offset} {size} {locals_size}"),
+        None => "- - -".to_owned(),
+    };
+    format!(
+        "{DEBUG_VARIABLE_COMMENT_PREFIX}{slot} {}: {}",
+        variable.name, variable.ty
+    )
+}
+
+/// Recover a variable from a comment made by `debug_variable_comment()`.  Its `opcodes` are left
+/// empty, to be filled in with those of its function.
+pub(crate) fn variable_from_debug_comment(comment: &str) -> Option<VariableLocation> {
+    let mut parts = comment
+        .strip_prefix(DEBUG_VARIABLE_COMMENT_PREFIX)?
+        .splitn(4, ' ');
+    let slot = match (parts.next()?, parts.next()?, parts.next()?) {
+        ("-", "-", "-") => None,
+        (offset, size, locals_size) => Some(StackSlot {
+            offset: offset.parse().ok()?,
+            size: size.parse().ok()?,
+            locals_size: locals_size.parse().ok()?,
+        }),
+    };
+    let (name, ty) = parts.next()?.split_once(": ")?;
+    Some(VariableLocation {
+        name: name.to_owned(),
+        ty: ty.to_owned(),
+        opcodes: 0..0,
+        slot,
+    })
+}
@@ -6,6 +6,5 @@ pub(super) mod abstract_instruction_set;
 pub(super) mod allocated_abstract_instruction_set;
 pub(super) mod checks;
 pub(super) mod fuel_asm_builder;
+pub(super) mod functions;
 pub(super) mod register_sequencer;
-
-mod functions;
@@ -11,6 +11,10 @@ mod instruction_set;
 mod programs;
 
 mod finalized_asm;
-pub use finalized_asm::{CompiledBytecode, FinalizedAsm, FinalizedEntry};
+pub use finalized_asm::{
+    CompiledBytecode, CoverageBranch, CoveragePoint, FinalizedAsm, FinalizedEntry, FunctionSize,
+    InlinedSize,
+};
 
+pub(crate) use instruction_set::InstructionSet;
 pub(crate) use programs::ProgramKind;
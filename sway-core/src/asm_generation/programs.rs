@@ -9,8 +9,10 @@ use super::fuel::{
 };
 
 use crate::{
+    asm_generation::FunctionSize,
     asm_lang::{allocated_ops::AllocatedOp, Label},
     decl_engine::DeclRefFunction,
+    source_map::VariableLocation,
 };
 
 type SelectorOpt = Option<[u8; 4]>;
@@ -64,6 +66,8 @@ pub(super) enum FinalProgram {
         data_section: DataSection,
         ops: Vec<AllocatedOp>,
         entries: Vec<(SelectorOpt, ImmOffset, FnName, Option<DeclRefFunction>)>,
+        function_sizes: Vec<FunctionSize>,
+        variables: Vec<VariableLocation>,
     },
     Evm {
         ops: Vec<etk_asm::ops::AbstractOp>,
@@ -1,9 +1,57 @@
 use super::{AllocatedProgram, FinalProgram};
 
-use crate::asm_generation::fuel::allocated_abstract_instruction_set::AllocatedAbstractInstructionSet;
+use crate::{
+    asm_generation::{
+        fuel::{
+            allocated_abstract_instruction_set::{op_len, AllocatedAbstractInstructionSet},
+            data_section::DataSection,
+            functions::{
+                function_name_from_start_comment, inlined_function_from_comment,
+                variable_from_debug_comment,
+            },
+        },
+        FunctionSize, InlinedSize,
+    },
+    asm_lang::{AllocatedAbstractOp, ControlFlowOp, Label},
+    source_map::VariableLocation,
+};
+
+use either::Either;
+use sway_types::Span;
 
 impl AllocatedProgram {
     pub(crate) fn into_final_program(mut self) -> Result<FinalProgram, crate::CompileError> {
+        // Find the label which starts each function, so we can attribute code size to them below,
+        // along with the variables noted within each function, which are located once the
+        // function's final position is known, and the code inlined into each function.
+        let function_starts = self
+            .functions
+            .iter()
+            .filter_map(|function| {
+                let (label, name, span) = function.ops.iter().find_map(|op| match op.opcode {
+                    Either::Right(ControlFlowOp::Label(label)) => {
+                        function_name_from_start_comment(&op.comment)
+                            .map(|name| (label, name.to_owned(), op.owning_span.clone()))
+                    }
+                    _ => None,
+                })?;
+                let variables = function
+                    .ops
+                    .iter()
+                    .filter(|op| matches!(op.opcode, Either::Right(ControlFlowOp::Comment)))
+                    .filter_map(|op| variable_from_debug_comment(&op.comment))
+                    .collect::<Vec<_>>();
+                let inlined = inlined_sizes(&function.ops, &self.data_section);
+                Some((label, name, span, variables, inlined))
+            })
+            .collect::<Vec<(
+                Label,
+                String,
+                Option<Span>,
+                Vec<VariableLocation>,
+                Vec<InlinedSize>,
+            )>>();
+
         // Concat the prologue and all the functions together.
         let abstract_ops = AllocatedAbstractInstructionSet {
             ops: std::iter::once(self.prologue.ops)
@@ -17,6 +65,49 @@ impl AllocatedProgram {
             .realize_labels(&mut self.data_section)?;
         let ops = realized_ops.pad_to_even();
 
+        // Each basic block is attributed to the function which starts closest before it.  Blocks
+        // may be moved by far jump relocation but this is rare enough not to worry about.
+        let mut function_offsets = function_starts
+            .into_iter()
+            .filter_map(|(label, name, span, variables, inlined)| {
+                label_offsets
+                    .get(&label)
+                    .map(|block| (block.offs, name, span, variables, inlined))
+            })
+            .collect::<Vec<_>>();
+        function_offsets.sort_by_key(|(offs, ..)| *offs);
+        let mut function_sizes = function_offsets
+            .iter()
+            .map(|(_, name, span, _, inlined)| FunctionSize {
+                name: name.clone(),
+                span: span.clone(),
+                size_in_bytes: 0,
+                estimated_gas: 0,
+                inlined: inlined.clone(),
+            })
+            .collect::<Vec<_>>();
+        let mut function_ends = function_offsets
+            .iter()
+            .map(|(offs, ..)| *offs)
+            .collect::<Vec<_>>();
+        for block in label_offsets.values() {
+            let idx = function_offsets.partition_point(|(offs, ..)| *offs <= block.offs);
+            if idx > 0 {
+                function_sizes[idx - 1].size_in_bytes += block.final_len * 4;
+                function_ends[idx - 1] = function_ends[idx - 1].max(block.offs + block.final_len);
+            }
+        }
+        let variables = function_offsets
+            .into_iter()
+            .zip(function_ends)
+            .flat_map(|((offs, _, _, variables, _), end)| {
+                variables.into_iter().map(move |variable| VariableLocation {
+                    opcodes: offs as usize..end as usize,
+                    ..variable
+                })
+            })
+            .collect();
+
         // Collect the entry point offsets.
         let entries = self
             .entries
@@ -35,10 +126,50 @@ impl AllocatedProgram {
             data_section: self.data_section,
             ops,
             entries,
+            function_sizes,
+            variables,
         })
     }
 }
 
+/// Sum the size of the code between the markers made by `inlined_code_comment()` per inlined
+/// function, in the order they were first inlined.  Like the basic blocks above, the sizes are
+/// those before any far jump relocation.
+fn inlined_sizes(ops: &[AllocatedAbstractOp], data_section: &DataSection) -> Vec<InlinedSize> {
+    let mut sizes: Vec<InlinedSize> = Vec::new();
+    let mut current = None;
+    for op in ops {
+        match op.opcode {
+            // Inlined code never continues past a label.
+            Either::Right(ControlFlowOp::Label(_)) => current = None,
+            Either::Right(ControlFlowOp::Comment) => {
+                if let Some(function) = inlined_function_from_comment(&op.comment) {
+                    current = function.map(|name| {
+                        sizes
+                            .iter()
+                            .position(|size| size.name == name)
+                            .unwrap_or_else(|| {
+                                sizes.push(InlinedSize {
+                                    name: name.to_owned(),
+                                    span: op.owning_span.clone(),
+                                    size_in_bytes: 0,
+                                });
+                                sizes.len() - 1
+                            })
+                    });
+                }
+            }
+            _ => {
+                if let Some(idx) = current {
+                    sizes[idx].size_in_bytes += op_len(op, data_section) * 4;
+                }
+            }
+        }
+    }
+    sizes.retain(|size| size.size_in_bytes > 0);
+    sizes
+}
+
 impl std::fmt::Display for AllocatedProgram {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, ";; {:?}", self.kind)?;
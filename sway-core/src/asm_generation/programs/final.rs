@@ -1,6 +1,7 @@
 use super::FinalProgram;
 
 use crate::{
+    abi_generation::ir_abi::IrAbi,
     asm_generation::{
         fuel::data_section::DataSection, instruction_set::InstructionSet, ProgramABI,
     },
@@ -15,6 +16,8 @@ impl FinalProgram {
                 data_section,
                 ops,
                 entries,
+                function_sizes,
+                variables,
             } => FinalizedAsm {
                 data_section,
                 program_section: InstructionSet::Fuel { ops },
@@ -29,6 +32,12 @@ impl FinalProgram {
                     })
                     .collect(),
                 abi: None,
+                ir_abi: IrAbi::default(),
+                emitted: vec![],
+                coverage_points: vec![],
+                function_sizes,
+                variables,
+                pass_pipeline: vec![],
             },
             FinalProgram::Evm { ops, abi } => FinalizedAsm {
                 data_section: DataSection {
@@ -38,6 +47,12 @@ impl FinalProgram {
                 program_kind: super::ProgramKind::Script,
                 entries: vec![],
                 abi: Some(ProgramABI::Evm(abi)),
+                ir_abi: IrAbi::default(),
+                emitted: vec![],
+                coverage_points: vec![],
+                function_sizes: vec![],
+                variables: vec![],
+                pass_pipeline: vec![],
             },
             FinalProgram::MidenVM { ops } => FinalizedAsm {
                 data_section: DataSection {
@@ -48,6 +63,12 @@ impl FinalProgram {
                 program_kind: super::ProgramKind::Script,
                 entries: vec![],
                 abi: None, /* TODO? */
+                ir_abi: IrAbi::default(),
+                emitted: vec![],
+                coverage_points: vec![],
+                function_sizes: vec![],
+                variables: vec![],
+                pass_pipeline: vec![],
             },
         }
     }
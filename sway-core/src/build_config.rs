@@ -1,6 +1,7 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc};
 
 use serde::{Deserialize, Serialize};
+use sway_types::Span;
 
 #[derive(
     Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize, clap::ValueEnum,
@@ -12,6 +13,48 @@ pub enum BuildTarget {
     MidenVM,
 }
 
+/// The optimisation level to build with, selecting the IR pass pipeline to be run.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum OptLevel {
+    /// Only run the passes required to produce code.
+    #[serde(rename = "0")]
+    Opt0,
+    #[default]
+    #[serde(rename = "1")]
+    Opt1,
+    /// Spend more time optimising, favouring runtime gas.
+    #[serde(rename = "2")]
+    Opt2,
+    /// Favour bytecode size.
+    #[serde(rename = "s")]
+    OptSize,
+    /// Favour bytecode size above all else.
+    #[serde(rename = "z")]
+    OptMinSize,
+}
+
+/// How the arguments and returned data of a contract's ABI methods are encoded.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AbiEncoding {
+    /// The arguments are laid out as they are in memory, with every scalar taking a whole word.
+    #[default]
+    Default,
+    /// Scalars take only the bytes they need and dynamic data is prefixed with its length.  See
+    /// `sway_ir::lower_packed_abi`.
+    Packed,
+}
+
+impl AbiEncoding {
+    /// The name of the encoding as it's recorded in the JSON ABI.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AbiEncoding::Default => "default",
+            AbiEncoding::Packed => "packed",
+        }
+    }
+}
+
 /// Configuration for the overall build and compilation process.
 #[derive(Clone)]
 pub struct BuildConfig {
@@ -24,7 +67,58 @@ pub struct BuildConfig {
     pub(crate) print_intermediate_asm: bool,
     pub(crate) print_finalized_asm: bool,
     pub(crate) print_ir: bool,
+    pub(crate) print_ir_memory: bool,
+    pub(crate) print_pass_statistics: bool,
     pub(crate) include_tests: bool,
+    pub(crate) emit: EmitConfig,
+    pub(crate) opt_level: OptLevel,
+    pub(crate) passes: Option<Vec<String>>,
+    pub(crate) instrument_coverage: bool,
+    pub(crate) gas_checkpoints: bool,
+    pub(crate) rewrite_rules: Option<PathBuf>,
+    pub(crate) freeze_configurables: BTreeMap<String, String>,
+    pub(crate) abi_encoding: AbiEncoding,
+    pub(crate) optimization_fuel: Option<u64>,
+    pub(crate) ir_cache_dir: Option<PathBuf>,
+}
+
+/// Intermediate artifacts to capture during compilation, in addition to the final bytecode.
+#[derive(Clone, Debug, Default)]
+pub struct EmitConfig {
+    /// Capture the IR once all optimisation passes have run.
+    pub ir: bool,
+    /// Capture the finalized ASM.
+    pub asm: bool,
+    /// Capture the IR immediately before each run of the named passes.
+    pub before_passes: Vec<String>,
+    /// Capture the IR immediately after each run of the named passes.
+    pub after_passes: Vec<String>,
+    /// Render the IR explorer, an HTML bundle showing each function's control flow graph,
+    /// dominator tree and IR after every pass, cross-linked with the source and the ASM.
+    pub explorer: bool,
+    /// Capture the IR of the function declared at the given span before and after each pass
+    /// which changes it, along with its final IR and the ASM generated for it.
+    pub function: Option<Span>,
+}
+
+impl EmitConfig {
+    /// Whether any artifacts have been requested at all.
+    pub fn is_empty(&self) -> bool {
+        !self.ir
+            && !self.asm
+            && !self.explorer
+            && self.before_passes.is_empty()
+            && self.after_passes.is_empty()
+            && self.function.is_none()
+    }
+}
+
+/// An intermediate artifact captured during compilation as requested by an [EmitConfig].
+#[derive(Clone, Debug)]
+pub struct EmittedArtifact {
+    /// The path of the artifact relative to the emit directory. E.g. `passes/03-after-inline.ir`.
+    pub path: PathBuf,
+    pub contents: String,
 }
 
 impl BuildConfig {
@@ -65,7 +159,19 @@ impl BuildConfig {
             print_intermediate_asm: false,
             print_finalized_asm: false,
             print_ir: false,
+            print_ir_memory: false,
+            print_pass_statistics: false,
             include_tests: false,
+            emit: EmitConfig::default(),
+            opt_level: OptLevel::default(),
+            passes: None,
+            instrument_coverage: false,
+            gas_checkpoints: false,
+            rewrite_rules: None,
+            freeze_configurables: BTreeMap::new(),
+            abi_encoding: AbiEncoding::default(),
+            optimization_fuel: None,
+            ir_cache_dir: None,
         }
     }
 
@@ -97,6 +203,24 @@ impl BuildConfig {
         }
     }
 
+    /// Whether to print the memory used by the IR once it's generated, after each pass and once
+    /// it's optimised.  See `sway_ir::Context::memory_report`.
+    pub fn print_ir_memory(self, a: bool) -> Self {
+        Self {
+            print_ir_memory: a,
+            ..self
+        }
+    }
+
+    /// Whether to print the time taken and changes made by each IR pass once optimisation is
+    /// done.  See `sway_ir::PassManager::statistics_report`.
+    pub fn print_pass_statistics(self, a: bool) -> Self {
+        Self {
+            print_pass_statistics: a,
+            ..self
+        }
+    }
+
     /// Whether or not to include test functions in parsing, type-checking and codegen.
     ///
     /// This should be set to `true` by invocations like `forc test` or `forc check --tests`.
@@ -109,6 +233,107 @@ impl BuildConfig {
         }
     }
 
+    /// The set of intermediate artifacts to capture during compilation.
+    ///
+    /// Default: nothing is emitted.
+    pub fn emit(self, emit: EmitConfig) -> Self {
+        Self { emit, ..self }
+    }
+
+    /// The optimisation level, which selects the preset IR pass pipeline.
+    ///
+    /// Default: `OptLevel::Opt1`
+    pub fn opt_level(self, opt_level: OptLevel) -> Self {
+        Self { opt_level, ..self }
+    }
+
+    /// An explicit list of IR passes to run, in order, in place of the `opt_level` preset.  Each
+    /// entry may be a comma separated pipeline, which may name a level such as `O1` for its passes.
+    ///
+    /// Default: `None`
+    pub fn passes(self, passes: Option<Vec<String>>) -> Self {
+        Self { passes, ..self }
+    }
+
+    /// Whether or not to instrument every block with a coverage counter.  The counters are
+    /// described by the `coverage_points` of the resulting `FinalizedAsm`.
+    ///
+    /// Default: `false`
+    pub fn instrument_coverage(self, instrument_coverage: bool) -> Self {
+        Self {
+            instrument_coverage,
+            ..self
+        }
+    }
+
+    /// Whether or not to lower the gas checkpoints marked by `__gas_checkpoint` to logs of the
+    /// remaining gas.  Otherwise they generate no code.
+    ///
+    /// Default: `false`
+    pub fn gas_checkpoints(self, gas_checkpoints: bool) -> Self {
+        Self {
+            gas_checkpoints,
+            ..self
+        }
+    }
+
+    /// A file of rewrite rules for the `rewrite` IR pass, which is run after the `opt_level` preset
+    /// when they're given.  See `sway_ir::parse_rules` for the format of the file.
+    ///
+    /// Default: `None`
+    pub fn rewrite_rules(self, rewrite_rules: Option<PathBuf>) -> Self {
+        Self {
+            rewrite_rules,
+            ..self
+        }
+    }
+
+    /// Values for `configurable` constants, by name, which are compiled in as constants rather
+    /// than left patchable in the data section.  See `sway_ir::freeze_configurables` for the
+    /// format of the values.
+    ///
+    /// Default: empty
+    pub fn freeze_configurables(self, freeze_configurables: BTreeMap<String, String>) -> Self {
+        Self {
+            freeze_configurables,
+            ..self
+        }
+    }
+
+    /// The encoding of a contract's ABI method arguments.  It has no effect on other programs.
+    ///
+    /// Default: `AbiEncoding::Default`
+    pub fn abi_encoding(self, abi_encoding: AbiEncoding) -> Self {
+        Self {
+            abi_encoding,
+            ..self
+        }
+    }
+
+    /// The number of changes each iterative IR pass may make to a function before giving up on
+    /// it, bounding the time spent optimising very large functions.  A warning is emitted for
+    /// each function left less optimised than it could be.
+    ///
+    /// Default: `None`, meaning no limit
+    pub fn optimization_fuel(self, optimization_fuel: Option<u64>) -> Self {
+        Self {
+            optimization_fuel,
+            ..self
+        }
+    }
+
+    /// A directory in which to cache the optimised IR of each function.  A function which is
+    /// unchanged from a previous compilation, along with the functions it calls and the passes to
+    /// run, is loaded from the cache rather than being optimised again.
+    ///
+    /// Default: `None`
+    pub fn ir_cache_dir(self, ir_cache_dir: Option<PathBuf>) -> Self {
+        Self {
+            ir_cache_dir,
+            ..self
+        }
+    }
+
     pub fn canonical_root_module(&self) -> Arc<PathBuf> {
         self.canonical_root_module.clone()
     }
@@ -6,7 +6,7 @@ use crate::{
         ty::{self, TyImplItem},
         CallPath, Visibility,
     },
-    transform::{self, AttributesMap},
+    transform::AttributesMap,
     type_system::TypeInfo,
     Engines, TypeEngine, TypeId,
 };
@@ -1802,19 +1802,7 @@ fn connect_storage_declaration<'eng: 'cfg, 'cfg>(
 /// Checks [AttributesMap] for `#[allow(dead_code)]` usage, if so returns true
 /// otherwise returns false.
 fn allow_dead_code(attributes: AttributesMap) -> bool {
-    fn allow_dead_code_helper(attributes: AttributesMap) -> Option<bool> {
-        Some(
-            attributes
-                .get(&transform::AttributeKind::Allow)?
-                .last()?
-                .args
-                .first()?
-                .as_str()
-                == ALLOW_DEAD_CODE_NAME,
-        )
-    }
-
-    allow_dead_code_helper(attributes).unwrap_or_default()
+    attributes.allows(ALLOW_DEAD_CODE_NAME)
 }
 
 /// Returns true when the given `node` contains the attribute `#[allow(dead_code)]`
@@ -0,0 +1,581 @@
+//! Rendering of the IR explorer, a bundle of static HTML pages describing how a program was
+//! compiled.
+//!
+//! There is a page for each function with its control flow graph, dominator tree and optimised IR,
+//! along with the function's IR after each pass which was run.  There is also a page for each
+//! source file which contributed to the program and one for the finalized ASM.  Source lines link
+//! to the IR and ASM generated for them, and IR instructions and ASM ops link back to the source.
+//!
+//! Functions are identified by their names qualified with the path of the module declaring them,
+//! as functions in different modules may share a name.
+
+use crate::{
+    asm_generation::{FinalizedAsm, InstructionSet},
+    metadata::MetadataManager,
+    EmittedArtifact,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use sway_ir::{compute_dom_tree, compute_post_order, printer, Block, Context, DomTree, Function};
+use sway_types::Span;
+
+/// The directory within the emit directory in which the explorer is written.
+const EXPLORER_DIR: &str = "explorer";
+
+/// The text of each function in the IR at some point during compilation.
+pub(crate) struct IrSnapshot {
+    /// The name of the pass which had just been run, or `None` for the IR before any passes.
+    pass: Option<String>,
+    /// The text of each function, keyed by its qualified name.
+    functions: BTreeMap<String, String>,
+}
+
+impl IrSnapshot {
+    pub(crate) fn new(ir: &Context, md_mgr: &mut MetadataManager, pass: Option<&str>) -> Self {
+        let functions = qualified_names(ir, md_mgr)
+            .into_iter()
+            .map(|(function, name)| (name, printer::function_to_string(ir, function)))
+            .collect();
+        IrSnapshot {
+            pass: pass.map(str::to_string),
+            functions,
+        }
+    }
+}
+
+/// A line within one of the source files, identified by the index of its page.
+type SourceLine = (usize, usize);
+
+/// Each function in `ir` with its name qualified by the path of the module declaring it, e.g.
+/// `foo::bar::baz` for `fn baz` in `src/foo/bar.sw`.  Functions without a known declaration keep
+/// their bare name, and names which are still ambiguous are numbered.
+fn qualified_names(ir: &Context, md_mgr: &mut MetadataManager) -> Vec<(Function, String)> {
+    let mut counts = HashMap::<String, usize>::new();
+    ir.module_iter()
+        .flat_map(|module| module.function_iter(ir))
+        .map(|function| {
+            let name = function.get_name(ir);
+            let module = md_mgr
+                .md_to_span(ir, function.get_metadata(ir))
+                .and_then(|span| span.path().map(|path| module_path(path)));
+            let qualified = match module {
+                Some(module) => format!("{module}::{name}"),
+                None => name.to_string(),
+            };
+            let count = counts.entry(qualified.clone()).or_default();
+            *count += 1;
+            match *count {
+                1 => (function, qualified),
+                n => (function, format!("{qualified}#{n}")),
+            }
+        })
+        .collect()
+}
+
+/// The path of the module in the source file at `path`, relative to its package's `src`
+/// directory, e.g. `foo::bar` for `src/foo/bar.sw`.
+fn module_path(path: &Path) -> String {
+    let components = path
+        .with_extension("")
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    let start = components
+        .iter()
+        .rposition(|component| component == "src")
+        .map_or(components.len().saturating_sub(1), |idx| idx + 1);
+    components[start..].join("::")
+}
+
+/// Render the explorer pages for the optimised `ir` and the `asm` generated from it.
+pub(crate) fn render(
+    ir: &Context,
+    snapshots: &[IrSnapshot],
+    asm: &FinalizedAsm,
+) -> Vec<EmittedArtifact> {
+    let mut explorer = Explorer::default();
+    let mut md_mgr = MetadataManager::default();
+
+    let functions = qualified_names(ir, &mut md_mgr);
+    let mut names = functions
+        .iter()
+        .map(|(_, name)| name.clone())
+        .collect::<Vec<_>>();
+    for snapshot in snapshots {
+        for name in snapshot.functions.keys() {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+    }
+    for name in &names {
+        explorer.add_function_page(name);
+    }
+
+    let mut pages = vec![];
+    for (function, name) in &functions {
+        let html = explorer.function_page(ir, &mut md_mgr, *function, name, snapshots);
+        pages.push((explorer.function_pages[name].clone(), html));
+    }
+    // Functions which were removed during optimisation only have their IR snapshots.
+    for name in names.iter().skip(functions.len()) {
+        let html = explorer.removed_function_page(name, snapshots);
+        pages.push((explorer.function_pages[name].clone(), html));
+    }
+    pages.push(("asm.html".to_string(), explorer.asm_page(asm)));
+    pages.push((
+        "index.html".to_string(),
+        explorer.index_page(&names, snapshots),
+    ));
+    // Source pages are rendered last so that they link to everything generated from them.
+    for (idx, (path, src)) in explorer.sources.iter().enumerate() {
+        pages.push((source_page_name(idx), explorer.source_page(idx, path, src)));
+    }
+
+    pages
+        .into_iter()
+        .map(|(page, contents)| EmittedArtifact {
+            path: PathBuf::from(EXPLORER_DIR).join(page),
+            contents,
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct Explorer {
+    /// The page name for each function, keyed by qualified function name.
+    function_pages: HashMap<String, String>,
+    /// The path and contents of each source file, in the order their pages are numbered.
+    sources: Vec<(Arc<PathBuf>, Arc<str>)>,
+    /// The first IR instruction generated for each source line, as a link.
+    ir_for_line: BTreeMap<SourceLine, String>,
+    /// The index of the first ASM op generated for each source line.
+    asm_for_line: BTreeMap<SourceLine, usize>,
+}
+
+impl Explorer {
+    fn add_function_page(&mut self, name: &str) {
+        let stem = name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect::<String>();
+        let mut page = format!("fn-{stem}.html");
+        // Names which only differ in characters which were replaced need distinct pages.
+        if self.function_pages.values().any(|other| *other == page) {
+            page = format!("fn-{stem}-{}.html", self.function_pages.len());
+        }
+        self.function_pages.insert(name.to_string(), page);
+    }
+
+    /// The line of the source file at which `span` begins, adding the file if it's new.
+    fn source_line(&mut self, span: &Span) -> Option<SourceLine> {
+        let path = span.path()?;
+        let idx = match self.sources.iter().position(|(p, _)| p == path) {
+            Some(idx) => idx,
+            None => {
+                self.sources.push((path.clone(), span.src().clone()));
+                self.sources.len() - 1
+            }
+        };
+        Some((idx, span.start_pos().line_col().0))
+    }
+
+    /// A link to the source at which `span` begins, if it's known.
+    fn source_link(&mut self, span: Option<&Span>) -> String {
+        match span.and_then(|span| self.source_line(span)) {
+            Some((idx, line)) => {
+                let path = &self.sources[idx].0;
+                let file = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                format!(
+                    r#"<a class="src" href="{}#L{line}" title="{}">{}:{line}</a>"#,
+                    source_page_name(idx),
+                    escape(&path.display().to_string()),
+                    escape(&file)
+                )
+            }
+            None => String::new(),
+        }
+    }
+
+    fn function_page(
+        &mut self,
+        ir: &Context,
+        md_mgr: &mut MetadataManager,
+        function: Function,
+        name: &str,
+        snapshots: &[IrSnapshot],
+    ) -> String {
+        let page = self.function_pages[name].clone();
+        let mut body = format!("<h1>fn {}</h1>\n", escape(name));
+        let decl_span = md_mgr.md_to_span(ir, function.get_metadata(ir));
+        let decl_link = self.source_link(decl_span.as_ref());
+        if !decl_link.is_empty() {
+            let _ = writeln!(body, "<p>Declared at {decl_link}</p>");
+        }
+
+        let po = compute_post_order(ir, &function);
+        let dom_tree = compute_dom_tree(ir, &function, &po);
+        let label = |block: &Block| block.get_label(ir).to_string();
+        let block_link = |block: &Block| {
+            let label = label(block);
+            format!(r##"<a href="#block-{0}">{0}</a>"##, escape(&label))
+        };
+
+        body.push_str("<h2>Control flow graph</h2>\n<table>\n");
+        body.push_str(
+            "<tr><th>Block</th><th>Predecessors</th><th>Successors</th><th>Immediate dominator</th></tr>\n",
+        );
+        for block in function.block_iter(ir) {
+            let preds = block.pred_iter(ir).map(block_link).collect::<Vec<_>>();
+            let succs = block
+                .successors(ir)
+                .iter()
+                .map(|succ| block_link(&succ.block))
+                .collect::<Vec<_>>();
+            let idom = match dom_tree.get(&block) {
+                Some(node) => node.parent.as_ref().map(block_link).unwrap_or_default(),
+                None => "<em>unreachable</em>".to_string(),
+            };
+            let _ = writeln!(
+                body,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{idom}</td></tr>",
+                block_link(&block),
+                preds.join(", "),
+                succs.join(", "),
+            );
+        }
+        body.push_str("</table>\n");
+
+        body.push_str("<h2>Dominator tree</h2>\n");
+        dom_tree_html(
+            &mut body,
+            &dom_tree,
+            function.get_entry_block(ir),
+            &label,
+            &block_link,
+        );
+
+        body.push_str("<h2>Optimised IR</h2>\n<pre>");
+        let mut ins_idx = 0;
+        for printed in printer::function_to_blocks(ir, function) {
+            let _ = writeln!(
+                body,
+                r#"<span id="block-{}">{}</span>"#,
+                escape(&label(&printed.block)),
+                escape(&printed.header)
+            );
+            for (value, text) in printed.instructions {
+                let span = md_mgr.val_to_span(ir, value);
+                let anchor = format!("ins-{ins_idx}");
+                if let Some(line) = span.as_ref().and_then(|span| self.source_line(span)) {
+                    self.ir_for_line
+                        .entry(line)
+                        .or_insert_with(|| format!("{page}#{anchor}"));
+                }
+                let _ = writeln!(
+                    body,
+                    r#"<span id="{anchor}">{}</span>    {}"#,
+                    indent(&text),
+                    self.source_link(span.as_ref())
+                );
+                ins_idx += 1;
+            }
+        }
+        body.push_str("</pre>\n");
+
+        body.push_str(&snapshots_html(name, snapshots));
+        html_page(&format!("fn {name}"), &body)
+    }
+
+    fn removed_function_page(&self, name: &str, snapshots: &[IrSnapshot]) -> String {
+        let body = format!(
+            "<h1>fn {}</h1>\n<p>This function was removed during optimisation.</p>\n{}",
+            escape(name),
+            snapshots_html(name, snapshots)
+        );
+        html_page(&format!("fn {name}"), &body)
+    }
+
+    fn asm_page(&mut self, asm: &FinalizedAsm) -> String {
+        let mut body = "<h1>Finalized ASM</h1>\n<pre>".to_string();
+        match &asm.program_section {
+            InstructionSet::Fuel { ops } => {
+                for (idx, op) in ops.iter().enumerate() {
+                    if let Some(line) = op
+                        .owning_span
+                        .as_ref()
+                        .and_then(|span| self.source_line(span))
+                    {
+                        self.asm_for_line.entry(line).or_insert(idx);
+                    }
+                    let _ = writeln!(
+                        body,
+                        r#"<span id="op-{idx}">{idx:>6}  {}</span>    {}"#,
+                        escape(&op.to_string()),
+                        self.source_link(op.owning_span.as_ref())
+                    );
+                }
+            }
+            // Other targets don't record where their ops came from.
+            _ => body.push_str(&escape(&asm.program_section.to_string())),
+        }
+        body.push_str("</pre>\n<h2>Data section</h2>\n<pre>");
+        body.push_str(&escape(&asm.data_section.to_string()));
+        body.push_str("</pre>\n");
+        html_page("ASM", &body)
+    }
+
+    fn index_page(&self, names: &[String], snapshots: &[IrSnapshot]) -> String {
+        let mut body = "<h1>IR explorer</h1>\n<h2>Functions</h2>\n<ul>\n".to_string();
+        let mut sorted = names.iter().collect::<Vec<_>>();
+        sorted.sort();
+        for name in sorted {
+            let _ = writeln!(
+                body,
+                r#"<li><a href="{}">{}</a></li>"#,
+                self.function_pages[name],
+                escape(name)
+            );
+        }
+        body.push_str("</ul>\n<h2>Passes</h2>\n<table>\n");
+        body.push_str("<tr><th>Pass</th><th>Functions changed</th></tr>\n");
+        for (prev, snapshot) in snapshots.iter().zip(snapshots.iter().skip(1)) {
+            let changed = snapshot
+                .functions
+                .iter()
+                .filter(|(name, text)| prev.functions.get(*name) != Some(*text))
+                .count()
+                + prev
+                    .functions
+                    .keys()
+                    .filter(|name| !snapshot.functions.contains_key(*name))
+                    .count();
+            let _ = writeln!(
+                body,
+                "<tr><td>{}</td><td>{changed}</td></tr>",
+                escape(snapshot.pass.as_deref().unwrap_or_default())
+            );
+        }
+        body.push_str("</table>\n<h2>Source files</h2>\n<ul>\n");
+        for (idx, (path, _)) in self.sources.iter().enumerate() {
+            let _ = writeln!(
+                body,
+                r#"<li><a href="{}">{}</a></li>"#,
+                source_page_name(idx),
+                escape(&path.display().to_string())
+            );
+        }
+        body.push_str("</ul>\n<p><a href=\"asm.html\">Finalized ASM</a></p>\n");
+        html_page("IR explorer", &body)
+    }
+
+    fn source_page(&self, idx: usize, path: &PathBuf, src: &str) -> String {
+        let mut body = format!("<h1>{}</h1>\n<pre>", escape(&path.display().to_string()));
+        for (line_idx, line) in src.lines().enumerate() {
+            let line_no = line_idx + 1;
+            let mut links = String::new();
+            if let Some(ir) = self.ir_for_line.get(&(idx, line_no)) {
+                let _ = write!(links, r#" <a class="xref" href="{ir}">IR</a>"#);
+            }
+            if let Some(op) = self.asm_for_line.get(&(idx, line_no)) {
+                let _ = write!(links, r#" <a class="xref" href="asm.html#op-{op}">ASM</a>"#);
+            }
+            let _ = writeln!(
+                body,
+                r##"<span id="L{line_no}"><a class="ln" href="#L{line_no}">{line_no:>5}</a>  {}</span>{links}"##,
+                escape(line)
+            );
+        }
+        body.push_str("</pre>\n");
+        html_page(&path.display().to_string(), &body)
+    }
+}
+
+fn source_page_name(idx: usize) -> String {
+    format!("src-{idx}.html")
+}
+
+/// The function's IR after each pass which was run, as collapsible sections.
+fn snapshots_html(name: &str, snapshots: &[IrSnapshot]) -> String {
+    let mut html = "<h2>IR after each pass</h2>\n".to_string();
+    let mut prev: Option<&String> = None;
+    for snapshot in snapshots {
+        let title = match &snapshot.pass {
+            Some(pass) => format!("After {pass}"),
+            None => "Before optimisation".to_string(),
+        };
+        match snapshot.functions.get(name) {
+            Some(text) => {
+                let changed = prev.map_or(true, |prev| prev != text);
+                let _ = writeln!(
+                    html,
+                    "<details><summary>{}{}</summary><pre>{}</pre></details>",
+                    escape(&title),
+                    if changed {
+                        " <strong>(changed)</strong>"
+                    } else {
+                        ""
+                    },
+                    escape(text)
+                );
+                prev = Some(text);
+            }
+            None if prev.is_some() => {
+                let _ = writeln!(html, "<p>{}: removed</p>", escape(&title));
+                prev = None;
+            }
+            None => (),
+        }
+    }
+    html
+}
+
+fn dom_tree_html(
+    html: &mut String,
+    dom_tree: &DomTree,
+    block: Block,
+    label: &impl Fn(&Block) -> String,
+    block_link: &impl Fn(&Block) -> String,
+) {
+    let _ = write!(html, "<ul><li>{}", block_link(&block));
+    if let Some(node) = dom_tree.get(&block) {
+        let mut children = node.children.clone();
+        children.sort_by_key(label);
+        for child in children {
+            dom_tree_html(html, dom_tree, child, label, block_link);
+        }
+    }
+    html.push_str("</li></ul>\n");
+}
+
+/// Indent and escape a printed instruction, which may span several lines.
+fn indent(text: &str) -> String {
+    text.lines()
+        .map(|line| format!("    {}", escape(line)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn html_page(title: &str, body: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{}</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; }}
+pre {{ background: #f6f8fa; padding: 1em; overflow-x: auto; }}
+table {{ border-collapse: collapse; }}
+th, td {{ border: 1px solid #d0d7de; padding: 0.2em 0.6em; text-align: left; }}
+a.src, a.xref {{ color: #57606a; font-size: smaller; }}
+a.ln {{ color: #8c959f; text-decoration: none; }}
+:target {{ background: #fff8c5; }}
+</style>
+</head>
+<body>
+<nav><a href="index.html">Index</a> | <a href="asm.html">ASM</a></nav>
+{body}</body>
+</html>
+"#,
+        escape(title)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn module_paths_are_relative_to_src() {
+        assert_eq!(module_path(Path::new("/pkg/src/main.sw")), "main");
+        assert_eq!(module_path(Path::new("/pkg/src/foo/bar.sw")), "foo::bar");
+        assert_eq!(module_path(Path::new("/src/pkg/src/lib.sw")), "lib");
+        assert_eq!(module_path(Path::new("bar.sw")), "bar");
+    }
+
+    #[test]
+    fn functions_with_the_same_name_get_distinct_pages() {
+        // Function declarations are read back from disk, so the modules must exist.
+        let pkg_dir =
+            std::env::temp_dir().join(format!("sway-explorer-test-{}", std::process::id()));
+        let src_dir = pkg_dir.join("src");
+        std::fs::create_dir_all(&src_dir).unwrap();
+        for module in ["a", "b"] {
+            std::fs::write(src_dir.join(format!("{module}.sw")), "fn f() {}\n").unwrap();
+        }
+        let ir = sway_ir::parse(&format!(
+            r#"
+script {{
+    entry fn main() -> () {{
+        entry():
+        v0 = const unit ()
+        ret () v0
+    }}
+
+    fn f() -> (), !2 {{
+        entry():
+        v0 = const unit ()
+        ret () v0
+    }}
+
+    fn f() -> (), !3 {{
+        entry():
+        v0 = const unit ()
+        ret () v0
+    }}
+}}
+
+!0 = "{0}/a.sw"
+!1 = "{0}/b.sw"
+!2 = span !0 0 9
+!3 = span !1 0 9
+"#,
+            src_dir.display()
+        ))
+        .unwrap();
+
+        let mut md_mgr = MetadataManager::default();
+        let functions = qualified_names(&ir, &mut md_mgr);
+        let names = functions
+            .iter()
+            .map(|(_, name)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["main", "a::f", "b::f"]);
+
+        let snapshot = IrSnapshot::new(&ir, &mut md_mgr, None);
+        assert_eq!(
+            snapshot.functions.keys().collect::<Vec<_>>(),
+            ["a::f", "b::f", "main"]
+        );
+
+        let mut explorer = Explorer::default();
+        for name in &names {
+            explorer.add_function_page(name);
+        }
+        assert_eq!(explorer.function_pages["a::f"], "fn-a__f.html");
+        assert_eq!(explorer.function_pages["b::f"], "fn-b__f.html");
+
+        let (function, name) = &functions[2];
+        let html = explorer.function_page(&ir, &mut md_mgr, *function, name, &[snapshot]);
+        assert!(html.contains("<h1>fn b::f</h1>"));
+        assert!(html.contains(r#"<a class="src" href="src-0.html#L1""#));
+        assert!(html.contains(">b.sw:1</a>"));
+
+        std::fs::remove_dir_all(pkg_dir).unwrap();
+    }
+}
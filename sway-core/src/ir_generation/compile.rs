@@ -475,6 +475,11 @@ fn compile_fn_with_args(
         let inline_md_idx = md_mgr.inline_to_md(context, inline);
         metadata = md_combine(context, &metadata, &inline_md_idx);
     }
+    if is_entry {
+        let doc_comment_md_idx =
+            md_mgr.doc_comment_to_md(context, &ast_fn_decl.attributes.doc_comment_lines());
+        metadata = md_combine(context, &metadata, &doc_comment_md_idx);
+    }
 
     let func = Function::new(
         context,
@@ -597,7 +597,10 @@ fn const_eval_intrinsic(
         | sway_ast::Intrinsic::StateStoreQuad
         | sway_ast::Intrinsic::Log
         | sway_ast::Intrinsic::Revert
-        | sway_ast::Intrinsic::Smo => Ok(None),
+        | sway_ast::Intrinsic::Smo
+        | sway_ast::Intrinsic::ContextGas
+        | sway_ast::Intrinsic::GlobalGas
+        | sway_ast::Intrinsic::GasCheckpoint => Ok(None),
     }
 }
 
@@ -920,6 +920,30 @@ impl<'eng> FnCompiler<'eng> {
                     .smo(recipient_and_message, message_size, output_index, coins)
                     .add_metadatum(context, span_md_idx))
             }
+            Intrinsic::ContextGas | Intrinsic::GlobalGas => {
+                let reg = match kind {
+                    Intrinsic::ContextGas => Register::Cgas,
+                    Intrinsic::GlobalGas => Register::Ggas,
+                    _ => unreachable!(),
+                };
+                let span_md_idx = md_mgr.span_to_md(context, &span);
+                Ok(self
+                    .current_block
+                    .ins(context)
+                    .read_register(reg)
+                    .add_metadatum(context, span_md_idx))
+            }
+            Intrinsic::GasCheckpoint => {
+                let id = self.compile_expression(context, md_mgr, &arguments[0])?;
+
+                // The `gas_checkpoint` instruction
+                let span_md_idx = md_mgr.span_to_md(context, &span);
+                Ok(self
+                    .current_block
+                    .ins(context)
+                    .gas_checkpoint(id)
+                    .add_metadatum(context, span_md_idx))
+            }
         }
     }
 
@@ -1718,6 +1742,9 @@ impl<'eng> FnCompiler<'eng> {
             &body.return_type,
             &body.span,
         )?;
+        let type_name = Engines::new(self.type_engine, self.decl_engine)
+            .help_out(body.return_type)
+            .to_string();
 
         // We must compile the RHS before checking for shadowing, as it will still be in the
         // previous scope.
@@ -1741,6 +1768,11 @@ impl<'eng> FnCompiler<'eng> {
                 .ins(context)
                 .get_local(local_var)
                 .add_metadatum(context, span_md_idx);
+            // Code blocks don't record their spans, so variables are scoped to their function.
+            if let Some(scope) = self.function.get_span(context) {
+                let variable = DebugVariable::new(name.as_str().to_owned(), type_name, scope);
+                local_val.add_debug_variable(context, &variable);
+            }
             self.current_block
                 .ins(context)
                 .store(local_val, init_val)
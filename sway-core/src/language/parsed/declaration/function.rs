@@ -41,10 +41,11 @@ impl PartialEqWithEngines for FunctionParameter {
 }
 
 impl FunctionDeclaration {
-    /// Checks if this `FunctionDeclaration` is a test.
+    /// Checks if this `FunctionDeclaration` is a test or a benchmark, both of which are only
+    /// included in builds with tests enabled.
     pub(crate) fn is_test(&self) -> bool {
         self.attributes
             .keys()
-            .any(|k| matches!(k, AttributeKind::Test))
+            .any(|k| matches!(k, AttributeKind::Test | AttributeKind::Bench))
     }
 }
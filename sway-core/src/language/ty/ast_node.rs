@@ -174,7 +174,8 @@ impl TyAstNode {
         }
     }
 
-    /// Check to see if this node is a function declaration of a function annotated as test.
+    /// Check to see if this node is a function declaration of a function annotated as test or
+    /// bench.
     pub(crate) fn is_test_function(&self, decl_engine: &DeclEngine) -> bool {
         match &self {
             TyAstNode {
@@ -187,6 +188,7 @@ impl TyAstNode {
             } => {
                 let TyFunctionDeclaration { attributes, .. } = decl_engine.get_function(decl_id);
                 attributes.contains_key(&AttributeKind::Test)
+                    || attributes.contains_key(&AttributeKind::Bench)
             }
             _ => false,
         }
@@ -223,7 +225,7 @@ impl TyAstNode {
                     ..
                 } => {
                     let decl = decl_engine.get_function(decl_id);
-                    decl.visibility == Visibility::Public || decl.is_test()
+                    decl.visibility == Visibility::Public || decl.is_test() || decl.is_bench()
                 }
                 TyAstNode {
                     content:
@@ -6,13 +6,14 @@ use crate::{
     decl_engine::*,
     engine_threading::*,
     error::*,
-    language::{parsed, ty::*, Inline, Purity, Visibility},
+    language::{parsed, ty::*, Inline, Literal, Purity, Visibility},
     transform,
     type_system::*,
 };
 
+use sway_error::error::CompileError;
 use sway_types::{
-    constants::{INLINE_ALWAYS_NAME, INLINE_NEVER_NAME},
+    constants::{INLINE_ALWAYS_NAME, INLINE_NEVER_NAME, SELECTOR_VALUE_NAME},
     Ident, Named, Span, Spanned,
 };
 
@@ -277,7 +278,8 @@ impl TyFunctionDeclaration {
 
     /// Converts a [TyFunctionDeclaration] into a value that is to be used in contract function
     /// selectors.
-    /// Hashes the name and parameters using SHA256, and then truncates to four bytes.
+    /// Hashes the name and parameters using SHA256, and then truncates to four bytes, unless the
+    /// selector is given explicitly with a `#[selector]` attribute.
     pub fn to_fn_selector_value(
         &self,
         type_engine: &TypeEngine,
@@ -285,6 +287,17 @@ impl TyFunctionDeclaration {
     ) -> CompileResult<[u8; 4]> {
         let mut errors = vec![];
         let mut warnings = vec![];
+        if let Some(attribute) = self.attributes.selector() {
+            return match selector_attribute_value(attribute) {
+                Some(selector) => ok(selector, warnings, errors),
+                None => {
+                    errors.push(CompileError::InvalidSelectorAttribute {
+                        span: attribute.span.clone(),
+                    });
+                    err(warnings, errors)
+                }
+            };
+        }
         let hash = check!(
             self.to_fn_selector_value_untruncated(type_engine, decl_engine),
             return err(warnings, errors),
@@ -336,6 +349,12 @@ impl TyFunctionDeclaration {
             .contains_key(&transform::AttributeKind::Test)
     }
 
+    /// Whether or not this function is a benchmark, i.e. decorated with `#[bench]`.
+    pub fn is_bench(&self) -> bool {
+        self.attributes
+            .contains_key(&transform::AttributeKind::Bench)
+    }
+
     pub fn inline(&self) -> Option<Inline> {
         match self
             .attributes
@@ -353,7 +372,7 @@ impl TyFunctionDeclaration {
 
     /// Whether or not this function describes a program entry point.
     pub fn is_entry(&self) -> bool {
-        self.is_main_entry() || self.is_test()
+        self.is_main_entry() || self.is_test() || self.is_bench()
     }
 }
 
@@ -432,3 +451,18 @@ impl TyFunctionSig {
         }
     }
 }
+
+/// The selector given by a `#[selector(value = "0x12345678")]` attribute, if it's well formed.
+fn selector_attribute_value(attribute: &transform::Attribute) -> Option<[u8; 4]> {
+    let value = match attribute.arg_value(SELECTOR_VALUE_NAME)? {
+        Literal::String(span) => span.as_str(),
+        _ => return None,
+    };
+    let digits = value
+        .strip_prefix("0x")
+        .filter(|digits| digits.len() == 8)?;
+    u32::from_str_radix(digits, 16)
+        .ok()
+        .filter(|_| digits.chars().all(|c| c.is_ascii_hexdigit()))
+        .map(u32::to_be_bytes)
+}
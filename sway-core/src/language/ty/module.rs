@@ -43,7 +43,7 @@ impl TyModule {
         }
     }
 
-    /// All test functions within this module.
+    /// All test and benchmark functions within this module.
     pub fn test_fns<'a: 'b, 'b>(
         &'b self,
         decl_engine: &'a DeclEngine,
@@ -56,7 +56,7 @@ impl TyModule {
             }) = &node.content
             {
                 let fn_decl = decl_engine.get_function(decl_id);
-                if fn_decl.is_test() {
+                if fn_decl.is_test() || fn_decl.is_bench() {
                     return Some((
                         fn_decl,
                         DeclRef::new(name.clone(), *decl_id, decl_span.clone()),
@@ -3,11 +3,14 @@ use crate::{
     error::*,
     fuel_prelude::fuel_tx::StorageSlot,
     language::{parsed, ty::*, Purity},
+    lint::LintLevels,
     semantic_analysis::storage_only_types,
+    storage_layout::StorageLayout,
     type_system::*,
     Engines,
 };
 
+use std::collections::HashMap;
 use sway_error::error::CompileError;
 use sway_types::*;
 
@@ -18,8 +21,13 @@ pub struct TyProgram {
     pub declarations: Vec<TyDeclaration>,
     pub configurables: Vec<TyConstantDeclaration>,
     pub storage_slots: Vec<StorageSlot>,
+    /// The slots allocated to each storage field.  Empty for programs other than contracts.
+    pub storage_layout: StorageLayout,
     pub logged_types: Vec<(LogId, TypeId)>,
     pub messages_types: Vec<(MessageId, TypeId)>,
+    /// The lint levels set by the attributes of the program, which also apply to the warnings of
+    /// IR generation and optimization.
+    pub lint_levels: LintLevels,
 }
 
 impl TyProgram {
@@ -200,6 +208,26 @@ impl TyProgram {
                     }
                 }
 
+                // Every ABI method must have its own selector, whether derived or given explicitly.
+                let mut selectors = HashMap::<[u8; 4], &TyFunctionDeclaration>::new();
+                for entry in &abi_entries {
+                    let selector = check!(
+                        entry.to_fn_selector_value(ty_engine, decl_engine),
+                        continue,
+                        warnings,
+                        errors
+                    );
+                    if let Some(other_entry) = selectors.insert(selector, entry) {
+                        errors.push(CompileError::SelectorCollision {
+                            selector: format!("0x{:08x}", u32::from_be_bytes(selector)),
+                            fn_name: entry.name.clone(),
+                            other_fn_name: other_entry.name.clone(),
+                            other_span: other_entry.name.span(),
+                            span: entry.name.span(),
+                        });
+                    }
+                }
+
                 TyProgramKind::Contract { abi_entries }
             }
             parsed::TreeType::Library => {
@@ -304,7 +332,7 @@ impl TyProgram {
         )
     }
 
-    /// All test function declarations within the program.
+    /// All test and benchmark function declarations within the program.
     pub fn test_fns<'a: 'b, 'b>(
         &'b self,
         decl_engine: &'a DeclEngine,
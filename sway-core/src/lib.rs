@@ -11,33 +11,46 @@ mod build_config;
 mod concurrent_slab;
 mod control_flow_analysis;
 pub mod decl_engine;
+mod explorer;
 pub mod ir_generation;
 pub mod language;
+pub mod lint;
 mod metadata;
 pub mod semantic_analysis;
 pub mod source_map;
+pub mod storage_layout;
 pub mod transform;
 pub mod type_system;
 
 use crate::ir_generation::check_function_purity;
 use crate::language::parsed::TreeType;
 use crate::{error::*, source_map::SourceMap};
+use abi_generation::ir_abi::IrAbi;
 pub use asm_generation::from_ir::compile_ir_to_asm;
 use asm_generation::FinalizedAsm;
-pub use asm_generation::{CompiledBytecode, FinalizedEntry};
-pub use build_config::{BuildConfig, BuildTarget};
+pub use asm_generation::{
+    CompiledBytecode, CoverageBranch, CoveragePoint, FinalizedEntry, FunctionSize, InlinedSize,
+};
+pub use build_config::{
+    AbiEncoding, BuildConfig, BuildTarget, EmitConfig, EmittedArtifact, OptLevel,
+};
 use control_flow_analysis::ControlFlowGraph;
 use metadata::MetadataManager;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use sway_ast::AttributeDecl;
+use sway_ast::{attribute::Annotated, AttributeDecl};
 use sway_error::handler::{ErrorEmitted, Handler};
+pub use sway_ir::COVERAGE_LOG_ID;
 use sway_ir::{
-    create_o1_pass_group, register_known_passes, Context, Kind, Module, PassManager,
-    MODULEPRINTER_NAME,
+    create_o0_pass_group, create_o1_pass_group, create_o2_pass_group, create_os_pass_group,
+    create_oz_pass_group, mandatory_inline_pass, register_known_passes, Context, FunctionCache,
+    IrError, Kind, Module, PassGroup, PassHookPoint, PassManager, DCE_NAME, MODULEPRINTER_NAME,
+    REWRITE_NAME,
+};
+use sway_types::constants::{
+    ALLOW_ATTRIBUTE_NAME, DENY_ATTRIBUTE_NAME, DOC_COMMENT_ATTRIBUTE_NAME, WARN_ATTRIBUTE_NAME,
 };
-use sway_types::constants::DOC_COMMENT_ATTRIBUTE_NAME;
 use transform::{Attribute, AttributeKind, AttributesMap};
 
 pub use semantic_analysis::namespace::{self, Namespace};
@@ -45,7 +58,7 @@ pub mod types;
 
 pub use error::CompileResult;
 use sway_error::error::CompileError;
-use sway_error::warning::CompileWarning;
+use sway_error::warning::{CompileWarning, Warning};
 use sway_types::{ident::Ident, span, Spanned};
 pub use type_system::*;
 
@@ -75,20 +88,97 @@ pub fn parse(
     input: Arc<str>,
     engines: Engines<'_>,
     config: Option<&BuildConfig>,
+) -> CompileResult<(lexed::LexedProgram, parsed::ParseProgram)> {
+    parse_with_cache(input, engines, config, &mut ModuleCache::default())
+}
+
+/// The modules parsed by previous calls to [parse_with_cache], which are reused by later calls as
+/// long as their sources are unchanged, so that only the edited modules of a program are parsed
+/// again.
+///
+/// Only modules which parsed without any diagnostics are cached.
+#[derive(Debug, Default)]
+pub struct ModuleCache {
+    modules: HashMap<Arc<PathBuf>, (Arc<str>, Annotated<sway_ast::Module>)>,
+}
+
+impl ModuleCache {
+    /// Forget all of the parsed modules, so that they are parsed again by the next call.
+    pub fn clear(&mut self) {
+        self.modules.clear();
+    }
+
+    /// The number of modules cached.
+    pub fn len(&self) -> usize {
+        self.modules.len()
+    }
+
+    /// Whether no modules are cached.
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+
+    /// Parse the module at `path`, unless it was already parsed from the same source.
+    fn parse_file(
+        &mut self,
+        handler: &Handler,
+        src: Arc<str>,
+        path: Arc<PathBuf>,
+    ) -> Result<Annotated<sway_ast::Module>, ErrorEmitted> {
+        if let Some((cached_src, module)) = self.modules.get(&path) {
+            if *cached_src == src {
+                return Ok(module.clone());
+            }
+        }
+        let module_handler = Handler::default();
+        let module = sway_parse::parse_file(&module_handler, src.clone(), Some(path.clone()));
+        let (errors, warnings) = module_handler.consume();
+        match &module {
+            Ok(module) if errors.is_empty() && warnings.is_empty() => {
+                self.modules.insert(path, (src, module.clone()));
+            }
+            _ => {
+                self.modules.remove(&path);
+            }
+        }
+        for error in errors {
+            handler.emit_err(error);
+        }
+        for warning in warnings {
+            handler.emit_warn(warning);
+        }
+        module
+    }
+}
+
+/// Like [parse], but the modules which were parsed by a previous call with the same `cache` are
+/// reused rather than parsed again, unless their sources have changed since.
+pub fn parse_with_cache(
+    input: Arc<str>,
+    engines: Engines<'_>,
+    config: Option<&BuildConfig>,
+    cache: &mut ModuleCache,
 ) -> CompileResult<(lexed::LexedProgram, parsed::ParseProgram)> {
     CompileResult::with_handler(|h| match config {
         None => parse_in_memory(h, engines, input),
         // When a `BuildConfig` is given,
         // the module source may declare `dep`s that must be parsed from other files.
-        Some(config) => parse_module_tree(h, engines, input, config.canonical_root_module(), None)
-            .map(|(kind, lexed, parsed)| {
-                let lexed = lexed::LexedProgram {
-                    kind: kind.clone(),
-                    root: lexed,
-                };
-                let parsed = parsed::ParseProgram { kind, root: parsed };
-                (lexed, parsed)
-            }),
+        Some(config) => parse_module_tree(
+            h,
+            engines,
+            cache,
+            input,
+            config.canonical_root_module(),
+            None,
+        )
+        .map(|(kind, lexed, parsed)| {
+            let lexed = lexed::LexedProgram {
+                kind: kind.clone(),
+                root: lexed,
+            };
+            let parsed = parsed::ParseProgram { kind, root: parsed };
+            (lexed, parsed)
+        }),
     })
 }
 
@@ -111,29 +201,31 @@ fn module_attrs_to_map(
         let attrs = attr_decl.attribute.get().into_iter();
         for attr in attrs {
             let name = attr.name.as_str();
-            if name != DOC_COMMENT_ATTRIBUTE_NAME {
-                // prevent using anything except doc comment attributes
+            let attr_kind = match name {
+                DOC_COMMENT_ATTRIBUTE_NAME => Some(AttributeKind::DocComment),
+                ALLOW_ATTRIBUTE_NAME => Some(AttributeKind::Allow),
+                WARN_ATTRIBUTE_NAME => Some(AttributeKind::Warn),
+                DENY_ATTRIBUTE_NAME => Some(AttributeKind::Deny),
+                _ => None,
+            };
+            if attr_kind.is_none() {
+                // prevent using anything except doc comment and lint attributes
                 handler.emit_err(CompileError::ExpectedModuleDocComment {
                     span: attr.name.span(),
                 });
             }
 
-            let args = attr
-                .args
-                .as_ref()
-                .map(|parens| parens.get().into_iter().cloned().collect())
-                .unwrap_or_else(Vec::new);
-
             let attribute = Attribute {
                 name: attr.name.clone(),
-                args,
+                args: to_parsed_lang::attr_args_to_args(
+                    &mut to_parsed_lang::Context::default(),
+                    handler,
+                    attr,
+                )?,
                 span: attr_decl.span(),
             };
 
-            if let Some(attr_kind) = match name {
-                DOC_COMMENT_ATTRIBUTE_NAME => Some(AttributeKind::DocComment),
-                _ => None,
-            } {
+            if let Some(attr_kind) = attr_kind {
                 attrs_map.entry(attr_kind).or_default().push(attribute);
             }
         }
@@ -147,7 +239,7 @@ fn parse_in_memory(
     engines: Engines<'_>,
     src: Arc<str>,
 ) -> Result<(lexed::LexedProgram, parsed::ParseProgram), ErrorEmitted> {
-    let module = sway_parse::parse_file(handler, src, None)?;
+    let module = sway_parse::parse_file(handler, src.clone(), None)?;
     let (kind, tree) = to_parsed_lang::convert_parse_tree(
         &mut to_parsed_lang::Context::default(),
         handler,
@@ -157,7 +249,7 @@ fn parse_in_memory(
     let submodules = Default::default();
     let attributes = module_attrs_to_map(handler, &module.attribute_list)?;
     let root = parsed::ParseModule {
-        span: span::Span::dummy(),
+        span: span::Span::new(src, 0, 0, None).unwrap(),
         tree,
         submodules,
         attributes,
@@ -182,6 +274,7 @@ struct Submodules {
 fn parse_submodules(
     handler: &Handler,
     engines: Engines<'_>,
+    cache: &mut ModuleCache,
     module_name: Option<&str>,
     module: &sway_ast::Module,
     module_dir: &Path,
@@ -209,6 +302,7 @@ fn parse_submodules(
         if let Ok((kind, lexed_module, parse_module)) = parse_module_tree(
             handler,
             engines,
+            cache,
             submod_str.clone(),
             submod_path.clone(),
             Some(submod.name.as_str()),
@@ -242,17 +336,25 @@ fn parse_submodules(
 fn parse_module_tree(
     handler: &Handler,
     engines: Engines<'_>,
+    cache: &mut ModuleCache,
     src: Arc<str>,
     path: Arc<PathBuf>,
     module_name: Option<&str>,
 ) -> Result<(parsed::TreeType, lexed::LexedModule, parsed::ParseModule), ErrorEmitted> {
     // Parse this module first.
     let module_dir = path.parent().expect("module file has no parent directory");
-    let module = sway_parse::parse_file(handler, src.clone(), Some(path.clone()))?;
+    let module = cache.parse_file(handler, src.clone(), path.clone())?;
 
     // Parse all submodules before converting to the `ParseTree`.
     // This always recovers on parse errors for the file itself by skipping that file.
-    let submodules = parse_submodules(handler, engines, module_name, &module.value, module_dir);
+    let submodules = parse_submodules(
+        handler,
+        engines,
+        cache,
+        module_name,
+        &module.value,
+        module_dir,
+    );
 
     // Convert from the raw parsed module to the `ParseTree` ready for type-check.
     let (kind, tree) = to_parsed_lang::convert_parse_tree(
@@ -400,6 +502,11 @@ pub fn parsed_to_ast(
 
     // Check if a non-test function calls `#[test]` function.
 
+    // Drop the warnings of allowed lints, and report those of denied lints as errors.
+    let warnings = typed_program_with_storage_slots
+        .lint_levels
+        .apply(warnings, &mut errors);
+
     ok(
         typed_program_with_storage_slots,
         dedup_unsorted(warnings),
@@ -488,8 +595,13 @@ pub fn ast_to_asm(
         Some(typed_program) => {
             let mut errors = ast_res.errors.clone();
             let mut warnings = ast_res.warnings.clone();
+            // The lint levels apply to the warnings of IR generation and optimization too.
+            let mut asm_res = compile_ast_to_ir_to_asm(engines, typed_program, build_config);
+            asm_res.warnings = typed_program
+                .lint_levels
+                .apply(asm_res.warnings, &mut asm_res.errors);
             let asm = check!(
-                compile_ast_to_ir_to_asm(engines, typed_program, build_config),
+                asm_res,
                 return deduped_err(warnings, errors),
                 warnings,
                 errors
@@ -525,6 +637,39 @@ pub(crate) fn compile_ast_to_ir_to_asm(
         Err(e) => return err(warnings, vec![e]),
     };
 
+    // Compile any frozen configurables in as constants, so that the passes can specialise the
+    // program for them.
+    if !build_config.freeze_configurables.is_empty() {
+        let unknown_configurables = build_config
+            .freeze_configurables
+            .keys()
+            .filter(|name| {
+                !program
+                    .configurables
+                    .iter()
+                    .any(|decl| decl.call_path.suffix.as_str() == name.as_str())
+            })
+            .map(|name| CompileError::UnknownFrozenConfigurable {
+                name: name.clone(),
+                span: span::Span::dummy(),
+            })
+            .collect::<Vec<_>>();
+        if !unknown_configurables.is_empty() {
+            return err(warnings, unknown_configurables);
+        }
+        for module in ir.module_iter() {
+            if let Err(error) =
+                sway_ir::freeze_configurables(&mut ir, &module, &build_config.freeze_configurables)
+            {
+                let error = CompileError::InvalidFrozenConfigurable {
+                    error: error.to_string(),
+                    span: span::Span::dummy(),
+                };
+                return err(warnings, vec![error]);
+            }
+        }
+    }
+
     // Find all the entry points for purity checking and DCE.
     let entry_point_functions: Vec<::sway_ir::Function> = ir
         .module_iter()
@@ -545,17 +690,221 @@ pub(crate) fn compile_ast_to_ir_to_asm(
         errors.extend(e);
     }
 
+    // Instrument the _unoptimised_ IR so that the coverage counters map closely onto the source.
+    // Predicates may not log so they're never instrumented.
+    let is_predicate = matches!(tree_type, TreeType::Predicate);
+    let mut coverage_points = Vec::new();
+    if build_config.instrument_coverage && !is_predicate {
+        let mut md_mgr = metadata::MetadataManager::default();
+        for module in ir.module_iter().collect::<Vec<_>>() {
+            for point in sway_ir::instrument_coverage(&mut ir, module) {
+                coverage_points.push(CoveragePoint {
+                    id: point.id,
+                    span: md_mgr.md_to_span(&ir, point.metadata),
+                    branch: point.branch.map(|branch| CoverageBranch {
+                        id: branch.id,
+                        is_true_arm: branch.is_true_arm,
+                        span: md_mgr.md_to_span(&ir, branch.metadata),
+                    }),
+                });
+            }
+        }
+    }
+
+    // Gas checkpoints generate no code unless they're lowered here.  Predicates may not log either.
+    if build_config.gas_checkpoints && !is_predicate {
+        for module in ir.module_iter().collect::<Vec<_>>() {
+            sway_ir::lower_gas_checkpoints(&mut ir, module);
+        }
+    }
+
+    // The decoders for the packed ABI encoding are generated before optimisation so that they're
+    // optimised along with the rest of each method.
+    let abi_encoding = match tree_type {
+        TreeType::Contract => build_config.abi_encoding,
+        _ => AbiEncoding::Default,
+    };
+    if abi_encoding == AbiEncoding::Packed {
+        for module in ir.module_iter().collect::<Vec<_>>() {
+            sway_ir::lower_packed_abi(&mut ir, module);
+        }
+    }
+
     // Initialize the pass manager and register known passes.
     let mut pass_mgr = PassManager::default();
     register_known_passes(&mut pass_mgr);
-    let mut pass_group = create_o1_pass_group(matches!(tree_type, TreeType::Predicate));
+
+    // Make sure any passes named explicitly or requested for emission actually exist.
+    let emit = &build_config.emit;
+    let unknown_passes = build_config
+        .passes
+        .iter()
+        .flatten()
+        .filter_map(|spec| match pass_mgr.parse_pipeline(spec, is_predicate) {
+            Err(IrError::UnknownPass(name)) => Some(name),
+            _ => None,
+        })
+        .chain(
+            emit.before_passes
+                .iter()
+                .chain(emit.after_passes.iter())
+                .filter(|name| pass_mgr.lookup_registered_pass(name).is_none())
+                .cloned(),
+        )
+        .map(|name| CompileError::UnknownIrPass {
+            name,
+            pass_names: pass_mgr.help_text(),
+            span: span::Span::dummy(),
+        })
+        .collect::<Vec<_>>();
+    if !unknown_passes.is_empty() {
+        errors.extend(unknown_passes);
+        return err(warnings, errors);
+    }
+
+    // Load the rules for the `rewrite` pass, checking them before any passes are run.
+    if let Some(path) = &build_config.rewrite_rules {
+        let rules = std::fs::read_to_string(path)
+            .map_err(|error| error.to_string())
+            .and_then(|source| sway_ir::parse_rules(&source).map_err(|error| error.to_string()));
+        match rules {
+            Ok(rules) => ir.set_rewrite_rules(rules),
+            Err(error) => {
+                errors.push(CompileError::InvalidRewriteRules {
+                    path: path.clone(),
+                    error,
+                    span: span::Span::dummy(),
+                });
+                return err(warnings, errors);
+            }
+        }
+    }
+
+    ir.set_pass_fuel(build_config.optimization_fuel);
+
+    // Catch invalid IR where it's produced rather than in codegen, at least while developing the
+    // compiler.
+    pass_mgr.set_verify_after_each(cfg!(debug_assertions));
+
+    // An explicit list of passes overrides the optimisation level preset.
+    let mut pass_group = match &build_config.passes {
+        // Unknown names were rejected above.
+        Some(passes) => {
+            let mut pass_group = pass_mgr
+                .parse_pipeline(&passes.join(","), is_predicate)
+                .unwrap_or_default();
+            // The code can't be generated until the calls it can't make have been inlined.
+            pass_group.append_pass(mandatory_inline_pass(is_predicate));
+            pass_group
+        }
+        None => match build_config.opt_level {
+            OptLevel::Opt0 => create_o0_pass_group(is_predicate),
+            OptLevel::Opt1 => create_o1_pass_group(is_predicate),
+            OptLevel::Opt2 => create_o2_pass_group(is_predicate),
+            OptLevel::OptSize => create_os_pass_group(is_predicate),
+            OptLevel::OptMinSize => create_oz_pass_group(is_predicate),
+        },
+    };
+    // Rewrite rules are applied once the preset has simplified the IR, cleaning up after them.
+    if build_config.passes.is_none() && build_config.rewrite_rules.is_some() {
+        pass_group.append_pass(REWRITE_NAME);
+        pass_group.append_pass(DCE_NAME);
+    }
+    let pass_pipeline = pass_group
+        .flatten_pass_group()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
     if build_config.print_ir {
         pass_group.append_pass(MODULEPRINTER_NAME);
     }
 
-    // Run the passes.
+    // Load the optimised functions which are unchanged from the cache, so that the passes only
+    // run over the others.  The cache is bypassed if the output of any of the passes was
+    // requested, or if rewrite rules were given since they aren't part of the cache keys.
+    let ir_cache = build_config
+        .ir_cache_dir
+        .as_ref()
+        .filter(|_| {
+            !build_config.print_ir
+                && build_config.rewrite_rules.is_none()
+                && !emit.explorer
+                && emit.before_passes.is_empty()
+                && emit.after_passes.is_empty()
+                && emit.function.is_none()
+        })
+        .map(|dir| FunctionCache::new(dir.clone(), ir_cache_salt(&pass_group)));
+    let ir_cache_keys = ir_cache.as_ref().map(|cache| cache.load(&mut ir));
+
+    if build_config.print_ir_memory {
+        println!("IR memory usage before optimization:\n{}", ir.memory_report());
+    }
+
+    // Run the passes, capturing the IR around any which were requested for emission.  The
+    // explorer shows the IR of each function after every pass.
+    let mut emitted = Vec::new();
+    let mut pass_idx = 0;
+    let mut snapshots = Vec::new();
+    let mut explorer_md_mgr = metadata::MetadataManager::default();
+    if emit.explorer {
+        snapshots.push(explorer::IrSnapshot::new(&ir, &mut explorer_md_mgr, None));
+    }
+    // Only the passes which change the requested function are captured.
+    let mut function_md_mgr = metadata::MetadataManager::default();
+    let mut function_ir = emit
+        .function
+        .as_ref()
+        .map(|span| function_ir_string(&ir, &mut function_md_mgr, span));
+    if let Some(function_ir) = &function_ir {
+        emitted.push(EmittedArtifact {
+            path: PathBuf::from("function").join("initial.ir"),
+            contents: function_ir.clone(),
+        });
+    }
     let res = CompileResult::with_handler(|handler| {
-        if let Err(ir_error) = pass_mgr.run(&mut ir, &pass_group) {
+        let hook = |ir: &Context, pass_name: &'static str, point: PassHookPoint| {
+            let (requested, when) = match point {
+                PassHookPoint::Before => (&emit.before_passes, "before"),
+                PassHookPoint::After => (&emit.after_passes, "after"),
+            };
+            if requested.iter().any(|name| name == pass_name) {
+                emitted.push(EmittedArtifact {
+                    path: PathBuf::from("passes")
+                        .join(format!("{pass_idx:02}-{when}-{pass_name}"))
+                        .with_extension("ir"),
+                    contents: sway_ir::printer::to_string(ir),
+                });
+            }
+            if point == PassHookPoint::After {
+                if build_config.print_ir_memory && pass_name != MODULEPRINTER_NAME {
+                    println!(
+                        "IR memory usage after {pass_name}: {}",
+                        sway_ir::format_memory_size(ir.memory_report().total_bytes())
+                    );
+                }
+                if emit.explorer && pass_name != MODULEPRINTER_NAME {
+                    snapshots.push(explorer::IrSnapshot::new(
+                        ir,
+                        &mut explorer_md_mgr,
+                        Some(pass_name),
+                    ));
+                }
+                if let (Some(span), Some(previous)) = (&emit.function, &mut function_ir) {
+                    let current = function_ir_string(ir, &mut function_md_mgr, span);
+                    if current != *previous {
+                        emitted.push(EmittedArtifact {
+                            path: PathBuf::from("function")
+                                .join(format!("{pass_idx:02}-after-{pass_name}"))
+                                .with_extension("ir"),
+                            contents: current.clone(),
+                        });
+                        *previous = current;
+                    }
+                }
+                pass_idx += 1;
+            }
+        };
+        if let Err(ir_error) = pass_mgr.run_with_hook(&mut ir, &pass_group, hook) {
             Err(handler.emit_err(CompileError::InternalOwned(
                 ir_error.to_string(),
                 span::Span::dummy(),
@@ -566,16 +915,141 @@ pub(crate) fn compile_ast_to_ir_to_asm(
     });
     check!(res, return err(warnings, errors), warnings, errors);
 
-    let final_asm = check!(
+    if build_config.print_ir_memory {
+        println!("IR memory usage after optimization:\n{}", ir.memory_report());
+    }
+    if build_config.print_pass_statistics {
+        println!("IR pass statistics:\n{}", pass_mgr.statistics_report());
+    }
+
+    // The passes still produce correct code when they run out of fuel, but it's worth knowing.
+    let mut md_mgr = metadata::MetadataManager::default();
+    for exhausted in pass_mgr.fuel_exhausted() {
+        warnings.push(CompileWarning {
+            span: md_mgr
+                .md_to_span(&ir, exhausted.metadata)
+                .unwrap_or_else(span::Span::dummy),
+            warning_content: Warning::OptimizationFuelExhausted {
+                pass: exhausted.pass.to_string(),
+                function: exhausted.function.clone(),
+            },
+        });
+    }
+
+    // Failing to write to the cache isn't an error, the passes will just be run again next time.
+    if let (Some(cache), Some(keys)) = (&ir_cache, &ir_cache_keys) {
+        let _ = cache.store(&ir, keys);
+    }
+
+    if emit.ir {
+        emitted.push(EmittedArtifact {
+            path: PathBuf::from("final.ir"),
+            contents: sway_ir::printer::to_string(&ir),
+        });
+    }
+
+    let mut final_asm = check!(
         compile_ir_to_asm(&ir, Some(build_config)),
         return err(warnings, errors),
         warnings,
         errors
     );
 
+    if emit.asm {
+        emitted.push(EmittedArtifact {
+            path: PathBuf::from("final.asm"),
+            contents: final_asm.to_string(),
+        });
+    }
+    if emit.explorer {
+        emitted.extend(explorer::render(&ir, &snapshots, &final_asm));
+    }
+    if let Some(span) = &emit.function {
+        emitted.push(EmittedArtifact {
+            path: PathBuf::from("function").join("final.ir"),
+            contents: function_ir_string(&ir, &mut function_md_mgr, span),
+        });
+        emitted.push(EmittedArtifact {
+            path: PathBuf::from("function").join("final.asm"),
+            contents: function_asm_string(&final_asm, span),
+        });
+    }
+    // The asm functions are named after the IR functions they were generated from.
+    let estimates = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .map(|function| {
+            let estimate = sway_ir::estimate_gas(&ir, &function);
+            (function.get_name(&ir).to_string(), estimate)
+        })
+        .collect::<HashMap<_, _>>();
+    for function_size in &mut final_asm.function_sizes {
+        if let Some(estimate) = estimates.get(&function_size.name) {
+            function_size.estimated_gas = *estimate;
+        }
+    }
+    final_asm.emitted = emitted;
+    final_asm.coverage_points = coverage_points;
+    final_asm.pass_pipeline = pass_pipeline;
+    final_asm.ir_abi = IrAbi::from_ir(&ir, &mut MetadataManager::default());
+    final_asm.ir_abi.encoding = abi_encoding;
+
     ok(final_asm, warnings, errors)
 }
 
+/// The IR of each function generated for the declaration at `span`, of which there is one per
+/// monomorphized copy of a generic function.
+fn function_ir_string(ir: &Context, md_mgr: &mut MetadataManager, span: &span::Span) -> String {
+    ir.module_iter()
+        .flat_map(|module| module.function_iter(ir))
+        .filter(|function| {
+            md_mgr
+                .md_to_span(ir, function.get_metadata(ir))
+                .map_or(false, |fn_span| {
+                    span_encloses(span, &fn_span) && span_encloses(&fn_span, span)
+                })
+        })
+        .map(|function| sway_ir::printer::function_to_string(ir, function))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The ASM ops generated for the source within `span`, wherever they were inlined, along with
+/// their offset within the program.
+fn function_asm_string(asm: &FinalizedAsm, span: &span::Span) -> String {
+    match &asm.program_section {
+        asm_generation::InstructionSet::Fuel { ops } => ops
+            .iter()
+            .enumerate()
+            .filter(|(_, op)| {
+                op.owning_span
+                    .as_ref()
+                    .map_or(false, |op_span| span_encloses(span, op_span))
+            })
+            .map(|(idx, op)| format!("{idx:>6}  {op}\n"))
+            .collect(),
+        // Other targets don't record where their ops came from.
+        _ => String::new(),
+    }
+}
+
+/// Whether `inner` lies within `outer`.  Only the locations are compared since the source of
+/// spans recovered from IR metadata is read back from disk.
+fn span_encloses(outer: &span::Span, inner: &span::Span) -> bool {
+    outer.path() == inner.path() && outer.start() <= inner.start() && inner.end() <= outer.end()
+}
+
+/// The salt for the keys of the cached optimised functions, made from the passes to be run and
+/// the versions of the compiler and of the rustc which built it.
+fn ir_cache_salt(pass_group: &PassGroup) -> String {
+    format!(
+        "sway {}\n{}\n{}",
+        env!("CARGO_PKG_VERSION"),
+        env!("SWAY_RUSTC_VERSION"),
+        pass_group.flatten_pass_group().join(",")
+    )
+}
+
 /// Given input Sway source code, compile to [CompiledBytecode], containing the asm in bytecode form.
 pub fn compile_to_bytecode(
     engines: Engines<'_>,
@@ -886,6 +1360,42 @@ fn test_unary_ordering() {
     };
 }
 
+#[test]
+fn test_module_cache_reuses_unchanged_modules() {
+    let path = Arc::new(PathBuf::from("/cached/src/lib.sw"));
+    let handler = Handler::default();
+    let mut cache = ModuleCache::default();
+    let source = "library;\n\nfn f() {}\n";
+
+    let first: Arc<str> = Arc::from(source);
+    cache
+        .parse_file(&handler, first.clone(), path.clone())
+        .unwrap();
+    assert_eq!(cache.len(), 1);
+
+    // An identical source reuses the module parsed from the first one.
+    let module = cache
+        .parse_file(&handler, Arc::from(source), path.clone())
+        .unwrap();
+    assert!(Arc::ptr_eq(module.value.span().src(), &first));
+
+    // A changed source is parsed again and replaces the cached module.
+    let edited: Arc<str> = Arc::from("library;\n\nfn g() {}\n");
+    let module = cache
+        .parse_file(&handler, edited.clone(), path.clone())
+        .unwrap();
+    assert!(Arc::ptr_eq(module.value.span().src(), &edited));
+    assert_eq!(cache.len(), 1);
+
+    // Modules with diagnostics are not cached.
+    assert!(cache
+        .parse_file(&handler, Arc::from("library;\n\nfn {"), path)
+        .is_err());
+    assert!(cache.is_empty());
+    let (errors, _) = handler.consume();
+    assert!(!errors.is_empty());
+}
+
 /// Return an irrecoverable compile result deduping any errors and warnings.
 fn deduped_err<T>(warnings: Vec<CompileWarning>, errors: Vec<CompileError>) -> CompileResult<T> {
     err(dedup_unsorted(warnings), dedup_unsorted(errors))
@@ -0,0 +1,288 @@
+//! The registry of lints, and the scoping of their levels with the `#[allow(..)]`, `#[warn(..)]`
+//! and `#[deny(..)]` attributes.
+//!
+//! Each lint groups a set of warnings under a name. The level of a lint can be set for a whole
+//! module, as in `#![deny(dead_code)]`, or for a single item, as in `#[allow(dead_code)]`, where
+//! the innermost attribute takes precedence. Warnings of allowed lints are dropped, and warnings
+//! of denied lints are reported as errors.
+
+use crate::{
+    language::parsed::{AstNodeContent, Declaration, ImplItem, ParseModule, ParseProgram},
+    transform::{AttributeKind, AttributesMap},
+};
+use std::sync::Arc;
+use sway_error::{
+    error::CompileError,
+    warning::{CompileWarning, Warning},
+};
+use sway_types::{
+    constants::{ALLOW_DEAD_CODE_NAME, ALLOW_DEPRECATED_NAME},
+    Span,
+};
+
+/// The level of a lint, which decides how its warnings are reported.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum LintLevel {
+    /// The warnings are not reported.
+    Allow,
+    /// The warnings are reported as warnings.
+    Warn,
+    /// The warnings are reported as errors.
+    Deny,
+}
+
+/// A named group of warnings whose level can be controlled with attributes.
+#[derive(Clone, Copy, Debug)]
+pub struct Lint {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// The level of the lint where no attribute sets it.
+    pub default_level: LintLevel,
+}
+
+/// All the lints known to the compiler.
+pub const LINTS: &[Lint] = &[
+    Lint {
+        name: ALLOW_DEAD_CODE_NAME,
+        description: "Declarations, fields and variants which are never used.",
+        default_level: LintLevel::Warn,
+    },
+    Lint {
+        name: ALLOW_DEPRECATED_NAME,
+        description: "Uses of items marked as `#[deprecated]`.",
+        default_level: LintLevel::Warn,
+    },
+    Lint {
+        name: "unreachable_code",
+        description: "Code and match arms which can never be reached.",
+        default_level: LintLevel::Warn,
+    },
+    Lint {
+        name: "unused_return_value",
+        description: "Non-unit values of expressions which are discarded.",
+        default_level: LintLevel::Warn,
+    },
+    Lint {
+        name: "loss_of_precision",
+        description: "Casts to a smaller integer type.",
+        default_level: LintLevel::Warn,
+    },
+    Lint {
+        name: "shadowing",
+        description: "Declarations which shadow other symbols or reserved registers.",
+        default_level: LintLevel::Warn,
+    },
+    Lint {
+        name: "non_idiomatic_names",
+        description: "Names which do not follow the naming conventions of their kind of item.",
+        default_level: LintLevel::Warn,
+    },
+    Lint {
+        name: "effect_after_interaction",
+        description: "Storage effects after external contract interactions.",
+        default_level: LintLevel::Warn,
+    },
+];
+
+/// Returns the lint with the given name, if there is one.
+pub fn lint_by_name(name: &str) -> Option<&'static Lint> {
+    LINTS.iter().find(|lint| lint.name == name)
+}
+
+/// Returns the lint the given warning belongs to, or `None` if its level cannot be controlled.
+pub fn lint_of(warning: &Warning) -> Option<&'static Lint> {
+    use Warning::*;
+    let name = match warning {
+        DeadDeclaration
+        | DeadEnumDeclaration
+        | DeadFunctionDeclaration
+        | DeadStructDeclaration
+        | DeadTrait
+        | DeadEnumVariant { .. }
+        | DeadMethod
+        | StructFieldNeverRead
+        | DeadStorageDeclaration
+        | DeadStorageDeclarationForFunction { .. } => ALLOW_DEAD_CODE_NAME,
+        UsingDeprecated { .. } => ALLOW_DEPRECATED_NAME,
+        UnreachableCode | MatchExpressionUnreachableArm => "unreachable_code",
+        UnusedReturnValue { .. } => "unused_return_value",
+        LossOfPrecision { .. } => "loss_of_precision",
+        ShadowsOtherSymbol { .. } | ShadowingReservedRegister { .. } => "shadowing",
+        NonClassCaseStructName { .. }
+        | NonClassCaseTypeParameter { .. }
+        | NonClassCaseTraitName { .. }
+        | NonClassCaseEnumName { .. }
+        | NonClassCaseEnumVariantName { .. }
+        | NonSnakeCaseStructFieldName { .. }
+        | NonSnakeCaseFunctionName { .. }
+        | NonScreamingSnakeCaseConstName { .. } => "non_idiomatic_names",
+        EffectAfterInteraction { .. } => "effect_after_interaction",
+        SimilarMethodFound { .. }
+        | OverridingTraitImplementation
+        | UnrecognizedAttribute { .. }
+        | AttributeExpectedNumberOfArguments { .. }
+        | UnexpectedAttributeArgumentValue { .. }
+        | OptimizationFuelExhausted { .. } => return None,
+    };
+    lint_by_name(name)
+}
+
+/// A lint level set by an attribute for the code within `span`.
+#[derive(Clone, Debug)]
+struct LintScope {
+    span: Span,
+    lint: &'static Lint,
+    level: LintLevel,
+}
+
+/// The lint levels set by the attributes of a program.
+#[derive(Clone, Debug, Default)]
+pub struct LintLevels {
+    scopes: Vec<LintScope>,
+}
+
+impl LintLevels {
+    /// Collects the lint levels set by the attributes of the modules and items of `program`.
+    pub fn from_program(program: &ParseProgram) -> Self {
+        let mut levels = LintLevels::default();
+        levels.collect_module(&program.root);
+        levels
+    }
+
+    fn collect_module(&mut self, module: &ParseModule) {
+        // The span of a module is empty, so the module attributes apply to its entire source.
+        let src = module.span.src();
+        if let Some(span) = Span::new(src.clone(), 0, src.len(), module.span.path().cloned()) {
+            self.collect_attributes(&module.attributes, &span);
+        }
+        for node in &module.tree.root_nodes {
+            if let AstNodeContent::Declaration(decl) = &node.content {
+                self.collect_declaration(decl);
+            }
+        }
+        for (_, submodule) in &module.submodules {
+            self.collect_module(&submodule.module);
+        }
+    }
+
+    fn collect_declaration(&mut self, decl: &Declaration) {
+        match decl {
+            Declaration::FunctionDeclaration(decl) => {
+                self.collect_attributes(&decl.attributes, &decl.span)
+            }
+            Declaration::TraitDeclaration(decl) => {
+                self.collect_attributes(&decl.attributes, &decl.span);
+                for method in &decl.methods {
+                    self.collect_attributes(&method.attributes, &method.span);
+                }
+            }
+            Declaration::StructDeclaration(decl) => {
+                self.collect_attributes(&decl.attributes, &decl.span);
+                for field in &decl.fields {
+                    self.collect_attributes(&field.attributes, &field.span);
+                }
+            }
+            Declaration::EnumDeclaration(decl) => {
+                self.collect_attributes(&decl.attributes, &decl.span);
+                for variant in &decl.variants {
+                    self.collect_attributes(&variant.attributes, &variant.span);
+                }
+            }
+            Declaration::ImplTrait(decl) => self.collect_impl_items(&decl.items),
+            Declaration::ImplSelf(decl) => self.collect_impl_items(&decl.items),
+            Declaration::AbiDeclaration(decl) => {
+                self.collect_attributes(&decl.attributes, &decl.span);
+                for method in &decl.methods {
+                    self.collect_attributes(&method.attributes, &method.span);
+                }
+            }
+            Declaration::ConstantDeclaration(decl) => {
+                self.collect_attributes(&decl.attributes, &decl.span)
+            }
+            Declaration::StorageDeclaration(decl) => {
+                self.collect_attributes(&decl.attributes, &decl.span);
+                for field in &decl.fields {
+                    self.collect_attributes(&field.attributes, &field.span);
+                }
+            }
+            Declaration::VariableDeclaration(_) => {}
+        }
+    }
+
+    fn collect_impl_items(&mut self, items: &[ImplItem]) {
+        for item in items {
+            match item {
+                ImplItem::Fn(method) => self.collect_attributes(&method.attributes, &method.span),
+            }
+        }
+    }
+
+    fn collect_attributes(&mut self, attributes: &AttributesMap, span: &Span) {
+        for (kind, level) in [
+            (AttributeKind::Allow, LintLevel::Allow),
+            (AttributeKind::Warn, LintLevel::Warn),
+            (AttributeKind::Deny, LintLevel::Deny),
+        ] {
+            let args = attributes
+                .get(&kind)
+                .into_iter()
+                .flatten()
+                .flat_map(|attribute| &attribute.args);
+            for arg in args {
+                // Unknown lint names have already been reported while converting the attributes.
+                if let Some(lint) = lint_by_name(arg.as_str()) {
+                    self.scopes.push(LintScope {
+                        span: span.clone(),
+                        lint,
+                        level,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Returns the level of `lint` for the code at `span`.
+    pub fn level(&self, lint: &Lint, span: &Span) -> LintLevel {
+        // Scopes either nest or are disjoint, so the innermost scope is the one starting last.
+        // Of the scopes of a single item, the last one collected wins.
+        self.scopes
+            .iter()
+            .filter(|scope| scope.lint.name == lint.name && contains(&scope.span, span))
+            .max_by_key(|scope| scope.span.start())
+            .map_or(lint.default_level, |scope| scope.level)
+    }
+
+    /// Applies the lint levels to `warnings`, returning the warnings which are still to be reported
+    /// as such. The warnings of denied lints are turned into errors and added to `errors`.
+    pub fn apply(
+        &self,
+        warnings: Vec<CompileWarning>,
+        errors: &mut Vec<CompileError>,
+    ) -> Vec<CompileWarning> {
+        warnings
+            .into_iter()
+            .filter(|warning| match lint_of(&warning.warning_content) {
+                None => true,
+                Some(lint) => match self.level(lint, &warning.span) {
+                    LintLevel::Allow => false,
+                    LintLevel::Warn => true,
+                    LintLevel::Deny => {
+                        errors.push(CompileError::DeniedLint {
+                            lint: lint.name.to_string(),
+                            warning: warning.to_friendly_warning_string(),
+                            span: warning.span.clone(),
+                        });
+                        false
+                    }
+                },
+            })
+            .collect()
+    }
+}
+
+/// Returns true if `inner` lies within `outer` in the same source.
+fn contains(outer: &Span, inner: &Span) -> bool {
+    Arc::ptr_eq(outer.src(), inner.src())
+        && outer.start() <= inner.start()
+        && inner.end() <= outer.end()
+}
@@ -3,7 +3,9 @@ use crate::{
     language::{ty::TyFunctionDeclaration, Inline, Purity},
 };
 
-use sway_ir::{Context, MetadataIndex, Metadatum, Value};
+use sway_ir::{
+    Context, MetadataIndex, Metadatum, SourceSpan, Value, INLINE_TAG, SPAN_TAG, STORAGE_KEY_TAG,
+};
 use sway_types::Span;
 
 use std::{collections::HashMap, path::PathBuf, rc::Rc, sync::Arc};
@@ -53,7 +55,7 @@ impl MetadataManager {
                 // Create a new span and save it in the cache.
                 md_idx
                     .get_content(context)
-                    .unwrap_struct("span", 3)
+                    .unwrap_struct(SPAN_TAG, 3)
                     .and_then(|fields| {
                         let (path, src) = self.md_to_file_location(context, &fields[0])?;
                         let start = fields[1].unwrap_integer()?;
@@ -68,6 +70,17 @@ impl MetadataManager {
         })
     }
 
+    /// Convert a span held within another metadatum, such as the declaration of the function an
+    /// instruction was inlined from, to a [`Span`].
+    pub(crate) fn source_span_to_span(
+        &mut self,
+        context: &Context,
+        span: &SourceSpan,
+    ) -> Option<Span> {
+        let (path, src) = self.md_to_file_location(context, &Metadatum::Index(span.file))?;
+        Span::new(src, span.start as usize, span.end as usize, Some(path))
+    }
+
     pub(crate) fn md_to_test_decl_index(
         &mut self,
         context: &Context,
@@ -132,7 +145,7 @@ impl MetadataManager {
                 // Create a new storage key and save it in the cache.
                 md_idx
                     .get_content(context)
-                    .unwrap_struct("state_index", 1)
+                    .unwrap_struct(STORAGE_KEY_TAG, 1)
                     .and_then(|fields| {
                         let key = fields[0].unwrap_integer()?;
 
@@ -145,11 +158,8 @@ impl MetadataManager {
     }
 
     /// Gets Inline information from metadata index.
-    /// TODO: We temporarily allow this because we need this
-    /// in the sway-ir inliner, but cannot access it. So the code
-    /// itself has been (modified and) copied there. When we decide
-    /// on the right place for Metadata to be
-    /// (and how it can be accessed form sway-ir), this will be fixed.
+    /// The sway-ir inliner reads it with `Function::get_inline` instead, so this is unused for
+    /// now.
     #[allow(dead_code)]
     pub(crate) fn md_to_inline(
         &mut self,
@@ -161,7 +171,7 @@ impl MetadataManager {
                 // Create a new inline and save it in the cache.
                 md_idx
                     .get_content(context)
-                    .unwrap_struct("inline", 1)
+                    .unwrap_struct(INLINE_TAG, 1)
                     .and_then(|fields| fields[0].unwrap_string())
                     .and_then(|inline_str| {
                         let inline = match inline_str {
@@ -245,7 +255,7 @@ impl MetadataManager {
                 let file_location_md_idx = self.file_location_to_md(context, path_buf)?;
                 let md_idx = MetadataIndex::new_struct(
                     context,
-                    "span",
+                    SPAN_TAG,
                     vec![
                         Metadatum::Index(file_location_md_idx),
                         Metadatum::Integer(span.start() as u64),
@@ -292,7 +302,7 @@ impl MetadataManager {
                 // Create new metadatum.
                 let md_idx = MetadataIndex::new_struct(
                     context,
-                    "state_index",
+                    STORAGE_KEY_TAG,
                     vec![Metadatum::Integer(storage_key)],
                 );
 
@@ -350,7 +360,7 @@ impl MetadataManager {
                     };
                     let md_idx = MetadataIndex::new_struct(
                         context,
-                        "inline",
+                        INLINE_TAG,
                         vec![Metadatum::String(field.to_owned())],
                     );
 
@@ -361,6 +371,24 @@ impl MetadataManager {
         )
     }
 
+    /// Inserts the lines of a doc comment into metadata, or nothing if there are none.
+    pub(crate) fn doc_comment_to_md(
+        &mut self,
+        context: &mut Context,
+        lines: &[&str],
+    ) -> Option<MetadataIndex> {
+        (!lines.is_empty()).then(|| {
+            MetadataIndex::new_struct(
+                context,
+                "doc_comment",
+                lines
+                    .iter()
+                    .map(|line| Metadatum::String(line.to_string()))
+                    .collect(),
+            )
+        })
+    }
+
     fn file_location_to_md(
         &mut self,
         context: &mut Context,
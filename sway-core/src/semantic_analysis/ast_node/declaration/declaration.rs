@@ -40,6 +40,9 @@ impl ty::TyDeclaration {
                     warnings,
                     errors
                 );
+                warnings.extend(
+                    ctx.type_deprecation_warnings(type_ascription.type_id, &type_ascription.span),
+                );
                 let mut ctx = ctx
                     .with_type_annotation(type_ascription.type_id)
                     .with_help_text(
@@ -98,6 +101,9 @@ impl ty::TyDeclaration {
                     warnings,
                     errors,
                 );
+                warnings.extend(
+                    ctx.type_deprecation_warnings(type_ascription.type_id, &type_ascription.span),
+                );
 
                 let mut ctx = ctx
                     .by_ref()
@@ -81,6 +81,7 @@ impl ty::TyEnumVariant {
             warnings,
             errors,
         );
+        warnings.extend(ctx.type_deprecation_warnings(type_argument.type_id, &type_argument.span));
         ok(
             ty::TyEnumVariant {
                 name: variant.name.clone(),
@@ -12,7 +12,7 @@ use crate::{
     semantic_analysis::*,
     type_system::*,
 };
-use sway_types::{style::is_snake_case, Spanned};
+use sway_types::{constants::ALLOW_DEPRECATED_NAME, style::is_snake_case, Spanned};
 
 impl ty::TyFunctionDeclaration {
     pub fn type_check(
@@ -63,6 +63,7 @@ impl ty::TyFunctionDeclaration {
             .by_ref()
             .scoped(&mut fn_namespace)
             .with_purity(purity)
+            .with_allow_deprecated(attributes.allows(ALLOW_DEPRECATED_NAME))
             .disallow_functions();
 
         // Type check the type parameters. This will also insert them into the
@@ -100,6 +101,7 @@ impl ty::TyFunctionDeclaration {
             warnings,
             errors,
         );
+        warnings.extend(ctx.type_deprecation_warnings(return_type.type_id, &return_type.span));
 
         // type check the function body
         //
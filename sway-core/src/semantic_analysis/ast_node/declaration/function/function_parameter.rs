@@ -40,6 +40,7 @@ impl ty::TyFunctionParameter {
             warnings,
             errors,
         );
+        warnings.extend(ctx.type_deprecation_warnings(type_argument.type_id, &type_argument.span));
 
         if !is_from_method {
             let mutability = ty::VariableMutability::new_from_ref_mut(is_reference, is_mutable);
@@ -96,6 +97,7 @@ impl ty::TyFunctionParameter {
             warnings,
             errors,
         );
+        warnings.extend(ctx.type_deprecation_warnings(type_argument.type_id, &type_argument.span));
 
         let typed_parameter = ty::TyFunctionParameter {
             name,
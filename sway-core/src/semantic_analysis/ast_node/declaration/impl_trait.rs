@@ -1,7 +1,10 @@
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+};
 
 use sway_error::error::{CompileError, InterfaceName};
-use sway_types::{Ident, Span, Spanned};
+use sway_types::{constants::SELECTOR_VALUE_NAME, Ident, Span, Spanned};
 
 use crate::{
     decl_engine::*,
@@ -13,6 +16,7 @@ use crate::{
         *,
     },
     semantic_analysis::{Mode, TypeCheckContext},
+    transform::{AttributeKind::DocComment, AttributesMap},
     type_system::*,
 };
 
@@ -909,6 +913,33 @@ fn type_check_impl_method(
         (true, true) | (false, false) => (), // no payability mismatch
     }
 
+    // check the selector given by the method signature, if any, is repeated by the implementation
+    // so that callers and the contract agree on it
+    let selector_value = |attributes: &AttributesMap| {
+        attributes
+            .selector()
+            .and_then(|attribute| attribute.arg_value(SELECTOR_VALUE_NAME))
+            .map(|value| value.to_string())
+    };
+    if selector_value(&impl_method_signature.attributes) != selector_value(&impl_method.attributes)
+    {
+        errors.push(CompileError::TraitImplSelectorMismatch {
+            fn_name: impl_method.name.clone(),
+            interface_name: interface_name(),
+            span: impl_method.span.clone(),
+        });
+    }
+
+    // ABI methods are usually documented in the ABI declaration, so the implementation
+    // inherits that documentation unless it has its own
+    if is_contract && !impl_method.attributes.contains_key(&DocComment) {
+        if let Some(doc_comments) = impl_method_signature.attributes.get(&DocComment) {
+            let mut attributes = impl_method.attributes.inner().clone();
+            attributes.insert(DocComment, doc_comments.clone());
+            impl_method.attributes = AttributesMap::new(Arc::new(attributes));
+        }
+    }
+
     if !type_engine
         .get(impl_method.return_type.type_id)
         .eq(&type_engine.get(impl_method_signature.return_type), engines)
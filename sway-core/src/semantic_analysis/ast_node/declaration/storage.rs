@@ -6,6 +6,7 @@ use crate::{
     },
     language::ty,
     metadata::MetadataManager,
+    storage_layout::{StorageLayout, StorageLayoutField},
     Engines,
 };
 use sway_error::error::CompileError;
@@ -19,9 +20,9 @@ impl ty::TyStorageDeclaration {
         context: &mut Context,
         md_mgr: &mut MetadataManager,
         module: Module,
-    ) -> CompileResult<Vec<StorageSlot>> {
+    ) -> CompileResult<(Vec<StorageSlot>, StorageLayout)> {
         let mut errors = vec![];
-        let storage_slots = self
+        let (storage_slots, fields): (Vec<_>, Vec<_>) = self
             .fields
             .iter()
             .enumerate()
@@ -35,11 +36,17 @@ impl ty::TyStorageDeclaration {
                 )
             })
             .filter_map(|s| s.map_err(|e| errors.push(e)).ok())
-            .flatten()
-            .collect::<Vec<_>>();
+            .unzip();
 
         match errors.is_empty() {
-            true => ok(storage_slots, vec![], vec![]),
+            true => ok(
+                (
+                    storage_slots.into_iter().flatten().collect(),
+                    StorageLayout { fields },
+                ),
+                vec![],
+                vec![],
+            ),
             false => err(vec![], errors),
         }
     }
@@ -53,7 +60,7 @@ impl ty::TyStorageField {
         md_mgr: &mut MetadataManager,
         module: Module,
         ix: &StateIndex,
-    ) -> Result<Vec<StorageSlot>, CompileError> {
+    ) -> Result<(Vec<StorageSlot>, StorageLayoutField), CompileError> {
         compile_constant_expression_to_constant(
             engines,
             context,
@@ -63,6 +70,16 @@ impl ty::TyStorageField {
             None,
             &self.initializer,
         )
-        .map(|constant| serialize_to_storage_slots(&constant, context, ix, &constant.ty, &[]))
+        .map(|constant| {
+            let storage_slots =
+                serialize_to_storage_slots(&constant, context, ix, &constant.ty, &[]);
+            let field = StorageLayoutField {
+                name: self.name.as_str().to_string(),
+                type_name: engines.help_out(self.type_argument.type_id).to_string(),
+                ir_type: constant.ty.as_string(context),
+                slots: storage_slots.iter().map(|slot| *slot.key()).collect(),
+            };
+            (storage_slots, field)
+        })
     }
 }
@@ -83,6 +83,7 @@ impl ty::TyStructField {
             warnings,
             errors,
         );
+        warnings.extend(ctx.type_deprecation_warnings(type_argument.type_id, &type_argument.span));
         let field = ty::TyStructField {
             name: field.name,
             span: field.span,
@@ -56,6 +56,7 @@ impl ty::TyTraitFn {
             warnings,
             errors,
         );
+        warnings.extend(ctx.type_deprecation_warnings(return_type, &return_type_span));
 
         let trait_fn = ty::TyTraitFn {
             name,
@@ -64,6 +64,12 @@ impl ty::TyIntrinsicFunctionKind {
                 type_check_ptr_ops(ctx, kind, arguments, type_arguments, span)
             }
             Intrinsic::Smo => type_check_smo(ctx, kind, arguments, type_arguments, span),
+            Intrinsic::ContextGas | Intrinsic::GlobalGas => {
+                type_check_gas(ctx, kind, arguments, type_arguments, span)
+            }
+            Intrinsic::GasCheckpoint => {
+                type_check_gas_checkpoint(ctx, kind, arguments, type_arguments, span)
+            }
         }
     }
 }
@@ -1436,3 +1442,118 @@ fn type_check_smo(
         errors,
     )
 }
+
+/// Signature: `__context_gas() -> u64`, `__global_gas() -> u64`
+/// Description: Return the gas remaining in the current context, or globally, i.e. the `$cgas`
+/// or `$ggas` register.
+/// Constraints: None.
+fn type_check_gas(
+    ctx: TypeCheckContext,
+    kind: sway_ast::Intrinsic,
+    arguments: Vec<Expression>,
+    type_arguments: Vec<TypeArgument>,
+    span: Span,
+) -> CompileResult<(ty::TyIntrinsicFunctionKind, TypeId)> {
+    let type_engine = ctx.type_engine;
+    let decl_engine = ctx.decl_engine;
+
+    let warnings = vec![];
+    let mut errors = vec![];
+
+    if !arguments.is_empty() {
+        errors.push(CompileError::IntrinsicIncorrectNumArgs {
+            name: kind.to_string(),
+            expected: 0,
+            span,
+        });
+        return err(warnings, errors);
+    }
+
+    if !type_arguments.is_empty() {
+        errors.push(CompileError::IntrinsicIncorrectNumTArgs {
+            name: kind.to_string(),
+            expected: 0,
+            span,
+        });
+        return err(warnings, errors);
+    }
+
+    ok(
+        (
+            ty::TyIntrinsicFunctionKind {
+                kind,
+                arguments: vec![],
+                type_arguments: vec![],
+                span,
+            },
+            type_engine.insert(
+                decl_engine,
+                TypeInfo::UnsignedInteger(IntegerBits::SixtyFour),
+            ),
+        ),
+        warnings,
+        errors,
+    )
+}
+
+/// Signature: `__gas_checkpoint(id: u64)`
+/// Description: Marks a point at which the remaining gas is logged along with `id`, if gas
+/// checkpoints are enabled for the build.  Otherwise does nothing.
+/// Constraints: None.
+fn type_check_gas_checkpoint(
+    mut ctx: TypeCheckContext,
+    kind: sway_ast::Intrinsic,
+    arguments: Vec<Expression>,
+    type_arguments: Vec<TypeArgument>,
+    span: Span,
+) -> CompileResult<(ty::TyIntrinsicFunctionKind, TypeId)> {
+    let type_engine = ctx.type_engine;
+    let decl_engine = ctx.decl_engine;
+
+    let mut warnings = vec![];
+    let mut errors = vec![];
+
+    if arguments.len() != 1 {
+        errors.push(CompileError::IntrinsicIncorrectNumArgs {
+            name: kind.to_string(),
+            expected: 1,
+            span,
+        });
+        return err(warnings, errors);
+    }
+
+    if !type_arguments.is_empty() {
+        errors.push(CompileError::IntrinsicIncorrectNumTArgs {
+            name: kind.to_string(),
+            expected: 0,
+            span,
+        });
+        return err(warnings, errors);
+    }
+
+    // Type check the argument which is the checkpoint ID, so it has to be a `u64`.
+    let mut ctx = ctx.by_ref().with_type_annotation(type_engine.insert(
+        decl_engine,
+        TypeInfo::UnsignedInteger(IntegerBits::SixtyFour),
+    ));
+    let id = check!(
+        ty::TyExpression::type_check(ctx.by_ref(), arguments[0].clone()),
+        return err(warnings, errors),
+        warnings,
+        errors
+    );
+
+    ok(
+        (
+            ty::TyIntrinsicFunctionKind {
+                kind,
+                arguments: vec![id],
+                type_arguments: vec![],
+                span,
+            },
+            type_engine.insert(decl_engine, TypeInfo::Tuple(vec![])),
+        ),
+        warnings,
+        errors,
+    )
+}
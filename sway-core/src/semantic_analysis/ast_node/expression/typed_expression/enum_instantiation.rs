@@ -28,6 +28,11 @@ pub(crate) fn instantiate_enum(
     let engines = ctx.engines();
 
     let enum_decl = decl_engine.get_enum(enum_decl_ref);
+    warnings.extend(ctx.deprecation_warning(
+        &enum_decl.call_path.suffix,
+        &enum_decl.attributes,
+        call_path_binding.inner.span(),
+    ));
     let enum_variant = check!(
         enum_decl
             .expect_variant_from_name(&enum_variant_name)
@@ -41,6 +41,12 @@ pub(crate) fn instantiate_function_application(
         });
     }
 
+    warnings.extend(ctx.deprecation_warning(
+        &function_decl.name,
+        &function_decl.attributes,
+        call_path_binding.inner.suffix.span(),
+    ));
+
     // check that the number of parameters and the number of the arguments is the same
     check!(
         check_function_arguments_arity(
@@ -60,6 +60,12 @@ pub(crate) fn type_check_method_application(
         return err(warnings, errors);
     }
 
+    warnings.extend(ctx.deprecation_warning(
+        &method.name,
+        &method.attributes,
+        method_name_binding.inner.easy_name().span(),
+    ));
+
     // check the function storage purity
     if !method.is_contract_call {
         // 'method.purity' is that of the callee, 'opts.purity' of the caller.
@@ -92,6 +92,11 @@ pub(crate) fn struct_instantiation(
         warnings,
         errors
     ));
+    warnings.extend(ctx.deprecation_warning(
+        &struct_decl.call_path.suffix,
+        &struct_decl.attributes,
+        call_path_binding.inner.suffix.span(),
+    ));
     let struct_name = struct_decl.call_path.suffix;
     let struct_fields = struct_decl.fields;
     let mut struct_fields = struct_fields;
@@ -619,6 +619,9 @@ fn effects_of_intrinsic(intr: &sway_ast::Intrinsic) -> HashSet<Effect> {
         | Add | Sub | Mul | Div | And | Or | Xor | PtrAdd | PtrSub | GetStorageKey => {
             HashSet::new()
         }
+        // Reading the gas left doesn't touch storage, and a gas checkpoint only emits a log,
+        // which like `Log` has no effect here.
+        ContextGas | GlobalGas | GasCheckpoint => HashSet::new(),
     }
 }
 
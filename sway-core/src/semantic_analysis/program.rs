@@ -1,11 +1,13 @@
 use crate::{
     error::*,
     language::{parsed::ParseProgram, ty},
+    lint::LintLevels,
     metadata::MetadataManager,
     semantic_analysis::{
         namespace::{self, Namespace},
         TypeCheckContext,
     },
+    storage_layout::StorageLayout,
     Engines,
 };
 use sway_ir::{Context, Module};
@@ -34,8 +36,10 @@ impl ty::TyProgram {
                 declarations,
                 configurables,
                 storage_slots: vec![],
+                storage_layout: StorageLayout::default(),
                 logged_types: vec![],
                 messages_types: vec![],
+                lint_levels: LintLevels::from_program(parsed),
             })
         })
     }
@@ -65,7 +69,7 @@ impl ty::TyProgram {
                         ..
                     }) => {
                         let decl = decl_engine.get_storage(decl_id);
-                        let mut storage_slots = check!(
+                        let (mut storage_slots, storage_layout) = check!(
                             decl.get_initialized_storage_slots(engines, context, md_mgr, module),
                             return err(warnings, errors),
                             warnings,
@@ -77,6 +81,7 @@ impl ty::TyProgram {
                         ok(
                             Self {
                                 storage_slots,
+                                storage_layout,
                                 ..self
                             },
                             warnings,
@@ -86,6 +91,7 @@ impl ty::TyProgram {
                     _ => ok(
                         Self {
                             storage_slots: vec![],
+                            storage_layout: StorageLayout::default(),
                             ..self
                         },
                         warnings,
@@ -96,6 +102,7 @@ impl ty::TyProgram {
             _ => ok(
                 Self {
                     storage_slots: vec![],
+                    storage_layout: StorageLayout::default(),
                     ..self
                 },
                 warnings,
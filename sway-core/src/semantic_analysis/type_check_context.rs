@@ -4,13 +4,14 @@ use crate::{
     language::{parsed::TreeType, Purity},
     namespace::Path,
     semantic_analysis::{ast_node::Mode, Namespace},
+    transform::AttributesMap,
     type_system::{
         EnforceTypeArguments, MonomorphizeHelper, SubstTypes, TypeArgument, TypeId, TypeInfo,
     },
     CompileResult, CompileWarning, TypeEngine,
 };
-use sway_error::error::CompileError;
-use sway_types::{span::Span, Ident};
+use sway_error::{error::CompileError, warning::Warning};
+use sway_types::{constants::DEPRECATED_NOTE_NAME, span::Span, Ident};
 
 /// Contextual state tracked and accumulated throughout type-checking.
 pub struct TypeCheckContext<'a> {
@@ -62,6 +63,10 @@ pub struct TypeCheckContext<'a> {
     /// disallowing functions from being defined inside of another function
     /// body).
     disallow_functions: bool,
+
+    /// Indicates whether uses of `#[deprecated]` items are allowed without a warning, i.e. when
+    /// within a function annotated with `#[allow(deprecated)]`.
+    allow_deprecated: bool,
 }
 
 impl<'a> TypeCheckContext<'a> {
@@ -91,6 +96,7 @@ impl<'a> TypeCheckContext<'a> {
             purity: Purity::default(),
             kind: TreeType::Contract,
             disallow_functions: false,
+            allow_deprecated: false,
         }
     }
 
@@ -114,6 +120,7 @@ impl<'a> TypeCheckContext<'a> {
             type_engine: self.type_engine,
             decl_engine: self.decl_engine,
             disallow_functions: self.disallow_functions,
+            allow_deprecated: self.allow_deprecated,
         }
     }
 
@@ -130,6 +137,7 @@ impl<'a> TypeCheckContext<'a> {
             type_engine: self.type_engine,
             decl_engine: self.decl_engine,
             disallow_functions: self.disallow_functions,
+            allow_deprecated: self.allow_deprecated,
         }
     }
 
@@ -206,6 +214,15 @@ impl<'a> TypeCheckContext<'a> {
         }
     }
 
+    /// Map this `TypeCheckContext` instance to a new one where uses of deprecated items do or do
+    /// not produce warnings.
+    pub(crate) fn with_allow_deprecated(self, allow_deprecated: bool) -> Self {
+        Self {
+            allow_deprecated,
+            ..self
+        }
+    }
+
     // A set of accessor methods. We do this rather than making the fields `pub` in order to ensure
     // that these are only updated via the `with_*` methods that produce a new `TypeCheckContext`.
 
@@ -239,6 +256,80 @@ impl<'a> TypeCheckContext<'a> {
 
     // Provide some convenience functions around the inner context.
 
+    /// Returns the warning for using the item `name` at `span`, if the item is marked as
+    /// `#[deprecated]` in its `attributes` and deprecated uses are not allowed in this context.
+    pub(crate) fn deprecation_warning(
+        &self,
+        name: &Ident,
+        attributes: &AttributesMap,
+        span: Span,
+    ) -> Option<CompileWarning> {
+        if self.allow_deprecated {
+            return None;
+        }
+        let note = attributes
+            .deprecated()?
+            .arg_value(DEPRECATED_NOTE_NAME)
+            .map(|note| note.to_string());
+        Some(CompileWarning {
+            span,
+            warning_content: Warning::UsingDeprecated {
+                name: name.clone(),
+                note,
+            },
+        })
+    }
+
+    /// Returns the warnings for the `#[deprecated]` structs and enums named by the type annotation
+    /// at `span`, which resolved to `type_id`, including those among its type arguments.
+    pub(crate) fn type_deprecation_warnings(
+        &self,
+        type_id: TypeId,
+        span: &Span,
+    ) -> Vec<CompileWarning> {
+        let mut warnings = vec![];
+        self.collect_type_deprecation_warnings(type_id, span, &mut warnings);
+        warnings
+    }
+
+    fn collect_type_deprecation_warnings(
+        &self,
+        type_id: TypeId,
+        span: &Span,
+        warnings: &mut Vec<CompileWarning>,
+    ) {
+        let type_info = self.type_engine.get(type_id);
+        // The implementing type may be used within its own impl blocks without warnings.
+        if type_info.eq(&self.type_engine.get(self.self_type), self.engines()) {
+            return;
+        }
+        let (name, attributes, type_parameters) = match type_info {
+            TypeInfo::Struct(decl_ref) => {
+                let decl = self.decl_engine.get_struct(&decl_ref);
+                (decl.call_path.suffix, decl.attributes, decl.type_parameters)
+            }
+            TypeInfo::Enum(decl_ref) => {
+                let decl = self.decl_engine.get_enum(&decl_ref);
+                (decl.call_path.suffix, decl.attributes, decl.type_parameters)
+            }
+            TypeInfo::Tuple(type_arguments) => {
+                for type_argument in type_arguments {
+                    self.collect_type_deprecation_warnings(type_argument.type_id, span, warnings);
+                }
+                return;
+            }
+            TypeInfo::Array(elem_type, _) => {
+                self.collect_type_deprecation_warnings(elem_type.type_id, span, warnings);
+                return;
+            }
+            _ => return,
+        };
+        warnings.extend(self.deprecation_warning(&name, &attributes, span.clone()));
+        for type_parameter in type_parameters {
+            self.collect_type_deprecation_warnings(type_parameter.type_id, span, warnings);
+        }
+    }
+
     /// Short-hand for calling the `monomorphize` function in the type engine
     pub(crate) fn monomorphize<T>(
         &mut self,
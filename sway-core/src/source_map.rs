@@ -20,6 +20,9 @@ pub struct SourceMap {
     paths: Vec<PathBuf>,
     /// Mapping from opcode index to source location
     map: HashMap<usize, SourceMapSpan>,
+    /// The user variables of each function, for debuggers.
+    #[serde(default)]
+    variables: Vec<VariableLocation>,
 }
 impl SourceMap {
     pub fn new() -> Self {
@@ -79,6 +82,52 @@ impl SourceMap {
             (p.to_owned(), sms.range)
         })
     }
+
+    /// Every opcode index with a known source location, along with that location.
+    pub fn addr_spans(&self) -> impl Iterator<Item = (usize, PathBuf, LocationRange)> + '_ {
+        self.map.keys().filter_map(|pc| {
+            self.addr_to_span(*pc)
+                .map(|(path, range)| (*pc, path, range))
+        })
+    }
+
+    /// Records where a user variable is held while its function executes.
+    pub fn insert_variable(&mut self, variable: VariableLocation) {
+        self.variables.push(variable);
+    }
+
+    /// The user variables of the function executing the opcode at `pc`, in declaration order.
+    pub fn variables_at(&self, pc: usize) -> impl Iterator<Item = &VariableLocation> + '_ {
+        self.variables
+            .iter()
+            .filter(move |variable| variable.opcodes.contains(&pc))
+    }
+}
+
+/// A user variable of a function and where it's held while the function executes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VariableLocation {
+    pub name: String,
+    /// The Sway type of the variable, as it would be written in the source.
+    pub ty: String,
+    /// The range of opcode indices of the function declaring the variable.
+    pub opcodes: std::ops::Range<usize>,
+    /// Where the variable is held, or `None` if it was optimized out.
+    pub slot: Option<StackSlot>,
+}
+
+/// A variable held among the locals of its function's stack frame.
+///
+/// The locals are allocated at the top of the stack when the function is entered, so while the
+/// function isn't making a call they start `locals_size` bytes below `$sp`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StackSlot {
+    /// The offset of the variable from the start of the locals, in bytes.
+    pub offset: u64,
+    /// The size of the variable in bytes.
+    pub size: u64,
+    /// The size of all the function's locals in bytes.
+    pub locals_size: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,204 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::fuel_prelude::fuel_types::Bytes32;
+
+/// The storage slots allocated to each field of a contract's `storage` declaration.
+///
+/// An upgraded contract keeps the storage of the contract it replaces, so its layout must be
+/// compatible with the previous one.  See [StorageLayout::incompatibilities].
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct StorageLayout {
+    pub fields: Vec<StorageLayoutField>,
+}
+
+/// A single field of a [StorageLayout].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct StorageLayoutField {
+    pub name: String,
+    /// The Sway type of the field, for display.
+    #[serde(rename = "type")]
+    pub type_name: String,
+    /// The IR type of the field, which determines how its value is spread over the slots.
+    pub ir_type: String,
+    /// The keys of the slots holding the field's initial value.  A `StorageMap` or `StorageVec`
+    /// derives the keys of its elements from these.
+    pub slots: Vec<Bytes32>,
+}
+
+/// A change between two [StorageLayout]s which would leave existing storage misinterpreted.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum StorageLayoutIncompatibility {
+    /// The field is stored in different slots, e.g. because an earlier field was removed.
+    Moved { name: String },
+    /// The field is stored in the same slots but its value is laid out differently.
+    TypeChanged {
+        name: String,
+        previous: String,
+        current: String,
+    },
+    /// A new field is stored in the slots of a previous field of a different type.
+    SlotsReused { name: String, previous_name: String },
+}
+
+impl fmt::Display for StorageLayoutIncompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Moved { name } => write!(f, "storage field `{name}` has moved to other slots"),
+            Self::TypeChanged {
+                name,
+                previous,
+                current,
+            } => write!(
+                f,
+                "storage field `{name}` has changed type from `{previous}` to `{current}`"
+            ),
+            Self::SlotsReused {
+                name,
+                previous_name,
+            } => write!(
+                f,
+                "storage field `{name}` reuses the slots of the previous field `{previous_name}`"
+            ),
+        }
+    }
+}
+
+impl StorageLayout {
+    /// The changes from the `previous` layout which are incompatible with the storage it left
+    /// behind.  New fields in unused slots, fields removed from the end and renamed fields of the
+    /// same type are all compatible.
+    pub fn incompatibilities(&self, previous: &StorageLayout) -> Vec<StorageLayoutIncompatibility> {
+        let mut incompatibilities = Vec::new();
+        for field in &self.fields {
+            match previous.fields.iter().find(|prev| prev.name == field.name) {
+                Some(prev) if prev.slots != field.slots => {
+                    incompatibilities.push(StorageLayoutIncompatibility::Moved {
+                        name: field.name.clone(),
+                    })
+                }
+                Some(prev) if prev.ir_type != field.ir_type => {
+                    incompatibilities.push(StorageLayoutIncompatibility::TypeChanged {
+                        name: field.name.clone(),
+                        previous: prev.type_name.clone(),
+                        current: field.type_name.clone(),
+                    })
+                }
+                Some(_) => (),
+                None => {
+                    let reused = previous.fields.iter().find(|prev| {
+                        prev.ir_type != field.ir_type
+                            && prev.slots.iter().any(|slot| field.slots.contains(slot))
+                    });
+                    if let Some(prev) = reused {
+                        incompatibilities.push(StorageLayoutIncompatibility::SlotsReused {
+                            name: field.name.clone(),
+                            previous_name: prev.name.clone(),
+                        })
+                    }
+                }
+            }
+        }
+        incompatibilities
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, ty: &str, slot: u8) -> StorageLayoutField {
+        StorageLayoutField {
+            name: name.to_string(),
+            type_name: ty.to_string(),
+            ir_type: ty.to_string(),
+            slots: vec![Bytes32::new([slot; 32])],
+        }
+    }
+
+    fn layout(fields: &[(&str, &str)]) -> StorageLayout {
+        StorageLayout {
+            fields: fields
+                .iter()
+                .enumerate()
+                .map(|(idx, (name, ty))| field(name, ty, idx as u8))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn compatible_changes() {
+        let previous = layout(&[("a", "u64"), ("b", "bool")]);
+        assert!(previous.incompatibilities(&previous).is_empty());
+        // Appended and renamed fields, and fields removed from the end.
+        let appended = layout(&[("a", "u64"), ("b", "bool"), ("c", "b256")]);
+        assert!(appended.incompatibilities(&previous).is_empty());
+        let renamed = layout(&[("a", "u64"), ("flag", "bool")]);
+        assert!(renamed.incompatibilities(&previous).is_empty());
+        let truncated = layout(&[("a", "u64")]);
+        assert!(truncated.incompatibilities(&previous).is_empty());
+    }
+
+    #[test]
+    fn reordered_fields_have_moved() {
+        let previous = layout(&[("a", "u64"), ("b", "u64")]);
+        let reordered = layout(&[("b", "u64"), ("a", "u64")]);
+        assert_eq!(
+            reordered.incompatibilities(&previous),
+            [
+                StorageLayoutIncompatibility::Moved {
+                    name: "b".to_string()
+                },
+                StorageLayoutIncompatibility::Moved {
+                    name: "a".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn removed_fields_move_those_after_them() {
+        let previous = layout(&[("a", "u64"), ("b", "bool"), ("c", "bool")]);
+        let removed = layout(&[("b", "bool"), ("c", "bool")]);
+        assert_eq!(
+            removed.incompatibilities(&previous),
+            [
+                StorageLayoutIncompatibility::Moved {
+                    name: "b".to_string()
+                },
+                StorageLayoutIncompatibility::Moved {
+                    name: "c".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn retyped_fields() {
+        let previous = layout(&[("a", "u64"), ("b", "bool")]);
+        let retyped = layout(&[("a", "u64"), ("b", "u64")]);
+        assert_eq!(
+            retyped.incompatibilities(&previous),
+            [StorageLayoutIncompatibility::TypeChanged {
+                name: "b".to_string(),
+                previous: "bool".to_string(),
+                current: "u64".to_string(),
+            }]
+        );
+        assert_eq!(
+            retyped.incompatibilities(&previous)[0].to_string(),
+            "storage field `b` has changed type from `bool` to `u64`"
+        );
+
+        // A renamed field of another type reuses the slots of the previous one.
+        let replaced = layout(&[("a", "u64"), ("c", "b256")]);
+        assert_eq!(
+            replaced.incompatibilities(&previous),
+            [StorageLayoutIncompatibility::SlotsReused {
+                name: "c".to_string(),
+                previous_name: "b".to_string(),
+            }]
+        );
+    }
+}
@@ -20,9 +20,14 @@
 //!
 //!   #[foo(bar, bar)]
 
-use sway_types::{constants::ALLOW_DEAD_CODE_NAME, Ident, Span};
+use crate::{language::Literal, lint::LINTS};
 
-use std::{collections::HashMap, hash::Hash, sync::Arc};
+use sway_types::{
+    constants::{DEPRECATED_NOTE_NAME, SELECTOR_VALUE_NAME},
+    Ident, Span, Spanned,
+};
+
+use std::{collections::HashMap, fmt, hash::Hash, sync::Arc};
 
 /// An attribute has a name (i.e "doc", "storage"),
 /// a vector of possible arguments and
@@ -30,10 +35,49 @@ use std::{collections::HashMap, hash::Hash, sync::Arc};
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Attribute {
     pub name: Ident,
-    pub args: Vec<Ident>,
+    pub args: Vec<AttributeArg>,
+    pub span: Span,
+}
+
+impl Attribute {
+    /// Returns the value given to the argument `name`, as in `#[attribute(name = value)]`.
+    pub fn arg_value(&self, name: &str) -> Option<&Literal> {
+        self.args
+            .iter()
+            .find(|arg| arg.name.as_str() == name)
+            .and_then(|arg| arg.value.as_ref())
+    }
+}
+
+/// An argument of an attribute, which may be given a value as in `#[attribute(name = value)]`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AttributeArg {
+    pub name: Ident,
+    pub value: Option<Literal>,
     pub span: Span,
 }
 
+impl AttributeArg {
+    pub fn as_str(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl fmt::Display for AttributeArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "{} = {value}", self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+impl Spanned for AttributeArg {
+    fn span(&self) -> Span {
+        self.span.clone()
+    }
+}
+
 /// Valid kinds of attributes supported by the compiler
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub enum AttributeKind {
@@ -42,8 +86,14 @@ pub enum AttributeKind {
     Storage,
     Inline,
     Test,
+    Bench,
     Payable,
+    Selector,
     Allow,
+    Warn,
+    Deny,
+    Derive,
+    Deprecated,
 }
 
 impl AttributeKind {
@@ -56,8 +106,12 @@ impl AttributeKind {
             AttributeKind::Storage => (0, None),
             AttributeKind::Inline => (0, None),
             AttributeKind::Test => (0, None),
+            AttributeKind::Bench => (0, None),
             AttributeKind::Payable => (0, None),
-            AttributeKind::Allow => (1, Some(1)),
+            AttributeKind::Selector => (1, Some(1)),
+            AttributeKind::Allow | AttributeKind::Warn | AttributeKind::Deny => (1, None),
+            AttributeKind::Derive => (1, None),
+            AttributeKind::Deprecated => (0, Some(1)),
         }
     }
 
@@ -69,8 +123,14 @@ impl AttributeKind {
             AttributeKind::Storage => None,
             AttributeKind::Inline => None,
             AttributeKind::Test => None,
+            AttributeKind::Bench => None,
             AttributeKind::Payable => None,
-            AttributeKind::Allow => Some(vec![ALLOW_DEAD_CODE_NAME.to_string()]),
+            AttributeKind::Selector => Some(vec![SELECTOR_VALUE_NAME.to_string()]),
+            AttributeKind::Allow | AttributeKind::Warn | AttributeKind::Deny => {
+                Some(LINTS.iter().map(|lint| lint.name.to_string()).collect())
+            }
+            AttributeKind::Derive => None,
+            AttributeKind::Deprecated => Some(vec![DEPRECATED_NOTE_NAME.to_string()]),
         }
     }
 }
@@ -105,6 +165,35 @@ impl AttributesMap {
     pub fn inner(&self) -> &HashMap<AttributeKind, Vec<Attribute>> {
         &self.0
     }
+
+    /// Returns true if an `#[allow]` attribute allows `name`, as in `#[allow(dead_code)]`.
+    pub fn allows(&self, name: &str) -> bool {
+        self.get(&AttributeKind::Allow)
+            .into_iter()
+            .flatten()
+            .flat_map(|attribute| &attribute.args)
+            .any(|arg| arg.as_str() == name)
+    }
+
+    /// Returns the `#[deprecated]` attribute, if there is one.
+    pub fn deprecated(&self) -> Option<&Attribute> {
+        self.get(&AttributeKind::Deprecated)?.last()
+    }
+
+    /// Returns the `#[selector]` attribute, if there is one.
+    pub fn selector(&self) -> Option<&Attribute> {
+        self.get(&AttributeKind::Selector)?.last()
+    }
+
+    /// Returns the lines of the doc comments, without the space which usually follows `///`.
+    pub fn doc_comment_lines(&self) -> Vec<&str> {
+        self.get(&AttributeKind::DocComment)
+            .into_iter()
+            .flatten()
+            .flat_map(|attribute| &attribute.args)
+            .map(|line| line.as_str().strip_prefix(' ').unwrap_or(line.as_str()))
+            .collect()
+    }
 }
 
 impl std::ops::Deref for AttributesMap {
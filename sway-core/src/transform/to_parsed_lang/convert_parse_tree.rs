@@ -1,6 +1,9 @@
 use crate::{
     language::{parsed::*, *},
-    transform::{attribute::*, to_parsed_lang::context::Context},
+    transform::{
+        attribute::*,
+        to_parsed_lang::{context::Context, derive},
+    },
     type_system::*,
     Engines,
 };
@@ -23,10 +26,12 @@ use sway_error::handler::{ErrorEmitted, Handler};
 use sway_error::warning::{CompileWarning, Warning};
 use sway_types::{
     constants::{
-        ALLOW_ATTRIBUTE_NAME, DESTRUCTURE_PREFIX, DOC_ATTRIBUTE_NAME, DOC_COMMENT_ATTRIBUTE_NAME,
+        ALLOW_ATTRIBUTE_NAME, BENCH_ATTRIBUTE_NAME, DENY_ATTRIBUTE_NAME, DEPRECATED_ATTRIBUTE_NAME,
+        DERIVE_ATTRIBUTE_NAME, DESTRUCTURE_PREFIX, DOC_ATTRIBUTE_NAME, DOC_COMMENT_ATTRIBUTE_NAME,
         INLINE_ATTRIBUTE_NAME, MATCH_RETURN_VAR_NAME_PREFIX, PAYABLE_ATTRIBUTE_NAME,
-        STORAGE_PURITY_ATTRIBUTE_NAME, STORAGE_PURITY_READ_NAME, STORAGE_PURITY_WRITE_NAME,
-        TEST_ATTRIBUTE_NAME, TUPLE_NAME_PREFIX, VALID_ATTRIBUTE_NAMES,
+        SELECTOR_ATTRIBUTE_NAME, STORAGE_PURITY_ATTRIBUTE_NAME, STORAGE_PURITY_READ_NAME,
+        STORAGE_PURITY_WRITE_NAME, TEST_ATTRIBUTE_NAME, TUPLE_NAME_PREFIX, VALID_ATTRIBUTE_NAMES,
+        WARN_ATTRIBUTE_NAME,
     },
     integer_bits::IntegerBits,
 };
@@ -84,7 +89,9 @@ pub fn module_to_sway_parse_tree(
 
 fn ast_node_is_test_fn(node: &AstNode) -> bool {
     if let AstNodeContent::Declaration(Declaration::FunctionDeclaration(ref decl)) = node.content {
-        if decl.attributes.contains_key(&AttributeKind::Test) {
+        if decl.attributes.contains_key(&AttributeKind::Test)
+            || decl.attributes.contains_key(&AttributeKind::Bench)
+        {
             return true;
         }
     }
@@ -103,6 +110,8 @@ fn item_to_ast_nodes(
 
     let decl = |d| vec![AstNodeContent::Declaration(d)];
 
+    let derived = derived_items(context, handler, engines, &attributes, &item.value, is_root)?;
+
     let span = item.span();
     let contents = match item.value {
         ItemKind::Submodule(submodule) => {
@@ -138,7 +147,7 @@ fn item_to_ast_nodes(
             };
             match prev_item {
                 Some(Annotated {
-                    value: ItemKind::Submodule(_),
+                    value: ItemKind::Submodule(_) | ItemKind::Error(_),
                     ..
                 }) => (),
                 Some(_) => emit_expected_dep_at_beginning(),
@@ -205,6 +214,8 @@ fn item_to_ast_nodes(
         .into_iter()
         .map(|decl| AstNodeContent::Declaration(Declaration::ConstantDeclaration(decl)))
         .collect(),
+        // The parser has already reported the error it recovered from.
+        ItemKind::Error(_) => vec![],
     };
 
     Ok(contents
@@ -213,9 +224,49 @@ fn item_to_ast_nodes(
             span: span.clone(),
             content,
         })
+        .chain(derived)
         .collect())
 }
 
+/// Generates the items requested by the `#[derive(..)]` attributes of an item, and converts
+/// them like the items written in the module.
+fn derived_items(
+    context: &mut Context,
+    handler: &Handler,
+    engines: Engines<'_>,
+    attributes: &AttributesMap,
+    item: &ItemKind,
+    is_root: bool,
+) -> Result<Vec<AstNode>, ErrorEmitted> {
+    let mut nodes = vec![];
+    for attribute in attributes.get(&AttributeKind::Derive).into_iter().flatten() {
+        for trait_name in &attribute.args {
+            let src = match derive::derived_impl_source(trait_name.as_str(), item) {
+                Some(src) => Arc::<str>::from(src),
+                None => {
+                    let error = ConvertParseTreeError::CannotDerive {
+                        trait_name: trait_name.name.clone(),
+                        span: trait_name.span(),
+                    };
+                    handler.emit_err(error.into());
+                    continue;
+                }
+            };
+            let token_stream = sway_parse::lex(handler, &src, 0, src.len(), None)?;
+            let derived_item: Item = sway_parse::Parser::new(handler, &token_stream).parse()?;
+            nodes.extend(item_to_ast_nodes(
+                context,
+                handler,
+                engines,
+                derived_item,
+                is_root,
+                None,
+            )?);
+        }
+    }
+    Ok(nodes)
+}
+
 fn item_use_to_use_statements(
     _context: &mut Context,
     handler: &Handler,
@@ -2196,6 +2247,8 @@ fn statement_to_ast_nodes(
         Statement::Expr { expr, .. } => {
             vec![expr_to_ast_node(context, handler, engines, expr, true)?]
         }
+        // The parser has already reported the error it recovered from.
+        Statement::Error(_) => vec![],
     };
     Ok(ast_nodes)
 }
@@ -3683,7 +3736,7 @@ where
 }
 
 fn item_attrs_to_map(
-    _context: &mut Context,
+    context: &mut Context,
     handler: &Handler,
     attribute_list: &[AttributeDecl],
 ) -> Result<AttributesMap, ErrorEmitted> {
@@ -3701,15 +3754,9 @@ fn item_attrs_to_map(
                 })
             }
 
-            let args = attr
-                .args
-                .as_ref()
-                .map(|parens| parens.get().into_iter().cloned().collect())
-                .unwrap_or_else(Vec::new);
-
             let attribute = Attribute {
                 name: attr.name.clone(),
-                args,
+                args: attr_args_to_args(context, handler, attr)?,
                 span: attr_decl.span(),
             };
 
@@ -3719,8 +3766,14 @@ fn item_attrs_to_map(
                 STORAGE_PURITY_ATTRIBUTE_NAME => Some(AttributeKind::Storage),
                 INLINE_ATTRIBUTE_NAME => Some(AttributeKind::Inline),
                 TEST_ATTRIBUTE_NAME => Some(AttributeKind::Test),
+                BENCH_ATTRIBUTE_NAME => Some(AttributeKind::Bench),
                 PAYABLE_ATTRIBUTE_NAME => Some(AttributeKind::Payable),
+                SELECTOR_ATTRIBUTE_NAME => Some(AttributeKind::Selector),
                 ALLOW_ATTRIBUTE_NAME => Some(AttributeKind::Allow),
+                WARN_ATTRIBUTE_NAME => Some(AttributeKind::Warn),
+                DENY_ATTRIBUTE_NAME => Some(AttributeKind::Deny),
+                DERIVE_ATTRIBUTE_NAME => Some(AttributeKind::Derive),
+                DEPRECATED_ATTRIBUTE_NAME => Some(AttributeKind::Deprecated),
                 _ => None,
             } {
                 match attrs_map.get_mut(&attr_kind) {
@@ -3776,6 +3829,29 @@ fn item_attrs_to_map(
     Ok(AttributesMap::new(Arc::new(attrs_map)))
 }
 
+/// Converts the arguments of an attribute, as in `#[attribute(arg, name = value)]`.
+pub(crate) fn attr_args_to_args(
+    context: &mut Context,
+    handler: &Handler,
+    attr: &sway_ast::attribute::Attribute,
+) -> Result<Vec<AttributeArg>, ErrorEmitted> {
+    attr.args
+        .iter()
+        .flat_map(|parens| parens.get())
+        .map(|arg| {
+            let value = match &arg.value {
+                Some((_, value)) => Some(literal_to_literal(context, handler, value.clone())?),
+                None => None,
+            };
+            Ok(AttributeArg {
+                name: arg.name.clone(),
+                value,
+                span: arg.span(),
+            })
+        })
+        .collect()
+}
+
 fn error_if_self_param_is_not_allowed(
     _context: &mut Context,
     handler: &Handler,
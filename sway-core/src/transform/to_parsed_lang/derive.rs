@@ -0,0 +1,294 @@
+//! Source generation for `#[derive(..)]` attributes.
+//!
+//! Derived implementations are generated as Sway source, which is then parsed and converted
+//! like any other item of the module.
+
+use sway_ast::{
+    ty::TyTupleDescriptor, GenericParams, ItemEnum, ItemKind, ItemStruct, Ty, TypeField,
+    WhereClause,
+};
+use sway_types::{
+    constants::{DERIVE_ABI_ENCODE_NAME, DERIVE_CLONE_NAME, DERIVE_EQ_NAME, DERIVE_HASH_NAME},
+    Ident, Spanned,
+};
+
+/// Returns the source of the implementation of `trait_name` for `item`, or `None` if the trait
+/// cannot be derived for it.
+pub(super) fn derived_impl_source(trait_name: &str, item: &ItemKind) -> Option<String> {
+    let signature = match trait_name {
+        DERIVE_EQ_NAME => "fn eq(self, other: Self) -> bool",
+        DERIVE_CLONE_NAME => "fn clone(self) -> Self",
+        DERIVE_HASH_NAME => "fn hash(self, ref mut state: Hasher)",
+        DERIVE_ABI_ENCODE_NAME => "fn abi_encode(self, ref mut buffer: Bytes)",
+        _ => return None,
+    };
+    let (name, generics, where_clause, body) = match item {
+        ItemKind::Struct(item_struct) => (
+            &item_struct.name,
+            item_struct.generics.as_ref(),
+            item_struct.where_clause_opt.as_ref(),
+            struct_body(trait_name, item_struct),
+        ),
+        ItemKind::Enum(item_enum) => (
+            &item_enum.name,
+            item_enum.generics.as_ref(),
+            item_enum.where_clause_opt.as_ref(),
+            enum_body(trait_name, item_enum),
+        ),
+        _ => return None,
+    };
+    Some(impl_source(
+        trait_name,
+        name,
+        generics,
+        where_clause,
+        &format!("{signature} {{ {body} }}"),
+    ))
+}
+
+fn struct_body(trait_name: &str, item_struct: &ItemStruct) -> String {
+    let fields = item_struct
+        .fields
+        .get()
+        .into_iter()
+        .map(|field| &field.value.name)
+        .collect::<Vec<_>>();
+    match trait_name {
+        DERIVE_EQ_NAME if fields.is_empty() => "true".to_string(),
+        DERIVE_EQ_NAME => fields
+            .iter()
+            .map(|field| format!("self.{field} == other.{field}"))
+            .collect::<Vec<_>>()
+            .join(" && "),
+        DERIVE_CLONE_NAME => format!(
+            "{} {{ {} }}",
+            item_struct.name,
+            fields
+                .iter()
+                .map(|field| format!("{field}: self.{field}.clone(),"))
+                .collect::<Vec<_>>()
+                .join(" ")
+        ),
+        DERIVE_HASH_NAME => fields
+            .iter()
+            .map(|field| format!("self.{field}.hash(state);"))
+            .collect::<Vec<_>>()
+            .join(" "),
+        _ => fields
+            .iter()
+            .map(|field| format!("self.{field}.abi_encode(buffer);"))
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+fn enum_body(trait_name: &str, item_enum: &ItemEnum) -> String {
+    let name = &item_enum.name;
+    let variants = item_enum
+        .fields
+        .get()
+        .into_iter()
+        .map(|variant| &variant.value)
+        .collect::<Vec<_>>();
+    // There are no values of an enum without variants, so its methods are never called.
+    if variants.is_empty() {
+        return match trait_name {
+            DERIVE_EQ_NAME => "true".to_string(),
+            DERIVE_CLONE_NAME => "self".to_string(),
+            _ => String::new(),
+        };
+    }
+
+    let mut arms = variants
+        .iter()
+        .enumerate()
+        .map(|(tag, variant)| {
+            let path = format!("{name}::{}", variant.name);
+            let (pattern, inner) = if is_unit(variant) {
+                (path.clone(), None)
+            } else {
+                (format!("{path}(inner)"), Some("inner"))
+            };
+            match (trait_name, inner) {
+                (DERIVE_EQ_NAME, None) => format!("({pattern}, {pattern}) => true,"),
+                (DERIVE_EQ_NAME, Some(_)) => format!("({path}(lhs), {path}(rhs)) => lhs == rhs,"),
+                (DERIVE_CLONE_NAME, None) => format!("{pattern} => {pattern},"),
+                (DERIVE_CLONE_NAME, Some(inner)) => {
+                    format!("{pattern} => {path}({inner}.clone()),")
+                }
+                (DERIVE_HASH_NAME, None) => format!("{pattern} => {{ state.write({tag}); }},"),
+                (DERIVE_HASH_NAME, Some(inner)) => {
+                    format!("{pattern} => {{ state.write({tag}); {inner}.hash(state); }},")
+                }
+                (_, None) => format!("{pattern} => {tag},"),
+                (_, Some(inner)) => {
+                    format!("{pattern} => {{ {inner}.abi_encode(encoded); {tag} }},")
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+    match trait_name {
+        DERIVE_EQ_NAME => {
+            // A single variant always matches itself, so a catch-all arm would be unreachable.
+            if arms.len() > 1 {
+                arms.push("_ => false,".to_string());
+            }
+            format!("match (self, other) {{ {} }}", arms.join(" "))
+        }
+        DERIVE_CLONE_NAME | DERIVE_HASH_NAME => format!("match self {{ {} }}", arms.join(" ")),
+        // The variant index is followed by the value of the variant, left-padded to the size of
+        // the largest variant.
+        _ => format!(
+            "let end = buffer.len() + __size_of_val(self); \
+             let mut encoded = Bytes::new(); \
+             let tag: u64 = match self {{ {} }}; \
+             tag.abi_encode(buffer); \
+             while buffer.len() + encoded.len() < end {{ buffer.push(0u8); }} \
+             buffer.append(encoded);",
+            arms.join(" ")
+        ),
+    }
+}
+
+fn is_unit(variant: &TypeField) -> bool {
+    matches!(
+        &variant.ty,
+        Ty::Tuple(tuple) if matches!(tuple.get(), TyTupleDescriptor::Nil)
+    )
+}
+
+/// Returns an implementation of `trait_name` with the given items for the type `name`. Each
+/// type parameter is additionally required to implement the trait, on top of the bounds the type
+/// already has.
+fn impl_source(
+    trait_name: &str,
+    name: &Ident,
+    generics: Option<&GenericParams>,
+    where_clause: Option<&WhereClause>,
+    items: &str,
+) -> String {
+    let params = generics
+        .map(|generics| {
+            (&generics.parameters.inner)
+                .into_iter()
+                .map(|param| param.as_str().to_string())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let mut bounds = where_clause
+        .map(|where_clause| {
+            (&where_clause.bounds)
+                .into_iter()
+                .map(|bound| format!("{}: {}", bound.ty_name, bound.bounds.span().as_str()))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    bounds.extend(params.iter().map(|param| format!("{param}: {trait_name}")));
+
+    let generics = if params.is_empty() {
+        String::new()
+    } else {
+        format!("<{}>", params.join(", "))
+    };
+    let where_clause = if bounds.is_empty() {
+        String::new()
+    } else {
+        format!(" where {}", bounds.join(", "))
+    };
+    format!("impl{generics} {trait_name} for {name}{generics}{where_clause} {{ {items} }}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use sway_error::handler::Handler;
+
+    // Derive `trait_name` for each item of `src`, which must all support it.
+    fn derive(trait_name: &str, src: &str) -> Vec<String> {
+        let handler = Handler::default();
+        let module = sway_parse::parse_file(&handler, Arc::from(src), None).unwrap();
+        module
+            .value
+            .items
+            .iter()
+            .map(|item| derived_impl_source(trait_name, &item.value).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn generic_types() {
+        let impls = derive(
+            DERIVE_EQ_NAME,
+            "library; \
+             struct Pair<A, B> where A: Clone { a: A, b: B } \
+             enum Maybe<T> { Nothing: (), Just: T }",
+        );
+        assert_eq!(
+            impls,
+            vec![
+                "impl<A, B> Eq for Pair<A, B> where A: Clone, A: Eq, B: Eq { \
+                 fn eq(self, other: Self) -> bool { self.a == other.a && self.b == other.b } }",
+                "impl<T> Eq for Maybe<T> where T: Eq { \
+                 fn eq(self, other: Self) -> bool { match (self, other) { \
+                 (Maybe::Nothing, Maybe::Nothing) => true, \
+                 (Maybe::Just(lhs), Maybe::Just(rhs)) => lhs == rhs, _ => false, } } }",
+            ]
+        );
+
+        // The generated source must itself parse.
+        let handler = Handler::default();
+        let src = format!("library; {}", impls.join(" "));
+        assert!(sway_parse::parse_file(&handler, Arc::from(src), None).is_ok());
+    }
+
+    #[test]
+    fn other_traits() {
+        let src = "library; \
+                   struct Point { x: u64, y: u64 } \
+                   enum Shape { Dot: (), Line: Point }";
+        assert_eq!(
+            derive(DERIVE_CLONE_NAME, src),
+            vec![
+                "impl Clone for Point { fn clone(self) -> Self { \
+                 Point { x: self.x.clone(), y: self.y.clone(), } } }",
+                "impl Clone for Shape { fn clone(self) -> Self { match self { \
+                 Shape::Dot => Shape::Dot, \
+                 Shape::Line(inner) => Shape::Line(inner.clone()), } } }",
+            ]
+        );
+        assert_eq!(
+            derive(DERIVE_HASH_NAME, src),
+            vec![
+                "impl Hash for Point { fn hash(self, ref mut state: Hasher) { \
+                 self.x.hash(state); self.y.hash(state); } }",
+                "impl Hash for Shape { fn hash(self, ref mut state: Hasher) { match self { \
+                 Shape::Dot => { state.write(0); }, \
+                 Shape::Line(inner) => { state.write(1); inner.hash(state); }, } } }",
+            ]
+        );
+        assert_eq!(
+            derive(DERIVE_ABI_ENCODE_NAME, src),
+            vec![
+                "impl AbiEncode for Point { fn abi_encode(self, ref mut buffer: Bytes) { \
+                 self.x.abi_encode(buffer); self.y.abi_encode(buffer); } }",
+                "impl AbiEncode for Shape { fn abi_encode(self, ref mut buffer: Bytes) { \
+                 let end = buffer.len() + __size_of_val(self); \
+                 let mut encoded = Bytes::new(); \
+                 let tag: u64 = match self { Shape::Dot => 0, \
+                 Shape::Line(inner) => { inner.abi_encode(encoded); 1 }, }; \
+                 tag.abi_encode(buffer); \
+                 while buffer.len() + encoded.len() < end { buffer.push(0u8); } \
+                 buffer.append(encoded); } }",
+            ]
+        );
+
+        let impls = [DERIVE_CLONE_NAME, DERIVE_HASH_NAME, DERIVE_ABI_ENCODE_NAME]
+            .into_iter()
+            .flat_map(|trait_name| derive(trait_name, src))
+            .collect::<Vec<_>>();
+        let handler = Handler::default();
+        let src = format!("library; {}", impls.join(" "));
+        assert!(sway_parse::parse_file(&handler, Arc::from(src), None).is_ok());
+    }
+}
@@ -1,5 +1,6 @@
 mod context;
 mod convert_parse_tree;
+mod derive;
 
 pub(crate) use context::*;
 pub(crate) use convert_parse_tree::*;
@@ -109,6 +109,11 @@ pub enum ConvertParseTreeError {
     DerefExprNotYetSupported { span: Span },
     #[error("Constant requires expression.")]
     ConstantRequiresExpression { span: Span },
+    #[error(
+        "Cannot derive `{trait_name}`. Only `Eq`, `Clone`, `Hash` and `AbiEncode` can be derived \
+         for structs and enums."
+    )]
+    CannotDerive { trait_name: Ident, span: Span },
 }
 
 impl Spanned for ConvertParseTreeError {
@@ -167,6 +172,7 @@ impl Spanned for ConvertParseTreeError {
             ConvertParseTreeError::RefExprNotYetSupported { span } => span.clone(),
             ConvertParseTreeError::DerefExprNotYetSupported { span } => span.clone(),
             ConvertParseTreeError::ConstantRequiresExpression { span } => span.clone(),
+            ConvertParseTreeError::CannotDerive { span, .. } => span.clone(),
         }
     }
 }
@@ -257,7 +257,8 @@ pub enum CompileError {
         function_name: Ident,
     },
     #[error(
-        "Expected Module level doc comment. All other attributes are unsupported at this level."
+        "Expected Module level doc comment or lint attribute. All other attributes are \
+         unsupported at this level."
     )]
     ExpectedModuleDocComment { span: Span },
     #[error(
@@ -278,6 +279,12 @@ pub enum CompileError {
         variant_name: Ident,
         span: Span,
     },
+    #[error("{warning} The lint \"{lint}\" is denied.")]
+    DeniedLint {
+        lint: String,
+        warning: String,
+        span: Span,
+    },
     #[error("Unknown opcode: \"{op_name}\".")]
     UnrecognizedOp { op_name: Ident, span: Span },
     #[error("Cannot infer type for type parameter \"{ty}\". Insufficient type information provided. Try annotating its type.")]
@@ -610,10 +617,52 @@ pub enum CompileError {
         missing_impl_attribute: bool,
         span: Span,
     },
+    #[error(
+        "Selector attribute mismatch. The \"{fn_name}\" method implementation must be given the \
+         same #[selector] attribute as its signature in {interface_name}."
+    )]
+    TraitImplSelectorMismatch {
+        fn_name: Ident,
+        interface_name: InterfaceName,
+        span: Span,
+    },
+    #[error(
+        "Invalid selector attribute. The selector must be given as \
+         #[selector(value = \"0x12345678\")], with eight hexadecimal digits."
+    )]
+    InvalidSelectorAttribute { span: Span },
+    #[error(
+        "The selector {selector} of ABI method \"{fn_name}\" is also the selector of \"{other_fn_name}\" \
+         on line {}. Use #[selector] to give one of them a different selector.",
+        other_span.line_col().0.line
+    )]
+    SelectorCollision {
+        selector: String,
+        fn_name: Ident,
+        other_fn_name: Ident,
+        other_span: Span,
+        span: Span,
+    },
     #[error("Configurable constants are not allowed in libraries.")]
     ConfigurableInLibrary { span: Span },
     #[error("The name `{name}` is defined multiple times")]
     NameDefinedMultipleTimes { name: String, span: Span },
+    #[error("Unknown IR pass \"{name}\". {pass_names}")]
+    UnknownIrPass {
+        name: String,
+        pass_names: String,
+        span: Span,
+    },
+    #[error("Invalid rewrite rules in \"{}\": {error}", path.display())]
+    InvalidRewriteRules {
+        path: PathBuf,
+        error: String,
+        span: Span,
+    },
+    #[error("Cannot freeze \"{name}\" as there is no configurable with that name.")]
+    UnknownFrozenConfigurable { name: String, span: Span },
+    #[error("{error}")]
+    InvalidFrozenConfigurable { error: String, span: Span },
 }
 
 impl std::convert::From<TypeError> for CompileError {
@@ -675,6 +724,7 @@ impl Spanned for CompileError {
             MissingImmediate { span, .. } => span.clone(),
             InvalidImmediateValue { span, .. } => span.clone(),
             UnknownEnumVariant { span, .. } => span.clone(),
+            DeniedLint { span, .. } => span.clone(),
             UnrecognizedOp { span, .. } => span.clone(),
             UnableToInferGeneric { span, .. } => span.clone(),
             UnconstrainedGenericParameter { span, .. } => span.clone(),
@@ -778,8 +828,15 @@ impl Spanned for CompileError {
             DisallowedWhileInPredicate { span } => span.clone(),
             CoinsPassedToNonPayableMethod { span, .. } => span.clone(),
             TraitImplPayabilityMismatch { span, .. } => span.clone(),
+            TraitImplSelectorMismatch { span, .. } => span.clone(),
+            InvalidSelectorAttribute { span } => span.clone(),
+            SelectorCollision { span, .. } => span.clone(),
             ConfigurableInLibrary { span } => span.clone(),
             NameDefinedMultipleTimes { span, .. } => span.clone(),
+            UnknownIrPass { span, .. } => span.clone(),
+            InvalidRewriteRules { span, .. } => span.clone(),
+            UnknownFrozenConfigurable { span, .. } => span.clone(),
+            InvalidFrozenConfigurable { span, .. } => span.clone(),
         }
     }
 }
@@ -788,6 +845,23 @@ impl CompileError {
     pub fn path(&self) -> Option<Arc<PathBuf>> {
         self.span().path().cloned()
     }
+
+    /// Other places in the source which are part of the error, each with a label saying how.
+    pub fn secondary_labels(&self) -> Vec<(Span, String)> {
+        use CompileError::*;
+        match self {
+            SelectorCollision {
+                selector,
+                other_fn_name,
+                other_span,
+                ..
+            } => vec![(
+                other_span.clone(),
+                format!("\"{other_fn_name}\" also has the selector {selector}"),
+            )],
+            _ => vec![],
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
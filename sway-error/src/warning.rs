@@ -108,6 +108,14 @@ pub enum Warning {
         effect_in_suggestion: String,
         block_name: Ident,
     },
+    UsingDeprecated {
+        name: Ident,
+        note: Option<String>,
+    },
+    OptimizationFuelExhausted {
+        pass: String,
+        function: String,
+    },
 }
 
 impl fmt::Display for Warning {
@@ -249,6 +257,15 @@ impl fmt::Display for Warning {
             EffectAfterInteraction {effect, effect_in_suggestion, block_name} =>
                 write!(f, "{effect} after external contract interaction in function or method \"{block_name}\". \
                           Consider {effect_in_suggestion} before calling another contract"),
+            UsingDeprecated { name, note } => match note {
+                Some(note) => write!(f, "\"{name}\" is deprecated: {note}"),
+                None => write!(f, "\"{name}\" is deprecated."),
+            },
+            OptimizationFuelExhausted { pass, function } => write!(
+                f,
+                "The \"{pass}\" optimization pass ran out of fuel on function \"{function}\", \
+                which may be less optimized as a result."
+            ),
         }
     }
 }
@@ -1,4 +1,24 @@
+pub mod alias;
+pub use alias::*;
+pub mod block_args;
+pub use block_args::*;
 pub mod call_graph;
 pub use call_graph::*;
+pub mod cost;
+pub use cost::*;
 pub mod dominator;
 pub use dominator::*;
+pub mod loops;
+pub use loops::*;
+pub mod memory_ssa;
+pub use memory_ssa::*;
+pub mod post_dominator;
+pub use post_dominator::*;
+pub mod purity;
+pub use purity::*;
+pub mod storage_access;
+pub use storage_access::*;
+pub mod unreachable;
+pub use unreachable::*;
+pub mod taint;
+pub use taint::*;
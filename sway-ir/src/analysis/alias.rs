@@ -0,0 +1,233 @@
+//! Alias analysis.
+//!
+//! Two pointers alias if the memory they refer to may overlap, in which case a write through one
+//! may change what is read through the other.  [`AliasAnalysis`] is the interface optimizations
+//! use to ask, and [`BasicAliasAnalysis`] answers from the shape of the pointers alone.
+//!
+//! Contract storage is addressed by keys rather than pointers, and is never the same as memory.
+//! [`MemoryLocation`] tells the two apart.
+
+use rustc_hash::FxHashSet;
+
+use crate::{
+    AnalysisResult, AnalysisResultT, AnalysisResults, Context, FuelVmInstruction, Function,
+    Instruction, IrError, LocalVar, Pass, PassMutability, ScopedPass, Value,
+};
+
+/// Whether two pointers may refer to overlapping memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AliasResult {
+    /// The memory never overlaps.
+    NoAlias,
+    /// The memory may or may not overlap.
+    MayAlias,
+    /// The pointers always refer to exactly the same memory.
+    MustAlias,
+}
+
+/// A place which may be read from or written to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemoryLocation {
+    /// The memory referred to by a pointer.
+    Memory(Value),
+    /// The storage slot whose key is in the memory referred to by a pointer.
+    Storage(Value),
+}
+
+pub trait AliasAnalysis {
+    /// Return whether the memory referred to by the pointers `v1` and `v2` may overlap.
+    fn may_alias(&self, context: &Context, v1: Value, v2: Value) -> AliasResult;
+
+    /// Return whether the locations `loc1` and `loc2` may overlap.
+    ///
+    /// Storage and memory never overlap.  Storage keys are read from memory, which may change
+    /// between accesses, so any two storage slots may be the same.
+    fn may_alias_locations(
+        &self,
+        context: &Context,
+        loc1: MemoryLocation,
+        loc2: MemoryLocation,
+    ) -> AliasResult {
+        match (loc1, loc2) {
+            (MemoryLocation::Memory(v1), MemoryLocation::Memory(v2)) => {
+                self.may_alias(context, v1, v2)
+            }
+            (MemoryLocation::Storage(_), MemoryLocation::Storage(_)) => AliasResult::MayAlias,
+            _ => AliasResult::NoAlias,
+        }
+    }
+}
+
+/// Where a pointer ultimately points.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PointerBase {
+    /// A local variable of the function.
+    Local(LocalVar),
+    /// Whatever another pointer points to, such as a function argument or a loaded pointer.
+    Unknown(Value),
+}
+
+/// An alias analysis which looks only at how each pointer is formed.
+///
+/// A pointer is a base, either a local or some other pointer, plus a constant offset made by
+/// `cast_ptr`.  The result of an insert into an aggregate is the same pointer as the aggregate.
+/// Pointers to different locals never alias, nor do pointers to non-overlapping parts of the same
+/// base.  A local whose address escapes the function, by being passed anywhere other than to a
+/// load, store, copy or field access, may be referred to by any pointer of unknown base.
+#[derive(Clone, Default)]
+pub struct BasicAliasAnalysis {
+    escaped: FxHashSet<LocalVar>,
+}
+impl AnalysisResultT for BasicAliasAnalysis {}
+
+impl BasicAliasAnalysis {
+    /// Find the locals of `function` whose addresses escape.
+    pub fn new(context: &Context, function: &Function) -> Self {
+        let local = |ptr: &Value| match base_and_offset(context, *ptr) {
+            (PointerBase::Local(local_var), _) => Some(local_var),
+            (PointerBase::Unknown(_), _) => None,
+        };
+        let is_copy_type = |val: Value| {
+            val.get_type(context).map_or(false, |ty| {
+                ty.is_unit(context) || ty.is_bool(context) || ty.is_uint(context)
+            })
+        };
+        let mut escaped = FxHashSet::default();
+        for (_, ins) in function.instruction_iter(context) {
+            let instruction = ins.get_instruction(context).unwrap();
+            match instruction {
+                // A load or extract of an aggregate is a pointer into it rather than a copy.
+                Instruction::Load(ptr)
+                | Instruction::ExtractValue { aggregate: ptr, .. }
+                | Instruction::ExtractElement { array: ptr, .. } => {
+                    if !is_copy_type(ins) {
+                        escaped.extend(local(ptr));
+                    }
+                }
+                Instruction::Store { stored_val, .. }
+                | Instruction::InsertValue {
+                    value: stored_val, ..
+                }
+                | Instruction::InsertElement {
+                    value: stored_val, ..
+                } => escaped.extend(local(stored_val)),
+                Instruction::CastPtr(..)
+                | Instruction::GetLocal(_)
+                | Instruction::MemCopy { .. }
+                | Instruction::FuelVm(
+                    FuelVmInstruction::StateClear { .. }
+                    | FuelVmInstruction::StateLoadQuadWord { .. }
+                    | FuelVmInstruction::StateLoadWord(_)
+                    | FuelVmInstruction::StateStoreQuadWord { .. }
+                    | FuelVmInstruction::StateStoreWord { .. },
+                ) => (),
+                _ => escaped.extend(instruction.get_operands().iter().filter_map(local)),
+            }
+        }
+        BasicAliasAnalysis { escaped }
+    }
+}
+
+impl AliasAnalysis for BasicAliasAnalysis {
+    fn may_alias(&self, context: &Context, v1: Value, v2: Value) -> AliasResult {
+        if v1 == v2 {
+            return AliasResult::MustAlias;
+        }
+        let (base1, offset1) = base_and_offset(context, v1);
+        let (base2, offset2) = base_and_offset(context, v2);
+        if base1 != base2 {
+            return match (base1, base2) {
+                (PointerBase::Local(_), PointerBase::Local(_)) => AliasResult::NoAlias,
+                (PointerBase::Local(local_var), PointerBase::Unknown(_))
+                | (PointerBase::Unknown(_), PointerBase::Local(local_var))
+                    if !self.escaped.contains(&local_var) =>
+                {
+                    AliasResult::NoAlias
+                }
+                _ => AliasResult::MayAlias,
+            };
+        }
+
+        // Both are within the same base, so compare the bytes they cover.
+        match (
+            offset1,
+            offset2,
+            pointee_size(context, v1),
+            pointee_size(context, v2),
+        ) {
+            (Some(offset1), Some(offset2), Some(size1), Some(size2)) => {
+                if offset1 == offset2 && size1 == size2 {
+                    AliasResult::MustAlias
+                } else if offset1.saturating_add(size1) <= offset2
+                    || offset2.saturating_add(size2) <= offset1
+                {
+                    AliasResult::NoAlias
+                } else {
+                    AliasResult::MayAlias
+                }
+            }
+            _ => AliasResult::MayAlias,
+        }
+    }
+}
+
+/// Return the base of `ptr` and its offset in bytes from it, if known.
+fn base_and_offset(context: &Context, mut ptr: Value) -> (PointerBase, Option<u64>) {
+    let mut offset = Some(0);
+    loop {
+        match ptr.get_instruction(context) {
+            Some(Instruction::GetLocal(local_var)) => {
+                return (PointerBase::Local(*local_var), offset)
+            }
+            // Inserting into an aggregate returns the pointer to it.
+            Some(
+                Instruction::InsertValue {
+                    aggregate: base_ptr,
+                    ..
+                }
+                | Instruction::InsertElement {
+                    array: base_ptr, ..
+                },
+            ) => ptr = *base_ptr,
+            Some(Instruction::CastPtr(base_ptr, ty, idx)) => {
+                offset = offset.and_then(|offset| {
+                    ty.size_in_bytes(context)
+                        .checked_mul(*idx)
+                        .and_then(|field_offset| offset.checked_add(field_offset))
+                });
+                ptr = *base_ptr;
+            }
+            _ => return (PointerBase::Unknown(ptr), offset),
+        }
+    }
+}
+
+/// Whether `ptr` points into a local of its function.
+pub(crate) fn points_to_local(context: &Context, ptr: Value) -> bool {
+    matches!(base_and_offset(context, ptr).0, PointerBase::Local(_))
+}
+
+fn pointee_size(context: &Context, ptr: Value) -> Option<u64> {
+    ptr.get_type(context).map(|ty| ty.size_in_bytes(context))
+}
+
+pub const ALIAS_NAME: &str = "alias";
+
+pub fn create_alias_pass() -> Pass {
+    Pass {
+        name: ALIAS_NAME,
+        descr: "Basic alias analysis",
+        deps: vec![],
+        preserves: vec![],
+        prune_unreachable: false,
+        runner: ScopedPass::FunctionPass(PassMutability::Analysis(compute_alias_pass)),
+    }
+}
+
+fn compute_alias_pass(
+    context: &Context,
+    _: &AnalysisResults,
+    function: Function,
+) -> Result<AnalysisResult, IrError> {
+    Ok(Box::new(BasicAliasAnalysis::new(context, &function)))
+}
@@ -0,0 +1,258 @@
+//! Inference and checking of the types of block arguments.
+//!
+//! After a CFG edit, such as redirecting a branch or adding a parameter to a block, the values
+//! passed along every edge must still match the parameters of its destination.
+//! [`infer_block_arg_types`] recomputes the parameter types a block requires from its incoming
+//! edges, [`check_block_args`] finds every edge of a function which disagrees with its
+//! destination and [`propagate_block_arg_types`] updates the parameters to the inferred types.
+//!
+//! Each [`BlockArgMismatch`] names the edge responsible for it.  Edges are found from the
+//! terminators of the function's blocks rather than from the recorded predecessors, so they are
+//! still found if an edit has not updated the predecessors yet.
+
+use crate::{
+    block::{Block, BlockArgument},
+    context::Context,
+    function::Function,
+    irtype::Type,
+    value::{Value, ValueDatum},
+};
+
+/// A branch from the terminator of one block to another.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct BranchEdge {
+    pub from: Block,
+    pub to: Block,
+    /// The index of the destination among the successors of `from`, which tells apart the two
+    /// edges of a conditional branch with the same destination.
+    pub succ_idx: usize,
+}
+
+impl BranchEdge {
+    /// Return the arguments passed along this edge.
+    pub fn args(&self, context: &Context) -> Vec<Value> {
+        self.from
+            .successors(context)
+            .get(self.succ_idx)
+            .map_or(vec![], |succ| succ.args.clone())
+    }
+}
+
+/// An edge whose arguments do not match the parameters of its destination.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BlockArgMismatch {
+    /// The edge passes `actual` arguments where `expected` are required.
+    Count {
+        edge: BranchEdge,
+        expected: usize,
+        actual: usize,
+    },
+    /// The argument at `idx` is of type `actual` where `expected` is required.  Values without a
+    /// type have no `actual` type.
+    Type {
+        edge: BranchEdge,
+        idx: usize,
+        expected: Type,
+        actual: Option<Type>,
+    },
+}
+
+impl BlockArgMismatch {
+    /// Return the edge responsible for the mismatch.
+    pub fn edge(&self) -> BranchEdge {
+        match self {
+            BlockArgMismatch::Count { edge, .. } | BlockArgMismatch::Type { edge, .. } => *edge,
+        }
+    }
+
+    /// Describe the mismatch with the labels of the blocks and the names of the types.
+    pub fn as_string(&self, context: &Context) -> String {
+        let edge = self.edge();
+        let from = edge.from.get_label(context);
+        let to = edge.to.get_label(context);
+        match self {
+            BlockArgMismatch::Count {
+                expected, actual, ..
+            } => format!(
+                "Branch from block '{from}' to block '{to}' passes {actual} arguments \
+                where {expected} are required."
+            ),
+            BlockArgMismatch::Type {
+                idx,
+                expected,
+                actual,
+                ..
+            } => format!(
+                "Branch from block '{from}' to block '{to}' passes argument {idx} of type {} \
+                where {} is required.",
+                actual.map_or("<untyped>".to_string(), |ty| ty.as_string(context)),
+                expected.as_string(context)
+            ),
+        }
+    }
+}
+
+/// Return the edges to `block` from the blocks of its function, in block order.
+pub fn incoming_edges(context: &Context, block: Block) -> Vec<BranchEdge> {
+    block
+        .get_function(context)
+        .block_iter(context)
+        .flat_map(|from| {
+            from.successors(context)
+                .into_iter()
+                .enumerate()
+                .filter(|(_, succ)| succ.block == block)
+                .map(move |(succ_idx, _)| BranchEdge {
+                    from,
+                    to: block,
+                    succ_idx,
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Compare the arguments passed along `edge` with the `expected` types.
+fn check_edge(context: &Context, edge: BranchEdge, expected: &[Type]) -> Vec<BlockArgMismatch> {
+    let args = edge.args(context);
+    if args.len() != expected.len() {
+        return vec![BlockArgMismatch::Count {
+            edge,
+            expected: expected.len(),
+            actual: args.len(),
+        }];
+    }
+    args.iter()
+        .zip(expected)
+        .enumerate()
+        .filter_map(|(idx, (arg, expected))| {
+            let actual = arg.get_type(context);
+            match actual {
+                Some(actual) if actual.eq(context, expected) => None,
+                _ => Some(BlockArgMismatch::Type {
+                    edge,
+                    idx,
+                    expected: *expected,
+                    actual,
+                }),
+            }
+        })
+        .collect()
+}
+
+fn param_types(context: &Context, block: Block) -> Vec<Type> {
+    block
+        .arg_iter(context)
+        .map(|arg| {
+            arg.get_type(context)
+                .expect("block arguments are always typed")
+        })
+        .collect()
+}
+
+/// Infer the parameter types `block` requires from the arguments passed along its incoming
+/// edges.
+///
+/// The first edge in block order decides the types, and every edge disagreeing with it is
+/// reported.  A block without incoming edges requires the types of its current parameters.
+pub fn infer_block_arg_types(
+    context: &Context,
+    block: Block,
+) -> Result<Vec<Type>, Vec<BlockArgMismatch>> {
+    let mut edges = incoming_edges(context, block).into_iter();
+    let first = match edges.next() {
+        Some(first) => first,
+        None => return Ok(param_types(context, block)),
+    };
+
+    let first_args = first.args(context);
+    let mut types = Vec::with_capacity(first_args.len());
+    for (idx, arg) in first_args.iter().enumerate() {
+        match arg.get_type(context) {
+            Some(ty) => types.push(ty),
+            None => {
+                // Nothing can be inferred from an untyped value, so judge it by the parameter.
+                let expected = block
+                    .get_arg(context, idx)
+                    .and_then(|param| param.get_type(context))
+                    .unwrap_or_else(|| Type::get_unit(context));
+                return Err(vec![BlockArgMismatch::Type {
+                    edge: first,
+                    idx,
+                    expected,
+                    actual: None,
+                }]);
+            }
+        }
+    }
+
+    let mismatches = edges
+        .flat_map(|edge| check_edge(context, edge, &types))
+        .collect::<Vec<_>>();
+    if mismatches.is_empty() {
+        Ok(types)
+    } else {
+        Err(mismatches)
+    }
+}
+
+/// Check the arguments passed along every edge of `function` against the parameters of its
+/// destination, returning the mismatches in block order.
+pub fn check_block_args(context: &Context, function: Function) -> Vec<BlockArgMismatch> {
+    function
+        .block_iter(context)
+        .flat_map(|block| {
+            let expected = param_types(context, block);
+            incoming_edges(context, block)
+                .into_iter()
+                .flat_map(|edge| check_edge(context, edge, &expected))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Update the parameter types of the blocks of `function` to those inferred from their incoming
+/// edges, returning whether any were changed.
+///
+/// Parameters are neither added nor removed, so an edge passing the wrong number of arguments is
+/// reported as a mismatch, as are the edges which disagree on a type.  Nothing is changed unless
+/// every block can be updated.
+pub fn propagate_block_arg_types(
+    context: &mut Context,
+    function: Function,
+) -> Result<bool, Vec<BlockArgMismatch>> {
+    let mut updates = Vec::new();
+    let mut mismatches = Vec::new();
+    for block in function.block_iter(context) {
+        match infer_block_arg_types(context, block) {
+            Ok(types) if types.len() == block.num_args(context) => {
+                for (param, ty) in block.arg_iter(context).zip(types) {
+                    if !param.get_type(context).unwrap().eq(context, &ty) {
+                        updates.push((*param, ty));
+                    }
+                }
+            }
+            Ok(types) => {
+                mismatches.extend(incoming_edges(context, block).into_iter().map(|edge| {
+                    BlockArgMismatch::Count {
+                        edge,
+                        expected: block.num_args(context),
+                        actual: types.len(),
+                    }
+                }))
+            }
+            Err(block_mismatches) => mismatches.extend(block_mismatches),
+        }
+    }
+    if !mismatches.is_empty() {
+        return Err(mismatches);
+    }
+
+    let modified = !updates.is_empty();
+    for (param, new_ty) in updates {
+        if let ValueDatum::Argument(BlockArgument { ty, .. }) = &mut context.values[param.0].value {
+            *ty = new_ty;
+        }
+    }
+    Ok(modified)
+}
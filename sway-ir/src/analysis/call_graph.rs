@@ -1,18 +1,49 @@
 /// Build call graphs for the program being compiled.
 /// If a function F1 calls function F2, then the call
 /// graph has an edge F1->F2.
-use crate::{Context, Function, Instruction, ValueDatum};
+use crate::{
+    AnalysisResult, AnalysisResultT, AnalysisResults, Context, Function, Instruction, IrError,
+    Module, Pass, PassMutability, ScopedPass, ValueDatum,
+};
 
-use rustc_hash::{FxHashMap, FxHashSet};
+use std::collections::{BTreeMap, BTreeSet};
 
-pub type CallGraph = FxHashMap<Function, FxHashSet<Function>>;
+use rustc_hash::FxHashSet;
+
+// Ordered, so that the callee first order is the same every time.
+pub type CallGraph = BTreeMap<Function, BTreeSet<Function>>;
+impl AnalysisResultT for CallGraph {}
+
+pub const CALL_GRAPH_NAME: &str = "call_graph";
+
+pub fn create_call_graph_pass() -> Pass {
+    Pass {
+        name: CALL_GRAPH_NAME,
+        descr: "Call graph of a module",
+        deps: vec![],
+        preserves: vec![],
+        prune_unreachable: false,
+        runner: ScopedPass::ModulePass(PassMutability::Analysis(compute_call_graph_pass)),
+    }
+}
+
+fn compute_call_graph_pass(
+    context: &Context,
+    _: &AnalysisResults,
+    module: Module,
+) -> Result<AnalysisResult, IrError> {
+    Ok(Box::new(build_call_graph(
+        context,
+        &module.function_iter(context).collect::<Vec<_>>(),
+    )))
+}
 
 /// Build call graph considering all providing functions.
 pub fn build_call_graph(ctx: &Context, functions: &[Function]) -> CallGraph {
     let mut res = CallGraph::default();
     for function in functions {
         let entry = res.entry(*function);
-        let entry = entry.or_insert_with(FxHashSet::default);
+        let entry = entry.or_insert_with(BTreeSet::default);
         for (_, inst) in function.instruction_iter(ctx) {
             if let ValueDatum::Instruction(Instruction::Call(callee, _)) = ctx.values[inst.0].value
             {
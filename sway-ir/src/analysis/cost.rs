@@ -0,0 +1,146 @@
+//! A rough static estimate of the gas spent executing a function.
+//!
+//! Every instruction is assumed to run exactly once, so loops are counted a single time and both
+//! arms of a branch are included.  The weights approximate the relative cost of the ops each
+//! instruction typically lowers to rather than the VM's exact gas schedule, which makes the
+//! estimate useful for comparing functions and changes to them but not for setting gas limits.
+
+use crate::{Context, FuelVmInstruction, Function, Instruction, PatternOp, ValueDatum};
+
+use rustc_hash::FxHashMap;
+
+/// Arithmetic, logic and comparisons are single ALU ops.
+const ARITHMETIC_COST: u64 = 1;
+/// Storage reads and writes dominate the cost of most contract calls.
+const STATE_ACCESS_COST: u64 = 250;
+/// Calling another contract, not counting the cost of the callee.
+const CONTRACT_CALL_COST: u64 = 2_000;
+/// Logging and sending messages involve receipts.
+const RECEIPT_COST: u64 = 100;
+/// Setting up and tearing down the frame of a local call.
+const CALL_OVERHEAD_COST: u64 = 10;
+/// Memory copies are charged per word moved.
+const WORD_SIZE: u64 = 8;
+
+/// Return the estimated gas spent by `function`, including the functions it calls.
+pub fn estimate_gas(context: &Context, function: &Function) -> u64 {
+    estimate_gas_memoized(context, function, &mut FxHashMap::default())
+}
+
+fn estimate_gas_memoized(
+    context: &Context,
+    function: &Function,
+    estimates: &mut FxHashMap<Function, u64>,
+) -> u64 {
+    if let Some(estimate) = estimates.get(function) {
+        return *estimate;
+    }
+    // Recursion isn't allowed, but guard against it anyway by treating a recursive call as free.
+    estimates.insert(*function, 0);
+    let mut estimate = 0;
+    for (_, inst) in function.instruction_iter(context) {
+        estimate += match &context.values[inst.0].value {
+            ValueDatum::Instruction(Instruction::Call(callee, _)) => {
+                CALL_OVERHEAD_COST + estimate_gas_memoized(context, callee, estimates)
+            }
+            ValueDatum::Instruction(instruction) => instruction_cost(context, instruction),
+            _ => 0,
+        };
+    }
+    estimates.insert(*function, estimate);
+    estimate
+}
+
+/// The size and shape of a function, used to decide whether calls to it should be inlined.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InlineCost {
+    /// A rough count of the VM ops the body lowers to, which is roughly how much each inlined call
+    /// adds to the bytecode.
+    pub weight: u64,
+    /// The number of calls the function makes.
+    pub num_calls: usize,
+    /// The number of blocks in the function.
+    pub num_blocks: usize,
+    /// Whether the function reads or writes contract storage.
+    pub accesses_storage: bool,
+}
+
+/// Return the cost of inlining `function`, not including the functions it calls.
+pub fn estimate_inline_cost(context: &Context, function: &Function) -> InlineCost {
+    let mut cost = InlineCost {
+        num_blocks: function.num_blocks(context),
+        ..InlineCost::default()
+    };
+    for (_, inst) in function.instruction_iter(context) {
+        let Some(instruction) = inst.get_instruction(context) else {
+            continue;
+        };
+        cost.weight += instruction_size(context, instruction);
+        match instruction {
+            Instruction::Call(..) => cost.num_calls += 1,
+            Instruction::FuelVm(
+                FuelVmInstruction::StateClear { .. }
+                | FuelVmInstruction::StateLoadQuadWord { .. }
+                | FuelVmInstruction::StateLoadWord(_)
+                | FuelVmInstruction::StateStoreQuadWord { .. }
+                | FuelVmInstruction::StateStoreWord { .. },
+            ) => cost.accesses_storage = true,
+            _ => (),
+        }
+    }
+    cost
+}
+
+fn instruction_size(context: &Context, instruction: &Instruction) -> u64 {
+    match instruction {
+        Instruction::AddrOf(_)
+        | Instruction::BitCast(..)
+        | Instruction::CastPtr(..)
+        | Instruction::IntToPtr(..)
+        | Instruction::Nop => 0,
+        Instruction::AsmBlock(asm_block, _) => {
+            asm_block.get_content(context).body.len().max(1) as u64
+        }
+        _ => 1,
+    }
+}
+
+/// Return the estimated gas spent by the instruction performing `op`.
+pub fn pattern_op_cost(op: &PatternOp) -> u64 {
+    match op {
+        PatternOp::Binary(_) | PatternOp::Cmp(_) => ARITHMETIC_COST,
+    }
+}
+
+fn instruction_cost(context: &Context, instruction: &Instruction) -> u64 {
+    match instruction {
+        // These only rename or reinterpret values and generate no code.
+        Instruction::AddrOf(_)
+        | Instruction::BitCast(..)
+        | Instruction::CastPtr(..)
+        | Instruction::IntToPtr(..)
+        | Instruction::Nop => 0,
+        Instruction::BinaryOp { .. } | Instruction::Cmp(..) => ARITHMETIC_COST,
+        Instruction::AsmBlock(asm_block, _) => {
+            asm_block.get_content(context).body.len().max(1) as u64
+        }
+        Instruction::ContractCall { .. } => CONTRACT_CALL_COST,
+        Instruction::MemCopy { byte_len, .. } => 1 + (byte_len + WORD_SIZE - 1) / WORD_SIZE,
+        Instruction::FuelVm(fuel_vm_instr) => match fuel_vm_instr {
+            FuelVmInstruction::StateClear { .. }
+            | FuelVmInstruction::StateLoadQuadWord { .. }
+            | FuelVmInstruction::StateLoadWord(_)
+            | FuelVmInstruction::StateStoreQuadWord { .. }
+            | FuelVmInstruction::StateStoreWord { .. } => STATE_ACCESS_COST,
+            // Gas checkpoints become logs once lowered, so assume they always are.
+            FuelVmInstruction::GasCheckpoint(_)
+            | FuelVmInstruction::Log { .. }
+            | FuelVmInstruction::Smo { .. } => RECEIPT_COST,
+            FuelVmInstruction::GetStorageKey
+            | FuelVmInstruction::Gtf { .. }
+            | FuelVmInstruction::ReadRegister(_)
+            | FuelVmInstruction::Revert(_) => 1,
+        },
+        _ => 1,
+    }
+}
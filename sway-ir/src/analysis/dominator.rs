@@ -29,6 +29,23 @@ impl DomTreeNode {
 pub type DomTree = FxHashMap<Block, DomTreeNode>;
 impl AnalysisResultT for DomTree {}
 
+/// Whether `dominator` dominates `block`, i.e., every path from the entry to `block` passes through
+/// it.  Unreachable blocks are neither dominated nor dominate anything.
+pub fn dominates(dom_tree: &DomTree, dominator: Block, mut block: Block) -> bool {
+    if !dom_tree.contains_key(&dominator) {
+        return false;
+    }
+    loop {
+        if block == dominator {
+            return true;
+        }
+        match dom_tree.get(&block).and_then(|node| node.parent) {
+            Some(parent) => block = parent,
+            None => return false,
+        }
+    }
+}
+
 // Dominance frontier sets.
 pub type DomFronts = FxHashMap<Block, FxHashSet<Block>>;
 impl AnalysisResultT for DomFronts {}
@@ -47,6 +64,8 @@ pub fn create_postorder_pass() -> Pass {
         name: POSTORDER_NAME,
         descr: "Postorder traversal of the control-flow graph",
         deps: vec![],
+        preserves: vec![],
+        prune_unreachable: false,
         runner: ScopedPass::FunctionPass(PassMutability::Analysis(compute_post_order_pass)),
     }
 }
@@ -103,17 +122,23 @@ pub fn create_dominators_pass() -> Pass {
         name: DOMINATORS_NAME,
         descr: "Dominator tree computation",
         deps: vec![POSTORDER_NAME],
-        runner: ScopedPass::FunctionPass(PassMutability::Analysis(compute_dom_tree)),
+        preserves: vec![],
+        prune_unreachable: false,
+        runner: ScopedPass::FunctionPass(PassMutability::Analysis(compute_dom_tree_pass)),
     }
 }
 
-/// Compute the dominator tree for the CFG.
-fn compute_dom_tree(
+fn compute_dom_tree_pass(
     context: &Context,
     analyses: &AnalysisResults,
     function: Function,
 ) -> Result<AnalysisResult, IrError> {
     let po: &PostOrder = analyses.get_analysis_result(function);
+    Ok(Box::new(compute_dom_tree(context, &function, po)))
+}
+
+/// Compute the dominator tree for the CFG, given its post ordering.
+pub fn compute_dom_tree(context: &Context, function: &Function, po: &PostOrder) -> DomTree {
     let mut dom_tree = DomTree::default();
     let entry = function.get_entry_block(context);
 
@@ -184,16 +209,14 @@ fn compute_dom_tree(
 
     // Fix the root.
     dom_tree.get_mut(&entry).unwrap().parent = None;
-    // Build the children.
-    let child_parent: Vec<_> = dom_tree
-        .iter()
-        .filter_map(|(n, n_node)| n_node.parent.map(|n_parent| (*n, n_parent)))
-        .collect();
-    for (child, parent) in child_parent {
-        dom_tree.get_mut(&parent).unwrap().children.push(child);
+    // Build the children, in reverse postorder so that walks of the tree are deterministic.
+    for child in po.po_to_block.iter().rev() {
+        if let Some(parent) = dom_tree[child].parent {
+            dom_tree.get_mut(&parent).unwrap().children.push(*child);
+        }
     }
 
-    Ok(Box::new(dom_tree))
+    dom_tree
 }
 
 pub const DOMFRONTS_NAME: &str = "dominance_frontiers";
@@ -203,17 +226,23 @@ pub fn create_dom_fronts_pass() -> Pass {
         name: DOMFRONTS_NAME,
         descr: "Dominator frontiers computation",
         deps: vec![DOMINATORS_NAME],
-        runner: ScopedPass::FunctionPass(PassMutability::Analysis(compute_dom_fronts)),
+        preserves: vec![],
+        prune_unreachable: false,
+        runner: ScopedPass::FunctionPass(PassMutability::Analysis(compute_dom_fronts_pass)),
     }
 }
 
-/// Compute dominance frontiers set for each block.
-fn compute_dom_fronts(
+fn compute_dom_fronts_pass(
     context: &Context,
     analyses: &AnalysisResults,
     function: Function,
 ) -> Result<AnalysisResult, IrError> {
     let dom_tree: &DomTree = analyses.get_analysis_result(function);
+    Ok(Box::new(compute_dom_fronts(context, dom_tree)))
+}
+
+/// Compute dominance frontiers set for each block.
+pub fn compute_dom_fronts(context: &Context, dom_tree: &DomTree) -> DomFronts {
     let mut res = DomFronts::default();
     for (b, _) in dom_tree.iter() {
         res.insert(*b, FxHashSet::default());
@@ -236,33 +265,43 @@ fn compute_dom_fronts(
             }
         }
     }
-    Ok(Box::new(res))
+    res
 }
 
-/// Print dominator tree in the graphviz dot format.
+/// Print dominator tree in the graphviz dot format, with the edges sorted by label.
 pub fn print_dot(context: &Context, func_name: &str, dom_tree: &DomTree) -> String {
+    let mut edges = dom_tree
+        .iter()
+        .filter_map(|(b, node)| {
+            node.parent
+                .map(|idom| (idom.get_label(context), b.get_label(context)))
+        })
+        .collect::<Vec<_>>();
+    edges.sort();
     let mut res = format!("digraph {func_name} {{\n");
-    for (b, idom) in dom_tree.iter() {
-        if let Some(idom) = idom.parent {
-            let _ = writeln!(
-                res,
-                "\t{} -> {}",
-                idom.get_label(context),
-                b.get_label(context)
-            );
-        }
+    for (idom, b) in edges {
+        let _ = writeln!(res, "\t{idom} -> {b}");
     }
     res += "}\n";
     res
 }
 
-/// Print dominator frontiers information.
+/// Print dominator frontiers information, sorted by label.
 pub fn print_dom_fronts(context: &Context, func_name: &str, dom_fronts: &DomFronts) -> String {
+    let mut fronts = dom_fronts
+        .iter()
+        .map(|(b, dfs)| {
+            let mut dfs = dfs.iter().map(|f| f.get_label(context)).collect::<Vec<_>>();
+            dfs.sort();
+            (b.get_label(context), dfs)
+        })
+        .collect::<Vec<_>>();
+    fronts.sort();
     let mut res = format!("Dominance frontiers set for {func_name}:\n");
-    for (b, dfs) in dom_fronts.iter() {
-        res += &("\t".to_string() + &b.get_label(context) + ": ");
+    for (b, dfs) in fronts {
+        res += &format!("\t{b}: ");
         for f in dfs {
-            res += &(f.get_label(context) + " ");
+            res += &format!("{f} ");
         }
         res += "\n";
     }
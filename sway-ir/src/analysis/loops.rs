@@ -0,0 +1,422 @@
+//! Natural loops of the control-flow graph.
+//!
+//! A back edge is an edge whose destination dominates its source.  The natural loop of a header
+//! is made of the header and every block which can reach the source of one of its back edges
+//! without passing through the header.  Natural loops with different headers are either disjoint
+//! or nested, which gives the loops of a function a tree structure.
+
+use rustc_hash::FxHashSet;
+
+use crate::{
+    block::Block, compute_dom_tree, compute_post_order, dominates, AnalysisResult, AnalysisResultT,
+    AnalysisResults, BinaryOpKind, BranchToWithArgs, ConstantValue, Context, DomTree, Function,
+    Instruction, IrError, Pass, PassMutability, Predicate, ScopedPass, Value, DOMINATORS_NAME,
+};
+
+/// The most iterations [`Loop::trip_count`] will count before giving up.
+pub const TRIP_COUNT_LIMIT: u64 = 1 << 16;
+
+/// An induction variable of a loop: an argument of its header which starts at a constant and is
+/// changed by a constant step on every iteration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InductionVariable {
+    /// The index of the argument of the header.
+    pub arg_idx: usize,
+    /// The value on entry to the loop.
+    pub init: u64,
+    /// The amount added on each iteration, or subtracted if `decreasing`.
+    pub step: u64,
+    pub decreasing: bool,
+    /// The largest value of the variable's type, past which a step overflows.
+    pub max: u64,
+}
+
+/// A natural loop.
+#[derive(Clone)]
+pub struct Loop {
+    /// The single entry to the loop, which dominates all of its blocks.
+    pub header: Block,
+    /// The sources of the back edges to the header, in block order.
+    pub latches: Vec<Block>,
+    /// The blocks of the loop, including the header and the blocks of any nested loops.
+    pub blocks: FxHashSet<Block>,
+    /// The index of the innermost loop containing this one in [`LoopInfo::loops`], if any.
+    pub parent: Option<usize>,
+}
+
+impl Loop {
+    /// Return an iterator over the blocks of the loop, in block order.
+    pub fn blocks_iter<'a>(&'a self, context: &'a Context) -> impl Iterator<Item = Block> + 'a {
+        self.header
+            .get_function(context)
+            .block_iter(context)
+            .filter(|block| self.blocks.contains(block))
+    }
+
+    /// Return the single latch of the loop, if it has only one.
+    pub fn latch(&self) -> Option<Block> {
+        match self.latches.as_slice() {
+            [latch] => Some(*latch),
+            _ => None,
+        }
+    }
+
+    /// Return the blocks outside the loop which are successors of a block within it, in block
+    /// order.
+    pub fn exits(&self, context: &Context) -> Vec<Block> {
+        let succs = self
+            .blocks
+            .iter()
+            .flat_map(|block| block.successors(context))
+            .map(|BranchToWithArgs { block: succ, .. }| succ)
+            .filter(|succ| !self.blocks.contains(succ))
+            .collect::<FxHashSet<_>>();
+        self.header
+            .get_function(context)
+            .block_iter(context)
+            .filter(|block| succs.contains(block))
+            .collect()
+    }
+
+    /// Return the preheader of the loop, if it has one: the single predecessor of the header from
+    /// outside the loop, whose only successor is the header.
+    pub fn preheader(&self, context: &Context) -> Option<Block> {
+        let mut outside_preds = self
+            .header
+            .pred_iter(context)
+            .filter(|pred| !self.blocks.contains(pred));
+        match (outside_preds.next(), outside_preds.next()) {
+            (Some(pred), None) if pred.successors(context).len() == 1 => Some(*pred),
+            _ => None,
+        }
+    }
+
+    /// Return the induction variables of the loop, which needs a single latch and a single branch
+    /// to its header from outside.
+    pub fn induction_variables(&self, context: &Context) -> Vec<InductionVariable> {
+        // The parameters passed to the header along its only edge from `from`, if it has one.
+        let params_from = |from: &Block| {
+            let mut edges = from
+                .successors(context)
+                .into_iter()
+                .filter(|succ| succ.block == self.header);
+            match (edges.next(), edges.next()) {
+                (Some(edge), None) => Some(edge.args),
+                _ => None,
+            }
+        };
+        let mut outside_preds = self
+            .header
+            .pred_iter(context)
+            .filter(|pred| !self.blocks.contains(pred));
+        let (Some(entry), None) = (outside_preds.next(), outside_preds.next()) else {
+            return Vec::new();
+        };
+        let (Some(init_params), Some(next_params)) = (
+            params_from(entry),
+            self.latch().and_then(|latch| params_from(&latch)),
+        ) else {
+            return Vec::new();
+        };
+
+        self.header
+            .arg_iter(context)
+            .enumerate()
+            .filter_map(|(arg_idx, arg)| {
+                let width = arg.get_type(context)?.get_uint_width(context)?;
+                let init = uint_constant(context, &init_params[arg_idx])?;
+                let (step, decreasing) = match next_params[arg_idx].get_instruction(context)? {
+                    Instruction::BinaryOp {
+                        op: BinaryOpKind::Add,
+                        arg1,
+                        arg2,
+                    } if arg1 == arg => (uint_constant(context, arg2)?, false),
+                    Instruction::BinaryOp {
+                        op: BinaryOpKind::Add,
+                        arg1,
+                        arg2,
+                    } if arg2 == arg => (uint_constant(context, arg1)?, false),
+                    Instruction::BinaryOp {
+                        op: BinaryOpKind::Sub,
+                        arg1,
+                        arg2,
+                    } if arg1 == arg => (uint_constant(context, arg2)?, true),
+                    _ => return None,
+                };
+                Some(InductionVariable {
+                    arg_idx,
+                    init,
+                    step,
+                    decreasing,
+                    max: if width >= 64 {
+                        u64::MAX
+                    } else {
+                        (1 << width) - 1
+                    },
+                })
+            })
+            .collect()
+    }
+
+    /// Return the number of times the body of a counted loop runs.
+    ///
+    /// A counted loop is left only from its header, depending on how an induction variable
+    /// compares with a constant, which is how the frontend lowers a `while` loop over a counter.
+    /// The count is found by stepping the variable, so it is `None` for a loop running more than
+    /// [`TRIP_COUNT_LIMIT`] times or whose variable would overflow.
+    pub fn trip_count(&self, context: &Context) -> Option<u64> {
+        let leaves_loop = |block: &Block| {
+            block
+                .successors(context)
+                .iter()
+                .any(|succ| !self.blocks.contains(&succ.block))
+        };
+        if self
+            .blocks
+            .iter()
+            .any(|block| *block != self.header && leaves_loop(block))
+        {
+            return None;
+        }
+        let Some(Instruction::ConditionalBranch {
+            cond_value,
+            true_block,
+            false_block,
+        }) = self.header.get_terminator(context)
+        else {
+            return None;
+        };
+        let continues_if = match (
+            self.blocks.contains(&true_block.block),
+            self.blocks.contains(&false_block.block),
+        ) {
+            (true, false) => true,
+            (false, true) => false,
+            _ => return None,
+        };
+        let Some(Instruction::Cmp(pred, lhs, rhs)) = cond_value.get_instruction(context) else {
+            return None;
+        };
+
+        let ivs = self.induction_variables(context);
+        let iv_of = |value: &Value| {
+            ivs.iter()
+                .find(|iv| self.header.get_arg(context, iv.arg_idx) == Some(*value))
+        };
+        let (iv, bound, iv_is_lhs) = match (iv_of(lhs), iv_of(rhs)) {
+            (Some(iv), None) => (iv, uint_constant(context, rhs)?, true),
+            (None, Some(iv)) => (iv, uint_constant(context, lhs)?, false),
+            _ => return None,
+        };
+
+        let mut value = iv.init;
+        for count in 0..=TRIP_COUNT_LIMIT {
+            let (lhs, rhs) = if iv_is_lhs {
+                (value, bound)
+            } else {
+                (bound, value)
+            };
+            let cond = match pred {
+                Predicate::Equal => lhs == rhs,
+                Predicate::LessThan => lhs < rhs,
+                Predicate::GreaterThan => lhs > rhs,
+            };
+            if cond != continues_if {
+                return Some(count);
+            }
+            value = if iv.decreasing {
+                value.checked_sub(iv.step)?
+            } else {
+                value.checked_add(iv.step).filter(|next| *next <= iv.max)?
+            };
+        }
+        None
+    }
+}
+
+fn uint_constant(context: &Context, value: &Value) -> Option<u64> {
+    match value.get_constant(context)?.value {
+        ConstantValue::Uint(n) => Some(n),
+        _ => None,
+    }
+}
+
+/// The natural loops of a function.
+#[derive(Clone)]
+pub struct LoopInfo {
+    /// The loops in the block order of their headers.
+    pub loops: Vec<Loop>,
+}
+impl AnalysisResultT for LoopInfo {}
+
+impl LoopInfo {
+    /// Return the index of the innermost loop containing `block`, if any.
+    pub fn innermost_loop(&self, block: Block) -> Option<usize> {
+        self.loops
+            .iter()
+            .enumerate()
+            .filter(|(_, lp)| lp.blocks.contains(&block))
+            .min_by_key(|(_, lp)| lp.blocks.len())
+            .map(|(idx, _)| idx)
+    }
+
+    /// Return the number of loops containing the loop at `idx`, including itself.
+    pub fn depth(&self, idx: usize) -> usize {
+        std::iter::successors(Some(idx), |idx| self.loops[*idx].parent).count()
+    }
+
+    /// Return whether the edge from `from` to `to` is a back edge of a loop.
+    pub fn is_back_edge(&self, from: Block, to: Block) -> bool {
+        self.loops
+            .iter()
+            .any(|lp| lp.header == to && lp.latches.contains(&from))
+    }
+
+    /// Return the preheader of the loop at `idx`, creating one if it has none.
+    ///
+    /// A new preheader is inserted before the header, takes the same arguments and passes them on
+    /// to it, and every branch to the header from outside the loop is redirected to it.  It is
+    /// added to the loops containing this one, so that this `LoopInfo` remains valid.  Returns
+    /// `None` if the header is the entry block, which can't have a preheader.
+    pub fn get_or_create_preheader(&mut self, context: &mut Context, idx: usize) -> Option<Block> {
+        let header = self.loops[idx].header;
+        if let Some(preheader) = self.loops[idx].preheader(context) {
+            return Some(preheader);
+        }
+        let function = header.get_function(context);
+        if function.get_entry_block(context) == header {
+            return None;
+        }
+
+        let label = format!("{}_preheader", header.get_label(context));
+        let preheader = function
+            .create_block_before(context, &header, Some(label))
+            .unwrap();
+        let mut args = Vec::new();
+        for arg in header.arg_iter(context).copied().collect::<Vec<_>>() {
+            let (ty, by_ref) = arg.get_argument_type_and_byref(context).unwrap();
+            let arg_idx = preheader.new_arg(context, ty, by_ref);
+            args.push(preheader.get_arg(context, arg_idx).unwrap());
+        }
+
+        let outside_preds = header
+            .pred_iter(context)
+            .filter(|pred| !self.loops[idx].blocks.contains(pred))
+            .copied()
+            .collect::<Vec<_>>();
+        for pred in outside_preds {
+            let to_blocks = match pred.get_terminator_mut(context) {
+                Some(Instruction::Branch(to_block)) => vec![to_block],
+                Some(Instruction::ConditionalBranch {
+                    true_block,
+                    false_block,
+                    ..
+                }) => vec![true_block, false_block],
+                _ => Vec::new(),
+            };
+            for to_block in to_blocks {
+                if to_block.block == header {
+                    to_block.block = preheader;
+                }
+            }
+            header.remove_pred(context, &pred);
+            preheader.add_pred(context, &pred);
+        }
+        preheader.ins(context).branch(header, args);
+
+        let mut parent = self.loops[idx].parent;
+        while let Some(parent_idx) = parent {
+            self.loops[parent_idx].blocks.insert(preheader);
+            parent = self.loops[parent_idx].parent;
+        }
+        Some(preheader)
+    }
+}
+
+pub const LOOPS_NAME: &str = "loops";
+
+pub fn create_loops_pass() -> Pass {
+    Pass {
+        name: LOOPS_NAME,
+        descr: "Natural loop detection",
+        deps: vec![DOMINATORS_NAME],
+        preserves: vec![],
+        prune_unreachable: false,
+        runner: ScopedPass::FunctionPass(PassMutability::Analysis(compute_loop_info_pass)),
+    }
+}
+
+fn compute_loop_info_pass(
+    context: &Context,
+    analyses: &AnalysisResults,
+    function: Function,
+) -> Result<AnalysisResult, IrError> {
+    let dom_tree: &DomTree = analyses.get_analysis_result(function);
+    Ok(Box::new(compute_loop_info(context, &function, dom_tree)))
+}
+
+/// Compute the natural loops of a function from its dominator tree.  Unreachable blocks are never
+/// part of a loop.
+pub fn compute_loop_info(context: &Context, function: &Function, dom_tree: &DomTree) -> LoopInfo {
+    // Group the back edges by their header.
+    let mut loops = Vec::<Loop>::new();
+    for header in function.block_iter(context) {
+        if !dom_tree.contains_key(&header) {
+            continue;
+        }
+        let latches = function
+            .block_iter(context)
+            .filter(|block| {
+                dom_tree.contains_key(block)
+                    && block
+                        .successors(context)
+                        .iter()
+                        .any(|BranchToWithArgs { block: succ, .. }| *succ == header)
+                    && dominates(dom_tree, header, *block)
+            })
+            .collect::<Vec<_>>();
+        if latches.is_empty() {
+            continue;
+        }
+
+        // Walk backwards from the latches, stopping at the header.
+        let mut blocks = FxHashSet::from_iter([header]);
+        let mut worklist = latches.clone();
+        while let Some(block) = worklist.pop() {
+            if blocks.insert(block) {
+                worklist.extend(
+                    block
+                        .pred_iter(context)
+                        .filter(|pred| dom_tree.contains_key(pred)),
+                );
+            }
+        }
+        loops.push(Loop {
+            header,
+            latches,
+            blocks,
+            parent: None,
+        });
+    }
+
+    // The parent of a loop is the smallest other loop containing its header.
+    for idx in 0..loops.len() {
+        loops[idx].parent = loops
+            .iter()
+            .enumerate()
+            .filter(|(other_idx, other)| {
+                *other_idx != idx && other.blocks.contains(&loops[idx].header)
+            })
+            .min_by_key(|(_, other)| other.blocks.len())
+            .map(|(other_idx, _)| other_idx);
+    }
+
+    LoopInfo { loops }
+}
+
+/// Compute the natural loops of a function, along with the analyses they depend on.
+pub fn compute_function_loop_info(context: &Context, function: &Function) -> LoopInfo {
+    let po = compute_post_order(context, function);
+    let dom_tree = compute_dom_tree(context, function, &po);
+    compute_loop_info(context, function, &dom_tree)
+}
@@ -0,0 +1,401 @@
+//! Memory SSA.
+//!
+//! All of memory and storage is treated as a single variable, which each instruction that may
+//! write to it redefines.  As with SSA for values, a phi merges the versions reaching a block from
+//! its predecessors, placed at the iterated dominance frontier of the blocks with definitions.
+//! Each instruction which reads or writes memory is then linked to the version it sees, so the
+//! stores which may have last written what a load reads can be found by walking back along the
+//! chain, rather than scanning the instructions before it.
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    compute_dom_fronts, compute_dom_tree, compute_post_order, AliasAnalysis, AliasResult,
+    AnalysisResult, AnalysisResultT, AnalysisResults, Block, BranchToWithArgs, Context, DomFronts,
+    DomTree, FuelVmInstruction, Function, FunctionAttribute, Instruction, IrError, MemoryLocation,
+    Pass, PassMutability, ScopedPass, Value, DOMFRONTS_NAME, DOMINATORS_NAME,
+};
+
+/// The most definitions and phis [`MemorySsa::clobbering_access`] will look through before giving
+/// up.
+const WALK_LIMIT: usize = 100;
+
+/// A version of memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MemoryAccess {
+    /// Memory as it is on entry to the function.
+    LiveOnEntry,
+    /// Memory after an instruction which may write to it.
+    Def(Value),
+    /// Memory at the start of a block, merging the versions from its predecessors.
+    Phi(Block),
+}
+
+/// Something which sees a version of memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MemoryUser {
+    /// An instruction which reads or writes memory.
+    Instruction(Value),
+    /// A phi merging it with the versions from other predecessors.
+    Phi(Block),
+}
+
+/// The locations an instruction reads and writes.  `None` means it may access anything.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemoryEffects {
+    pub reads: Option<Vec<MemoryLocation>>,
+    pub writes: Option<Vec<MemoryLocation>>,
+}
+
+impl MemoryEffects {
+    /// Return the locations accessed by `inst`.
+    pub fn of(context: &Context, inst: Value) -> Self {
+        let memory = |ptr: &Value| MemoryLocation::Memory(*ptr);
+        // Values of copy types are held in registers, other values are pointers to memory.
+        let memory_value = |val: &Value| {
+            val.get_type(context)
+                .filter(|ty| !(ty.is_unit(context) || ty.is_bool(context) || ty.is_uint(context)))
+                .map(|_| MemoryLocation::Memory(*val))
+        };
+        let effects = |reads: Vec<MemoryLocation>, writes: Vec<MemoryLocation>| MemoryEffects {
+            reads: Some(reads),
+            writes: Some(writes),
+        };
+        let Some(instruction) = inst.get_instruction(context) else {
+            return MemoryEffects::default();
+        };
+        match instruction {
+            Instruction::Load(ptr) => effects(vec![memory(ptr)], vec![]),
+            Instruction::Store {
+                dst_val,
+                stored_val,
+            } => effects(
+                memory_value(stored_val).into_iter().collect(),
+                vec![memory(dst_val)],
+            ),
+            Instruction::MemCopy {
+                dst_val, src_val, ..
+            } => effects(vec![memory(src_val)], vec![memory(dst_val)]),
+            Instruction::ExtractElement { array, .. } => effects(vec![memory(array)], vec![]),
+            Instruction::ExtractValue { aggregate, .. } => effects(vec![memory(aggregate)], vec![]),
+            Instruction::InsertElement { array, value, .. } => effects(
+                memory_value(value).into_iter().collect(),
+                vec![memory(array)],
+            ),
+            Instruction::InsertValue {
+                aggregate, value, ..
+            } => effects(
+                memory_value(value).into_iter().collect(),
+                vec![memory(aggregate)],
+            ),
+            Instruction::Cmp(_, lhs, rhs) => effects(
+                memory_value(lhs)
+                    .into_iter()
+                    .chain(memory_value(rhs))
+                    .collect(),
+                vec![],
+            ),
+            Instruction::Ret(val, _) => effects(memory_value(val).into_iter().collect(), vec![]),
+            Instruction::Call(callee, _)
+                if callee.has_attribute(context, FunctionAttribute::Pure) =>
+            {
+                MemoryEffects {
+                    reads: None,
+                    writes: Some(vec![]),
+                }
+            }
+            Instruction::FuelVm(FuelVmInstruction::Log { .. } | FuelVmInstruction::Smo { .. }) => {
+                MemoryEffects {
+                    reads: None,
+                    writes: Some(vec![]),
+                }
+            }
+            Instruction::FuelVm(FuelVmInstruction::StateLoadWord(key)) => {
+                effects(vec![MemoryLocation::Storage(*key), memory(key)], vec![])
+            }
+            Instruction::FuelVm(FuelVmInstruction::StateLoadQuadWord { load_val, key, .. }) => {
+                effects(
+                    vec![MemoryLocation::Storage(*key), memory(key)],
+                    vec![memory(load_val)],
+                )
+            }
+            Instruction::FuelVm(FuelVmInstruction::StateStoreWord { key, .. }) => {
+                effects(vec![memory(key)], vec![MemoryLocation::Storage(*key)])
+            }
+            Instruction::FuelVm(FuelVmInstruction::StateStoreQuadWord {
+                stored_val, key, ..
+            }) => effects(
+                vec![memory(stored_val), memory(key)],
+                vec![MemoryLocation::Storage(*key)],
+            ),
+            Instruction::FuelVm(FuelVmInstruction::StateClear { key, .. }) => {
+                effects(vec![memory(key)], vec![MemoryLocation::Storage(*key)])
+            }
+            Instruction::AsmBlock(..)
+            | Instruction::Call(..)
+            | Instruction::ContractCall { .. } => MemoryEffects {
+                reads: None,
+                writes: None,
+            },
+            _ => effects(vec![], vec![]),
+        }
+    }
+
+    /// Whether any memory may be written.
+    pub fn is_def(&self) -> bool {
+        self.writes
+            .as_ref()
+            .map_or(true, |writes| !writes.is_empty())
+    }
+
+    /// Whether any memory may be read or written.
+    pub fn is_access(&self) -> bool {
+        self.is_def() || self.reads.as_ref().map_or(true, |reads| !reads.is_empty())
+    }
+}
+
+/// The memory SSA form of a function.
+#[derive(Clone, Default)]
+pub struct MemorySsa {
+    /// The version of memory seen by each instruction which accesses it.
+    defining: FxHashMap<Value, MemoryAccess>,
+    /// The version reaching each phi from each of its block's predecessors.
+    phis: FxHashMap<Block, Vec<(Block, MemoryAccess)>>,
+    /// The users of each version.
+    users: FxHashMap<MemoryAccess, Vec<MemoryUser>>,
+}
+impl AnalysisResultT for MemorySsa {}
+
+impl MemorySsa {
+    /// Return the version of memory seen by `inst`, if it accesses memory in a reachable block.
+    pub fn defining_access(&self, inst: Value) -> Option<MemoryAccess> {
+        self.defining.get(&inst).copied()
+    }
+
+    /// Return the version reaching the phi at the start of `block` from each predecessor, if it
+    /// has one.
+    pub fn phi_incoming(&self, block: Block) -> Option<&[(Block, MemoryAccess)]> {
+        self.phis.get(&block).map(Vec::as_slice)
+    }
+
+    /// Return the instructions and phis which see `access`.
+    pub fn users(&self, access: MemoryAccess) -> &[MemoryUser] {
+        self.users.get(&access).map_or(&[], Vec::as_slice)
+    }
+
+    /// Return the nearest version before `inst` which may have changed what it reads: a definition
+    /// which may write to one of its locations, a phi merging different such definitions, or the
+    /// memory on entry.  For a load this is the store it may read from, if any.
+    pub fn clobbering_access(
+        &self,
+        context: &Context,
+        aa: &dyn AliasAnalysis,
+        inst: Value,
+    ) -> Option<MemoryAccess> {
+        let access = self.defining_access(inst)?;
+        let Some(reads) = MemoryEffects::of(context, inst).reads else {
+            return Some(access);
+        };
+        Some(self.clobbering_access_from(context, aa, &reads, access))
+    }
+
+    /// Return the nearest version at or before `access` which may have changed any of `reads`.
+    pub fn clobbering_access_from(
+        &self,
+        context: &Context,
+        aa: &dyn AliasAnalysis,
+        reads: &[MemoryLocation],
+        access: MemoryAccess,
+    ) -> MemoryAccess {
+        let mut budget = WALK_LIMIT;
+        let clobber = self.walk(
+            context,
+            aa,
+            reads,
+            access,
+            &mut FxHashSet::default(),
+            &mut budget,
+        );
+        // A walk which was cut short may have stopped at something which doesn't clobber.
+        if budget == 0 {
+            return access;
+        }
+        clobber.unwrap_or(access)
+    }
+
+    // Walk back from `access` to the nearest version which may write to `reads`.  `None` means
+    // the walk came back to a phi already being walked through, so found nothing on that path.
+    fn walk(
+        &self,
+        context: &Context,
+        aa: &dyn AliasAnalysis,
+        reads: &[MemoryLocation],
+        mut access: MemoryAccess,
+        visiting: &mut FxHashSet<Block>,
+        budget: &mut usize,
+    ) -> Option<MemoryAccess> {
+        loop {
+            if *budget == 0 {
+                return Some(access);
+            }
+            *budget -= 1;
+            match access {
+                MemoryAccess::LiveOnEntry => return Some(access),
+                MemoryAccess::Def(def) => {
+                    let clobbers = MemoryEffects::of(context, def)
+                        .writes
+                        .map_or(true, |writes| {
+                            writes.iter().any(|write| {
+                                reads.iter().any(|read| {
+                                    aa.may_alias_locations(context, *write, *read)
+                                        != AliasResult::NoAlias
+                                })
+                            })
+                        });
+                    if clobbers {
+                        return Some(access);
+                    }
+                    access = self.defining[&def];
+                }
+                MemoryAccess::Phi(block) => {
+                    if !visiting.insert(block) {
+                        return None;
+                    }
+                    let mut found = None;
+                    for (_, incoming) in &self.phis[&block] {
+                        match self.walk(context, aa, reads, *incoming, visiting, budget) {
+                            Some(clobber) if found.map_or(false, |found| found != clobber) => {
+                                found = Some(access);
+                                break;
+                            }
+                            Some(clobber) => found = Some(clobber),
+                            None => (),
+                        }
+                    }
+                    visiting.remove(&block);
+                    return Some(found.unwrap_or(access));
+                }
+            }
+        }
+    }
+}
+
+pub const MEMORY_SSA_NAME: &str = "memory_ssa";
+
+pub fn create_memory_ssa_pass() -> Pass {
+    Pass {
+        name: MEMORY_SSA_NAME,
+        descr: "Memory SSA construction",
+        deps: vec![DOMINATORS_NAME, DOMFRONTS_NAME],
+        preserves: vec![],
+        prune_unreachable: false,
+        runner: ScopedPass::FunctionPass(PassMutability::Analysis(compute_memory_ssa_pass)),
+    }
+}
+
+fn compute_memory_ssa_pass(
+    context: &Context,
+    analyses: &AnalysisResults,
+    function: Function,
+) -> Result<AnalysisResult, IrError> {
+    let dom_tree: &DomTree = analyses.get_analysis_result(function);
+    let dom_fronts: &DomFronts = analyses.get_analysis_result(function);
+    Ok(Box::new(compute_memory_ssa(
+        context, &function, dom_tree, dom_fronts,
+    )))
+}
+
+/// Compute the memory SSA form of a function.  Unreachable blocks are left out.
+pub fn compute_memory_ssa(
+    context: &Context,
+    function: &Function,
+    dom_tree: &DomTree,
+    dom_fronts: &DomFronts,
+) -> MemorySsa {
+    let entry_block = function.get_entry_block(context);
+    let effects = function
+        .instruction_iter(context)
+        .filter(|(block, _)| dom_tree.contains_key(block))
+        .filter_map(|(_, inst)| {
+            let effects = MemoryEffects::of(context, inst);
+            effects.is_access().then(|| (inst, effects.is_def()))
+        })
+        .collect::<FxHashMap<_, _>>();
+
+    // Place phis at the iterated dominance frontier of the blocks with definitions, counting the
+    // memory on entry as defined in the entry block.
+    let mut phi_blocks = FxHashSet::default();
+    let mut worklist = function
+        .block_iter(context)
+        .filter(|block| {
+            *block == entry_block
+                || block
+                    .instruction_iter(context)
+                    .any(|inst| effects.get(&inst).copied().unwrap_or(false))
+        })
+        .collect::<Vec<_>>();
+    while let Some(block) = worklist.pop() {
+        for frontier in dom_fronts.get(&block).into_iter().flatten() {
+            if phi_blocks.insert(*frontier) {
+                worklist.push(*frontier);
+            }
+        }
+    }
+
+    // Walk down the dominator tree tracking the current version, as the version at the end of a
+    // block reaches every block it immediately dominates which doesn't start with a phi.
+    let mut memory_ssa = MemorySsa {
+        phis: phi_blocks
+            .iter()
+            .map(|block| (*block, Vec::new()))
+            .collect(),
+        ..MemorySsa::default()
+    };
+    let mut worklist = vec![(entry_block, MemoryAccess::LiveOnEntry)];
+    while let Some((block, mut current)) = worklist.pop() {
+        if phi_blocks.contains(&block) {
+            current = MemoryAccess::Phi(block);
+        }
+        for inst in block.instruction_iter(context) {
+            let Some(is_def) = effects.get(&inst) else {
+                continue;
+            };
+            memory_ssa.defining.insert(inst, current);
+            memory_ssa
+                .users
+                .entry(current)
+                .or_default()
+                .push(MemoryUser::Instruction(inst));
+            if *is_def {
+                current = MemoryAccess::Def(inst);
+            }
+        }
+
+        for BranchToWithArgs { block: succ, .. } in block.successors(context) {
+            if let Some(incoming) = memory_ssa.phis.get_mut(&succ) {
+                // Both arms of a conditional branch may go to the same block.
+                if !incoming.iter().any(|(pred, _)| *pred == block) {
+                    incoming.push((block, current));
+                    memory_ssa
+                        .users
+                        .entry(current)
+                        .or_default()
+                        .push(MemoryUser::Phi(succ));
+                }
+            }
+        }
+        for child in &dom_tree[&block].children {
+            worklist.push((*child, current));
+        }
+    }
+    memory_ssa
+}
+
+/// Compute the memory SSA form of a function, computing the dominator tree and dominance
+/// frontiers it depends on along the way.
+pub fn compute_function_memory_ssa(context: &Context, function: &Function) -> MemorySsa {
+    let po = compute_post_order(context, function);
+    let dom_tree = compute_dom_tree(context, function, &po);
+    let dom_fronts = compute_dom_fronts(context, &dom_tree);
+    compute_memory_ssa(context, function, &dom_tree, &dom_fronts)
+}
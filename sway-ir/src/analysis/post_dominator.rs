@@ -0,0 +1,260 @@
+//! Post-dominator tree and post-dominance frontiers.
+//!
+//! A block post-dominates another if every path from the other to an exit of the function passes
+//! through it.  These are the dominators of the reversed control-flow graph, computed with the
+//! same algorithm as [`compute_dom_tree`](crate::compute_dom_tree), and the post-dominance
+//! frontiers are the basis of control dependence.
+//!
+//! A function may have several exits, so a virtual exit node is added as the root of the tree,
+//! with an edge to it from every block without successors.  Blocks in an infinite loop never
+//! reach an exit, so one block of each such loop is also given an edge to the virtual exit, as if
+//! the loop could be left from there.  Blocks which are unreachable from the entry are ignored.
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    block::Block, compute_post_order, AnalysisResult, AnalysisResultT, AnalysisResults,
+    BranchToWithArgs, Context, DomTreeNode, Function, IrError, Pass, PassMutability, ScopedPass,
+};
+
+/// The post-dominator tree, mapping each reachable block to its node.
+///
+/// The parent of a node is its immediate post-dominator, or `None` if that's the virtual exit.
+pub struct PostDomTree {
+    pub nodes: FxHashMap<Block, DomTreeNode>,
+    /// The blocks immediately post-dominated by the virtual exit, in block order.
+    pub roots: Vec<Block>,
+}
+impl AnalysisResultT for PostDomTree {}
+
+impl PostDomTree {
+    /// Return whether `post_dominator` post-dominates `block`.  Every block post-dominates itself.
+    pub fn post_dominates(&self, post_dominator: Block, mut block: Block) -> bool {
+        loop {
+            if block == post_dominator {
+                break true;
+            }
+            match self.nodes.get(&block).and_then(|node| node.parent) {
+                Some(parent) => block = parent,
+                None => break false,
+            }
+        }
+    }
+}
+
+/// The post-dominance frontier of each reachable block: the blocks on which it is control
+/// dependent.
+pub struct PostDomFronts(pub FxHashMap<Block, FxHashSet<Block>>);
+impl AnalysisResultT for PostDomFronts {}
+
+pub const POSTDOMINATORS_NAME: &str = "postdominators";
+
+pub fn create_post_dominators_pass() -> Pass {
+    Pass {
+        name: POSTDOMINATORS_NAME,
+        descr: "Post-dominator tree computation",
+        deps: vec![],
+        preserves: vec![],
+        prune_unreachable: false,
+        runner: ScopedPass::FunctionPass(PassMutability::Analysis(compute_post_dom_tree_pass)),
+    }
+}
+
+fn compute_post_dom_tree_pass(
+    context: &Context,
+    _: &AnalysisResults,
+    function: Function,
+) -> Result<AnalysisResult, IrError> {
+    Ok(Box::new(compute_post_dom_tree(context, &function)))
+}
+
+/// Compute the post-dominator tree of a function.
+pub fn compute_post_dom_tree(context: &Context, function: &Function) -> PostDomTree {
+    // Number the reachable blocks in block order, with the virtual exit after them.
+    let reachable = compute_post_order(context, function).block_to_po;
+    let blocks = function
+        .block_iter(context)
+        .filter(|block| reachable.contains_key(block))
+        .collect::<Vec<_>>();
+    let index = blocks
+        .iter()
+        .enumerate()
+        .map(|(idx, block)| (*block, idx))
+        .collect::<FxHashMap<_, _>>();
+    let exit = blocks.len();
+
+    let succs = blocks
+        .iter()
+        .map(|block| {
+            block
+                .successors(context)
+                .iter()
+                .map(|BranchToWithArgs { block: succ, .. }| index[succ])
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let mut preds = vec![Vec::new(); blocks.len()];
+    for (idx, block_succs) in succs.iter().enumerate() {
+        for succ in block_succs {
+            if !preds[*succ].contains(&idx) {
+                preds[*succ].push(idx);
+            }
+        }
+    }
+
+    // Connect the exits, then a block of each infinite loop, to the virtual exit.  The latest
+    // block which can't yet reach an exit is picked, which for a loop at the end of a function is
+    // usually its latch.
+    let mut exits = (0..blocks.len())
+        .filter(|idx| succs[*idx].is_empty())
+        .collect::<Vec<_>>();
+    let mut rpo = reverse_post_order(&preds, &exits, exit);
+    while rpo.len() < blocks.len() + 1 {
+        let reached = rpo.iter().copied().collect::<FxHashSet<_>>();
+        let unreached = (0..blocks.len()).rev().find(|idx| !reached.contains(idx));
+        exits.push(unreached.unwrap());
+        rpo = reverse_post_order(&preds, &exits, exit);
+    }
+
+    // The dominator algorithm over the reversed graph, in which the predecessors of a block are
+    // its successors, or the virtual exit.
+    let order = rpo
+        .iter()
+        .enumerate()
+        .map(|(order, idx)| (*idx, order))
+        .collect::<FxHashMap<_, _>>();
+    let rev_preds = |idx: usize| {
+        succs[idx]
+            .iter()
+            .copied()
+            .chain(exits.contains(&idx).then_some(exit))
+            .collect::<Vec<_>>()
+    };
+    let mut idoms: Vec<Option<usize>> = vec![None; blocks.len() + 1];
+    idoms[exit] = Some(exit);
+    let intersect = |idoms: &[Option<usize>], mut finger1: usize, mut finger2: usize| {
+        while finger1 != finger2 {
+            while order[&finger1] > order[&finger2] {
+                finger1 = idoms[finger1].unwrap();
+            }
+            while order[&finger2] > order[&finger1] {
+                finger2 = idoms[finger2].unwrap();
+            }
+        }
+        finger1
+    };
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for idx in rpo.iter().skip(1).copied() {
+            let mut new_idom = None;
+            for pred in rev_preds(idx) {
+                if idoms[pred].is_some() {
+                    new_idom = Some(match new_idom {
+                        None => pred,
+                        Some(new_idom) => intersect(&idoms, pred, new_idom),
+                    });
+                }
+            }
+            if new_idom.is_some() && idoms[idx] != new_idom {
+                idoms[idx] = new_idom;
+                changed = true;
+            }
+        }
+    }
+
+    let mut nodes = blocks
+        .iter()
+        .enumerate()
+        .map(|(idx, block)| {
+            let parent = idoms[idx]
+                .filter(|idom| *idom != exit)
+                .map(|idom| blocks[idom]);
+            (*block, DomTreeNode::new(parent))
+        })
+        .collect::<FxHashMap<_, _>>();
+    let mut roots = Vec::new();
+    for block in &blocks {
+        match nodes[block].parent {
+            Some(parent) => nodes.get_mut(&parent).unwrap().children.push(*block),
+            None => roots.push(*block),
+        }
+    }
+    PostDomTree { nodes, roots }
+}
+
+/// The reverse post order of the reversed graph from the virtual exit, which is first.
+fn reverse_post_order(preds: &[Vec<usize>], exits: &[usize], exit: usize) -> Vec<usize> {
+    let mut visited = FxHashSet::from_iter([exit]);
+    let mut post_order = Vec::new();
+    // An explicit stack of each node and the index of its next child, to cope with deep graphs.
+    let mut stack = vec![(exit, 0)];
+    while let Some((node, child)) = stack.last_mut() {
+        let children = if *node == exit { exits } else { &preds[*node] };
+        match children.get(*child) {
+            Some(next) => {
+                *child += 1;
+                if visited.insert(*next) {
+                    stack.push((*next, 0));
+                }
+            }
+            None => {
+                post_order.push(*node);
+                stack.pop();
+            }
+        }
+    }
+    post_order.reverse();
+    post_order
+}
+
+pub const POSTDOMFRONTS_NAME: &str = "post_dominance_frontiers";
+
+pub fn create_post_dom_fronts_pass() -> Pass {
+    Pass {
+        name: POSTDOMFRONTS_NAME,
+        descr: "Post-dominance frontiers computation",
+        deps: vec![POSTDOMINATORS_NAME],
+        preserves: vec![],
+        prune_unreachable: false,
+        runner: ScopedPass::FunctionPass(PassMutability::Analysis(compute_post_dom_fronts_pass)),
+    }
+}
+
+fn compute_post_dom_fronts_pass(
+    context: &Context,
+    analyses: &AnalysisResults,
+    function: Function,
+) -> Result<AnalysisResult, IrError> {
+    let post_dom_tree: &PostDomTree = analyses.get_analysis_result(function);
+    Ok(Box::new(compute_post_dom_fronts(context, post_dom_tree)))
+}
+
+/// Compute the post-dominance frontier of each block from the post-dominator tree.
+///
+/// A block is in the frontier of another if the other post-dominates one of its successors but
+/// not the block itself.
+pub fn compute_post_dom_fronts(context: &Context, post_dom_tree: &PostDomTree) -> PostDomFronts {
+    let mut res = post_dom_tree
+        .nodes
+        .keys()
+        .map(|block| (*block, FxHashSet::default()))
+        .collect::<FxHashMap<_, _>>();
+
+    for (block, node) in &post_dom_tree.nodes {
+        let succs = block.successors(context);
+        if succs.len() < 2 {
+            continue;
+        }
+        // Walk up from each successor to the immediate post-dominator of the branching block,
+        // or to the virtual exit.
+        for BranchToWithArgs { block: succ, .. } in succs {
+            let mut runner = Some(succ);
+            while let Some(runner_block) = runner.filter(|runner| Some(*runner) != node.parent) {
+                res.get_mut(&runner_block).unwrap().insert(*block);
+                runner = post_dom_tree.nodes[&runner_block].parent;
+            }
+        }
+    }
+    PostDomFronts(res)
+}
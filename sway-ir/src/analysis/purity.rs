@@ -0,0 +1,192 @@
+//! Purity analysis.
+//!
+//! Finds what each function of a module may do besides computing its result, including through
+//! the functions it calls: whether it reads or writes storage, reads or writes memory other than
+//! its own locals, has other side effects such as logging, or may not return at all.  A function
+//! which writes nothing, has no other side effects and always returns is pure, and calls to it may
+//! be removed when their result is unused.
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    analysis::alias::points_to_local, compute_post_order, AnalysisResult, AnalysisResultT,
+    AnalysisResults, Context, FuelVmInstruction, Function, Instruction, IrError, MemoryEffects,
+    MemoryLocation, Module, Pass, PassMutability, ScopedPass,
+};
+
+/// What a function, or any function it calls, may do besides computing its result.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FunctionEffects {
+    pub reads_storage: bool,
+    pub writes_storage: bool,
+    /// Whether memory other than the function's own locals may be read, such as that referred to
+    /// by a pointer argument.
+    pub reads_memory: bool,
+    /// Whether memory other than the function's own locals may be written.
+    pub writes_memory: bool,
+    /// Whether there are any other side effects, such as logs, messages, contract calls or ASM
+    /// blocks.
+    pub other_side_effects: bool,
+    /// Whether the function may revert or loop forever rather than return.
+    pub may_not_return: bool,
+}
+
+impl FunctionEffects {
+    /// Whether a call to the function may be removed when its result is unused.
+    pub fn is_pure(&self) -> bool {
+        !(self.writes_storage
+            || self.writes_memory
+            || self.other_side_effects
+            || self.may_not_return)
+    }
+
+    fn merge(&mut self, other: &FunctionEffects) {
+        self.reads_storage |= other.reads_storage;
+        self.writes_storage |= other.writes_storage;
+        self.reads_memory |= other.reads_memory;
+        self.writes_memory |= other.writes_memory;
+        self.other_side_effects |= other.other_side_effects;
+        self.may_not_return |= other.may_not_return;
+    }
+}
+
+/// The effects of each function in a module.
+pub type ModuleEffects = FxHashMap<Function, FunctionEffects>;
+impl AnalysisResultT for ModuleEffects {}
+
+pub const PURITY_NAME: &str = "purity";
+
+pub fn create_purity_pass() -> Pass {
+    Pass {
+        name: PURITY_NAME,
+        descr: "Effects of the functions of a module",
+        deps: vec![],
+        preserves: vec![],
+        prune_unreachable: false,
+        runner: ScopedPass::ModulePass(PassMutability::Analysis(compute_purity_pass)),
+    }
+}
+
+fn compute_purity_pass(
+    context: &Context,
+    _: &AnalysisResults,
+    module: Module,
+) -> Result<AnalysisResult, IrError> {
+    Ok(Box::new(compute_module_effects(context, module)))
+}
+
+/// Compute the effects of every function in `module`.
+///
+/// A function which may call itself, directly or otherwise, is assumed to possibly not return.
+pub fn compute_module_effects(context: &Context, module: Module) -> ModuleEffects {
+    let mut effects = ModuleEffects::default();
+    let mut callees = FxHashMap::<Function, Vec<Function>>::default();
+    for function in module.function_iter(context) {
+        let (own_effects, own_callees) = local_effects(context, function);
+        effects.insert(function, own_effects);
+        callees.insert(function, own_callees);
+    }
+
+    // Add the effects of the callees until nothing changes.  Effects are only ever added, so this
+    // terminates.
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (function, function_callees) in &callees {
+            let mut merged = effects[function];
+            for callee in function_callees {
+                if let Some(callee_effects) = effects.get(callee) {
+                    merged.merge(callee_effects);
+                }
+            }
+            if merged != effects[function] {
+                effects.insert(*function, merged);
+                changed = true;
+            }
+        }
+    }
+
+    for function in callees.keys() {
+        if calls_itself(&callees, *function) {
+            effects.get_mut(function).unwrap().may_not_return = true;
+        }
+    }
+    effects
+}
+
+// The effects of the instructions of `function` itself, and the functions it calls.
+fn local_effects(context: &Context, function: Function) -> (FunctionEffects, Vec<Function>) {
+    let mut effects = FunctionEffects::default();
+    let mut callees = Vec::new();
+    for (_, inst) in function.instruction_iter(context) {
+        match inst.get_instruction(context).unwrap() {
+            Instruction::Call(callee, _) => {
+                if !callees.contains(callee) {
+                    callees.push(*callee);
+                }
+                continue;
+            }
+            Instruction::AsmBlock(..)
+            | Instruction::ContractCall { .. }
+            | Instruction::FuelVm(
+                FuelVmInstruction::GasCheckpoint(_)
+                | FuelVmInstruction::Log { .. }
+                | FuelVmInstruction::Smo { .. },
+            ) => effects.other_side_effects = true,
+            Instruction::FuelVm(FuelVmInstruction::Revert(_)) => effects.may_not_return = true,
+            _ => (),
+        }
+
+        let MemoryEffects { reads, writes } = MemoryEffects::of(context, inst);
+        let (reads_storage, reads_memory) = location_kinds(context, reads.as_deref());
+        let (writes_storage, writes_memory) = location_kinds(context, writes.as_deref());
+        effects.reads_storage |= reads_storage;
+        effects.reads_memory |= reads_memory;
+        effects.writes_storage |= writes_storage;
+        effects.writes_memory |= writes_memory;
+    }
+
+    // A loop may not terminate.
+    let post_order = compute_post_order(context, &function);
+    for (block, po) in &post_order.block_to_po {
+        if block
+            .successors(context)
+            .iter()
+            .any(|succ| post_order.block_to_po[&succ.block] >= *po)
+        {
+            effects.may_not_return = true;
+        }
+    }
+
+    (effects, callees)
+}
+
+// Whether `locations` include storage, and memory other than locals.  `None` means anything.
+fn location_kinds(context: &Context, locations: Option<&[MemoryLocation]>) -> (bool, bool) {
+    match locations {
+        None => (true, true),
+        Some(locations) => (
+            locations
+                .iter()
+                .any(|location| matches!(location, MemoryLocation::Storage(_))),
+            locations.iter().any(|location| {
+                matches!(location, MemoryLocation::Memory(ptr) if !points_to_local(context, *ptr))
+            }),
+        ),
+    }
+}
+
+fn calls_itself(callees: &FxHashMap<Function, Vec<Function>>, function: Function) -> bool {
+    let mut visited = Vec::new();
+    let mut worklist = callees[&function].clone();
+    while let Some(callee) = worklist.pop() {
+        if callee == function {
+            return true;
+        }
+        if !visited.contains(&callee) {
+            visited.push(callee);
+            worklist.extend(callees.get(&callee).into_iter().flatten());
+        }
+    }
+    false
+}
@@ -0,0 +1,251 @@
+//! Storage access analysis.
+//!
+//! Finds the storage slots each function of a module may read and write, including through the
+//! functions it calls.  Slots are identified as precisely as the IR allows: by the storage key
+//! index the frontend attached to the access, by the constant key the access uses, or failing
+//! both as unknown.
+
+use std::collections::BTreeSet;
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    interpret::add_to_key, AnalysisResult, AnalysisResultT, AnalysisResults, ConstantValue,
+    Context, FuelVmInstruction, Function, Instruction, IrError, Module, Pass, PassMutability,
+    ScopedPass, Value,
+};
+
+/// A storage slot, or set of slots, accessed by a function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StorageKey {
+    /// The slots of the storage field with this index, from the access's storage key metadata.
+    Index(u64),
+    /// The slot with this key.
+    Slot([u8; 32]),
+    /// Any slot.
+    Unknown,
+}
+
+/// The storage slots a function, or any function it calls, may read and write.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StorageAccess {
+    pub reads: BTreeSet<StorageKey>,
+    pub writes: BTreeSet<StorageKey>,
+}
+
+impl StorageAccess {
+    /// Whether storage is read but never written.
+    pub fn is_read_only(&self) -> bool {
+        !self.reads.is_empty() && self.writes.is_empty()
+    }
+
+    /// Whether storage is neither read nor written.
+    pub fn is_empty(&self) -> bool {
+        self.reads.is_empty() && self.writes.is_empty()
+    }
+
+    fn merge(&mut self, other: &StorageAccess) {
+        self.reads.extend(other.reads.iter().copied());
+        self.writes.extend(other.writes.iter().copied());
+    }
+}
+
+/// The storage access of each function in a module.
+pub type ModuleStorageAccess = FxHashMap<Function, StorageAccess>;
+impl AnalysisResultT for ModuleStorageAccess {}
+
+pub const STORAGE_ACCESS_NAME: &str = "storage_access";
+
+pub fn create_storage_access_pass() -> Pass {
+    Pass {
+        name: STORAGE_ACCESS_NAME,
+        descr: "Storage slots read and written by the functions of a module",
+        deps: vec![],
+        preserves: vec![],
+        prune_unreachable: false,
+        runner: ScopedPass::ModulePass(PassMutability::Analysis(compute_storage_access_pass)),
+    }
+}
+
+fn compute_storage_access_pass(
+    context: &Context,
+    _: &AnalysisResults,
+    module: Module,
+) -> Result<AnalysisResult, IrError> {
+    Ok(Box::new(compute_module_storage_access(context, module)))
+}
+
+/// Compute the storage slots read and written by every function in `module`.
+pub fn compute_module_storage_access(context: &Context, module: Module) -> ModuleStorageAccess {
+    let mut access = ModuleStorageAccess::default();
+    let mut callees = FxHashMap::<Function, Vec<Function>>::default();
+    for function in module.function_iter(context) {
+        let (own_access, own_callees) = local_access(context, function);
+        access.insert(function, own_access);
+        callees.insert(function, own_callees);
+    }
+
+    // Add the slots accessed by the callees until nothing changes.  Slots are only ever added, so
+    // this terminates.
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (function, function_callees) in &callees {
+            let mut merged = access[function].clone();
+            for callee in function_callees {
+                if let Some(callee_access) = access.get(callee) {
+                    merged.merge(callee_access);
+                }
+            }
+            if merged != access[function] {
+                access.insert(*function, merged);
+                changed = true;
+            }
+        }
+    }
+    access
+}
+
+// The slots accessed by the instructions of `function` itself, and the functions it calls.
+fn local_access(context: &Context, function: Function) -> (StorageAccess, Vec<Function>) {
+    let mut access = StorageAccess::default();
+    let mut callees = Vec::new();
+    for (_, inst) in function.instruction_iter(context) {
+        match inst.get_instruction(context).unwrap() {
+            Instruction::Call(callee, _) if !callees.contains(callee) => callees.push(*callee),
+            Instruction::FuelVm(FuelVmInstruction::StateLoadWord(key)) => {
+                access
+                    .reads
+                    .extend(accessed_keys(context, function, inst, *key, None));
+            }
+            Instruction::FuelVm(FuelVmInstruction::StateLoadQuadWord {
+                key,
+                number_of_slots,
+                ..
+            }) => {
+                access.reads.extend(accessed_keys(
+                    context,
+                    function,
+                    inst,
+                    *key,
+                    Some(*number_of_slots),
+                ));
+            }
+            Instruction::FuelVm(FuelVmInstruction::StateStoreWord { key, .. }) => {
+                access
+                    .writes
+                    .extend(accessed_keys(context, function, inst, *key, None));
+            }
+            Instruction::FuelVm(
+                FuelVmInstruction::StateStoreQuadWord {
+                    key,
+                    number_of_slots,
+                    ..
+                }
+                | FuelVmInstruction::StateClear {
+                    key,
+                    number_of_slots,
+                },
+            ) => {
+                access.writes.extend(accessed_keys(
+                    context,
+                    function,
+                    inst,
+                    *key,
+                    Some(*number_of_slots),
+                ));
+            }
+            Instruction::AsmBlock(asm_block, _) => {
+                for asm_ins in &asm_block.get_content(context).body {
+                    match asm_ins.name.as_str() {
+                        "srw" | "srwq" => {
+                            access.reads.insert(StorageKey::Unknown);
+                        }
+                        "sww" | "swwq" | "scwq" => {
+                            access.writes.insert(StorageKey::Unknown);
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    (access, callees)
+}
+
+// The most slots of a single access which are listed individually.  Larger accesses of constant
+// keys are recorded as unknown.
+const MAX_LISTED_SLOTS: u64 = 64;
+
+// The slots accessed by `inst`, which uses the key pointed to by `key` and `number_of_slots`
+// consecutive slots, or a single slot if `None`.
+fn accessed_keys(
+    context: &Context,
+    function: Function,
+    inst: Value,
+    key: Value,
+    number_of_slots: Option<Value>,
+) -> Vec<StorageKey> {
+    if let Some(index) = inst.get_storage_key(context) {
+        return vec![StorageKey::Index(index)];
+    }
+    let number_of_slots = match number_of_slots {
+        None => Some(1),
+        Some(value) => value.get_constant(context).and_then(|c| match c.value {
+            ConstantValue::Uint(n) => Some(n),
+            _ => None,
+        }),
+    };
+    match (constant_key(context, function, key), number_of_slots) {
+        (Some(key), Some(n)) if n <= MAX_LISTED_SLOTS => (0..n)
+            .map(|offset| StorageKey::Slot(add_to_key(key, offset)))
+            .collect(),
+        _ => vec![StorageKey::Unknown],
+    }
+}
+
+// The key pointed to by `key_ptr`, if it's a local which only ever holds the same constant.
+fn constant_key(context: &Context, function: Function, key_ptr: Value) -> Option<[u8; 32]> {
+    let Some(Instruction::GetLocal(local)) = key_ptr.get_instruction(context) else {
+        return None;
+    };
+    let is_local_ptr = |value: &Value| matches!(value.get_instruction(context), Some(Instruction::GetLocal(other)) if other == local);
+    let as_key = |value: &ConstantValue| match value {
+        ConstantValue::B256(key) => Some(*key),
+        _ => None,
+    };
+
+    let mut key = match local.get_initializer(context) {
+        Some(initializer) => Some(as_key(&initializer.value)?),
+        None => None,
+    };
+    for (_, inst) in function.instruction_iter(context) {
+        match inst.get_instruction(context).unwrap() {
+            Instruction::GetLocal(_) | Instruction::Load(_) => (),
+            Instruction::Store {
+                dst_val,
+                stored_val,
+            } if is_local_ptr(dst_val) => {
+                let stored = as_key(&stored_val.get_constant(context)?.value)?;
+                if key.map_or(false, |key| key != stored) {
+                    return None;
+                }
+                key = Some(stored);
+            }
+            // These only read their memory operands.
+            Instruction::FuelVm(
+                FuelVmInstruction::StateLoadWord(_)
+                | FuelVmInstruction::StateStoreWord { .. }
+                | FuelVmInstruction::StateStoreQuadWord { .. }
+                | FuelVmInstruction::StateClear { .. },
+            ) => (),
+            Instruction::FuelVm(FuelVmInstruction::StateLoadQuadWord { load_val, .. })
+                if !is_local_ptr(load_val) => {}
+            // Any other use of the local may change it.
+            other if other.get_operands().iter().any(is_local_ptr) => return None,
+            _ => (),
+        }
+    }
+    key
+}
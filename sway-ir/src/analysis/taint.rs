@@ -0,0 +1,200 @@
+//! Taint analysis of the data supplied by the callers of a contract.
+//!
+//! The arguments of every contract ABI method are tainted, as is any value computed from a tainted
+//! value, any local a tainted value is stored to and the arguments of a function or block which
+//! are passed a tainted value.  The result of a call is tainted if the callee may return a tainted
+//! value.  The analysis is flow insensitive and doesn't distinguish between the call sites of a
+//! function, so it may over-approximate.
+//!
+//! Values computed by `asm` blocks are treated as clean.  Hashing is done in `asm` blocks, so a
+//! storage key hashed from caller data, as `StorageMap` does, isn't tainted while a key taken from
+//! the caller as is remains so.  A storage write at a tainted key lets the caller overwrite any
+//! slot, including those of other storage fields.
+
+use rustc_hash::FxHashSet;
+
+use crate::{
+    AnalysisResult, AnalysisResultT, AnalysisResults, BranchToWithArgs, Context, FuelVmInstruction,
+    Function, Instruction, IrError, Kind, LocalVar, Module, Pass, PassMutability, ScopedPass,
+    Value,
+};
+
+/// The values and locals of a module which may hold data supplied by the caller of a contract
+/// method.
+#[derive(Debug, Default)]
+pub struct ModuleTaint {
+    values: FxHashSet<Value>,
+    locals: FxHashSet<LocalVar>,
+    returns: FxHashSet<Function>,
+    /// The storage writes and clears whose key is tainted, in the order they appear in the module.
+    pub tainted_storage_writes: Vec<Value>,
+}
+impl AnalysisResultT for ModuleTaint {}
+
+impl ModuleTaint {
+    /// Whether `value` may hold data supplied by the caller, or point to memory which does.
+    pub fn is_tainted(&self, context: &Context, value: &Value) -> bool {
+        self.values.contains(value)
+            || base_local(context, value).map_or(false, |local| self.locals.contains(&local))
+    }
+
+    fn taint(&mut self, value: Value) -> bool {
+        self.values.insert(value)
+    }
+}
+
+pub const TAINT_NAME: &str = "taint";
+
+pub fn create_taint_pass() -> Pass {
+    Pass {
+        name: TAINT_NAME,
+        descr: "Values of a contract which may hold data supplied by its callers",
+        deps: vec![],
+        preserves: vec![],
+        prune_unreachable: false,
+        runner: ScopedPass::ModulePass(PassMutability::Analysis(compute_taint_pass)),
+    }
+}
+
+fn compute_taint_pass(
+    context: &Context,
+    _: &AnalysisResults,
+    module: Module,
+) -> Result<AnalysisResult, IrError> {
+    Ok(Box::new(compute_module_taint(context, module)))
+}
+
+/// Compute the values of `module` which may hold data supplied by the callers of its contract
+/// methods.  Nothing is tainted unless `module` is a contract.
+pub fn compute_module_taint(context: &Context, module: Module) -> ModuleTaint {
+    let mut taint = ModuleTaint::default();
+    if module.get_kind(context) != Kind::Contract {
+        return taint;
+    }
+    for function in module.function_iter(context) {
+        if function.has_selector(context) {
+            for (_, arg) in function.args_iter(context) {
+                taint.taint(*arg);
+            }
+        }
+    }
+
+    // Taint is only ever added, so this terminates.
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for function in module.function_iter(context) {
+            for (_, inst) in function.instruction_iter(context) {
+                changed |= propagate(context, &mut taint, function, inst);
+            }
+        }
+    }
+
+    taint.tainted_storage_writes = module
+        .function_iter(context)
+        .flat_map(|function| function.instruction_iter(context))
+        .filter_map(|(_, inst)| match inst.get_instruction(context) {
+            Some(Instruction::FuelVm(
+                FuelVmInstruction::StateStoreWord { key, .. }
+                | FuelVmInstruction::StateStoreQuadWord { key, .. }
+                | FuelVmInstruction::StateClear { key, .. },
+            )) if taint.is_tainted(context, key) => Some(inst),
+            _ => None,
+        })
+        .collect();
+    taint
+}
+
+// Taint whatever `inst` passes tainted data to, returning whether anything new was tainted.
+fn propagate(context: &Context, taint: &mut ModuleTaint, function: Function, inst: Value) -> bool {
+    let is_tainted = |taint: &ModuleTaint, value: &Value| taint.is_tainted(context, value);
+    match inst.get_instruction(context).unwrap() {
+        Instruction::AddrOf(value)
+        | Instruction::BitCast(value, _)
+        | Instruction::CastPtr(value, _, _)
+        | Instruction::IntToPtr(value, _)
+        | Instruction::Load(value)
+        | Instruction::ExtractElement { array: value, .. }
+        | Instruction::ExtractValue {
+            aggregate: value, ..
+        } => is_tainted(taint, value) && taint.taint(inst),
+        Instruction::BinaryOp { arg1, arg2, .. } => {
+            (is_tainted(taint, arg1) || is_tainted(taint, arg2)) && taint.taint(inst)
+        }
+        Instruction::InsertElement {
+            array: aggregate,
+            value,
+            ..
+        }
+        | Instruction::InsertValue {
+            aggregate, value, ..
+        } => (is_tainted(taint, aggregate) || is_tainted(taint, value)) && taint.taint(inst),
+        Instruction::Store {
+            dst_val: dst,
+            stored_val: src,
+        }
+        | Instruction::MemCopy {
+            dst_val: dst,
+            src_val: src,
+            ..
+        } => {
+            if !is_tainted(taint, src) {
+                return false;
+            }
+            let local_changed =
+                base_local(context, dst).map_or(false, |local| taint.locals.insert(local));
+            taint.taint(*dst) | local_changed
+        }
+        Instruction::Call(callee, args) => {
+            let mut changed = false;
+            for (arg, (_, param)) in args.iter().zip(callee.args_iter(context)) {
+                if is_tainted(taint, arg) {
+                    changed |= taint.taint(*param);
+                }
+            }
+            if taint.returns.contains(callee) {
+                changed |= taint.taint(inst);
+            }
+            changed
+        }
+        Instruction::Branch(to) => taint_block_args(context, taint, to),
+        Instruction::ConditionalBranch {
+            true_block,
+            false_block,
+            ..
+        } => {
+            taint_block_args(context, taint, true_block)
+                | taint_block_args(context, taint, false_block)
+        }
+        Instruction::Ret(value, _) => is_tainted(taint, value) && taint.returns.insert(function),
+        Instruction::AsmBlock(..)
+        | Instruction::Cmp(..)
+        | Instruction::ContractCall { .. }
+        | Instruction::FuelVm(_)
+        | Instruction::GetLocal(_)
+        | Instruction::Nop => false,
+    }
+}
+
+fn taint_block_args(context: &Context, taint: &mut ModuleTaint, to: &BranchToWithArgs) -> bool {
+    let mut changed = false;
+    for (idx, arg) in to.args.iter().enumerate() {
+        if taint.is_tainted(context, arg) {
+            if let Some(param) = to.block.get_arg(context, idx) {
+                changed |= taint.taint(param);
+            }
+        }
+    }
+    changed
+}
+
+// The local which `value` points into, if it's known.
+fn base_local(context: &Context, value: &Value) -> Option<LocalVar> {
+    match value.get_instruction(context)? {
+        Instruction::GetLocal(local) => Some(*local),
+        Instruction::AddrOf(value)
+        | Instruction::BitCast(value, _)
+        | Instruction::CastPtr(value, _, _) => base_local(context, value),
+        _ => None,
+    }
+}
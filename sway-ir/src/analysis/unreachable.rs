@@ -0,0 +1,77 @@
+/// Find the blocks of a function which can't be reached from its entry block.
+///
+/// Transforms which fold or redirect branches may leave such orphans behind.  They're never run
+/// and only clutter the printed IR, so passes may ask the pass manager to prune them afterwards
+/// by setting [`Pass::prune_unreachable`].
+use crate::{
+    block::Block, AnalysisResult, AnalysisResultT, AnalysisResults, BranchToWithArgs, Context,
+    Function, IrError, Pass, PassMutability, ScopedPass,
+};
+
+use rustc_hash::FxHashSet;
+
+/// The unreachable blocks of a function, in block order.
+pub struct UnreachableBlocks(pub Vec<Block>);
+impl AnalysisResultT for UnreachableBlocks {}
+
+pub const UNREACHABLE_BLOCKS_NAME: &str = "unreachable_blocks";
+
+pub fn create_unreachable_blocks_pass() -> Pass {
+    Pass {
+        name: UNREACHABLE_BLOCKS_NAME,
+        descr: "Blocks unreachable from the function entry",
+        deps: vec![],
+        preserves: vec![],
+        prune_unreachable: false,
+        runner: ScopedPass::FunctionPass(PassMutability::Analysis(compute_unreachable_blocks_pass)),
+    }
+}
+
+fn compute_unreachable_blocks_pass(
+    context: &Context,
+    _: &AnalysisResults,
+    function: Function,
+) -> Result<AnalysisResult, IrError> {
+    Ok(Box::new(UnreachableBlocks(find_unreachable_blocks(
+        context, function,
+    ))))
+}
+
+fn reachable_blocks(context: &Context, function: Function) -> FxHashSet<Block> {
+    let entry_block = function.get_entry_block(context);
+    let mut reachable = FxHashSet::from_iter([entry_block]);
+    let mut worklist = vec![entry_block];
+    while let Some(block) = worklist.pop() {
+        for BranchToWithArgs { block: succ, .. } in block.successors(context) {
+            if reachable.insert(succ) {
+                worklist.push(succ);
+            }
+        }
+    }
+    reachable
+}
+
+/// Return the blocks of `function` which can't be reached from its entry block, in block order.
+pub fn find_unreachable_blocks(context: &Context, function: Function) -> Vec<Block> {
+    let reachable = reachable_blocks(context, function);
+    function
+        .block_iter(context)
+        .filter(|block| !reachable.contains(block))
+        .collect()
+}
+
+/// Remove the blocks of `function` which can't be reached from its entry block, returning whether
+/// there were any.
+pub fn prune_unreachable_blocks(
+    context: &mut Context,
+    function: Function,
+) -> Result<bool, IrError> {
+    let unreachable = find_unreachable_blocks(context, function);
+    for block in &unreachable {
+        for BranchToWithArgs { block: succ, .. } in block.successors(context) {
+            succ.remove_pred(context, block);
+        }
+        function.remove_block(context, block)?;
+    }
+    Ok(!unreachable.is_empty())
+}
@@ -5,8 +5,8 @@ use std::{
 
 use anyhow::anyhow;
 use sway_ir::{
-    insert_after_each, register_known_passes, PassGroup, PassManager, MODULEPRINTER_NAME,
-    MODULEVERIFIER_NAME,
+    insert_after_each, register_known_passes, shuffle_pass_group, Context, Kind, PassManager,
+    PrintPassOptions, PrinterOptions, BINARY_MAGIC, MODULEPRINTER_NAME,
 };
 
 // -------------------------------------------------------------------------------------------------
@@ -20,31 +20,80 @@ fn main() -> Result<(), anyhow::Error> {
     let config = ConfigBuilder::build(&pass_mgr, std::env::args())?;
 
     // Read the input file, or standard in.
-    let input_str = read_from_input(&config.input_path)?;
+    let input = read_from_input(&config.input_path)?;
 
-    // Parse it. XXX Improve this error message too.
-    let mut ir = sway_ir::parser::parse(&input_str)?;
+    // Decode it if it's in the binary format, otherwise parse it. XXX Improve this error message
+    // too.
+    let mut ir = if input.starts_with(&BINARY_MAGIC) {
+        Context::from_bytes(&input)?
+    } else {
+        sway_ir::parser::parse(&String::from_utf8_lossy(&input))?
+    };
 
-    // Perform optimisation passes in order.
-    let mut passes = PassGroup::default();
-    for pass in config.passes {
-        passes.append_pass(pass);
+    ir.set_pass_fuel(config.fuel);
+    if config.inline_max_weight.is_some() {
+        ir.set_inline_policy(sway_ir::InlinePolicy {
+            max_weight: config.inline_max_weight,
+            ..Default::default()
+        });
+    }
+
+    // Load the rules for the `rewrite` pass.
+    if let Some(rules_path) = &config.rules_path {
+        ir.set_rewrite_rules(sway_ir::parse_rules(&std::fs::read_to_string(rules_path)?)?);
+    }
+
+    // Keep a normalised copy of the input around if we're to show what changed.
+    let print = |ir: &sway_ir::Context| {
+        sway_ir::printer::to_string_with_options(ir, &config.printer_options)
+    };
+    let orig_ir_str = config.diff.then(|| print(&ir));
+
+    // Perform optimisation passes in order.  The optimisation levels differ for predicates.
+    let is_predicate = ir
+        .module_iter()
+        .any(|module| module.get_kind(&ir) == Kind::Predicate);
+    let mut passes = pass_mgr
+        .parse_pipeline(&config.pipeline.join(","), is_predicate)
+        .map_err(|err| anyhow!("{err}\n\n{}", pass_mgr.help_text()))?;
+    if let Some(seed) = config.shuffle_seed {
+        passes = shuffle_pass_group(&passes, seed);
+        eprintln!("Shuffled passes: {passes}");
     }
     if config.print_after_each {
         passes = insert_after_each(passes, MODULEPRINTER_NAME);
     }
-    if config.verify_after_each {
-        passes = insert_after_each(passes, MODULEVERIFIER_NAME);
-    }
+    pass_mgr.set_verify_after_each(config.verify_after_each);
+    pass_mgr.set_print_options(PrintPassOptions {
+        printer_options: config.printer_options.clone(),
+        ..config.print_passes
+    });
     pass_mgr.run(&mut ir, &passes)?;
+    for exhausted in pass_mgr.fuel_exhausted() {
+        eprintln!(
+            "Pass '{}' ran out of fuel on function '{}'.",
+            exhausted.pass, exhausted.function
+        );
+    }
+    if config.stats {
+        eprint!("{}", pass_mgr.statistics_report());
+    }
+
+    // Make sure the passes have left us with valid IR.
+    let ir = ir.verify()?;
 
     // Write the output file or standard out.
-    write_to_output(ir, &config.output_path)?;
+    match orig_ir_str {
+        Some(orig_ir_str) => {
+            write_to_output(diff_lines(&orig_ir_str, &print(&ir)), &config.output_path)?
+        }
+        None => write_to_output(print(&ir), &config.output_path)?,
+    }
 
     Ok(())
 }
 
-fn read_from_input(path_str: &Option<String>) -> std::io::Result<String> {
+fn read_from_input(path_str: &Option<String>) -> std::io::Result<Vec<u8>> {
     let mut input = Vec::new();
     match path_str {
         None => {
@@ -55,7 +104,7 @@ fn read_from_input(path_str: &Option<String>) -> std::io::Result<String> {
             BufReader::new(file).read_to_end(&mut input)?;
         }
     }
-    Ok(String::from_utf8_lossy(&input).to_string())
+    Ok(input)
 }
 
 fn write_to_output<S: Into<String>>(ir_str: S, path_str: &Option<String>) -> std::io::Result<()> {
@@ -73,6 +122,43 @@ fn write_to_output<S: Into<String>>(ir_str: S, path_str: &Option<String>) -> std
     }
 }
 
+// A simple line based diff, with lines prefixed by `-` if they're only in `before`, `+` if they're
+// only in `after` or a space if they're in both.  Finds the longest common subsequence, which is
+// quadratic but fine for the size of IR we're typically looking at.
+fn diff_lines(before: &str, after: &str) -> String {
+    let before = before.lines().collect::<Vec<_>>();
+    let after = after.lines().collect::<Vec<_>>();
+
+    // lcs[i][j] is the length of the LCS of before[i..] and after[j..].
+    let mut lcs = vec![vec![0; after.len() + 1]; before.len() + 1];
+    for i in (0..before.len()).rev() {
+        for j in (0..after.len()).rev() {
+            lcs[i][j] = if before[i] == after[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut output = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < before.len() || j < after.len() {
+        if i < before.len() && j < after.len() && before[i] == after[j] {
+            output.push(format!(" {}", before[i]));
+            i += 1;
+            j += 1;
+        } else if i < before.len() && (j == after.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            output.push(format!("-{}", before[i]));
+            i += 1;
+        } else {
+            output.push(format!("+{}", after[j]));
+            j += 1;
+        }
+    }
+    output.join("\n")
+}
+
 // -------------------------------------------------------------------------------------------------
 // Using a bespoke CLI parser since the order in which passes are specified is important.
 
@@ -80,13 +166,20 @@ fn write_to_output<S: Into<String>>(ir_str: S, path_str: &Option<String>) -> std
 struct Config {
     input_path: Option<String>,
     output_path: Option<String>,
+    rules_path: Option<String>,
+    shuffle_seed: Option<u64>,
+    fuel: Option<u64>,
+    inline_max_weight: Option<u64>,
 
     verify_after_each: bool,
     print_after_each: bool,
-    _time_passes: bool,
-    _stats: bool,
+    diff: bool,
+    printer_options: PrinterOptions,
+    print_passes: PrintPassOptions,
+    stats: bool,
 
-    passes: Vec<&'static str>,
+    // Pass names and pipeline specs, in the order they were given.
+    pipeline: Vec<String>,
 }
 
 // This is a little clumsy in that it needs to consume items from the iterator carefully in each
@@ -127,12 +220,76 @@ impl<'a, I: Iterator<Item = String>> ConfigBuilder<'a, I> {
                         self.cfg.print_after_each = true;
                         self.build_root()
                     }
+                    "-print-before-all" => {
+                        self.cfg.print_passes.before_all = true;
+                        self.build_root()
+                    }
+                    "-print-after-all" => {
+                        self.cfg.print_passes.after_all = true;
+                        self.build_root()
+                    }
+                    "-print-before" => self.build_print_before(),
+                    "-print-after" => self.build_print_after(),
+                    "-diff" => {
+                        self.cfg.diff = true;
+                        self.build_root()
+                    }
+                    "-stats" => {
+                        self.cfg.stats = true;
+                        self.build_root()
+                    }
+                    "-no-metadata" => {
+                        self.cfg.printer_options.metadata = false;
+                        self.build_root()
+                    }
+                    "-no-spans" => {
+                        self.cfg.printer_options.spans = false;
+                        self.build_root()
+                    }
+                    "-stable-order" => {
+                        self.cfg.printer_options.stable_order = true;
+                        self.build_root()
+                    }
+                    "-annotate-types" => {
+                        self.cfg.printer_options.annotate_types = true;
+                        self.build_root()
+                    }
+                    "-function" => self.build_function(),
+                    "-rules" => self.build_rules(),
+                    "-shuffle" => self.build_shuffle(),
+                    "-fuel" => self.build_fuel(),
+                    "-inline-max-weight" => self.build_inline_max_weight(),
+                    "-pipeline" => self.build_pipeline(),
                     "-h" => {
                         print!(
                             "Usage: opt [passname...] -i input_file -o output_file\n\n{}",
                             self.pass_mgr.help_text()
                         );
                         print!("\n\nIn the absense of -i or -o options, input is taken from stdin and output is printed to stdout.\n");
+                        println!("The input may be either textual IR or binary IR written by `Context::to_bytes`.");
+                        println!("\nOther options are:\n");
+                        println!("  -verify-after-each - run the verifier after every pass which changes the IR");
+                        println!("  -print-after-each  - print the IR after every pass");
+                        println!("  -print-before <pass> - print the IR to stderr before each run of a pass");
+                        println!("  -print-after <pass>  - print the IR to stderr after each run of a pass");
+                        println!("  -print-before-all  - print the IR to stderr before every transform pass");
+                        println!("  -print-after-all   - print the IR to stderr after every transform pass");
+                        println!("  -diff              - output a diff between the input and the optimised IR");
+                        println!("  -stats             - report the time taken and changes made by each pass");
+                        println!("  -no-metadata       - don't print any metadata");
+                        println!("  -no-spans          - don't print span metadata");
+                        println!("  -function <name>   - only print the named function(s)");
+                        println!("  -stable-order      - print the functions of each module sorted by name");
+                        println!("  -annotate-types    - print the type of each named instruction in a comment");
+                        println!("  -rules <file>      - load the rules for the rewrite pass from a file");
+                        println!("  -shuffle <seed>    - run the passes in a random order, repeating some");
+                        println!("  -fuel <n>          - limit the changes each pass makes to a function");
+                        println!(
+                            "  -inline-max-weight <n> - don't inline functions heavier than this"
+                        );
+                        println!("  -pipeline <spec>   - run a comma separated list of passes, where any of");
+                        println!("                       -O0, -O1, -O2, -Os or -Oz stands for its passes");
+                        println!("\nThe resulting IR is always verified before being output.");
                         exit(0);
                     }
 
@@ -170,9 +327,109 @@ impl<'a, I: Iterator<Item = String>> ConfigBuilder<'a, I> {
         }
     }
 
+    fn build_function(mut self) -> Result<Config, anyhow::Error> {
+        match self.next {
+            None => Err(anyhow!("-function option requires an argument.")),
+            Some(name) => {
+                self.cfg
+                    .printer_options
+                    .functions
+                    .get_or_insert_with(Vec::new)
+                    .push(name);
+                self.next = self.rest.next();
+                self.build_root()
+            }
+        }
+    }
+
+    fn build_rules(mut self) -> Result<Config, anyhow::Error> {
+        match self.next {
+            None => Err(anyhow!("-rules option requires an argument.")),
+            Some(path) => {
+                self.cfg.rules_path = Some(path);
+                self.next = self.rest.next();
+                self.build_root()
+            }
+        }
+    }
+
+    fn build_shuffle(mut self) -> Result<Config, anyhow::Error> {
+        match self.next {
+            None => Err(anyhow!("-shuffle option requires an argument.")),
+            Some(seed) => {
+                self.cfg.shuffle_seed = Some(seed.parse()?);
+                self.next = self.rest.next();
+                self.build_root()
+            }
+        }
+    }
+
+    fn build_fuel(mut self) -> Result<Config, anyhow::Error> {
+        match self.next {
+            None => Err(anyhow!("-fuel option requires an argument.")),
+            Some(fuel) => {
+                self.cfg.fuel = Some(fuel.parse()?);
+                self.next = self.rest.next();
+                self.build_root()
+            }
+        }
+    }
+
+    fn build_inline_max_weight(mut self) -> Result<Config, anyhow::Error> {
+        match self.next {
+            None => Err(anyhow!("-inline-max-weight option requires an argument.")),
+            Some(weight) => {
+                self.cfg.inline_max_weight = Some(weight.parse()?);
+                self.next = self.rest.next();
+                self.build_root()
+            }
+        }
+    }
+
+    fn build_print_before(mut self) -> Result<Config, anyhow::Error> {
+        match self.next {
+            None => Err(anyhow!("-print-before option requires an argument.")),
+            Some(pass) if self.pass_mgr.lookup_registered_pass(&pass).is_none() => Err(anyhow!(
+                "Unrecognised pass name '{pass}'.\n\n{}",
+                self.pass_mgr.help_text()
+            )),
+            Some(pass) => {
+                self.cfg.print_passes.before.push(pass);
+                self.next = self.rest.next();
+                self.build_root()
+            }
+        }
+    }
+
+    fn build_print_after(mut self) -> Result<Config, anyhow::Error> {
+        match self.next {
+            None => Err(anyhow!("-print-after option requires an argument.")),
+            Some(pass) if self.pass_mgr.lookup_registered_pass(&pass).is_none() => Err(anyhow!(
+                "Unrecognised pass name '{pass}'.\n\n{}",
+                self.pass_mgr.help_text()
+            )),
+            Some(pass) => {
+                self.cfg.print_passes.after.push(pass);
+                self.next = self.rest.next();
+                self.build_root()
+            }
+        }
+    }
+
+    fn build_pipeline(mut self) -> Result<Config, anyhow::Error> {
+        match self.next {
+            None => Err(anyhow!("-pipeline option requires an argument.")),
+            Some(spec) => {
+                self.cfg.pipeline.push(spec);
+                self.next = self.rest.next();
+                self.build_root()
+            }
+        }
+    }
+
     fn build_pass(mut self, name: &str) -> Result<Config, anyhow::Error> {
         if let Some(pass) = self.pass_mgr.lookup_registered_pass(name) {
-            self.cfg.passes.push(pass.name);
+            self.cfg.pipeline.push(pass.name.to_string());
             self.build_root()
         } else {
             Err(anyhow!(
@@ -0,0 +1,82 @@
+use std::{
+    io::{Read, Write},
+    process::exit,
+};
+
+use anyhow::anyhow;
+use sway_ir::{superoptimize, SuperoptConfig};
+
+// -------------------------------------------------------------------------------------------------
+
+fn main() -> Result<(), anyhow::Error> {
+    let (config, input_path, output_path) = parse_args(std::env::args().skip(1))?;
+
+    // Read the input file, or standard in.
+    let input_str = match &input_path {
+        Some(path) => std::fs::read_to_string(path)?,
+        None => {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input)?;
+            input
+        }
+    };
+    let ir = sway_ir::parser::parse(&input_str)?;
+
+    // Search every function, writing the candidates out as rules.
+    let mut output = String::new();
+    for module in ir.module_iter() {
+        for function in module.function_iter(&ir) {
+            for candidate in superoptimize(&ir, function, &config) {
+                output.push_str(&format!(
+                    "; cost {} => {}\n{}\n",
+                    candidate.original_cost,
+                    candidate.cost,
+                    candidate.rule.to_sexp()
+                ));
+            }
+        }
+    }
+
+    match &output_path {
+        Some(path) => std::fs::write(path, output)?,
+        None => std::io::stdout().write_all(output.as_bytes())?,
+    }
+    Ok(())
+}
+
+fn parse_args(
+    mut args: impl Iterator<Item = String>,
+) -> Result<(SuperoptConfig, Option<String>, Option<String>), anyhow::Error> {
+    let mut config = SuperoptConfig::default();
+    let mut input_path = None;
+    let mut output_path = None;
+    while let Some(opt) = args.next() {
+        let mut arg = || {
+            args.next()
+                .ok_or_else(|| anyhow!("{opt} option requires an argument."))
+        };
+        match opt.as_str() {
+            "-i" => input_path = Some(arg()?),
+            "-o" => output_path = Some(arg()?),
+            "-max-cost" => config.max_cost = arg()?.parse()?,
+            "-max-operands" => config.max_operands = arg()?.parse()?,
+            "-samples" => config.samples = arg()?.parse()?,
+            "-verify-samples" => config.verify_samples = arg()?.parse()?,
+            "-seed" => config.seed = arg()?.parse()?,
+            "-h" => {
+                println!("Usage: superopt -i input_file -o output_file\n");
+                println!("Searches for cheaper equivalents of the arithmetic in the input IR, writing any found as rewrite rules.");
+                println!("In the absense of -i or -o options, input is taken from stdin and output is printed to stdout.");
+                println!("\nOther options are:\n");
+                println!("  -max-cost <n>       - the greatest cost of the expressions searched");
+                println!("  -max-operands <n>   - skip values with more operands than this");
+                println!("  -samples <n>        - the number of inputs to compare expressions on");
+                println!("  -verify-samples <n> - the number of inputs to check candidates on");
+                println!("  -seed <n>           - the seed for the random inputs");
+                exit(0);
+            }
+            _ => return Err(anyhow!("Unrecognised option '{opt}'.")),
+        }
+    }
+    Ok((config, input_path, output_path))
+}
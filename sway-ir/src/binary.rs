@@ -0,0 +1,1641 @@
+//! A compact binary encoding of a [`Context`].
+//!
+//! The textual IR is meant for people.  Tools which just want to save the IR and load it back
+//! later, e.g., to cache it between builds, can use [`Context::to_bytes`] and
+//! [`Context::from_bytes`] instead, which are smaller and much quicker than printing and parsing.
+//!
+//! The encoding starts with the magic bytes `SWIR` and a little-endian `u32` format version,
+//! [`BINARY_VERSION`], which must be bumped whenever the layout below changes.  Decoding a stream
+//! with a different version is an error rather than garbage.
+//!
+//! The header is followed by a table of the types and a table of the metadata, each entry after
+//! any it refers to, and then the modules.  Everything else refers to types, metadata, functions,
+//! blocks, locals and values by their index.  Integers are encoded as unsigned LEB128.
+//!
+//! Each function has a header with its signature and locals, and the headers of all functions in
+//! a module come before any of their bodies so that calls may refer to any function.  Likewise a
+//! body lists all its blocks, with their arguments and the number of instructions in each, before
+//! the instructions themselves, so that operands and branches may refer to any value or block.
+//! Values are numbered in order: the arguments of each block followed by its instructions.
+
+use std::mem::discriminant;
+
+use rustc_hash::FxHashMap;
+use sway_types::{ident::Ident, span::Span};
+
+use crate::{
+    asm::{AsmArg, AsmBlock, AsmInstruction},
+    block::{Block, BlockArgument},
+    constant::{Constant, ConstantValue},
+    context::Context,
+    error::IrError,
+    function::{Function, FunctionAttribute},
+    instruction::{
+        BinaryOpKind, BranchToWithArgs, FuelVmInstruction, Instruction, Predicate, Register,
+    },
+    irtype::{Type, TypeContent},
+    local_var::LocalVar,
+    metadata::{MetadataIndex, Metadatum},
+    module::{Kind, Module},
+    value::{Value, ValueDatum},
+};
+
+/// The magic bytes at the start of every encoded context.
+pub const BINARY_MAGIC: [u8; 4] = *b"SWIR";
+
+/// The magic bytes at the start of every function encoded on its own.
+pub const BINARY_FUNCTION_MAGIC: [u8; 4] = *b"SWIF";
+
+/// The version of the encoding written by [`Context::to_bytes`] and read by
+/// [`Context::from_bytes`].
+pub const BINARY_VERSION: u32 = 1;
+
+impl Context {
+    /// Encode every module in this context.
+    ///
+    /// Fails if an instruction refers to a value or function which isn't in its own function or
+    /// module, which the verifier would reject anyway.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, IrError> {
+        Encoder::new(self).encode(self.module_iter())
+    }
+
+    /// Decode a new context from bytes written by [`Context::to_bytes`] or [`Module::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Context, IrError> {
+        Decoder::new(bytes, BINARY_MAGIC)?.decode()
+    }
+}
+
+impl Module {
+    /// Encode just this module, to be decoded into a context of its own.
+    pub fn to_bytes(&self, context: &Context) -> Result<Vec<u8>, IrError> {
+        Encoder::new(context).encode(std::iter::once(*self))
+    }
+}
+
+impl Function {
+    /// Encode just this function, to be decoded over a function of its module with
+    /// [`decode_function_into`].
+    ///
+    /// Callees and configurables are referred to by name rather than by their handles, so the
+    /// function may be decoded into a later build of its module as long as they're still there.
+    pub fn to_bytes(&self, context: &Context) -> Result<Vec<u8>, IrError> {
+        Encoder::new(context).encode_function(*self)
+    }
+}
+
+/// Replace the contents of `function` with those encoded by [`Function::to_bytes`], in the same
+/// way as [`crate::parse_function_into`] does for the textual IR.
+///
+/// `function` is left alone if the bytes can't be decoded.
+pub fn decode_function_into(
+    context: &mut Context,
+    function: Function,
+    bytes: &[u8],
+) -> Result<(), IrError> {
+    let mut decoder = Decoder::new(bytes, BINARY_FUNCTION_MAGIC)?;
+    std::mem::swap(&mut decoder.context, context);
+    let result = decoder.decode_function_into(function);
+    std::mem::swap(&mut decoder.context, context);
+    result
+}
+
+// The fieldless enums are encoded as their index in these tables, which must only ever be
+// appended to without bumping the version.
+
+const KINDS: [Kind; 4] = [Kind::Contract, Kind::Library, Kind::Predicate, Kind::Script];
+
+const FUNCTION_ATTRIBUTES: [FunctionAttribute; 4] = [
+    FunctionAttribute::InlineAlways,
+    FunctionAttribute::InlineNever,
+    FunctionAttribute::Pure,
+    FunctionAttribute::Cold,
+];
+
+const BINARY_OPS: [BinaryOpKind; 7] = [
+    BinaryOpKind::Add,
+    BinaryOpKind::Sub,
+    BinaryOpKind::Mul,
+    BinaryOpKind::Div,
+    BinaryOpKind::And,
+    BinaryOpKind::Or,
+    BinaryOpKind::Xor,
+];
+
+const PREDICATES: [Predicate; 3] = [
+    Predicate::Equal,
+    Predicate::LessThan,
+    Predicate::GreaterThan,
+];
+
+const REGISTERS: [Register; 14] = [
+    Register::Of,
+    Register::Pc,
+    Register::Ssp,
+    Register::Sp,
+    Register::Fp,
+    Register::Hp,
+    Register::Error,
+    Register::Ggas,
+    Register::Cgas,
+    Register::Bal,
+    Register::Is,
+    Register::Ret,
+    Register::Retl,
+    Register::Flag,
+];
+
+fn table_tag<T>(table: &[T], item: &T) -> u8 {
+    table
+        .iter()
+        .position(|entry| discriminant(entry) == discriminant(item))
+        .expect("Every variant must be in its encoding table.") as u8
+}
+
+fn write_uint(bytes: &mut Vec<u8>, mut n: u64) {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+fn write_len(bytes: &mut Vec<u8>, len: usize) {
+    write_uint(bytes, len as u64)
+}
+
+fn write_bool(bytes: &mut Vec<u8>, b: bool) {
+    bytes.push(b as u8)
+}
+
+fn write_str(bytes: &mut Vec<u8>, s: &str) {
+    write_len(bytes, s.len());
+    bytes.extend_from_slice(s.as_bytes());
+}
+
+// -------------------------------------------------------------------------------------------------
+
+struct Encoder<'a> {
+    context: &'a Context,
+
+    // The types and metadata tables, filled in as they're first referred to.
+    types: FxHashMap<Type, u64>,
+    type_bytes: Vec<u8>,
+    metadata: FxHashMap<MetadataIndex, u64>,
+    metadata_bytes: Vec<u8>,
+
+    // Indices within the current module.
+    functions: FxHashMap<Function, u64>,
+    configurables: FxHashMap<Value, u64>,
+
+    // Indices within the current function.
+    blocks: FxHashMap<Block, u64>,
+    locals: FxHashMap<LocalVar, u64>,
+    values: FxHashMap<Value, u64>,
+}
+
+impl<'a> Encoder<'a> {
+    fn new(context: &'a Context) -> Self {
+        Encoder {
+            context,
+            types: FxHashMap::default(),
+            type_bytes: Vec::new(),
+            metadata: FxHashMap::default(),
+            metadata_bytes: Vec::new(),
+            functions: FxHashMap::default(),
+            configurables: FxHashMap::default(),
+            blocks: FxHashMap::default(),
+            locals: FxHashMap::default(),
+            values: FxHashMap::default(),
+        }
+    }
+
+    fn encode(mut self, modules: impl Iterator<Item = Module>) -> Result<Vec<u8>, IrError> {
+        // The tables must come first but are only known once the modules are encoded.
+        let modules = modules.collect::<Vec<_>>();
+        let mut body = Vec::new();
+        write_len(&mut body, modules.len());
+        for module in modules {
+            self.encode_module(&mut body, module)?;
+        }
+
+        Ok(self.finish(BINARY_MAGIC, body))
+    }
+
+    // Like a module, but with the paths of the configurables and the names of the functions in
+    // place of their definitions.
+    fn encode_function(mut self, function: Function) -> Result<Vec<u8>, IrError> {
+        let context = self.context;
+        let module = context
+            .module_iter()
+            .find(|module| context.modules[module.0].functions.contains(&function))
+            .expect("Function must belong to a module.");
+        let mut body = Vec::new();
+
+        let configurables = module.global_configurable_iter(context).collect::<Vec<_>>();
+        write_len(&mut body, configurables.len());
+        for (idx, (call_path, value)) in configurables.into_iter().enumerate() {
+            self.configurables.insert(*value, idx as u64);
+            write_len(&mut body, call_path.len());
+            for segment in call_path {
+                write_str(&mut body, segment);
+            }
+        }
+
+        let functions = module.function_iter(context).collect::<Vec<_>>();
+        write_len(&mut body, functions.len());
+        for (idx, callee) in functions.into_iter().enumerate() {
+            self.functions.insert(callee, idx as u64);
+            write_str(&mut body, callee.get_name(context));
+        }
+
+        self.encode_function_header(&mut body, function);
+        self.encode_function_body(&mut body, function)?;
+        Ok(self.finish(BINARY_FUNCTION_MAGIC, body))
+    }
+
+    fn finish(mut self, magic: [u8; 4], mut body: Vec<u8>) -> Vec<u8> {
+        let mut bytes = magic.to_vec();
+        bytes.extend_from_slice(&BINARY_VERSION.to_le_bytes());
+        write_len(&mut bytes, self.types.len());
+        bytes.append(&mut self.type_bytes);
+        write_len(&mut bytes, self.metadata.len());
+        bytes.append(&mut self.metadata_bytes);
+        bytes.append(&mut body);
+        bytes
+    }
+
+    fn type_ref(&mut self, ty: Type) -> u64 {
+        if let Some(idx) = self.types.get(&ty) {
+            return *idx;
+        }
+
+        let context = self.context;
+        let mut bytes = Vec::new();
+        match ty.get_content(context) {
+            TypeContent::Unit => bytes.push(0),
+            TypeContent::Bool => bytes.push(1),
+            TypeContent::Uint(width) => {
+                bytes.push(2);
+                bytes.push(*width);
+            }
+            TypeContent::B256 => bytes.push(3),
+            TypeContent::String(len) => {
+                bytes.push(4);
+                write_uint(&mut bytes, *len);
+            }
+            TypeContent::Array(elm_ty, len) => {
+                bytes.push(5);
+                let elm_ty = self.type_ref(*elm_ty);
+                write_uint(&mut bytes, elm_ty);
+                write_uint(&mut bytes, *len);
+            }
+            TypeContent::Union(fields) | TypeContent::Struct(fields) => {
+                bytes.push(
+                    if matches!(ty.get_content(context), TypeContent::Union(_)) {
+                        6
+                    } else {
+                        7
+                    },
+                );
+                let fields = fields
+                    .iter()
+                    .map(|field| self.type_ref(*field))
+                    .collect::<Vec<_>>();
+                write_len(&mut bytes, fields.len());
+                for field in fields {
+                    write_uint(&mut bytes, field);
+                }
+            }
+            TypeContent::Slice => bytes.push(8),
+        }
+
+        let idx = self.types.len() as u64;
+        self.types.insert(ty, idx);
+        self.type_bytes.append(&mut bytes);
+        idx
+    }
+
+    fn write_type(&mut self, bytes: &mut Vec<u8>, ty: Type) {
+        let idx = self.type_ref(ty);
+        write_uint(bytes, idx);
+    }
+
+    fn metadata_ref(&mut self, md_idx: MetadataIndex) -> u64 {
+        if let Some(idx) = self.metadata.get(&md_idx) {
+            return *idx;
+        }
+
+        let mut bytes = Vec::new();
+        self.encode_metadatum(&mut bytes, md_idx.get_content(self.context));
+
+        let idx = self.metadata.len() as u64;
+        self.metadata.insert(md_idx, idx);
+        self.metadata_bytes.append(&mut bytes);
+        idx
+    }
+
+    fn encode_metadatum(&mut self, bytes: &mut Vec<u8>, md: &'a Metadatum) {
+        match md {
+            Metadatum::Integer(n) => {
+                bytes.push(0);
+                write_uint(bytes, *n);
+            }
+            Metadatum::Index(idx) => {
+                bytes.push(1);
+                let idx = self.metadata_ref(*idx);
+                write_uint(bytes, idx);
+            }
+            Metadatum::String(s) => {
+                bytes.push(2);
+                write_str(bytes, s);
+            }
+            Metadatum::Struct(tag, fields) => {
+                bytes.push(3);
+                write_str(bytes, tag);
+                write_len(bytes, fields.len());
+                for field in fields {
+                    self.encode_metadatum(bytes, field);
+                }
+            }
+            Metadatum::List(idcs) => {
+                bytes.push(4);
+                write_len(bytes, idcs.len());
+                for idx in idcs {
+                    let idx = self.metadata_ref(*idx);
+                    write_uint(bytes, idx);
+                }
+            }
+        }
+    }
+
+    // Optional metadata are written as 0 for none, or their index plus one.
+    fn write_metadata(&mut self, bytes: &mut Vec<u8>, md_idx: Option<MetadataIndex>) {
+        let idx = md_idx.map_or(0, |md_idx| self.metadata_ref(md_idx) + 1);
+        write_uint(bytes, idx);
+    }
+
+    fn write_constant(&mut self, bytes: &mut Vec<u8>, constant: &Constant) {
+        self.write_type(bytes, constant.ty);
+        match &constant.value {
+            ConstantValue::Undef => bytes.push(0),
+            ConstantValue::Unit => bytes.push(1),
+            ConstantValue::Bool(b) => {
+                bytes.push(2);
+                write_bool(bytes, *b);
+            }
+            ConstantValue::Uint(n) => {
+                bytes.push(3);
+                write_uint(bytes, *n);
+            }
+            ConstantValue::B256(b) => {
+                bytes.push(4);
+                bytes.extend_from_slice(b);
+            }
+            ConstantValue::String(s) => {
+                bytes.push(5);
+                write_len(bytes, s.len());
+                bytes.extend_from_slice(s);
+            }
+            ConstantValue::Array(elems) | ConstantValue::Struct(elems) => {
+                bytes.push(if matches!(constant.value, ConstantValue::Array(_)) {
+                    6
+                } else {
+                    7
+                });
+                write_len(bytes, elems.len());
+                for elem in elems {
+                    self.write_constant(bytes, elem);
+                }
+            }
+        }
+    }
+
+    fn encode_module(&mut self, bytes: &mut Vec<u8>, module: Module) -> Result<(), IrError> {
+        let context = self.context;
+        bytes.push(table_tag(&KINDS, &module.get_kind(context)));
+
+        self.configurables.clear();
+        let configurables = module.global_configurable_iter(context).collect::<Vec<_>>();
+        write_len(bytes, configurables.len());
+        for (idx, (call_path, value)) in configurables.into_iter().enumerate() {
+            self.configurables.insert(*value, idx as u64);
+            write_len(bytes, call_path.len());
+            for segment in call_path {
+                write_str(bytes, segment);
+            }
+            let Some(constant) = value.get_configurable(context) else {
+                return Err(IrError::InvalidConfigurableValue(call_path.join("::")));
+            };
+            self.write_constant(bytes, constant);
+            self.write_metadata(bytes, value.get_metadata(context));
+        }
+
+        let functions = module.function_iter(context).collect::<Vec<_>>();
+        self.functions = FxHashMap::from_iter(
+            functions
+                .iter()
+                .enumerate()
+                .map(|(idx, function)| (*function, idx as u64)),
+        );
+        write_len(bytes, functions.len());
+        for function in &functions {
+            self.encode_function_header(bytes, *function);
+        }
+        for function in functions {
+            self.encode_function_body(bytes, function)?;
+        }
+        Ok(())
+    }
+
+    fn encode_function_header(&mut self, bytes: &mut Vec<u8>, function: Function) {
+        let context = self.context;
+        write_str(bytes, function.get_name(context));
+
+        write_len(bytes, function.num_args(context));
+        for (name, arg) in function.args_iter(context) {
+            let (ty, by_ref) = arg
+                .get_argument_type_and_byref(context)
+                .expect("Function arguments are block arguments.");
+            write_str(bytes, name);
+            self.write_type(bytes, ty);
+            write_bool(bytes, by_ref);
+            self.write_metadata(bytes, arg.get_metadata(context));
+        }
+        self.write_type(bytes, function.get_return_type(context));
+
+        match function.get_selector(context) {
+            None => write_bool(bytes, false),
+            Some(selector) => {
+                write_bool(bytes, true);
+                bytes.extend_from_slice(&selector);
+            }
+        }
+        write_bool(bytes, context.functions[function.0].is_public);
+        write_bool(bytes, function.is_entry(context));
+
+        let attributes = function.get_attributes(context);
+        write_len(bytes, attributes.len());
+        for attribute in attributes {
+            bytes.push(table_tag(&FUNCTION_ATTRIBUTES, &attribute));
+        }
+        self.write_metadata(bytes, function.get_metadata(context));
+
+        let locals = function.locals_iter(context).collect::<Vec<_>>();
+        write_len(bytes, locals.len());
+        for (name, local) in locals {
+            write_str(bytes, name);
+            self.write_type(bytes, local.get_type(context));
+            match local.get_initializer(context) {
+                None => write_bool(bytes, false),
+                Some(initializer) => {
+                    write_bool(bytes, true);
+                    self.write_constant(bytes, initializer);
+                }
+            }
+        }
+    }
+
+    fn encode_function_body(
+        &mut self,
+        bytes: &mut Vec<u8>,
+        function: Function,
+    ) -> Result<(), IrError> {
+        let context = self.context;
+
+        self.locals = FxHashMap::from_iter(
+            function
+                .locals_iter(context)
+                .enumerate()
+                .map(|(idx, (_, local))| (*local, idx as u64)),
+        );
+        self.blocks.clear();
+        self.values.clear();
+
+        let blocks = function.block_iter(context).collect::<Vec<_>>();
+        write_len(bytes, blocks.len());
+        for (block_idx, block) in blocks.iter().enumerate() {
+            self.blocks.insert(*block, block_idx as u64);
+            write_str(bytes, block.get_label(context));
+
+            // The entry block arguments are the function arguments, already in the header.
+            if block_idx != 0 {
+                write_len(bytes, block.num_args(context));
+            }
+            for arg in block.arg_iter(context) {
+                if block_idx != 0 {
+                    let (ty, by_ref) = arg
+                        .get_argument_type_and_byref(context)
+                        .ok_or(IrError::VerifyBlockArgMalformed)?;
+                    self.write_type(bytes, ty);
+                    write_bool(bytes, by_ref);
+                    self.write_metadata(bytes, arg.get_metadata(context));
+                }
+                self.values.insert(*arg, self.values.len() as u64);
+            }
+
+            write_len(bytes, block.num_instructions(context));
+            for ins in block.instruction_iter(context) {
+                self.values.insert(ins, self.values.len() as u64);
+            }
+        }
+
+        for block in blocks {
+            for ins in block.instruction_iter(context) {
+                self.write_metadata(bytes, ins.get_metadata(context));
+                let Some(instruction) = ins.get_instruction(context) else {
+                    return Err(IrError::ValueNotFound(format!("{ins:?}")));
+                };
+                self.encode_instruction(bytes, instruction)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Operands are tagged: 0 for a value of this function, 1 for an inline constant or 2 for a
+    // configurable of this module.
+    fn write_value(&mut self, bytes: &mut Vec<u8>, value: Value) -> Result<(), IrError> {
+        let context = self.context;
+        if let Some(idx) = self.values.get(&value) {
+            bytes.push(0);
+            write_uint(bytes, *idx);
+            return Ok(());
+        }
+        match &context.values[value.0].value {
+            ValueDatum::Constant(constant) => {
+                bytes.push(1);
+                self.write_constant(bytes, constant);
+                self.write_metadata(bytes, value.get_metadata(context));
+            }
+            ValueDatum::Configurable(_) => {
+                let Some(idx) = self.configurables.get(&value) else {
+                    return Err(IrError::ValueNotFound(format!("{value:?}")));
+                };
+                bytes.push(2);
+                write_uint(bytes, *idx);
+            }
+            ValueDatum::Argument(_) | ValueDatum::Instruction(_) => {
+                return Err(IrError::ValueNotFound(format!("{value:?}")));
+            }
+        }
+        Ok(())
+    }
+
+    fn write_values(&mut self, bytes: &mut Vec<u8>, values: &[Value]) -> Result<(), IrError> {
+        write_len(bytes, values.len());
+        for value in values {
+            self.write_value(bytes, *value)?;
+        }
+        Ok(())
+    }
+
+    fn write_branch(
+        &mut self,
+        bytes: &mut Vec<u8>,
+        branch: &BranchToWithArgs,
+    ) -> Result<(), IrError> {
+        let Some(idx) = self.blocks.get(&branch.block) else {
+            return Err(IrError::VerifyBranchToMissingBlock(
+                branch.block.get_label(self.context).to_owned(),
+            ));
+        };
+        write_uint(bytes, *idx);
+        self.write_values(bytes, &branch.args)
+    }
+
+    fn write_indices(bytes: &mut Vec<u8>, indices: &[u64]) {
+        write_len(bytes, indices.len());
+        for idx in indices {
+            write_uint(bytes, *idx);
+        }
+    }
+
+    fn encode_instruction(
+        &mut self,
+        bytes: &mut Vec<u8>,
+        instruction: &Instruction,
+    ) -> Result<(), IrError> {
+        let context = self.context;
+        match instruction {
+            Instruction::AddrOf(value) => {
+                bytes.push(0);
+                self.write_value(bytes, *value)?;
+            }
+            Instruction::AsmBlock(asm, args) => {
+                bytes.push(1);
+                let content = asm.get_content(context);
+                write_len(bytes, args.len());
+                for (arg, name) in args.iter().zip(&content.args_names) {
+                    write_str(bytes, name.as_str());
+                    match arg.initializer {
+                        None => write_bool(bytes, false),
+                        Some(initializer) => {
+                            write_bool(bytes, true);
+                            self.write_value(bytes, initializer)?;
+                        }
+                    }
+                }
+                write_len(bytes, content.body.len());
+                for asm_ins in &content.body {
+                    write_str(bytes, asm_ins.name.as_str());
+                    write_len(bytes, asm_ins.args.len());
+                    for arg in &asm_ins.args {
+                        write_str(bytes, arg.as_str());
+                    }
+                    match &asm_ins.immediate {
+                        None => write_bool(bytes, false),
+                        Some(imm) => {
+                            write_bool(bytes, true);
+                            write_str(bytes, imm.as_str());
+                        }
+                    }
+                    self.write_metadata(bytes, asm_ins.metadata);
+                }
+                self.write_type(bytes, content.return_type);
+                match &content.return_name {
+                    None => write_bool(bytes, false),
+                    Some(name) => {
+                        write_bool(bytes, true);
+                        write_str(bytes, name.as_str());
+                    }
+                }
+            }
+            Instruction::BinaryOp { op, arg1, arg2 } => {
+                bytes.push(2);
+                bytes.push(table_tag(&BINARY_OPS, op));
+                self.write_value(bytes, *arg1)?;
+                self.write_value(bytes, *arg2)?;
+            }
+            Instruction::BitCast(value, ty) => {
+                bytes.push(3);
+                self.write_value(bytes, *value)?;
+                self.write_type(bytes, *ty);
+            }
+            Instruction::Branch(to_block) => {
+                bytes.push(4);
+                self.write_branch(bytes, to_block)?;
+            }
+            Instruction::Call(function, args) => {
+                bytes.push(5);
+                let Some(idx) = self.functions.get(function) else {
+                    return Err(IrError::VerifyCallToMissingFunction(
+                        function.get_name(context).to_owned(),
+                    ));
+                };
+                write_uint(bytes, *idx);
+                self.write_values(bytes, args)?;
+            }
+            Instruction::CastPtr(value, ty, offset) => {
+                bytes.push(6);
+                self.write_value(bytes, *value)?;
+                self.write_type(bytes, *ty);
+                write_uint(bytes, *offset);
+            }
+            Instruction::Cmp(pred, lhs, rhs) => {
+                bytes.push(7);
+                bytes.push(table_tag(&PREDICATES, pred));
+                self.write_value(bytes, *lhs)?;
+                self.write_value(bytes, *rhs)?;
+            }
+            Instruction::ConditionalBranch {
+                cond_value,
+                true_block,
+                false_block,
+            } => {
+                bytes.push(8);
+                self.write_value(bytes, *cond_value)?;
+                self.write_branch(bytes, true_block)?;
+                self.write_branch(bytes, false_block)?;
+            }
+            Instruction::ContractCall {
+                return_type,
+                name,
+                params,
+                coins,
+                asset_id,
+                gas,
+            } => {
+                bytes.push(9);
+                self.write_type(bytes, *return_type);
+                write_str(bytes, name);
+                self.write_value(bytes, *params)?;
+                self.write_value(bytes, *coins)?;
+                self.write_value(bytes, *asset_id)?;
+                self.write_value(bytes, *gas)?;
+            }
+            Instruction::ExtractElement {
+                array,
+                ty,
+                index_val,
+            } => {
+                bytes.push(10);
+                self.write_value(bytes, *array)?;
+                self.write_type(bytes, *ty);
+                self.write_value(bytes, *index_val)?;
+            }
+            Instruction::ExtractValue {
+                aggregate,
+                ty,
+                indices,
+            } => {
+                bytes.push(11);
+                self.write_value(bytes, *aggregate)?;
+                self.write_type(bytes, *ty);
+                Self::write_indices(bytes, indices);
+            }
+            Instruction::FuelVm(fuel_vm_instr) => {
+                bytes.push(12);
+                self.encode_fuel_vm_instruction(bytes, fuel_vm_instr)?;
+            }
+            Instruction::GetLocal(local) => {
+                bytes.push(13);
+                let Some(idx) = self.locals.get(local) else {
+                    return Err(IrError::VerifyGetNonExistentPointer);
+                };
+                write_uint(bytes, *idx);
+            }
+            Instruction::InsertElement {
+                array,
+                ty,
+                value,
+                index_val,
+            } => {
+                bytes.push(14);
+                self.write_value(bytes, *array)?;
+                self.write_type(bytes, *ty);
+                self.write_value(bytes, *value)?;
+                self.write_value(bytes, *index_val)?;
+            }
+            Instruction::InsertValue {
+                aggregate,
+                ty,
+                value,
+                indices,
+            } => {
+                bytes.push(15);
+                self.write_value(bytes, *aggregate)?;
+                self.write_type(bytes, *ty);
+                self.write_value(bytes, *value)?;
+                Self::write_indices(bytes, indices);
+            }
+            Instruction::IntToPtr(value, ty) => {
+                bytes.push(16);
+                self.write_value(bytes, *value)?;
+                self.write_type(bytes, *ty);
+            }
+            Instruction::Load(src_val) => {
+                bytes.push(17);
+                self.write_value(bytes, *src_val)?;
+            }
+            Instruction::MemCopy {
+                dst_val,
+                src_val,
+                byte_len,
+            } => {
+                bytes.push(18);
+                self.write_value(bytes, *dst_val)?;
+                self.write_value(bytes, *src_val)?;
+                write_uint(bytes, *byte_len);
+            }
+            Instruction::Nop => bytes.push(19),
+            Instruction::Ret(value, ty) => {
+                bytes.push(20);
+                self.write_value(bytes, *value)?;
+                self.write_type(bytes, *ty);
+            }
+            Instruction::Store {
+                dst_val,
+                stored_val,
+            } => {
+                bytes.push(21);
+                self.write_value(bytes, *dst_val)?;
+                self.write_value(bytes, *stored_val)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn encode_fuel_vm_instruction(
+        &mut self,
+        bytes: &mut Vec<u8>,
+        instruction: &FuelVmInstruction,
+    ) -> Result<(), IrError> {
+        match instruction {
+            FuelVmInstruction::GasCheckpoint(id) => {
+                bytes.push(0);
+                self.write_value(bytes, *id)?;
+            }
+            FuelVmInstruction::GetStorageKey => bytes.push(1),
+            FuelVmInstruction::Gtf { index, tx_field_id } => {
+                bytes.push(2);
+                self.write_value(bytes, *index)?;
+                write_uint(bytes, *tx_field_id);
+            }
+            FuelVmInstruction::Log {
+                log_val,
+                log_ty,
+                log_id,
+            } => {
+                bytes.push(3);
+                self.write_value(bytes, *log_val)?;
+                self.write_type(bytes, *log_ty);
+                self.write_value(bytes, *log_id)?;
+            }
+            FuelVmInstruction::ReadRegister(reg) => {
+                bytes.push(4);
+                bytes.push(table_tag(&REGISTERS, reg));
+            }
+            FuelVmInstruction::Revert(value) => {
+                bytes.push(5);
+                self.write_value(bytes, *value)?;
+            }
+            FuelVmInstruction::Smo {
+                recipient_and_message,
+                message_size,
+                output_index,
+                coins,
+            } => {
+                bytes.push(6);
+                self.write_value(bytes, *recipient_and_message)?;
+                self.write_value(bytes, *message_size)?;
+                self.write_value(bytes, *output_index)?;
+                self.write_value(bytes, *coins)?;
+            }
+            FuelVmInstruction::StateClear {
+                key,
+                number_of_slots,
+            } => {
+                bytes.push(7);
+                self.write_value(bytes, *key)?;
+                self.write_value(bytes, *number_of_slots)?;
+            }
+            FuelVmInstruction::StateLoadQuadWord {
+                load_val,
+                key,
+                number_of_slots,
+            } => {
+                bytes.push(8);
+                self.write_value(bytes, *load_val)?;
+                self.write_value(bytes, *key)?;
+                self.write_value(bytes, *number_of_slots)?;
+            }
+            FuelVmInstruction::StateLoadWord(key) => {
+                bytes.push(9);
+                self.write_value(bytes, *key)?;
+            }
+            FuelVmInstruction::StateStoreQuadWord {
+                stored_val,
+                key,
+                number_of_slots,
+            } => {
+                bytes.push(10);
+                self.write_value(bytes, *stored_val)?;
+                self.write_value(bytes, *key)?;
+                self.write_value(bytes, *number_of_slots)?;
+            }
+            FuelVmInstruction::StateStoreWord { stored_val, key } => {
+                bytes.push(11);
+                self.write_value(bytes, *stored_val)?;
+                self.write_value(bytes, *key)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    context: Context,
+
+    types: Vec<Type>,
+    metadata: Vec<MetadataIndex>,
+
+    // Handles within the current module.
+    functions: Vec<Function>,
+    configurables: Vec<Value>,
+
+    // Handles within the current function.
+    blocks: Vec<Block>,
+    locals: Vec<LocalVar>,
+    values: Vec<Value>,
+}
+
+fn decode_error(reason: impl Into<String>) -> IrError {
+    IrError::BinaryDecodeFailed(reason.into())
+}
+
+impl<'a> Decoder<'a> {
+    fn new(bytes: &'a [u8], magic: [u8; 4]) -> Result<Self, IrError> {
+        if bytes.len() < magic.len() || bytes[..magic.len()] != magic {
+            return Err(IrError::BinaryNotIr);
+        }
+        let mut decoder = Decoder {
+            bytes,
+            pos: magic.len(),
+            context: Context::default(),
+            types: Vec::new(),
+            metadata: Vec::new(),
+            functions: Vec::new(),
+            configurables: Vec::new(),
+            blocks: Vec::new(),
+            locals: Vec::new(),
+            values: Vec::new(),
+        };
+        let version = u32::from_le_bytes(decoder.read_bytes::<4>()?);
+        if version != BINARY_VERSION {
+            return Err(IrError::BinaryVersionMismatch(version, BINARY_VERSION));
+        }
+        Ok(decoder)
+    }
+
+    fn decode(mut self) -> Result<Context, IrError> {
+        self.decode_tables()?;
+
+        let num_modules = self.read_len()?;
+        for _ in 0..num_modules {
+            self.decode_module()?;
+        }
+
+        self.expect_end()?;
+        Ok(self.context)
+    }
+
+    fn decode_tables(&mut self) -> Result<(), IrError> {
+        let num_types = self.read_len()?;
+        for _ in 0..num_types {
+            let ty = self.decode_type()?;
+            self.types.push(ty);
+        }
+
+        let num_metadata = self.read_len()?;
+        for _ in 0..num_metadata {
+            let md = self.decode_metadatum()?;
+            let md_idx = MetadataIndex(self.context.metadata.insert(md));
+            self.metadata.push(md_idx);
+        }
+        Ok(())
+    }
+
+    fn expect_end(&self) -> Result<(), IrError> {
+        if self.pos != self.bytes.len() {
+            return Err(decode_error(format!(
+                "unexpected trailing bytes at offset {}",
+                self.pos
+            )));
+        }
+        Ok(())
+    }
+
+    fn decode_function_into(&mut self, function: Function) -> Result<(), IrError> {
+        self.decode_tables()?;
+        let context = &self.context;
+        let module = context
+            .module_iter()
+            .find(|module| context.modules[module.0].functions.contains(&function))
+            .expect("Function must belong to a module.");
+
+        let num_configurables = self.read_len()?;
+        for _ in 0..num_configurables {
+            let num_segments = self.read_len()?;
+            let call_path = (0..num_segments)
+                .map(|_| self.read_str())
+                .collect::<Result<Vec<_>, _>>()?;
+            let value = module
+                .get_global_configurable(&self.context, &call_path)
+                .ok_or_else(|| {
+                    decode_error(format!("missing configurable `{}`", call_path.join("::")))
+                })?;
+            self.configurables.push(value);
+        }
+
+        // The new contents are decoded into a new function, which then swaps contents with
+        // `function`.  The callees are looked up first, so a recursive call refers to `function`.
+        let num_functions = self.read_len()?;
+        for _ in 0..num_functions {
+            let name = self.read_str()?;
+            let callee = module
+                .function_iter(&self.context)
+                .find(|callee| callee.get_name(&self.context) == name)
+                .ok_or_else(|| decode_error(format!("missing function `{name}`")))?;
+            self.functions.push(callee);
+        }
+
+        let num_functions = self.context.modules[module.0].functions.len();
+        let result = self.decode_function_header(module).and_then(|decoded| {
+            self.decode_function_body(decoded)?;
+            self.expect_end()?;
+            Ok(decoded)
+        });
+        let decoded = match result {
+            Ok(decoded) => decoded,
+            Err(error) => {
+                if self.context.modules[module.0].functions.len() > num_functions {
+                    let decoded = *self.context.modules[module.0].functions.last().unwrap();
+                    module.remove_function(&mut self.context, &decoded);
+                }
+                return Err(error);
+            }
+        };
+
+        let (old_content, new_content) = self.context.functions.get2_mut(function.0, decoded.0);
+        std::mem::swap(old_content.unwrap(), new_content.unwrap());
+        for (owner, blocks) in [
+            (function, self.context.functions[function.0].blocks.clone()),
+            (decoded, self.context.functions[decoded.0].blocks.clone()),
+        ] {
+            for block in blocks {
+                self.context.blocks[block.0].function = owner;
+            }
+        }
+        module.remove_function(&mut self.context, &decoded);
+        Ok(())
+    }
+
+    fn read_u8(&mut self) -> Result<u8, IrError> {
+        let byte = *self
+            .bytes
+            .get(self.pos)
+            .ok_or_else(|| decode_error("unexpected end of input"))?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn read_bytes<const N: usize>(&mut self) -> Result<[u8; N], IrError> {
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos + N)
+            .ok_or_else(|| decode_error("unexpected end of input"))?;
+        self.pos += N;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    fn read_uint(&mut self) -> Result<u64, IrError> {
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_u8()?;
+            let bits = (byte & 0x7f) as u64;
+            if bits << shift >> shift != bits {
+                break;
+            }
+            n |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err(decode_error(format!(
+            "integer overflow at offset {}",
+            self.pos
+        )))
+    }
+
+    // Lengths are checked against what's left of the input, so that a corrupt length fails
+    // quickly rather than looping or allocating for a very long time.
+    fn read_len(&mut self) -> Result<usize, IrError> {
+        let len = self.read_uint()?;
+        if len > (self.bytes.len() - self.pos) as u64 {
+            return Err(decode_error(format!(
+                "length {len} at offset {} is past the end of input",
+                self.pos
+            )));
+        }
+        Ok(len as usize)
+    }
+
+    fn read_bool(&mut self) -> Result<bool, IrError> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            byte => Err(self.bad_tag("bool", byte)),
+        }
+    }
+
+    fn read_byte_vec(&mut self) -> Result<Vec<u8>, IrError> {
+        let len = self.read_len()?;
+        let bytes = self.bytes[self.pos..self.pos + len].to_vec();
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn read_str(&mut self) -> Result<String, IrError> {
+        let pos = self.pos;
+        String::from_utf8(self.read_byte_vec()?)
+            .map_err(|_| decode_error(format!("invalid UTF-8 string at offset {pos}")))
+    }
+
+    fn read_ident(&mut self) -> Result<Ident, IrError> {
+        let s = self.read_str()?;
+        let len = s.len();
+        Ok(Ident::new(Span::new(s.into(), 0, len, None).unwrap()))
+    }
+
+    fn read_index(&mut self, what: &str, len: usize) -> Result<usize, IrError> {
+        let idx = self.read_uint()?;
+        if idx >= len as u64 {
+            return Err(decode_error(format!(
+                "{what} index {idx} out of range at offset {}",
+                self.pos
+            )));
+        }
+        Ok(idx as usize)
+    }
+
+    fn read_table<T: Copy>(&mut self, what: &str, table: &[T]) -> Result<T, IrError> {
+        let tag = self.read_u8()?;
+        table
+            .get(tag as usize)
+            .copied()
+            .ok_or_else(|| self.bad_tag(what, tag))
+    }
+
+    fn bad_tag(&self, what: &str, tag: u8) -> IrError {
+        decode_error(format!(
+            "invalid {what} tag {tag} at offset {}",
+            self.pos - 1
+        ))
+    }
+
+    fn read_type(&mut self) -> Result<Type, IrError> {
+        let idx = self.read_index("type", self.types.len())?;
+        Ok(self.types[idx])
+    }
+
+    fn read_metadata(&mut self) -> Result<Option<MetadataIndex>, IrError> {
+        let idx = self.read_index("metadata", self.metadata.len() + 1)?;
+        Ok(idx.checked_sub(1).map(|idx| self.metadata[idx]))
+    }
+
+    fn decode_type(&mut self) -> Result<Type, IrError> {
+        Ok(match self.read_u8()? {
+            0 => Type::get_unit(&self.context),
+            1 => Type::get_bool(&self.context),
+            2 => {
+                let width = self.read_u8()?;
+                Type::new_uint(&mut self.context, width)
+            }
+            3 => Type::get_b256(&self.context),
+            4 => {
+                let len = self.read_uint()?;
+                Type::new_string(&mut self.context, len)
+            }
+            5 => {
+                let elm_ty = self.read_type()?;
+                let len = self.read_uint()?;
+                Type::new_array(&mut self.context, elm_ty, len)
+            }
+            tag @ (6 | 7) => {
+                let num_fields = self.read_len()?;
+                let fields = (0..num_fields)
+                    .map(|_| self.read_type())
+                    .collect::<Result<Vec<_>, _>>()?;
+                if tag == 6 {
+                    Type::new_union(&mut self.context, fields)
+                } else {
+                    Type::new_struct(&mut self.context, fields)
+                }
+            }
+            8 => Type::get_slice(&mut self.context),
+            tag => return Err(self.bad_tag("type", tag)),
+        })
+    }
+
+    fn decode_metadatum(&mut self) -> Result<Metadatum, IrError> {
+        Ok(match self.read_u8()? {
+            0 => Metadatum::Integer(self.read_uint()?),
+            1 => {
+                let idx = self.read_index("metadata", self.metadata.len())?;
+                Metadatum::Index(self.metadata[idx])
+            }
+            2 => Metadatum::String(self.read_str()?),
+            3 => {
+                let tag = self.read_str()?;
+                let num_fields = self.read_len()?;
+                let fields = (0..num_fields)
+                    .map(|_| self.decode_metadatum())
+                    .collect::<Result<Vec<_>, _>>()?;
+                Metadatum::Struct(tag, fields)
+            }
+            4 => {
+                let num_idcs = self.read_len()?;
+                let idcs = (0..num_idcs)
+                    .map(|_| {
+                        let idx = self.read_index("metadata", self.metadata.len())?;
+                        Ok(self.metadata[idx])
+                    })
+                    .collect::<Result<Vec<_>, IrError>>()?;
+                Metadatum::List(idcs)
+            }
+            tag => return Err(self.bad_tag("metadatum", tag)),
+        })
+    }
+
+    fn decode_constant(&mut self) -> Result<Constant, IrError> {
+        let ty = self.read_type()?;
+        let value = match self.read_u8()? {
+            0 => ConstantValue::Undef,
+            1 => ConstantValue::Unit,
+            2 => ConstantValue::Bool(self.read_bool()?),
+            3 => ConstantValue::Uint(self.read_uint()?),
+            4 => ConstantValue::B256(self.read_bytes::<32>()?),
+            5 => ConstantValue::String(self.read_byte_vec()?),
+            tag @ (6 | 7) => {
+                let num_elems = self.read_len()?;
+                let elems = (0..num_elems)
+                    .map(|_| self.decode_constant())
+                    .collect::<Result<Vec<_>, _>>()?;
+                if tag == 6 {
+                    ConstantValue::Array(elems)
+                } else {
+                    ConstantValue::Struct(elems)
+                }
+            }
+            tag => return Err(self.bad_tag("constant", tag)),
+        };
+        Ok(Constant { ty, value })
+    }
+
+    fn decode_module(&mut self) -> Result<(), IrError> {
+        let kind = self.read_table("module kind", &KINDS)?;
+        let module = Module::new(&mut self.context, kind);
+
+        self.configurables.clear();
+        let num_configurables = self.read_len()?;
+        for _ in 0..num_configurables {
+            let num_segments = self.read_len()?;
+            let call_path = (0..num_segments)
+                .map(|_| self.read_str())
+                .collect::<Result<Vec<_>, _>>()?;
+            let constant = self.decode_constant()?;
+            let md = self.read_metadata()?;
+            let value = Value::new_configurable(&mut self.context, constant)
+                .add_metadatum(&mut self.context, md);
+            module.add_global_configurable(&mut self.context, call_path, value);
+            self.configurables.push(value);
+        }
+
+        self.functions.clear();
+        let num_functions = self.read_len()?;
+        for _ in 0..num_functions {
+            let function = self.decode_function_header(module)?;
+            self.functions.push(function);
+        }
+        for function in self.functions.clone() {
+            self.decode_function_body(function)?;
+        }
+        Ok(())
+    }
+
+    fn decode_function_header(&mut self, module: Module) -> Result<Function, IrError> {
+        let name = self.read_str()?;
+
+        let num_args = self.read_len()?;
+        let args = (0..num_args)
+            .map(|_| {
+                Ok((
+                    self.read_str()?,
+                    self.read_type()?,
+                    self.read_bool()?,
+                    self.read_metadata()?,
+                ))
+            })
+            .collect::<Result<Vec<_>, IrError>>()?;
+        let return_type = self.read_type()?;
+
+        let selector = if self.read_bool()? {
+            Some(self.read_bytes::<4>()?)
+        } else {
+            None
+        };
+        let is_public = self.read_bool()?;
+        let is_entry = self.read_bool()?;
+
+        let num_attributes = self.read_len()?;
+        let attributes = (0..num_attributes)
+            .map(|_| self.read_table("function attribute", &FUNCTION_ATTRIBUTES))
+            .collect::<Result<Vec<_>, _>>()?;
+        let md = self.read_metadata()?;
+
+        let function = Function::new(
+            &mut self.context,
+            module,
+            name,
+            args,
+            return_type,
+            selector,
+            is_public,
+            is_entry,
+            md,
+        );
+        for attribute in attributes {
+            function.add_attribute(&mut self.context, attribute);
+        }
+
+        let num_locals = self.read_len()?;
+        for _ in 0..num_locals {
+            let name = self.read_str()?;
+            let ty = self.read_type()?;
+            let initializer = if self.read_bool()? {
+                Some(self.decode_constant()?)
+            } else {
+                None
+            };
+            function.new_local_var(&mut self.context, name, ty, initializer)?;
+        }
+        Ok(function)
+    }
+
+    fn decode_function_body(&mut self, function: Function) -> Result<(), IrError> {
+        self.locals = function
+            .locals_iter(&self.context)
+            .map(|(_, local)| *local)
+            .collect();
+        self.blocks.clear();
+        self.values.clear();
+
+        // Create every block, with its arguments and placeholders for its instructions, so that
+        // operands and branches may refer forwards.
+        let num_blocks = self.read_len()?;
+        if num_blocks == 0 {
+            return Err(IrError::MissingBlock(
+                function.get_name(&self.context).to_owned(),
+            ));
+        }
+        let mut num_instructions = Vec::with_capacity(num_blocks);
+        for block_idx in 0..num_blocks {
+            let label = self.read_str()?;
+            let block = if block_idx == 0 {
+                let block = function.get_entry_block(&self.context);
+                self.context.blocks[block.0].label = self.context.intern(&label);
+                self.values
+                    .extend(block.arg_iter(&self.context).copied().collect::<Vec<_>>());
+                block
+            } else {
+                let block = function.create_block(&mut self.context, Some(label));
+                let num_args = self.read_len()?;
+                for idx in 0..num_args {
+                    let ty = self.read_type()?;
+                    let by_ref = self.read_bool()?;
+                    let md = self.read_metadata()?;
+                    let arg = Value::new_argument(
+                        &mut self.context,
+                        BlockArgument {
+                            block,
+                            idx,
+                            ty,
+                            by_ref,
+                        },
+                    )
+                    .add_metadatum(&mut self.context, md);
+                    block.add_arg(&mut self.context, arg);
+                    self.values.push(arg);
+                }
+                block
+            };
+            self.blocks.push(block);
+
+            let num_ins = self.read_len()?;
+            for _ in 0..num_ins {
+                let ins = Value::new_instruction(&mut self.context, Instruction::Nop);
+                self.context.blocks[block.0].instructions.push(ins);
+                ins.set_block(&mut self.context, Some(block));
+                self.values.push(ins);
+            }
+            num_instructions.push(num_ins);
+        }
+
+        let mut next_value = 0;
+        for (block, num_ins) in self.blocks.clone().into_iter().zip(num_instructions) {
+            next_value += block.num_args(&self.context);
+            for _ in 0..num_ins {
+                let ins = self.values[next_value];
+                next_value += 1;
+                let md = self.read_metadata()?;
+                let instruction = self.decode_instruction()?;
+                ins.replace(&mut self.context, ValueDatum::Instruction(instruction));
+                ins.add_metadatum(&mut self.context, md);
+            }
+        }
+
+        for block in self.blocks.clone() {
+            for BranchToWithArgs { block: succ, .. } in block.successors(&self.context) {
+                succ.add_pred(&mut self.context, &block);
+            }
+        }
+        Ok(())
+    }
+
+    fn read_value(&mut self) -> Result<Value, IrError> {
+        match self.read_u8()? {
+            0 => {
+                let idx = self.read_index("value", self.values.len())?;
+                Ok(self.values[idx])
+            }
+            1 => {
+                let constant = self.decode_constant()?;
+                let md = self.read_metadata()?;
+                Ok(Value::new_constant(&mut self.context, constant)
+                    .add_metadatum(&mut self.context, md))
+            }
+            2 => {
+                let idx = self.read_index("configurable", self.configurables.len())?;
+                Ok(self.configurables[idx])
+            }
+            tag => Err(self.bad_tag("value", tag)),
+        }
+    }
+
+    fn read_values(&mut self) -> Result<Vec<Value>, IrError> {
+        let len = self.read_len()?;
+        (0..len).map(|_| self.read_value()).collect()
+    }
+
+    fn read_branch(&mut self) -> Result<BranchToWithArgs, IrError> {
+        let idx = self.read_index("block", self.blocks.len())?;
+        Ok(BranchToWithArgs {
+            block: self.blocks[idx],
+            args: self.read_values()?,
+        })
+    }
+
+    fn read_indices(&mut self) -> Result<Vec<u64>, IrError> {
+        let len = self.read_len()?;
+        (0..len).map(|_| self.read_uint()).collect()
+    }
+
+    fn decode_instruction(&mut self) -> Result<Instruction, IrError> {
+        Ok(match self.read_u8()? {
+            0 => Instruction::AddrOf(self.read_value()?),
+            1 => {
+                let num_args = self.read_len()?;
+                let mut args_names = Vec::with_capacity(num_args);
+                let mut args = Vec::with_capacity(num_args);
+                for _ in 0..num_args {
+                    let name = self.read_ident()?;
+                    let initializer = if self.read_bool()? {
+                        Some(self.read_value()?)
+                    } else {
+                        None
+                    };
+                    args_names.push(name.clone());
+                    args.push(AsmArg { name, initializer });
+                }
+                let num_asm_ins = self.read_len()?;
+                let body = (0..num_asm_ins)
+                    .map(|_| {
+                        let name = self.read_ident()?;
+                        let num_args = self.read_len()?;
+                        let args = (0..num_args)
+                            .map(|_| self.read_ident())
+                            .collect::<Result<Vec<_>, _>>()?;
+                        let immediate = if self.read_bool()? {
+                            Some(self.read_ident()?)
+                        } else {
+                            None
+                        };
+                        let metadata = self.read_metadata()?;
+                        Ok(AsmInstruction {
+                            name,
+                            args,
+                            immediate,
+                            metadata,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, IrError>>()?;
+                let return_type = self.read_type()?;
+                let return_name = if self.read_bool()? {
+                    Some(self.read_ident()?)
+                } else {
+                    None
+                };
+                let asm = AsmBlock::new(
+                    &mut self.context,
+                    args_names,
+                    body,
+                    return_type,
+                    return_name,
+                );
+                Instruction::AsmBlock(asm, args)
+            }
+            2 => Instruction::BinaryOp {
+                op: self.read_table("binary op", &BINARY_OPS)?,
+                arg1: self.read_value()?,
+                arg2: self.read_value()?,
+            },
+            3 => Instruction::BitCast(self.read_value()?, self.read_type()?),
+            4 => Instruction::Branch(self.read_branch()?),
+            5 => {
+                let idx = self.read_index("function", self.functions.len())?;
+                Instruction::Call(self.functions[idx], self.read_values()?)
+            }
+            6 => Instruction::CastPtr(self.read_value()?, self.read_type()?, self.read_uint()?),
+            7 => Instruction::Cmp(
+                self.read_table("predicate", &PREDICATES)?,
+                self.read_value()?,
+                self.read_value()?,
+            ),
+            8 => Instruction::ConditionalBranch {
+                cond_value: self.read_value()?,
+                true_block: self.read_branch()?,
+                false_block: self.read_branch()?,
+            },
+            9 => Instruction::ContractCall {
+                return_type: self.read_type()?,
+                name: self.read_str()?,
+                params: self.read_value()?,
+                coins: self.read_value()?,
+                asset_id: self.read_value()?,
+                gas: self.read_value()?,
+            },
+            10 => Instruction::ExtractElement {
+                array: self.read_value()?,
+                ty: self.read_type()?,
+                index_val: self.read_value()?,
+            },
+            11 => Instruction::ExtractValue {
+                aggregate: self.read_value()?,
+                ty: self.read_type()?,
+                indices: self.read_indices()?,
+            },
+            12 => Instruction::FuelVm(self.decode_fuel_vm_instruction()?),
+            13 => {
+                let idx = self.read_index("local", self.locals.len())?;
+                Instruction::GetLocal(self.locals[idx])
+            }
+            14 => Instruction::InsertElement {
+                array: self.read_value()?,
+                ty: self.read_type()?,
+                value: self.read_value()?,
+                index_val: self.read_value()?,
+            },
+            15 => Instruction::InsertValue {
+                aggregate: self.read_value()?,
+                ty: self.read_type()?,
+                value: self.read_value()?,
+                indices: self.read_indices()?,
+            },
+            16 => Instruction::IntToPtr(self.read_value()?, self.read_type()?),
+            17 => Instruction::Load(self.read_value()?),
+            18 => Instruction::MemCopy {
+                dst_val: self.read_value()?,
+                src_val: self.read_value()?,
+                byte_len: self.read_uint()?,
+            },
+            19 => Instruction::Nop,
+            20 => Instruction::Ret(self.read_value()?, self.read_type()?),
+            21 => Instruction::Store {
+                dst_val: self.read_value()?,
+                stored_val: self.read_value()?,
+            },
+            tag => return Err(self.bad_tag("instruction", tag)),
+        })
+    }
+
+    fn decode_fuel_vm_instruction(&mut self) -> Result<FuelVmInstruction, IrError> {
+        Ok(match self.read_u8()? {
+            0 => FuelVmInstruction::GasCheckpoint(self.read_value()?),
+            1 => FuelVmInstruction::GetStorageKey,
+            2 => FuelVmInstruction::Gtf {
+                index: self.read_value()?,
+                tx_field_id: self.read_uint()?,
+            },
+            3 => FuelVmInstruction::Log {
+                log_val: self.read_value()?,
+                log_ty: self.read_type()?,
+                log_id: self.read_value()?,
+            },
+            4 => FuelVmInstruction::ReadRegister(self.read_table("register", &REGISTERS)?),
+            5 => FuelVmInstruction::Revert(self.read_value()?),
+            6 => FuelVmInstruction::Smo {
+                recipient_and_message: self.read_value()?,
+                message_size: self.read_value()?,
+                output_index: self.read_value()?,
+                coins: self.read_value()?,
+            },
+            7 => FuelVmInstruction::StateClear {
+                key: self.read_value()?,
+                number_of_slots: self.read_value()?,
+            },
+            8 => FuelVmInstruction::StateLoadQuadWord {
+                load_val: self.read_value()?,
+                key: self.read_value()?,
+                number_of_slots: self.read_value()?,
+            },
+            9 => FuelVmInstruction::StateLoadWord(self.read_value()?),
+            10 => FuelVmInstruction::StateStoreQuadWord {
+                stored_val: self.read_value()?,
+                key: self.read_value()?,
+                number_of_slots: self.read_value()?,
+            },
+            11 => FuelVmInstruction::StateStoreWord {
+                stored_val: self.read_value()?,
+                key: self.read_value()?,
+            },
+            tag => return Err(self.bad_tag("fuel vm instruction", tag)),
+        })
+    }
+}
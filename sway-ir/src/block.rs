@@ -10,7 +10,7 @@
 //!
 //! Every [`Function`] has at least one block, the first of which is usually labeled `entry`.
 
-use rustc_hash::{FxHashMap, FxHashSet};
+use rustc_hash::FxHashMap;
 
 use crate::{
     context::Context,
@@ -18,6 +18,7 @@ use crate::{
     function::Function,
     instruction::{FuelVmInstruction, Instruction, InstructionInserter, InstructionIterator},
     pretty::DebugWithContext,
+    symbol::Symbol,
     value::{Value, ValueDatum},
     BranchToWithArgs, Type,
 };
@@ -30,15 +31,15 @@ pub struct Block(pub generational_arena::Index);
 #[doc(hidden)]
 pub struct BlockContent {
     /// Block label, useful for printing.
-    pub label: Label,
+    pub label: Symbol,
     /// The function containing this block.
     pub function: Function,
     /// List of instructions in the block.
     pub instructions: Vec<Value>,
     /// Block arguments: Another form of SSA PHIs.
     pub args: Vec<Value>,
-    /// CFG predecessors
-    pub preds: FxHashSet<Block>,
+    /// CFG predecessors, in the order they were added.
+    pub preds: Vec<Block>,
 }
 
 #[derive(Debug, Clone, DebugWithContext)]
@@ -82,7 +83,7 @@ impl Block {
             function,
             instructions: vec![],
             args: vec![],
-            preds: FxHashSet::default(),
+            preds: Vec::new(),
         };
         Block(context.blocks.insert(content))
     }
@@ -99,8 +100,13 @@ impl Block {
 
     /// Get the label of this block.  If it wasn't given one upon creation it will be a generated
     /// label.
-    pub fn get_label(&self, context: &Context) -> String {
-        context.blocks[self.0].label.clone()
+    pub fn get_label<'a>(&self, context: &'a Context) -> &'a str {
+        context.blocks[self.0].label.as_str(context)
+    }
+
+    /// Get the interned label of this block, for comparing labels in constant time.
+    pub fn get_label_symbol(&self, context: &Context) -> Symbol {
+        context.blocks[self.0].label
     }
 
     /// Get the number of instructions in this block
@@ -149,6 +155,45 @@ impl Block {
         }
     }
 
+    /// Remove the block argument at `index`, along with the parameter passed for it by each
+    /// predecessor.  The arguments after it are renumbered.
+    pub fn remove_arg(&self, context: &mut Context, index: usize) {
+        for pred in self.pred_iter(context).copied().collect::<Vec<_>>() {
+            let Some(term) = context.blocks[pred.0].instructions.last().copied() else {
+                continue;
+            };
+            term.remove_uses(context);
+            match &mut context.values[term.0].value {
+                ValueDatum::Instruction(Instruction::ConditionalBranch {
+                    true_block,
+                    false_block,
+                    ..
+                }) => {
+                    for branch in [true_block, false_block] {
+                        if branch.block == *self {
+                            branch.args.remove(index);
+                        }
+                    }
+                }
+                ValueDatum::Instruction(Instruction::Branch(branch)) if branch.block == *self => {
+                    branch.args.remove(index);
+                }
+                _ => (),
+            }
+            term.update_uses(context);
+        }
+
+        context.blocks[self.0].args.remove(index);
+        for arg_idx in index..context.blocks[self.0].args.len() {
+            let arg = context.blocks[self.0].args[arg_idx];
+            if let ValueDatum::Argument(BlockArgument { idx, .. }) =
+                &mut context.values[arg.0].value
+            {
+                *idx -= 1;
+            }
+        }
+    }
+
     /// Get an iterator over this block's args.
     pub fn arg_iter<'a>(&'a self, context: &'a Context) -> impl Iterator<Item = &Value> {
         context.blocks[self.0].args.iter()
@@ -166,12 +211,17 @@ impl Block {
 
     /// Add `from_block` to the set of predecessors of this block.
     pub fn add_pred(&self, context: &mut Context, from_block: &Block) {
-        context.blocks[self.0].preds.insert(*from_block);
+        let preds = &mut context.blocks[self.0].preds;
+        if !preds.contains(from_block) {
+            preds.push(*from_block);
+        }
     }
 
     /// Remove `from_block` from the set of predecessors of this block.
     pub fn remove_pred(&self, context: &mut Context, from_block: &Block) {
-        context.blocks[self.0].preds.remove(from_block);
+        context.blocks[self.0]
+            .preds
+            .retain(|pred| pred != from_block);
     }
 
     /// Replace a `old_source` with `new_source` as a predecessor.
@@ -196,7 +246,8 @@ impl Block {
 
     /// Get a mut reference to the block terminator.
     ///
-    /// Returns `None` if block is empty.
+    /// Returns `None` if block is empty.  Any operand added through the reference must be recorded
+    /// with [`Value::update_uses`].
     pub fn get_terminator_mut<'a>(&self, context: &'a mut Context) -> Option<&'a mut Instruction> {
         context.blocks[self.0].instructions.last().and_then(|val| {
             // It's guaranteed to be an instruction value.
@@ -209,7 +260,7 @@ impl Block {
     }
 
     /// Get the CFG successors (and the parameters passed to them) of this block.
-    pub(super) fn successors<'a>(&'a self, context: &'a Context) -> Vec<BranchToWithArgs> {
+    pub fn successors<'a>(&'a self, context: &'a Context) -> Vec<BranchToWithArgs> {
         match self.get_terminator(context) {
             Some(Instruction::ConditionalBranch {
                 true_block,
@@ -231,7 +282,9 @@ impl Block {
             .map_or(vec![], |branch| branch.args.clone())
     }
 
-    /// For a particular successor (if it indeed is one), get a mut ref to parameters passed.
+    /// For a particular successor (if it indeed is one), get a mut ref to parameters passed.  Any
+    /// parameter added through the reference must be recorded with [`Value::update_uses`] on the
+    /// terminator.
     pub fn get_succ_params_mut<'a>(
         &'a self,
         context: &'a mut Context,
@@ -266,6 +319,10 @@ impl Block {
         new_params: Vec<Value>,
     ) {
         let mut modified = false;
+        let terminator = context.blocks[self.0].instructions.last().copied();
+        if let Some(terminator) = terminator {
+            terminator.remove_uses(context);
+        }
         if let Some(term) = self.get_terminator_mut(context) {
             match term {
                 Instruction::ConditionalBranch {
@@ -305,6 +362,9 @@ impl Block {
             old_succ.remove_pred(context, self);
             new_succ.add_pred(context, self);
         }
+        if let Some(terminator) = terminator {
+            terminator.update_uses(context);
+        }
     }
 
     /// Return whether this block is already terminated.  Checks if the final instruction, if it
@@ -340,14 +400,43 @@ impl Block {
     /// Remove an instruction from this block.
     ///
     /// **NOTE:** We must be very careful!  We mustn't remove the phi or the terminator.  Some
-    /// extra checks should probably be performed here to avoid corruption! Ideally we use get a
-    /// user/uses system implemented.  Using `Vec::remove()` is also O(n) which we may want to
-    /// avoid someday.
+    /// extra checks should probably be performed here to avoid corruption!  The instruction is no
+    /// longer a user of its operands, see [`Value::users`].  Using `Vec::remove()` is also O(n)
+    /// which we may want to avoid someday.
     pub fn remove_instruction(&self, context: &mut Context, instr_val: Value) {
         let ins = &mut context.blocks[self.0].instructions;
         if let Some(pos) = ins.iter().position(|iv| *iv == instr_val) {
             ins.remove(pos);
+            instr_val.remove_uses(context);
+            instr_val.set_block(context, None);
+        }
+    }
+
+    /// Insert `instructions`, which aren't in any block, at `idx` in this block.  They're moved
+    /// rather than created, so their uses are left as they are.
+    pub(crate) fn insert_instructions(
+        &self,
+        context: &mut Context,
+        idx: usize,
+        instructions: impl IntoIterator<Item = Value>,
+    ) {
+        let instructions = instructions.into_iter().collect::<Vec<_>>();
+        for ins in &instructions {
+            ins.set_block(context, Some(*self));
+        }
+        context.blocks[self.0]
+            .instructions
+            .splice(idx..idx, instructions);
+    }
+
+    /// Remove the instructions at and following `idx` from this block, to be inserted elsewhere
+    /// with [`Block::insert_instructions`].  They're still users of their operands.
+    pub(crate) fn take_instructions(&self, context: &mut Context, idx: usize) -> Vec<Value> {
+        let instructions = context.blocks[self.0].instructions.split_off(idx);
+        for ins in &instructions {
+            ins.set_block(context, None);
         }
+        instructions
     }
 
     /// Replace an instruction in this block with another.  Will return a ValueNotFound on error.
@@ -369,6 +458,9 @@ impl Block {
             )),
             Some(instr_val) => {
                 *instr_val = new_instr_val;
+                old_instr_val.remove_uses(context);
+                old_instr_val.set_block(context, None);
+                new_instr_val.set_block(context, Some(*self));
                 self.get_function(context).replace_value(
                     context,
                     old_instr_val,
@@ -416,10 +508,8 @@ impl Block {
             let new_block = function.create_block_after(context, self, None).unwrap();
 
             // Split the instructions at the index and append them to the new block.
-            let mut tail_instructions = context.blocks[self.0].instructions.split_off(split_idx);
-            context.blocks[new_block.0]
-                .instructions
-                .append(&mut tail_instructions);
+            let tail_instructions = self.take_instructions(context, split_idx);
+            new_block.insert_instructions(context, 0, tail_instructions);
 
             // If the terminator of the old block (now the new block) was a branch then we need to
             // update the destination block's preds.
@@ -447,6 +537,36 @@ impl Block {
         }
     }
 
+    /// Split the block into two after `instruction`.
+    ///
+    /// The instructions following `instruction`, including the terminator, are moved to a new
+    /// block placed after this one, and this block then branches to it.  The successors of the
+    /// terminator have their preds updated.  Values defined before the split still dominate their
+    /// uses after it, so the new block needs no arguments.  Returns both blocks.
+    pub fn split_after(
+        &self,
+        context: &mut Context,
+        instruction: Value,
+    ) -> Result<(Block, Block), IrError> {
+        let split_idx = context.blocks[self.0]
+            .instructions
+            .iter()
+            .position(|instr_val| *instr_val == instruction)
+            .ok_or_else(|| {
+                IrError::ValueNotFound(
+                    "Attempting to split a block after an instruction not in it".to_owned(),
+                )
+            })?;
+        if instruction.is_terminator(context) {
+            return Err(IrError::ValueNotFound(
+                "Attempting to split a block after its terminator".to_owned(),
+            ));
+        }
+        let (head, tail) = self.split_at(context, split_idx + 1);
+        head.ins(context).branch(tail, Vec::new());
+        Ok((head, tail))
+    }
+
     /// Return an instruction iterator for each instruction in this block.
     pub fn instruction_iter(&self, context: &Context) -> InstructionIterator {
         InstructionIterator::new(context, self)
@@ -0,0 +1,321 @@
+//! A high level builder for constructing IR outside of the Sway compiler.
+//!
+//! [`IrBuilder`] and [`FunctionBuilder`] wrap the lower level APIs of this crate behind a small
+//! surface which is intended to remain stable across releases, so that other frontends may target
+//! Sway-IR without depending on how the [`Context`] stores its contents.  Handles such as
+//! [`Function`], [`Block`], [`Value`] and [`Type`] are still returned, but only to be passed back
+//! into the builders or to be inspected via their own accessors.
+//!
+//! Instructions are appended to the current block of a [`FunctionBuilder`], and their types are
+//! derived from their operands wherever possible.  Nothing is checked while building; the finished
+//! IR is verified by [`IrBuilder::finish`].
+//!
+//! # Example
+//!
+//! ```
+//! use sway_ir::{BinaryOpKind, IrBuilder, Kind};
+//!
+//! let mut builder = IrBuilder::new(Kind::Script);
+//! let u64_ty = builder.u64_type();
+//! let main = builder.declare_entry_function("main", &[("x", u64_ty)], u64_ty);
+//!
+//! let mut main_builder = builder.function_builder(main);
+//! let x = main_builder.param(0);
+//! let one = main_builder.const_u64(1);
+//! let sum = main_builder.binary_op(BinaryOpKind::Add, x, one);
+//! main_builder.ret(sum);
+//!
+//! let context = builder.finish().unwrap();
+//! assert!(sway_ir::printer::to_string(&context).contains("add x, v0"));
+//! ```
+
+use crate::{
+    block::Block,
+    constant::Constant,
+    context::Context,
+    error::IrError,
+    function::Function,
+    instruction::{BinaryOpKind, Predicate},
+    irtype::Type,
+    module::{Kind, Module},
+    value::Value,
+};
+
+/// Builds a single [`Module`] in its own [`Context`].
+pub struct IrBuilder {
+    context: Context,
+    module: Module,
+}
+
+impl IrBuilder {
+    /// Return a new builder for an empty module of the given kind.
+    pub fn new(kind: Kind) -> Self {
+        let mut context = Context::default();
+        let module = Module::new(&mut context, kind);
+        IrBuilder { context, module }
+    }
+
+    /// Return the module being built.
+    pub fn module(&self) -> Module {
+        self.module
+    }
+
+    /// Return the context being built, e.g. for printing it.
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Verify the IR built so far and return its context.
+    pub fn finish(self) -> Result<Context, IrError> {
+        self.context.verify()
+    }
+
+    pub fn unit_type(&self) -> Type {
+        Type::get_unit(&self.context)
+    }
+
+    pub fn bool_type(&self) -> Type {
+        Type::get_bool(&self.context)
+    }
+
+    pub fn u64_type(&self) -> Type {
+        Type::get_uint64(&self.context)
+    }
+
+    /// Return the unsigned integer type of the given bit `width`.
+    pub fn uint_type(&mut self, width: u8) -> Type {
+        Type::new_uint(&mut self.context, width)
+    }
+
+    pub fn b256_type(&self) -> Type {
+        Type::get_b256(&self.context)
+    }
+
+    /// Return the type of strings of exactly `len` bytes.
+    pub fn string_type(&mut self, len: u64) -> Type {
+        Type::new_string(&mut self.context, len)
+    }
+
+    pub fn array_type(&mut self, elem_type: Type, len: u64) -> Type {
+        Type::new_array(&mut self.context, elem_type, len)
+    }
+
+    pub fn struct_type(&mut self, field_types: &[Type]) -> Type {
+        Type::new_struct(&mut self.context, field_types.to_vec())
+    }
+
+    pub fn union_type(&mut self, variant_types: &[Type]) -> Type {
+        Type::new_union(&mut self.context, variant_types.to_vec())
+    }
+
+    /// Declare a public function with the given parameters and return type.  Its body is built
+    /// with [`IrBuilder::function_builder`].
+    pub fn declare_function(
+        &mut self,
+        name: &str,
+        params: &[(&str, Type)],
+        return_type: Type,
+    ) -> Function {
+        self.new_function(name, params, return_type, false)
+    }
+
+    /// Declare a function which is an entry point of the module, e.g. `main` in a script.
+    pub fn declare_entry_function(
+        &mut self,
+        name: &str,
+        params: &[(&str, Type)],
+        return_type: Type,
+    ) -> Function {
+        self.new_function(name, params, return_type, true)
+    }
+
+    fn new_function(
+        &mut self,
+        name: &str,
+        params: &[(&str, Type)],
+        return_type: Type,
+        is_entry: bool,
+    ) -> Function {
+        let args = params
+            .iter()
+            .map(|(name, ty)| (name.to_string(), *ty, false, None))
+            .collect();
+        Function::new(
+            &mut self.context,
+            self.module,
+            name.to_string(),
+            args,
+            return_type,
+            None,
+            true,
+            is_entry,
+            None,
+        )
+    }
+
+    /// Return a builder for the body of `function`, positioned at the end of its entry block.
+    pub fn function_builder(&mut self, function: Function) -> FunctionBuilder<'_> {
+        let block = function.get_entry_block(&self.context);
+        FunctionBuilder {
+            context: &mut self.context,
+            function,
+            block,
+        }
+    }
+}
+
+/// Builds the body of a [`Function`] by appending instructions to its current block.
+pub struct FunctionBuilder<'a> {
+    context: &'a mut Context,
+    function: Function,
+    block: Block,
+}
+
+impl<'a> FunctionBuilder<'a> {
+    /// Return the function being built.
+    pub fn function(&self) -> Function {
+        self.function
+    }
+
+    /// Return the parameter of the function at `index`.
+    ///
+    /// Panics if the function has fewer parameters.
+    pub fn param(&self, index: usize) -> Value {
+        self.function
+            .args_iter(self.context)
+            .nth(index)
+            .map(|(_, value)| *value)
+            .expect("parameter index out of range")
+    }
+
+    /// Return the block which instructions are currently appended to.
+    pub fn current_block(&self) -> Block {
+        self.block
+    }
+
+    /// Append a new block to the function, without making it the current block.
+    pub fn create_block(&mut self, label: &str) -> Block {
+        self.function
+            .create_block(self.context, Some(label.to_string()))
+    }
+
+    /// Add a parameter of type `ty` to `block`, returning its value.  Branches to `block` must
+    /// pass an argument for each of its parameters.
+    pub fn add_block_param(&mut self, block: Block, ty: Type) -> Value {
+        let index = block.new_arg(self.context, ty, false);
+        block.get_arg(self.context, index).unwrap()
+    }
+
+    /// Append any following instructions to `block`.
+    pub fn switch_to_block(&mut self, block: Block) {
+        self.block = block;
+    }
+
+    /// Declare a local variable of type `ty`, returning a pointer to it.  The name is made unique
+    /// within the function if needed.
+    pub fn local(&mut self, name: &str, ty: Type) -> Value {
+        let local_var =
+            self.function
+                .new_unique_local_var(self.context, name.to_string(), ty, None);
+        self.block.ins(self.context).get_local(local_var)
+    }
+
+    pub fn const_unit(&mut self) -> Value {
+        Constant::get_unit(self.context)
+    }
+
+    pub fn const_bool(&mut self, value: bool) -> Value {
+        Constant::get_bool(self.context, value)
+    }
+
+    pub fn const_u64(&mut self, value: u64) -> Value {
+        Constant::get_uint(self.context, 64, value)
+    }
+
+    pub fn const_b256(&mut self, value: [u8; 32]) -> Value {
+        Constant::get_b256(self.context, value)
+    }
+
+    pub fn const_string(&mut self, value: &[u8]) -> Value {
+        Constant::get_string(self.context, value.to_vec())
+    }
+
+    pub fn binary_op(&mut self, op: BinaryOpKind, lhs: Value, rhs: Value) -> Value {
+        self.block.ins(self.context).binary_op(op, lhs, rhs)
+    }
+
+    pub fn cmp(&mut self, pred: Predicate, lhs: Value, rhs: Value) -> Value {
+        self.block.ins(self.context).cmp(pred, lhs, rhs)
+    }
+
+    /// Load the value pointed to by `ptr`.
+    pub fn load(&mut self, ptr: Value) -> Value {
+        self.block.ins(self.context).load(ptr)
+    }
+
+    /// Store `value` to where `ptr` points.
+    pub fn store(&mut self, ptr: Value, value: Value) -> Value {
+        self.block.ins(self.context).store(ptr, value)
+    }
+
+    /// Extract the field at `indices` from the struct or union `aggregate`.
+    pub fn extract_value(&mut self, aggregate: Value, indices: &[u64]) -> Value {
+        let ty = self.type_of(aggregate);
+        self.block
+            .ins(self.context)
+            .extract_value(aggregate, ty, indices.to_vec())
+    }
+
+    /// Return a copy of `aggregate` with the field at `indices` replaced by `value`.
+    pub fn insert_value(&mut self, aggregate: Value, value: Value, indices: &[u64]) -> Value {
+        let ty = self.type_of(aggregate);
+        self.block
+            .ins(self.context)
+            .insert_value(aggregate, ty, value, indices.to_vec())
+    }
+
+    pub fn call(&mut self, function: Function, args: &[Value]) -> Value {
+        self.block.ins(self.context).call(function, args)
+    }
+
+    /// Terminate the current block with a branch to `to_block`, passing `args` to its parameters.
+    pub fn branch(&mut self, to_block: Block, args: &[Value]) -> Value {
+        self.block.ins(self.context).branch(to_block, args.to_vec())
+    }
+
+    /// Terminate the current block with a branch to `true_block` if `cond` is true and to
+    /// `false_block` otherwise.
+    pub fn conditional_branch(
+        &mut self,
+        cond: Value,
+        true_block: Block,
+        true_args: &[Value],
+        false_block: Block,
+        false_args: &[Value],
+    ) -> Value {
+        self.block.ins(self.context).conditional_branch(
+            cond,
+            true_block,
+            false_block,
+            true_args.to_vec(),
+            false_args.to_vec(),
+        )
+    }
+
+    /// Terminate the current block by returning `value` from the function.
+    pub fn ret(&mut self, value: Value) -> Value {
+        let ty = self.function.get_return_type(self.context);
+        self.block.ins(self.context).ret(value, ty)
+    }
+
+    /// Terminate the current block by reverting with the `u64` `code`.
+    pub fn revert(&mut self, code: Value) -> Value {
+        self.block.ins(self.context).revert(code)
+    }
+
+    fn type_of(&self, value: Value) -> Type {
+        value
+            .get_type(self.context)
+            .unwrap_or_else(|| Type::get_unit(self.context))
+    }
+}
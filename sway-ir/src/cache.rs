@@ -0,0 +1,145 @@
+//! An on-disk cache of optimized functions, so that an incremental build only runs the passes
+//! over the functions which have changed.
+//!
+//! The optimized body of a function depends on its own content and on every function it may
+//! call, since any of them may be inlined into it.  So each function is keyed on its
+//! [`Function::content_hash`] along with those of the functions reachable from it in the call
+//! graph, the module's kind and configurables and a salt given by the compiler for the passes to
+//! be run.  Changing a function then misses the cache for it and its callers, while the rest of
+//! the module is still served from the cache.  The keys are SHA-256 digests, so that they're the
+//! same for every build of the compiler which is given the same salt.
+//!
+//! The entries are in the binary encoding of [`Function::to_bytes`], which is much quicker to load
+//! than the textual IR.  A function found in the cache has its body replaced by the cached one and
+//! is marked as [`Function::is_optimized`], so that the passes leave it alone.  The inliner may
+//! still decide differently for a caller once other callers of the same function change, so the
+//! output of an incremental build is equivalent to, though not necessarily identical with, that
+//! of a clean build.
+//!
+//! Only the passes are skipped: code generation still runs over the whole module, since the
+//! functions share registers, labels and the data section once lowered.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    fs, io,
+    path::PathBuf,
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{binary::decode_function_into, build_call_graph, printer, Context, Function};
+
+/// A directory of optimized functions, each in its own file named by its key.
+pub struct FunctionCache {
+    dir: PathBuf,
+    salt: String,
+}
+
+impl FunctionCache {
+    /// Return a cache in `dir`, where `salt` must identify the passes which produce the optimized
+    /// functions along with anything else, like the compiler version, which affects them.
+    pub fn new(dir: PathBuf, salt: String) -> Self {
+        FunctionCache { dir, salt }
+    }
+
+    /// Return the key of every function in `context`.
+    pub fn function_keys(&self, context: &Context) -> BTreeMap<Function, String> {
+        let mut keys = BTreeMap::new();
+        for module in context.module_iter() {
+            let mut module_hasher = Sha256::new();
+            module_hasher.update(self.salt.len().to_le_bytes());
+            module_hasher.update(&self.salt);
+            module_hasher.update(format!("{:?}", module.get_kind(context)));
+            module_hasher.update(printer::configurables_to_string(context, module));
+
+            let functions = module.function_iter(context).collect::<Vec<_>>();
+            let content_hashes = functions
+                .iter()
+                .map(|function| (*function, function.content_hash(context)))
+                .collect::<BTreeMap<_, _>>();
+            let call_graph = build_call_graph(context, &functions);
+            for function in functions {
+                let mut reachable = BTreeSet::from([function]);
+                let mut worklist = vec![function];
+                while let Some(caller) = worklist.pop() {
+                    for callee in call_graph.get(&caller).into_iter().flatten() {
+                        if reachable.insert(*callee) {
+                            worklist.push(*callee);
+                        }
+                    }
+                }
+                // The handles differ between builds, so the callees are ordered by their hashes.
+                let mut callee_hashes = reachable
+                    .into_iter()
+                    .filter(|callee| *callee != function)
+                    .map(|callee| content_hashes[&callee])
+                    .collect::<Vec<_>>();
+                callee_hashes.sort_unstable();
+
+                let mut hasher = module_hasher.clone();
+                hasher.update(content_hashes[&function].to_le_bytes());
+                for callee_hash in callee_hashes {
+                    hasher.update(callee_hash.to_le_bytes());
+                }
+                let key = hasher
+                    .finalize()
+                    .iter()
+                    .fold(String::new(), |mut key, byte| {
+                        let _ = write!(key, "{byte:02x}");
+                        key
+                    });
+                keys.insert(function, key);
+            }
+        }
+        keys
+    }
+
+    /// Replace the body of each function in `context` which is in the cache with its optimized
+    /// body, and mark it as optimized.  Returns the key of every function, to be given to
+    /// [`FunctionCache::store`] once the passes have run.
+    ///
+    /// An entry which can't be read or parsed is treated as missing.
+    pub fn load(&self, context: &mut Context) -> BTreeMap<Function, String> {
+        let keys = self.function_keys(context);
+        for (function, key) in &keys {
+            let Ok(contents) = fs::read(self.path(key)) else {
+                continue;
+            };
+            if decode_function_into(context, *function, &contents).is_ok() {
+                function.set_optimized(context, true);
+            }
+        }
+        keys
+    }
+
+    /// Write each function in `context` which was optimized by the passes to the cache, under the
+    /// key it had before them.  Functions which were removed by the passes are skipped, as are
+    /// those which were loaded from the cache.
+    pub fn store(&self, context: &Context, keys: &BTreeMap<Function, String>) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        for module in context.module_iter() {
+            for function in module.function_iter(context) {
+                let Some(key) = keys.get(&function) else {
+                    continue;
+                };
+                if function.is_optimized(context) {
+                    continue;
+                }
+                // Write a temporary file first so that the entry is never seen half written.
+                let Ok(bytes) = function.to_bytes(context) else {
+                    continue;
+                };
+                let path = self.path(key);
+                let tmp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+                fs::write(&tmp_path, bytes)?;
+                fs::rename(&tmp_path, &path)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.bin"))
+    }
+}
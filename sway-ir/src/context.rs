@@ -7,12 +7,14 @@
 //! It is passed around as a mutable reference to many of the Sway-IR APIs.
 
 use generational_arena::Arena;
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 
 use crate::{
-    asm::AsmBlockContent, block::BlockContent, function::FunctionContent,
-    local_var::LocalVarContent, metadata::Metadatum, module::ModuleContent, module::ModuleIterator,
-    value::ValueContent, Type, TypeContent,
+    asm::AsmBlockContent, block::BlockContent, function::FunctionContent, instruction::Instruction,
+    local_var::LocalVarContent, metadata::AttachmentPolicy, metadata::MetadataIndex,
+    metadata::Metadatum, module::ModuleContent, module::ModuleIterator,
+    optimize::inline::InlinePolicy, pattern::Rule, symbol::Interner, value::ValueContent,
+    value::ValueDatum, Type, TypeContent, Value,
 };
 
 /// The main IR context handle.
@@ -24,13 +26,26 @@ pub struct Context {
     pub(crate) functions: Arena<FunctionContent>,
     pub(crate) blocks: Arena<BlockContent>,
     pub(crate) values: Arena<ValueContent>,
+    /// The instructions using each value as an operand.  See [`Value::users`].
+    pub(crate) users: FxHashMap<Value, Vec<Value>>,
     pub(crate) local_vars: Arena<LocalVarContent>,
     pub(crate) types: Arena<TypeContent>,
     pub(crate) type_map: FxHashMap<TypeContent, Type>,
     pub(crate) asm_blocks: Arena<AsmBlockContent>,
     pub(crate) metadata: Arena<Metadatum>,
+    pub(crate) symbols: Interner,
+    pub(crate) attachment_policies: FxHashMap<String, AttachmentPolicy>,
+    pub(crate) rewrite_rules: Vec<Rule>,
+    pub(crate) inline_policy: InlinePolicy,
+    pass_fuel: Option<u64>,
+    remaining_fuel: Option<u64>,
+    out_of_fuel: bool,
+    pass_counters: FxHashMap<&'static str, u64>,
 
     next_unique_sym_tag: u64,
+    /// The IDs of the next coverage point and branch, unique across all modules.
+    pub(crate) next_coverage_point_id: u64,
+    pub(crate) next_coverage_branch_id: u64,
 }
 
 impl Default for Context {
@@ -40,12 +55,23 @@ impl Default for Context {
             functions: Default::default(),
             blocks: Default::default(),
             values: Default::default(),
+            users: Default::default(),
             local_vars: Default::default(),
             types: Default::default(),
             type_map: Default::default(),
             asm_blocks: Default::default(),
             metadata: Default::default(),
+            symbols: Default::default(),
+            attachment_policies: Default::default(),
+            rewrite_rules: Default::default(),
+            inline_policy: Default::default(),
+            pass_fuel: Default::default(),
+            remaining_fuel: Default::default(),
+            out_of_fuel: Default::default(),
+            pass_counters: Default::default(),
             next_unique_sym_tag: Default::default(),
+            next_coverage_point_id: Default::default(),
+            next_coverage_branch_id: Default::default(),
         };
         Type::create_basic_types(&mut def);
         def
@@ -65,6 +91,175 @@ impl Context {
         format!("anon_{}", self.get_unique_id())
     }
 
+    /// Set how the optimization passes treat the attachments in `namespace`.
+    pub fn set_attachment_policy(&mut self, namespace: &str, policy: AttachmentPolicy) {
+        self.attachment_policies
+            .insert(namespace.to_owned(), policy);
+    }
+
+    /// Get how the optimization passes treat the attachments in `namespace`.
+    pub fn get_attachment_policy(&self, namespace: &str) -> AttachmentPolicy {
+        self.attachment_policies
+            .get(namespace)
+            .copied()
+            .unwrap_or(AttachmentPolicy::Keep)
+    }
+
+    /// Set the rules applied by the `rewrite` pass.
+    pub fn set_rewrite_rules(&mut self, rules: Vec<Rule>) {
+        self.rewrite_rules = rules;
+    }
+
+    /// Get the rules applied by the `rewrite` pass.
+    pub fn get_rewrite_rules(&self) -> &[Rule] {
+        &self.rewrite_rules
+    }
+
+    /// Set the heuristics used by the `inline_non_predicate_module` pass.
+    pub fn set_inline_policy(&mut self, policy: InlinePolicy) {
+        self.inline_policy = policy;
+    }
+
+    /// Get the heuristics used by the `inline_non_predicate_module` pass.
+    pub fn get_inline_policy(&self) -> &InlinePolicy {
+        &self.inline_policy
+    }
+
+    /// Set the fuel each transform pass is given for each function, or `None` for no limit.
+    ///
+    /// Iterative passes consume a unit of fuel for each change they make and stop once it runs
+    /// out, leaving the function correct but less optimised.  See [Context::consume_fuel].
+    pub fn set_pass_fuel(&mut self, fuel: Option<u64>) {
+        self.pass_fuel = fuel;
+    }
+
+    /// Consume a unit of the current pass's fuel, returning `false` if there is none left, in
+    /// which case the pass should stop making changes.
+    pub fn consume_fuel(&mut self) -> bool {
+        match &mut self.remaining_fuel {
+            None => true,
+            Some(0) => {
+                self.out_of_fuel = true;
+                false
+            }
+            Some(fuel) => {
+                *fuel -= 1;
+                true
+            }
+        }
+    }
+
+    /// Start metering the fuel of a pass, giving it a full tank.
+    pub(crate) fn refuel(&mut self) {
+        self.remaining_fuel = self.pass_fuel;
+        self.out_of_fuel = false;
+    }
+
+    /// Stop metering the fuel of a pass, returning whether it ran out.
+    pub(crate) fn stop_fuel(&mut self) -> bool {
+        self.remaining_fuel = None;
+        std::mem::take(&mut self.out_of_fuel)
+    }
+
+    /// Add `n` to the counter `name` of the current pass, e.g., the number of instructions it has
+    /// removed.  The counters are collected into [crate::PassStatistics] by the pass manager.
+    pub fn add_pass_statistic(&mut self, name: &'static str, n: u64) {
+        *self.pass_counters.entry(name).or_default() += n;
+    }
+
+    /// Take the counters added by the pass which has just run.
+    pub(crate) fn take_pass_statistics(&mut self) -> FxHashMap<&'static str, u64> {
+        std::mem::take(&mut self.pass_counters)
+    }
+
+    /// Remove the metadata which are no longer referred to, directly or through other metadata, by
+    /// any function, block argument, instruction or operand in a module, and return how many were
+    /// removed.
+    ///
+    /// The values, functions and asm blocks which are no longer in a module lose their metadata
+    /// too, as they can't be reached any more.
+    pub fn gc_metadata(&mut self) -> usize {
+        let mut live_values = FxHashSet::default();
+        let mut live_functions = FxHashSet::default();
+        let mut live_asm_blocks = FxHashSet::default();
+        for module in self.modules.iter().map(|(_, module)| module) {
+            live_values.extend(module.global_constants.values().copied());
+            live_values.extend(module.global_configurable.values().copied());
+            for function in &module.functions {
+                live_functions.insert(function.0);
+                let function = &self.functions[function.0];
+                live_values.extend(function.arguments.iter().map(|(_, arg)| *arg));
+                for block in &function.blocks {
+                    let block = &self.blocks[block.0];
+                    live_values.extend(block.args.iter().copied());
+                    for ins in &block.instructions {
+                        live_values.insert(*ins);
+                        if let ValueDatum::Instruction(ins) = &self.values[ins.0].value {
+                            live_values.extend(ins.get_operands());
+                            if let Instruction::AsmBlock(asm, _) = ins {
+                                live_asm_blocks.insert(asm.0);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // Drop the references from whatever is unreachable, and gather those from the rest.
+        let mut worklist = Vec::new();
+        for (idx, value) in self.values.iter_mut() {
+            if live_values.contains(&Value(idx)) {
+                worklist.extend(value.metadata);
+            } else {
+                value.metadata = None;
+            }
+        }
+        for (idx, function) in self.functions.iter_mut() {
+            if live_functions.contains(&idx) {
+                worklist.extend(function.metadata);
+            } else {
+                function.metadata = None;
+            }
+        }
+        for (idx, asm) in self.asm_blocks.iter_mut() {
+            for asm_ins in &mut asm.body {
+                if live_asm_blocks.contains(&idx) {
+                    worklist.extend(asm_ins.metadata);
+                } else {
+                    asm_ins.metadata = None;
+                }
+            }
+        }
+
+        // Mark everything the live references lead to.
+        let mut live_metadata = FxHashSet::default();
+        while let Some(md_idx) = worklist.pop() {
+            if !live_metadata.insert(md_idx) {
+                continue;
+            }
+            let mut mds = vec![&self.metadata[md_idx.0]];
+            while let Some(md) = mds.pop() {
+                match md {
+                    Metadatum::Integer(_) | Metadatum::String(_) => (),
+                    Metadatum::Index(idx) => worklist.push(*idx),
+                    Metadatum::Struct(_, fields) => mds.extend(fields),
+                    Metadatum::List(idcs) => worklist.extend(idcs),
+                }
+            }
+        }
+
+        let dead_metadata = self
+            .metadata
+            .iter()
+            .map(|(idx, _)| idx)
+            .filter(|idx| !live_metadata.contains(&MetadataIndex(*idx)))
+            .collect::<Vec<_>>();
+        for idx in &dead_metadata {
+            self.metadata.remove(*idx);
+        }
+        dead_metadata.len()
+    }
+
     /// Get a globally unique symbol id.
     pub fn get_unique_id(&mut self) -> u64 {
         let sym = self.next_unique_sym_tag;
@@ -0,0 +1,103 @@
+//! Mapping IR back to the Sway source it was generated from.
+//!
+//! Values and functions are located in the source by their [`SourceSpan`] metadata.  Not every
+//! instruction has a span of its own though, particularly those created by the optimization passes,
+//! so [`DebugInfo::nearest_span`] falls back to the span of the closest preceding instruction in
+//! the same block, and then to the span of the function.
+
+use std::sync::Arc;
+
+use rustc_hash::FxHashMap;
+
+use crate::{context::Context, metadata::SourceSpan, value::Value};
+
+/// Source locations for the values of a [`Context`].
+///
+/// The sources are read from their files as needed and kept for later queries, unless they were
+/// given already with [`DebugInfo::add_source`].
+pub struct DebugInfo<'a> {
+    context: &'a Context,
+    // The span inherited by each instruction without a span of its own.
+    inherited_spans: FxHashMap<Value, SourceSpan>,
+    // The sources by path, or `None` for those which couldn't be read.
+    sources: FxHashMap<String, Option<Arc<str>>>,
+}
+
+impl<'a> DebugInfo<'a> {
+    pub fn new(context: &'a Context) -> Self {
+        let mut inherited_spans = FxHashMap::default();
+        for module in context.module_iter() {
+            for function in module.function_iter(context) {
+                let function_span = function.get_span(context);
+                for block in function.block_iter(context) {
+                    let mut span = function_span;
+                    for ins in block.instruction_iter(context) {
+                        match ins.get_span(context) {
+                            Some(ins_span) => span = Some(ins_span),
+                            None => {
+                                if let Some(span) = span {
+                                    inherited_spans.insert(ins, span);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        DebugInfo {
+            context,
+            inherited_spans,
+            sources: FxHashMap::default(),
+        }
+    }
+
+    /// Use `source` for the file at `path` rather than reading it.
+    pub fn add_source<S: Into<Arc<str>>>(&mut self, path: &str, source: S) {
+        self.sources.insert(path.to_owned(), Some(source.into()));
+    }
+
+    /// Return the span of `value` itself, if it has one.
+    pub fn span(&self, value: Value) -> Option<SourceSpan> {
+        value.get_span(self.context)
+    }
+
+    /// Return the span of `value`, or failing that the span it inherits from the instructions
+    /// before it or its function.
+    pub fn nearest_span(&self, value: Value) -> Option<SourceSpan> {
+        self.span(value)
+            .or_else(|| self.inherited_spans.get(&value).copied())
+    }
+
+    /// Return the path of the source file of `span`.
+    pub fn path(&self, span: &SourceSpan) -> Option<&'a str> {
+        span.path(self.context)
+    }
+
+    /// Return the text of the source within `span`, or `None` if the source couldn't be read or
+    /// the span is out of its bounds.
+    pub fn source_text(&mut self, span: &SourceSpan) -> Option<&str> {
+        let source = self.source(span)?;
+        source.get(span.start as usize..span.end as usize)
+    }
+
+    /// Return the one-based line and column of the start of `span`, with columns counted in
+    /// characters.
+    pub fn line_col(&mut self, span: &SourceSpan) -> Option<(usize, usize)> {
+        let source = self.source(span)?;
+        let before = source.get(..span.start as usize)?;
+        let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+        Some((
+            before.matches('\n').count() + 1,
+            before[line_start..].chars().count() + 1,
+        ))
+    }
+
+    fn source(&mut self, span: &SourceSpan) -> Option<&str> {
+        let path = self.path(span)?;
+        self.sources
+            .entry(path.to_owned())
+            .or_insert_with(|| std::fs::read_to_string(path).ok().map(Arc::from))
+            .as_deref()
+    }
+}
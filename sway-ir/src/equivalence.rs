@@ -0,0 +1,647 @@
+//! Structural comparison of two [`Context`]s.
+//!
+//! Arena handles are allocated in whatever order the IR happened to be built, so two contexts
+//! holding the same program, e.g., one parsed from the printed form of the other, will almost
+//! never agree on them.  [`Context::eq_modulo_handles`] instead pairs up the modules, functions,
+//! blocks and values of each context by their position and compares what they hold.
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    asm::{AsmArg, AsmBlock},
+    block::Block,
+    constant::{Constant, ConstantValue},
+    context::Context,
+    function::Function,
+    instruction::{BranchToWithArgs, FuelVmInstruction, Instruction},
+    irtype::Type,
+    local_var::LocalVar,
+    metadata::{MetadataIndex, Metadatum},
+    module::Module,
+    value::{Value, ValueDatum},
+    BlockArgument,
+};
+
+impl Context {
+    /// Whether `other` holds the same modules as this context, ignoring the arena handles used to
+    /// refer to them.
+    ///
+    /// Functions, blocks, locals and values must appear in the same order, with the same names,
+    /// labels and metadata.  Metadata is compared by content rather than by index.  The global
+    /// constants of a module are only a cache for IR generation and aren't compared.
+    pub fn eq_modulo_handles(&self, other: &Context) -> bool {
+        let lhs_modules = self.module_iter().collect::<Vec<_>>();
+        let rhs_modules = other.module_iter().collect::<Vec<_>>();
+        if lhs_modules.len() != rhs_modules.len() {
+            return false;
+        }
+
+        let mut handles = HandleMap {
+            lhs: self,
+            rhs: other,
+            functions: FxHashMap::default(),
+            blocks: FxHashMap::default(),
+            locals: FxHashMap::default(),
+            values: FxHashMap::default(),
+        };
+        lhs_modules
+            .iter()
+            .zip(&rhs_modules)
+            .all(|(lhs, rhs)| handles.pair_module(*lhs, *rhs))
+            && lhs_modules
+                .iter()
+                .zip(&rhs_modules)
+                .all(|(lhs, rhs)| handles.module_eq(*lhs, *rhs))
+    }
+}
+
+/// The handles of one context paired with those of the other.
+struct HandleMap<'a> {
+    lhs: &'a Context,
+    rhs: &'a Context,
+    functions: FxHashMap<Function, Function>,
+    blocks: FxHashMap<Block, Block>,
+    locals: FxHashMap<LocalVar, LocalVar>,
+    values: FxHashMap<Value, Value>,
+}
+
+impl<'a> HandleMap<'a> {
+    /// Pair up the functions of the modules and everything they contain, returning whether their
+    /// shapes agree.
+    fn pair_module(&mut self, lhs: Module, rhs: Module) -> bool {
+        let lhs_functions = &self.lhs.modules[lhs.0].functions;
+        let rhs_functions = &self.rhs.modules[rhs.0].functions;
+        if lhs_functions.len() != rhs_functions.len() {
+            return false;
+        }
+        for (lhs_function, rhs_function) in lhs_functions.iter().zip(rhs_functions) {
+            self.functions.insert(*lhs_function, *rhs_function);
+
+            let lhs_content = &self.lhs.functions[lhs_function.0];
+            let rhs_content = &self.rhs.functions[rhs_function.0];
+            if lhs_content.blocks.len() != rhs_content.blocks.len()
+                || lhs_content.local_storage.len() != rhs_content.local_storage.len()
+            {
+                return false;
+            }
+            self.locals.extend(
+                lhs_content
+                    .local_storage
+                    .values()
+                    .copied()
+                    .zip(rhs_content.local_storage.values().copied()),
+            );
+            for (lhs_block, rhs_block) in lhs_content.blocks.iter().zip(&rhs_content.blocks) {
+                self.blocks.insert(*lhs_block, *rhs_block);
+
+                let lhs_block_content = &self.lhs.blocks[lhs_block.0];
+                let rhs_block_content = &self.rhs.blocks[rhs_block.0];
+                if lhs_block_content.args.len() != rhs_block_content.args.len()
+                    || lhs_block_content.instructions.len() != rhs_block_content.instructions.len()
+                {
+                    return false;
+                }
+                self.values.extend(
+                    lhs_block_content
+                        .args
+                        .iter()
+                        .chain(&lhs_block_content.instructions)
+                        .copied()
+                        .zip(
+                            rhs_block_content
+                                .args
+                                .iter()
+                                .chain(&rhs_block_content.instructions)
+                                .copied(),
+                        ),
+                );
+            }
+        }
+        true
+    }
+
+    fn module_eq(&self, lhs: Module, rhs: Module) -> bool {
+        let lhs_content = &self.lhs.modules[lhs.0];
+        let rhs_content = &self.rhs.modules[rhs.0];
+        lhs_content.kind == rhs_content.kind
+            && lhs_content.global_configurable.len() == rhs_content.global_configurable.len()
+            && lhs_content
+                .global_configurable
+                .iter()
+                .zip(&rhs_content.global_configurable)
+                .all(|((lhs_name, lhs_value), (rhs_name, rhs_value))| {
+                    lhs_name == rhs_name && self.value_eq(*lhs_value, *rhs_value)
+                })
+            && lhs_content
+                .functions
+                .iter()
+                .zip(&rhs_content.functions)
+                .all(|(lhs_function, rhs_function)| self.function_eq(*lhs_function, *rhs_function))
+    }
+
+    fn function_eq(&self, lhs: Function, rhs: Function) -> bool {
+        let lhs_content = &self.lhs.functions[lhs.0];
+        let rhs_content = &self.rhs.functions[rhs.0];
+        lhs_content.name.as_str(self.lhs) == rhs_content.name.as_str(self.rhs)
+            && lhs_content.is_public == rhs_content.is_public
+            && lhs_content.is_entry == rhs_content.is_entry
+            && lhs_content.selector == rhs_content.selector
+            && lhs_content.attributes == rhs_content.attributes
+            && self.metadata_eq(lhs_content.metadata, rhs_content.metadata)
+            && self.type_eq(lhs_content.return_type, rhs_content.return_type)
+            && lhs_content.arguments.len() == rhs_content.arguments.len()
+            && lhs_content
+                .arguments
+                .iter()
+                .zip(&rhs_content.arguments)
+                .all(|((lhs_name, lhs_arg), (rhs_name, rhs_arg))| {
+                    lhs_name == rhs_name && self.values.get(lhs_arg) == Some(rhs_arg)
+                })
+            && lhs_content
+                .local_storage
+                .iter()
+                .zip(&rhs_content.local_storage)
+                .all(|((lhs_name, lhs_local), (rhs_name, rhs_local))| {
+                    let lhs_local = &self.lhs.local_vars[lhs_local.0];
+                    let rhs_local = &self.rhs.local_vars[rhs_local.0];
+                    lhs_name == rhs_name
+                        && self.type_eq(lhs_local.ty, rhs_local.ty)
+                        && match (&lhs_local.initializer, &rhs_local.initializer) {
+                            (Some(lhs_init), Some(rhs_init)) => {
+                                self.constant_eq(lhs_init, rhs_init)
+                            }
+                            (None, None) => true,
+                            _ => false,
+                        }
+                })
+            && lhs_content
+                .blocks
+                .iter()
+                .zip(&rhs_content.blocks)
+                .all(|(lhs_block, rhs_block)| self.block_eq(*lhs_block, *rhs_block))
+    }
+
+    fn block_eq(&self, lhs: Block, rhs: Block) -> bool {
+        let lhs_content = &self.lhs.blocks[lhs.0];
+        let rhs_content = &self.rhs.blocks[rhs.0];
+        lhs_content.label.as_str(self.lhs) == rhs_content.label.as_str(self.rhs)
+            && lhs_content.preds.len() == rhs_content.preds.len()
+            && lhs_content
+                .preds
+                .iter()
+                .all(|pred| rhs_content.preds.contains(&self.blocks[pred]))
+            && lhs_content
+                .args
+                .iter()
+                .chain(&lhs_content.instructions)
+                .zip(rhs_content.args.iter().chain(&rhs_content.instructions))
+                .all(|(lhs_value, rhs_value)| self.value_content_eq(*lhs_value, *rhs_value))
+    }
+
+    /// Whether `rhs` is where `lhs` was paired with, or for constants which aren't paired, has
+    /// the same content.
+    fn value_eq(&self, lhs: Value, rhs: Value) -> bool {
+        match self.values.get(&lhs) {
+            Some(paired) => *paired == rhs,
+            None => self.value_content_eq(lhs, rhs),
+        }
+    }
+
+    fn value_content_eq(&self, lhs: Value, rhs: Value) -> bool {
+        let lhs_content = &self.lhs.values[lhs.0];
+        let rhs_content = &self.rhs.values[rhs.0];
+        self.metadata_eq(lhs_content.metadata, rhs_content.metadata)
+            && match (&lhs_content.value, &rhs_content.value) {
+                (
+                    ValueDatum::Argument(BlockArgument {
+                        block: lhs_block,
+                        idx: lhs_idx,
+                        ty: lhs_ty,
+                        by_ref: lhs_by_ref,
+                    }),
+                    ValueDatum::Argument(BlockArgument {
+                        block: rhs_block,
+                        idx: rhs_idx,
+                        ty: rhs_ty,
+                        by_ref: rhs_by_ref,
+                    }),
+                ) => {
+                    self.block_handle_eq(*lhs_block, *rhs_block)
+                        && lhs_idx == rhs_idx
+                        && self.type_eq(*lhs_ty, *rhs_ty)
+                        && lhs_by_ref == rhs_by_ref
+                }
+                (ValueDatum::Configurable(lhs), ValueDatum::Configurable(rhs))
+                | (ValueDatum::Constant(lhs), ValueDatum::Constant(rhs)) => {
+                    self.constant_eq(lhs, rhs)
+                }
+                (ValueDatum::Instruction(lhs), ValueDatum::Instruction(rhs)) => {
+                    self.instruction_eq(lhs, rhs)
+                }
+                _ => false,
+            }
+    }
+
+    fn values_eq(&self, lhs: &[Value], rhs: &[Value]) -> bool {
+        lhs.len() == rhs.len()
+            && lhs
+                .iter()
+                .zip(rhs)
+                .all(|(lhs, rhs)| self.value_eq(*lhs, *rhs))
+    }
+
+    fn block_handle_eq(&self, lhs: Block, rhs: Block) -> bool {
+        self.blocks.get(&lhs) == Some(&rhs)
+    }
+
+    fn branch_eq(&self, lhs: &BranchToWithArgs, rhs: &BranchToWithArgs) -> bool {
+        self.block_handle_eq(lhs.block, rhs.block) && self.values_eq(&lhs.args, &rhs.args)
+    }
+
+    fn type_eq(&self, lhs: Type, rhs: Type) -> bool {
+        lhs.as_string(self.lhs) == rhs.as_string(self.rhs)
+    }
+
+    fn constant_eq(&self, lhs: &Constant, rhs: &Constant) -> bool {
+        self.type_eq(lhs.ty, rhs.ty)
+            && match (&lhs.value, &rhs.value) {
+                (ConstantValue::Undef, ConstantValue::Undef)
+                | (ConstantValue::Unit, ConstantValue::Unit) => true,
+                (ConstantValue::Bool(lhs), ConstantValue::Bool(rhs)) => lhs == rhs,
+                (ConstantValue::Uint(lhs), ConstantValue::Uint(rhs)) => lhs == rhs,
+                (ConstantValue::B256(lhs), ConstantValue::B256(rhs)) => lhs == rhs,
+                (ConstantValue::String(lhs), ConstantValue::String(rhs)) => lhs == rhs,
+                (ConstantValue::Array(lhs), ConstantValue::Array(rhs))
+                | (ConstantValue::Struct(lhs), ConstantValue::Struct(rhs)) => {
+                    lhs.len() == rhs.len()
+                        && lhs
+                            .iter()
+                            .zip(rhs)
+                            .all(|(lhs, rhs)| self.constant_eq(lhs, rhs))
+                }
+                _ => false,
+            }
+    }
+
+    fn metadata_eq(&self, lhs: Option<MetadataIndex>, rhs: Option<MetadataIndex>) -> bool {
+        match (lhs, rhs) {
+            (Some(lhs), Some(rhs)) => {
+                self.metadatum_eq(&self.lhs.metadata[lhs.0], &self.rhs.metadata[rhs.0])
+            }
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    fn metadatum_eq(&self, lhs: &Metadatum, rhs: &Metadatum) -> bool {
+        match (lhs, rhs) {
+            (Metadatum::Integer(lhs), Metadatum::Integer(rhs)) => lhs == rhs,
+            (Metadatum::Index(lhs), Metadatum::Index(rhs)) => {
+                self.metadata_eq(Some(*lhs), Some(*rhs))
+            }
+            (Metadatum::String(lhs), Metadatum::String(rhs)) => lhs == rhs,
+            (Metadatum::Struct(lhs_tag, lhs), Metadatum::Struct(rhs_tag, rhs)) => {
+                lhs_tag == rhs_tag
+                    && lhs.len() == rhs.len()
+                    && lhs
+                        .iter()
+                        .zip(rhs)
+                        .all(|(lhs, rhs)| self.metadatum_eq(lhs, rhs))
+            }
+            (Metadatum::List(lhs), Metadatum::List(rhs)) => {
+                lhs.len() == rhs.len()
+                    && lhs
+                        .iter()
+                        .zip(rhs)
+                        .all(|(lhs, rhs)| self.metadata_eq(Some(*lhs), Some(*rhs)))
+            }
+            _ => false,
+        }
+    }
+
+    fn asm_eq(
+        &self,
+        lhs: &AsmBlock,
+        lhs_args: &[AsmArg],
+        rhs: &AsmBlock,
+        rhs_args: &[AsmArg],
+    ) -> bool {
+        let lhs_content = lhs.get_content(self.lhs);
+        let rhs_content = rhs.get_content(self.rhs);
+        let idents_eq = |lhs: &[sway_types::Ident], rhs: &[sway_types::Ident]| {
+            lhs.len() == rhs.len()
+                && lhs
+                    .iter()
+                    .zip(rhs)
+                    .all(|(lhs, rhs)| lhs.as_str() == rhs.as_str())
+        };
+        idents_eq(&lhs_content.args_names, &rhs_content.args_names)
+            && self.type_eq(lhs_content.return_type, rhs_content.return_type)
+            && lhs_content.return_name.as_ref().map(|name| name.as_str())
+                == rhs_content.return_name.as_ref().map(|name| name.as_str())
+            && lhs_content.body.len() == rhs_content.body.len()
+            && lhs_content
+                .body
+                .iter()
+                .zip(&rhs_content.body)
+                .all(|(lhs, rhs)| {
+                    lhs.name.as_str() == rhs.name.as_str()
+                        && idents_eq(&lhs.args, &rhs.args)
+                        && lhs.immediate.as_ref().map(|imm| imm.as_str())
+                            == rhs.immediate.as_ref().map(|imm| imm.as_str())
+                        && self.metadata_eq(lhs.metadata, rhs.metadata)
+                })
+            && lhs_args.len() == rhs_args.len()
+            && lhs_args.iter().zip(rhs_args).all(|(lhs, rhs)| {
+                lhs.name.as_str() == rhs.name.as_str()
+                    && match (lhs.initializer, rhs.initializer) {
+                        (Some(lhs), Some(rhs)) => self.value_eq(lhs, rhs),
+                        (None, None) => true,
+                        _ => false,
+                    }
+            })
+    }
+
+    fn instruction_eq(&self, lhs: &Instruction, rhs: &Instruction) -> bool {
+        use Instruction::*;
+        match (lhs, rhs) {
+            (AddrOf(lhs), AddrOf(rhs)) | (Load(lhs), Load(rhs)) => self.value_eq(*lhs, *rhs),
+            (AsmBlock(lhs, lhs_args), AsmBlock(rhs, rhs_args)) => {
+                self.asm_eq(lhs, lhs_args, rhs, rhs_args)
+            }
+            (
+                BinaryOp {
+                    op: lhs_op,
+                    arg1: lhs_arg1,
+                    arg2: lhs_arg2,
+                },
+                BinaryOp {
+                    op: rhs_op,
+                    arg1: rhs_arg1,
+                    arg2: rhs_arg2,
+                },
+            ) => {
+                lhs_op == rhs_op
+                    && self.value_eq(*lhs_arg1, *rhs_arg1)
+                    && self.value_eq(*lhs_arg2, *rhs_arg2)
+            }
+            (BitCast(lhs, lhs_ty), BitCast(rhs, rhs_ty))
+            | (IntToPtr(lhs, lhs_ty), IntToPtr(rhs, rhs_ty))
+            | (Ret(lhs, lhs_ty), Ret(rhs, rhs_ty)) => {
+                self.value_eq(*lhs, *rhs) && self.type_eq(*lhs_ty, *rhs_ty)
+            }
+            (Branch(lhs), Branch(rhs)) => self.branch_eq(lhs, rhs),
+            (Call(lhs, lhs_args), Call(rhs, rhs_args)) => {
+                self.functions.get(lhs) == Some(rhs) && self.values_eq(lhs_args, rhs_args)
+            }
+            (CastPtr(lhs, lhs_ty, lhs_offs), CastPtr(rhs, rhs_ty, rhs_offs)) => {
+                self.value_eq(*lhs, *rhs) && self.type_eq(*lhs_ty, *rhs_ty) && lhs_offs == rhs_offs
+            }
+            (Cmp(lhs_pred, lhs_a, lhs_b), Cmp(rhs_pred, rhs_a, rhs_b)) => {
+                lhs_pred == rhs_pred
+                    && self.value_eq(*lhs_a, *rhs_a)
+                    && self.value_eq(*lhs_b, *rhs_b)
+            }
+            (
+                ConditionalBranch {
+                    cond_value: lhs_cond,
+                    true_block: lhs_true,
+                    false_block: lhs_false,
+                },
+                ConditionalBranch {
+                    cond_value: rhs_cond,
+                    true_block: rhs_true,
+                    false_block: rhs_false,
+                },
+            ) => {
+                self.value_eq(*lhs_cond, *rhs_cond)
+                    && self.branch_eq(lhs_true, rhs_true)
+                    && self.branch_eq(lhs_false, rhs_false)
+            }
+            (
+                ContractCall {
+                    return_type: lhs_ty,
+                    name: lhs_name,
+                    params: lhs_params,
+                    coins: lhs_coins,
+                    asset_id: lhs_asset_id,
+                    gas: lhs_gas,
+                },
+                ContractCall {
+                    return_type: rhs_ty,
+                    name: rhs_name,
+                    params: rhs_params,
+                    coins: rhs_coins,
+                    asset_id: rhs_asset_id,
+                    gas: rhs_gas,
+                },
+            ) => {
+                self.type_eq(*lhs_ty, *rhs_ty)
+                    && lhs_name == rhs_name
+                    && self.values_eq(
+                        &[*lhs_params, *lhs_coins, *lhs_asset_id, *lhs_gas],
+                        &[*rhs_params, *rhs_coins, *rhs_asset_id, *rhs_gas],
+                    )
+            }
+            (
+                ExtractElement {
+                    array: lhs_array,
+                    ty: lhs_ty,
+                    index_val: lhs_idx,
+                },
+                ExtractElement {
+                    array: rhs_array,
+                    ty: rhs_ty,
+                    index_val: rhs_idx,
+                },
+            ) => {
+                self.value_eq(*lhs_array, *rhs_array)
+                    && self.type_eq(*lhs_ty, *rhs_ty)
+                    && self.value_eq(*lhs_idx, *rhs_idx)
+            }
+            (
+                ExtractValue {
+                    aggregate: lhs_aggregate,
+                    ty: lhs_ty,
+                    indices: lhs_indices,
+                },
+                ExtractValue {
+                    aggregate: rhs_aggregate,
+                    ty: rhs_ty,
+                    indices: rhs_indices,
+                },
+            ) => {
+                self.value_eq(*lhs_aggregate, *rhs_aggregate)
+                    && self.type_eq(*lhs_ty, *rhs_ty)
+                    && lhs_indices == rhs_indices
+            }
+            (FuelVm(lhs), FuelVm(rhs)) => self.fuel_vm_instruction_eq(lhs, rhs),
+            (GetLocal(lhs), GetLocal(rhs)) => self.locals.get(lhs) == Some(rhs),
+            (
+                InsertElement {
+                    array: lhs_array,
+                    ty: lhs_ty,
+                    value: lhs_value,
+                    index_val: lhs_idx,
+                },
+                InsertElement {
+                    array: rhs_array,
+                    ty: rhs_ty,
+                    value: rhs_value,
+                    index_val: rhs_idx,
+                },
+            ) => {
+                self.type_eq(*lhs_ty, *rhs_ty)
+                    && self.values_eq(
+                        &[*lhs_array, *lhs_value, *lhs_idx],
+                        &[*rhs_array, *rhs_value, *rhs_idx],
+                    )
+            }
+            (
+                InsertValue {
+                    aggregate: lhs_aggregate,
+                    ty: lhs_ty,
+                    value: lhs_value,
+                    indices: lhs_indices,
+                },
+                InsertValue {
+                    aggregate: rhs_aggregate,
+                    ty: rhs_ty,
+                    value: rhs_value,
+                    indices: rhs_indices,
+                },
+            ) => {
+                self.type_eq(*lhs_ty, *rhs_ty)
+                    && lhs_indices == rhs_indices
+                    && self.values_eq(&[*lhs_aggregate, *lhs_value], &[*rhs_aggregate, *rhs_value])
+            }
+            (
+                MemCopy {
+                    dst_val: lhs_dst,
+                    src_val: lhs_src,
+                    byte_len: lhs_len,
+                },
+                MemCopy {
+                    dst_val: rhs_dst,
+                    src_val: rhs_src,
+                    byte_len: rhs_len,
+                },
+            ) => lhs_len == rhs_len && self.values_eq(&[*lhs_dst, *lhs_src], &[*rhs_dst, *rhs_src]),
+            (Nop, Nop) => true,
+            (
+                Store {
+                    dst_val: lhs_dst,
+                    stored_val: lhs_stored,
+                },
+                Store {
+                    dst_val: rhs_dst,
+                    stored_val: rhs_stored,
+                },
+            ) => self.values_eq(&[*lhs_dst, *lhs_stored], &[*rhs_dst, *rhs_stored]),
+            _ => false,
+        }
+    }
+
+    fn fuel_vm_instruction_eq(&self, lhs: &FuelVmInstruction, rhs: &FuelVmInstruction) -> bool {
+        use FuelVmInstruction::*;
+        match (lhs, rhs) {
+            (GasCheckpoint(lhs), GasCheckpoint(rhs))
+            | (Revert(lhs), Revert(rhs))
+            | (StateLoadWord(lhs), StateLoadWord(rhs)) => self.value_eq(*lhs, *rhs),
+            (GetStorageKey, GetStorageKey) => true,
+            (
+                Gtf {
+                    index: lhs_index,
+                    tx_field_id: lhs_id,
+                },
+                Gtf {
+                    index: rhs_index,
+                    tx_field_id: rhs_id,
+                },
+            ) => lhs_id == rhs_id && self.value_eq(*lhs_index, *rhs_index),
+            (
+                Log {
+                    log_val: lhs_val,
+                    log_ty: lhs_ty,
+                    log_id: lhs_id,
+                },
+                Log {
+                    log_val: rhs_val,
+                    log_ty: rhs_ty,
+                    log_id: rhs_id,
+                },
+            ) => {
+                self.type_eq(*lhs_ty, *rhs_ty)
+                    && self.values_eq(&[*lhs_val, *lhs_id], &[*rhs_val, *rhs_id])
+            }
+            (ReadRegister(lhs), ReadRegister(rhs)) => {
+                std::mem::discriminant(lhs) == std::mem::discriminant(rhs)
+            }
+            (
+                Smo {
+                    recipient_and_message: lhs_recipient,
+                    message_size: lhs_size,
+                    output_index: lhs_index,
+                    coins: lhs_coins,
+                },
+                Smo {
+                    recipient_and_message: rhs_recipient,
+                    message_size: rhs_size,
+                    output_index: rhs_index,
+                    coins: rhs_coins,
+                },
+            ) => self.values_eq(
+                &[*lhs_recipient, *lhs_size, *lhs_index, *lhs_coins],
+                &[*rhs_recipient, *rhs_size, *rhs_index, *rhs_coins],
+            ),
+            (
+                StateClear {
+                    key: lhs_key,
+                    number_of_slots: lhs_slots,
+                },
+                StateClear {
+                    key: rhs_key,
+                    number_of_slots: rhs_slots,
+                },
+            ) => self.values_eq(&[*lhs_key, *lhs_slots], &[*rhs_key, *rhs_slots]),
+            (
+                StateLoadQuadWord {
+                    load_val: lhs_val,
+                    key: lhs_key,
+                    number_of_slots: lhs_slots,
+                },
+                StateLoadQuadWord {
+                    load_val: rhs_val,
+                    key: rhs_key,
+                    number_of_slots: rhs_slots,
+                },
+            )
+            | (
+                StateStoreQuadWord {
+                    stored_val: lhs_val,
+                    key: lhs_key,
+                    number_of_slots: lhs_slots,
+                },
+                StateStoreQuadWord {
+                    stored_val: rhs_val,
+                    key: rhs_key,
+                    number_of_slots: rhs_slots,
+                },
+            ) => self.values_eq(
+                &[*lhs_val, *lhs_key, *lhs_slots],
+                &[*rhs_val, *rhs_key, *rhs_slots],
+            ),
+            (
+                StateStoreWord {
+                    stored_val: lhs_val,
+                    key: lhs_key,
+                },
+                StateStoreWord {
+                    stored_val: rhs_val,
+                    key: rhs_key,
+                },
+            ) => self.values_eq(&[*lhs_val, *lhs_key], &[*rhs_val, *rhs_key]),
+            _ => false,
+        }
+    }
+}
@@ -5,15 +5,32 @@
 
 #[derive(Debug)]
 pub enum IrError {
+    BinaryDecodeFailed(String),
+    BinaryNotIr,
+    BinaryVersionMismatch(u32, u32),
     FunctionLocalClobbered(String, String),
+    InvalidConfigurableValue(String),
     InvalidMetadatum(String),
     InvalidPhi,
+    InvalidRewriteRule(String),
     MisplacedTerminator(String),
     MissingBlock(String),
     MissingTerminator(String),
     ParseFailure(String, String),
     RemoveMissingBlock(String),
+    SelectorCollision {
+        selector: [u8; 4],
+        functions: Vec<String>,
+    },
+    UnknownPass(String),
     ValueNotFound(String),
+    VerifyAfterPass(String, Box<IrError>),
+    VerifyFailed {
+        function: String,
+        block: Option<String>,
+        instruction: Option<String>,
+        error: Box<IrError>,
+    },
 
     VerifyAccessElementInconsistentTypes,
     VerifyAccessElementOnNonArray,
@@ -30,7 +47,7 @@ pub enum IrError {
     VerifyBitcastBetweenInvalidTypes(String, String),
     VerifyBinaryOpIncorrectArgType,
     VerifyBranchToMissingBlock(String),
-    VerifyBranchParamsMismatch,
+    VerifyBranchParamsMismatch(String, String),
     VerifyCallArgTypeMismatch(String),
     VerifyCallToMissingFunction(String),
     VerifyCmpBadTypes(String, String),
@@ -47,7 +64,10 @@ pub enum IrError {
     VerifyLoadFromNonPointer,
     VerifyMemcopyNonExistentPointer,
     VerifyMismatchedReturnTypes(String),
+    VerifyOperandNotDominated,
     VerifyBlockArgMalformed,
+    VerifyInconsistentParent,
+    VerifyEntryArgsMismatch(String),
     VerifyPtrCastFromNonPointer,
     VerifyPtrCastToNonPointer,
     VerifyReturnRefTypeValue(String, String),
@@ -59,6 +79,7 @@ pub enum IrError {
     VerifyStoreToNonPointer,
     VerifyUntypedValuePassedToFunction,
     VerifyInvalidGtfIndexType,
+    VerifyGasCheckpointIdBadType,
     VerifyLogId,
     VerifyMismatchedLoggedTypes,
     VerifyRevertCodeBadType,
@@ -76,10 +97,21 @@ use std::fmt;
 impl fmt::Display for IrError {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         match self {
+            IrError::BinaryDecodeFailed(reason) => {
+                write!(f, "Failed to decode binary IR: {reason}.")
+            }
+            IrError::BinaryNotIr => write!(f, "Input is not binary IR."),
+            IrError::BinaryVersionMismatch(found, expected) => write!(
+                f,
+                "Binary IR is format version {found} but only version {expected} is supported."
+            ),
             IrError::FunctionLocalClobbered(fn_str, var_str) => write!(
                 f,
                 "Local storage for function {fn_str} already has an entry for variable {var_str}."
             ),
+            IrError::InvalidConfigurableValue(msg) => {
+                write!(f, "Invalid configurable value: {msg}")
+            }
             IrError::InvalidMetadatum(why_str) => {
                 write!(f, "Unable to convert from invalid metadatum: {why_str}.")
             }
@@ -87,6 +119,7 @@ impl fmt::Display for IrError {
                 f,
                 "Phi instruction has invalid block or value reference list."
             ),
+            IrError::InvalidRewriteRule(msg) => write!(f, "Invalid rewrite rule: {msg}"),
             IrError::MisplacedTerminator(blk_str) => {
                 write!(f, "Block {blk_str} has a misplaced terminator.")
             }
@@ -103,6 +136,19 @@ impl fmt::Display for IrError {
             IrError::RemoveMissingBlock(blk_str) => {
                 write!(f, "Unable to remove block {blk_str}; not found.")
             }
+            IrError::SelectorCollision {
+                selector,
+                functions,
+            } => write!(
+                f,
+                "Functions {} share the selector {}.",
+                functions.join(", "),
+                selector
+                    .iter()
+                    .map(|b| format!("{b:02x}"))
+                    .collect::<String>()
+            ),
+            IrError::UnknownPass(name) => write!(f, "Unknown pass or pipeline '{name}'."),
             IrError::ValueNotFound(reason) => {
                 write!(f, "Invalid value: {reason}.")
             }
@@ -141,6 +187,24 @@ impl fmt::Display for IrError {
                     "Verification failed: Attempt to access a field from a non struct."
                 )
             }
+            IrError::VerifyAfterPass(pass, error) => {
+                write!(f, "Invalid IR after pass '{pass}': {error}")
+            }
+            IrError::VerifyFailed {
+                function,
+                block,
+                instruction,
+                error,
+            } => {
+                write!(f, "In function '{function}'")?;
+                if let Some(block) = block {
+                    write!(f, ", block '{block}'")?;
+                }
+                if let Some(instruction) = instruction {
+                    write!(f, ", instruction `{instruction}`")?;
+                }
+                write!(f, ": {error}")
+            }
             IrError::VerifyArgumentValueIsNotArgument(callee) => write!(
                 f,
                 "Verification failed: Argument specifier for function '{callee}' is not an \
@@ -267,13 +331,28 @@ impl fmt::Display for IrError {
                 "Verification failed: Function {fn_str} return type must match its RET \
                 instructions."
             ),
+            IrError::VerifyOperandNotDominated => write!(
+                f,
+                "Verification failed: An operand is not defined before its use, earlier in the \
+                block or in a block which dominates it."
+            ),
             IrError::VerifyBlockArgMalformed => {
                 write!(f, "Verification failed: Block argument is malformed")
             }
-            IrError::VerifyBranchParamsMismatch => {
+            IrError::VerifyInconsistentParent => write!(
+                f,
+                "Verification failed: A block or instruction doesn't record where it is."
+            ),
+            IrError::VerifyEntryArgsMismatch(fn_str) => write!(
+                f,
+                "Verification failed: Entry block arguments of function '{fn_str}' must match \
+                its signature."
+            ),
+            IrError::VerifyBranchParamsMismatch(from_block, to_block) => {
                 write!(
                     f,
-                    "Verification failed: Block parameter passed in branch is malformed"
+                    "Verification failed: Block parameter passed in branch from block \
+                    '{from_block}' to block '{to_block}' is malformed"
                 )
             }
             IrError::VerifyPtrCastFromNonPointer => {
@@ -329,6 +408,12 @@ impl fmt::Display for IrError {
                 f,
                 "Verification failed: An non-integer value has been passed to a 'gtf' instruction."
             ),
+            IrError::VerifyGasCheckpointIdBadType => {
+                write!(
+                    f,
+                    "Verification failed: gas checkpoint identifier must be a u64."
+                )
+            }
             IrError::VerifyLogId => {
                 write!(f, "Verification failed: log ID must be an integer.")
             }
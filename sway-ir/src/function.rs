@@ -10,28 +10,40 @@ use std::collections::{BTreeMap, HashMap};
 use std::fmt::Write;
 
 use rustc_hash::{FxHashMap, FxHashSet};
+use sha2::{Digest, Sha256};
 
 use crate::{
+    analysis::{
+        cost::{estimate_inline_cost, InlineCost},
+        dominator::{compute_dom_tree, compute_post_order},
+        loops::{compute_function_loop_info, LoopInfo},
+    },
     block::{Block, BlockIterator, Label},
     constant::Constant,
     context::Context,
     error::IrError,
+    instruction::{FuelVmInstruction, Instruction},
     irtype::Type,
     local_var::{LocalVar, LocalVarContent},
-    metadata::MetadataIndex,
+    metadata::{self, DebugVariable, Inline, MetadataIndex, MetadataKind, Metadatum, SourceSpan},
     module::Module,
+    printer,
+    symbol::Symbol,
     value::Value,
     BlockArgument, BranchToWithArgs,
 };
 
 /// A wrapper around an [ECS](https://github.com/fitzgen/generational-arena) handle into the
 /// [`Context`].
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+///
+/// Functions are ordered by their handles, which is arbitrary but doesn't depend on hashing, so
+/// ordered collections of functions iterate the same way every time.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
 pub struct Function(pub generational_arena::Index);
 
 #[doc(hidden)]
 pub struct FunctionContent {
-    pub name: String,
+    pub name: Symbol,
     pub arguments: Vec<(String, Value)>,
     pub return_type: Type,
     pub blocks: Vec<Block>,
@@ -39,12 +51,41 @@ pub struct FunctionContent {
     pub is_entry: bool,
     pub selector: Option<[u8; 4]>,
     pub metadata: Option<MetadataIndex>,
+    pub attributes: FxHashSet<FunctionAttribute>,
 
     pub local_storage: BTreeMap<String, LocalVar>, // BTree rather than Hash for deterministic ordering.
+    /// Whether the body is already optimized, in which case the passes leave it alone.
+    pub is_optimized: bool,
 
     next_label_idx: u64,
 }
 
+/// A hint about, or a guarantee made by, a function which the optimization passes may use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FunctionAttribute {
+    /// Calls to the function should be inlined wherever possible.
+    InlineAlways,
+    /// Calls to the function should not be inlined unless they must be.
+    InlineNever,
+    /// The function has no side effects and always returns, so a call whose result is unused may
+    /// be removed.
+    Pure,
+    /// The function is rarely called, so isn't worth inlining.
+    Cold,
+}
+
+impl FunctionAttribute {
+    /// Return the attribute as it's written in the textual IR.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FunctionAttribute::InlineAlways => "inline_always",
+            FunctionAttribute::InlineNever => "inline_never",
+            FunctionAttribute::Pure => "pure",
+            FunctionAttribute::Cold => "cold",
+        }
+    }
+}
+
 impl Function {
     /// Return a new [`Function`] handle.
     ///
@@ -66,7 +107,7 @@ impl Function {
         metadata: Option<MetadataIndex>,
     ) -> Function {
         let content = FunctionContent {
-            name,
+            name: context.intern(&name),
             // Arguments to a function are the arguments to its entry block.
             // We set it up after creating the entry block below.
             arguments: Vec::new(),
@@ -76,7 +117,9 @@ impl Function {
             is_entry,
             selector,
             metadata,
+            attributes: FxHashSet::default(),
             local_storage: BTreeMap::new(),
+            is_optimized: false,
             next_label_idx: 0,
         };
         let func = Function(context.functions.insert(content));
@@ -118,6 +161,121 @@ impl Function {
         func
     }
 
+    /// Create a deep copy of this function named `new_name`, in the same module.
+    ///
+    /// The blocks, block arguments, instructions and locals are all copied, along with their
+    /// metadata, and every use of a value in the copy refers to the copied value.  The copy has no
+    /// selector and is not an entry, since those must be unique within a module.  Calls are left
+    /// alone, so a recursive call in the copy still calls this function.
+    pub fn clone_function(&self, context: &mut Context, new_name: String) -> Function {
+        let module = context
+            .module_iter()
+            .find(|module| context.modules[module.0].functions.contains(self))
+            .expect("Function must belong to a module.");
+
+        let content = &context.functions[self.0];
+        let (return_type, is_public, metadata) =
+            (content.return_type, content.is_public, content.metadata);
+        let args = content
+            .arguments
+            .iter()
+            .map(|(name, arg_val)| {
+                let (ty, by_ref) = arg_val
+                    .get_argument_type_and_byref(context)
+                    .expect("Function arguments must be block arguments.");
+                (name.clone(), ty, by_ref, arg_val.get_metadata(context))
+            })
+            .collect();
+        let new_func = Function::new(
+            context,
+            module,
+            new_name,
+            args,
+            return_type,
+            None,
+            is_public,
+            false,
+            metadata,
+        );
+
+        let local_map = context.functions[self.0]
+            .local_storage
+            .iter()
+            .map(|(name, var)| (name.clone(), *var, context.local_vars[var.0].clone()))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|(name, old_var, LocalVarContent { ty, initializer })| {
+                let new_var = new_func
+                    .new_local_var(context, name, ty, initializer)
+                    .unwrap();
+                (old_var, new_var)
+            })
+            .collect::<FxHashMap<_, _>>();
+
+        // Create all the blocks and their arguments first, so that branches can be mapped.
+        let mut value_map = FxHashMap::default();
+        let mut block_map = FxHashMap::default();
+        let new_entry = new_func.get_entry_block(context);
+        for block in self.block_iter(context) {
+            if block == self.get_entry_block(context) {
+                for (old_arg, new_arg) in block
+                    .arg_iter(context)
+                    .zip(new_entry.arg_iter(context))
+                    .map(|(old_arg, new_arg)| (*old_arg, *new_arg))
+                    .collect::<Vec<_>>()
+                {
+                    value_map.insert(old_arg, new_arg);
+                }
+                block_map.insert(block, new_entry);
+                continue;
+            }
+            let label = block.get_label(context).to_owned();
+            let new_block = new_func.create_block(context, Some(label));
+            for old_arg in block.arg_iter(context).copied().collect::<Vec<_>>() {
+                let (ty, by_ref) = old_arg.get_argument_type_and_byref(context).unwrap();
+                let idx = new_block.new_arg(context, ty, by_ref);
+                let new_arg = new_block.get_arg(context, idx).unwrap();
+                new_arg.add_metadatum(context, old_arg.get_metadata(context));
+                value_map.insert(old_arg, new_arg);
+            }
+            block_map.insert(block, new_block);
+        }
+
+        // Copy the instructions, then remap their operands once every value has a copy, since a
+        // branch may pass a value defined in a later block.
+        for (block, old_ins_val) in self.instruction_iter(context).collect::<Vec<_>>() {
+            let mut ins = old_ins_val.get_instruction(context).unwrap().clone();
+            match &mut ins {
+                Instruction::Branch(to_block) => to_block.block = block_map[&to_block.block],
+                Instruction::ConditionalBranch {
+                    true_block,
+                    false_block,
+                    ..
+                } => {
+                    true_block.block = block_map[&true_block.block];
+                    false_block.block = block_map[&false_block.block];
+                }
+                Instruction::GetLocal(local_var) => *local_var = local_map[local_var],
+                _ => (),
+            }
+            let new_block = block_map[&block];
+            let new_ins_val = Value::new_instruction(context, ins)
+                .add_metadatum(context, old_ins_val.get_metadata(context));
+            context.blocks[new_block.0].instructions.push(new_ins_val);
+            new_ins_val.set_block(context, Some(new_block));
+            value_map.insert(old_ins_val, new_ins_val);
+        }
+        new_func.replace_values(context, &value_map, None);
+
+        for block in new_func.block_iter(context) {
+            for BranchToWithArgs { block: succ, .. } in block.successors(context) {
+                succ.add_pred(context, &block);
+            }
+        }
+
+        new_func
+    }
+
     /// Create and append a new [`Block`] to this function.
     pub fn create_block(&self, context: &mut Context, label: Option<Label>) -> Block {
         let block = Block::new(context, *self, label);
@@ -146,10 +304,7 @@ impl Function {
                 func.blocks.insert(idx, new_block);
                 new_block
             })
-            .ok_or_else(|| {
-                let label = &context.blocks[other.0].label;
-                IrError::MissingBlock(label.clone())
-            })
+            .ok_or_else(|| IrError::MissingBlock(other.get_label(context).to_owned()))
     }
 
     /// Create and insert a new [`Block`] into this function.
@@ -172,18 +327,17 @@ impl Function {
                 func.blocks.insert(idx + 1, new_block);
                 new_block
             })
-            .ok_or_else(|| {
-                let label = &context.blocks[other.0].label;
-                IrError::MissingBlock(label.clone())
-            })
+            .ok_or_else(|| IrError::MissingBlock(other.get_label(context).to_owned()))
     }
 
     /// Remove a [`Block`] from this function.
     ///
     /// > Care must be taken to ensure the block has no predecessors otherwise the function will be
     /// > made invalid.
+    ///
+    /// Its instructions are no longer users of their operands, see [`Value::users`].
     pub fn remove_block(&self, context: &mut Context, block: &Block) -> Result<(), IrError> {
-        let label = block.get_label(context);
+        let label = block.get_label(context).to_owned();
         let func = context.functions.get_mut(self.0).unwrap();
         let block_idx = func
             .blocks
@@ -191,9 +345,88 @@ impl Function {
             .position(|b| b == block)
             .ok_or(IrError::RemoveMissingBlock(label))?;
         func.blocks.remove(block_idx);
+        for ins in context.blocks[block.0].instructions.clone() {
+            ins.remove_uses(context);
+        }
         Ok(())
     }
 
+    /// Split every critical edge in this function, returning whether there were any.
+    ///
+    /// An edge is critical if its source has more than one successor and its destination more
+    /// than one incoming edge, so there is nowhere to put code which must run only along it.  Each
+    /// is redirected through a new empty block, inserted after the source, which branches on to
+    /// the destination with the original arguments.  Both arms of a `cbr` to the same block are
+    /// critical edges and are each given their own block.
+    pub fn split_critical_edges(&self, context: &mut Context) -> bool {
+        let mut incoming = FxHashMap::<Block, usize>::default();
+        for block in self.block_iter(context) {
+            for BranchToWithArgs { block: succ, .. } in block.successors(context) {
+                *incoming.entry(succ).or_default() += 1;
+            }
+        }
+
+        let mut modified = false;
+        for block in self.block_iter(context).collect::<Vec<_>>() {
+            let Some(terminator) = context.blocks[block.0].instructions.last().copied() else {
+                continue;
+            };
+            let metadata = terminator.get_metadata(context);
+            let mut insert_after = block;
+            for is_true_arm in [true, false] {
+                let Some(Instruction::ConditionalBranch {
+                    true_block,
+                    false_block,
+                    ..
+                }) = terminator.get_instruction(context)
+                else {
+                    break;
+                };
+                let BranchToWithArgs { block: succ, args } = if is_true_arm {
+                    true_block.clone()
+                } else {
+                    false_block.clone()
+                };
+                if incoming[&succ] < 2 {
+                    continue;
+                }
+
+                let edge_block = self
+                    .create_block_after(context, &insert_after, None)
+                    .unwrap();
+                insert_after = edge_block;
+                edge_block
+                    .ins(context)
+                    .metadata(metadata)
+                    .branch(succ, args);
+
+                terminator.remove_uses(context);
+                if let Some(Instruction::ConditionalBranch {
+                    true_block,
+                    false_block,
+                    ..
+                }) = block.get_terminator_mut(context)
+                {
+                    let arm = if is_true_arm { true_block } else { false_block };
+                    arm.block = edge_block;
+                    arm.args = Vec::new();
+                }
+                terminator.update_uses(context);
+
+                edge_block.add_pred(context, &block);
+                if !block
+                    .successors(context)
+                    .iter()
+                    .any(|branch| branch.block == succ)
+                {
+                    succ.remove_pred(context, &block);
+                }
+                modified = true;
+            }
+        }
+        modified
+    }
+
     /// Get a new unique block label.
     ///
     /// If `hint` is `None` then the label will be in the form `"blockN"` where N is an
@@ -201,18 +434,21 @@ impl Function {
     ///
     /// Otherwise if the hint is already unique to this function it will be returned.  If not
     /// already unique it will have N appended to it until it is unique.
-    pub fn get_unique_label(&self, context: &mut Context, hint: Option<String>) -> String {
+    pub fn get_unique_label(&self, context: &mut Context, hint: Option<String>) -> Symbol {
         match hint {
             Some(hint) => {
-                if context.functions[self.0]
-                    .blocks
-                    .iter()
-                    .any(|block| context.blocks[block.0].label == hint)
-                {
+                // A label which was never interned can't be taken.
+                let taken = context.get_symbol(&hint).map_or(false, |symbol| {
+                    context.functions[self.0]
+                        .blocks
+                        .iter()
+                        .any(|block| context.blocks[block.0].label == symbol)
+                });
+                if taken {
                     let idx = self.get_next_label_idx(context);
                     self.get_unique_label(context, Some(format!("{hint}{idx}")))
                 } else {
-                    hint
+                    context.intern(&hint)
                 }
             }
             None => {
@@ -241,9 +477,62 @@ impl Function {
             .sum()
     }
 
+    /// Add an attribute to this function.
+    pub fn add_attribute(&self, context: &mut Context, attribute: FunctionAttribute) {
+        context.functions[self.0].attributes.insert(attribute);
+    }
+
+    /// Return whether this function has `attribute`.
+    pub fn has_attribute(&self, context: &Context, attribute: FunctionAttribute) -> bool {
+        context.functions[self.0].attributes.contains(&attribute)
+    }
+
+    /// Return the attributes of this function, in a stable order.
+    pub fn get_attributes(&self, context: &Context) -> Vec<FunctionAttribute> {
+        let mut attributes = context.functions[self.0]
+            .attributes
+            .iter()
+            .copied()
+            .collect::<Vec<_>>();
+        attributes.sort();
+        attributes
+    }
+
+    /// Return the cost of inlining calls to this function.  See [`InlineCost`].
+    pub fn inline_cost(&self, context: &Context) -> InlineCost {
+        estimate_inline_cost(context, self)
+    }
+
     /// Return the function name.
     pub fn get_name<'a>(&self, context: &'a Context) -> &'a str {
-        &context.functions[self.0].name
+        context.functions[self.0].name.as_str(context)
+    }
+
+    /// Return the interned function name, for comparing names in constant time.
+    pub fn get_name_symbol(&self, context: &Context) -> Symbol {
+        context.functions[self.0].name
+    }
+
+    /// Return a hash of the function's content, including any metadata it refers to.
+    ///
+    /// Functions with the same content have the same hash, even in different contexts, so this
+    /// may be used to detect whether a function has changed between compilations, even by
+    /// different builds of the compiler.  Calls are by name so a change to a callee does not
+    /// change the hash of its callers.
+    pub fn content_hash(&self, context: &Context) -> u64 {
+        let digest = Sha256::digest(printer::function_to_string(context, *self).as_bytes());
+        u64::from_le_bytes(digest[..8].try_into().unwrap())
+    }
+
+    /// Return whether the function's body is already optimized, e.g. because it was loaded from a
+    /// [`crate::FunctionCache`].  The passes leave optimized functions alone.
+    pub fn is_optimized(&self, context: &Context) -> bool {
+        context.functions[self.0].is_optimized
+    }
+
+    /// Mark whether the function's body is already optimized.
+    pub fn set_optimized(&self, context: &mut Context, optimized: bool) {
+        context.functions[self.0].is_optimized = optimized;
     }
 
     /// Return the function entry (i.e., the first) block.
@@ -256,6 +545,67 @@ impl Function {
         context.functions[self.0].metadata
     }
 
+    /// Return the source span of this function, if it has one.
+    pub fn get_span(&self, context: &Context) -> Option<SourceSpan> {
+        metadata::get_span(context, context.functions[self.0].metadata)
+    }
+
+    /// Set the source span of this function, replacing any it already has.
+    pub fn set_span(&self, context: &mut Context, span: SourceSpan) {
+        let md_idx = context.functions[self.0].metadata;
+        context.functions[self.0].metadata = metadata::set_span(context, md_idx, span);
+    }
+
+    /// Return the fields of this function's metadatum of `kind`, if it has one.
+    pub fn get_metadatum<'a>(
+        &self,
+        context: &'a Context,
+        kind: &MetadataKind,
+    ) -> Option<&'a [Metadatum]> {
+        metadata::get_metadatum(context, context.functions[self.0].metadata, kind)
+    }
+
+    /// Set this function's metadatum of `kind` to one with `fields`, replacing any it already
+    /// has.
+    pub fn set_metadatum(
+        &self,
+        context: &mut Context,
+        kind: &MetadataKind,
+        fields: Vec<Metadatum>,
+    ) {
+        let md_idx = context.functions[self.0].metadata;
+        context.functions[self.0].metadata = metadata::set_metadatum(context, md_idx, kind, fields);
+    }
+
+    /// Remove this function's metadatum of `kind`, if it has one.
+    pub fn remove_metadatum(&self, context: &mut Context, kind: &MetadataKind) {
+        let md_idx = context.functions[self.0].metadata;
+        context.functions[self.0].metadata = metadata::remove_metadatum(context, md_idx, kind);
+    }
+
+    /// Return the inline request of this function, if it has one.
+    pub fn get_inline(&self, context: &Context) -> Option<Inline> {
+        self.get_metadatum(context, &MetadataKind::Inline)
+            .and_then(Inline::from_fields)
+    }
+
+    /// Set the inline request of this function, replacing any it already has.
+    pub fn set_inline(&self, context: &mut Context, inline: Inline) {
+        self.set_metadatum(context, &MetadataKind::Inline, inline.to_fields());
+    }
+
+    /// Return the user variables of this function which have no value left.
+    pub fn get_debug_variables(&self, context: &Context) -> Vec<DebugVariable> {
+        metadata::get_debug_variables(context, context.functions[self.0].metadata)
+    }
+
+    /// Record that the user variable `variable` of this function has no value left.
+    pub fn add_debug_variable(&self, context: &mut Context, variable: &DebugVariable) {
+        let md_idx = context.functions[self.0].metadata;
+        context.functions[self.0].metadata =
+            metadata::add_debug_variable(context, md_idx, variable);
+    }
+
     /// Whether this function has a valid selector.
     pub fn has_selector(&self, context: &Context) -> bool {
         context.functions[self.0].selector.is_some()
@@ -266,6 +616,12 @@ impl Function {
         context.functions[self.0].selector
     }
 
+    /// Set or clear the function selector.  See [`Module::recompute_selectors`] for replacing the
+    /// selectors of a whole module.
+    pub fn set_selector(&self, context: &mut Context, selector: Option<[u8; 4]>) {
+        context.functions[self.0].selector = selector;
+    }
+
     /// Whether or not the function is a program entry point, i.e. `main`, `#[test]` fns or abi
     /// methods.
     pub fn is_entry(&self, context: &Context) -> bool {
@@ -332,10 +688,11 @@ impl Function {
         initializer: Option<Constant>,
     ) -> Result<LocalVar, IrError> {
         let var = LocalVar::new(context, local_type, initializer);
+        let func_name = self.get_name(context).to_owned();
         let func = context.functions.get_mut(self.0).unwrap();
         func.local_storage
             .insert(name.clone(), var)
-            .map(|_| Err(IrError::FunctionLocalClobbered(func.name.clone(), name)))
+            .map(|_| Err(IrError::FunctionLocalClobbered(func_name, name)))
             .unwrap_or(Ok(var))
     }
 
@@ -433,31 +790,59 @@ impl Function {
             })
     }
 
-    /// Replace a value with another within this function.
+    /// Replace values with others within this function.
+    ///
+    /// Only the instructions using the values in `replace_map` are visited, found through
+    /// [`Value::users`], so the cost depends on the number of uses rather than the size of the
+    /// function.  Chains of replacements are followed, so `replace_map` mustn't have any cycles.
     ///
-    /// This is a convenience method which iterates over this function's blocks and calls
-    /// [`Block::replace_values`] in turn.
+    /// `starting_block` limits the replacement to that block and those after it, for when the
+    /// first possible reference to the values is known.
     ///
-    /// `starting_block` is an optimisation for when the first possible reference to `old_val` is
-    /// known.
+    /// The attachments of each replaced value are propagated to the value it's finally replaced
+    /// with, as [`Value::propagate_attachments`] does, so every pass replacing values follows the
+    /// [`AttachmentPolicy`](crate::AttachmentPolicy) of their namespaces.
     pub fn replace_values(
         &self,
         context: &mut Context,
         replace_map: &FxHashMap<Value, Value>,
         starting_block: Option<Block>,
     ) {
-        let mut block_iter = self.block_iter(context).peekable();
-
-        if let Some(ref starting_block) = starting_block {
-            // Skip blocks until we hit the starting block.
-            while block_iter
-                .next_if(|block| block != starting_block)
-                .is_some()
-            {}
+        let mut old_vals = replace_map.keys().copied().collect::<Vec<_>>();
+        old_vals.sort_by_key(|old_val| old_val.0);
+        for old_val in old_vals {
+            let mut new_val = replace_map[&old_val];
+            while let Some(next_val) = replace_map.get(&new_val) {
+                new_val = *next_val;
+            }
+            old_val.propagate_attachments(context, new_val);
         }
 
-        for block in block_iter {
-            block.replace_values(context, replace_map);
+        let blocks = starting_block.map(|starting_block| {
+            self.block_iter(context)
+                .skip_while(|block| *block != starting_block)
+                .collect::<FxHashSet<_>>()
+        });
+        let mut users = Vec::new();
+        let mut seen = FxHashSet::default();
+        for old_val in replace_map.keys() {
+            for user in old_val.users(context) {
+                let in_scope = user.get_block(context).map_or(false, |block| {
+                    block.get_function(context) == *self
+                        && blocks
+                            .as_ref()
+                            .map_or(true, |blocks| blocks.contains(&block))
+                });
+                if in_scope && seen.insert(user) {
+                    users.push(user);
+                }
+            }
+        }
+        // The map's keys are in hash order, so sort the users to record them as users of the new
+        // values in the same order every time.
+        users.sort_by_key(|user| user.0);
+        for user in users {
+            user.replace_instruction_values(context, replace_map);
         }
     }
 
@@ -474,34 +859,187 @@ impl Function {
     }
 
     /// A graphviz dot graph of the control-flow-graph.
+    ///
+    /// The blocks of each natural loop are grouped in a cluster, nested within the clusters of the
+    /// loops containing it, and back edges are colored red.  Unreachable blocks are left out.
     pub fn dot_cfg(&self, context: &Context) -> String {
-        let mut worklist = Vec::<Block>::new();
-        let mut visited = FxHashSet::<Block>::default();
-        let entry = self.get_entry_block(context);
-        let mut res = format!("digraph {} {{\n", self.get_name(context));
+        self.dot_cfg_with_options(context, &DotCfgOptions::default())
+    }
 
-        worklist.push(entry);
-        while !worklist.is_empty() {
-            let n = worklist.pop().unwrap();
-            visited.insert(n);
-            for BranchToWithArgs { block: n_succ, .. } in n.successors(context) {
+    /// Like [`Function::dot_cfg`] but with more detail as chosen by `options`.
+    pub fn dot_cfg_with_options(&self, context: &Context, options: &DotCfgOptions) -> String {
+        let loop_info = compute_function_loop_info(context, self);
+        let reachable = compute_post_order(context, self).block_to_po;
+        let blocks = self
+            .block_iter(context)
+            .filter(|block| reachable.contains_key(block))
+            .collect::<Vec<_>>();
+        let (nodes, names) = dot_nodes(context, *self, options);
+
+        let mut res = format!("digraph {} {{\n", self.get_name(context));
+        dot_loop_cluster(context, &loop_info, &blocks, &nodes, None, 1, &mut res);
+        for block in &blocks {
+            for BranchToWithArgs { block: succ, args } in block.successors(context) {
+                let mut attrs = Vec::new();
+                if loop_info.is_back_edge(*block, succ) {
+                    attrs.push("color=red".to_owned());
+                }
+                if options.edge_args && !args.is_empty() {
+                    let args = args
+                        .iter()
+                        .map(|arg| names.get(arg).map_or("?", |name| name.as_str()))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    attrs.push(format!("label=\"{}\"", dot_escape(&args, "\"\\")));
+                }
                 let _ = writeln!(
                     res,
-                    "\t{} -> {}\n",
-                    n.get_label(context),
-                    n_succ.get_label(context)
+                    "\t{} -> {}{}",
+                    block.get_label(context),
+                    succ.get_label(context),
+                    if attrs.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" [{}]", attrs.join(", "))
+                    }
                 );
-                if !visited.contains(&n_succ) {
-                    worklist.push(n_succ);
-                }
             }
         }
+        res += "}\n";
+        res
+    }
 
+    /// A graphviz dot graph of the dominator tree, with an edge from each block to those it
+    /// immediately dominates.  Unreachable blocks are left out.
+    pub fn dot_domtree(&self, context: &Context) -> String {
+        self.dot_domtree_with_options(context, &DotCfgOptions::default())
+    }
+
+    /// Like [`Function::dot_domtree`] but with blocks drawn as chosen by `options`.  There are no
+    /// edge arguments to show.
+    pub fn dot_domtree_with_options(&self, context: &Context, options: &DotCfgOptions) -> String {
+        let dom_tree = compute_dom_tree(context, self, &compute_post_order(context, self));
+        let blocks = self
+            .block_iter(context)
+            .filter(|block| dom_tree.contains_key(block))
+            .collect::<Vec<_>>();
+        let (nodes, _) = dot_nodes(context, *self, options);
+
+        let mut res = format!("digraph {} {{\n", self.get_name(context));
+        for block in &blocks {
+            let _ = writeln!(res, "\t{}", nodes[block]);
+        }
+        for block in &blocks {
+            if let Some(idom) = dom_tree[block].parent {
+                let _ = writeln!(
+                    res,
+                    "\t{} -> {}",
+                    idom.get_label(context),
+                    block.get_label(context)
+                );
+            }
+        }
         res += "}\n";
         res
     }
 }
 
+/// Options for [`Function::dot_cfg_with_options`] and [`Function::dot_domtree_with_options`].  By default the graph is just as it is for
+/// [`Function::dot_cfg`].
+#[derive(Clone, Debug, Default)]
+pub struct DotCfgOptions {
+    /// Draw each block as a record holding its header and instructions.  Blocks which return are
+    /// colored blue and those which revert are colored orange.
+    pub instructions: bool,
+    /// Label each edge with the arguments passed to the block branched to.
+    pub edge_args: bool,
+}
+
+// The node for each block, drawn as chosen by `options`, and the names of the values as they're
+// printed.
+fn dot_nodes(
+    context: &Context,
+    function: Function,
+    options: &DotCfgOptions,
+) -> (FxHashMap<Block, String>, HashMap<Value, String>) {
+    let (printed_blocks, names) = printer::function_to_blocks_and_names(context, function);
+    let nodes = printed_blocks
+        .into_iter()
+        .map(|printed| {
+            let label = printed.block.get_label(context).to_owned();
+            let node = if options.instructions {
+                dot_record_node(context, &label, &printed)
+            } else {
+                label
+            };
+            (printed.block, node)
+        })
+        .collect();
+    (nodes, names)
+}
+
+// Escape each of `special` and any backslash within `text`.
+fn dot_escape(text: &str, special: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c == '\\' || special.contains(c) {
+            res.push('\\');
+        }
+        res.push(c);
+    }
+    res
+}
+
+// A record node for a block, with a line for the header and then its instructions left aligned.
+fn dot_record_node(context: &Context, label: &str, printed: &printer::PrintedBlock) -> String {
+    let lines = printed
+        .instructions
+        .iter()
+        .flat_map(|(_, text)| text.lines())
+        .map(|line| format!("{}\\l", dot_escape(line.trim(), "{}|<>\"")))
+        .collect::<String>();
+    let color = match printed.block.get_terminator(context) {
+        Some(Instruction::Ret(..)) => ", color=blue",
+        Some(Instruction::FuelVm(FuelVmInstruction::Revert(_))) => ", color=orange",
+        _ => "",
+    };
+    format!(
+        "{label} [shape=record{color}, label=\"{{{}|{lines}}}\"]",
+        dot_escape(&printed.header, "{}|<>\"")
+    )
+}
+
+// Write the blocks whose innermost loop is `loop_idx`, or which are in no loop if it is `None`,
+// followed by a cluster for each loop nested directly within it.
+fn dot_loop_cluster(
+    context: &Context,
+    loop_info: &LoopInfo,
+    blocks: &[Block],
+    nodes: &FxHashMap<Block, String>,
+    loop_idx: Option<usize>,
+    depth: usize,
+    res: &mut String,
+) {
+    let indent = "\t".repeat(depth);
+    for block in blocks {
+        if loop_info.innermost_loop(*block) == loop_idx {
+            let _ = writeln!(res, "{indent}{}", nodes[block]);
+        }
+    }
+    for (idx, lp) in loop_info.loops.iter().enumerate() {
+        if lp.parent == loop_idx {
+            let _ = writeln!(res, "{indent}subgraph cluster_{idx} {{");
+            let _ = writeln!(
+                res,
+                "{indent}\tlabel = \"loop {}\";",
+                lp.header.get_label(context)
+            );
+            dot_loop_cluster(context, loop_info, blocks, nodes, Some(idx), depth + 1, res);
+            let _ = writeln!(res, "{indent}}}");
+        }
+    }
+}
+
 /// An iterator over each [`Function`] in a [`Module`].
 pub struct FunctionIterator {
     functions: Vec<generational_arena::Index>,
@@ -17,6 +17,7 @@ use crate::{
     function::Function,
     irtype::Type,
     local_var::LocalVar,
+    metadata::MetadataIndex,
     pretty::DebugWithContext,
     value::{Value, ValueDatum},
 };
@@ -121,6 +122,10 @@ pub enum Instruction {
 
 #[derive(Debug, Clone, DebugWithContext)]
 pub enum FuelVmInstruction {
+    /// A user-marked point at which the remaining gas may be recorded.  The operand is the `u64`
+    /// identifier of the checkpoint.  Checkpoints are lowered by the `gas_checkpoints` pass and
+    /// otherwise generate no code.
+    GasCheckpoint(Value),
     /// Generate a unique integer value
     GetStorageKey,
     Gtf {
@@ -178,14 +183,14 @@ pub enum FuelVmInstruction {
 }
 
 /// Comparison operations.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Predicate {
     Equal,
     LessThan,
     GreaterThan,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum BinaryOpKind {
     Add,
     Sub,
@@ -246,6 +251,9 @@ impl Instruction {
             Instruction::ContractCall { return_type, .. } => Some(*return_type),
             Instruction::ExtractElement { ty, .. } => ty.get_array_elem_type(context),
             Instruction::ExtractValue { ty, indices, .. } => ty.get_indexed_type(context, indices),
+            Instruction::FuelVm(FuelVmInstruction::GasCheckpoint(_)) => {
+                Some(Type::get_unit(context))
+            }
             Instruction::FuelVm(FuelVmInstruction::GetStorageKey) => Some(Type::get_b256(context)),
             Instruction::FuelVm(FuelVmInstruction::Gtf { .. }) => Some(Type::get_uint64(context)),
             Instruction::FuelVm(FuelVmInstruction::Log { .. }) => Some(Type::get_unit(context)),
@@ -360,6 +368,7 @@ impl Instruction {
                 indices: _,
             } => vec![*aggregate],
             Instruction::FuelVm(fuel_vm_instr) => match fuel_vm_instr {
+                FuelVmInstruction::GasCheckpoint(id) => vec![*id],
                 FuelVmInstruction::GetStorageKey => vec![],
                 FuelVmInstruction::Gtf {
                     index,
@@ -499,6 +508,7 @@ impl Instruction {
             }
             Instruction::ExtractValue { aggregate, .. } => replace(aggregate),
             Instruction::FuelVm(fuel_vm_instr) => match fuel_vm_instr {
+                FuelVmInstruction::GasCheckpoint(id) => replace(id),
                 FuelVmInstruction::GetStorageKey => (),
                 FuelVmInstruction::Gtf { index, .. } => replace(index),
                 FuelVmInstruction::Log {
@@ -554,7 +564,7 @@ impl Instruction {
                 }
             },
             Instruction::IntToPtr(value, _) => replace(value),
-            Instruction::Load(_) => (),
+            Instruction::Load(ptr) => replace(ptr),
             Instruction::MemCopy {
                 dst_val, src_val, ..
             } => {
@@ -563,7 +573,11 @@ impl Instruction {
             }
             Instruction::Nop => (),
             Instruction::Ret(ret_val, _) => replace(ret_val),
-            Instruction::Store { stored_val, .. } => {
+            Instruction::Store {
+                dst_val,
+                stored_val,
+            } => {
+                replace(dst_val);
                 replace(stored_val);
             }
         }
@@ -574,6 +588,7 @@ impl Instruction {
             Instruction::AsmBlock(_, _)
                 | Instruction::Call(..)
                 | Instruction::ContractCall { .. }
+                | Instruction::FuelVm(FuelVmInstruction::GasCheckpoint(_))
                 | Instruction::FuelVm(FuelVmInstruction::Log { .. })
                 | Instruction::FuelVm(FuelVmInstruction::Smo { .. })
                 | Instruction::FuelVm(FuelVmInstruction::StateClear { .. })
@@ -668,26 +683,71 @@ impl DoubleEndedIterator for InstructionIterator {
     }
 }
 
-/// Provide a context for appending new [`Instruction`]s to a [`Block`].
+/// Where an [`InstructionInserter`] puts a new instruction in its block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InsertionPoint {
+    /// Before the first instruction.
+    Start,
+    /// After the last instruction.  This is the default.
+    End,
+    /// Immediately before an instruction in the block.
+    Before(Value),
+    /// Immediately after an instruction in the block.
+    After(Value),
+}
+
+/// Provide a context for adding new [`Instruction`]s to a [`Block`].
+///
+/// By default the new instruction is appended to the block without any metadata.  Use
+/// [`InstructionInserter::position`] and [`InstructionInserter::metadata`] to change that, e.g.,
+/// `block.ins(context).position(InsertionPoint::Before(ins)).metadata(md).load(ptr)`.
 pub struct InstructionInserter<'a> {
     context: &'a mut Context,
     block: Block,
-}
-
-macro_rules! make_instruction {
-    ($self: ident, $ctor: expr) => {{
-        let instruction_val = Value::new_instruction($self.context, $ctor);
-        $self.context.blocks[$self.block.0]
-            .instructions
-            .push(instruction_val);
-        instruction_val
-    }};
+    position: InsertionPoint,
+    metadata: Option<MetadataIndex>,
 }
 
 impl<'a> InstructionInserter<'a> {
     /// Return a new [`InstructionInserter`] context for `block`.
     pub fn new(context: &'a mut Context, block: Block) -> InstructionInserter<'a> {
-        InstructionInserter { context, block }
+        InstructionInserter {
+            context,
+            block,
+            position: InsertionPoint::End,
+            metadata: None,
+        }
+    }
+
+    /// Put the new instruction at `position` in the block.
+    pub fn position(self, position: InsertionPoint) -> Self {
+        Self { position, ..self }
+    }
+
+    /// Add `metadata` to the new instruction.
+    pub fn metadata(self, metadata: Option<MetadataIndex>) -> Self {
+        Self { metadata, ..self }
+    }
+
+    fn insert(self, instruction: Instruction) -> Value {
+        let value = Value::new_instruction(self.context, instruction)
+            .add_metadatum(self.context, self.metadata);
+        let instructions = &mut self.context.blocks[self.block.0].instructions;
+        let position_of = |ins_val: Value| {
+            instructions
+                .iter()
+                .position(|val| *val == ins_val)
+                .expect("Insertion point must be an instruction in the block.")
+        };
+        let idx = match self.position {
+            InsertionPoint::Start => 0,
+            InsertionPoint::End => instructions.len(),
+            InsertionPoint::Before(ins_val) => position_of(ins_val),
+            InsertionPoint::After(ins_val) => position_of(ins_val) + 1,
+        };
+        instructions.insert(idx, value);
+        value.set_block(self.context, Some(self.block));
+        value
     }
 
     //
@@ -713,44 +773,39 @@ impl<'a> InstructionInserter<'a> {
     }
 
     pub fn asm_block_from_asm(self, asm: AsmBlock, args: Vec<AsmArg>) -> Value {
-        make_instruction!(self, Instruction::AsmBlock(asm, args))
+        self.insert(Instruction::AsmBlock(asm, args))
     }
 
     pub fn addr_of(self, value: Value) -> Value {
-        make_instruction!(self, Instruction::AddrOf(value))
+        self.insert(Instruction::AddrOf(value))
     }
 
     pub fn bitcast(self, value: Value, ty: Type) -> Value {
-        make_instruction!(self, Instruction::BitCast(value, ty))
+        self.insert(Instruction::BitCast(value, ty))
     }
 
     pub fn binary_op(self, op: BinaryOpKind, arg1: Value, arg2: Value) -> Value {
-        make_instruction!(self, Instruction::BinaryOp { op, arg1, arg2 })
+        self.insert(Instruction::BinaryOp { op, arg1, arg2 })
     }
 
     pub fn branch(self, to_block: Block, dest_params: Vec<Value>) -> Value {
-        let br_val = Value::new_instruction(
-            self.context,
-            Instruction::Branch(BranchToWithArgs {
-                block: to_block,
-                args: dest_params,
-            }),
-        );
         to_block.add_pred(self.context, &self.block);
-        self.context.blocks[self.block.0].instructions.push(br_val);
-        br_val
+        self.insert(Instruction::Branch(BranchToWithArgs {
+            block: to_block,
+            args: dest_params,
+        }))
     }
 
     pub fn call(self, function: Function, args: &[Value]) -> Value {
-        make_instruction!(self, Instruction::Call(function, args.to_vec()))
+        self.insert(Instruction::Call(function, args.to_vec()))
     }
 
     pub fn cast_ptr(self, val: Value, ty: Type, offs: u64) -> Value {
-        make_instruction!(self, Instruction::CastPtr(val, ty, offs))
+        self.insert(Instruction::CastPtr(val, ty, offs))
     }
 
     pub fn cmp(self, pred: Predicate, lhs_value: Value, rhs_value: Value) -> Value {
-        make_instruction!(self, Instruction::Cmp(pred, lhs_value, rhs_value))
+        self.insert(Instruction::Cmp(pred, lhs_value, rhs_value))
     }
 
     pub fn conditional_branch(
@@ -761,24 +816,19 @@ impl<'a> InstructionInserter<'a> {
         true_dest_params: Vec<Value>,
         false_dest_params: Vec<Value>,
     ) -> Value {
-        let cbr_val = Value::new_instruction(
-            self.context,
-            Instruction::ConditionalBranch {
-                cond_value,
-                true_block: BranchToWithArgs {
-                    block: true_block,
-                    args: true_dest_params,
-                },
-                false_block: BranchToWithArgs {
-                    block: false_block,
-                    args: false_dest_params,
-                },
-            },
-        );
         true_block.add_pred(self.context, &self.block);
         false_block.add_pred(self.context, &self.block);
-        self.context.blocks[self.block.0].instructions.push(cbr_val);
-        cbr_val
+        self.insert(Instruction::ConditionalBranch {
+            cond_value,
+            true_block: BranchToWithArgs {
+                block: true_block,
+                args: true_dest_params,
+            },
+            false_block: BranchToWithArgs {
+                block: false_block,
+                args: false_dest_params,
+            },
+        })
     }
 
     pub fn contract_call(
@@ -790,66 +840,58 @@ impl<'a> InstructionInserter<'a> {
         asset_id: Value, // b256 asset ID of the coint being forwarded
         gas: Value,      // amount of gas to forward
     ) -> Value {
-        make_instruction!(
-            self,
-            Instruction::ContractCall {
-                return_type,
-                name,
-                params,
-                coins,
-                asset_id,
-                gas,
-            }
-        )
+        self.insert(Instruction::ContractCall {
+            return_type,
+            name,
+            params,
+            coins,
+            asset_id,
+            gas,
+        })
     }
 
     pub fn extract_element(self, array: Value, ty: Type, index_val: Value) -> Value {
-        make_instruction!(
-            self,
-            Instruction::ExtractElement {
-                array,
-                ty,
-                index_val,
-            }
-        )
+        self.insert(Instruction::ExtractElement {
+            array,
+            ty,
+            index_val,
+        })
     }
 
     pub fn extract_value(self, aggregate: Value, ty: Type, indices: Vec<u64>) -> Value {
-        make_instruction!(
-            self,
-            Instruction::ExtractValue {
-                aggregate,
-                ty,
-                indices,
-            }
-        )
+        self.insert(Instruction::ExtractValue {
+            aggregate,
+            ty,
+            indices,
+        })
+    }
+
+    pub fn gas_checkpoint(self, id: Value) -> Value {
+        self.insert(Instruction::FuelVm(FuelVmInstruction::GasCheckpoint(id)))
     }
 
     pub fn get_storage_key(self) -> Value {
-        make_instruction!(self, Instruction::FuelVm(FuelVmInstruction::GetStorageKey))
+        self.insert(Instruction::FuelVm(FuelVmInstruction::GetStorageKey))
     }
 
     pub fn gtf(self, index: Value, tx_field_id: u64) -> Value {
-        make_instruction!(
-            self,
-            Instruction::FuelVm(FuelVmInstruction::Gtf { index, tx_field_id })
-        )
+        self.insert(Instruction::FuelVm(FuelVmInstruction::Gtf {
+            index,
+            tx_field_id,
+        }))
     }
 
     pub fn get_local(self, local_var: LocalVar) -> Value {
-        make_instruction!(self, Instruction::GetLocal(local_var))
+        self.insert(Instruction::GetLocal(local_var))
     }
 
     pub fn insert_element(self, array: Value, ty: Type, value: Value, index_val: Value) -> Value {
-        make_instruction!(
-            self,
-            Instruction::InsertElement {
-                array,
-                ty,
-                value,
-                index_val,
-            }
-        )
+        self.insert(Instruction::InsertElement {
+            array,
+            ty,
+            value,
+            index_val,
+        })
     }
 
     pub fn insert_value(
@@ -859,71 +901,52 @@ impl<'a> InstructionInserter<'a> {
         value: Value,
         indices: Vec<u64>,
     ) -> Value {
-        make_instruction!(
-            self,
-            Instruction::InsertValue {
-                aggregate,
-                ty,
-                value,
-                indices,
-            }
-        )
+        self.insert(Instruction::InsertValue {
+            aggregate,
+            ty,
+            value,
+            indices,
+        })
     }
 
     pub fn int_to_ptr(self, value: Value, ty: Type) -> Value {
-        make_instruction!(self, Instruction::IntToPtr(value, ty))
+        self.insert(Instruction::IntToPtr(value, ty))
     }
 
     pub fn load(self, src_val: Value) -> Value {
-        make_instruction!(self, Instruction::Load(src_val))
+        self.insert(Instruction::Load(src_val))
     }
 
     pub fn log(self, log_val: Value, log_ty: Type, log_id: Value) -> Value {
-        make_instruction!(
-            self,
-            Instruction::FuelVm(FuelVmInstruction::Log {
-                log_val,
-                log_ty,
-                log_id
-            })
-        )
+        self.insert(Instruction::FuelVm(FuelVmInstruction::Log {
+            log_val,
+            log_ty,
+            log_id,
+        }))
     }
 
     pub fn mem_copy(self, dst_val: Value, src_val: Value, byte_len: u64) -> Value {
-        make_instruction!(
-            self,
-            Instruction::MemCopy {
-                dst_val,
-                src_val,
-                byte_len
-            }
-        )
+        self.insert(Instruction::MemCopy {
+            dst_val,
+            src_val,
+            byte_len,
+        })
     }
 
     pub fn nop(self) -> Value {
-        make_instruction!(self, Instruction::Nop)
+        self.insert(Instruction::Nop)
     }
 
     pub fn read_register(self, reg: Register) -> Value {
-        make_instruction!(
-            self,
-            Instruction::FuelVm(FuelVmInstruction::ReadRegister(reg))
-        )
+        self.insert(Instruction::FuelVm(FuelVmInstruction::ReadRegister(reg)))
     }
 
     pub fn ret(self, value: Value, ty: Type) -> Value {
-        make_instruction!(self, Instruction::Ret(value, ty))
+        self.insert(Instruction::Ret(value, ty))
     }
 
     pub fn revert(self, value: Value) -> Value {
-        let revert_val = Value::new_instruction(
-            self.context,
-            Instruction::FuelVm(FuelVmInstruction::Revert(value)),
-        );
-        self.context.blocks[self.block.0]
-            .instructions
-            .push(revert_val);
-        revert_val
+        self.insert(Instruction::FuelVm(FuelVmInstruction::Revert(value)))
     }
 
     pub fn smo(
@@ -933,25 +956,19 @@ impl<'a> InstructionInserter<'a> {
         output_index: Value,
         coins: Value,
     ) -> Value {
-        make_instruction!(
-            self,
-            Instruction::FuelVm(FuelVmInstruction::Smo {
-                recipient_and_message,
-                message_size,
-                output_index,
-                coins,
-            })
-        )
+        self.insert(Instruction::FuelVm(FuelVmInstruction::Smo {
+            recipient_and_message,
+            message_size,
+            output_index,
+            coins,
+        }))
     }
 
     pub fn state_clear(self, key: Value, number_of_slots: Value) -> Value {
-        make_instruction!(
-            self,
-            Instruction::FuelVm(FuelVmInstruction::StateClear {
-                key,
-                number_of_slots
-            })
-        )
+        self.insert(Instruction::FuelVm(FuelVmInstruction::StateClear {
+            key,
+            number_of_slots,
+        }))
     }
 
     pub fn state_load_quad_word(
@@ -960,21 +977,15 @@ impl<'a> InstructionInserter<'a> {
         key: Value,
         number_of_slots: Value,
     ) -> Value {
-        make_instruction!(
-            self,
-            Instruction::FuelVm(FuelVmInstruction::StateLoadQuadWord {
-                load_val,
-                key,
-                number_of_slots
-            })
-        )
+        self.insert(Instruction::FuelVm(FuelVmInstruction::StateLoadQuadWord {
+            load_val,
+            key,
+            number_of_slots,
+        }))
     }
 
     pub fn state_load_word(self, key: Value) -> Value {
-        make_instruction!(
-            self,
-            Instruction::FuelVm(FuelVmInstruction::StateLoadWord(key))
-        )
+        self.insert(Instruction::FuelVm(FuelVmInstruction::StateLoadWord(key)))
     }
 
     pub fn state_store_quad_word(
@@ -983,30 +994,24 @@ impl<'a> InstructionInserter<'a> {
         key: Value,
         number_of_slots: Value,
     ) -> Value {
-        make_instruction!(
-            self,
-            Instruction::FuelVm(FuelVmInstruction::StateStoreQuadWord {
-                stored_val,
-                key,
-                number_of_slots
-            })
-        )
+        self.insert(Instruction::FuelVm(FuelVmInstruction::StateStoreQuadWord {
+            stored_val,
+            key,
+            number_of_slots,
+        }))
     }
 
     pub fn state_store_word(self, stored_val: Value, key: Value) -> Value {
-        make_instruction!(
-            self,
-            Instruction::FuelVm(FuelVmInstruction::StateStoreWord { stored_val, key })
-        )
+        self.insert(Instruction::FuelVm(FuelVmInstruction::StateStoreWord {
+            stored_val,
+            key,
+        }))
     }
 
     pub fn store(self, dst_val: Value, stored_val: Value) -> Value {
-        make_instruction!(
-            self,
-            Instruction::Store {
-                dst_val,
-                stored_val,
-            }
-        )
+        self.insert(Instruction::Store {
+            dst_val,
+            stored_val,
+        })
     }
 }
@@ -0,0 +1,657 @@
+//! An interpreter for the IR.
+//!
+//! It's used to check that transforms preserve the observable behaviour of a function: what it
+//! returns, or whether it reverts or panics, along with the storage it leaves behind and the
+//! values it logs.  See [`interpret`].
+//!
+//! Values are modelled as the Fuel VM backend models them, each taking a single word.  The unit,
+//! bools and integers are held directly and every other type is held as the address of its value
+//! in a flat memory, laid out as the backend lays it out with every scalar taking a whole word.
+//! Memory is never freed, so a function may return the address of one of its locals.
+//!
+//! Instructions which depend on the VM state beyond storage, such as ASM blocks and contract calls,
+//! aren't supported and stop the interpreter with [`InterpretError::Unsupported`].
+
+use std::collections::BTreeMap;
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    BinaryOpKind, Block, BranchToWithArgs, Constant, ConstantValue, Context, FuelVmInstruction,
+    Function, Instruction, Predicate, Type, TypeContent, Value, ValueDatum,
+};
+
+/// The most instructions executed by a single call to [`interpret`], so that it stops on
+/// inputs which loop for a long time or forever.
+pub const INTERPRET_STEP_LIMIT: u64 = 100_000;
+
+/// An argument passed to the function being interpreted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InterpretArg {
+    Uint(u64),
+    Bool(bool),
+    B256([u8; 32]),
+}
+
+/// How a call to [`interpret`] finished.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The function returned a value, given as the bytes of its layout in memory.
+    Return(Vec<u8>),
+    /// The function reverted with the given code.
+    Revert(u64),
+    /// The function panicked, e.g. on an overflow or an out of bounds memory access.
+    Panic,
+}
+
+/// Everything observable about a call to [`interpret`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Execution {
+    pub outcome: Outcome,
+    /// The storage slots written by the function, which start out empty, by key.
+    pub storage: BTreeMap<[u8; 32], [u8; 32]>,
+    /// The log ids and the bytes of the values logged, in the order they were logged.
+    pub logs: Vec<(u64, Vec<u8>)>,
+}
+
+/// A reason the interpreter couldn't finish.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InterpretError {
+    /// The function uses something the interpreter doesn't model, named here.
+    Unsupported(String),
+    /// The function didn't finish within [`INTERPRET_STEP_LIMIT`] instructions.
+    StepLimit,
+}
+
+/// Interpret a call to `function` with `args`.
+pub fn interpret(
+    context: &Context,
+    function: Function,
+    args: &[InterpretArg],
+) -> Result<Execution, InterpretError> {
+    let mut machine = Machine {
+        context,
+        // Address zero is never handed out.
+        memory: vec![0; WORD],
+        storage: BTreeMap::new(),
+        logs: Vec::new(),
+        steps: 0,
+    };
+    let arg_words = args
+        .iter()
+        .map(|arg| match arg {
+            InterpretArg::Uint(n) => *n,
+            InterpretArg::Bool(b) => *b as u64,
+            InterpretArg::B256(bytes) => {
+                let addr = machine.alloc(32);
+                machine.memory[addr as usize..addr as usize + 32].copy_from_slice(bytes);
+                addr
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let outcome = match machine.call(function, &arg_words)? {
+        Ok(word) => {
+            let ty = function.get_return_type(context);
+            match machine.value_bytes(ty, word) {
+                Ok(bytes) => Outcome::Return(bytes),
+                Err(halt) => halt.into(),
+            }
+        }
+        Err(halt) => halt.into(),
+    };
+    Ok(Execution {
+        outcome,
+        storage: machine.storage,
+        logs: machine.logs,
+    })
+}
+
+const WORD: usize = 8;
+
+/// Why execution stopped early.
+enum Halt {
+    Revert(u64),
+    Panic,
+}
+
+impl From<Halt> for Outcome {
+    fn from(halt: Halt) -> Self {
+        match halt {
+            Halt::Revert(code) => Outcome::Revert(code),
+            Halt::Panic => Outcome::Panic,
+        }
+    }
+}
+
+/// The result of executing part of a function.  The outer `Result` stops the interpreter and the
+/// inner one stops the program being interpreted.
+type Step<T> = Result<Result<T, Halt>, InterpretError>;
+
+/// Unwrap a [`Step`], returning early from the enclosing function if execution has stopped.
+macro_rules! step {
+    ($e: expr) => {
+        match $e? {
+            Ok(value) => value,
+            Err(halt) => return Ok(Err(halt)),
+        }
+    };
+}
+
+struct Machine<'a> {
+    context: &'a Context,
+    memory: Vec<u8>,
+    storage: BTreeMap<[u8; 32], [u8; 32]>,
+    logs: Vec<(u64, Vec<u8>)>,
+    steps: u64,
+}
+
+impl<'a> Machine<'a> {
+    /// Allocate `size` bytes of zeroed memory, returning their address.
+    fn alloc(&mut self, size: u64) -> u64 {
+        let addr = self.memory.len() as u64;
+        let size = (size as usize + WORD - 1) / WORD * WORD;
+        self.memory.resize(self.memory.len() + size, 0);
+        addr
+    }
+
+    fn bytes(&self, addr: u64, len: u64) -> Result<&[u8], Halt> {
+        let start = addr as usize;
+        let end = start.checked_add(len as usize).ok_or(Halt::Panic)?;
+        self.memory.get(start..end).ok_or(Halt::Panic)
+    }
+
+    fn bytes_mut(&mut self, addr: u64, len: u64) -> Result<&mut [u8], Halt> {
+        let start = addr as usize;
+        let end = start.checked_add(len as usize).ok_or(Halt::Panic)?;
+        self.memory.get_mut(start..end).ok_or(Halt::Panic)
+    }
+
+    fn read_word(&self, addr: u64) -> Result<u64, Halt> {
+        let bytes = self.bytes(addr, WORD as u64)?;
+        Ok(u64::from_be_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn write_word(&mut self, addr: u64, word: u64) -> Result<(), Halt> {
+        self.bytes_mut(addr, WORD as u64)?
+            .copy_from_slice(&word.to_be_bytes());
+        Ok(())
+    }
+
+    fn copy(&mut self, dst: u64, src: u64, len: u64) -> Result<(), Halt> {
+        let bytes = self.bytes(src, len)?.to_vec();
+        self.bytes_mut(dst, len)?.copy_from_slice(&bytes);
+        Ok(())
+    }
+
+    /// Read a value of type `ty` held in memory at `addr`.
+    fn read(&self, ty: Type, addr: u64) -> Result<u64, Halt> {
+        match is_copy_type(self.context, ty) {
+            true => self.read_word(addr),
+            false => Ok(addr),
+        }
+    }
+
+    /// Write the value `word` of type `ty` to memory at `addr`.
+    fn write(&mut self, ty: Type, addr: u64, word: u64) -> Result<(), Halt> {
+        match is_copy_type(self.context, ty) {
+            true => self.write_word(addr, word),
+            false => self.copy(addr, word, ty.size_in_bytes(self.context)),
+        }
+    }
+
+    /// The bytes of the value `word` of type `ty` as they're laid out in memory.
+    fn value_bytes(&self, ty: Type, word: u64) -> Result<Vec<u8>, Halt> {
+        match is_copy_type(self.context, ty) {
+            true => Ok(word.to_be_bytes().to_vec()),
+            false => Ok(self.bytes(word, ty.size_in_bytes(self.context))?.to_vec()),
+        }
+    }
+
+    /// Write `constant` to memory at `addr`.
+    fn write_constant(&mut self, constant: &Constant, addr: u64) -> Result<(), Halt> {
+        match &constant.value {
+            ConstantValue::Undef | ConstantValue::Unit => Ok(()),
+            ConstantValue::Bool(b) => self.write_word(addr, *b as u64),
+            ConstantValue::Uint(n) => self.write_word(addr, *n),
+            ConstantValue::B256(bytes) => {
+                self.bytes_mut(addr, 32)?.copy_from_slice(bytes);
+                Ok(())
+            }
+            ConstantValue::String(bytes) => {
+                self.bytes_mut(addr, bytes.len() as u64)?
+                    .copy_from_slice(bytes);
+                Ok(())
+            }
+            ConstantValue::Array(elems) => {
+                let mut offset = addr;
+                for elem in elems {
+                    self.write_constant(elem, offset)?;
+                    offset += elem.ty.size_in_bytes(self.context);
+                }
+                Ok(())
+            }
+            ConstantValue::Struct(fields) => {
+                let mut offset = addr;
+                for field in fields {
+                    self.write_constant(field, offset)?;
+                    offset += field.ty.size_in_bytes(self.context);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// The value of `constant`, which is allocated in memory unless it has a copy type.
+    fn constant(&mut self, constant: &Constant) -> Result<u64, Halt> {
+        match &constant.value {
+            ConstantValue::Undef | ConstantValue::Unit
+                if is_copy_type(self.context, constant.ty) =>
+            {
+                Ok(0)
+            }
+            ConstantValue::Bool(b) => Ok(*b as u64),
+            ConstantValue::Uint(n) => Ok(*n),
+            _ => {
+                let addr = self.alloc(constant.ty.size_in_bytes(self.context));
+                self.write_constant(constant, addr)?;
+                Ok(addr)
+            }
+        }
+    }
+
+    /// Call `function` with the words of its arguments, returning the word of its result.
+    fn call(&mut self, function: Function, args: &[u64]) -> Step<u64> {
+        let context = self.context;
+        let mut frame = Frame::default();
+        for ((_, arg), word) in function.args_iter(context).zip(args) {
+            frame.values.insert(*arg, *word);
+        }
+        for (_, local) in function.locals_iter(context) {
+            let ty = local.get_type(context);
+            let addr = self.alloc(ty.size_in_bytes(context));
+            if let Some(initializer) = local.get_initializer(context) {
+                step!(Ok(self.write_constant(initializer, addr)));
+            }
+            frame.locals.insert(*local, addr);
+        }
+
+        let mut block = function.get_entry_block(context);
+        'blocks: loop {
+            for instruction in block.instruction_iter(context) {
+                self.steps += 1;
+                if self.steps > INTERPRET_STEP_LIMIT {
+                    return Err(InterpretError::StepLimit);
+                }
+                match step!(self.execute(&mut frame, instruction)) {
+                    Flow::Next(word) => {
+                        frame.values.insert(instruction, word);
+                    }
+                    Flow::Jump(next) => {
+                        block = next;
+                        continue 'blocks;
+                    }
+                    Flow::Return(word) => return Ok(Ok(word)),
+                }
+            }
+            return Err(InterpretError::Unsupported(
+                "a block without a terminator".to_owned(),
+            ));
+        }
+    }
+
+    /// The word of `value` in `frame`.
+    fn value(&mut self, frame: &mut Frame, value: Value) -> Step<u64> {
+        if let Some(word) = frame.values.get(&value) {
+            return Ok(Ok(*word));
+        }
+        let word = match &self.context.values[value.0].value {
+            ValueDatum::Constant(constant) | ValueDatum::Configurable(constant) => {
+                step!(Ok(self.constant(constant)))
+            }
+            // Arguments and instructions are given their words when they're defined, so this is
+            // a use which doesn't follow its definition.
+            ValueDatum::Argument(_) | ValueDatum::Instruction(_) => {
+                return Err(InterpretError::Unsupported(
+                    "a value used before it's defined".to_owned(),
+                ))
+            }
+        };
+        // Constants are evaluated once per call, so that values inserted into them persist.
+        frame.values.insert(value, word);
+        Ok(Ok(word))
+    }
+
+    /// Pass the arguments of `branch` to its block and jump to it.
+    fn jump(&mut self, frame: &mut Frame, branch: &BranchToWithArgs) -> Step<Flow> {
+        // The arguments are all read before any are written, as they may refer to each other.
+        let mut words = Vec::with_capacity(branch.args.len());
+        for arg in &branch.args {
+            words.push(step!(self.value(frame, *arg)));
+        }
+        for (param, word) in branch.block.arg_iter(self.context).zip(words) {
+            frame.values.insert(*param, word);
+        }
+        Ok(Ok(Flow::Jump(branch.block)))
+    }
+
+    fn execute(&mut self, frame: &mut Frame, instruction: Value) -> Step<Flow> {
+        let context = self.context;
+        let unsupported = |what: &str| Err(InterpretError::Unsupported(what.to_owned()));
+        let Some(inst) = instruction.get_instruction(context) else {
+            return unsupported("a non-instruction in a block");
+        };
+        let word = match inst {
+            Instruction::AddrOf(value) | Instruction::BitCast(value, _) => {
+                step!(self.value(frame, *value))
+            }
+            Instruction::IntToPtr(value, _) => step!(self.value(frame, *value)),
+            Instruction::AsmBlock(..) => return unsupported("ASM blocks"),
+            Instruction::BinaryOp { op, arg1, arg2 } => {
+                let lhs = step!(self.value(frame, *arg1));
+                let rhs = step!(self.value(frame, *arg2));
+                let width = arg1
+                    .get_type(context)
+                    .and_then(|ty| ty.get_uint_width(context))
+                    .unwrap_or(64);
+                match binary_op(*op, width, lhs, rhs) {
+                    Some(word) => word,
+                    None => return Ok(Err(Halt::Panic)),
+                }
+            }
+            Instruction::Branch(branch) => return self.jump(frame, branch),
+            Instruction::Call(callee, args) => {
+                let mut words = Vec::with_capacity(args.len());
+                for arg in args {
+                    words.push(step!(self.value(frame, *arg)));
+                }
+                step!(self.call(*callee, &words))
+            }
+            Instruction::CastPtr(value, ty, offset) => {
+                let addr = step!(self.value(frame, *value));
+                addr.wrapping_add(ty.size_in_bytes(context) * offset)
+            }
+            Instruction::Cmp(pred, lhs_val, rhs_val) => {
+                let lhs = step!(self.value(frame, *lhs_val));
+                let rhs = step!(self.value(frame, *rhs_val));
+                let ty = lhs_val.get_type(context).unwrap();
+                let result = match (pred, is_copy_type(context, ty)) {
+                    (Predicate::Equal, true) => lhs == rhs,
+                    (Predicate::LessThan, true) => lhs < rhs,
+                    (Predicate::GreaterThan, true) => lhs > rhs,
+                    (Predicate::Equal, false) => {
+                        let size = ty.size_in_bytes(context);
+                        step!(Ok(self.bytes(lhs, size).map(<[u8]>::to_vec)))
+                            == step!(Ok(self.bytes(rhs, size)))
+                    }
+                    (_, false) => return unsupported("ordering of non-copy types"),
+                };
+                result as u64
+            }
+            Instruction::ConditionalBranch {
+                cond_value,
+                true_block,
+                false_block,
+            } => {
+                return match step!(self.value(frame, *cond_value)) {
+                    0 => self.jump(frame, false_block),
+                    _ => self.jump(frame, true_block),
+                };
+            }
+            Instruction::ContractCall { .. } => return unsupported("contract calls"),
+            Instruction::ExtractElement {
+                array,
+                ty,
+                index_val,
+            } => {
+                let addr = step!(self.element_addr(frame, *array, *ty, *index_val));
+                let elem_ty = ty.get_array_elem_type(context).unwrap();
+                step!(Ok(self.read(elem_ty, addr)))
+            }
+            Instruction::ExtractValue {
+                aggregate,
+                ty,
+                indices,
+            } => {
+                let base = step!(self.value(frame, *aggregate));
+                let (offset, field_ty) = field_offset(context, *ty, indices);
+                step!(Ok(self.read(field_ty, base + offset)))
+            }
+            Instruction::FuelVm(inst) => return self.execute_fuel_vm(frame, inst),
+            Instruction::GetLocal(local) => match frame.locals.get(local) {
+                Some(addr) => *addr,
+                None => return unsupported("a local of another function"),
+            },
+            Instruction::InsertElement {
+                array,
+                ty,
+                value,
+                index_val,
+            } => {
+                let addr = step!(self.element_addr(frame, *array, *ty, *index_val));
+                let elem_ty = ty.get_array_elem_type(context).unwrap();
+                let word = step!(self.value(frame, *value));
+                step!(Ok(self.write(elem_ty, addr, word)));
+                step!(self.value(frame, *array))
+            }
+            Instruction::InsertValue {
+                aggregate,
+                ty,
+                value,
+                indices,
+            } => {
+                let base = step!(self.value(frame, *aggregate));
+                let (offset, field_ty) = field_offset(context, *ty, indices);
+                let word = step!(self.value(frame, *value));
+                step!(Ok(self.write(field_ty, base + offset, word)));
+                base
+            }
+            Instruction::Load(ptr) => {
+                let addr = step!(self.value(frame, *ptr));
+                let ty = instruction.get_type(context).unwrap();
+                match is_copy_type(context, ty) {
+                    true => step!(Ok(self.read_word(addr))),
+                    // Loaded aggregates are copied, so that later stores to the pointer don't
+                    // change them.
+                    false => {
+                        let size = ty.size_in_bytes(context);
+                        let copy = self.alloc(size);
+                        step!(Ok(self.copy(copy, addr, size)));
+                        copy
+                    }
+                }
+            }
+            Instruction::MemCopy {
+                dst_val,
+                src_val,
+                byte_len,
+            } => {
+                let dst = step!(self.value(frame, *dst_val));
+                let src = step!(self.value(frame, *src_val));
+                step!(Ok(self.copy(dst, src, *byte_len)));
+                0
+            }
+            Instruction::Nop => 0,
+            Instruction::Ret(value, _) => {
+                return Ok(Ok(Flow::Return(step!(self.value(frame, *value)))));
+            }
+            Instruction::Store {
+                dst_val,
+                stored_val,
+            } => {
+                let dst = step!(self.value(frame, *dst_val));
+                let word = step!(self.value(frame, *stored_val));
+                let ty = stored_val.get_type(context).unwrap();
+                step!(Ok(self.write(ty, dst, word)));
+                0
+            }
+        };
+        Ok(Ok(Flow::Next(word)))
+    }
+
+    fn execute_fuel_vm(&mut self, frame: &mut Frame, inst: &FuelVmInstruction) -> Step<Flow> {
+        let unsupported = |what: &str| Err(InterpretError::Unsupported(what.to_owned()));
+        let word = match inst {
+            FuelVmInstruction::GasCheckpoint(_) => 0,
+            FuelVmInstruction::GetStorageKey => return unsupported("get_storage_key"),
+            FuelVmInstruction::Gtf { .. } => return unsupported("gtf"),
+            FuelVmInstruction::Log {
+                log_val,
+                log_ty,
+                log_id,
+            } => {
+                let word = step!(self.value(frame, *log_val));
+                let id = step!(self.value(frame, *log_id));
+                let bytes = step!(Ok(self.value_bytes(*log_ty, word)));
+                self.logs.push((id, bytes));
+                0
+            }
+            FuelVmInstruction::ReadRegister(_) => return unsupported("read_register"),
+            FuelVmInstruction::Revert(code) => {
+                return Ok(Err(Halt::Revert(step!(self.value(frame, *code)))));
+            }
+            FuelVmInstruction::Smo { .. } => return unsupported("smo"),
+            FuelVmInstruction::StateClear {
+                key,
+                number_of_slots,
+            } => {
+                let key = step!(self.key(frame, *key));
+                for slot in 0..step!(self.value(frame, *number_of_slots)) {
+                    self.storage.remove(&add_to_key(key, slot));
+                }
+                0
+            }
+            FuelVmInstruction::StateLoadQuadWord {
+                load_val,
+                key,
+                number_of_slots,
+            } => {
+                let addr = step!(self.value(frame, *load_val));
+                let key = step!(self.key(frame, *key));
+                for slot in 0..step!(self.value(frame, *number_of_slots)) {
+                    let value = self
+                        .storage
+                        .get(&add_to_key(key, slot))
+                        .copied()
+                        .unwrap_or_default();
+                    step!(Ok(self.bytes_mut(addr + slot * 32, 32))).copy_from_slice(&value);
+                }
+                0
+            }
+            FuelVmInstruction::StateLoadWord(key) => {
+                let key = step!(self.key(frame, *key));
+                let value = self.storage.get(&key).copied().unwrap_or_default();
+                u64::from_be_bytes(value[..WORD].try_into().unwrap())
+            }
+            FuelVmInstruction::StateStoreQuadWord {
+                stored_val,
+                key,
+                number_of_slots,
+            } => {
+                let addr = step!(self.value(frame, *stored_val));
+                let key = step!(self.key(frame, *key));
+                for slot in 0..step!(self.value(frame, *number_of_slots)) {
+                    let value = step!(Ok(self.bytes(addr + slot * 32, 32)));
+                    let value = value.try_into().unwrap();
+                    self.storage.insert(add_to_key(key, slot), value);
+                }
+                0
+            }
+            FuelVmInstruction::StateStoreWord { stored_val, key } => {
+                let word = step!(self.value(frame, *stored_val));
+                let key = step!(self.key(frame, *key));
+                let mut value = [0; 32];
+                value[..WORD].copy_from_slice(&word.to_be_bytes());
+                self.storage.insert(key, value);
+                0
+            }
+        };
+        Ok(Ok(Flow::Next(word)))
+    }
+
+    /// The storage key pointed to by `key`.
+    fn key(&mut self, frame: &mut Frame, key: Value) -> Step<[u8; 32]> {
+        let addr = step!(self.value(frame, key));
+        Ok(self.bytes(addr, 32).map(|bytes| bytes.try_into().unwrap()))
+    }
+
+    /// The address of the element at `index_val` in the array `array` of type `ty`, panicking if
+    /// it's out of bounds.
+    fn element_addr(
+        &mut self,
+        frame: &mut Frame,
+        array: Value,
+        ty: Type,
+        index_val: Value,
+    ) -> Step<u64> {
+        let base = step!(self.value(frame, array));
+        let index = step!(self.value(frame, index_val));
+        let (elem_ty, len) = match ty.get_content(self.context) {
+            TypeContent::Array(elem_ty, len) => (*elem_ty, *len),
+            _ => {
+                return Err(InterpretError::Unsupported(
+                    "a non-array element".to_owned(),
+                ))
+            }
+        };
+        if index >= len {
+            return Ok(Err(Halt::Panic));
+        }
+        Ok(Ok(base + index * elem_ty.size_in_bytes(self.context)))
+    }
+}
+
+/// The values of a single call.
+#[derive(Default)]
+struct Frame {
+    values: FxHashMap<Value, u64>,
+    locals: FxHashMap<crate::LocalVar, u64>,
+}
+
+/// What to do after an instruction.
+enum Flow {
+    /// Continue to the next instruction, the word being the instruction's value.
+    Next(u64),
+    Jump(Block),
+    Return(u64),
+}
+
+fn is_copy_type(context: &Context, ty: Type) -> bool {
+    ty.is_unit(context) || ty.is_bool(context) || ty.is_uint(context)
+}
+
+/// The byte offset and type of the field at `indices` within a value of type `ty`.  Union
+/// variants are right aligned within the union.
+fn field_offset(context: &Context, ty: Type, indices: &[u64]) -> (u64, Type) {
+    indices.iter().fold((0, ty), |(offset, ty), idx| {
+        let field_tys = ty.get_field_types(context);
+        let field_ty = field_tys[*idx as usize];
+        let field_offset = match ty.get_content(context) {
+            TypeContent::Union(_) => ty.size_in_bytes(context) - field_ty.size_in_bytes(context),
+            _ => field_tys
+                .iter()
+                .take(*idx as usize)
+                .map(|field_ty| field_ty.size_in_bytes(context))
+                .sum(),
+        };
+        (offset + field_offset, field_ty)
+    })
+}
+
+/// The result of `op` on integers of `width` bits, or `None` if it panics.
+fn binary_op(op: BinaryOpKind, width: u8, lhs: u64, rhs: u64) -> Option<u64> {
+    match crate::PatternOp::Binary(op).fold(width, lhs, rhs)? {
+        ConstantValue::Uint(n) => Some(n),
+        _ => None,
+    }
+}
+
+/// The storage key `n` slots after `key`.
+pub(crate) fn add_to_key(mut key: [u8; 32], mut n: u64) -> [u8; 32] {
+    for byte in key.iter_mut().rev() {
+        let sum = *byte as u64 + (n & 0xff);
+        *byte = sum as u8;
+        n = (n >> 8) + (sum >> 8);
+    }
+    key
+}
@@ -311,6 +311,28 @@ impl Type {
             _ => vec![],
         }
     }
+
+    /// Get the size in bytes of a value of this type in memory.  Copy types take a whole word and
+    /// strings are padded to a whole number of words.
+    pub fn size_in_bytes(&self, context: &Context) -> u64 {
+        const WORD: u64 = 8;
+        match self.get_content(context) {
+            TypeContent::Unit | TypeContent::Bool | TypeContent::Uint(_) => WORD,
+            TypeContent::Slice => 2 * WORD,
+            TypeContent::B256 => 32,
+            TypeContent::String(n) => (n + WORD - 1) / WORD * WORD,
+            TypeContent::Array(elem_ty, n) => n * elem_ty.size_in_bytes(context),
+            TypeContent::Struct(field_tys) => field_tys
+                .iter()
+                .map(|field_ty| field_ty.size_in_bytes(context))
+                .sum(),
+            TypeContent::Union(field_tys) => field_tys
+                .iter()
+                .map(|field_ty| field_ty.size_in_bytes(context))
+                .max()
+                .unwrap_or(0),
+        }
+    }
 }
 
 /// A helper to check if an Option<Type> value is of a particular Type.
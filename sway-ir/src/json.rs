@@ -0,0 +1,641 @@
+//! A JSON representation of a [`Module`], for external tools which would otherwise have to
+//! scrape the textual IR.
+//!
+//! The module is an object holding its `kind`, `configurables` and `functions`, along with two
+//! tables which everything else refers to:
+//!
+//! - `types` maps the name of each type used, as it's printed in the textual IR, to its structure.
+//!   Types are always referred to by this name.
+//! - `metadata` is an array of the metadata used.  Metadata are always referred to by their
+//!   position in it, and each entry comes after any it refers to.
+//!
+//! Functions hold their signature, `locals` and `blocks`, and each block holds its `args`, `preds`
+//! and `instructions`.  An instruction has an `op` named by its textual IR mnemonic, its result
+//! `name` and `type` if it has one, and then fields particular to the op.  Values are named
+//! exactly as they are by the printer.  Operands are objects holding one of `value`, the name of
+//! an argument or instruction; `constant`, a constant inline; or `configurable`, the name of a
+//! configurable.
+
+use std::collections::HashMap;
+
+use rustc_hash::FxHashMap;
+use serde_json::{json, Map, Value as Json};
+
+use crate::{
+    asm::AsmArg,
+    block::Block,
+    constant::{Constant, ConstantValue},
+    context::Context,
+    function::Function,
+    instruction::{
+        BinaryOpKind, BranchToWithArgs, FuelVmInstruction, Instruction, Predicate, Register,
+    },
+    irtype::{Type, TypeContent},
+    metadata::{MetadataIndex, Metadatum},
+    module::{Kind, Module},
+    printer::module_value_names,
+    value::{Value, ValueDatum},
+};
+
+impl Module {
+    /// Return a structured JSON representation of this module.  See the [`json`](crate::json)
+    /// module for its layout.
+    pub fn to_json(&self, context: &Context) -> Json {
+        let mut exporter = JsonExporter {
+            context,
+            names: module_value_names(context, *self),
+            types: Map::new(),
+            metadata_ids: FxHashMap::default(),
+            metadata: Vec::new(),
+        };
+
+        let configurables = self
+            .global_configurable_iter(context)
+            .map(|(call_path, value)| exporter.configurable_to_json(call_path, value))
+            .collect::<Vec<_>>();
+        let functions = self
+            .function_iter(context)
+            .map(|function| exporter.function_to_json(function))
+            .collect::<Vec<_>>();
+
+        json!({
+            "kind": match self.get_kind(context) {
+                Kind::Contract => "contract",
+                Kind::Library => "library",
+                Kind::Predicate => "predicate",
+                Kind::Script => "script",
+            },
+            "configurables": configurables,
+            "functions": functions,
+            "types": exporter.types,
+            "metadata": exporter.metadata,
+        })
+    }
+}
+
+struct JsonExporter<'a> {
+    context: &'a Context,
+    names: HashMap<Value, String>,
+    types: Map<String, Json>,
+    metadata_ids: FxHashMap<MetadataIndex, usize>,
+    metadata: Vec<Json>,
+}
+
+impl<'a> JsonExporter<'a> {
+    // Add the type to the table if it's new, and return its name.
+    fn type_to_json(&mut self, ty: Type) -> Json {
+        let context = self.context;
+        let name = ty.as_string(context);
+        if !self.types.contains_key(&name) {
+            let structure = match ty.get_content(context) {
+                TypeContent::Unit => json!({ "kind": "unit" }),
+                TypeContent::Bool => json!({ "kind": "bool" }),
+                TypeContent::Uint(width) => json!({ "kind": "uint", "width": width }),
+                TypeContent::B256 => json!({ "kind": "b256" }),
+                TypeContent::String(len) => json!({ "kind": "string", "length": len }),
+                TypeContent::Array(elm_ty, len) => {
+                    json!({ "kind": "array", "element": self.type_to_json(*elm_ty), "length": len })
+                }
+                TypeContent::Union(fields) => json!({
+                    "kind": "union",
+                    "fields": fields.iter().map(|field| self.type_to_json(*field)).collect::<Vec<_>>(),
+                }),
+                TypeContent::Struct(fields) => json!({
+                    "kind": "struct",
+                    "fields": fields.iter().map(|field| self.type_to_json(*field)).collect::<Vec<_>>(),
+                }),
+                TypeContent::Slice => json!({ "kind": "slice" }),
+            };
+            self.types.insert(name.clone(), structure);
+        }
+        Json::String(name)
+    }
+
+    // Add the metadata to the table if they're new, and return their position.
+    fn metadata_to_json(&mut self, md_idx: Option<MetadataIndex>) -> Json {
+        match md_idx {
+            None => Json::Null,
+            Some(md_idx) => json!(self.metadata_id(md_idx)),
+        }
+    }
+
+    fn metadata_id(&mut self, md_idx: MetadataIndex) -> usize {
+        if let Some(id) = self.metadata_ids.get(&md_idx) {
+            return *id;
+        }
+        let md = self.metadatum_to_json(&self.context.metadata[md_idx.0]);
+        let id = self.metadata.len();
+        self.metadata.push(md);
+        self.metadata_ids.insert(md_idx, id);
+        id
+    }
+
+    fn metadatum_to_json(&mut self, md: &'a Metadatum) -> Json {
+        match md {
+            Metadatum::Integer(n) => json!({ "integer": n }),
+            Metadatum::Index(idx) => json!({ "index": self.metadata_id(*idx) }),
+            Metadatum::String(s) => json!({ "string": s }),
+            Metadatum::Struct(tag, fields) => json!({
+                "struct": tag,
+                "fields": fields
+                    .iter()
+                    .map(|field| self.metadatum_to_json(field))
+                    .collect::<Vec<_>>(),
+            }),
+            Metadatum::List(idcs) => json!({
+                "list": idcs.iter().map(|idx| self.metadata_id(*idx)).collect::<Vec<_>>(),
+            }),
+        }
+    }
+
+    fn constant_to_json(&mut self, constant: &Constant) -> Json {
+        let ty = self.type_to_json(constant.ty);
+        let (kind, value) = match &constant.value {
+            ConstantValue::Undef => ("undef", Json::Null),
+            ConstantValue::Unit => ("unit", Json::Null),
+            ConstantValue::Bool(b) => ("bool", json!(b)),
+            ConstantValue::Uint(n) => ("uint", json!(n)),
+            ConstantValue::B256(bs) => (
+                "b256",
+                json!(format!(
+                    "0x{}",
+                    bs.iter().map(|b| format!("{b:02x}")).collect::<String>()
+                )),
+            ),
+            // Strings needn't be UTF-8, in which case their bytes are given instead.
+            ConstantValue::String(bs) => (
+                "string",
+                match std::str::from_utf8(bs) {
+                    Ok(s) => json!(s),
+                    Err(_) => json!(bs),
+                },
+            ),
+            ConstantValue::Array(elems) => (
+                "array",
+                json!(elems
+                    .iter()
+                    .map(|elem| self.constant_to_json(elem))
+                    .collect::<Vec<_>>()),
+            ),
+            ConstantValue::Struct(fields) => (
+                "struct",
+                json!(fields
+                    .iter()
+                    .map(|field| self.constant_to_json(field))
+                    .collect::<Vec<_>>()),
+            ),
+        };
+        json!({ "type": ty, "kind": kind, "value": value })
+    }
+
+    fn name(&self, value: &Value) -> Json {
+        self.names
+            .get(value)
+            .map_or(Json::Null, |name| Json::String(name.clone()))
+    }
+
+    fn operand_to_json(&mut self, value: &Value) -> Json {
+        let context = self.context;
+        match &context.values[value.0].value {
+            ValueDatum::Constant(constant) => json!({
+                "constant": self.constant_to_json(constant),
+                "metadata": self.metadata_to_json(value.get_metadata(context)),
+            }),
+            ValueDatum::Configurable(_) => json!({ "configurable": self.name(value) }),
+            ValueDatum::Argument(_) | ValueDatum::Instruction(_) => {
+                json!({ "value": self.name(value) })
+            }
+        }
+    }
+
+    fn operands_to_json(&mut self, values: &[Value]) -> Json {
+        json!(values
+            .iter()
+            .map(|value| self.operand_to_json(value))
+            .collect::<Vec<_>>())
+    }
+
+    fn branch_to_json(&mut self, branch: &BranchToWithArgs) -> Json {
+        json!({
+            "block": branch.block.get_label(self.context),
+            "args": self.operands_to_json(&branch.args),
+        })
+    }
+
+    fn configurable_to_json(&mut self, call_path: &[String], value: &Value) -> Json {
+        let context = self.context;
+        let constant = value
+            .get_configurable(context)
+            .expect("Configurables are configurable values.");
+        json!({
+            "name": self.name(value),
+            "path": call_path,
+            "value": self.constant_to_json(constant),
+            "metadata": self.metadata_to_json(value.get_metadata(context)),
+        })
+    }
+
+    fn arg_to_json(&mut self, name: Json, arg: &Value) -> Json {
+        let context = self.context;
+        let (ty, by_ref) = arg
+            .get_argument_type_and_byref(context)
+            .expect("Arguments are argument values.");
+        json!({
+            "name": name,
+            "type": self.type_to_json(ty),
+            "by_ref": by_ref,
+            "metadata": self.metadata_to_json(arg.get_metadata(context)),
+        })
+    }
+
+    fn function_to_json(&mut self, function: Function) -> Json {
+        let context = self.context;
+        let args = function
+            .args_iter(context)
+            .map(|(name, arg)| self.arg_to_json(json!(name), arg))
+            .collect::<Vec<_>>();
+        let locals = function
+            .locals_iter(context)
+            .map(|(name, local)| {
+                json!({
+                    "name": name,
+                    "type": self.type_to_json(local.get_type(context)),
+                    "initializer": local
+                        .get_initializer(context)
+                        .map_or(Json::Null, |initializer| self.constant_to_json(initializer)),
+                })
+            })
+            .collect::<Vec<_>>();
+        let blocks = function
+            .block_iter(context)
+            .map(|block| self.block_to_json(function, block))
+            .collect::<Vec<_>>();
+
+        json!({
+            "name": function.get_name(context),
+            "public": context.functions[function.0].is_public,
+            "entry": function.is_entry(context),
+            "selector": function.get_selector(context).map(|selector| {
+                selector.iter().map(|b| format!("{b:02x}")).collect::<String>()
+            }),
+            "attributes": function
+                .get_attributes(context)
+                .iter()
+                .map(|attribute| attribute.as_str())
+                .collect::<Vec<_>>(),
+            "args": args,
+            "return_type": self.type_to_json(function.get_return_type(context)),
+            "metadata": self.metadata_to_json(function.get_metadata(context)),
+            "locals": locals,
+            "blocks": blocks,
+        })
+    }
+
+    fn block_to_json(&mut self, function: Function, block: Block) -> Json {
+        let context = self.context;
+        let args = block
+            .arg_iter(context)
+            .map(|arg| self.arg_to_json(self.name(arg), arg))
+            .collect::<Vec<_>>();
+        // Predecessors are unordered, so give them in block order.
+        let preds = function
+            .block_iter(context)
+            .filter(|pred| block.pred_iter(context).any(|p| p == pred))
+            .map(|pred| pred.get_label(context))
+            .collect::<Vec<_>>();
+        let instructions = block
+            .instruction_iter(context)
+            .map(|ins| self.instruction_to_json(function, ins))
+            .collect::<Vec<_>>();
+
+        json!({
+            "label": block.get_label(context),
+            "args": args,
+            "preds": preds,
+            "instructions": instructions,
+        })
+    }
+
+    fn instruction_to_json(&mut self, function: Function, ins: Value) -> Json {
+        let context = self.context;
+        let instruction = ins
+            .get_instruction(context)
+            .expect("Blocks hold instructions.");
+        let (op, mut fields) = match instruction {
+            Instruction::AddrOf(value) => {
+                ("addr_of", json!({ "value": self.operand_to_json(value) }))
+            }
+            Instruction::AsmBlock(asm, args) => {
+                let content = asm.get_content(context);
+                let args = args
+                    .iter()
+                    .map(|AsmArg { name, initializer }| {
+                        json!({
+                            "name": name.as_str(),
+                            "initializer": initializer
+                                .map_or(Json::Null, |initializer| self.operand_to_json(&initializer)),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                let body = content
+                    .body
+                    .iter()
+                    .map(|asm_ins| {
+                        json!({
+                            "op": asm_ins.name.as_str(),
+                            "args": asm_ins.args.iter().map(|arg| arg.as_str()).collect::<Vec<_>>(),
+                            "immediate": asm_ins.immediate.as_ref().map(|imm| imm.as_str()),
+                            "metadata": self.metadata_to_json(asm_ins.metadata),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                (
+                    "asm",
+                    json!({
+                        "args": args,
+                        "body": body,
+                        "return_name": content.return_name.as_ref().map(|name| name.as_str()),
+                    }),
+                )
+            }
+            Instruction::BinaryOp { op, arg1, arg2 } => (
+                match op {
+                    BinaryOpKind::Add => "add",
+                    BinaryOpKind::Sub => "sub",
+                    BinaryOpKind::Mul => "mul",
+                    BinaryOpKind::Div => "div",
+                    BinaryOpKind::And => "and",
+                    BinaryOpKind::Or => "or",
+                    BinaryOpKind::Xor => "xor",
+                },
+                json!({
+                    "arg1": self.operand_to_json(arg1),
+                    "arg2": self.operand_to_json(arg2),
+                }),
+            ),
+            Instruction::BitCast(value, _) => {
+                ("bitcast", json!({ "value": self.operand_to_json(value) }))
+            }
+            Instruction::Branch(to_block) => {
+                ("br", json!({ "target": self.branch_to_json(to_block) }))
+            }
+            Instruction::Call(function, args) => (
+                "call",
+                json!({
+                    "function": function.get_name(context),
+                    "args": self.operands_to_json(args),
+                }),
+            ),
+            Instruction::CastPtr(value, _, offset) => (
+                "cast_ptr",
+                json!({ "value": self.operand_to_json(value), "offset": offset }),
+            ),
+            Instruction::Cmp(pred, lhs, rhs) => (
+                "cmp",
+                json!({
+                    "predicate": match pred {
+                        Predicate::Equal => "eq",
+                        Predicate::LessThan => "lt",
+                        Predicate::GreaterThan => "gt",
+                    },
+                    "lhs": self.operand_to_json(lhs),
+                    "rhs": self.operand_to_json(rhs),
+                }),
+            ),
+            Instruction::ConditionalBranch {
+                cond_value,
+                true_block,
+                false_block,
+            } => (
+                "cbr",
+                json!({
+                    "cond": self.operand_to_json(cond_value),
+                    "true_target": self.branch_to_json(true_block),
+                    "false_target": self.branch_to_json(false_block),
+                }),
+            ),
+            Instruction::ContractCall {
+                name,
+                params,
+                coins,
+                asset_id,
+                gas,
+                ..
+            } => (
+                "contract_call",
+                json!({
+                    "callee": name,
+                    "params": self.operand_to_json(params),
+                    "coins": self.operand_to_json(coins),
+                    "asset_id": self.operand_to_json(asset_id),
+                    "gas": self.operand_to_json(gas),
+                }),
+            ),
+            Instruction::ExtractElement {
+                array, index_val, ..
+            } => (
+                "extract_element",
+                json!({
+                    "array": self.operand_to_json(array),
+                    "index": self.operand_to_json(index_val),
+                }),
+            ),
+            Instruction::ExtractValue {
+                aggregate, indices, ..
+            } => (
+                "extract_value",
+                json!({
+                    "aggregate": self.operand_to_json(aggregate),
+                    "indices": indices,
+                }),
+            ),
+            Instruction::FuelVm(fuel_vm_instr) => self.fuel_vm_instruction_to_json(fuel_vm_instr),
+            Instruction::GetLocal(local) => (
+                "get_local",
+                json!({
+                    "local": function.lookup_local_name(context, local),
+                }),
+            ),
+            Instruction::InsertElement {
+                array,
+                value,
+                index_val,
+                ..
+            } => (
+                "insert_element",
+                json!({
+                    "array": self.operand_to_json(array),
+                    "value": self.operand_to_json(value),
+                    "index": self.operand_to_json(index_val),
+                }),
+            ),
+            Instruction::InsertValue {
+                aggregate,
+                value,
+                indices,
+                ..
+            } => (
+                "insert_value",
+                json!({
+                    "aggregate": self.operand_to_json(aggregate),
+                    "value": self.operand_to_json(value),
+                    "indices": indices,
+                }),
+            ),
+            Instruction::IntToPtr(value, _) => (
+                "int_to_ptr",
+                json!({ "value": self.operand_to_json(value) }),
+            ),
+            Instruction::Load(src_val) => ("load", json!({ "src": self.operand_to_json(src_val) })),
+            Instruction::MemCopy {
+                dst_val,
+                src_val,
+                byte_len,
+            } => (
+                "mem_copy",
+                json!({
+                    "dst": self.operand_to_json(dst_val),
+                    "src": self.operand_to_json(src_val),
+                    "byte_len": byte_len,
+                }),
+            ),
+            Instruction::Nop => ("nop", json!({})),
+            Instruction::Ret(value, _) => ("ret", json!({ "value": self.operand_to_json(value) })),
+            Instruction::Store {
+                dst_val,
+                stored_val,
+            } => (
+                "store",
+                json!({
+                    "dst": self.operand_to_json(dst_val),
+                    "value": self.operand_to_json(stored_val),
+                }),
+            ),
+        };
+
+        // Every instruction has these, but only those with a result have a name.
+        let fields = fields.as_object_mut().unwrap();
+        fields.insert("op".into(), json!(op));
+        let name = self.name(&ins);
+        fields.insert("name".into(), name);
+        let ty = match ins.get_type(context) {
+            Some(ty) => self.type_to_json(ty),
+            None => Json::Null,
+        };
+        fields.insert("type".into(), ty);
+        let md = self.metadata_to_json(ins.get_metadata(context));
+        fields.insert("metadata".into(), md);
+        Json::Object(std::mem::take(fields))
+    }
+
+    fn fuel_vm_instruction_to_json(
+        &mut self,
+        instruction: &FuelVmInstruction,
+    ) -> (&'static str, Json) {
+        match instruction {
+            FuelVmInstruction::GasCheckpoint(id) => {
+                ("gas_checkpoint", json!({ "id": self.operand_to_json(id) }))
+            }
+            FuelVmInstruction::GetStorageKey => ("get_storage_key", json!({})),
+            FuelVmInstruction::Gtf { index, tx_field_id } => (
+                "gtf",
+                json!({ "index": self.operand_to_json(index), "tx_field_id": tx_field_id }),
+            ),
+            FuelVmInstruction::Log {
+                log_val,
+                log_ty,
+                log_id,
+            } => (
+                "log",
+                json!({
+                    "value": self.operand_to_json(log_val),
+                    "value_type": self.type_to_json(*log_ty),
+                    "id": self.operand_to_json(log_id),
+                }),
+            ),
+            FuelVmInstruction::ReadRegister(reg) => (
+                "read_register",
+                json!({
+                    "register": match reg {
+                        Register::Of => "of",
+                        Register::Pc => "pc",
+                        Register::Ssp => "ssp",
+                        Register::Sp => "sp",
+                        Register::Fp => "fp",
+                        Register::Hp => "hp",
+                        Register::Error => "err",
+                        Register::Ggas => "ggas",
+                        Register::Cgas => "cgas",
+                        Register::Bal => "bal",
+                        Register::Is => "is",
+                        Register::Ret => "ret",
+                        Register::Retl => "retl",
+                        Register::Flag => "flag",
+                    }
+                }),
+            ),
+            FuelVmInstruction::Revert(value) => {
+                ("revert", json!({ "value": self.operand_to_json(value) }))
+            }
+            FuelVmInstruction::Smo {
+                recipient_and_message,
+                message_size,
+                output_index,
+                coins,
+            } => (
+                "smo",
+                json!({
+                    "recipient_and_message": self.operand_to_json(recipient_and_message),
+                    "message_size": self.operand_to_json(message_size),
+                    "output_index": self.operand_to_json(output_index),
+                    "coins": self.operand_to_json(coins),
+                }),
+            ),
+            FuelVmInstruction::StateClear {
+                key,
+                number_of_slots,
+            } => (
+                "state_clear",
+                json!({
+                    "key": self.operand_to_json(key),
+                    "number_of_slots": self.operand_to_json(number_of_slots),
+                }),
+            ),
+            FuelVmInstruction::StateLoadQuadWord {
+                load_val,
+                key,
+                number_of_slots,
+            } => (
+                "state_load_quad_word",
+                json!({
+                    "dst": self.operand_to_json(load_val),
+                    "key": self.operand_to_json(key),
+                    "number_of_slots": self.operand_to_json(number_of_slots),
+                }),
+            ),
+            FuelVmInstruction::StateLoadWord(key) => (
+                "state_load_word",
+                json!({ "key": self.operand_to_json(key) }),
+            ),
+            FuelVmInstruction::StateStoreQuadWord {
+                stored_val,
+                key,
+                number_of_slots,
+            } => (
+                "state_store_quad_word",
+                json!({
+                    "src": self.operand_to_json(stored_val),
+                    "key": self.operand_to_json(key),
+                    "number_of_slots": self.operand_to_json(number_of_slots),
+                }),
+            ),
+            FuelVmInstruction::StateStoreWord { stored_val, key } => (
+                "state_store_word",
+                json!({
+                    "value": self.operand_to_json(stored_val),
+                    "key": self.operand_to_json(key),
+                }),
+            ),
+        }
+    }
+}
@@ -19,6 +19,9 @@
 //!
 //! The optimization passes are found in the [optimize] module.
 //!
+//! Frontends other than the Sway compiler should construct IR using the [builder] module, which
+//! provides a stable API on top of the types described above.
+//!
 //! # Note:
 //!
 //! Most of the public data types used in this library are in fact wrappers around a handle into
@@ -41,20 +44,34 @@ pub mod analysis;
 pub use analysis::*;
 pub mod asm;
 pub use asm::*;
+pub mod binary;
+pub use binary::*;
 pub mod block;
 pub use block::*;
+pub mod builder;
+pub use builder::*;
+pub mod cache;
+pub use cache::*;
 pub mod constant;
 pub use constant::*;
 pub mod context;
 pub use context::*;
+pub mod debug_info;
+pub use debug_info::*;
 pub mod error;
 pub use error::*;
+pub mod equivalence;
 pub mod function;
 pub use function::*;
 pub mod instruction;
 pub use instruction::*;
+pub mod interpret;
+pub use interpret::*;
 pub mod irtype;
 pub use irtype::*;
+pub mod json;
+pub mod memory;
+pub use memory::*;
 pub mod metadata;
 pub use metadata::*;
 pub mod module;
@@ -63,6 +80,8 @@ pub mod optimize;
 pub use optimize::*;
 pub mod parser;
 pub use parser::*;
+pub mod pattern;
+pub use pattern::*;
 pub mod local_var;
 pub use local_var::*;
 pub mod pass_manager;
@@ -71,6 +90,10 @@ pub mod pretty;
 pub use pretty::*;
 pub mod printer;
 pub use printer::*;
+pub mod superopt;
+pub use superopt::*;
+pub mod symbol;
+pub use symbol::*;
 pub mod value;
 pub use value::*;
 pub mod verify;
@@ -0,0 +1,264 @@
+//! A report of the memory used by a [`Context`], broken down by the kind of IR entity.
+//!
+//! The sizes are approximate.  Each arena is counted at its capacity, and the heap allocations
+//! owned by each entity are counted at their capacity too, but the overhead of the allocator and of
+//! hash maps is ignored.
+
+use std::{fmt, mem::size_of};
+
+use generational_arena::Arena;
+
+use crate::{
+    asm::AsmInstruction, block::BlockContent, constant::ConstantValue, function::FunctionContent,
+    metadata::Metadatum, value::ValueContent, value::ValueDatum, Constant, Context, LocalVar, Type,
+    TypeContent, Value,
+};
+
+/// The number of one kind of IR entity in a [`Context`] and the approximate bytes they use.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemoryUsage {
+    pub name: &'static str,
+    pub count: usize,
+    pub bytes: usize,
+}
+
+/// The memory used by a [`Context`].  See [`Context::memory_report`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemoryReport {
+    pub usages: Vec<MemoryUsage>,
+}
+
+impl MemoryReport {
+    /// The usage of the entities called `name`, e.g. `"blocks"`.
+    pub fn get(&self, name: &str) -> Option<&MemoryUsage> {
+        self.usages.iter().find(|usage| usage.name == name)
+    }
+
+    pub fn total_bytes(&self) -> usize {
+        self.usages.iter().map(|usage| usage.bytes).sum()
+    }
+}
+
+impl fmt::Display for MemoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for usage in &self.usages {
+            writeln!(
+                f,
+                "{:16}{:>10}{:>14}",
+                usage.name,
+                usage.count,
+                format_memory_size(usage.bytes)
+            )?;
+        }
+        write!(
+            f,
+            "{:16}{:>10}{:>14}",
+            "total",
+            "",
+            format_memory_size(self.total_bytes())
+        )
+    }
+}
+
+/// Format a number of bytes in B, KiB or MiB.
+pub fn format_memory_size(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{bytes} B")
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KiB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+impl Context {
+    /// Report the number of each kind of IR entity in this context and the approximate memory
+    /// they use.
+    ///
+    /// Constants and configurables are counted separately from the other values, which include
+    /// the record of their users.
+    pub fn memory_report(&self) -> MemoryReport {
+        fn arena_bytes<T>(arena: &Arena<T>) -> usize {
+            arena.capacity() * size_of::<T>()
+        }
+
+        let module_heap: usize = self
+            .modules
+            .iter()
+            .map(|(_, module)| {
+                module.functions.capacity() * size_of::<crate::Function>()
+                    + module.global_constants.capacity() * size_of::<(Vec<String>, Value)>()
+                    + module.global_configurable.len() * size_of::<(Vec<String>, Value)>()
+            })
+            .sum();
+
+        let function_heap: usize = self
+            .functions
+            .iter()
+            .map(|(_, function)| function_heap_bytes(function))
+            .sum();
+
+        let block_heap: usize = self
+            .blocks
+            .iter()
+            .map(|(_, block)| block_heap_bytes(block))
+            .sum();
+
+        let (mut constant_count, mut constant_heap) = (0, 0);
+        for (_, value) in &self.values {
+            if let ValueDatum::Constant(constant) | ValueDatum::Configurable(constant) =
+                &value.value
+            {
+                constant_count += 1;
+                constant_heap += constant_heap_bytes(constant);
+            }
+        }
+        let users_heap = self.users.capacity() * size_of::<(Value, Vec<Value>)>()
+            + self
+                .users
+                .values()
+                .map(|users| users.capacity() * size_of::<Value>())
+                .sum::<usize>();
+        let value_bytes = arena_bytes(&self.values) + users_heap;
+        let constant_bytes = constant_count * size_of::<ValueContent>() + constant_heap;
+
+        let local_heap: usize = self
+            .local_vars
+            .iter()
+            .filter_map(|(_, local)| local.initializer.as_ref())
+            .map(constant_heap_bytes)
+            .sum();
+
+        let type_heap: usize = self
+            .types
+            .iter()
+            .map(|(_, ty)| match ty {
+                TypeContent::Union(fields) | TypeContent::Struct(fields) => {
+                    fields.capacity() * size_of::<Type>()
+                }
+                _ => 0,
+            })
+            .sum::<usize>()
+            + self.type_map.capacity() * size_of::<(TypeContent, Type)>();
+
+        let asm_heap: usize = self
+            .asm_blocks
+            .iter()
+            .map(|(_, asm)| {
+                asm.args_names.capacity() * size_of::<sway_types::Ident>()
+                    + asm.body.capacity() * size_of::<AsmInstruction>()
+                    + asm
+                        .body
+                        .iter()
+                        .map(|ins| ins.args.capacity() * size_of::<sway_types::Ident>())
+                        .sum::<usize>()
+            })
+            .sum();
+
+        let metadata_heap: usize = self
+            .metadata
+            .iter()
+            .map(|(_, md)| metadatum_heap_bytes(md))
+            .sum();
+        let (symbol_count, symbol_bytes) = self.symbols.usage();
+
+        MemoryReport {
+            usages: vec![
+                MemoryUsage {
+                    name: "modules",
+                    count: self.modules.len(),
+                    bytes: arena_bytes(&self.modules) + module_heap,
+                },
+                MemoryUsage {
+                    name: "functions",
+                    count: self.functions.len(),
+                    bytes: arena_bytes(&self.functions) + function_heap,
+                },
+                MemoryUsage {
+                    name: "blocks",
+                    count: self.blocks.len(),
+                    bytes: arena_bytes(&self.blocks) + block_heap,
+                },
+                MemoryUsage {
+                    name: "values",
+                    count: self.values.len() - constant_count,
+                    bytes: value_bytes - constant_count * size_of::<ValueContent>(),
+                },
+                MemoryUsage {
+                    name: "constants",
+                    count: constant_count,
+                    bytes: constant_bytes,
+                },
+                MemoryUsage {
+                    name: "locals",
+                    count: self.local_vars.len(),
+                    bytes: arena_bytes(&self.local_vars) + local_heap,
+                },
+                MemoryUsage {
+                    name: "types",
+                    count: self.types.len(),
+                    bytes: arena_bytes(&self.types) + type_heap,
+                },
+                MemoryUsage {
+                    name: "asm blocks",
+                    count: self.asm_blocks.len(),
+                    bytes: arena_bytes(&self.asm_blocks) + asm_heap,
+                },
+                MemoryUsage {
+                    name: "metadata",
+                    count: self.metadata.len(),
+                    bytes: arena_bytes(&self.metadata) + metadata_heap,
+                },
+                MemoryUsage {
+                    name: "symbols",
+                    count: symbol_count,
+                    bytes: symbol_bytes,
+                },
+            ],
+        }
+    }
+}
+
+fn function_heap_bytes(function: &FunctionContent) -> usize {
+    function.arguments.capacity() * size_of::<(String, Value)>()
+        + function
+            .arguments
+            .iter()
+            .map(|(name, _)| name.capacity())
+            .sum::<usize>()
+        + function.blocks.capacity() * size_of::<crate::Block>()
+        + function
+            .local_storage
+            .keys()
+            .map(|name| size_of::<(String, LocalVar)>() + name.capacity())
+            .sum::<usize>()
+}
+
+fn block_heap_bytes(block: &BlockContent) -> usize {
+    (block.instructions.capacity() + block.args.capacity()) * size_of::<Value>()
+        + block.preds.capacity() * size_of::<crate::Block>()
+}
+
+fn constant_heap_bytes(constant: &Constant) -> usize {
+    match &constant.value {
+        ConstantValue::String(bytes) => bytes.capacity(),
+        ConstantValue::Array(elements) | ConstantValue::Struct(elements) => {
+            elements.capacity() * size_of::<Constant>()
+                + elements.iter().map(constant_heap_bytes).sum::<usize>()
+        }
+        _ => 0,
+    }
+}
+
+fn metadatum_heap_bytes(md: &Metadatum) -> usize {
+    match md {
+        Metadatum::String(string) => string.capacity(),
+        Metadatum::Struct(tag, fields) => {
+            tag.capacity()
+                + fields.capacity() * size_of::<Metadatum>()
+                + fields.iter().map(metadatum_heap_bytes).sum::<usize>()
+        }
+        Metadatum::List(indices) => indices.capacity() * size_of::<crate::MetadataIndex>(),
+        _ => 0,
+    }
+}
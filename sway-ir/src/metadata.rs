@@ -6,6 +6,16 @@
 ///!
 ///! The metadata themselves are opaque to `sway-ir` and are represented with simple value types;
 ///! integers, strings, symbols (tags) and lists.
+///!
+///! Tools may also attach namespaced key-value [`Attachment`]s to values, which are stored among
+///! the other metadata of the value and are printed and parsed like them.
+///!
+///! The struct metadata are told apart by their [`MetadataKind`], and `sway-ir` does know about a
+///! few of them.  The [`SourceSpan`] locates the Sway source a value was generated from, which the
+///! [`DebugInfo`](crate::DebugInfo) helper uses to map the IR back to the source.  The storage key
+///! and [`Inline`] request are set by the frontend, and the [`DebugVariable`]s naming the user
+///! variables a value holds are kept up to date by the passes moving values around.  The inliner
+///! notes the function each inlined instruction was [`InlinedFrom`].
 use crate::context::Context;
 
 #[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, Hash)]
@@ -132,3 +142,516 @@ impl Metadatum {
         }
     }
 }
+
+/// The tag of the struct metadatum holding an [`Attachment`], e.g.,
+/// `attr "tooling" "generated-by" "abi-decode"`.
+pub const ATTACHMENT_TAG: &str = "attr";
+
+/// A key-value pair attached to a value by a tool, with the key scoped to the tool's namespace.
+///
+/// A value has at most one attachment for each key within a namespace.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Attachment {
+    pub namespace: String,
+    pub key: String,
+    pub value: String,
+}
+
+/// How the optimization passes treat the attachments of a namespace.  The policy is set per
+/// namespace with [`Context::set_attachment_policy`].
+///
+/// Regardless of the policy, attachments are copied along with the instructions inlined into
+/// another function and are lost with the values which are removed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AttachmentPolicy {
+    /// The attachments stay on their value, including when a pass rewrites it in place, but are
+    /// not carried over to a value which replaces it.  This is the default.
+    Keep,
+    /// As with `Keep`, but the attachments are also copied to any value a pass replaces the uses
+    /// of their value with, e.g., the stored value replacing a `load` in `mem2reg`.
+    Propagate,
+    /// The attachments are removed as soon as a pass rewrites or moves their value, so they only
+    /// remain on instructions which were left as they were.
+    Drop,
+}
+
+impl Attachment {
+    pub fn new<S: Into<String>>(namespace: S, key: S, value: S) -> Self {
+        Attachment {
+            namespace: namespace.into(),
+            key: key.into(),
+            value: value.into(),
+        }
+    }
+
+    fn from_metadatum(md: &Metadatum) -> Option<Self> {
+        match md.unwrap_struct(ATTACHMENT_TAG, 3)? {
+            [Metadatum::String(namespace), Metadatum::String(key), Metadatum::String(value)] => {
+                Some(Attachment::new(namespace, key, value))
+            }
+            _otherwise => None,
+        }
+    }
+
+    fn to_metadatum(&self) -> Metadatum {
+        Metadatum::Struct(
+            ATTACHMENT_TAG.to_owned(),
+            vec![
+                Metadatum::String(self.namespace.clone()),
+                Metadatum::String(self.key.clone()),
+                Metadatum::String(self.value.clone()),
+            ],
+        )
+    }
+
+    fn is_keyed(&self, namespace: &str, key: &str) -> bool {
+        self.namespace == namespace && self.key == key
+    }
+}
+
+/// Return the attachments among the metadata at `md_idx`.
+pub(crate) fn get_attachments(context: &Context, md_idx: Option<MetadataIndex>) -> Vec<Attachment> {
+    flatten(context, md_idx)
+        .into_iter()
+        .filter_map(|md_idx| Attachment::from_metadatum(md_idx.get_content(context)))
+        .collect()
+}
+
+/// Return the metadata at `md_idx` with `attachment` added, replacing any attachment with the
+/// same namespace and key.
+pub(crate) fn add_attachment(
+    context: &mut Context,
+    md_idx: Option<MetadataIndex>,
+    attachment: &Attachment,
+) -> Option<MetadataIndex> {
+    let mut md_idcs = retain_attachments(context, md_idx, |other| {
+        !other.is_keyed(&attachment.namespace, &attachment.key)
+    });
+    md_idcs.push(MetadataIndex(
+        context.metadata.insert(attachment.to_metadatum()),
+    ));
+    unflatten(context, md_idcs)
+}
+
+/// Return the metadata at `md_idx` without the attachments for which `remove` is true.
+pub(crate) fn remove_attachments<F: Fn(&Attachment) -> bool>(
+    context: &mut Context,
+    md_idx: Option<MetadataIndex>,
+    remove: F,
+) -> Option<MetadataIndex> {
+    let md_idcs = retain_attachments(context, md_idx, |attachment| !remove(attachment));
+    if md_idcs.len() == flatten(context, md_idx).len() {
+        // Avoid creating a new list when nothing was removed.
+        md_idx
+    } else {
+        unflatten(context, md_idcs)
+    }
+}
+
+fn retain_attachments<F: Fn(&Attachment) -> bool>(
+    context: &Context,
+    md_idx: Option<MetadataIndex>,
+    keep: F,
+) -> Vec<MetadataIndex> {
+    flatten(context, md_idx)
+        .into_iter()
+        .filter(|md_idx| {
+            let attachment = Attachment::from_metadatum(md_idx.get_content(context));
+            attachment.filter(|attachment| !keep(attachment)).is_none()
+        })
+        .collect()
+}
+
+/// The tag of the struct metadatum holding a [`SourceSpan`], e.g., `span !0 10 20` where `!0` is
+/// the path of the source file.
+pub const SPAN_TAG: &str = "span";
+
+/// The tag of the struct metadatum holding the storage key a value accesses, e.g., `state_index 3`.
+pub const STORAGE_KEY_TAG: &str = "state_index";
+
+/// The tag of the struct metadatum holding the [`Inline`] request of a function, e.g.,
+/// `inline "never"`.
+pub const INLINE_TAG: &str = "inline";
+
+/// The tag of the struct metadatum holding the [`InlinedFrom`] function of an instruction, e.g.,
+/// `inlined_from "foo" !3` where `!3` is the span of the declaration of `foo`.
+pub const INLINED_FROM_TAG: &str = "inlined_from";
+
+/// The kinds of struct metadatum, identified by their tags.
+///
+/// A value or function has at most one metadatum of each kind.  The kinds `sway-ir` knows the
+/// fields of have typed accessors, e.g., [`Value::get_storage_key`](crate::Value::get_storage_key),
+/// and the fields of the others are accessed with
+/// [`Value::get_metadatum`](crate::Value::get_metadatum) and the like.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum MetadataKind {
+    /// A [`SourceSpan`].
+    Span,
+    /// The storage key accessed by a value.
+    StorageKey,
+    /// An [`Inline`] request of a function.
+    Inline,
+    /// The [`InlinedFrom`] function of an instruction.
+    InlinedFrom,
+    /// Any other kind, by its tag.
+    Custom(String),
+}
+
+impl MetadataKind {
+    /// Return the kind with `tag`.
+    pub fn new(tag: &str) -> Self {
+        match tag {
+            SPAN_TAG => MetadataKind::Span,
+            STORAGE_KEY_TAG => MetadataKind::StorageKey,
+            INLINE_TAG => MetadataKind::Inline,
+            INLINED_FROM_TAG => MetadataKind::InlinedFrom,
+            _otherwise => MetadataKind::Custom(tag.to_owned()),
+        }
+    }
+
+    pub fn tag(&self) -> &str {
+        match self {
+            MetadataKind::Span => SPAN_TAG,
+            MetadataKind::StorageKey => STORAGE_KEY_TAG,
+            MetadataKind::Inline => INLINE_TAG,
+            MetadataKind::InlinedFrom => INLINED_FROM_TAG,
+            MetadataKind::Custom(tag) => tag,
+        }
+    }
+}
+
+/// Whether a function should be inlined, as requested with the `#[inline(..)]` attribute in the
+/// source.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Inline {
+    Always,
+    Never,
+}
+
+impl Inline {
+    pub(crate) fn from_fields(fields: &[Metadatum]) -> Option<Self> {
+        match fields {
+            [Metadatum::String(inline)] if inline == "always" => Some(Inline::Always),
+            [Metadatum::String(inline)] if inline == "never" => Some(Inline::Never),
+            _otherwise => None,
+        }
+    }
+
+    pub(crate) fn to_fields(self) -> Vec<Metadatum> {
+        let inline = match self {
+            Inline::Always => "always",
+            Inline::Never => "never",
+        };
+        vec![Metadatum::String(inline.to_owned())]
+    }
+}
+
+/// Return the fields of the metadatum of `kind` among the metadata at `md_idx`.
+pub(crate) fn get_metadatum<'a>(
+    context: &'a Context,
+    md_idx: Option<MetadataIndex>,
+    kind: &MetadataKind,
+) -> Option<&'a [Metadatum]> {
+    flatten(context, md_idx)
+        .into_iter()
+        .find_map(|md_idx| match md_idx.get_content(context) {
+            Metadatum::Struct(tag, fields) if tag == kind.tag() => Some(fields.as_slice()),
+            _otherwise => None,
+        })
+}
+
+/// Return the metadata at `md_idx` with their metadatum of `kind`, if any, replaced by one with
+/// `fields`.
+pub(crate) fn set_metadatum(
+    context: &mut Context,
+    md_idx: Option<MetadataIndex>,
+    kind: &MetadataKind,
+    fields: Vec<Metadatum>,
+) -> Option<MetadataIndex> {
+    let mut md_idcs = retain_kinds(context, md_idx, kind);
+    md_idcs.push(MetadataIndex::new_struct(context, kind.tag(), fields));
+    unflatten(context, md_idcs)
+}
+
+/// Return the metadata at `md_idx` without their metadatum of `kind`.
+pub(crate) fn remove_metadatum(
+    context: &mut Context,
+    md_idx: Option<MetadataIndex>,
+    kind: &MetadataKind,
+) -> Option<MetadataIndex> {
+    let md_idcs = retain_kinds(context, md_idx, kind);
+    if md_idcs.len() == flatten(context, md_idx).len() {
+        md_idx
+    } else {
+        unflatten(context, md_idcs)
+    }
+}
+
+fn retain_kinds(
+    context: &Context,
+    md_idx: Option<MetadataIndex>,
+    kind: &MetadataKind,
+) -> Vec<MetadataIndex> {
+    flatten(context, md_idx)
+        .into_iter()
+        .filter(|md_idx| {
+            !matches!(md_idx.get_content(context), Metadatum::Struct(tag, _) if tag == kind.tag())
+        })
+        .collect()
+}
+
+/// The range of bytes in a Sway source file from which a value or function was generated.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct SourceSpan {
+    /// The string metadatum holding the path of the source file.  It identifies the file and is
+    /// shared by all the spans within it.  See [`SourceSpan::new_file`].
+    pub file: MetadataIndex,
+    /// The byte offset of the start of the span.
+    pub start: u64,
+    /// The byte offset just past the end of the span.
+    pub end: u64,
+}
+
+impl SourceSpan {
+    pub fn new(file: MetadataIndex, start: u64, end: u64) -> Self {
+        SourceSpan { file, start, end }
+    }
+
+    /// Return a new file identifier for spans within the source file at `path`.
+    pub fn new_file<S: Into<String>>(context: &mut Context, path: S) -> MetadataIndex {
+        MetadataIndex::new_string(context, path)
+    }
+
+    /// Return the path of the source file, if the file identifier is valid.
+    pub fn path<'a>(&self, context: &'a Context) -> Option<&'a str> {
+        self.file.get_content(context).unwrap_string()
+    }
+
+    fn from_metadatum(md: &Metadatum) -> Option<Self> {
+        Self::from_fields(md.unwrap_struct(SPAN_TAG, 3)?)
+    }
+
+    fn from_fields(fields: &[Metadatum]) -> Option<Self> {
+        match fields {
+            [Metadatum::Index(file), Metadatum::Integer(start), Metadatum::Integer(end)] => {
+                Some(SourceSpan::new(*file, *start, *end))
+            }
+            _otherwise => None,
+        }
+    }
+
+    fn to_metadatum(self) -> Metadatum {
+        Metadatum::Struct(SPAN_TAG.to_owned(), self.to_fields())
+    }
+
+    fn to_fields(self) -> Vec<Metadatum> {
+        vec![
+            Metadatum::Index(self.file),
+            Metadatum::Integer(self.start),
+            Metadatum::Integer(self.end),
+        ]
+    }
+}
+
+/// Return the span among the metadata at `md_idx`.
+pub(crate) fn get_span(context: &Context, md_idx: Option<MetadataIndex>) -> Option<SourceSpan> {
+    get_metadatum(context, md_idx, &MetadataKind::Span).and_then(SourceSpan::from_fields)
+}
+
+/// Return the metadata at `md_idx` with their span, if any, replaced by `span`.
+pub(crate) fn set_span(
+    context: &mut Context,
+    md_idx: Option<MetadataIndex>,
+    span: SourceSpan,
+) -> Option<MetadataIndex> {
+    set_metadatum(context, md_idx, &MetadataKind::Span, span.to_fields())
+}
+
+/// The function an instruction was copied from by the inliner, so that the code generated for it
+/// may be attributed to that function rather than to the one it was inlined into.
+///
+/// An instruction inlined more than once keeps the function it was first inlined from, in which
+/// it was written.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct InlinedFrom {
+    /// The name of the inlined function.
+    pub function: String,
+    /// The span of the declaration of the inlined function, if it has one.
+    pub span: Option<SourceSpan>,
+}
+
+impl InlinedFrom {
+    fn from_fields(context: &Context, fields: &[Metadatum]) -> Option<Self> {
+        match fields {
+            [Metadatum::String(function)] => Some(InlinedFrom {
+                function: function.clone(),
+                span: None,
+            }),
+            [Metadatum::String(function), Metadatum::Index(span)] => Some(InlinedFrom {
+                function: function.clone(),
+                span: Some(SourceSpan::from_metadatum(span.get_content(context))?),
+            }),
+            _otherwise => None,
+        }
+    }
+
+    fn to_fields(&self, context: &mut Context) -> Vec<Metadatum> {
+        let mut fields = vec![Metadatum::String(self.function.clone())];
+        if let Some(span) = self.span {
+            fields.push(Metadatum::Index(MetadataIndex(
+                context.metadata.insert(span.to_metadatum()),
+            )));
+        }
+        fields
+    }
+}
+
+/// Return the function an instruction was inlined from among the metadata at `md_idx`.
+pub(crate) fn get_inlined_from(
+    context: &Context,
+    md_idx: Option<MetadataIndex>,
+) -> Option<InlinedFrom> {
+    get_metadatum(context, md_idx, &MetadataKind::InlinedFrom)
+        .and_then(|fields| InlinedFrom::from_fields(context, fields))
+}
+
+/// Return the metadata at `md_idx` with their inlined from function, if any, replaced by
+/// `inlined_from`.
+pub(crate) fn set_inlined_from(
+    context: &mut Context,
+    md_idx: Option<MetadataIndex>,
+    inlined_from: &InlinedFrom,
+) -> Option<MetadataIndex> {
+    let fields = inlined_from.to_fields(context);
+    set_metadatum(context, md_idx, &MetadataKind::InlinedFrom, fields)
+}
+
+/// The tag of the struct metadatum holding a [`DebugVariable`], e.g., `dbg_var "x" "u64" !1` where
+/// `!1` is the span of its scope, optionally followed by the span of the call it was inlined at.
+pub const DEBUG_VARIABLE_TAG: &str = "dbg_var";
+
+/// A user variable of the Sway source which a value holds.
+///
+/// A variable attached to a `get_local` declares that the local holds it, and `mem2reg` moves it on
+/// to the values stored in the local when it is promoted.  A variable attached to a function has
+/// no value left at all, e.g., because its values were found dead and removed, and so should be
+/// shown as optimized out.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct DebugVariable {
+    /// The name of the variable.
+    pub name: String,
+    /// The Sway type of the variable, as it would be written in the source.
+    pub ty: String,
+    /// The span of the lexical scope in which the variable is declared.
+    pub scope: SourceSpan,
+    /// The span of the call its function was inlined at, if it was.
+    pub inlined_at: Option<SourceSpan>,
+}
+
+impl DebugVariable {
+    pub fn new<S: Into<String>>(name: S, ty: S, scope: SourceSpan) -> Self {
+        DebugVariable {
+            name: name.into(),
+            ty: ty.into(),
+            scope,
+            inlined_at: None,
+        }
+    }
+
+    fn from_metadatum(context: &Context, md: &Metadatum) -> Option<Self> {
+        let span = |md_idx: &MetadataIndex| SourceSpan::from_metadatum(md_idx.get_content(context));
+        let (fields, inlined_at) = match md.unwrap_struct(DEBUG_VARIABLE_TAG, 4) {
+            Some([fields @ .., Metadatum::Index(inlined_at)]) => (fields, Some(span(inlined_at)?)),
+            _otherwise => (md.unwrap_struct(DEBUG_VARIABLE_TAG, 3)?, None),
+        };
+        match fields {
+            [Metadatum::String(name), Metadatum::String(ty), Metadatum::Index(scope)] => {
+                Some(DebugVariable {
+                    name: name.clone(),
+                    ty: ty.clone(),
+                    scope: span(scope)?,
+                    inlined_at,
+                })
+            }
+            _otherwise => None,
+        }
+    }
+
+    fn to_metadatum(&self, context: &mut Context) -> Metadatum {
+        let mut fields = vec![
+            Metadatum::String(self.name.clone()),
+            Metadatum::String(self.ty.clone()),
+            Metadatum::Index(MetadataIndex(
+                context.metadata.insert(self.scope.to_metadatum()),
+            )),
+        ];
+        if let Some(inlined_at) = self.inlined_at {
+            fields.push(Metadatum::Index(MetadataIndex(
+                context.metadata.insert(inlined_at.to_metadatum()),
+            )));
+        }
+        Metadatum::Struct(DEBUG_VARIABLE_TAG.to_owned(), fields)
+    }
+}
+
+/// Return the debug variables among the metadata at `md_idx`.
+pub(crate) fn get_debug_variables(
+    context: &Context,
+    md_idx: Option<MetadataIndex>,
+) -> Vec<DebugVariable> {
+    flatten(context, md_idx)
+        .into_iter()
+        .filter_map(|md_idx| DebugVariable::from_metadatum(context, md_idx.get_content(context)))
+        .collect()
+}
+
+/// Return the metadata at `md_idx` with `variable` added, unless it's there already.
+pub(crate) fn add_debug_variable(
+    context: &mut Context,
+    md_idx: Option<MetadataIndex>,
+    variable: &DebugVariable,
+) -> Option<MetadataIndex> {
+    if get_debug_variables(context, md_idx).contains(variable) {
+        return md_idx;
+    }
+    let mut md_idcs = flatten(context, md_idx);
+    let md = variable.to_metadatum(context);
+    md_idcs.push(MetadataIndex(context.metadata.insert(md)));
+    unflatten(context, md_idcs)
+}
+
+/// Return the metadata at `md_idx` without any debug variables.
+pub(crate) fn remove_debug_variables(
+    context: &mut Context,
+    md_idx: Option<MetadataIndex>,
+) -> Option<MetadataIndex> {
+    let md_idcs = flatten(context, md_idx)
+        .into_iter()
+        .filter(|md_idx| {
+            DebugVariable::from_metadatum(context, md_idx.get_content(context)).is_none()
+        })
+        .collect::<Vec<_>>();
+    if md_idcs.len() == flatten(context, md_idx).len() {
+        md_idx
+    } else {
+        unflatten(context, md_idcs)
+    }
+}
+
+fn flatten(context: &Context, md_idx: Option<MetadataIndex>) -> Vec<MetadataIndex> {
+    match md_idx {
+        None => Vec::new(),
+        Some(md_idx) => md_idx
+            .get_content(context)
+            .unwrap_list()
+            .map_or_else(|| vec![md_idx], |md_idcs| md_idcs.to_vec()),
+    }
+}
+
+fn unflatten(context: &mut Context, md_idcs: Vec<MetadataIndex>) -> Option<MetadataIndex> {
+    match md_idcs.len() {
+        0 => None,
+        1 => Some(md_idcs[0]),
+        _ => Some(MetadataIndex::new_list(context, md_idcs)),
+    }
+}
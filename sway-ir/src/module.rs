@@ -3,10 +3,16 @@
 //! A module also has a 'kind' corresponding to the different Sway module types.
 
 use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use sha2::{Digest, Sha256};
 
 use crate::{
     context::Context,
+    error::IrError,
     function::{Function, FunctionIterator},
+    instruction::{FuelVmInstruction, Instruction},
+    printer,
     value::Value,
 };
 
@@ -24,7 +30,7 @@ pub struct ModuleContent {
 }
 
 /// The different 'kinds' of Sway module: `Contract`, `Library`, `Predicate` or `Script`.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub enum Kind {
     Contract,
     Library,
@@ -49,6 +55,18 @@ impl Module {
         context.modules[self.0].kind
     }
 
+    /// Return a hash of the module's content: its kind, its configurables and the
+    /// [`Function::content_hash`] of each of its functions, in order.
+    pub fn content_hash(&self, context: &Context) -> u64 {
+        let mut hasher = Sha256::new();
+        hasher.update(format!("{:?}", self.get_kind(context)));
+        hasher.update(printer::configurables_to_string(context, *self));
+        for function in self.function_iter(context) {
+            hasher.update(function.content_hash(context).to_le_bytes());
+        }
+        u64::from_le_bytes(hasher.finalize()[..8].try_into().unwrap())
+    }
+
     /// Return an interator over each of the [`Function`]s in this module.
     pub fn function_iter(&self, context: &Context) -> FunctionIterator {
         FunctionIterator::new(context, self)
@@ -98,16 +116,220 @@ impl Module {
             .copied()
     }
 
-    /// Removed a function from the module.  Returns true if function was found and removed.
+    /// Return an iterator over the global configurable values of this module, with their call
+    /// paths.
+    pub fn global_configurable_iter<'a>(
+        &self,
+        context: &'a Context,
+    ) -> impl Iterator<Item = (&'a Vec<String>, &'a Value)> {
+        context.modules[self.0].global_configurable.iter()
+    }
+
+    /// A graphviz dot graph of the static calls between the functions of this module.
+    ///
+    /// Each edge is labelled with the number of calls from the caller to the callee.  Entry
+    /// functions are drawn in bold and contract ABI functions are colored blue with their
+    /// selector.  Functions which access storage directly are drawn as boxes, so that the ABI
+    /// functions which reach storage may be seen at a glance.
+    pub fn dot_callgraph(&self, context: &Context) -> String {
+        let quoted = |function: &Function| format!("{:?}", function.get_name(context));
+
+        let mut res = format!(
+            "digraph {} {{\n",
+            match self.get_kind(context) {
+                Kind::Contract => "contract",
+                Kind::Library => "library",
+                Kind::Predicate => "predicate",
+                Kind::Script => "script",
+            }
+        );
+        let mut edges = String::new();
+        for function in self.function_iter(context) {
+            let mut attrs = Vec::new();
+            if function.is_entry(context) {
+                attrs.push("style=bold".to_owned());
+            }
+            if let Some(selector) = function.get_selector(context) {
+                attrs.push("color=blue".to_owned());
+                let label = format!(
+                    "{}\n<{}>",
+                    function.get_name(context),
+                    selector
+                        .iter()
+                        .map(|b| format!("{b:02x}"))
+                        .collect::<String>()
+                );
+                attrs.push(format!("label={label:?}"));
+            }
+
+            // Count the calls to each callee, in the order they're first called.
+            let mut callees: Vec<(Function, usize)> = Vec::new();
+            let mut accesses_storage = false;
+            for (_, ins) in function.instruction_iter(context) {
+                match ins.get_instruction(context) {
+                    Some(Instruction::Call(callee, _)) => {
+                        match callees.iter_mut().find(|(f, _)| f == callee) {
+                            Some((_, count)) => *count += 1,
+                            None => callees.push((*callee, 1)),
+                        }
+                    }
+                    Some(Instruction::FuelVm(
+                        FuelVmInstruction::StateClear { .. }
+                        | FuelVmInstruction::StateLoadQuadWord { .. }
+                        | FuelVmInstruction::StateLoadWord(_)
+                        | FuelVmInstruction::StateStoreQuadWord { .. }
+                        | FuelVmInstruction::StateStoreWord { .. },
+                    )) => accesses_storage = true,
+                    _ => (),
+                }
+            }
+            if accesses_storage {
+                attrs.push("shape=box".to_owned());
+            }
+
+            let _ = writeln!(
+                res,
+                "\t{}{}",
+                quoted(&function),
+                if attrs.is_empty() {
+                    String::new()
+                } else {
+                    format!(" [{}]", attrs.join(", "))
+                }
+            );
+            for (callee, count) in callees {
+                let _ = writeln!(
+                    edges,
+                    "\t{} -> {} [label=\"{count}\"]",
+                    quoted(&function),
+                    quoted(&callee)
+                );
+            }
+        }
+        res += &edges;
+        res += "}\n";
+        res
+    }
+
+    /// Return each function in this module which has a selector, with its selector.
+    pub fn selector_iter<'a>(
+        &self,
+        context: &'a Context,
+    ) -> impl Iterator<Item = (Function, [u8; 4])> + 'a {
+        self.function_iter(context)
+            .filter_map(|function| Some((function, function.get_selector(context)?)))
+    }
+
+    /// Return each selector shared by more than one function in this module, with the functions
+    /// sharing it in module order.
+    pub fn selector_collisions(&self, context: &Context) -> Vec<([u8; 4], Vec<Function>)> {
+        selector_collisions(self.selector_iter(context))
+    }
+
+    /// Check that no two functions in this module share a selector.
+    pub fn check_selectors(&self, context: &Context) -> Result<(), IrError> {
+        match self.selector_collisions(context).into_iter().next() {
+            Some((selector, functions)) => Err(selector_collision(context, selector, &functions)),
+            None => Ok(()),
+        }
+    }
+
+    /// Replace the selector of each function in this module which has one with the result of
+    /// `hash` for the function, e.g. a hash of its ABI signature.
+    ///
+    /// If any of the new selectors collide then an [`IrError::SelectorCollision`] is returned and
+    /// no selector is changed.
+    pub fn recompute_selectors(
+        &self,
+        context: &mut Context,
+        mut hash: impl FnMut(&Context, Function) -> [u8; 4],
+    ) -> Result<(), IrError> {
+        let selectors = self
+            .selector_iter(context)
+            .map(|(function, _)| (function, hash(context, function)))
+            .collect::<Vec<_>>();
+        if let Some((selector, functions)) = selector_collisions(selectors.iter().copied())
+            .into_iter()
+            .next()
+        {
+            return Err(selector_collision(context, selector, &functions));
+        }
+        for (function, selector) in selectors {
+            function.set_selector(context, Some(selector));
+        }
+        Ok(())
+    }
+
+    /// Remove a function from the module and free it from the context, along with its blocks,
+    /// their arguments and instructions, and its locals.  Any handle to these is then invalid.
+    ///
+    /// Constants, asm blocks and metadata may be shared with other functions, so aren't freed.
+    /// Nothing is freed if the function isn't in this module.
     ///
     /// **Use with care!  Be sure the function is not an entry point nor called at any stage.**
     pub fn remove_function(&self, context: &mut Context, function: &Function) {
-        context
+        let functions = &mut context
             .modules
             .get_mut(self.0)
             .expect("Module must exist in context.")
+            .functions;
+        let Some(idx) = functions.iter().position(|mod_fn| mod_fn == function) else {
+            return;
+        };
+        functions.remove(idx);
+
+        let content = context
             .functions
-            .retain(|mod_fn| mod_fn != function);
+            .remove(function.0)
+            .expect("Function must exist in context.");
+        for block in content.blocks {
+            let block_content = context
+                .blocks
+                .remove(block.0)
+                .expect("Block must exist in context.");
+            for value in block_content
+                .args
+                .into_iter()
+                .chain(block_content.instructions)
+            {
+                value.free(context);
+            }
+        }
+        for local_var in content.local_storage.values() {
+            context.local_vars.remove(local_var.0);
+        }
+    }
+}
+
+// Each selector of `selectors` shared by more than one function, in the order the selectors first
+// appear.
+pub(crate) fn selector_collisions(
+    selectors: impl Iterator<Item = (Function, [u8; 4])>,
+) -> Vec<([u8; 4], Vec<Function>)> {
+    let mut by_selector = Vec::<([u8; 4], Vec<Function>)>::new();
+    let mut indices = HashMap::new();
+    for (function, selector) in selectors {
+        let idx = *indices.entry(selector).or_insert_with(|| {
+            by_selector.push((selector, Vec::new()));
+            by_selector.len() - 1
+        });
+        by_selector[idx].1.push(function);
+    }
+    by_selector.retain(|(_, functions)| functions.len() > 1);
+    by_selector
+}
+
+pub(crate) fn selector_collision(
+    context: &Context,
+    selector: [u8; 4],
+    functions: &[Function],
+) -> IrError {
+    IrError::SelectorCollision {
+        selector,
+        functions: functions
+            .iter()
+            .map(|function| function.get_name(context).to_owned())
+            .collect(),
     }
 }
 
@@ -15,11 +15,41 @@
 
 pub mod constants;
 pub use constants::*;
+pub mod coverage;
+pub use coverage::*;
+pub mod critical_edges;
+pub use critical_edges::*;
+pub mod cse;
+pub use cse::*;
+pub mod freeze;
+pub use freeze::*;
+pub mod gas_checkpoints;
+pub use gas_checkpoints::*;
 pub mod inline;
 pub use inline::*;
+pub mod instcombine;
+pub use instcombine::*;
+pub mod licm;
+pub use licm::*;
+pub mod mark_pure;
+pub use mark_pure::*;
+pub mod packed_abi;
+pub use packed_abi::*;
+pub mod rewrite;
+pub use rewrite::*;
+pub mod rle;
+pub use rle::*;
 pub mod simplify_cfg;
 pub use simplify_cfg::*;
+pub mod tail_call;
+pub use tail_call::*;
+pub mod unroll;
+pub use unroll::*;
 pub mod dce;
 pub use dce::*;
+pub mod dse;
+pub use dse::*;
+pub mod egraph;
+pub use egraph::*;
 pub mod mem2reg;
 pub use mem2reg::*;
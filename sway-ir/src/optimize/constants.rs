@@ -21,6 +21,8 @@ pub fn create_const_combine_pass() -> Pass {
         name: CONSTCOMBINE_NAME,
         descr: "constant folding.",
         deps: vec![],
+        preserves: vec![],
+        prune_unreachable: true,
         runner: ScopedPass::FunctionPass(PassMutability::Transform(combine_constants)),
     }
 }
@@ -33,23 +35,18 @@ pub fn combine_constants(
 ) -> Result<bool, IrError> {
     let mut modified = false;
     loop {
-        if combine_const_insert_values(context, &function) {
-            modified = true;
-            continue;
-        }
-
-        if combine_cmp(context, &function) {
-            modified = true;
-            continue;
+        // Other passes here... always continue to the top if pass returns true.
+        let combined = combine_const_insert_values(context, &function)
+            || combine_cmp(context, &function)
+            || combine_cbr(context, &function)?;
+        if !combined {
+            break;
         }
+        modified = true;
 
-        if combine_cbr(context, &function)? {
-            modified = true;
-            continue;
+        if !context.consume_fuel() {
+            break;
         }
-
-        // Other passes here... always continue to the top if pass returns true.
-        break;
     }
 
     Ok(modified)
@@ -97,7 +94,10 @@ fn combine_cbr(context: &mut Context, function: &Function) -> Result<bool, IrErr
                 ..
             },
         )| {
-            no_more_dest.remove_pred(context, &from_block);
+            // Both arms may be to the same block, which is still a successor.
+            if no_more_dest != dest.block {
+                no_more_dest.remove_pred(context, &from_block);
+            }
             cbr.replace(context, ValueDatum::Instruction(Instruction::Branch(dest)));
             Ok(true)
         },
@@ -188,6 +188,7 @@ fn combine_const_insert_values(context: &mut Context, function: &Function) -> bo
         let new_aggregate = combine_const_aggregate_field(context, aggregate, const_val, &indices);
 
         // Replace uses of the `insert_value` instruction with the new aggregate.
+        ins_val.propagate_attachments(context, new_aggregate);
         function.replace_value(context, ins_val, new_aggregate, None);
 
         // Remove the `insert_value` instruction.
@@ -212,6 +213,7 @@ fn combine_const_aggregate_field(
         ValueContent {
             value: ValueDatum::Constant(c),
             metadata,
+            ..
         } => (c.clone(), *metadata),
         _otherwise => {
             unreachable!("BUG! Invalid aggregate parameter to combine_const_insert_value()")
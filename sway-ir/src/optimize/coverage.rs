@@ -0,0 +1,169 @@
+//! Coverage instrumentation.
+//!
+//! Every block is prefixed with a `log` of a unique counter ID, using the reserved
+//! [`COVERAGE_LOG_ID`].  A test runner can then count the log receipts carrying that ID to find
+//! how often each block was executed, and map the counters back to the source via the
+//! [`CoveragePoint`]s returned by [`instrument_coverage`].
+//!
+//! So that every arm of every conditional branch has a counter of its own, the critical edges
+//! are split first.  Each call, including calls to other contracts, is also followed by a
+//! counter, since the code after it is only reached if the call returns.
+//!
+//! Instrumentation should be done before any optimisations so that the blocks still closely
+//! resemble the source.  The logs are side effects and so they will survive any later passes,
+//! though inlining may duplicate them, which is fine since they then count the same source block.
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    AnalysisResults, Block, Constant, Context, InsertionPoint, Instruction, IrError, MetadataIndex,
+    Module, Pass, PassMutability, ScopedPass, Type, Value,
+};
+
+pub const COVERAGE_NAME: &str = "coverage";
+
+pub fn create_coverage_pass() -> Pass {
+    Pass {
+        name: COVERAGE_NAME,
+        descr: "Instrument every block, branch arm and call with a coverage counter.",
+        deps: vec![],
+        preserves: vec![],
+        prune_unreachable: false,
+        runner: ScopedPass::ModulePass(PassMutability::Transform(coverage)),
+    }
+}
+
+/// The log ID reserved for coverage counters.
+pub const COVERAGE_LOG_ID: u64 = u64::MAX;
+
+/// A counter inserted by [`instrument_coverage`].
+#[derive(Clone, Debug)]
+pub struct CoveragePoint {
+    /// The value logged whenever this point is reached.
+    pub id: u64,
+    /// The instrumented block.
+    pub block: Block,
+    /// Metadata for the block, taken from its first instruction which has any, or failing that
+    /// its function.  For a counter following a call, it's taken from the first instruction
+    /// after the call which has any, or failing that the call itself.
+    pub metadata: Option<MetadataIndex>,
+    /// Set if this block is the destination of a conditional branch.
+    pub branch: Option<CoverageBranch>,
+    /// Set if this counter follows a call, either local or to another contract, rather than
+    /// starting the block.
+    pub call: Option<Value>,
+}
+
+/// An arm of a conditional branch which leads to a [`CoveragePoint`].
+#[derive(Clone, Debug)]
+pub struct CoverageBranch {
+    /// The ID of the conditional branch, shared by both of its arms.
+    pub id: u64,
+    /// Whether this is the arm taken when the condition is true.
+    pub is_true_arm: bool,
+    /// Metadata from the conditional branch instruction.
+    pub metadata: Option<MetadataIndex>,
+}
+
+fn coverage(context: &mut Context, _: &AnalysisResults, module: Module) -> Result<bool, IrError> {
+    Ok(!instrument_coverage(context, module).is_empty())
+}
+
+/// Insert a coverage counter at the start of every block in `module` and after every call,
+/// returning a description of each counter.
+///
+/// The counter and branch IDs are numbered across the whole [`Context`], so that they stay unique
+/// when more than one module is instrumented.
+pub fn instrument_coverage(context: &mut Context, module: Module) -> Vec<CoveragePoint> {
+    let mut points = Vec::new();
+    let mut next_branch_id = context.next_coverage_branch_id;
+
+    let log_ty = Type::get_uint64(context);
+    let log_id = Constant::get_uint(context, 64, COVERAGE_LOG_ID);
+
+    for function in module.function_iter(context).collect::<Vec<_>>() {
+        // Find the destinations of the conditional branches first.  Once the critical edges are
+        // split, each arm leads to a block which is reached along that arm alone.
+        function.split_critical_edges(context);
+        let mut branch_arms: FxHashMap<Block, CoverageBranch> = FxHashMap::default();
+        for (_block, ins) in function.instruction_iter(context) {
+            if let Some(Instruction::ConditionalBranch {
+                true_block,
+                false_block,
+                ..
+            }) = ins.get_instruction(context)
+            {
+                let metadata = ins.get_metadata(context);
+                for (dest, is_true_arm) in [(true_block.block, true), (false_block.block, false)] {
+                    branch_arms.insert(
+                        dest,
+                        CoverageBranch {
+                            id: next_branch_id,
+                            is_true_arm,
+                            metadata,
+                        },
+                    );
+                }
+                next_branch_id += 1;
+            }
+        }
+
+        for block in function.block_iter(context).collect::<Vec<_>>() {
+            let id = context.next_coverage_point_id;
+            context.next_coverage_point_id += 1;
+            let metadata = block
+                .instruction_iter(context)
+                .find_map(|ins| ins.get_metadata(context))
+                .or_else(|| function.get_metadata(context));
+
+            let log_val = Constant::get_uint(context, 64, id);
+            block
+                .ins(context)
+                .position(InsertionPoint::Start)
+                .metadata(metadata)
+                .log(log_val, log_ty, log_id);
+
+            points.push(CoveragePoint {
+                id,
+                block,
+                metadata,
+                branch: branch_arms.remove(&block),
+                call: None,
+            });
+
+            let instructions = block.instruction_iter(context).collect::<Vec<_>>();
+            for (idx, call) in instructions.iter().enumerate() {
+                if !matches!(
+                    call.get_instruction(context),
+                    Some(Instruction::Call(..) | Instruction::ContractCall { .. })
+                ) {
+                    continue;
+                }
+                let id = context.next_coverage_point_id;
+                context.next_coverage_point_id += 1;
+                let metadata = instructions[idx + 1..]
+                    .iter()
+                    .find_map(|ins| ins.get_metadata(context))
+                    .or_else(|| call.get_metadata(context));
+
+                let log_val = Constant::get_uint(context, 64, id);
+                block
+                    .ins(context)
+                    .position(InsertionPoint::After(*call))
+                    .metadata(metadata)
+                    .log(log_val, log_ty, log_id);
+
+                points.push(CoveragePoint {
+                    id,
+                    block,
+                    metadata,
+                    branch: None,
+                    call: Some(*call),
+                });
+            }
+        }
+    }
+    context.next_coverage_branch_id = next_branch_id;
+
+    points
+}
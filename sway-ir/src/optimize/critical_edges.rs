@@ -0,0 +1,29 @@
+//! Critical edge splitting.
+//!
+//! An edge is critical if it leaves a block with more than one successor and enters a block with
+//! more than one incoming edge.  Code which must run only along such an edge can go in neither
+//! block, so register allocation and some SSA transforms need them split first.  This pass does
+//! nothing else, see [`Function::split_critical_edges`].
+
+use crate::{AnalysisResults, Context, Function, IrError, Pass, PassMutability, ScopedPass};
+
+pub const SPLIT_CRITICAL_EDGES_NAME: &str = "split_critical_edges";
+
+pub fn create_split_critical_edges_pass() -> Pass {
+    Pass {
+        name: SPLIT_CRITICAL_EDGES_NAME,
+        descr: "Insert empty blocks on critical edges.",
+        deps: vec![],
+        preserves: vec![],
+        prune_unreachable: false,
+        runner: ScopedPass::FunctionPass(PassMutability::Transform(split_critical_edges)),
+    }
+}
+
+fn split_critical_edges(
+    context: &mut Context,
+    _: &AnalysisResults,
+    function: Function,
+) -> Result<bool, IrError> {
+    Ok(function.split_critical_edges(context))
+}
@@ -0,0 +1,169 @@
+//! Common subexpression elimination.
+//!
+//! A pure instruction computing the same operation on the same operands as another which
+//! dominates it is replaced by the other.  The dominator tree is walked in preorder with a scoped
+//! table of the instructions available in the current block, i.e., those in the blocks which
+//! dominate it, so each is found in a single walk.
+//!
+//! Instructions are matched by opcode and operands, where constant scalar operands match by
+//! value, so two `get_local`s of the same local or two `add`s of equal constants are merged.
+//! Commutative operations also match with their operands swapped, as do `lt` and `gt`.  Memory
+//! accesses are never merged, as a store may come between them.
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    AnalysisResults, BinaryOpKind, Block, ConstantValue, Context, DomTree, Function, Instruction,
+    IrError, LocalVar, Pass, PassMutability, Predicate, ScopedPass, Type, Value, CALL_GRAPH_NAME,
+    CFG_ANALYSES, DOMINATORS_NAME,
+};
+
+pub const CSE_NAME: &str = "cse";
+
+pub fn create_cse_pass() -> Pass {
+    Pass {
+        name: CSE_NAME,
+        descr: "Common subexpression elimination.",
+        deps: vec![DOMINATORS_NAME],
+        preserves: [CFG_ANALYSES.as_slice(), &[CALL_GRAPH_NAME]].concat(),
+        prune_unreachable: false,
+        runner: ScopedPass::FunctionPass(PassMutability::Transform(cse)),
+    }
+}
+
+/// An operand of an instruction being matched: a scalar constant by value, or otherwise the value
+/// itself.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Operand {
+    Const(Type, u64),
+    Value(Value),
+}
+
+/// The operation computed by a pure instruction.
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Expr {
+    AddrOf(Operand),
+    BinaryOp(BinaryOpKind, Operand, Operand),
+    BitCast(Operand, Type),
+    CastPtr(Operand, Type, u64),
+    Cmp(Predicate, Operand, Operand),
+    GetLocal(LocalVar),
+    IntToPtr(Operand, Type),
+}
+
+impl Expr {
+    // The same operation with its operands swapped, if there is one.
+    fn swapped(&self) -> Option<Expr> {
+        match self {
+            Expr::BinaryOp(
+                op @ (BinaryOpKind::Add
+                | BinaryOpKind::Mul
+                | BinaryOpKind::And
+                | BinaryOpKind::Or
+                | BinaryOpKind::Xor),
+                arg1,
+                arg2,
+            ) => Some(Expr::BinaryOp(*op, *arg2, *arg1)),
+            Expr::Cmp(pred, arg1, arg2) => {
+                let pred = match pred {
+                    Predicate::Equal => Predicate::Equal,
+                    Predicate::LessThan => Predicate::GreaterThan,
+                    Predicate::GreaterThan => Predicate::LessThan,
+                };
+                Some(Expr::Cmp(pred, *arg2, *arg1))
+            }
+            _ => None,
+        }
+    }
+}
+
+pub fn cse(
+    context: &mut Context,
+    analyses: &AnalysisResults,
+    function: Function,
+) -> Result<bool, IrError> {
+    let dom_tree: &DomTree = analyses.get_analysis_result(function);
+
+    // The instructions available in the current block, along with the expressions made available
+    // by each block being visited so they can be dropped once it's left.
+    let mut available = FxHashMap::<Expr, Value>::default();
+    let mut scopes = Vec::<Vec<Expr>>::new();
+    let mut replacements = FxHashMap::<Value, Value>::default();
+    let mut redundant = Vec::<(Block, Value)>::new();
+
+    // `None` marks leaving the scope of the block visited before it.
+    let mut stack = vec![Some(function.get_entry_block(context))];
+    while let Some(next) = stack.pop() {
+        let Some(block) = next else {
+            for expr in scopes.pop().unwrap() {
+                available.remove(&expr);
+            }
+            continue;
+        };
+
+        let mut scope = Vec::new();
+        for ins in block.instruction_iter(context) {
+            let Some(expr) = get_expr(context, ins, &replacements) else {
+                continue;
+            };
+            let existing = available.get(&expr).copied().or_else(|| {
+                expr.swapped()
+                    .and_then(|swapped| available.get(&swapped).copied())
+            });
+            match existing {
+                Some(existing) => {
+                    replacements.insert(ins, existing);
+                    redundant.push((block, ins));
+                }
+                None => {
+                    available.insert(expr.clone(), ins);
+                    scope.push(expr);
+                }
+            }
+        }
+        scopes.push(scope);
+        stack.push(None);
+        stack.extend(
+            dom_tree[&block]
+                .children
+                .iter()
+                .rev()
+                .map(|child| Some(*child)),
+        );
+    }
+
+    if redundant.is_empty() {
+        return Ok(false);
+    }
+    function.replace_values(context, &replacements, None);
+    for (block, ins) in redundant {
+        block.remove_instruction(context, ins);
+    }
+    Ok(true)
+}
+
+// The expression computed by `ins` if it's pure, with its operands replaced as they will be.
+fn get_expr(context: &Context, ins: Value, replacements: &FxHashMap<Value, Value>) -> Option<Expr> {
+    let opd = |val: &Value| {
+        let val = replacements.get(val).copied().unwrap_or(*val);
+        match val.get_constant(context) {
+            Some(constant) => match constant.value {
+                ConstantValue::Unit => Operand::Const(constant.ty, 0),
+                ConstantValue::Bool(b) => Operand::Const(constant.ty, b as u64),
+                ConstantValue::Uint(n) => Operand::Const(constant.ty, n),
+                _ => Operand::Value(val),
+            },
+            None => Operand::Value(val),
+        }
+    };
+    Some(match ins.get_instruction(context)? {
+        Instruction::AddrOf(val) => Expr::AddrOf(opd(val)),
+        Instruction::BinaryOp { op, arg1, arg2 } => Expr::BinaryOp(*op, opd(arg1), opd(arg2)),
+        Instruction::BitCast(val, ty) => Expr::BitCast(opd(val), *ty),
+        Instruction::CastPtr(val, ty, offset) => Expr::CastPtr(opd(val), *ty, *offset),
+        Instruction::Cmp(pred, lhs, rhs) => Expr::Cmp(*pred, opd(lhs), opd(rhs)),
+        Instruction::GetLocal(local_var) => Expr::GetLocal(*local_var),
+        Instruction::IntToPtr(val, ty) => Expr::IntToPtr(opd(val), *ty),
+        _ => return None,
+    })
+}
@@ -4,10 +4,19 @@
 //!   1. A liveness analysis that keeps track of the uses of a definition,
 //!   2. At the time of inspecting a definition, if it has no uses, it is removed.
 //! This pass does not do CFG transformations. That is handled by simplify_cfg.
+//!
+//! The aggressive variant instead assumes everything is dead until it is found to be needed, which
+//! also removes code which is only used by other dead code, and the conditional branches around
+//! it.
+//!
+//! The user variables held by the removed values are recorded on their function, so that they can
+//! still be shown as optimized out.
 
 use crate::{
-    AnalysisResults, Block, Context, Function, Instruction, IrError, Module, Pass, PassMutability,
-    ScopedPass, Value, ValueDatum,
+    AnalysisResults, Block, BlockArgument, BranchToWithArgs, Context, Function, FunctionAttribute,
+    Instruction, IrError, Module, Pass, PassMutability, PostDomFronts, PostDomTree, PostOrder,
+    ScopedPass, Value, ValueDatum, CFG_ANALYSES, POSTDOMFRONTS_NAME, POSTDOMINATORS_NAME,
+    POSTORDER_NAME,
 };
 
 use std::collections::{HashMap, HashSet};
@@ -18,8 +27,23 @@ pub fn create_dce_pass() -> Pass {
     Pass {
         name: DCE_NAME,
         descr: "Dead code elimination.",
+        prune_unreachable: false,
         runner: ScopedPass::FunctionPass(PassMutability::Transform(dce)),
         deps: vec![],
+        preserves: CFG_ANALYSES.to_vec(),
+    }
+}
+
+pub const ADCE_NAME: &str = "adce";
+
+pub fn create_adce_pass() -> Pass {
+    Pass {
+        name: ADCE_NAME,
+        descr: "Aggressive dead code elimination.",
+        deps: vec![POSTORDER_NAME, POSTDOMINATORS_NAME, POSTDOMFRONTS_NAME],
+        preserves: vec![],
+        prune_unreachable: true,
+        runner: ScopedPass::FunctionPass(PassMutability::Transform(adce)),
     }
 }
 
@@ -30,13 +54,31 @@ pub fn create_func_dce_pass() -> Pass {
         name: FUNC_DCE_NAME,
         descr: "Dead function elimination.",
         deps: vec![],
+        preserves: vec![],
+        prune_unreachable: false,
         runner: ScopedPass::ModulePass(PassMutability::Transform(func_dce)),
     }
 }
 
 fn can_eliminate_instruction(context: &Context, val: Value) -> bool {
     let inst = val.get_instruction(context).unwrap();
-    !inst.is_terminator() && !inst.may_have_side_effect()
+    !inst.is_terminator() && !has_side_effect(context, inst)
+}
+
+/// A call to a pure function has no side effects, whatever the callee does internally.
+fn has_side_effect(context: &Context, inst: &Instruction) -> bool {
+    match inst {
+        Instruction::Call(callee, _) => !callee.has_attribute(context, FunctionAttribute::Pure),
+        _ => inst.may_have_side_effect(),
+    }
+}
+
+/// Record the user variables held by the dead `value`, which is about to be removed, as optimized
+/// out in `function`.
+fn drop_debug_variables(context: &mut Context, function: Function, value: Value) {
+    for variable in value.take_debug_variables(context) {
+        function.add_debug_variable(context, &variable);
+    }
 }
 
 /// Perform dead code (if any) elimination and return true if function modified.
@@ -45,55 +87,210 @@ pub fn dce(
     _: &AnalysisResults,
     function: Function,
 ) -> Result<bool, IrError> {
-    // Number of uses that an instruction has.
-    let mut num_uses: HashMap<Value, (Block, u32)> = HashMap::new();
-
-    // Go through each instruction and update use_count.
-    for (block, inst) in function.instruction_iter(context) {
-        let opds = inst.get_instruction(context).unwrap().get_operands();
-        for v in opds {
-            match context.values[v.0].value {
-                ValueDatum::Instruction(_) => {
-                    num_uses
-                        .entry(v)
-                        .and_modify(|(_block, count)| *count += 1)
-                        .or_insert((block, 1));
-                }
-                ValueDatum::Configurable(_) | ValueDatum::Constant(_) | ValueDatum::Argument(_) => {
-                }
-            }
-        }
-    }
+    // The block of each instruction which may yet be found dead.
+    let mut blocks: HashMap<Value, Block> = function
+        .instruction_iter(context)
+        .map(|(block, inst)| (inst, block))
+        .collect();
 
     let mut worklist = function
         .instruction_iter(context)
-        .filter(|(_block, inst)| num_uses.get(inst).is_none())
+        .filter(|(_block, inst)| inst.users(context).is_empty())
         .collect::<Vec<_>>();
 
     let mut modified = false;
-    while !worklist.is_empty() {
-        let (in_block, dead) = worklist.pop().unwrap();
+    while let Some((in_block, dead)) = worklist.pop() {
         if !can_eliminate_instruction(context, dead) {
             continue;
         }
-        // Process dead's operands.
         let opds = dead.get_instruction(context).unwrap().get_operands();
+        drop_debug_variables(context, function, dead);
+        in_block.remove_instruction(context, dead);
+        context.add_pass_statistic("instructions removed", 1);
+        modified = true;
+
+        // Any operand which was only used by `dead` may now be dead too.
         for v in opds {
-            // Reduce the use count of v. If it reaches 0, add it to the worklist.
-            match context.values[v.0].value {
-                ValueDatum::Instruction(_) => {
-                    let (block, nu) = num_uses.get_mut(&v).unwrap();
-                    *nu -= 1;
-                    if *nu == 0 {
-                        worklist.push((*block, v));
+            if v.users(context).is_empty() {
+                if let Some(block) = blocks.remove(&v) {
+                    worklist.push((block, v));
+                }
+            }
+        }
+    }
+
+    Ok(modified)
+}
+
+/// Perform aggressive dead code elimination and return true if function modified.
+///
+/// Side effects, returns and reverts are needed, as is everything they use.  A needed block
+/// argument needs the branches to its block and the parameters they pass for it, and a needed
+/// instruction needs the conditional branches which decide whether it is run: the terminators of
+/// the blocks in the post-dominance frontier of its block.  Everything else is removed, and a
+/// conditional branch which isn't needed is replaced by a branch to its immediate post-dominator,
+/// leaving the blocks between them to simplify_cfg.
+///
+/// A branch back to an earlier block is always needed, as the loop might not terminate.  Blocks
+/// which are unreachable are left alone.
+pub fn adce(
+    context: &mut Context,
+    analyses: &AnalysisResults,
+    function: Function,
+) -> Result<bool, IrError> {
+    let post_order: &PostOrder = analyses.get_analysis_result(function);
+    let post_dom_tree: &PostDomTree = analyses.get_analysis_result(function);
+    let post_dom_fronts: &PostDomFronts = analyses.get_analysis_result(function);
+
+    let blocks: HashMap<Value, Block> = function
+        .instruction_iter(context)
+        .map(|(block, inst)| (inst, block))
+        .collect();
+    let mut terminators: HashMap<Block, Value> = HashMap::new();
+    let mut branches_to: HashMap<Block, Vec<Value>> = HashMap::new();
+    let mut worklist = Vec::new();
+    for block in function.block_iter(context) {
+        let Some(terminator) = block.instruction_iter(context).next_back() else {
+            continue;
+        };
+        terminators.insert(block, terminator);
+        for BranchToWithArgs { block: succ, .. } in block.successors(context) {
+            let branches = branches_to.entry(succ).or_default();
+            if !branches.contains(&terminator) {
+                branches.push(terminator);
+            }
+        }
+
+        let Some(po) = post_order.block_to_po.get(&block) else {
+            worklist.extend(block.arg_iter(context).copied());
+            worklist.extend(block.instruction_iter(context));
+            continue;
+        };
+        for inst in block.instruction_iter(context) {
+            let needed = match inst.get_instruction(context).unwrap() {
+                Instruction::Branch(_) | Instruction::ConditionalBranch { .. } => {
+                    block
+                        .successors(context)
+                        .iter()
+                        .any(|succ| post_order.block_to_po[&succ.block] >= *po)
+                        || post_dom_tree.nodes[&block].parent.is_none()
+                }
+                ins => ins.is_terminator() || has_side_effect(context, ins),
+            };
+            if needed {
+                worklist.push(inst);
+            }
+        }
+    }
+
+    let mut live: HashSet<Value> = HashSet::new();
+    let mut live_blocks: HashSet<Block> = HashSet::new();
+    let dead_cond_branches = loop {
+        while let Some(value) = worklist.pop() {
+            if !live.insert(value) {
+                continue;
+            }
+            let block = match &context.values[value.0].value {
+                ValueDatum::Instruction(Instruction::Branch(_)) => blocks[&value],
+                ValueDatum::Instruction(Instruction::ConditionalBranch { cond_value, .. }) => {
+                    worklist.push(*cond_value);
+                    blocks[&value]
+                }
+                ValueDatum::Instruction(inst) => {
+                    worklist.extend(inst.get_operands());
+                    blocks[&value]
+                }
+                ValueDatum::Argument(BlockArgument { block, idx, .. }) => {
+                    for branch in branches_to.get(block).into_iter().flatten() {
+                        worklist.push(*branch);
+                        worklist.extend(
+                            blocks[branch]
+                                .successors(context)
+                                .into_iter()
+                                .filter(|succ| succ.block == *block)
+                                .map(|succ| succ.args[*idx]),
+                        );
                     }
+                    *block
                 }
-                ValueDatum::Configurable(_) | ValueDatum::Constant(_) | ValueDatum::Argument(_) => {
+                ValueDatum::Constant(_) | ValueDatum::Configurable(_) => continue,
+            };
+            if live_blocks.insert(block) {
+                for controller in post_dom_fronts.0.get(&block).into_iter().flatten() {
+                    worklist.push(terminators[controller]);
                 }
             }
         }
 
-        in_block.remove_instruction(context, dead);
+        // A conditional branch which isn't needed becomes a branch without parameters, so one to
+        // a block with a needed argument is needed after all.
+        let mut dead_cond_branches = Vec::new();
+        for block in function.block_iter(context) {
+            let Some(terminator) = terminators.get(&block) else {
+                continue;
+            };
+            if live.contains(terminator)
+                || !matches!(
+                    terminator.get_instruction(context),
+                    Some(Instruction::ConditionalBranch { .. })
+                )
+            {
+                continue;
+            }
+            let post_dom = post_dom_tree.nodes[&block].parent.unwrap();
+            if post_dom.arg_iter(context).any(|arg| live.contains(arg)) {
+                worklist.push(*terminator);
+            } else {
+                dead_cond_branches.push((block, *terminator, post_dom));
+            }
+        }
+        if worklist.is_empty() {
+            break dead_cond_branches;
+        }
+    };
+
+    let mut modified = false;
+
+    // Remove the unneeded block arguments, the last first so the indices stay valid.
+    let entry_block = function.get_entry_block(context);
+    for block in function.block_iter(context).collect::<Vec<_>>() {
+        if block == entry_block {
+            continue;
+        }
+        let args = block.arg_iter(context).copied().collect::<Vec<_>>();
+        for (idx, arg) in args.into_iter().enumerate().rev() {
+            if !live.contains(&arg) {
+                drop_debug_variables(context, function, arg);
+                block.remove_arg(context, idx);
+                modified = true;
+            }
+        }
+    }
+
+    for (block, branch, post_dom) in dead_cond_branches {
+        for BranchToWithArgs { block: succ, .. } in block.successors(context) {
+            succ.remove_pred(context, &block);
+        }
+        branch.replace(
+            context,
+            ValueDatum::Instruction(Instruction::Branch(BranchToWithArgs {
+                block: post_dom,
+                args: Vec::new(),
+            })),
+        );
+        post_dom.add_pred(context, &block);
+        modified = true;
+    }
+
+    let dead_insts = function
+        .instruction_iter(context)
+        .filter(|(_block, inst)| {
+            !live.contains(inst) && !inst.get_instruction(context).unwrap().is_terminator()
+        })
+        .collect::<Vec<_>>();
+    for (block, inst) in dead_insts {
+        drop_debug_variables(context, function, inst);
+        block.remove_instruction(context, inst);
         modified = true;
     }
 
@@ -0,0 +1,183 @@
+//! Dead store elimination.
+//!
+//! A store to a local is dead if the local isn't read again before it is next overwritten or the
+//! function returns.  This is found with a backward liveness analysis of locals over the CFG, in
+//! which a load or the source of a `mem_copy` makes a local live and a store makes it dead.  A
+//! `mem_copy` into a local may only overwrite part of it, so it doesn't kill it but may itself be
+//! removed if the local is dead.
+//!
+//! Only locals whose address never escapes are considered: their pointers must only be loaded
+//! from, stored to or copied to or from.  Loads of aggregates are pointers to the local rather
+//! than copies, so may be read later, and make it escape too.  Removing a copy may make the
+//! stores to its source dead, so the analysis is repeated until nothing more is removed.
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    AnalysisResults, Block, BranchToWithArgs, Context, Function, Instruction, IrError, LocalVar,
+    Pass, PassMutability, ScopedPass, Value, CALL_GRAPH_NAME, CFG_ANALYSES,
+};
+
+pub const DSE_NAME: &str = "dse";
+
+pub fn create_dse_pass() -> Pass {
+    Pass {
+        name: DSE_NAME,
+        descr: "Dead store elimination.",
+        deps: vec![],
+        preserves: [CFG_ANALYSES.as_slice(), &[CALL_GRAPH_NAME]].concat(),
+        prune_unreachable: false,
+        runner: ScopedPass::FunctionPass(PassMutability::Transform(dse)),
+    }
+}
+
+pub fn dse(
+    context: &mut Context,
+    _: &AnalysisResults,
+    function: Function,
+) -> Result<bool, IrError> {
+    let mut modified = false;
+    while remove_dead_stores(context, &function) {
+        modified = true;
+    }
+    Ok(modified)
+}
+
+/// How an instruction accesses the tracked locals.
+#[derive(Default)]
+struct Access {
+    /// The local written to, if any.
+    write: Option<LocalVar>,
+    /// Whether the write overwrites the whole local.
+    overwrites: bool,
+    /// The local read from, if any.
+    read: Option<LocalVar>,
+}
+
+fn get_access(context: &Context, ins: Value, tracked: &FxHashSet<LocalVar>) -> Access {
+    let local = |ptr: &Value| match ptr.get_instruction(context) {
+        Some(Instruction::GetLocal(local_var)) if tracked.contains(local_var) => Some(*local_var),
+        _ => None,
+    };
+    match ins.get_instruction(context) {
+        Some(Instruction::Load(ptr)) => Access {
+            read: local(ptr),
+            ..Access::default()
+        },
+        Some(Instruction::Store { dst_val, .. }) => Access {
+            write: local(dst_val),
+            overwrites: true,
+            read: None,
+        },
+        Some(Instruction::MemCopy {
+            dst_val, src_val, ..
+        }) => Access {
+            write: local(dst_val),
+            overwrites: false,
+            read: local(src_val),
+        },
+        _ => Access::default(),
+    }
+}
+
+// The locals whose pointers are only used by loads of copy types, the destinations of stores and
+// the operands of copies.
+fn tracked_locals(context: &Context, function: &Function) -> FxHashSet<LocalVar> {
+    let as_local = |val: &Value| match val.get_instruction(context) {
+        Some(Instruction::GetLocal(local_var)) => Some(*local_var),
+        _ => None,
+    };
+    let mut locals = FxHashSet::default();
+    let mut escaped = FxHashSet::default();
+    for (_, ins) in function.instruction_iter(context) {
+        let instruction = ins.get_instruction(context).unwrap();
+        match instruction {
+            Instruction::GetLocal(local_var) => {
+                locals.insert(*local_var);
+            }
+            Instruction::Load(ptr) => {
+                if let Some(local_var) = as_local(ptr) {
+                    let ty = local_var.get_type(context);
+                    if !(ty.is_unit(context) || ty.is_bool(context) || ty.is_uint(context)) {
+                        escaped.insert(local_var);
+                    }
+                }
+            }
+            Instruction::Store { stored_val, .. } => escaped.extend(as_local(stored_val)),
+            Instruction::MemCopy { .. } => (),
+            _ => escaped.extend(instruction.get_operands().iter().filter_map(as_local)),
+        }
+    }
+    locals.retain(|local_var| !escaped.contains(local_var));
+    locals
+}
+
+// Remove the stores which are dead according to a single liveness analysis, returning whether
+// there were any.
+fn remove_dead_stores(context: &mut Context, function: &Function) -> bool {
+    let tracked = tracked_locals(context, function);
+    if tracked.is_empty() {
+        return false;
+    }
+    let blocks = function.block_iter(context).collect::<Vec<_>>();
+
+    // Walk back through a block from the locals live at its end, calling `on_write` with each
+    // write and whether its local is live after it, and returning the locals live at its start.
+    let walk_block = |context: &Context,
+                      block: Block,
+                      mut live: FxHashSet<LocalVar>,
+                      on_write: &mut dyn FnMut(Value, bool)| {
+        for ins in block.instruction_iter(context).rev() {
+            let access = get_access(context, ins, &tracked);
+            if let Some(local_var) = access.write {
+                on_write(ins, live.contains(&local_var));
+                if access.overwrites {
+                    live.remove(&local_var);
+                }
+            }
+            if let Some(local_var) = access.read {
+                live.insert(local_var);
+            }
+        }
+        live
+    };
+    let live_out =
+        |context: &Context, live_in: &FxHashMap<Block, FxHashSet<LocalVar>>, block: Block| {
+            block
+                .successors(context)
+                .iter()
+                .flat_map(|BranchToWithArgs { block: succ, .. }| live_in[succ].iter().copied())
+                .collect::<FxHashSet<_>>()
+        };
+
+    let mut live_in = blocks
+        .iter()
+        .map(|block| (*block, FxHashSet::default()))
+        .collect::<FxHashMap<_, _>>();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for block in blocks.iter().rev() {
+            let live = live_out(context, &live_in, *block);
+            let live = walk_block(context, *block, live, &mut |_, _| ());
+            if live != live_in[block] {
+                live_in.insert(*block, live);
+                changed = true;
+            }
+        }
+    }
+
+    let mut dead = Vec::new();
+    for block in &blocks {
+        let live = live_out(context, &live_in, *block);
+        walk_block(context, *block, live, &mut |ins, is_live| {
+            if !is_live {
+                dead.push((*block, ins));
+            }
+        });
+    }
+    for (block, ins) in &dead {
+        block.remove_instruction(context, *ins);
+    }
+    !dead.is_empty()
+}
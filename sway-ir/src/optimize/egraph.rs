@@ -0,0 +1,592 @@
+//! Equality saturation of arithmetic.
+//!
+//! The side-effect-free arithmetic of each block, i.e. its `binary_op` and `cmp` instructions on
+//! integers and bools, is added to an [`EGraph`], which represents many equivalent expressions at
+//! once.  The graph is saturated by applying the [`egraph_rules`], along with any rules set with
+//! [`Context::set_rewrite_rules`], until nothing new is found or a limit is reached.  The cheapest
+//! expression for each value used outside of the arithmetic is then extracted according to the
+//! VM cost model, and replaces the original if it is cheaper.
+//!
+//! Unlike `instcombine`, the rules aren't applied in a fixed order and needn't make progress on
+//! their own, so combinations of rewrites which only pay off together are found too.  The
+//! expressions which are replaced are left for DCE to remove.
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    instcombine_rules, pattern_op_cost, rule, AnalysisResults, Block, Constant, ConstantValue,
+    Context, Function, IrError, Pass, PassMutability, Pattern, PatternInserter, PatternOp, Rule,
+    ScopedPass, Type, Value,
+};
+
+pub const EGRAPH_NAME: &str = "egraph";
+
+pub fn create_egraph_pass() -> Pass {
+    Pass {
+        name: EGRAPH_NAME,
+        descr: "Equality saturation of arithmetic.",
+        deps: vec![],
+        preserves: vec![],
+        prune_unreachable: false,
+        runner: ScopedPass::FunctionPass(PassMutability::Transform(egraph)),
+    }
+}
+
+/// The most rounds of rule applications made while saturating.
+const MAX_ITERATIONS: usize = 8;
+/// Saturation stops early once the graph has grown to this many nodes.
+const MAX_NODES: usize = 5_000;
+
+pub fn egraph(
+    context: &mut Context,
+    _: &AnalysisResults,
+    function: Function,
+) -> Result<bool, IrError> {
+    let mut rules = egraph_rules();
+    rules.extend(context.get_rewrite_rules().iter().cloned());
+
+    let mut modified = false;
+    for block in function.block_iter(context).collect::<Vec<_>>() {
+        modified |= optimize_block(context, function, block, &rules);
+    }
+    Ok(modified)
+}
+
+/// The rules saturated by the `egraph` pass: the [`instcombine_rules`], along with rules which
+/// make no progress on their own but may enable others.
+pub fn egraph_rules() -> Vec<Rule> {
+    let mut rules = instcombine_rules();
+    rules.extend([
+        rule!(gt_lt: (gt x y) => (lt y x)),
+        rule!(eq_xor_zero: (eq (xor x y) 0) => (eq x y)),
+        // Reassociation, which can't overflow for bitwise operations.
+        rule!(and_assoc: (and (and x y) z) => (and x (and y z))),
+        rule!(or_assoc: (or (or x y) z) => (or x (or y z))),
+        rule!(xor_assoc: (xor (xor x y) z) => (xor x (xor y z))),
+        // Absorption and factoring.
+        rule!(and_absorb: (and x (or x y)) => x),
+        rule!(or_absorb: (or x (and x y)) => x),
+        rule!(and_or_factor: (or (and x y) (and x z)) => (and x (or y z))),
+        rule!(and_xor_factor: (xor (and x y) (and x z)) => (and x (xor y z))),
+    ]);
+    rules
+}
+
+/// The identifier of an equivalence class of an [`EGraph`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ClassId(usize);
+
+/// An expression whose operands are equivalence classes rather than expressions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ENode {
+    /// A value about which nothing is known, such as an argument or the result of a load.
+    Leaf(Value),
+    /// An integer constant of the given type.
+    Uint(Type, u64),
+    /// A bool constant.
+    Bool(bool),
+    /// An operation on a value from each of the classes.
+    Op(PatternOp, ClassId, ClassId),
+}
+
+/// The classes bound to the names of a [`Pattern`] by a match in an [`EGraph`].
+pub type Bindings = FxHashMap<String, ClassId>;
+
+struct EClass {
+    nodes: Vec<ENode>,
+    ty: Type,
+}
+
+/// A set of expressions partitioned into classes of equivalent expressions.
+///
+/// The operands of each node are classes, so that a node stands for its operation applied to
+/// every combination of the members of its operand classes.  After [`EGraph::union`] the graph
+/// must be [rebuilt](EGraph::rebuild) before it is searched or extracted from again.
+#[derive(Default)]
+pub struct EGraph {
+    /// The union-find forest over the class IDs.
+    parents: Vec<ClassId>,
+    /// The classes, keyed by the IDs which are their own parents.
+    classes: FxHashMap<ClassId, EClass>,
+    /// The class of each node.
+    memo: FxHashMap<ENode, ClassId>,
+}
+
+impl EGraph {
+    /// Return the ID of the class which `id` has been merged into.
+    pub fn find(&self, mut id: ClassId) -> ClassId {
+        while self.parents[id.0] != id {
+            id = self.parents[id.0];
+        }
+        id
+    }
+
+    /// Return the type of the values of class `id`.
+    pub fn class_type(&self, id: ClassId) -> Type {
+        self.classes[&self.find(id)].ty
+    }
+
+    /// Return the nodes of class `id`.
+    pub fn nodes(&self, id: ClassId) -> &[ENode] {
+        &self.classes[&self.find(id)].nodes
+    }
+
+    /// Return the number of nodes in the graph.
+    pub fn num_nodes(&self) -> usize {
+        self.memo.len()
+    }
+
+    fn class_ids(&self) -> Vec<ClassId> {
+        let mut ids = self.classes.keys().copied().collect::<Vec<_>>();
+        ids.sort();
+        ids
+    }
+
+    fn canonicalize(&self, node: ENode) -> ENode {
+        match node {
+            ENode::Op(op, lhs, rhs) => ENode::Op(op, self.find(lhs), self.find(rhs)),
+            node => node,
+        }
+    }
+
+    fn uint_constant(&self, id: ClassId) -> Option<u64> {
+        self.nodes(id).iter().find_map(|node| match node {
+            ENode::Uint(_, n) => Some(*n),
+            _ => None,
+        })
+    }
+
+    /// Return the constant an operation on constants evaluates to, if any.
+    fn fold(&self, context: &Context, node: ENode) -> Option<ENode> {
+        match node {
+            ENode::Op(op, lhs, rhs) => {
+                let ty = self.class_type(lhs);
+                let width = ty.get_uint_width(context)?;
+                match op.fold(width, self.uint_constant(lhs)?, self.uint_constant(rhs)?)? {
+                    ConstantValue::Uint(n) => Some(ENode::Uint(ty, n)),
+                    ConstantValue::Bool(b) => Some(ENode::Bool(b)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Add `node` to the graph, returning its class.  An operation on constants is made
+    /// equivalent to the constant it evaluates to.
+    pub fn add(&mut self, context: &Context, node: ENode) -> ClassId {
+        let node = self.canonicalize(node);
+        if let Some(id) = self.memo.get(&node) {
+            return self.find(*id);
+        }
+
+        let ty = match node {
+            ENode::Leaf(value) => value
+                .get_type(context)
+                .expect("only typed values are added"),
+            ENode::Uint(ty, _) => ty,
+            ENode::Op(PatternOp::Binary(_), lhs, _) => self.class_type(lhs),
+            ENode::Bool(_) | ENode::Op(PatternOp::Cmp(_), ..) => Type::get_bool(context),
+        };
+        let id = ClassId(self.parents.len());
+        self.parents.push(id);
+        self.classes.insert(
+            id,
+            EClass {
+                nodes: vec![node],
+                ty,
+            },
+        );
+        self.memo.insert(node, id);
+
+        if let Some(folded) = self.fold(context, node) {
+            let folded = self.add(context, folded);
+            self.union(id, folded);
+        }
+        self.find(id)
+    }
+
+    /// Merge the classes `a` and `b`, returning whether they were distinct.
+    pub fn union(&mut self, a: ClassId, b: ClassId) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        let (root, child) = if a < b { (a, b) } else { (b, a) };
+        self.parents[child.0] = root;
+        let child_nodes = self.classes.remove(&child).unwrap().nodes;
+        self.classes
+            .get_mut(&root)
+            .unwrap()
+            .nodes
+            .extend(child_nodes);
+        true
+    }
+
+    /// Restore the invariants broken by [`EGraph::union`]: the operands of every node are the
+    /// classes they've been merged into, and nodes which have become identical are in the same
+    /// class.
+    pub fn rebuild(&mut self, context: &Context) {
+        loop {
+            let mut memo = FxHashMap::default();
+            let mut merges = Vec::new();
+            let mut folds = Vec::new();
+            for id in self.class_ids() {
+                let mut seen = FxHashSet::default();
+                let nodes = self.classes[&id]
+                    .nodes
+                    .iter()
+                    .map(|node| self.canonicalize(*node))
+                    .filter(|node| seen.insert(*node))
+                    .collect::<Vec<_>>();
+                for node in &nodes {
+                    if let Some(other) = memo.insert(*node, id) {
+                        merges.push((other, id));
+                    }
+                    // An operand may have been found to be constant since the node was added.
+                    if let Some(folded) = self.fold(context, *node) {
+                        folds.push((id, folded));
+                    }
+                }
+                self.classes.get_mut(&id).unwrap().nodes = nodes;
+            }
+            self.memo = memo;
+
+            let mut changed = false;
+            for (a, b) in merges {
+                changed |= self.union(a, b);
+            }
+            for (id, folded) in folds {
+                let folded = self.add(context, folded);
+                changed |= self.union(id, folded);
+            }
+            if !changed {
+                break;
+            }
+        }
+    }
+
+    /// Return every match of `pattern` in the graph, as the class matched along with the classes
+    /// bound to the names of the pattern.  The single use requirements of the pattern are
+    /// ignored, since the graph doesn't duplicate anything.
+    pub fn search(&self, pattern: &Pattern) -> Vec<(ClassId, Bindings)> {
+        self.class_ids()
+            .into_iter()
+            .flat_map(|id| {
+                self.match_class(pattern, id, &Bindings::default())
+                    .into_iter()
+                    .map(move |bindings| (id, bindings))
+            })
+            .collect()
+    }
+
+    fn match_class(&self, pattern: &Pattern, id: ClassId, bindings: &Bindings) -> Vec<Bindings> {
+        let id = self.find(id);
+        let bind = |name: &String| match bindings.get(name) {
+            Some(bound) if self.find(*bound) != id => vec![],
+            Some(_) => vec![bindings.clone()],
+            None => {
+                let mut bindings = bindings.clone();
+                bindings.insert(name.clone(), id);
+                vec![bindings]
+            }
+        };
+        match pattern {
+            Pattern::Value(name) => bind(name),
+            Pattern::Constant(name) if self.uint_constant(id).is_some() => bind(name),
+            Pattern::Uint(n) if self.uint_constant(id) == Some(*n) => vec![bindings.clone()],
+            Pattern::Bool(b) if self.nodes(id).contains(&ENode::Bool(*b)) => {
+                vec![bindings.clone()]
+            }
+            Pattern::Constant(_) | Pattern::Uint(_) | Pattern::Bool(_) => vec![],
+            Pattern::Op { op, lhs, rhs, .. } => {
+                let mut matches = Vec::new();
+                for node in self.nodes(id) {
+                    let (arg1, arg2) = match node {
+                        ENode::Op(node_op, arg1, arg2) if node_op == op => (*arg1, *arg2),
+                        _ => continue,
+                    };
+                    let mut orders = vec![(arg1, arg2)];
+                    if op.is_commutative() && arg1 != arg2 {
+                        orders.push((arg2, arg1));
+                    }
+                    for (arg1, arg2) in orders {
+                        for bindings in self.match_class(lhs, arg1, bindings) {
+                            matches.extend(self.match_class(rhs, arg2, &bindings));
+                        }
+                    }
+                }
+                matches
+            }
+        }
+    }
+
+    /// Add the expression described by the template `pattern` to the graph, using the classes
+    /// bound in `bindings`, and return its class.  `ty` is the type given to integer literals.
+    ///
+    /// Panics if the template refers to a name which isn't bound.
+    pub fn instantiate(
+        &mut self,
+        context: &Context,
+        pattern: &Pattern,
+        bindings: &Bindings,
+        ty: Type,
+    ) -> ClassId {
+        match pattern {
+            Pattern::Value(name) | Pattern::Constant(name) => *bindings
+                .get(name)
+                .unwrap_or_else(|| panic!("'{name}' is not bound by the pattern")),
+            Pattern::Uint(n) => self.add(context, ENode::Uint(ty, *n)),
+            Pattern::Bool(b) => self.add(context, ENode::Bool(*b)),
+            Pattern::Op { op, lhs, rhs, .. } => {
+                // As with `Pattern::build`, a comparison's operands take their type from each
+                // other.
+                let operand_ty = match op {
+                    PatternOp::Binary(_) => ty,
+                    PatternOp::Cmp(_) => Type::get_uint64(context),
+                };
+                let (arg1, arg2) = if matches!(**lhs, Pattern::Uint(_)) {
+                    let arg2 = self.instantiate(context, rhs, bindings, operand_ty);
+                    let arg1_ty = self.class_type(arg2);
+                    (self.instantiate(context, lhs, bindings, arg1_ty), arg2)
+                } else {
+                    let arg1 = self.instantiate(context, lhs, bindings, operand_ty);
+                    let arg2_ty = self.class_type(arg1);
+                    (arg1, self.instantiate(context, rhs, bindings, arg2_ty))
+                };
+                self.add(context, ENode::Op(*op, arg1, arg2))
+            }
+        }
+    }
+
+    /// Apply `rules` to every match in the graph until no new equivalences are found, returning
+    /// whether this happened within [`MAX_ITERATIONS`] rounds and before the graph grew past
+    /// [`MAX_NODES`] nodes.
+    pub fn saturate(&mut self, context: &Context, rules: &[Rule]) -> bool {
+        for _ in 0..MAX_ITERATIONS {
+            let matches = rules
+                .iter()
+                .flat_map(|rule| {
+                    self.search(&rule.pattern)
+                        .into_iter()
+                        .map(move |(id, bindings)| (rule, id, bindings))
+                })
+                .collect::<Vec<_>>();
+
+            let mut changed = false;
+            for (rule, id, bindings) in matches {
+                let ty = self.class_type(id);
+                let replacement = self.instantiate(context, &rule.replacement, &bindings, ty);
+                changed |= self.union(id, replacement);
+                if self.num_nodes() > MAX_NODES {
+                    self.rebuild(context);
+                    return false;
+                }
+            }
+            self.rebuild(context);
+            if !changed {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Return the cheapest node of each class from which an expression can be built, along with
+    /// the cost of that expression.  Leaves for which `available` returns false are never chosen.
+    ///
+    /// Operands are costed once per use, so the cost of an expression sharing operands is an
+    /// overestimate.
+    pub fn extract(&self, available: impl Fn(Value) -> bool) -> FxHashMap<ClassId, (u64, ENode)> {
+        let ids = self.class_ids();
+        let mut best = FxHashMap::<ClassId, (u64, ENode)>::default();
+        loop {
+            let mut changed = false;
+            for id in &ids {
+                for node in &self.classes[id].nodes {
+                    let cost = match node {
+                        ENode::Leaf(value) if !available(*value) => continue,
+                        ENode::Leaf(_) | ENode::Uint(..) | ENode::Bool(_) => 0,
+                        ENode::Op(op, lhs, rhs) => {
+                            match (best.get(&self.find(*lhs)), best.get(&self.find(*rhs))) {
+                                (Some((lhs_cost, _)), Some((rhs_cost, _))) => {
+                                    pattern_op_cost(op) + lhs_cost + rhs_cost
+                                }
+                                _ => continue,
+                            }
+                        }
+                    };
+                    let cheaper = match best.get(id) {
+                        Some((best_cost, _)) => cost < *best_cost,
+                        None => true,
+                    };
+                    if cheaper {
+                        best.insert(*id, (cost, *node));
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                break best;
+            }
+        }
+    }
+}
+
+/// The instructions of `block` which the graph can represent, with their operation and operands:
+/// the `binary_op` and `cmp` instructions on integers and bools.
+pub(crate) fn block_arithmetic(
+    context: &Context,
+    block: Block,
+) -> FxHashMap<Value, (PatternOp, Value, Value)> {
+    let is_arithmetic = |value: Value| matches!(value.get_type(context), Some(ty) if ty.is_uint(context) || ty.is_bool(context));
+    block
+        .instruction_iter(context)
+        .filter_map(|ins| {
+            let (op, arg1, arg2) = PatternOp::of_instruction(ins.get_instruction(context)?)?;
+            (is_arithmetic(arg1) && is_arithmetic(arg2)).then_some((ins, (op, arg1, arg2)))
+        })
+        .collect()
+}
+
+/// Return the values of the `arithmetic` of `block` used by anything else in `function`.
+pub(crate) fn arithmetic_roots(
+    context: &Context,
+    function: Function,
+    block: Block,
+    arithmetic: &FxHashMap<Value, (PatternOp, Value, Value)>,
+) -> FxHashSet<Value> {
+    let mut roots = FxHashSet::default();
+    for (user_block, user) in function.instruction_iter(context) {
+        if user_block == block && arithmetic.contains_key(&user) {
+            continue;
+        }
+        if let Some(instruction) = user.get_instruction(context) {
+            roots.extend(
+                instruction
+                    .get_operands()
+                    .into_iter()
+                    .filter(|operand| arithmetic.contains_key(operand)),
+            );
+        }
+    }
+    roots
+}
+
+/// Saturate the arithmetic of `block`, replacing each value it computes for use elsewhere with
+/// the cheapest equivalent found, if that's cheaper.
+fn optimize_block(context: &mut Context, function: Function, block: Block, rules: &[Rule]) -> bool {
+    let arithmetic = block_arithmetic(context, block);
+    if arithmetic.is_empty() {
+        return false;
+    }
+    let roots = arithmetic_roots(context, function, block, &arithmetic);
+    let instructions = context.blocks[block.0].instructions.clone();
+
+    // Add the arithmetic to the graph in order, so that operands are always added first.
+    let mut graph = EGraph::default();
+    let mut classes = FxHashMap::<Value, ClassId>::default();
+    let mut original_costs = FxHashMap::<Value, u64>::default();
+    for ins in &instructions {
+        if let Some((op, arg1, arg2)) = arithmetic.get(ins) {
+            let lhs = operand_class(context, &mut graph, &classes, *arg1);
+            let rhs = operand_class(context, &mut graph, &classes, *arg2);
+            let class = graph.add(context, ENode::Op(*op, lhs, rhs));
+            classes.insert(*ins, class);
+            let operand_cost = |arg| original_costs.get(arg).copied().unwrap_or(0);
+            let cost = pattern_op_cost(op) + operand_cost(arg1) + operand_cost(arg2);
+            original_costs.insert(*ins, cost);
+        }
+    }
+    graph.saturate(context, rules);
+
+    let positions = instructions
+        .iter()
+        .enumerate()
+        .map(|(idx, ins)| (*ins, idx))
+        .collect::<FxHashMap<_, _>>();
+    let mut modified = false;
+    for (root_idx, root) in instructions.iter().enumerate() {
+        if !roots.contains(root) {
+            continue;
+        }
+        // The replacement is inserted before the root, so it may only use the values defined
+        // before it.
+        let best = graph.extract(|value| match positions.get(&value) {
+            Some(idx) => *idx < root_idx,
+            None => true,
+        });
+        let class = graph.find(classes[root]);
+        match best.get(&class) {
+            Some((cost, _)) if *cost < original_costs[root] => (),
+            _ => continue,
+        }
+
+        let mut inserter = PatternInserter::before(context, block, *root);
+        let replacement = build_class(
+            context,
+            &graph,
+            &best,
+            class,
+            &mut inserter,
+            &mut FxHashMap::default(),
+        );
+        root.propagate_attachments(context, replacement);
+        function.replace_value(context, *root, replacement, None);
+        block.remove_instruction(context, *root);
+        modified = true;
+    }
+    modified
+}
+
+fn operand_class(
+    context: &Context,
+    graph: &mut EGraph,
+    classes: &FxHashMap<Value, ClassId>,
+    value: Value,
+) -> ClassId {
+    if let Some(class) = classes.get(&value) {
+        return *class;
+    }
+    let node = match value.get_constant(context) {
+        Some(Constant {
+            ty,
+            value: ConstantValue::Uint(n),
+        }) => ENode::Uint(*ty, *n),
+        Some(Constant {
+            value: ConstantValue::Bool(b),
+            ..
+        }) => ENode::Bool(*b),
+        _ => ENode::Leaf(value),
+    };
+    graph.add(context, node)
+}
+
+/// Build the cheapest expression of class `id`, reusing the values already built for classes.
+fn build_class(
+    context: &mut Context,
+    graph: &EGraph,
+    best: &FxHashMap<ClassId, (u64, ENode)>,
+    id: ClassId,
+    inserter: &mut PatternInserter,
+    built: &mut FxHashMap<ClassId, Value>,
+) -> Value {
+    let id = graph.find(id);
+    if let Some(value) = built.get(&id) {
+        return *value;
+    }
+    let value = match best[&id].1 {
+        ENode::Leaf(value) => value,
+        ENode::Uint(ty, n) => {
+            let width = ty.get_uint_width(context).unwrap_or(64);
+            Constant::get_uint(context, width, n)
+        }
+        ENode::Bool(b) => Constant::get_bool(context, b),
+        ENode::Op(op, lhs, rhs) => {
+            let arg1 = build_class(context, graph, best, lhs, inserter, built);
+            let arg2 = build_class(context, graph, best, rhs, inserter, built);
+            inserter.insert(context, op.instruction(arg1, arg2))
+        }
+    };
+    built.insert(id, value);
+    value
+}
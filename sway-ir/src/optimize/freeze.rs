@@ -0,0 +1,108 @@
+//! Freezing of configurables.
+//!
+//! A configurable is normally compiled as a patchable slot in the data section, so that its value
+//! may be changed when the contract is deployed.  When the values are already known, e.g. for a
+//! particular deployment profile, [`freeze_configurables`] turns the configurables into plain
+//! constants, which lets the constant folding and dead code passes specialise the program.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    constant::{Constant, ConstantValue},
+    context::Context,
+    irtype::{Type, TypeContent},
+    module::Module,
+    value::ValueDatum,
+    IrError,
+};
+
+/// Replace the configurables in `module` named in `values` with constants.
+///
+/// The values are given as text, which must be a literal of the configurable's type: a decimal
+/// or `0x` prefixed hexadecimal integer, `true` or `false`, a `0x` prefixed 64 digit `b256`, or a
+/// string of the exact length.  Only the last element of a configurable's call path is used as
+/// its name.
+///
+/// Returns the names of the configurables which were frozen.  A name without a configurable in the
+/// module is not an error, since configurables which are never used aren't compiled to IR.
+pub fn freeze_configurables(
+    context: &mut Context,
+    module: &Module,
+    values: &BTreeMap<String, String>,
+) -> Result<Vec<String>, IrError> {
+    let configs: Vec<_> = context.modules[module.0]
+        .global_configurable
+        .iter()
+        .filter_map(|(call_path, value)| {
+            let name = call_path.last()?;
+            values
+                .get(name)
+                .map(|text| (call_path.clone(), name.clone(), *value, text))
+        })
+        .collect();
+
+    let mut frozen = Vec::with_capacity(configs.len());
+    for (call_path, name, value, text) in configs {
+        let ty = value
+            .get_configurable(context)
+            .expect("Global configurables are always configurable values.")
+            .ty;
+        let constant = parse_constant(context, ty, text).map_err(|msg| {
+            IrError::InvalidConfigurableValue(format!("configurable '{name}': {msg}"))
+        })?;
+
+        // Every use of the configurable now sees a constant instead.
+        context.values[value.0].value = ValueDatum::Constant(constant);
+        let module_content = &mut context.modules[module.0];
+        module_content.global_configurable.remove(&call_path);
+        module_content.global_constants.insert(call_path, value);
+        frozen.push(name);
+    }
+    Ok(frozen)
+}
+
+fn parse_constant(context: &Context, ty: Type, text: &str) -> Result<Constant, String> {
+    let value = match ty.get_content(context) {
+        TypeContent::Bool => match text {
+            "true" => ConstantValue::Bool(true),
+            "false" => ConstantValue::Bool(false),
+            _ => return Err(format!("'{text}' is not a bool")),
+        },
+        TypeContent::Uint(nbits) => {
+            let n = match text.strip_prefix("0x") {
+                Some(hex) => u64::from_str_radix(&hex.replace('_', ""), 16),
+                None => text.replace('_', "").parse::<u64>(),
+            }
+            .map_err(|_| format!("'{text}' is not an integer"))?;
+            if *nbits < 64 && n >> *nbits != 0 {
+                return Err(format!("{n} doesn't fit in a u{nbits}"));
+            }
+            ConstantValue::Uint(n)
+        }
+        TypeContent::B256 => {
+            let hex = text
+                .strip_prefix("0x")
+                .filter(|hex| hex.len() == 64 && hex.is_ascii())
+                .ok_or_else(|| format!("'{text}' is not a 0x prefixed 64 digit b256"))?;
+            let mut bytes = [0u8; 32];
+            for (idx, byte) in bytes.iter_mut().enumerate() {
+                *byte = u8::from_str_radix(&hex[idx * 2..idx * 2 + 2], 16)
+                    .map_err(|_| format!("'{text}' is not a hexadecimal b256"))?;
+            }
+            ConstantValue::B256(bytes)
+        }
+        TypeContent::String(len) => {
+            if text.len() as u64 != *len {
+                return Err(format!("'{text}' is not a string of length {len}"));
+            }
+            ConstantValue::String(text.as_bytes().to_vec())
+        }
+        _ => {
+            return Err(format!(
+                "values of type {} can't be given as text",
+                ty.as_string(context)
+            ))
+        }
+    };
+    Ok(Constant { ty, value })
+}
@@ -0,0 +1,92 @@
+//! Gas checkpoint lowering.
+//!
+//! A `gas_checkpoint` instruction marks a point at which a contract wants the gas remaining to be
+//! recorded, e.g. to decide how much of a batch of work it may still attempt or how much to
+//! refund.  Unless this pass is run they generate no code at all, so checkpoints may be left in
+//! production builds for free.
+//!
+//! Each checkpoint is replaced with an `asm` block emitting a single `log` receipt, which holds
+//! the gas remaining in the current context in `ra`, the reserved [`GAS_CHECKPOINT_LOG_ID`] in
+//! `rb` and the identifier of the checkpoint in `rc`.
+
+use sway_types::Ident;
+
+use crate::{
+    AnalysisResults, AsmArg, AsmBlock, AsmInstruction, Constant, Context, FuelVmInstruction,
+    Instruction, IrError, Module, Pass, PassMutability, ScopedPass, Type, ValueDatum,
+};
+
+pub const GAS_CHECKPOINTS_NAME: &str = "gas_checkpoints";
+
+pub fn create_gas_checkpoints_pass() -> Pass {
+    Pass {
+        name: GAS_CHECKPOINTS_NAME,
+        descr: "Lower gas checkpoints to logs of the remaining gas.",
+        deps: vec![],
+        preserves: vec![],
+        prune_unreachable: false,
+        runner: ScopedPass::ModulePass(PassMutability::Transform(gas_checkpoints)),
+    }
+}
+
+/// The log ID reserved for gas checkpoints.
+pub const GAS_CHECKPOINT_LOG_ID: u64 = u64::MAX - 1;
+
+fn gas_checkpoints(
+    context: &mut Context,
+    _: &AnalysisResults,
+    module: Module,
+) -> Result<bool, IrError> {
+    Ok(lower_gas_checkpoints(context, module))
+}
+
+/// Replace every gas checkpoint in `module` with a log of the remaining gas, returning whether
+/// there were any.
+pub fn lower_gas_checkpoints(context: &mut Context, module: Module) -> bool {
+    let checkpoints = module
+        .function_iter(context)
+        .flat_map(|function| function.instruction_iter(context))
+        .filter_map(|(_block, ins)| match ins.get_instruction(context) {
+            Some(Instruction::FuelVm(FuelVmInstruction::GasCheckpoint(id))) => Some((ins, *id)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let log_id = Constant::get_uint(context, 64, GAS_CHECKPOINT_LOG_ID);
+    let unit_ty = Type::get_unit(context);
+    for (ins, id) in &checkpoints {
+        let ident = |name: &str| Ident::new_no_span(name.to_string());
+        let args = vec![
+            AsmArg {
+                name: ident("checkpoint_id"),
+                initializer: Some(*id),
+            },
+            AsmArg {
+                name: ident("log_id"),
+                initializer: Some(log_id),
+            },
+        ];
+        let body = vec![AsmInstruction {
+            name: ident("log"),
+            args: ["cgas", "log_id", "checkpoint_id", "zero"]
+                .into_iter()
+                .map(ident)
+                .collect(),
+            immediate: None,
+            metadata: ins.get_metadata(context),
+        }];
+        let asm = AsmBlock::new(
+            context,
+            args.iter().map(|arg| arg.name.clone()).collect(),
+            body,
+            unit_ty,
+            None,
+        );
+        ins.replace(
+            context,
+            ValueDatum::Instruction(Instruction::AsmBlock(asm, args)),
+        );
+    }
+
+    !checkpoints.is_empty()
+}
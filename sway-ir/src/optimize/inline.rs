@@ -2,9 +2,7 @@
 //!
 //! Function inlining is pretty hairy so these passes must be maintained with care.
 
-use std::{cell::RefCell, collections::HashMap};
-
-use rustc_hash::FxHashMap;
+use std::collections::HashMap;
 
 use crate::{
     asm::AsmArg,
@@ -12,13 +10,16 @@ use crate::{
     call_graph,
     context::Context,
     error::IrError,
-    function::Function,
+    function::{Function, FunctionAttribute},
     instruction::{FuelVmInstruction, Instruction},
     irtype::Type,
     local_var::LocalVar,
-    metadata::{combine, MetadataIndex},
+    metadata::{
+        combine, get_inlined_from, DebugVariable, Inline, InlinedFrom, MetadataIndex, SourceSpan,
+    },
     value::{Value, ValueContent, ValueDatum},
-    AnalysisResults, BlockArgument, Module, Pass, PassMutability, ScopedPass,
+    AnalysisResults, BlockArgument, CallGraph, InlineCost, Module, Pass, PassMutability,
+    ScopedPass, CALL_GRAPH_NAME,
 };
 
 pub const INLINE_MAIN_NAME: &str = "inline_main";
@@ -28,6 +29,8 @@ pub fn create_inline_in_main_pass() -> Pass {
         name: INLINE_MAIN_NAME,
         descr: "inline from main fn.",
         deps: vec![],
+        preserves: vec![],
+        prune_unreachable: false,
         runner: ScopedPass::ModulePass(PassMutability::Transform(inline_in_main)),
     }
 }
@@ -38,7 +41,9 @@ pub fn create_inline_in_predicate_pass() -> Pass {
     Pass {
         name: INLINE_PREDICATE_NAME,
         descr: "inline function calls in a predicate module.",
-        deps: vec![],
+        deps: vec![CALL_GRAPH_NAME],
+        preserves: vec![],
+        prune_unreachable: false,
         runner: ScopedPass::ModulePass(PassMutability::Transform(inline_in_predicate_module)),
     }
 }
@@ -49,54 +54,62 @@ pub fn create_inline_in_non_predicate_pass() -> Pass {
     Pass {
         name: INLINE_NONPREDICATE_NAME,
         descr: "inline function calls in a non-predicate module.",
-        deps: vec![],
+        deps: vec![CALL_GRAPH_NAME],
+        preserves: vec![],
+        prune_unreachable: false,
         runner: ScopedPass::ModulePass(PassMutability::Transform(inline_in_non_predicate_module)),
     }
 }
 
-/// This is a copy of sway_core::inline::Inline.
-/// TODO: Reuse: Depend on sway_core? Move it to sway_types?
-#[derive(Debug)]
-enum Inline {
-    Always,
-    Never,
-}
 /// This is a copy of sway_core::asm_generation::compiler_constants.
 /// TODO: Once we have a target specific IR generator / legalizer,
 ///       use that to mark related functions as ALWAYS_INLINE.
 ///       Then we no longer depend on this const value below.
 const NUM_ARG_REGISTERS: u8 = 6;
 
-fn metadata_to_inline(context: &Context, md_idx: Option<MetadataIndex>) -> Option<Inline> {
-    fn for_each_md_idx<T, F: FnMut(MetadataIndex) -> Option<T>>(
-        context: &Context,
-        md_idx: Option<MetadataIndex>,
-        mut f: F,
-    ) -> Option<T> {
-        // If md_idx is not None and is a list then try them all.
-        md_idx.and_then(|md_idx| {
-            if let Some(md_idcs) = md_idx.get_content(context).unwrap_list() {
-                md_idcs.iter().find_map(|md_idx| f(*md_idx))
-            } else {
-                f(md_idx)
-            }
-        })
+/// The heuristics deciding which calls the `inline_non_predicate_module` pass inlines, set with
+/// [`Context::set_inline_policy`].
+///
+/// An `#[inline(never)]` attribute takes precedence, and calls which can't yet be lowered without
+/// inlining are always inlined.  By default every function called only once and every small
+/// function is inlined, which can take a large contract past the bytecode size limit; setting
+/// `max_weight` stops larger functions being copied into each caller.
+#[derive(Clone, Debug)]
+pub struct InlinePolicy {
+    /// Functions with an [`InlineCost::weight`] no greater than this are inlined.
+    pub small_fn_weight: u64,
+    /// Whether a function called from only one place is inlined, whatever its size.
+    pub inline_single_calls: bool,
+    /// Functions heavier than this are not inlined.
+    pub max_weight: Option<u64>,
+    /// Whether functions which access storage may be inlined.
+    pub inline_storage_access: bool,
+}
+
+impl Default for InlinePolicy {
+    fn default() -> Self {
+        InlinePolicy {
+            small_fn_weight: 4,
+            inline_single_calls: true,
+            max_weight: None,
+            inline_storage_access: true,
+        }
+    }
+}
+
+impl InlinePolicy {
+    /// Return whether a function with `cost`, which is called from `call_count` places, should be
+    /// inlined.
+    pub fn should_inline(&self, cost: &InlineCost, call_count: u64) -> bool {
+        if self
+            .max_weight
+            .map_or(false, |max_weight| cost.weight > max_weight)
+            || (cost.accesses_storage && !self.inline_storage_access)
+        {
+            return false;
+        }
+        (self.inline_single_calls && call_count == 1) || cost.weight <= self.small_fn_weight
     }
-    for_each_md_idx(context, md_idx, |md_idx| {
-        // Create a new inline and save it in the cache.
-        md_idx
-            .get_content(context)
-            .unwrap_struct("inline", 1)
-            .and_then(|fields| fields[0].unwrap_string())
-            .and_then(|inline_str| {
-                let inline = match inline_str {
-                    "always" => Some(Inline::Always),
-                    "never" => Some(Inline::Never),
-                    _otherwise => None,
-                }?;
-                Some(inline)
-            })
-    })
 }
 
 /// Inline function calls based on two conditions:
@@ -107,25 +120,26 @@ fn metadata_to_inline(context: &Context, md_idx: Option<MetadataIndex>) -> Optio
 ///    in the `inline_heuristc` closure in `inline_in_non_predicate_module`.
 pub fn inline_in_predicate_module(
     context: &mut Context,
-    _: &AnalysisResults,
+    analyses: &AnalysisResults,
     module: Module,
 ) -> Result<bool, IrError> {
-    let cg =
-        call_graph::build_call_graph(context, &module.function_iter(context).collect::<Vec<_>>());
+    let cg: &CallGraph = analyses.get_analysis_result(module);
 
-    let functions = call_graph::callee_first_order(&cg);
+    let functions = call_graph::callee_first_order(cg);
 
     let mut modified = false;
 
     for function in functions {
-        modified |= inline_all_function_calls(context, &function)?;
+        if !function.is_optimized(context) {
+            modified |= inline_all_function_calls(context, &function)?;
+        }
     }
     Ok(modified)
 }
 
 pub fn inline_in_non_predicate_module(
     context: &mut Context,
-    _: &AnalysisResults,
+    analyses: &AnalysisResults,
     module: Module,
 ) -> Result<bool, IrError> {
     // Inspect ALL calls and count how often each function is called.
@@ -144,8 +158,16 @@ pub fn inline_in_non_predicate_module(
                 counts
             });
 
+    let policy = context.get_inline_policy().clone();
     let inline_heuristic = |ctx: &Context, func: &Function, _call_site: &Value| {
-        let attributed_inline = metadata_to_inline(ctx, func.get_metadata(ctx));
+        if func.has_attribute(ctx, FunctionAttribute::InlineNever) {
+            return false;
+        }
+        if func.has_attribute(ctx, FunctionAttribute::InlineAlways) {
+            return true;
+        }
+
+        let attributed_inline = func.get_inline(ctx);
         match attributed_inline {
             Some(Inline::Always) => {
                 // TODO: check if inlining of function is possible
@@ -163,17 +185,6 @@ pub fn inline_in_non_predicate_module(
             return true;
         }
 
-        // If the function is called only once then definitely inline it.
-        if call_counts.get(func).copied().unwrap_or(0) == 1 {
-            return true;
-        }
-
-        // If the function is (still) small then also inline it.
-        const MAX_INLINE_INSTRS_COUNT: usize = 4;
-        if func.num_instructions(ctx) <= MAX_INLINE_INSTRS_COUNT {
-            return true;
-        }
-
         // As per https://github.com/FuelLabs/sway/issues/2819 we can hit problems if a function
         // argument is used as a pointer (probably because it has a ref type) although it actually
         // isn't one.  Ref type args which aren't pointers need to be inlined.
@@ -188,16 +199,28 @@ pub fn inline_in_non_predicate_module(
             return true;
         }
 
-        false
+        // Cold functions aren't worth growing their callers for.
+        if func.has_attribute(ctx, FunctionAttribute::Cold) {
+            return false;
+        }
+
+        // Otherwise it's up to the policy, which by default inlines functions which are called
+        // only once or are (still) small.
+        policy.should_inline(
+            &func.inline_cost(ctx),
+            call_counts.get(func).copied().unwrap_or(0),
+        )
     };
 
-    let cg =
-        call_graph::build_call_graph(context, &module.function_iter(context).collect::<Vec<_>>());
-    let functions = call_graph::callee_first_order(&cg);
+    let cg: &CallGraph = analyses.get_analysis_result(module);
+    let functions = call_graph::callee_first_order(cg);
     let mut modified = false;
 
     for function in functions {
-        modified |= inline_some_function_calls(context, &function, inline_heuristic)?;
+        // Functions loaded already optimized from a cache have had their calls inlined.
+        if !function.is_optimized(context) {
+            modified |= inline_some_function_calls(context, &function, inline_heuristic)?;
+        }
     }
     Ok(modified)
 }
@@ -209,7 +232,7 @@ pub fn inline_in_main(
 ) -> Result<bool, IrError> {
     // For now we inline everything into `main()`.  Eventually we can be more selective.
     for function in module.function_iter(context) {
-        if function.get_name(context) == "main" {
+        if function.get_name(context) == "main" && !function.is_optimized(context) {
             return inline_all_function_calls(context, &function);
         }
     }
@@ -243,32 +266,26 @@ pub fn inline_some_function_calls<F: Fn(&Context, &Function, &Value) -> bool>(
     predicate: F,
 ) -> Result<bool, IrError> {
     // Find call sites which passes the predicate.
-    // We use a RefCell so that the inliner can modify the value
-    // when it moves other instructions (which could be in call_date) after an inline.
-    let call_data: FxHashMap<Value, RefCell<(Block, Function)>> = function
+    let call_sites: Vec<(Value, Function)> = function
         .instruction_iter(context)
-        .filter_map(|(block, call_val)| match context.values[call_val.0].value {
-            ValueDatum::Instruction(Instruction::Call(inlined_function, _)) => {
+        .filter_map(|(_, call_val)| match context.values[call_val.0].value {
+            // A function can't be inlined into itself, as its body is what's being copied.
+            ValueDatum::Instruction(Instruction::Call(inlined_function, _))
+                if inlined_function != *function =>
+            {
                 predicate(context, &inlined_function, &call_val)
-                    .then_some((call_val, RefCell::new((block, inlined_function))))
+                    .then_some((call_val, inlined_function))
             }
             _ => None,
         })
         .collect();
 
-    for (call_site, call_site_in) in &call_data {
-        let (block, inlined_function) = *call_site_in.borrow();
-        inline_function_call(
-            context,
-            *function,
-            block,
-            *call_site,
-            inlined_function,
-            &call_data,
-        )?;
+    for (call_site, inlined_function) in &call_sites {
+        inline_function_call(context, *function, *call_site, *inlined_function)?;
+        context.add_pass_statistic("calls inlined", 1);
     }
 
-    Ok(!call_data.is_empty())
+    Ok(!call_sites.is_empty())
 }
 
 /// A utility to get a predicate which can be passed to inline_some_function_calls() based on
@@ -325,52 +342,42 @@ pub fn is_small_fn(
 
 /// Inline a function to a specific call site within another function.
 ///
-/// The destination function, block and call site must be specified along with the function to
-/// inline.
-
+/// The destination function and call site must be specified along with the function to inline.
 pub fn inline_function_call(
     context: &mut Context,
     function: Function,
-    block: Block,
     call_site: Value,
     inlined_function: Function,
-    call_data: &FxHashMap<Value, RefCell<(Block, Function)>>,
 ) -> Result<(), IrError> {
     // Split the block at right after the call site.
+    let block = call_site
+        .get_block(context)
+        .expect("Call site must be in a block.");
     let call_site_idx = context.blocks[block.0]
         .instructions
         .iter()
         .position(|&v| v == call_site)
         .unwrap();
     let (pre_block, post_block) = block.split_at(context, call_site_idx + 1);
-    if post_block != block {
-        // We need to update call_data for every call_site that was in block.
-        for inst in post_block.instruction_iter(context).filter(|inst| {
-            matches!(
-                context.values[inst.0].value,
-                ValueDatum::Instruction(Instruction::Call(..))
-            )
-        }) {
-            if let Some(call_info) = call_data.get(&inst) {
-                call_info.borrow_mut().0 = post_block;
-            }
-        }
-    }
 
     // Remove the call from the pre_block instructions.  It's still in the context.values[] though.
     context.blocks[pre_block.0].instructions.pop();
+    call_site.set_block(context, None);
 
     // Returned values, if any, go to `post_block`, so a block arg there.
     // We don't expect `post_block` to already have any block args.
     if post_block.new_arg(context, call_site.get_type(context).unwrap(), false) != 0 {
         panic!("Expected newly created post_block to not have block args")
     }
-    function.replace_value(
-        context,
-        call_site,
-        post_block.get_arg(context, 0).unwrap(),
-        None,
-    );
+    let returned_val = post_block.get_arg(context, 0).unwrap();
+    function.replace_value(context, call_site, returned_val, None);
+
+    // The user variables held by the call's result are now held by the returned value, rather
+    // than by every inlined instruction.
+    for variable in call_site.take_debug_variables(context) {
+        returned_val.add_debug_variable(context, &variable);
+    }
+    let call_span = call_site.get_span(context);
 
     // Take the locals from the inlined function and add them to this function.  `value_map` is a
     // map from the original local ptrs to the new ptrs.
@@ -389,13 +396,28 @@ pub fn inline_function_call(
             value_map.insert(arg_val.1, *passed_val);
         }
     }
+    let arg_vals = inlined_function
+        .args_iter(context)
+        .map(|(_, arg_val)| *arg_val)
+        .collect::<Vec<_>>();
+    for arg_val in arg_vals {
+        let Some(passed_val) = value_map.get(&arg_val).copied() else {
+            continue;
+        };
+        for variable in arg_val.get_debug_variables(context) {
+            passed_val.add_debug_variable(context, &inlined_variable(variable, call_span));
+        }
+    }
+    for variable in inlined_function.get_debug_variables(context) {
+        function.add_debug_variable(context, &inlined_variable(variable, call_span));
+    }
 
     // Get the metadata attached to the function call which may need to be propagated to the
     // inlined instructions.
     let metadata = context.values[call_site.0].metadata;
 
     // Now remove the call altogether.
-    context.values.remove(call_site.0);
+    call_site.free(context);
 
     // Insert empty blocks from the inlined function between our split blocks, and create a mapping
     // from old blocks to new.  We need this when inlining branch instructions, so they branch to
@@ -432,7 +454,11 @@ pub fn inline_function_call(
             }) = &context.values[inlined_arg.0].value
             {
                 let index = new_block.new_arg(context, *ty, *by_ref);
-                value_map.insert(inlined_arg, new_block.get_arg(context, index).unwrap());
+                let new_arg = new_block.get_arg(context, index).unwrap();
+                for variable in inlined_arg.get_debug_variables(context) {
+                    new_arg.add_debug_variable(context, &inlined_variable(variable, call_span));
+                }
+                value_map.insert(inlined_arg, new_arg);
             } else {
                 unreachable!("Expected a block argument")
             }
@@ -444,6 +470,10 @@ pub fn inline_function_call(
     // old values (locals and args at this stage) to new values.  We can copy instructions over,
     // translating their blocks and values to refer to the new ones.  The value map is still live
     // as we add new instructions which replace the old ones to it too.
+    let inlined_from = InlinedFrom {
+        function: inlined_fn_name,
+        span: inlined_function.get_span(context),
+    };
     let inlined_blocks = context.functions[inlined_function.0].blocks.clone();
     for block in &inlined_blocks {
         for ins in context.blocks[block.0].instructions.clone() {
@@ -456,6 +486,8 @@ pub fn inline_function_call(
                 &mut value_map,
                 &ptr_map,
                 metadata,
+                call_span,
+                &inlined_from,
             );
         }
     }
@@ -473,6 +505,8 @@ fn inline_instruction(
     value_map: &mut HashMap<Value, Value>,
     local_map: &HashMap<LocalVar, LocalVar>,
     fn_metadata: Option<MetadataIndex>,
+    call_span: Option<SourceSpan>,
+    inlined_from: &InlinedFrom,
 ) {
     // Util to translate old blocks to new.  If an old block isn't in the map then we panic, since
     // it should be guaranteed to be there...that's a bug otherwise.
@@ -493,6 +527,7 @@ fn inline_instruction(
     if let ValueContent {
         value: ValueDatum::Instruction(old_ins),
         metadata: val_metadata,
+        ..
     } = context.values[instruction.0].clone()
     {
         // Combine the function metadata with this instruction metadata so we don't lose the
@@ -581,6 +616,9 @@ fn inline_instruction(
                 .ins(context)
                 .extract_value(map_value(aggregate), ty, indices),
             Instruction::FuelVm(fuel_vm_instr) => match fuel_vm_instr {
+                FuelVmInstruction::GasCheckpoint(id) => {
+                    new_block.ins(context).gas_checkpoint(map_value(id))
+                }
                 FuelVmInstruction::GetStorageKey => new_block.ins(context).get_storage_key(),
                 FuelVmInstruction::Gtf { index, tx_field_id } => {
                     new_block.ins(context).gtf(map_value(index), tx_field_id)
@@ -686,6 +724,25 @@ fn inline_instruction(
         }
         .add_metadatum(context, metadata);
 
+        for variable in new_ins.take_debug_variables(context) {
+            new_ins.add_debug_variable(context, &inlined_variable(variable, call_span));
+        }
+
+        // The instruction is attributed to the function it was written in, replacing the call's
+        // own function if that was inlined too.
+        let inlined_from =
+            get_inlined_from(context, val_metadata).unwrap_or_else(|| inlined_from.clone());
+        new_ins.set_inlined_from(context, &inlined_from);
+
         value_map.insert(*instruction, new_ins);
     }
 }
+
+// A user variable of an inlined function is marked with the call it was inlined at, unless it was
+// inlined into that function already.
+fn inlined_variable(mut variable: DebugVariable, call_span: Option<SourceSpan>) -> DebugVariable {
+    if variable.inlined_at.is_none() {
+        variable.inlined_at = call_span;
+    }
+    variable
+}
@@ -0,0 +1,60 @@
+//! Instruction combining.
+//!
+//! Simplifies arithmetic, logic and comparison instructions using the algebraic [`Rule`]s
+//! returned by [`instcombine_rules`], which are written with the [pattern DSL](crate::pattern).
+//!
+//! The rules must preserve the behaviour of the VM exactly.  In particular, arithmetic which may
+//! overflow is never reassociated, since that could remove a panic.
+
+use crate::{
+    apply_rules, rule, AnalysisResults, Context, Function, IrError, Pass, PassMutability, Rule,
+    ScopedPass, CALL_GRAPH_NAME, CFG_ANALYSES,
+};
+
+pub const INSTCOMBINE_NAME: &str = "instcombine";
+
+pub fn create_instcombine_pass() -> Pass {
+    Pass {
+        name: INSTCOMBINE_NAME,
+        descr: "Algebraic simplification of instructions.",
+        deps: vec![],
+        preserves: [CFG_ANALYSES.as_slice(), &[CALL_GRAPH_NAME]].concat(),
+        prune_unreachable: false,
+        runner: ScopedPass::FunctionPass(PassMutability::Transform(instcombine)),
+    }
+}
+
+pub fn instcombine(
+    context: &mut Context,
+    _: &AnalysisResults,
+    function: Function,
+) -> Result<bool, IrError> {
+    Ok(apply_rules(context, function, &instcombine_rules()))
+}
+
+/// The rules applied by the `instcombine` pass, in order.
+pub fn instcombine_rules() -> Vec<Rule> {
+    vec![
+        // Identities.
+        rule!(add_zero: (add x 0) => x),
+        rule!(sub_zero: (sub x 0) => x),
+        rule!(mul_one: (mul x 1) => x),
+        rule!(div_one: (div x 1) => x),
+        rule!(and_self: (and x x) => x),
+        rule!(or_zero: (or x 0) => x),
+        rule!(or_self: (or x x) => x),
+        rule!(xor_zero: (xor x 0) => x),
+        // Annihilators.
+        rule!(sub_self: (sub x x) => 0),
+        rule!(mul_zero: (mul x 0) => 0),
+        rule!(and_zero: (and x 0) => 0),
+        rule!(xor_self: (xor x x) => 0),
+        rule!(eq_self: (eq x x) => true),
+        rule!(lt_self: (lt x x) => false),
+        rule!(gt_self: (gt x x) => false),
+        // Reassociation of constants, which can't overflow for bitwise operations.
+        rule!(and_consts: (and (one_use and x (const a)) (const b)) => (and x (and a b))),
+        rule!(or_consts: (or (one_use or x (const a)) (const b)) => (or x (or a b))),
+        rule!(xor_consts: (xor (one_use xor x (const a)) (const b)) => (xor x (xor a b))),
+    ]
+}
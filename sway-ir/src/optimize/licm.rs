@@ -0,0 +1,185 @@
+//! Loop-invariant code motion.
+//!
+//! An instruction in a loop is invariant if each of its operands is defined outside the loop or
+//! is itself invariant, so it computes the same value on every iteration and may instead be
+//! computed once in the loop's preheader.  Only instructions without side effects are hoisted:
+//! arithmetic, comparisons, casts, local and field addressing, and loads from locals which can't
+//! be modified in the loop.
+//!
+//! The preheader is run even when the loop would have been left before reaching a hoisted
+//! instruction, so those which may panic, such as overflowing arithmetic or an out of bounds
+//! `extract_element`, are only hoisted from the header, ahead of any instruction with side
+//! effects, as the header is run at least once whenever the preheader is.  Loops are visited
+//! innermost first so an instruction may be hoisted out of several nested loops in turn.
+
+use rustc_hash::FxHashSet;
+
+use crate::{
+    AnalysisResults, BinaryOpKind, Block, Context, Function, Instruction, IrError, LocalVar, Loop,
+    LoopInfo, Pass, PassMutability, ScopedPass, Value, LOOPS_NAME,
+};
+
+pub const LICM_NAME: &str = "licm";
+
+pub fn create_licm_pass() -> Pass {
+    Pass {
+        name: LICM_NAME,
+        descr: "Hoist loop-invariant instructions into loop preheaders.",
+        deps: vec![LOOPS_NAME],
+        preserves: vec![],
+        prune_unreachable: false,
+        runner: ScopedPass::FunctionPass(PassMutability::Transform(licm)),
+    }
+}
+
+pub fn licm(
+    context: &mut Context,
+    analyses: &AnalysisResults,
+    function: Function,
+) -> Result<bool, IrError> {
+    let loop_info: &LoopInfo = analyses.get_analysis_result(function);
+    let mut loop_info = loop_info.clone();
+    let unescaped_locals = unescaped_locals(context, &function);
+
+    let mut order = (0..loop_info.loops.len()).collect::<Vec<_>>();
+    order.sort_by_key(|idx| std::cmp::Reverse(loop_info.depth(*idx)));
+
+    let mut modified = false;
+    for idx in order {
+        let hoistable = find_hoistable(context, &loop_info.loops[idx], &unescaped_locals);
+        if hoistable.is_empty() {
+            continue;
+        }
+        let Some(preheader) = loop_info.get_or_create_preheader(context, idx) else {
+            continue;
+        };
+        for (block, ins) in hoistable {
+            context.blocks[block.0]
+                .instructions
+                .retain(|other| *other != ins);
+            let idx = preheader.num_instructions(context) - 1;
+            preheader.insert_instructions(context, idx, [ins]);
+            ins.drop_attachments(context);
+        }
+        modified = true;
+    }
+    Ok(modified)
+}
+
+// The locals whose pointers are only ever loaded from or stored to, so which can't be modified
+// by anything other than those stores.
+fn unescaped_locals(context: &Context, function: &Function) -> FxHashSet<LocalVar> {
+    let as_local = |val: &Value| match val.get_instruction(context) {
+        Some(Instruction::GetLocal(local_var)) => Some(*local_var),
+        _ => None,
+    };
+    let mut locals = FxHashSet::default();
+    let mut escaped = FxHashSet::default();
+    for (_, ins) in function.instruction_iter(context) {
+        let instruction = ins.get_instruction(context).unwrap();
+        match instruction {
+            Instruction::GetLocal(local_var) => {
+                locals.insert(*local_var);
+            }
+            Instruction::Load(_) => (),
+            Instruction::Store { stored_val, .. } => escaped.extend(as_local(stored_val)),
+            _ => escaped.extend(instruction.get_operands().iter().filter_map(as_local)),
+        }
+    }
+    locals.retain(|local_var| !escaped.contains(local_var));
+    locals
+}
+
+// The invariant instructions of a loop which may be hoisted, in an order in which each follows
+// any of the others it uses.
+fn find_hoistable(
+    context: &Context,
+    lp: &Loop,
+    unescaped_locals: &FxHashSet<LocalVar>,
+) -> Vec<(Block, Value)> {
+    let blocks = lp.blocks_iter(context).collect::<Vec<_>>();
+
+    // The values defined in the loop which aren't yet known to be invariant.
+    let mut variant = blocks
+        .iter()
+        .flat_map(|block| {
+            block
+                .arg_iter(context)
+                .copied()
+                .chain(block.instruction_iter(context))
+        })
+        .collect::<FxHashSet<_>>();
+
+    let instructions = blocks
+        .iter()
+        .flat_map(|block| block.instruction_iter(context).map(|ins| (*block, ins)))
+        .collect::<Vec<_>>();
+    let writes_memory = instructions
+        .iter()
+        .any(|(_, ins)| ins.get_instruction(context).unwrap().may_have_side_effect());
+    let stored_locals = instructions
+        .iter()
+        .filter_map(|(_, ins)| match ins.get_instruction(context) {
+            Some(Instruction::Store { dst_val, .. }) => match dst_val.get_instruction(context) {
+                Some(Instruction::GetLocal(local_var)) => Some(*local_var),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect::<FxHashSet<_>>();
+
+    // The instructions in the header which are run on every iteration before anything with a side
+    // effect, so may panic in the preheader instead.
+    let header_prefix = lp
+        .header
+        .instruction_iter(context)
+        .take_while(|ins| !ins.get_instruction(context).unwrap().may_have_side_effect())
+        .collect::<FxHashSet<_>>();
+
+    let mut hoistable = Vec::new();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (block, ins) in &instructions {
+            if !variant.contains(ins) {
+                continue;
+            }
+            let instruction = ins.get_instruction(context).unwrap();
+            if instruction
+                .get_operands()
+                .iter()
+                .any(|opd| variant.contains(opd))
+            {
+                continue;
+            }
+            let is_hoistable = match instruction {
+                Instruction::BinaryOp {
+                    op: BinaryOpKind::And | BinaryOpKind::Or | BinaryOpKind::Xor,
+                    ..
+                }
+                | Instruction::AddrOf(_)
+                | Instruction::BitCast(..)
+                | Instruction::CastPtr(..)
+                | Instruction::Cmp(..)
+                | Instruction::GetLocal(_)
+                | Instruction::IntToPtr(..) => true,
+                Instruction::BinaryOp { .. } => header_prefix.contains(ins),
+                Instruction::ExtractValue { .. } => !writes_memory,
+                Instruction::ExtractElement { .. } => !writes_memory && header_prefix.contains(ins),
+                Instruction::Load(ptr) => match ptr.get_instruction(context) {
+                    Some(Instruction::GetLocal(local_var)) => {
+                        unescaped_locals.contains(local_var) && !stored_locals.contains(local_var)
+                    }
+                    _ => false,
+                },
+                _ => false,
+            };
+            if is_hoistable {
+                variant.remove(ins);
+                hoistable.push((*block, *ins));
+                changed = true;
+            }
+        }
+    }
+    hoistable
+}
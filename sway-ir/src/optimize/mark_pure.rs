@@ -0,0 +1,37 @@
+//! Mark the functions found to be pure by the purity analysis with the `pure` attribute, so that
+//! calls to them whose results are unused may be removed.
+
+use crate::{
+    AnalysisResults, Context, FunctionAttribute, IrError, Module, ModuleEffects, Pass,
+    PassMutability, ScopedPass, CALL_GRAPH_NAME, CFG_ANALYSES, PURITY_NAME,
+};
+
+pub const MARK_PURE_NAME: &str = "mark_pure";
+
+pub fn create_mark_pure_pass() -> Pass {
+    Pass {
+        name: MARK_PURE_NAME,
+        descr: "Mark pure functions with the `pure` attribute.",
+        deps: vec![PURITY_NAME],
+        preserves: [CFG_ANALYSES.as_slice(), &[CALL_GRAPH_NAME, PURITY_NAME]].concat(),
+        prune_unreachable: false,
+        runner: ScopedPass::ModulePass(PassMutability::Transform(mark_pure)),
+    }
+}
+
+pub fn mark_pure(
+    context: &mut Context,
+    analyses: &AnalysisResults,
+    module: Module,
+) -> Result<bool, IrError> {
+    let effects: &ModuleEffects = analyses.get_analysis_result(module);
+    let mut modified = false;
+    for function in module.function_iter(context).collect::<Vec<_>>() {
+        if effects[&function].is_pure() && !function.has_attribute(context, FunctionAttribute::Pure)
+        {
+            function.add_attribute(context, FunctionAttribute::Pure);
+            modified = true;
+        }
+    }
+    Ok(modified)
+}
@@ -8,9 +8,9 @@ use std::collections::{HashMap, HashSet};
 use sway_utils::mapped_stack::MappedStack;
 
 use crate::{
-    AnalysisResults, Block, BranchToWithArgs, Context, DomFronts, DomTree, Function, Instruction,
-    IrError, LocalVar, Pass, PassMutability, PostOrder, ScopedPass, Type, Value, ValueDatum,
-    DOMFRONTS_NAME, DOMINATORS_NAME, POSTORDER_NAME,
+    AnalysisResults, Block, BranchToWithArgs, Constant, Context, DebugVariable, DomFronts, DomTree,
+    Function, Instruction, IrError, LocalVar, Pass, PassMutability, PostOrder, ScopedPass, Type,
+    Value, ValueDatum, DOMFRONTS_NAME, DOMINATORS_NAME, POSTORDER_NAME,
 };
 
 pub const MEM2REG_NAME: &str = "mem2reg";
@@ -20,6 +20,8 @@ pub fn create_mem2reg_pass() -> Pass {
         name: MEM2REG_NAME,
         descr: "Promote local memory to SSA registers.",
         deps: vec![POSTORDER_NAME, DOMINATORS_NAME, DOMFRONTS_NAME],
+        preserves: vec![],
+        prune_unreachable: false,
         runner: ScopedPass::FunctionPass(PassMutability::Transform(promote_to_registers)),
     }
 }
@@ -39,6 +41,43 @@ fn get_validate_local_var(
     }
 }
 
+// The value of a local before anything is stored to it: its initializer, or `undef` if it has
+// none, like any other read of uninitialized memory.
+fn initial_value(context: &mut Context, local_var: LocalVar) -> Value {
+    let constant = local_var
+        .get_initializer(context)
+        .cloned()
+        .unwrap_or_else(|| Constant::get_undef(local_var.get_type(context)));
+    Value::new_constant(context, constant)
+}
+
+// Remove the user variables from the `get_local`s of `locals`, returning them by local.
+fn take_local_variables(
+    context: &mut Context,
+    function: &Function,
+    locals: &HashSet<String>,
+) -> HashMap<String, Vec<DebugVariable>> {
+    let mut variables = HashMap::<String, Vec<DebugVariable>>::new();
+    let get_locals = function
+        .instruction_iter(context)
+        .filter_map(|(_, inst)| {
+            get_validate_local_var(context, function, &inst)
+                .filter(|(local, _)| locals.contains(local))
+                .map(|(local, _)| (local, inst))
+        })
+        .collect::<Vec<_>>();
+    for (local, inst) in get_locals {
+        let local_variables = variables.entry(local).or_default();
+        for variable in inst.take_debug_variables(context) {
+            if !local_variables.contains(&variable) {
+                local_variables.push(variable);
+            }
+        }
+    }
+    variables.retain(|_, local_variables| !local_variables.is_empty());
+    variables
+}
+
 // Returns those locals that can be promoted to SSA registers.
 fn filter_usable_locals(context: &mut Context, function: &Function) -> HashSet<String> {
     // The size of an SSA register is target specific.  Here we're going to just stick with atomic
@@ -178,7 +217,10 @@ pub fn promote_to_registers(
     // Transitively add PHIs, till nothing more to do.
     while !worklist.is_empty() {
         let (local, ty, known_def) = worklist.pop().unwrap();
-        for df in dom_fronts[&known_def].iter() {
+        // The frontier is a set, so visit it in postorder to add the PHIs in a stable order.
+        let mut frontier = dom_fronts[&known_def].iter().collect::<Vec<_>>();
+        frontier.sort_by_key(|block| po.block_to_po[block]);
+        for df in frontier {
             if !new_phi_tracker.contains(&(local.clone(), *df)) && liveins[df].contains(&local) {
                 // Insert PHI for this local at block df.
                 let index = df.new_arg(context, ty, false);
@@ -229,15 +271,7 @@ pub fn promote_to_registers(
                             // We should replace all uses of inst with new_stack[local].
                             let new_val = match name_stack.get(&local) {
                                 Some(val) => *val,
-                                None => {
-                                    // Nothing on the stack, let's attempt to get the initializer
-                                    Value::new_constant(
-                                        context,
-                                        var.get_initializer(context)
-                                            .expect("We're dealing with an uninitialized value")
-                                            .clone(),
-                                    )
-                                }
+                                None => initial_value(context, var),
                             };
                             rewrites.insert(inst, new_val);
                             deletes.push((node, inst));
@@ -278,18 +312,12 @@ pub fn promote_to_registers(
                     let ptr = function.get_local_var(context, local).unwrap();
                     let new_val = match name_stack.get(local) {
                         Some(val) => *val,
-                        None => {
-                            // Nothing on the stack, let's attempt to get the initializer
-                            Value::new_constant(
-                                context,
-                                ptr.get_initializer(context)
-                                    .expect("We're dealing with an uninitialized value")
-                                    .clone(),
-                            )
-                        }
+                        None => initial_value(context, ptr),
                     };
                     let params = node.get_succ_params_mut(context, &succ).unwrap();
                     params.push(new_val);
+                    let terminator = *context.blocks[node.0].instructions.last().unwrap();
+                    terminator.update_uses(context);
                 }
             }
         }
@@ -332,6 +360,40 @@ pub fn promote_to_registers(
         &mut delete_insts,
     );
 
+    // Move the user variables of the promoted locals on to the values which hold them now.
+    let variables = take_local_variables(context, &function, &safe_locals);
+    if !variables.is_empty() {
+        let resolve = |mut val: Value| {
+            while let Some(new_val) = value_replacement.get(&val) {
+                val = *new_val;
+            }
+            val
+        };
+        let mut holders = function
+            .block_iter(context)
+            .flat_map(|block| &context.blocks[block.0].args)
+            .filter_map(|arg| phi_to_local.get(arg).map(|local| (*arg, local.clone())))
+            .collect::<Vec<_>>();
+        for (_, inst) in &delete_insts {
+            let (ptr, holder) = match context.values[inst.0].value {
+                ValueDatum::Instruction(Instruction::Load(ptr)) => (ptr, resolve(*inst)),
+                ValueDatum::Instruction(Instruction::Store {
+                    dst_val,
+                    stored_val,
+                }) => (dst_val, resolve(stored_val)),
+                _ => continue,
+            };
+            if let Some((local, _)) = get_validate_local_var(context, &function, &ptr) {
+                holders.push((holder, local));
+            }
+        }
+        for (holder, local) in holders {
+            for variable in variables.get(&local).into_iter().flatten() {
+                holder.add_debug_variable(context, variable);
+            }
+        }
+    }
+
     // Apply the rewrites.
     function.replace_values(context, &value_replacement, None);
     // Delete the loads and stores.
@@ -0,0 +1,389 @@
+//! Packed ABI encoding lowering.
+//!
+//! By default the arguments of a contract's ABI methods are passed in call data laid out exactly
+//! as they are in memory, where every scalar takes a whole word.  The packed encoding instead
+//! gives each value only the bytes it needs:
+//!
+//! - `bool` and `u8` take one byte, and `u16`, `u32` and `u64` two, four and eight bytes, all big
+//!   endian.
+//! - `b256` takes 32 bytes and `str[n]` exactly `n` bytes.
+//! - Arrays and structs are their elements or fields, one after another without padding.
+//! - Unions are copied whole, as their size is fixed but their contents depend on the tag.
+//! - Slices are dynamic, taking a `u64` length in bytes followed by the data.
+//!
+//! With the packed encoding the call frame always holds the address of the encoded arguments.
+//! [`lower_packed_abi`] gives each ABI method a prologue which decodes them into a local laid out
+//! as before, so the rest of the method is unchanged.
+//!
+//! Return values which are returned as data rather than in a register, i.e., those which aren't
+//! `()`, `bool` or integers, are encoded the same way before each `ret`, unless they include a
+//! slice.  The method then returns a string of the encoded bytes, which the VM pads to a whole
+//! number of words.
+
+use sway_types::Ident;
+
+use crate::{
+    AnalysisResults, AsmArg, AsmBlock, AsmInstruction, BlockArgument, Context, Function,
+    InsertionPoint, Instruction, IrError, Module, Pass, PassMutability, ScopedPass, Type,
+    TypeContent, Value, ValueDatum,
+};
+
+pub const PACKED_ABI_NAME: &str = "packed_abi";
+
+pub fn create_packed_abi_pass() -> Pass {
+    Pass {
+        name: PACKED_ABI_NAME,
+        descr: "Lower contract ABI method arguments and return values to the packed encoding.",
+        deps: vec![],
+        preserves: vec![],
+        prune_unreachable: false,
+        runner: ScopedPass::ModulePass(PassMutability::Transform(packed_abi)),
+    }
+}
+
+fn packed_abi(context: &mut Context, _: &AnalysisResults, module: Module) -> Result<bool, IrError> {
+    Ok(lower_packed_abi(context, module))
+}
+
+/// The number of bytes taken by a value of type `ty` in the packed encoding, or `None` if it
+/// includes a slice and so varies.
+pub fn packed_size(context: &Context, ty: &Type) -> Option<u64> {
+    match ty.get_content(context) {
+        TypeContent::Unit => Some(0),
+        TypeContent::Bool => Some(1),
+        TypeContent::Uint(nbits) => Some(*nbits as u64 / 8),
+        TypeContent::B256 => Some(32),
+        TypeContent::String(n) => Some(*n),
+        TypeContent::Array(elem_ty, n) => packed_size(context, elem_ty).map(|size| size * n),
+        TypeContent::Struct(field_tys) => field_tys
+            .iter()
+            .map(|field_ty| packed_size(context, field_ty))
+            .sum(),
+        TypeContent::Union(_) => Some(word_layout_size(context, ty)),
+        TypeContent::Slice => None,
+    }
+}
+
+/// Give every ABI method in `module` a single argument, the address of its arguments in the
+/// packed encoding, and a prologue decoding them.  Methods returning data encode it before
+/// returning.  Returns whether any methods were lowered.
+pub fn lower_packed_abi(context: &mut Context, module: Module) -> bool {
+    let methods = module
+        .function_iter(context)
+        .filter(|function| function.has_selector(context))
+        .collect::<Vec<_>>();
+    let mut modified = false;
+    for function in methods {
+        if function.num_args(context) > 0 {
+            lower_args(context, function);
+            modified = true;
+        }
+        let ret_ty = function.get_return_type(context);
+        if let Some(size) = packed_return_size(context, &ret_ty) {
+            lower_returns(context, function, ret_ty, size);
+            modified = true;
+        }
+    }
+    modified
+}
+
+/// The packed size of a return value of type `ty`, if it's returned as data and so encoded.
+fn packed_return_size(context: &Context, ty: &Type) -> Option<u64> {
+    if ty.is_unit(context) || ty.is_bool(context) || ty.is_uint(context) {
+        None
+    } else {
+        packed_size(context, ty)
+    }
+}
+
+fn lower_args(context: &mut Context, function: Function) {
+    let entry = function.get_entry_block(context);
+    let (arg_names, arg_vals): (Vec<String>, Vec<Value>) =
+        function.args_iter(context).cloned().unzip();
+    let arg_tys = arg_vals
+        .iter()
+        .map(|arg| arg.get_type(context).unwrap())
+        .collect::<Vec<_>>();
+
+    // The arguments are decoded into a local struct, which is how several arguments are laid out
+    // in the default encoding.
+    let args_ty = Type::new_struct(context, arg_tys.clone());
+    let args_local = function.new_unique_local_var(context, "__abi_args".to_owned(), args_ty, None);
+
+    let packed_arg = Value::new_argument(
+        context,
+        BlockArgument {
+            block: entry,
+            idx: 0,
+            ty: Type::get_uint64(context),
+            by_ref: false,
+        },
+    );
+    let mut prologue = Vec::new();
+    let mut push = |context: &mut Context, instruction| {
+        let value = Value::new_instruction(context, instruction);
+        prologue.push(value);
+        value
+    };
+
+    let args_ptr = push(context, Instruction::GetLocal(args_local));
+    let args_addr = push(context, Instruction::AddrOf(args_ptr));
+    let body = decoder_body(context, args_ty);
+    let asm_args = vec![
+        AsmArg {
+            name: ident("dst"),
+            initializer: Some(args_addr),
+        },
+        AsmArg {
+            name: ident("packed"),
+            initializer: Some(packed_arg),
+        },
+    ];
+    let unit_ty = Type::get_unit(context);
+    let asm = AsmBlock::new(
+        context,
+        asm_args.iter().map(|arg| arg.name.clone()).collect(),
+        body,
+        unit_ty,
+        None,
+    );
+    push(context, Instruction::AsmBlock(asm, asm_args));
+
+    let mut replacements = rustc_hash::FxHashMap::default();
+    for (idx, (old_arg, arg_ty)) in arg_vals.iter().zip(arg_tys).enumerate() {
+        let new_arg = push(
+            context,
+            Instruction::ExtractValue {
+                aggregate: args_ptr,
+                ty: args_ty,
+                indices: vec![idx as u64],
+            },
+        );
+        // Keep the argument's metadata, e.g. its span, with the value replacing it.
+        let metadata = old_arg.get_metadata(context);
+        new_arg.add_metadatum(context, metadata);
+        debug_assert_eq!(new_arg.get_type(context), Some(arg_ty));
+        replacements.insert(*old_arg, new_arg);
+    }
+    function.replace_values(context, &replacements, None);
+
+    let name = if arg_names.len() == 1 {
+        arg_names[0].clone()
+    } else {
+        "__packed_args".to_owned()
+    };
+    context.functions[function.0].arguments = vec![(name, packed_arg)];
+    context.blocks[entry.0].args = vec![packed_arg];
+    entry.insert_instructions(context, 0, prologue);
+}
+
+/// Encode the value returned by each `ret` in `function` into a local string of `size` bytes,
+/// and return that instead.
+fn lower_returns(context: &mut Context, function: Function, ret_ty: Type, size: u64) {
+    let packed_ty = Type::new_string(context, size);
+    let packed_local =
+        function.new_unique_local_var(context, "__abi_ret".to_owned(), packed_ty, None);
+    let rets = function
+        .instruction_iter(context)
+        .filter_map(|(block, ins)| match ins.get_instruction(context) {
+            Some(Instruction::Ret(value, _)) => Some((block, ins, *value)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    for (block, ret, value) in rets {
+        let packed_ptr = block
+            .ins(context)
+            .position(InsertionPoint::Before(ret))
+            .get_local(packed_local);
+        // Both aggregates are passed to the asm block by reference.
+        let asm_args = vec![
+            AsmArg {
+                name: ident("value"),
+                initializer: Some(value),
+            },
+            AsmArg {
+                name: ident("packed"),
+                initializer: Some(packed_ptr),
+            },
+        ];
+        let body = encoder_body(context, ret_ty);
+        let unit_ty = Type::get_unit(context);
+        block
+            .ins(context)
+            .position(InsertionPoint::Before(ret))
+            .asm_block(asm_args, body, unit_ty, None);
+        ret.replace(
+            context,
+            ValueDatum::Instruction(Instruction::Ret(packed_ptr, packed_ty)),
+        );
+    }
+    context.functions[function.0].return_type = packed_ty;
+}
+
+/// The instructions copying each value of type `ty` from the packed encoding at `packed` to the
+/// default layout at `dst`.  The default layout is cleared first so that the unused bytes of
+/// words are zero.
+fn decoder_body(context: &Context, ty: Type) -> Vec<AsmInstruction> {
+    let mut body = vec![op("add", &["src", "packed", "zero"], None)];
+    load_immediate(&mut body, "len", word_layout_size(context, &ty));
+    body.push(op("mcl", &["dst", "len"], None));
+    decode_value(context, ty, 0, &mut body);
+    body
+}
+
+fn decode_value(context: &Context, ty: Type, offset: u64, body: &mut Vec<AsmInstruction>) {
+    // Copy `len` bytes from the source into the default layout at `offset`, and step past them.
+    let copy = |body: &mut Vec<AsmInstruction>, offset: u64, len: u64| {
+        if len > 0 {
+            load_immediate(body, "off", offset);
+            body.push(op("add", &["at", "dst", "off"], None));
+            load_immediate(body, "len", len);
+            body.extend([
+                op("mcp", &["at", "src", "len"], None),
+                op("add", &["src", "src", "len"], None),
+            ]);
+        }
+    };
+    match ty.get_content(context) {
+        TypeContent::Unit => (),
+        TypeContent::Bool => copy(body, offset + 7, 1),
+        TypeContent::Uint(nbits) => {
+            let len = *nbits as u64 / 8;
+            copy(body, offset + 8 - len, len)
+        }
+        TypeContent::B256 | TypeContent::Union(_) => {
+            copy(body, offset, word_layout_size(context, &ty))
+        }
+        TypeContent::String(n) => copy(body, offset, *n),
+        TypeContent::Array(elem_ty, n) => {
+            let elem_size = word_layout_size(context, elem_ty);
+            for idx in 0..*n {
+                decode_value(context, *elem_ty, offset + idx * elem_size, body);
+            }
+        }
+        TypeContent::Struct(field_tys) => {
+            let mut field_offset = offset;
+            for field_ty in field_tys {
+                decode_value(context, *field_ty, field_offset, body);
+                field_offset += word_layout_size(context, field_ty);
+            }
+        }
+        TypeContent::Slice => {
+            // The slice points at its data within the encoded arguments.
+            body.extend([
+                op("lw", &["len", "src"], Some(0)),
+                op("addi", &["src", "src"], Some(8)),
+            ]);
+            load_immediate(body, "off", offset);
+            body.extend([
+                op("add", &["at", "dst", "off"], None),
+                op("sw", &["at", "src"], Some(0)),
+                op("sw", &["at", "len"], Some(1)),
+                op("add", &["src", "src", "len"], None),
+            ]);
+        }
+    }
+}
+
+/// The instructions copying each value of type `ty`, which has no slices, from the default
+/// layout at `value` to the packed encoding at `packed`.
+fn encoder_body(context: &Context, ty: Type) -> Vec<AsmInstruction> {
+    let mut body = vec![op("add", &["dst", "packed", "zero"], None)];
+    encode_value(context, ty, 0, &mut body);
+    body
+}
+
+fn encode_value(context: &Context, ty: Type, offset: u64, body: &mut Vec<AsmInstruction>) {
+    // Copy `len` bytes from the default layout at `offset` to the destination, and step past
+    // them.
+    let copy = |body: &mut Vec<AsmInstruction>, offset: u64, len: u64| {
+        if len > 0 {
+            load_immediate(body, "off", offset);
+            body.push(op("add", &["at", "value", "off"], None));
+            load_immediate(body, "len", len);
+            body.extend([
+                op("mcp", &["dst", "at", "len"], None),
+                op("add", &["dst", "dst", "len"], None),
+            ]);
+        }
+    };
+    match ty.get_content(context) {
+        TypeContent::Unit | TypeContent::Slice => (),
+        TypeContent::Bool => copy(body, offset + 7, 1),
+        TypeContent::Uint(nbits) => {
+            let len = *nbits as u64 / 8;
+            copy(body, offset + 8 - len, len)
+        }
+        TypeContent::B256 | TypeContent::Union(_) => {
+            copy(body, offset, word_layout_size(context, &ty))
+        }
+        TypeContent::String(n) => copy(body, offset, *n),
+        TypeContent::Array(elem_ty, n) => {
+            let elem_size = word_layout_size(context, elem_ty);
+            for idx in 0..*n {
+                encode_value(context, *elem_ty, offset + idx * elem_size, body);
+            }
+        }
+        TypeContent::Struct(field_tys) => {
+            let mut field_offset = offset;
+            for field_ty in field_tys {
+                encode_value(context, *field_ty, field_offset, body);
+                field_offset += word_layout_size(context, field_ty);
+            }
+        }
+    }
+}
+
+/// The largest value which fits in the 18 bit immediate of `movi`.
+const MAX_MOVI_IMMEDIATE: u64 = (1 << 18) - 1;
+
+/// Set `reg` to `value`.  Values too large for a single `movi` are built from its top bits,
+/// followed by the rest 12 bits at a time with `slli` and `ori`.
+fn load_immediate(body: &mut Vec<AsmInstruction>, reg: &str, value: u64) {
+    let mut chunks = Vec::new();
+    let mut top = value;
+    while top > MAX_MOVI_IMMEDIATE {
+        chunks.push(top & 0xfff);
+        top >>= 12;
+    }
+    body.push(op("movi", &[reg], Some(top)));
+    for chunk in chunks.into_iter().rev() {
+        body.push(op("slli", &[reg, reg], Some(12)));
+        if chunk != 0 {
+            body.push(op("ori", &[reg, reg], Some(chunk)));
+        }
+    }
+}
+
+/// The size of a value of type `ty` as it's laid out in memory, where scalars take a whole word.
+fn word_layout_size(context: &Context, ty: &Type) -> u64 {
+    match ty.get_content(context) {
+        TypeContent::Unit | TypeContent::Bool | TypeContent::Uint(_) => 8,
+        TypeContent::Slice => 16,
+        TypeContent::B256 => 32,
+        TypeContent::String(n) => (n + 7) / 8 * 8,
+        TypeContent::Array(elem_ty, n) => n * word_layout_size(context, elem_ty),
+        TypeContent::Struct(field_tys) => field_tys
+            .iter()
+            .map(|field_ty| word_layout_size(context, field_ty))
+            .sum(),
+        TypeContent::Union(field_tys) => field_tys
+            .iter()
+            .map(|field_ty| word_layout_size(context, field_ty))
+            .max()
+            .unwrap_or(0),
+    }
+}
+
+fn ident(name: &str) -> Ident {
+    Ident::new_no_span(name.to_owned())
+}
+
+fn op(name: &str, args: &[&str], immediate: Option<u64>) -> AsmInstruction {
+    AsmInstruction {
+        name: ident(name),
+        args: args.iter().map(|arg| ident(arg)).collect(),
+        immediate: immediate.map(|imm| ident(&format!("i{imm}"))),
+        metadata: None,
+    }
+}
@@ -0,0 +1,36 @@
+//! Rewriting with externally supplied rules.
+//!
+//! Applies the [`Rule`]s set with [`Context::set_rewrite_rules`], which are typically loaded from
+//! a file with [`parse_rules`](crate::parse_rules).  Unlike `instcombine`, nothing is known about
+//! these rules beyond their being well typed, so it's up to their author to make sure they
+//! preserve the behaviour of the program.
+
+use crate::{
+    apply_rules, AnalysisResults, Context, Function, IrError, Pass, PassMutability, Rule,
+    ScopedPass,
+};
+
+pub const REWRITE_NAME: &str = "rewrite";
+
+pub fn create_rewrite_pass() -> Pass {
+    Pass {
+        name: REWRITE_NAME,
+        descr: "Rewriting of instructions with the rules set in the context.",
+        deps: vec![],
+        preserves: vec![],
+        prune_unreachable: false,
+        runner: ScopedPass::FunctionPass(PassMutability::Transform(rewrite)),
+    }
+}
+
+pub fn rewrite(
+    context: &mut Context,
+    _: &AnalysisResults,
+    function: Function,
+) -> Result<bool, IrError> {
+    // The rules are taken out of the context while it's being modified.
+    let rules: Vec<Rule> = std::mem::take(&mut context.rewrite_rules);
+    let modified = apply_rules(context, function, &rules);
+    context.rewrite_rules = rules;
+    Ok(modified)
+}
@@ -0,0 +1,233 @@
+//! Redundant load elimination.
+//!
+//! A load is redundant if the value it reads is already known: either it was just stored to the
+//! same place, or it was loaded from there already and nothing since may have changed it.  Both
+//! are found with the memory SSA form, by walking back from each load to the nearest access which
+//! may clobber what it reads.  A store there to exactly the same place gives the value directly,
+//! and a load with the same clobber from exactly the same place which dominates this one has
+//! already read it.
+//!
+//! Struct fields are read and written with `extract_value` and `insert_value`, which are treated
+//! as loads and stores of the field.  An insert into a different field of the same aggregate
+//! doesn't change the field being read, so the walk continues past it.
+//!
+//! Only values of copy types are replaced.  A load of an aggregate is a pointer to it.
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    AliasAnalysis, AliasResult, AnalysisResults, BasicAliasAnalysis, Block, Context, DomTree,
+    Function, Instruction, IrError, MemoryAccess, MemoryEffects, MemorySsa, Pass, PassMutability,
+    ScopedPass, Type, Value, ALIAS_NAME, CALL_GRAPH_NAME, CFG_ANALYSES, DOMINATORS_NAME,
+    MEMORY_SSA_NAME,
+};
+
+pub const RLE_NAME: &str = "rle";
+
+pub fn create_rle_pass() -> Pass {
+    Pass {
+        name: RLE_NAME,
+        descr: "Redundant load elimination.",
+        deps: vec![DOMINATORS_NAME, MEMORY_SSA_NAME, ALIAS_NAME],
+        preserves: [CFG_ANALYSES.as_slice(), &[CALL_GRAPH_NAME]].concat(),
+        prune_unreachable: false,
+        runner: ScopedPass::FunctionPass(PassMutability::Transform(rle)),
+    }
+}
+
+/// A place in memory: a field of the value of type `ty` pointed to by `ptr`.  A load or store
+/// accesses the whole value, with no field indices.
+struct Place<'a> {
+    ptr: Value,
+    ty: Type,
+    indices: &'a [u64],
+}
+
+impl Place<'_> {
+    fn is_same(&self, context: &Context, aa: &dyn AliasAnalysis, other: &Place) -> bool {
+        aa.may_alias(context, self.ptr, other.ptr) == AliasResult::MustAlias
+            && self.ty.eq(context, &other.ty)
+            && self.indices == other.indices
+    }
+
+    // Whether the fields are of the same aggregate but never overlap.
+    fn is_disjoint(&self, context: &Context, aa: &dyn AliasAnalysis, other: &Place) -> bool {
+        if aa.may_alias(context, self.ptr, other.ptr) != AliasResult::MustAlias
+            || !self.ty.eq(context, &other.ty)
+        {
+            return false;
+        }
+        let mut ty = self.ty;
+        for (idx, other_idx) in self.indices.iter().zip(other.indices) {
+            // The fields of a union all overlap.
+            if ty.is_union(context) {
+                return false;
+            }
+            if idx != other_idx {
+                return true;
+            }
+            match ty.get_indexed_type(context, &[*idx]) {
+                Some(field_ty) => ty = field_ty,
+                None => return false,
+            }
+        }
+        false
+    }
+}
+
+// The place read by `ins`, if it's a load or an extract.
+fn read_place(context: &Context, ins: Value) -> Option<Place<'_>> {
+    match ins.get_instruction(context)? {
+        Instruction::Load(ptr) => Some(Place {
+            ptr: *ptr,
+            ty: ptr.get_type(context)?,
+            indices: &[],
+        }),
+        Instruction::ExtractValue {
+            aggregate,
+            ty,
+            indices,
+        } => Some(Place {
+            ptr: *aggregate,
+            ty: *ty,
+            indices,
+        }),
+        _ => None,
+    }
+}
+
+// The place written by `ins` and the value written, if it's a store or an insert.
+fn written_place(context: &Context, ins: Value) -> Option<(Place<'_>, Value)> {
+    match ins.get_instruction(context)? {
+        Instruction::Store {
+            dst_val,
+            stored_val,
+        } => Some((
+            Place {
+                ptr: *dst_val,
+                ty: dst_val.get_type(context)?,
+                indices: &[],
+            },
+            *stored_val,
+        )),
+        Instruction::InsertValue {
+            aggregate,
+            ty,
+            value,
+            indices,
+        } => Some((
+            Place {
+                ptr: *aggregate,
+                ty: *ty,
+                indices,
+            },
+            *value,
+        )),
+        _ => None,
+    }
+}
+
+pub fn rle(
+    context: &mut Context,
+    analyses: &AnalysisResults,
+    function: Function,
+) -> Result<bool, IrError> {
+    let dom_tree: &DomTree = analyses.get_analysis_result(function);
+    let memory_ssa: &MemorySsa = analyses.get_analysis_result(function);
+    let aa: &BasicAliasAnalysis = analyses.get_analysis_result(function);
+
+    // The loads available in the current block by their clobbering access, along with the
+    // accesses made available by each block being visited so they can be dropped once it's left.
+    let mut available = FxHashMap::<MemoryAccess, Vec<Value>>::default();
+    let mut scopes = Vec::<Vec<MemoryAccess>>::new();
+    let mut replacements = FxHashMap::<Value, Value>::default();
+    let mut redundant = Vec::<(Block, Value)>::new();
+
+    // `None` marks leaving the scope of the block visited before it, as in CSE.
+    let mut stack = vec![Some(function.get_entry_block(context))];
+    while let Some(next) = stack.pop() {
+        let Some(block) = next else {
+            for clobber in scopes.pop().unwrap() {
+                available.get_mut(&clobber).unwrap().pop();
+            }
+            continue;
+        };
+
+        let mut scope = Vec::new();
+        for ins in block.instruction_iter(context) {
+            let Some(place) = read_place(context, ins) else {
+                continue;
+            };
+            if !ins.get_type(context).map_or(false, |ty| {
+                ty.is_unit(context) || ty.is_bool(context) || ty.is_uint(context)
+            }) {
+                continue;
+            }
+            let (Some(mut clobber), Some(reads)) = (
+                memory_ssa.clobbering_access(context, aa, ins),
+                MemoryEffects::of(context, ins).reads,
+            ) else {
+                continue;
+            };
+
+            // Walk back past writes to other fields, stopping at one to the same place.
+            let mut stored = None;
+            let mut visited = FxHashSet::default();
+            while let MemoryAccess::Def(def) = clobber {
+                let Some((written, value)) = written_place(context, def) else {
+                    break;
+                };
+                if written.is_same(context, aa, &place) {
+                    stored = Some(value);
+                    break;
+                }
+                if !written.is_disjoint(context, aa, &place) || !visited.insert(def) {
+                    break;
+                }
+                let Some(access) = memory_ssa.defining_access(def) else {
+                    break;
+                };
+                clobber = memory_ssa.clobbering_access_from(context, aa, &reads, access);
+            }
+
+            let known = stored.or_else(|| {
+                available.get(&clobber).and_then(|loads| {
+                    loads.iter().rev().copied().find(|load| {
+                        read_place(context, *load)
+                            .map_or(false, |load_place| load_place.is_same(context, aa, &place))
+                    })
+                })
+            });
+            match known {
+                Some(val) => {
+                    let val = replacements.get(&val).copied().unwrap_or(val);
+                    replacements.insert(ins, val);
+                    redundant.push((block, ins));
+                }
+                None => {
+                    available.entry(clobber).or_default().push(ins);
+                    scope.push(clobber);
+                }
+            }
+        }
+        scopes.push(scope);
+        stack.push(None);
+        stack.extend(
+            dom_tree[&block]
+                .children
+                .iter()
+                .rev()
+                .map(|child| Some(*child)),
+        );
+    }
+
+    if redundant.is_empty() {
+        return Ok(false);
+    }
+    function.replace_values(context, &replacements, None);
+    context.add_pass_statistic("loads removed", redundant.len() as u64);
+    for (block, ins) in redundant {
+        block.remove_instruction(context, ins);
+    }
+    Ok(true)
+}
@@ -4,6 +4,11 @@
 //! This may be done by removing 'dead' blocks which are no longer called (or in other words, have
 //! no predecessors) or by merging blocks which are linked by a single unconditional branch.
 //!
+//! Conditional branches are simplified too: those on a constant condition or with both arms the
+//! same become unconditional, and a jump to a block which does nothing but branch again on one of
+//! its arguments is threaded straight through to the final destination when the argument passed
+//! is a constant.
+//!
 //! Removing blocks will make the IR neater and more efficient but will also remove indirection of
 //! data flow via PHI instructions which in turn can make analyses for passes like constant folding
 //! much simpler.
@@ -12,7 +17,8 @@ use rustc_hash::{FxHashMap, FxHashSet};
 
 use crate::{
     block::Block, context::Context, error::IrError, function::Function, instruction::Instruction,
-    value::ValueDatum, AnalysisResults, BranchToWithArgs, Pass, PassMutability, ScopedPass, Value,
+    prune_unreachable_blocks, value::ValueDatum, AnalysisResults, BranchToWithArgs, ConstantValue,
+    Pass, PassMutability, ScopedPass, Value,
 };
 
 pub const SIMPLIFYCFG_NAME: &str = "simplifycfg";
@@ -22,6 +28,8 @@ pub fn create_simplify_cfg_pass() -> Pass {
         name: SIMPLIFYCFG_NAME,
         descr: "merge or remove redundant blocks.",
         deps: vec![],
+        preserves: vec![],
+        prune_unreachable: false,
         runner: ScopedPass::FunctionPass(PassMutability::Transform(simplify_cfg)),
     }
 }
@@ -32,12 +40,111 @@ pub fn simplify_cfg(
     function: Function,
 ) -> Result<bool, IrError> {
     let mut modified = false;
-    modified |= remove_dead_blocks(context, &function)?;
+    modified |= fold_branches(context, &function)?;
+    modified |= thread_jumps(context, &function)?;
+    modified |= prune_unreachable_blocks(context, function)?;
     modified |= merge_blocks(context, &function)?;
     modified |= unlink_empty_blocks(context, &function)?;
     Ok(modified)
 }
 
+/// Return the constant boolean value of `cond`, if it has one.
+fn get_bool_constant(context: &Context, cond: &Value) -> Option<bool> {
+    match cond.get_constant(context).map(|cn| &cn.value) {
+        Some(ConstantValue::Bool(b)) => Some(*b),
+        _ => None,
+    }
+}
+
+fn fold_branches(context: &mut Context, function: &Function) -> Result<bool, IrError> {
+    let mut modified = false;
+    for block in function.block_iter(context) {
+        let Some(Instruction::ConditionalBranch {
+            cond_value,
+            true_block,
+            false_block,
+        }) = block.get_terminator(context)
+        else {
+            continue;
+        };
+        let (dest, other) = match get_bool_constant(context, cond_value) {
+            Some(true) => (true_block.clone(), false_block.block),
+            Some(false) => (false_block.clone(), true_block.block),
+            None if true_block.block == false_block.block
+                && true_block.args == false_block.args =>
+            {
+                (true_block.clone(), true_block.block)
+            }
+            None => continue,
+        };
+        if !context.consume_fuel() {
+            break;
+        }
+
+        // Both arms may be to the same block, which is still a successor.
+        if other != dest.block {
+            other.remove_pred(context, &block);
+        }
+        let cbr = block.instruction_iter(context).next_back().unwrap();
+        cbr.replace(context, ValueDatum::Instruction(Instruction::Branch(dest)));
+        modified = true;
+    }
+    Ok(modified)
+}
+
+fn thread_jumps(context: &mut Context, function: &Function) -> Result<bool, IrError> {
+    let mut modified = false;
+    for block in function.block_iter(context) {
+        // Look for a block which only branches on one of its own arguments.
+        if block.num_instructions(context) != 1 {
+            continue;
+        }
+        let Some(Instruction::ConditionalBranch {
+            cond_value,
+            true_block,
+            false_block,
+        }) = block.get_terminator(context).cloned()
+        else {
+            continue;
+        };
+        let cond_idx = match &context.values[cond_value.0].value {
+            ValueDatum::Argument(arg) if arg.block == block => arg.idx,
+            _ => continue,
+        };
+
+        let preds: Vec<_> = block.pred_iter(context).copied().collect();
+        for pred in preds {
+            // Only an unconditional branch can be threaded, as it's the only edge from `pred`.
+            let params = match pred.get_terminator(context) {
+                Some(Instruction::Branch(BranchToWithArgs { args, .. })) => args.clone(),
+                _ => continue,
+            };
+            let dest = match get_bool_constant(context, &params[cond_idx]) {
+                Some(true) => &true_block,
+                Some(false) => &false_block,
+                None => continue,
+            };
+            if !context.consume_fuel() {
+                return Ok(modified);
+            }
+
+            // The arguments of `block` passed on to `dest` are now passed from `pred` directly.
+            // Anything else passed dominates `block` and so `pred` too.
+            let new_params = dest
+                .args
+                .iter()
+                .map(|param| match &context.values[param.0].value {
+                    ValueDatum::Argument(arg) if arg.block == block => params[arg.idx],
+                    _ => *param,
+                })
+                .collect();
+            pred.replace_successor(context, block, dest.block, new_params);
+            modified = true;
+        }
+    }
+    Ok(modified)
+}
+
 fn unlink_empty_blocks(context: &mut Context, function: &Function) -> Result<bool, IrError> {
     let mut modified = false;
     let candidates: Vec<_> = function
@@ -76,6 +183,9 @@ fn unlink_empty_blocks(context: &mut Context, function: &Function) -> Result<boo
             // may get updated *during* this optimization (i.e., inside this loop).
             continue;
         }
+        if !context.consume_fuel() {
+            break;
+        }
         let preds: Vec<_> = block.pred_iter(context).copied().collect();
         for pred in preds {
             // Whatever parameters "block" passed to "to_block", that
@@ -99,45 +209,6 @@ fn unlink_empty_blocks(context: &mut Context, function: &Function) -> Result<boo
     Ok(modified)
 }
 
-fn remove_dead_blocks(context: &mut Context, function: &Function) -> Result<bool, IrError> {
-    let mut worklist = Vec::<Block>::new();
-    let mut reachable = std::collections::HashSet::<Block>::new();
-
-    // The entry is always reachable. Let's begin with that.
-    let entry_block = function.get_entry_block(context);
-    reachable.insert(entry_block);
-    worklist.push(entry_block);
-
-    // Mark reachable nodes.
-    while !worklist.is_empty() {
-        let block = worklist.pop().unwrap();
-        let succs = block.successors(context);
-        for BranchToWithArgs { block: succ, .. } in succs {
-            // If this isn't already marked reachable, we mark it and add to the worklist.
-            if !reachable.contains(&succ) {
-                reachable.insert(succ);
-                worklist.push(succ);
-            }
-        }
-    }
-
-    // Delete all unreachable nodes.
-    let mut modified = false;
-    for block in function.block_iter(context) {
-        if !reachable.contains(&block) {
-            modified = true;
-
-            for BranchToWithArgs { block: succ, .. } in block.successors(context) {
-                succ.remove_pred(context, &block);
-            }
-
-            function.remove_block(context, &block)?;
-        }
-    }
-
-    Ok(modified)
-}
-
 fn merge_blocks(context: &mut Context, function: &Function) -> Result<bool, IrError> {
     // Check if block branches soley to another block B, and that B has exactly one predecessor.
     fn check_candidate(context: &Context, from_block: Block) -> Option<(Block, Block)> {
@@ -170,6 +241,9 @@ fn merge_blocks(context: &mut Context, function: &Function) -> Result<bool, IrEr
             Some((from_block, to_block)) => vec![from_block, to_block],
             None => continue,
         };
+        if !context.consume_fuel() {
+            break;
+        }
 
         // There may be more blocks which are also singly paired with these twins, so iteratively
         // search for more blocks in a chain which can be all merged into one.
@@ -204,18 +278,15 @@ fn merge_blocks(context: &mut Context, function: &Function) -> Result<bool, IrEr
                 replace_map.insert(to_block_arg, from_params[arg_idx]);
             }
 
-            // Re-get the block contents mutably.
-            let (from_contents, to_contents) = context.blocks.get2_mut(from_block.0, to_block.0);
-            let from_contents = from_contents.unwrap();
-            let to_contents = to_contents.unwrap();
-
             // Drop the terminator from `from_block`.
-            from_contents.instructions.pop();
+            let terminator = context.blocks[from_block.0].instructions.pop().unwrap();
+            terminator.remove_uses(context);
+            terminator.set_block(context, None);
 
             // Move instructions from `to_block` to `from_block`.
-            from_contents
-                .instructions
-                .append(&mut to_contents.instructions);
+            let to_instructions = to_block.take_instructions(context, 0);
+            let idx = from_block.num_instructions(context);
+            from_block.insert_instructions(context, idx, to_instructions);
 
             // Remove `to_block`.
             function.remove_block(context, &to_block)?;
@@ -0,0 +1,131 @@
+//! Tail call optimization.
+//!
+//! A tail call is a call whose value is immediately returned.  Each recursive call otherwise
+//! pushes a new frame, so a function recursing on itself uses stack in proportion to its depth.
+//! This pass turns a function's tail calls to itself into branches back to its start, making the
+//! recursion a loop which runs in a single frame.
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    AnalysisResults, Block, Context, Function, Instruction, IrError, Pass, PassMutability,
+    ScopedPass, Value,
+};
+
+pub const TCO_NAME: &str = "tco";
+
+pub fn create_tco_pass() -> Pass {
+    Pass {
+        name: TCO_NAME,
+        descr: "Tail call optimization: self-recursive tail calls become loops.",
+        deps: vec![],
+        preserves: vec![],
+        prune_unreachable: false,
+        runner: ScopedPass::FunctionPass(PassMutability::Transform(tco)),
+    }
+}
+
+/// Return each call in `function` which is immediately followed by a return of its value, along
+/// with its block.
+pub fn find_tail_calls(context: &Context, function: &Function) -> Vec<(Block, Value)> {
+    function
+        .block_iter(context)
+        .filter_map(|block| {
+            let mut insts = block.instruction_iter(context).rev();
+            let ret = insts.next()?;
+            let call = insts.next()?;
+            match (
+                ret.get_instruction(context)?,
+                call.get_instruction(context)?,
+            ) {
+                (Instruction::Ret(ret_val, _), Instruction::Call(..)) if *ret_val == call => {
+                    Some((block, call))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Turn the self-recursive tail calls in `function` into a loop, returning whether the function
+/// was modified.
+///
+/// The body of the entry block is moved to a new loop header, which takes the function arguments
+/// as block arguments, and each tail call becomes a branch to it passing the call's arguments.
+///
+/// A new frame would have its locals freshly initialised, and a pointer argument could refer to a
+/// local of the calling frame which the loop then reuses.  So functions with initialised locals
+/// or arguments which aren't passed by value in a register are left alone.
+pub fn tco(
+    context: &mut Context,
+    _: &AnalysisResults,
+    function: Function,
+) -> Result<bool, IrError> {
+    let tail_calls = find_tail_calls(context, &function)
+        .into_iter()
+        .filter(|(_block, call)| {
+            matches!(call.get_instruction(context), Some(Instruction::Call(callee, _))
+                if *callee == function)
+        })
+        .collect::<Vec<_>>();
+    if tail_calls.is_empty() {
+        return Ok(false);
+    }
+
+    if function
+        .locals_iter(context)
+        .any(|(_name, local)| local.get_initializer(context).is_some())
+    {
+        return Ok(false);
+    }
+    let arg_types = function
+        .args_iter(context)
+        .map(|(_name, arg)| arg.get_argument_type_and_byref(context).unwrap())
+        .collect::<Vec<_>>();
+    if arg_types.iter().any(|(ty, by_ref)| {
+        *by_ref || !(ty.is_unit(context) | ty.is_bool(context) | ty.is_uint(context))
+    }) {
+        return Ok(false);
+    }
+
+    // Move the entry block's instructions to the loop header, updating the preds of their
+    // successors.
+    let entry_block = function.get_entry_block(context);
+    let header = function
+        .create_block_after(context, &entry_block, Some("tail_loop".to_owned()))
+        .unwrap();
+    let insts = entry_block.take_instructions(context, 0);
+    header.insert_instructions(context, 0, insts);
+    for succ in header.successors(context) {
+        succ.block.replace_pred(context, &entry_block, &header);
+    }
+
+    // The header's arguments take the place of the function's.
+    let fn_args = function
+        .args_iter(context)
+        .map(|(_name, arg)| *arg)
+        .collect::<Vec<_>>();
+    let mut replace_map = FxHashMap::default();
+    for (fn_arg, (ty, _by_ref)) in fn_args.iter().zip(arg_types) {
+        let idx = header.new_arg(context, ty, false);
+        replace_map.insert(*fn_arg, header.get_arg(context, idx).unwrap());
+    }
+    function.replace_values(context, &replace_map, Some(header));
+    entry_block.ins(context).branch(header, fn_args);
+
+    for (block, call) in tail_calls {
+        let Some(Instruction::Call(_, args)) = call.get_instruction(context) else {
+            unreachable!("Tail call must be a call.");
+        };
+        let args = args.clone();
+        let md_idx = call.get_metadata(context);
+        // A tail call in the entry block has moved to the header.
+        let block = if block == entry_block { header } else { block };
+        let ret = block.instruction_iter(context).next_back().unwrap();
+        block.remove_instruction(context, ret);
+        block.remove_instruction(context, call);
+        block.ins(context).metadata(md_idx).branch(header, args);
+    }
+
+    Ok(true)
+}
@@ -0,0 +1,269 @@
+//! Loop unrolling.
+//!
+//! A counted loop, one whose trip count can be found by [`Loop::trip_count`], is unrolled by
+//! copying its blocks so that each pass around the loop runs several iterations.  If the whole
+//! loop fits within a size budget it is unrolled fully, leaving straight-line code which later
+//! passes can fold with the constant value of the counter in each copy.  Otherwise it is unrolled
+//! by the largest factor of its trip count which fits, so that only the original header needs to
+//! test whether to leave the loop.  Loops without a known trip count are left alone.
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    AnalysisResults, Block, BranchToWithArgs, Context, Function, Instruction, IrError, Loop,
+    LoopInfo, Pass, PassMutability, ScopedPass, Value, ValueDatum, LOOPS_NAME,
+};
+
+pub const UNROLL_NAME: &str = "unroll";
+
+/// The most instructions the copies of an unrolled loop may add up to.
+const UNROLL_BUDGET: u64 = 64;
+/// The most copies of the body a partially unrolled loop may have.
+const MAX_UNROLL_FACTOR: u64 = 4;
+
+pub fn create_unroll_pass() -> Pass {
+    Pass {
+        name: UNROLL_NAME,
+        descr: "Unroll counted loops.",
+        deps: vec![LOOPS_NAME],
+        preserves: vec![],
+        prune_unreachable: false,
+        runner: ScopedPass::FunctionPass(PassMutability::Transform(unroll)),
+    }
+}
+
+/// Unroll the innermost counted loops of `function`, returning whether it was modified.
+///
+/// Only innermost loops are unrolled, so that unrolling one doesn't change the blocks of another.
+pub fn unroll(
+    context: &mut Context,
+    analyses: &AnalysisResults,
+    function: Function,
+) -> Result<bool, IrError> {
+    let loop_info: &LoopInfo = analyses.get_analysis_result(function);
+
+    let mut modified = false;
+    for (idx, lp) in loop_info.loops.iter().enumerate() {
+        if loop_info
+            .loops
+            .iter()
+            .any(|inner| inner.parent == Some(idx))
+        {
+            continue;
+        }
+        let Some(trip_count) = lp.trip_count(context) else {
+            continue;
+        };
+        let size = lp
+            .blocks
+            .iter()
+            .map(|block| block.num_instructions(context) as u64)
+            .sum::<u64>();
+        let full = trip_count * size <= UNROLL_BUDGET;
+        let factor = if full {
+            trip_count
+        } else {
+            match (2..=MAX_UNROLL_FACTOR)
+                .rev()
+                .find(|factor| trip_count % factor == 0 && factor * size <= UNROLL_BUDGET)
+            {
+                Some(factor) => factor,
+                None => continue,
+            }
+        };
+        if !context.consume_fuel() {
+            break;
+        }
+        unroll_loop(context, function, lp, factor, full);
+        modified = true;
+    }
+    Ok(modified)
+}
+
+/// Unroll `lp` by `factor`, which must divide its trip count.  If `full` then the factor is the
+/// trip count and the loop is removed.
+fn unroll_loop(context: &mut Context, function: Function, lp: &Loop, factor: u64, full: bool) {
+    let header = lp.header;
+    let Some(Instruction::ConditionalBranch {
+        true_block,
+        false_block,
+        ..
+    }) = header.get_terminator(context).cloned()
+    else {
+        unreachable!("The header of a counted loop ends with a conditional branch.");
+    };
+    let (continue_branch, exit_branch) = if lp.blocks.contains(&true_block.block) {
+        (true_block, false_block)
+    } else {
+        (false_block, true_block)
+    };
+    if full && factor == 0 {
+        replace_terminator(context, header, exit_branch);
+        return;
+    }
+
+    let blocks = lp.blocks_iter(context).collect::<Vec<_>>();
+    let mut insert_after = *blocks.last().unwrap();
+
+    // Chain copies of the whole loop after it.  The header of each copy never needs to test
+    // whether to leave the loop, as the trip count is a multiple of the factor.  All the copies
+    // are made before any latch is retargeted, so that each starts out branching to its own
+    // header.
+    let copies = (1..factor)
+        .map(|_| {
+            let maps = copy_blocks(context, &blocks, insert_after);
+            insert_after = maps.0[blocks.last().unwrap()];
+            maps
+        })
+        .collect::<Vec<_>>();
+    let mut latch = lp.latch().unwrap();
+    let mut latch_target = header;
+    for (block_map, value_map) in copies {
+        let copy_header = block_map[&header];
+        replace_terminator(
+            context,
+            copy_header,
+            map_branch(&continue_branch, &block_map, &value_map),
+        );
+        retarget(context, latch, latch_target, copy_header);
+        latch = block_map[&lp.latch().unwrap()];
+        latch_target = copy_header;
+    }
+
+    if !full {
+        retarget(context, latch, latch_target, header);
+        return;
+    }
+
+    // A final copy of the header is reached once the count is up, and leaves the loop.  The
+    // original header is then run only once, so it needn't test anything either.
+    let (block_map, value_map) = copy_blocks(context, &[header], insert_after);
+    let final_header = block_map[&header];
+    replace_terminator(
+        context,
+        final_header,
+        map_branch(&exit_branch, &block_map, &value_map),
+    );
+    retarget(context, latch, latch_target, final_header);
+    replace_terminator(context, header, continue_branch);
+
+    // Only the header's values can be used after the loop, as it's the only block left from, and
+    // they now come from the final copy.
+    let header_values = header
+        .arg_iter(context)
+        .copied()
+        .chain(header.instruction_iter(context))
+        .map(|value| (value, value_map[&value]))
+        .collect::<FxHashMap<_, _>>();
+    for block in function.block_iter(context).collect::<Vec<_>>() {
+        if !lp.blocks.contains(&block) {
+            block.replace_values(context, &header_values);
+        }
+    }
+}
+
+/// Copy `blocks` to new blocks after `insert_after`, in the same order.  Branches between them
+/// and values defined in them are mapped to the copies, and the maps are returned.
+fn copy_blocks(
+    context: &mut Context,
+    blocks: &[Block],
+    mut insert_after: Block,
+) -> (FxHashMap<Block, Block>, FxHashMap<Value, Value>) {
+    let function = insert_after.get_function(context);
+    let mut block_map = FxHashMap::default();
+    let mut value_map = FxHashMap::default();
+    for block in blocks {
+        let label = block.get_label(context).to_owned();
+        let new_block = function
+            .create_block_after(context, &insert_after, Some(label))
+            .unwrap();
+        for arg in block.arg_iter(context).copied().collect::<Vec<_>>() {
+            let (ty, by_ref) = arg.get_argument_type_and_byref(context).unwrap();
+            let idx = new_block.new_arg(context, ty, by_ref);
+            value_map.insert(arg, new_block.get_arg(context, idx).unwrap());
+        }
+        block_map.insert(*block, new_block);
+        insert_after = new_block;
+    }
+
+    // Copy the instructions before mapping their operands, as a use may come before its
+    // definition in block order.
+    let mut new_insts = Vec::new();
+    for block in blocks {
+        for inst in block.instruction_iter(context).collect::<Vec<_>>() {
+            let new_inst =
+                Value::new_instruction(context, inst.get_instruction(context).unwrap().clone())
+                    .add_metadatum(context, inst.get_metadata(context));
+            context.blocks[block_map[block].0]
+                .instructions
+                .push(new_inst);
+            new_inst.set_block(context, Some(block_map[block]));
+            value_map.insert(inst, new_inst);
+            new_insts.push(new_inst);
+        }
+    }
+    for new_inst in new_insts {
+        new_inst.remove_uses(context);
+        let instruction = new_inst.get_instruction_mut(context).unwrap();
+        instruction.replace_values(&value_map);
+        match instruction {
+            Instruction::Branch(branch) => {
+                branch.block = *block_map.get(&branch.block).unwrap_or(&branch.block);
+            }
+            Instruction::ConditionalBranch {
+                true_block,
+                false_block,
+                ..
+            } => {
+                for branch in [true_block, false_block] {
+                    branch.block = *block_map.get(&branch.block).unwrap_or(&branch.block);
+                }
+            }
+            _ => (),
+        }
+        new_inst.update_uses(context);
+    }
+    for block in blocks {
+        let new_block = block_map[block];
+        for BranchToWithArgs { block: succ, .. } in new_block.successors(context) {
+            succ.add_pred(context, &new_block);
+        }
+    }
+
+    (block_map, value_map)
+}
+
+fn map_branch(
+    branch: &BranchToWithArgs,
+    block_map: &FxHashMap<Block, Block>,
+    value_map: &FxHashMap<Value, Value>,
+) -> BranchToWithArgs {
+    BranchToWithArgs {
+        block: *block_map.get(&branch.block).unwrap_or(&branch.block),
+        args: branch
+            .args
+            .iter()
+            .map(|arg| *value_map.get(arg).unwrap_or(arg))
+            .collect(),
+    }
+}
+
+/// Replace the terminator of `block` with an unconditional branch.
+fn replace_terminator(context: &mut Context, block: Block, branch: BranchToWithArgs) {
+    for BranchToWithArgs { block: succ, .. } in block.successors(context) {
+        succ.remove_pred(context, &block);
+    }
+    let to_block = branch.block;
+    let terminator = block.instruction_iter(context).next_back().unwrap();
+    terminator.replace(
+        context,
+        ValueDatum::Instruction(Instruction::Branch(branch)),
+    );
+    to_block.add_pred(context, &block);
+}
+
+/// Make `block` branch to `new_succ` instead of `old_succ`, passing the same parameters.
+fn retarget(context: &mut Context, block: Block, old_succ: Block, new_succ: Block) {
+    let params = block.get_succ_params(context, &old_succ);
+    block.replace_successor(context, old_succ, new_succ, params);
+}
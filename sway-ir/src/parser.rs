@@ -1,21 +1,51 @@
 //! A parser for the printed IR, useful mostly for testing.
 
-use crate::{context::Context, error::IrError};
+use crate::{context::Context, error::IrError, function::Function, module::Module};
 
 // -------------------------------------------------------------------------------------------------
 /// Parse a string produced by [`crate::printer::to_string`] into a new [`Context`].
 pub fn parse(input: &str) -> Result<Context, IrError> {
-    let irmod = ir_builder::parser::ir_descrs(input).map_err(|err| {
-        let found = if input.len() - err.location.offset <= 20 {
-            &input[err.location.offset..]
-        } else {
-            &input[err.location.offset..][..20]
-        };
-        IrError::ParseFailure(err.to_string(), found.into())
-    })?;
+    let irmod = ir_builder::parser::ir_descrs(input).map_err(|err| parse_failure(input, err))?;
     ir_builder::build_context(irmod)?.verify()
 }
 
+/// Parse a string produced by [`crate::printer::function_to_string`] and make it the body of
+/// `function`, which keeps its handle so that calls to it are unaffected.
+///
+/// Calls in the parsed body are resolved by name to the other functions in the module, and uses of
+/// configurables to those of the module.
+pub fn parse_function_into(
+    context: &mut Context,
+    function: Function,
+    input: &str,
+) -> Result<(), IrError> {
+    let irfn = ir_builder::parser::fn_descr(input).map_err(|err| parse_failure(input, err))?;
+    ir_builder::build_function_into(context, function, irfn)
+}
+
+/// Parse a string produced by [`crate::printer::function_to_string`] into a new function at the end
+/// of `module`.
+///
+/// As for [`parse_function_into`], calls are resolved by name to the functions in the module,
+/// which include the new function itself, and uses of configurables to those of the module.
+pub fn parse_function_into_module(
+    context: &mut Context,
+    module: Module,
+    input: &str,
+) -> Result<Function, IrError> {
+    let irfn = ir_builder::parser::fn_descr(input).map_err(|err| parse_failure(input, err))?;
+    ir_builder::build_function_into_module(context, module, irfn)
+}
+
+fn parse_failure(input: &str, err: peg::error::ParseError<peg::str::LineCol>) -> IrError {
+    let found = if input.len() - err.location.offset <= 20 {
+        &input[err.location.offset..]
+    } else {
+        &input[err.location.offset..][..20]
+    };
+    IrError::ParseFailure(err.to_string(), found.into())
+}
+
 // -------------------------------------------------------------------------------------------------
 
 mod ir_builder {
@@ -43,6 +73,14 @@ mod ir_builder {
                     }
                 }
 
+            pub(in crate::parser) rule fn_descr() -> IrAstFunction
+                = _ fn_decl:fn_decl() metadata:metadata_decls() eoi() {
+                    IrAstFunction {
+                        fn_decl,
+                        metadata
+                    }
+                }
+
             rule module_kind() -> Kind
                 = "script" _ { Kind::Script }
                 / "predicate" _ { Kind::Predicate }
@@ -72,12 +110,13 @@ mod ir_builder {
                 = is_public:is_public() _ is_entry:is_entry() _ "fn" _
                         name:id() _ selector:selector_id()? _ "(" _
                         args:(block_arg() ** comma()) ")" _ "->" _ ret_type:ast_ty()
-                            metadata:comma_metadata_idx()? "{" _
+                            attributes:fn_attributes()? metadata:comma_metadata_idx()? "{" _
                         locals:fn_local()*
                         blocks:block_decl()*
                     "}" _ {
                     IrAstFnDecl {
                         name,
+                        attributes: attributes.unwrap_or_default(),
                         args,
                         ret_type,
                         is_public,
@@ -89,6 +128,17 @@ mod ir_builder {
                     }
                 }
 
+            rule fn_attributes() -> Vec<FunctionAttribute>
+                = "[" _ attributes:(fn_attribute() ** comma()) "]" _ {
+                    attributes
+                }
+
+            rule fn_attribute() -> FunctionAttribute
+                = "inline_always" _ { FunctionAttribute::InlineAlways }
+                / "inline_never" _ { FunctionAttribute::InlineNever }
+                / "pure" _ { FunctionAttribute::Pure }
+                / "cold" _ { FunctionAttribute::Cold }
+
             rule is_public() -> bool
                 = "pub" _ { true }
                 / "" _ { false }
@@ -177,6 +227,7 @@ mod ir_builder {
                 / op_contract_call()
                 / op_extract_element()
                 / op_extract_value()
+                / op_gas_checkpoint()
                 / op_get_storage_key()
                 / op_get_local()
                 / op_gtf()
@@ -256,6 +307,10 @@ mod ir_builder {
                 = "const" _ val_ty:ast_ty() cv:constant() {
                     IrAstOperation::Const(val_ty, cv)
                 }
+                / "const" _ val_ty:ast_ty() "undef" _ {
+                    let cv = IrAstConst { value: IrAstConstValue::Undef(val_ty.clone()), meta_idx: None };
+                    IrAstOperation::Const(val_ty, cv)
+                }
 
             rule op_contract_call() -> IrAstOperation
                 = "contract_call" _
@@ -274,6 +329,11 @@ mod ir_builder {
                     IrAstOperation::ExtractValue(name, ty, idcs)
                 }
 
+            rule op_gas_checkpoint() -> IrAstOperation
+                = "gas_checkpoint" _ id:id() {
+                    IrAstOperation::GasCheckpoint(id)
+                }
+
             rule op_get_storage_key() -> IrAstOperation
                 = "get_storage_key" _ {
                     IrAstOperation::GetStorageKey()
@@ -516,13 +576,14 @@ mod ir_builder {
                     IrAstTy::Struct(tys)
                 }
 
+            // Configurables are named by their call path, so a name may have several segments.
             rule id() -> String
-                = !ast_ty() id:$(id_char0() id_char()*) _ {
+                = !ty_keyword() id:$(id_char0() id_char()* ("::" id_char0() id_char()*)*) _ {
                     id.to_owned()
                 }
 
             rule id_id() -> Ident
-                = !ast_ty() id:$(id_char0() id_char()*) _ {
+                = !ty_keyword() id:$(id_char0() id_char()*) _ {
                     Ident::new(Span::new(id.into(), 0, id.len(), None).unwrap())
                 }
 
@@ -557,9 +618,8 @@ mod ir_builder {
                 / "!" idx:dec_digits() __ {
                     IrMetadatum::Index(idx)
                 }
-                / ['"'] s:$(([^ '"' | '\\'] / ['\\'] ['\\' | '"' ])+) ['"'] __ {
-                    // Metadata strings are printed with '\\' escaped on parsing we unescape it.
-                    IrMetadatum::String(s.to_owned().replace("\\\\", "\\"))
+                / ['"'] s:$(([^ '"' | '\\'] / ['\\'] (['\\' | '"' | '\'' | 'n' | 'r' | 't' | '0'] / "u{" hex_digit()+ "}"))*) ['"'] __ {
+                    IrMetadatum::String(unescape_string(s))
                 }
                 / tag:$(id_char0() id_char()*) __ els:metadata_item()* {
                     IrMetadatum::Struct(tag.to_owned(), els)
@@ -569,6 +629,9 @@ mod ir_builder {
                     IrMetadatum::List(els)
                 }
 
+            rule ty_keyword()
+                = ("unit" / "bool" / "u64" / "b256" / "string") !id_char()
+
             rule id_char0()
                 = quiet!{ ['A'..='Z' | 'a'..='z' | '_'] }
 
@@ -618,7 +681,7 @@ mod ir_builder {
         constant::{Constant, ConstantValue},
         context::Context,
         error::IrError,
-        function::Function,
+        function::{Function, FunctionAttribute},
         instruction::{Instruction, Predicate, Register},
         irtype::Type,
         local_var::LocalVar,
@@ -636,9 +699,16 @@ mod ir_builder {
         metadata: Vec<(MdIdxRef, IrMetadatum)>,
     }
 
+    #[derive(Debug)]
+    pub(super) struct IrAstFunction {
+        fn_decl: IrAstFnDecl,
+        metadata: Vec<(MdIdxRef, IrMetadatum)>,
+    }
+
     #[derive(Debug)]
     struct IrAstFnDecl {
         name: String,
+        attributes: Vec<FunctionAttribute>,
         args: Vec<(IrAstTy, String, bool, Option<MdIdxRef>)>,
         ret_type: IrAstTy,
         is_public: bool,
@@ -684,6 +754,7 @@ mod ir_builder {
         ContractCall(IrAstTy, String, String, String, String, String),
         ExtractElement(String, IrAstTy, String),
         ExtractValue(String, IrAstTy, Vec<u64>),
+        GasCheckpoint(String),
         GetStorageKey(),
         GetLocal(String),
         Gtf(String, u64),
@@ -781,7 +852,10 @@ mod ir_builder {
 
         fn as_value(&self, context: &mut Context, val_ty: IrAstTy) -> Value {
             match self {
-                IrAstConstValue::Undef(_) => unreachable!("Can't convert 'undef' to a value."),
+                IrAstConstValue::Undef(_) => {
+                    let undef = Constant::get_undef(val_ty.to_ir_type(context));
+                    Value::new_constant(context, undef)
+                }
                 IrAstConstValue::Unit => Constant::get_unit(context),
                 IrAstConstValue::Bool(b) => Constant::get_bool(context, *b),
                 IrAstConstValue::B256(bs) => Constant::get_b256(context, *bs),
@@ -867,11 +941,97 @@ mod ir_builder {
         for fn_decl in ir_ast_mod.fn_decls {
             builder.add_fn_decl(&mut ctx, fn_decl)?;
         }
-        builder.resolve_calls(&mut ctx)?;
+        builder.resolve_calls(&mut ctx, None)?;
 
         Ok(ctx)
     }
 
+    pub(super) fn build_function_into(
+        context: &mut Context,
+        function: Function,
+        ir_ast_fn: IrAstFunction,
+    ) -> Result<(), IrError> {
+        let module = context
+            .module_iter()
+            .find(|module| context.modules[module.0].functions.contains(&function))
+            .expect("Function must belong to a module.");
+        let mut builder = function_builder(context, module, ir_ast_fn.metadata);
+
+        // The body is built as a new function, which then swaps contents with `function`.  The
+        // calls are resolved while the new function is still excluded from the name lookup, so a
+        // recursive call refers to `function`.
+        let num_functions = context.modules[module.0].functions.len();
+        let result = builder
+            .add_fn_decl(context, ir_ast_fn.fn_decl)
+            .and_then(|_| {
+                let parsed = *context.modules[module.0].functions.last().unwrap();
+                builder.resolve_calls(context, Some(parsed)).map(|_| parsed)
+            });
+        let parsed = match result {
+            Ok(parsed) => parsed,
+            Err(error) => {
+                if context.modules[module.0].functions.len() > num_functions {
+                    let parsed = *context.modules[module.0].functions.last().unwrap();
+                    module.remove_function(context, &parsed);
+                }
+                return Err(error);
+            }
+        };
+
+        let (old_content, new_content) = context.functions.get2_mut(function.0, parsed.0);
+        std::mem::swap(old_content.unwrap(), new_content.unwrap());
+        for (owner, blocks) in [
+            (function, context.functions[function.0].blocks.clone()),
+            (parsed, context.functions[parsed.0].blocks.clone()),
+        ] {
+            for block in blocks {
+                context.blocks[block.0].function = owner;
+            }
+        }
+        module.remove_function(context, &parsed);
+        Ok(())
+    }
+
+    pub(super) fn build_function_into_module(
+        context: &mut Context,
+        module: Module,
+        ir_ast_fn: IrAstFunction,
+    ) -> Result<Function, IrError> {
+        let mut builder = function_builder(context, module, ir_ast_fn.metadata);
+        let num_functions = context.modules[module.0].functions.len();
+        let result = builder
+            .add_fn_decl(context, ir_ast_fn.fn_decl)
+            .and_then(|_| {
+                let parsed = *context.modules[module.0].functions.last().unwrap();
+                builder.resolve_calls(context, None).map(|_| parsed)
+            });
+        if result.is_err() && context.modules[module.0].functions.len() > num_functions {
+            let parsed = *context.modules[module.0].functions.last().unwrap();
+            module.remove_function(context, &parsed);
+        }
+        result
+    }
+
+    /// A builder for a single function added to `module`, which refers to its configurables.
+    fn function_builder(
+        context: &mut Context,
+        module: Module,
+        metadata: Vec<(MdIdxRef, IrMetadatum)>,
+    ) -> IrBuilder {
+        let md_map = build_metadata_map(context, metadata);
+        let configs_map = context.modules[module.0]
+            .global_configurable
+            .iter()
+            .map(|(path, value)| (path.join("::"), *value))
+            .collect();
+        IrBuilder {
+            module,
+            configs_map,
+            md_map,
+            unresolved_calls: Vec::new(),
+        }
+    }
+
     struct IrBuilder {
         module: Module,
         configs_map: HashMap<String, Value>,
@@ -917,6 +1077,9 @@ mod ir_builder {
                 fn_decl.is_entry,
                 convert_md_idx(&fn_decl.metadata),
             );
+            for attribute in fn_decl.attributes {
+                func.add_attribute(context, attribute);
+            }
 
             // Gather all the (new) arg values by name into a map. Initialize this map with all
             // config variables as they are globally available
@@ -1170,6 +1333,10 @@ mod ir_builder {
                             .extract_value(*val_map.get(&val).unwrap(), ir_ty, idcs)
                             .add_metadatum(context, opt_metadata)
                     }
+                    IrAstOperation::GasCheckpoint(id) => block
+                        .ins(context)
+                        .gas_checkpoint(*val_map.get(&id).unwrap())
+                        .add_metadatum(context, opt_metadata),
                     IrAstOperation::GetStorageKey() => block
                         .ins(context)
                         .get_storage_key()
@@ -1325,24 +1492,27 @@ mod ir_builder {
             }
         }
 
-        fn resolve_calls(self, context: &mut Context) -> Result<(), IrError> {
+        fn resolve_calls(
+            self,
+            context: &mut Context,
+            skip: Option<Function>,
+        ) -> Result<(), IrError> {
             // All of the call instructions are currently invalid (recursive) CALLs to their own
             // function, which need to be replaced with the proper callee function.  We couldn't do
             // it above until we'd gone and created all the functions first.
             //
-            // Now we can loop and find the callee function for each call and update them.
+            // Now we can loop and find the callee function for each call in the module, other
+            // than `skip`, and update them.
             for pending_call in self.unresolved_calls {
-                let call_func = context
-                    .functions
-                    .iter()
-                    .find_map(|(idx, content)| {
-                        if content.name == pending_call.callee {
-                            Some(Function(idx))
-                        } else {
-                            None
-                        }
+                let call_func = self
+                    .module
+                    .function_iter(context)
+                    .find(|function| {
+                        Some(*function) != skip && function.get_name(context) == pending_call.callee
                     })
-                    .unwrap();
+                    .ok_or_else(|| {
+                        IrError::VerifyCallToMissingFunction(pending_call.callee.clone())
+                    })?;
 
                 if let Some(Instruction::Call(dummy_func, _args)) =
                     pending_call.call_val.get_instruction_mut(context)
@@ -1375,7 +1545,7 @@ mod ir_builder {
                     Value::new_configurable(context, as_const).add_metadatum(context, opt_metadata);
                 module.add_global_configurable(
                     context,
-                    vec![config.value_name.clone()],
+                    config.value_name.split("::").map(str::to_owned).collect(),
                     config_val,
                 );
                 (config.value_name.clone(), config_val)
@@ -1428,6 +1598,36 @@ mod ir_builder {
         md_map
     }
 
+    // Metadata strings are printed using `Debug`, which escapes them like Rust string literals,
+    // so on parsing we unescape them.  The grammar has already checked that every escape is one
+    // which `Debug` may produce.
+    fn unescape_string(s: &str) -> String {
+        let mut chars = s.chars();
+        let mut unescaped = String::with_capacity(s.len());
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                unescaped.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => unescaped.push('\n'),
+                Some('r') => unescaped.push('\r'),
+                Some('t') => unescaped.push('\t'),
+                Some('0') => unescaped.push('\0'),
+                Some('u') => {
+                    let code = chars
+                        .by_ref()
+                        .skip(1)
+                        .take_while(|c| *c != '}')
+                        .collect::<String>();
+                    unescaped.extend(u32::from_str_radix(&code, 16).ok().and_then(char::from_u32));
+                }
+                c => unescaped.extend(c),
+            }
+        }
+        unescaped
+    }
+
     fn string_to_hex<const N: usize>(s: &str) -> [u8; N] {
         let mut bytes: [u8; N] = [0; N];
         let mut cur_byte: u8 = 0;
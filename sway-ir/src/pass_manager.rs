@@ -1,16 +1,28 @@
 use crate::{
-    create_const_combine_pass, create_dce_pass, create_dom_fronts_pass, create_dominators_pass,
-    create_func_dce_pass, create_inline_in_main_pass, create_inline_in_non_predicate_pass,
-    create_inline_in_predicate_pass, create_mem2reg_pass, create_module_printer_pass,
-    create_module_verifier_pass, create_postorder_pass, create_simplify_cfg_pass, Context,
-    Function, IrError, Module, CONSTCOMBINE_NAME, DCE_NAME, FUNC_DCE_NAME,
-    INLINE_NONPREDICATE_NAME, INLINE_PREDICATE_NAME, MEM2REG_NAME, SIMPLIFYCFG_NAME,
+    create_adce_pass, create_alias_pass, create_call_graph_pass, create_const_combine_pass,
+    create_coverage_pass, create_cse_pass, create_dce_pass, create_dom_fronts_pass,
+    create_dominators_pass, create_dse_pass, create_egraph_pass, create_func_dce_pass,
+    create_gas_checkpoints_pass, create_inline_in_main_pass, create_inline_in_non_predicate_pass,
+    create_inline_in_predicate_pass, create_instcombine_pass, create_licm_pass, create_loops_pass,
+    create_mark_pure_pass, create_mem2reg_pass, create_memory_ssa_pass, create_module_printer_pass,
+    create_module_verifier_pass, create_packed_abi_pass, create_post_dom_fronts_pass,
+    create_post_dominators_pass, create_postorder_pass, create_purity_pass, create_rewrite_pass,
+    create_rle_pass, create_simplify_cfg_pass, create_split_critical_edges_pass,
+    create_storage_access_pass, create_taint_pass, create_tco_pass, create_unreachable_blocks_pass,
+    create_unroll_pass, printer::to_string_with_options, prune_unreachable_blocks, Context,
+    Function, IrError, MetadataIndex, Module, PrinterOptions, ADCE_NAME, CONSTCOMBINE_NAME,
+    CSE_NAME, DCE_NAME, DOMFRONTS_NAME, DOMINATORS_NAME, DSE_NAME, FUNC_DCE_NAME,
+    INLINE_NONPREDICATE_NAME, INLINE_PREDICATE_NAME, INSTCOMBINE_NAME, LICM_NAME, LOOPS_NAME,
+    MARK_PURE_NAME, MEM2REG_NAME, POSTDOMFRONTS_NAME, POSTDOMINATORS_NAME, POSTORDER_NAME,
+    RLE_NAME, SIMPLIFYCFG_NAME, TCO_NAME, UNREACHABLE_BLOCKS_NAME,
 };
 use downcast_rs::{impl_downcast, Downcast};
 use rustc_hash::FxHashMap;
 use std::{
     any::{type_name, TypeId},
-    collections::hash_map,
+    collections::{hash_map, BTreeMap},
+    fmt,
+    time::{Duration, Instant},
 };
 
 /// Result of an analysis. Specific result must be downcasted to.
@@ -55,6 +67,12 @@ pub struct Pass {
     pub descr: &'static str,
     /// Other passes that this pass depends on.
     pub deps: Vec<&'static str>,
+    /// Analyses whose results remain valid after this pass modifies the IR.  The results of all
+    /// other analyses at the modified scope are invalidated.
+    pub preserves: Vec<&'static str>,
+    /// Whether to remove the blocks left unreachable from the entry of each function this pass
+    /// modifies.
+    pub prune_unreachable: bool,
     /// The executor.
     ///
     pub runner: ScopedPass,
@@ -72,6 +90,18 @@ impl Pass {
     }
 }
 
+/// The analyses of a function which depend only on its blocks and the branches between them.  A
+/// transform which changes neither may preserve them all.
+pub const CFG_ANALYSES: [&str; 7] = [
+    POSTORDER_NAME,
+    DOMINATORS_NAME,
+    DOMFRONTS_NAME,
+    LOOPS_NAME,
+    POSTDOMINATORS_NAME,
+    POSTDOMFRONTS_NAME,
+    UNREACHABLE_BLOCKS_NAME,
+];
+
 #[derive(Default)]
 pub struct AnalysisResults {
     // Hash from (AnalysisResultT, (PassScope, Scope Identity)) to an actual result.
@@ -130,19 +160,91 @@ impl AnalysisResults {
         self.name_typeid_map.insert(name, result_typeid);
     }
 
-    /// Invalidate all results at a given scope.
-    fn invalidate_all_results_at_scope<S: PassScope + 'static>(&mut self, scope: S) {
+    /// Invalidate the results at a given scope, except those of the `preserved` analyses.
+    fn invalidate_results_at_scope<S: PassScope + 'static>(
+        &mut self,
+        scope: S,
+        preserved: &[&'static str],
+    ) {
+        let preserved_typeids = preserved
+            .iter()
+            .filter_map(|name| self.name_typeid_map.get(name).copied())
+            .collect::<Vec<_>>();
         self.results
-            .retain(|(_result_typeid, (scope_typeid, scope_idx)), _v| {
+            .retain(|(result_typeid, (scope_typeid, scope_idx)), _v| {
                 (*scope_typeid, *scope_idx) != (TypeId::of::<S>(), scope.get_arena_idx())
+                    || preserved_typeids.contains(result_typeid)
             });
     }
 }
 
+/// Where a hook passed to [PassManager::run_with_hook] is being called, relative to its pass.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PassHookPoint {
+    Before,
+    After,
+}
+
+/// A transform pass which ran out of fuel on a function, leaving it less optimised than it could
+/// be.  See [Context::set_pass_fuel].
+///
+/// The function is named rather than referred to by handle, since a later pass may remove it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FuelExhausted {
+    pub pass: &'static str,
+    pub function: String,
+    pub metadata: Option<MetadataIndex>,
+}
+
+/// What a pass did over all of its runs by a [PassManager].
+#[derive(Clone, Debug, Default)]
+pub struct PassStatistics {
+    pub pass: &'static str,
+    /// The number of times the pass was run, including as a dependency of another pass.
+    pub runs: u64,
+    /// The number of functions, or modules for a module pass, which were changed.  For an analysis
+    /// it's the number of results computed rather than found already cached.
+    pub changes: u64,
+    /// The wall time taken, excluding any dependencies.
+    pub time: Duration,
+    /// The counters added by the pass with [Context::add_pass_statistic].
+    pub counters: BTreeMap<&'static str, u64>,
+}
+
+/// When to print the IR to stderr while running transform passes, for debugging them.  See
+/// [PassManager::set_print_options].
+#[derive(Clone, Debug, Default)]
+pub struct PrintPassOptions {
+    /// Print the IR before each run of the named passes.
+    pub before: Vec<String>,
+    /// Print the IR after each run of the named passes.
+    pub after: Vec<String>,
+    /// Print the IR before every transform pass.
+    pub before_all: bool,
+    /// Print the IR after every transform pass.
+    pub after_all: bool,
+    /// How to print the IR, e.g., only some of the functions.
+    pub printer_options: PrinterOptions,
+}
+
+impl PrintPassOptions {
+    fn should_print(&self, pass: &str, point: PassHookPoint) -> bool {
+        match point {
+            PassHookPoint::Before => self.before_all || self.before.iter().any(|name| name == pass),
+            PassHookPoint::After => self.after_all || self.after.iter().any(|name| name == pass),
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct PassManager {
     passes: FxHashMap<&'static str, Pass>,
     analyses: AnalysisResults,
+    fuel_exhausted: Vec<FuelExhausted>,
+    statistics: Vec<PassStatistics>,
+    print_options: PrintPassOptions,
+    verify_after_each: bool,
+    gc_metadata: bool,
 }
 
 impl PassManager {
@@ -186,6 +288,11 @@ impl PassManager {
 
         // To please the borrow checker, get current pass again.
         let pass_t = self.passes.get(pass).expect("Unregistered pass");
+        if pass_t.is_transform() {
+            self.print_ir(ir, pass, PassHookPoint::Before);
+        }
+        let start = Instant::now();
+        let mut changes = 0;
 
         for m in ir.module_iter() {
             match &pass_t.runner {
@@ -194,14 +301,24 @@ impl PassManager {
                         if !self.analyses.is_analysis_result_available(pass_t.name, m) {
                             let result = analysis(ir, &self.analyses, m)?;
                             self.analyses.add_result(pass_t.name, m, result);
+                            changes += 1;
                         }
                     }
                     PassMutability::Transform(transform) => {
                         if transform(ir, &self.analyses, m)? {
-                            self.analyses.invalidate_all_results_at_scope(m);
+                            let mut pruned = false;
+                            if pass_t.prune_unreachable {
+                                for f in m.function_iter(ir) {
+                                    pruned |= prune_unreachable_blocks(ir, f)?;
+                                }
+                            }
+                            // Pruning changes the CFG, so nothing is preserved after it.
+                            let preserves: &[_] = if pruned { &[] } else { &pass_t.preserves };
+                            self.analyses.invalidate_results_at_scope(m, preserves);
                             for f in m.function_iter(ir) {
-                                self.analyses.invalidate_all_results_at_scope(f);
+                                self.analyses.invalidate_results_at_scope(f, preserves);
                             }
+                            changes += 1;
                             modified = true;
                         }
                     }
@@ -213,12 +330,28 @@ impl PassManager {
                                 if !self.analyses.is_analysis_result_available(pass_t.name, f) {
                                     let result = analysis(ir, &self.analyses, f)?;
                                     self.analyses.add_result(pass_t.name, f, result);
+                                    changes += 1;
                                 }
                             }
+                            PassMutability::Transform(_) if f.is_optimized(ir) => {}
                             PassMutability::Transform(transform) => {
-                                if transform(ir, &self.analyses, f)? {
-                                    self.analyses.invalidate_all_results_at_scope(f);
-                                    self.analyses.invalidate_all_results_at_scope(m);
+                                ir.refuel();
+                                let function_modified = transform(ir, &self.analyses, f)?;
+                                if ir.stop_fuel() {
+                                    self.fuel_exhausted.push(FuelExhausted {
+                                        pass: pass_t.name,
+                                        function: f.get_name(ir).to_string(),
+                                        metadata: f.get_metadata(ir),
+                                    });
+                                }
+                                if function_modified {
+                                    let pruned = pass_t.prune_unreachable
+                                        && prune_unreachable_blocks(ir, f)?;
+                                    let preserves: &[_] =
+                                        if pruned { &[] } else { &pass_t.preserves };
+                                    self.analyses.invalidate_results_at_scope(f, preserves);
+                                    self.analyses.invalidate_results_at_scope(m, preserves);
+                                    changes += 1;
                                     modified = true;
                                 }
                             }
@@ -227,23 +360,178 @@ impl PassManager {
                 }
             }
         }
+
+        let statistics = match self.statistics.iter().position(|stats| stats.pass == pass) {
+            Some(idx) => &mut self.statistics[idx],
+            None => {
+                self.statistics.push(PassStatistics {
+                    pass,
+                    ..Default::default()
+                });
+                self.statistics.last_mut().unwrap()
+            }
+        };
+        statistics.runs += 1;
+        statistics.changes += changes;
+        statistics.time += start.elapsed();
+        for (name, count) in ir.take_pass_statistics() {
+            *statistics.counters.entry(name).or_default() += count;
+        }
+
+        if self.passes[pass].is_transform() {
+            self.print_ir(ir, pass, PassHookPoint::After);
+        }
+        if modified && self.verify_after_each {
+            for m in ir.module_iter() {
+                ir.verify_module(&ir.modules[m.0])
+                    .map_err(|error| IrError::VerifyAfterPass(pass.to_string(), Box::new(error)))?;
+            }
+        }
         Ok(modified)
     }
 
     /// Run the passes specified in `config`.
     pub fn run(&mut self, ir: &mut Context, passes: &PassGroup) -> Result<bool, IrError> {
+        self.run_with_hook(ir, passes, |_, _, _| ())
+    }
+
+    /// Run the passes specified in `config`, invoking `hook` immediately before and after each
+    /// pass in the group.
+    ///
+    /// The hook is given the pass name and is not called for any dependencies which are run
+    /// implicitly.
+    pub fn run_with_hook<H>(
+        &mut self,
+        ir: &mut Context,
+        passes: &PassGroup,
+        mut hook: H,
+    ) -> Result<bool, IrError>
+    where
+        H: FnMut(&Context, &'static str, PassHookPoint),
+    {
         let mut modified = false;
         for pass in passes.flatten_pass_group() {
+            hook(ir, pass, PassHookPoint::Before);
             modified |= self.actually_run(ir, pass)?;
+            hook(ir, pass, PassHookPoint::After);
+        }
+        if modified && self.gc_metadata {
+            ir.gc_metadata();
         }
         Ok(modified)
     }
 
+    /// Set whether to verify the IR after every transform pass which changes it, so that invalid IR
+    /// is reported along with the pass which produced it.
+    pub fn set_verify_after_each(&mut self, verify: bool) {
+        self.verify_after_each = verify;
+    }
+
+    /// Set whether to collect the metadata left unreferenced by the passes once a group has run.
+    /// See [`Context::gc_metadata`].
+    pub fn set_gc_metadata(&mut self, gc: bool) {
+        self.gc_metadata = gc;
+    }
+
+    /// Set when to print the IR while running passes.
+    pub fn set_print_options(&mut self, options: PrintPassOptions) {
+        self.print_options = options;
+    }
+
+    fn print_ir(&self, ir: &Context, pass: &str, point: PassHookPoint) {
+        if self.print_options.should_print(pass, point) {
+            let when = match point {
+                PassHookPoint::Before => "before",
+                PassHookPoint::After => "after",
+            };
+            eprintln!(
+                "// IR {when} {pass}:\n{}",
+                to_string_with_options(ir, &self.print_options.printer_options)
+            );
+        }
+    }
+
+    /// The functions on which a pass has run out of fuel, in the order it happened.
+    pub fn fuel_exhausted(&self) -> &[FuelExhausted] {
+        &self.fuel_exhausted
+    }
+
+    /// The statistics of every pass which has been run, in the order they were first run.
+    pub fn statistics(&self) -> &[PassStatistics] {
+        &self.statistics
+    }
+
+    /// A table of the [PassStatistics], with the slowest pass first.
+    pub fn statistics_report(&self) -> String {
+        let mut statistics = self.statistics.iter().collect::<Vec<_>>();
+        statistics.sort_by_key(|stats| std::cmp::Reverse(stats.time));
+
+        let mut report = format!(
+            "{:32} {:>8} {:>8} {:>12}\n",
+            "pass", "runs", "changes", "time (ms)"
+        );
+        for stats in statistics {
+            report += &format!(
+                "{:32} {:>8} {:>8} {:>12.3}\n",
+                stats.pass,
+                stats.runs,
+                stats.changes,
+                stats.time.as_secs_f64() * 1000.0
+            );
+            for (name, count) in &stats.counters {
+                report += &format!("  {name:30} {count:>8}\n");
+            }
+        }
+        let total = self
+            .statistics
+            .iter()
+            .map(|stats| stats.time)
+            .sum::<Duration>();
+        report += &format!("{:50} {:>12.3}\n", "total", total.as_secs_f64() * 1000.0);
+        report
+    }
+
     /// Get reference to a registered pass.
     pub fn lookup_registered_pass(&self, name: &str) -> Option<&Pass> {
         self.passes.get(name)
     }
 
+    /// Return the names of all the registered passes, in alphabetical order.
+    pub fn pass_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        let mut names = self.passes.keys().copied().collect::<Vec<_>>();
+        names.sort();
+        names.into_iter()
+    }
+
+    /// Parse a pipeline from a comma separated list of pass names, e.g., `mem2reg,simplifycfg,dce`.
+    ///
+    /// An optimisation level `O0`, `O1`, `O2`, `Os` or `Oz`, optionally preceded by a `-`, may be
+    /// named in place of a pass to include its whole group.  Which passes a level includes depends
+    /// on whether the program is a predicate.
+    pub fn parse_pipeline(&self, spec: &str, is_predicate: bool) -> Result<PassGroup, IrError> {
+        let mut pass_group = PassGroup::default();
+        for name in spec
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            let level = match name.strip_prefix('-').unwrap_or(name) {
+                "O0" => Some(create_o0_pass_group(is_predicate)),
+                "O1" => Some(create_o1_pass_group(is_predicate)),
+                "O2" => Some(create_o2_pass_group(is_predicate)),
+                "Os" => Some(create_os_pass_group(is_predicate)),
+                "Oz" => Some(create_oz_pass_group(is_predicate)),
+                _ => None,
+            };
+            match (level, self.lookup_registered_pass(name)) {
+                (Some(group), _) => pass_group.append_group(group),
+                (None, Some(pass)) => pass_group.append_pass(pass.name),
+                (None, None) => return Err(IrError::UnknownPass(name.to_string())),
+            }
+        }
+        Ok(pass_group)
+    }
+
     pub fn help_text(&self) -> String {
         let summary = self
             .passes
@@ -261,6 +549,13 @@ impl PassManager {
 #[derive(Default)]
 pub struct PassGroup(Vec<PassOrGroup>);
 
+/// The flattened passes as a pipeline which [PassManager::parse_pipeline] accepts.
+impl fmt::Display for PassGroup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.flatten_pass_group().join(","))
+    }
+}
+
 /// An individual pass, or a group (with possible subgroup) of passes.
 pub enum PassOrGroup {
     Pass(&'static str),
@@ -268,8 +563,8 @@ pub enum PassOrGroup {
 }
 
 impl PassGroup {
-    // Flatten a group of passes into an ordered list.
-    fn flatten_pass_group(&self) -> Vec<&'static str> {
+    /// Flatten a group of passes into an ordered list.
+    pub fn flatten_pass_group(&self) -> Vec<&'static str> {
         let mut output = Vec::<&str>::new();
         fn inner(output: &mut Vec<&str>, input: &PassGroup) {
             for pass_or_group in &input.0 {
@@ -300,6 +595,16 @@ pub fn register_known_passes(pm: &mut PassManager) {
     pm.register(create_postorder_pass());
     pm.register(create_dominators_pass());
     pm.register(create_dom_fronts_pass());
+    pm.register(create_loops_pass());
+    pm.register(create_post_dominators_pass());
+    pm.register(create_post_dom_fronts_pass());
+    pm.register(create_alias_pass());
+    pm.register(create_memory_ssa_pass());
+    pm.register(create_call_graph_pass());
+    pm.register(create_purity_pass());
+    pm.register(create_storage_access_pass());
+    pm.register(create_unreachable_blocks_pass());
+    pm.register(create_taint_pass());
     pm.register(create_module_printer_pass());
     pm.register(create_module_verifier_pass());
     // Optimization passes.
@@ -308,9 +613,42 @@ pub fn register_known_passes(pm: &mut PassManager) {
     pm.register(create_inline_in_non_predicate_pass());
     pm.register(create_inline_in_main_pass());
     pm.register(create_const_combine_pass());
+    pm.register(create_cse_pass());
+    pm.register(create_instcombine_pass());
+    pm.register(create_licm_pass());
+    pm.register(create_mark_pure_pass());
+    pm.register(create_egraph_pass());
+    pm.register(create_rewrite_pass());
     pm.register(create_simplify_cfg_pass());
+    pm.register(create_split_critical_edges_pass());
     pm.register(create_func_dce_pass());
     pm.register(create_dce_pass());
+    pm.register(create_adce_pass());
+    pm.register(create_tco_pass());
+    pm.register(create_unroll_pass());
+    pm.register(create_rle_pass());
+    pm.register(create_dse_pass());
+    pm.register(create_coverage_pass());
+    pm.register(create_gas_checkpoints_pass());
+    pm.register(create_packed_abi_pass());
+}
+
+/// The inlining pass which must always be run.  Predicates cannot make calls at all, and
+/// non-predicates must inline functions which ASMgen can't yet call.
+pub fn mandatory_inline_pass(is_predicate: bool) -> &'static str {
+    if is_predicate {
+        INLINE_PREDICATE_NAME
+    } else {
+        INLINE_NONPREDICATE_NAME
+    }
+}
+
+/// The minimal set of passes required to produce code, for the fastest compilation.
+pub fn create_o0_pass_group(is_predicate: bool) -> PassGroup {
+    let mut o0 = PassGroup::default();
+    o0.append_pass(mandatory_inline_pass(is_predicate));
+
+    o0
 }
 
 pub fn create_o1_pass_group(is_predicate: bool) -> PassGroup {
@@ -318,11 +656,7 @@ pub fn create_o1_pass_group(is_predicate: bool) -> PassGroup {
     let mut o1 = PassGroup::default();
     // Configure to run our passes.
     o1.append_pass(MEM2REG_NAME);
-    if is_predicate {
-        o1.append_pass(INLINE_PREDICATE_NAME);
-    } else {
-        o1.append_pass(INLINE_NONPREDICATE_NAME);
-    }
+    o1.append_pass(mandatory_inline_pass(is_predicate));
     o1.append_pass(CONSTCOMBINE_NAME);
     o1.append_pass(SIMPLIFYCFG_NAME);
     o1.append_pass(CONSTCOMBINE_NAME);
@@ -333,6 +667,88 @@ pub fn create_o1_pass_group(is_predicate: bool) -> PassGroup {
     o1
 }
 
+/// As for O1 but with further rounds of simplification, trading compile time for runtime gas.
+pub fn create_o2_pass_group(is_predicate: bool) -> PassGroup {
+    let mut o2 = PassGroup::default();
+    o2.append_pass(MEM2REG_NAME);
+    o2.append_pass(mandatory_inline_pass(is_predicate));
+    o2.append_pass(TCO_NAME);
+    o2.append_pass(MARK_PURE_NAME);
+    for _ in 0..3 {
+        o2.append_pass(INSTCOMBINE_NAME);
+        o2.append_pass(CONSTCOMBINE_NAME);
+        o2.append_pass(CSE_NAME);
+        o2.append_pass(RLE_NAME);
+        o2.append_pass(SIMPLIFYCFG_NAME);
+        o2.append_pass(DSE_NAME);
+        o2.append_pass(DCE_NAME);
+    }
+    o2.append_pass(LICM_NAME);
+    o2.append_pass(FUNC_DCE_NAME);
+    o2.append_pass(DCE_NAME);
+
+    o2
+}
+
+/// Optimise for bytecode size.
+///
+/// Runs the simplifications of O2, which remove or fold instructions, but leaves out loop
+/// invariant code motion, which only pays off in runtime gas.  Dead functions are removed before
+/// simplifying as well as after, so that no time is spent on them.
+pub fn create_os_pass_group(is_predicate: bool) -> PassGroup {
+    let mut os = PassGroup::default();
+    os.append_pass(MEM2REG_NAME);
+    os.append_pass(mandatory_inline_pass(is_predicate));
+    os.append_pass(FUNC_DCE_NAME);
+    os.append_pass(DCE_NAME);
+    os.append_pass(TCO_NAME);
+    os.append_pass(MARK_PURE_NAME);
+    for _ in 0..2 {
+        os.append_pass(INSTCOMBINE_NAME);
+        os.append_pass(CONSTCOMBINE_NAME);
+        os.append_pass(CSE_NAME);
+        os.append_pass(RLE_NAME);
+        os.append_pass(SIMPLIFYCFG_NAME);
+        os.append_pass(DSE_NAME);
+        os.append_pass(DCE_NAME);
+    }
+    os.append_pass(FUNC_DCE_NAME);
+    os.append_pass(DCE_NAME);
+
+    os
+}
+
+/// Optimise aggressively for bytecode size: as for Os, then also removing the code which only
+/// feeds branches that don't affect the result.
+pub fn create_oz_pass_group(is_predicate: bool) -> PassGroup {
+    let mut oz = create_os_pass_group(is_predicate);
+    oz.append_pass(ADCE_NAME);
+    oz.append_pass(SIMPLIFYCFG_NAME);
+    oz.append_pass(FUNC_DCE_NAME);
+
+    oz
+}
+
+/// Shuffle the passes in a group into a random order determined by `seed`, repeating some of them.
+///
+/// Every pass in the group is kept so the result is as valid as the original pipeline, but any
+/// dependency of one transform on having been preceded by another is exposed.
+pub fn shuffle_pass_group(pg: &PassGroup, seed: u64) -> PassGroup {
+    let mut rng = crate::superopt::Rng::new(seed);
+    let mut passes = pg.flatten_pass_group();
+    // Repeat about a quarter of the passes.
+    for idx in 0..passes.len() {
+        if rng.next() & 3 == 0 {
+            passes.push(passes[idx]);
+        }
+    }
+    // Fisher-Yates.
+    for idx in (1..passes.len()).rev() {
+        passes.swap(idx, (rng.next() % (idx as u64 + 1)) as usize);
+    }
+    PassGroup(passes.into_iter().map(PassOrGroup::Pass).collect())
+}
+
 /// Utility to insert a pass after every pass in the given group
 pub fn insert_after_each(pg: PassGroup, pass: &'static str) -> PassGroup {
     PassGroup(
@@ -0,0 +1,807 @@
+//! A small DSL for writing instruction combining rules declaratively.
+//!
+//! A [`Rule`] rewrites any instruction matching its pattern into its replacement.  Both are
+//! [`Pattern`]s, which are written as s-expressions with the [`pattern!`](crate::pattern!) and
+//! [`rule!`](crate::rule!) macros:
+//!
+//! - `x` matches any value, binding it to the name `x`.  A name bound more than once must match
+//!   the same value each time.
+//! - `(const c)` matches any integer constant, binding it to the name `c`.
+//! - `0`, `true` and `false` match constants with exactly that value.
+//! - `(add lhs rhs)` matches a `binary_op` or `cmp` instruction whose operands match `lhs` and
+//!   `rhs`.  The operations are `add`, `sub`, `mul`, `div`, `and`, `or`, `xor`, `eq`, `lt` and
+//!   `gt`.  The operands of commutative operations are matched in either order.
+//! - `(one_use add lhs rhs)` also requires the instruction to have a single use, so that it
+//!   becomes dead once the rule is applied rather than being computed twice.  This is ignored
+//!   for the instruction being rewritten itself.
+//!
+//! The replacement uses the same syntax, where names refer to the values bound by the pattern.
+//! Any new instructions are inserted before the rewritten instruction, and are folded if all of
+//! their operands are constants.  Integer literals take the type of the rewritten instruction, or
+//! for comparisons the type of the other operand.
+//!
+//! ```
+//! use sway_ir::rule;
+//!
+//! let rule = rule!(and_consts: (and (one_use and x (const a)) (const b)) => (and x (and a b)));
+//! assert_eq!(
+//!     rule.to_string(),
+//!     "and_consts: (and (one_use and x (const a)) (const b)) => (and x (and a b))"
+//! );
+//! ```
+//!
+//! Rules may also be loaded at run time with [`parse_rules`], which reads them from a file of
+//! `(rule <name> <pattern> <replacement>)` forms and checks that each is well typed, for use by
+//! the `rewrite` pass.
+
+use std::fmt;
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    block::Block,
+    constant::{Constant, ConstantValue},
+    context::Context,
+    error::IrError,
+    function::Function,
+    instruction::{BinaryOpKind, Instruction, Predicate},
+    irtype::Type,
+    metadata::MetadataIndex,
+    value::Value,
+};
+
+/// An operation matched or built by a [`Pattern`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PatternOp {
+    Binary(BinaryOpKind),
+    Cmp(Predicate),
+}
+
+impl PatternOp {
+    /// Return whether the operands of the operation may be swapped.
+    pub fn is_commutative(&self) -> bool {
+        matches!(
+            self,
+            PatternOp::Binary(
+                BinaryOpKind::Add
+                    | BinaryOpKind::Mul
+                    | BinaryOpKind::And
+                    | BinaryOpKind::Or
+                    | BinaryOpKind::Xor
+            ) | PatternOp::Cmp(Predicate::Equal)
+        )
+    }
+
+    /// Return the operation with the given name in the pattern syntax.
+    pub fn from_name(name: &str) -> Option<PatternOp> {
+        Some(match name {
+            "add" => PatternOp::Binary(BinaryOpKind::Add),
+            "sub" => PatternOp::Binary(BinaryOpKind::Sub),
+            "mul" => PatternOp::Binary(BinaryOpKind::Mul),
+            "div" => PatternOp::Binary(BinaryOpKind::Div),
+            "and" => PatternOp::Binary(BinaryOpKind::And),
+            "or" => PatternOp::Binary(BinaryOpKind::Or),
+            "xor" => PatternOp::Binary(BinaryOpKind::Xor),
+            "eq" => PatternOp::Cmp(Predicate::Equal),
+            "lt" => PatternOp::Cmp(Predicate::LessThan),
+            "gt" => PatternOp::Cmp(Predicate::GreaterThan),
+            _ => return None,
+        })
+    }
+
+    /// Return the name of the operation in the pattern syntax.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PatternOp::Binary(BinaryOpKind::Add) => "add",
+            PatternOp::Binary(BinaryOpKind::Sub) => "sub",
+            PatternOp::Binary(BinaryOpKind::Mul) => "mul",
+            PatternOp::Binary(BinaryOpKind::Div) => "div",
+            PatternOp::Binary(BinaryOpKind::And) => "and",
+            PatternOp::Binary(BinaryOpKind::Or) => "or",
+            PatternOp::Binary(BinaryOpKind::Xor) => "xor",
+            PatternOp::Cmp(Predicate::Equal) => "eq",
+            PatternOp::Cmp(Predicate::LessThan) => "lt",
+            PatternOp::Cmp(Predicate::GreaterThan) => "gt",
+        }
+    }
+
+    pub(crate) fn of_instruction(instruction: &Instruction) -> Option<(PatternOp, Value, Value)> {
+        match instruction {
+            Instruction::BinaryOp { op, arg1, arg2 } => {
+                Some((PatternOp::Binary(*op), *arg1, *arg2))
+            }
+            Instruction::Cmp(pred, lhs, rhs) => Some((PatternOp::Cmp(*pred), *lhs, *rhs)),
+            _ => None,
+        }
+    }
+
+    /// Return the instruction performing the operation on `arg1` and `arg2`.
+    pub(crate) fn instruction(&self, arg1: Value, arg2: Value) -> Instruction {
+        match self {
+            PatternOp::Binary(op) => Instruction::BinaryOp {
+                op: *op,
+                arg1,
+                arg2,
+            },
+            PatternOp::Cmp(pred) => Instruction::Cmp(*pred, arg1, arg2),
+        }
+    }
+
+    /// Evaluate the operation on constant operands of `width` bits, if the result is defined.
+    pub(crate) fn fold(&self, width: u8, lhs: u64, rhs: u64) -> Option<ConstantValue> {
+        let fits = |n: u64| width >= 64 || n < (1 << width);
+        Some(match self {
+            PatternOp::Binary(op) => ConstantValue::Uint(
+                match op {
+                    BinaryOpKind::Add => lhs.checked_add(rhs),
+                    BinaryOpKind::Sub => lhs.checked_sub(rhs),
+                    BinaryOpKind::Mul => lhs.checked_mul(rhs),
+                    BinaryOpKind::Div => lhs.checked_div(rhs),
+                    BinaryOpKind::And => Some(lhs & rhs),
+                    BinaryOpKind::Or => Some(lhs | rhs),
+                    BinaryOpKind::Xor => Some(lhs ^ rhs),
+                }
+                .filter(|n| fits(*n))?,
+            ),
+            PatternOp::Cmp(Predicate::Equal) => ConstantValue::Bool(lhs == rhs),
+            PatternOp::Cmp(Predicate::LessThan) => ConstantValue::Bool(lhs < rhs),
+            PatternOp::Cmp(Predicate::GreaterThan) => ConstantValue::Bool(lhs > rhs),
+        })
+    }
+}
+
+/// A pattern over values, or a template for building them.  See the [module
+/// documentation](self) for the syntax.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Pattern {
+    /// Any value, bound to a name.
+    Value(String),
+    /// Any integer constant, bound to a name.
+    Constant(String),
+    /// An integer constant with this value.
+    Uint(u64),
+    /// A bool constant with this value.
+    Bool(bool),
+    /// An instruction whose operands match the operand patterns.
+    Op {
+        op: PatternOp,
+        lhs: Box<Pattern>,
+        rhs: Box<Pattern>,
+        one_use: bool,
+    },
+}
+
+/// The values bound to the names of a [`Pattern`] by a successful match.
+pub type Captures = FxHashMap<String, Value>;
+
+/// The number of uses of each value within a function.
+pub type UseCounts = FxHashMap<Value, usize>;
+
+impl Pattern {
+    pub fn op(op: PatternOp, lhs: Pattern, rhs: Pattern) -> Pattern {
+        Pattern::Op {
+            op,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+            one_use: false,
+        }
+    }
+
+    /// Require the instruction matched by this pattern to have a single use.
+    pub fn one_use(self) -> Pattern {
+        match self {
+            Pattern::Op { op, lhs, rhs, .. } => Pattern::Op {
+                op,
+                lhs,
+                rhs,
+                one_use: true,
+            },
+            other => other,
+        }
+    }
+
+    /// Return the names bound by this pattern, in order of their first appearance.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.collect_names(&mut names);
+        names
+    }
+
+    fn collect_names<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            Pattern::Value(name) | Pattern::Constant(name) => {
+                if !names.contains(&name.as_str()) {
+                    names.push(name)
+                }
+            }
+            Pattern::Uint(_) | Pattern::Bool(_) => (),
+            Pattern::Op { lhs, rhs, .. } => {
+                lhs.collect_names(names);
+                rhs.collect_names(names);
+            }
+        }
+    }
+
+    /// Match `value` against this pattern, returning the bound values on success.
+    pub fn matches(&self, context: &Context, uses: &UseCounts, value: Value) -> Option<Captures> {
+        let mut captures = Captures::default();
+        self.match_value(context, uses, value, true, &mut captures)
+            .then_some(captures)
+    }
+
+    fn match_value(
+        &self,
+        context: &Context,
+        uses: &UseCounts,
+        value: Value,
+        is_root: bool,
+        captures: &mut Captures,
+    ) -> bool {
+        let bind = |captures: &mut Captures, name: &String| match captures.get(name) {
+            Some(bound) => *bound == value,
+            None => {
+                captures.insert(name.clone(), value);
+                true
+            }
+        };
+        match self {
+            Pattern::Value(name) => bind(captures, name),
+            Pattern::Constant(name) => {
+                uint_constant(context, value).is_some() && bind(captures, name)
+            }
+            Pattern::Uint(n) => uint_constant(context, value) == Some(*n),
+            Pattern::Bool(b) => matches!(
+                value.get_constant(context),
+                Some(Constant { value: ConstantValue::Bool(cb), .. }) if cb == b
+            ),
+            Pattern::Op {
+                op,
+                lhs,
+                rhs,
+                one_use,
+            } => {
+                let (ins_op, arg1, arg2) = match value
+                    .get_instruction(context)
+                    .and_then(PatternOp::of_instruction)
+                {
+                    Some(operation) => operation,
+                    None => return false,
+                };
+                if ins_op != *op || (*one_use && !is_root && uses.get(&value) != Some(&1)) {
+                    return false;
+                }
+
+                let saved = captures.clone();
+                if lhs.match_value(context, uses, arg1, false, captures)
+                    && rhs.match_value(context, uses, arg2, false, captures)
+                {
+                    return true;
+                }
+                *captures = saved;
+                op.is_commutative()
+                    && lhs.match_value(context, uses, arg2, false, captures)
+                    && rhs.match_value(context, uses, arg1, false, captures)
+            }
+        }
+    }
+
+    /// Build the value described by this template, using the values bound in `captures`.  `ty`
+    /// is the type given to integer literals.  New instructions are inserted by `inserter`.
+    ///
+    /// Panics if the template refers to a name which isn't bound.
+    pub fn build(
+        &self,
+        context: &mut Context,
+        captures: &Captures,
+        ty: Type,
+        inserter: &mut PatternInserter,
+    ) -> Value {
+        match self {
+            Pattern::Value(name) | Pattern::Constant(name) => *captures
+                .get(name)
+                .unwrap_or_else(|| panic!("'{name}' is not bound by the pattern")),
+            Pattern::Uint(n) => {
+                let width = ty.get_uint_width(context).unwrap_or(64);
+                Constant::get_uint(context, width, *n)
+            }
+            Pattern::Bool(b) => Constant::get_bool(context, *b),
+            Pattern::Op { op, lhs, rhs, .. } => {
+                // The operands of a binary operation share its type, but a comparison's operands
+                // can only take their type from each other.
+                let operand_ty = match op {
+                    PatternOp::Binary(_) => ty,
+                    PatternOp::Cmp(_) => Type::get_uint64(context),
+                };
+                let (arg1, arg2) = if matches!(**lhs, Pattern::Uint(_)) {
+                    let arg2 = rhs.build(context, captures, operand_ty, inserter);
+                    let arg1_ty = arg2.get_type(context).unwrap_or(operand_ty);
+                    (lhs.build(context, captures, arg1_ty, inserter), arg2)
+                } else {
+                    let arg1 = lhs.build(context, captures, operand_ty, inserter);
+                    let arg2_ty = arg1.get_type(context).unwrap_or(operand_ty);
+                    (arg1, rhs.build(context, captures, arg2_ty, inserter))
+                };
+
+                let width = arg1
+                    .get_type(context)
+                    .and_then(|ty| ty.get_uint_width(context));
+                let folded = match (
+                    width,
+                    uint_constant(context, arg1),
+                    uint_constant(context, arg2),
+                ) {
+                    (Some(width), Some(lhs), Some(rhs)) => op.fold(width, lhs, rhs),
+                    _ => None,
+                };
+                match folded {
+                    Some(ConstantValue::Uint(n)) => Constant::get_uint(context, width.unwrap(), n),
+                    Some(ConstantValue::Bool(b)) => Constant::get_bool(context, b),
+                    _ => inserter.insert(context, op.instruction(arg1, arg2)),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pattern::Value(name) => write!(f, "{name}"),
+            Pattern::Constant(name) => write!(f, "(const {name})"),
+            Pattern::Uint(n) => write!(f, "{n}"),
+            Pattern::Bool(b) => write!(f, "{b}"),
+            Pattern::Op {
+                op,
+                lhs,
+                rhs,
+                one_use,
+            } => write!(
+                f,
+                "({}{} {lhs} {rhs})",
+                if *one_use { "one_use " } else { "" },
+                op.name()
+            ),
+        }
+    }
+}
+
+fn uint_constant(context: &Context, value: Value) -> Option<u64> {
+    match value.get_constant(context) {
+        Some(Constant {
+            value: ConstantValue::Uint(n),
+            ..
+        }) => Some(*n),
+        _ => None,
+    }
+}
+
+/// Inserts the instructions built by [`Pattern::build`] at a fixed position in a block.
+pub struct PatternInserter {
+    block: Block,
+    position: usize,
+    metadata: Option<MetadataIndex>,
+}
+
+impl PatternInserter {
+    /// Insert before the instruction `ins_val` in `block`, copying its metadata.
+    pub fn before(context: &Context, block: Block, ins_val: Value) -> PatternInserter {
+        let position = context.blocks[block.0]
+            .instructions
+            .iter()
+            .position(|val| *val == ins_val)
+            .expect("instruction is in its block");
+        PatternInserter {
+            block,
+            position,
+            metadata: ins_val.get_metadata(context),
+        }
+    }
+
+    pub(crate) fn insert(&mut self, context: &mut Context, instruction: Instruction) -> Value {
+        let value =
+            Value::new_instruction(context, instruction).add_metadatum(context, self.metadata);
+        context.blocks[self.block.0]
+            .instructions
+            .insert(self.position, value);
+        value.set_block(context, Some(self.block));
+        self.position += 1;
+        value
+    }
+}
+
+/// A named rewrite of the instructions matching `pattern` into `replacement`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rule {
+    pub name: String,
+    pub pattern: Pattern,
+    pub replacement: Pattern,
+}
+
+impl Rule {
+    /// Return a new rule.
+    ///
+    /// Panics if the rule is invalid, see [`Rule::try_new`].
+    pub fn new(name: &str, pattern: Pattern, replacement: Pattern) -> Rule {
+        Rule::try_new(name, pattern, replacement).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Return a new rule, checking that `pattern` matches an instruction, that `replacement` only
+    /// refers to names bound by `pattern` and that both are well typed, with `replacement` of the
+    /// same type as `pattern`.
+    pub fn try_new(name: &str, pattern: Pattern, replacement: Pattern) -> Result<Rule, IrError> {
+        let invalid = |msg: String| IrError::InvalidRewriteRule(format!("rule '{name}': {msg}"));
+        if !matches!(pattern, Pattern::Op { .. }) {
+            return Err(invalid("the pattern must match an instruction".to_string()));
+        }
+        let bound = pattern.names();
+        if let Some(unbound) = replacement
+            .names()
+            .into_iter()
+            .find(|name| !bound.contains(name))
+        {
+            return Err(invalid(format!("'{unbound}' is not bound by the pattern")));
+        }
+
+        let mut checker = KindChecker {
+            kinds: FxHashMap::default(),
+            binding: true,
+        };
+        let kind = checker.check(&pattern, None).map_err(invalid)?;
+        checker.binding = false;
+        checker.check(&replacement, kind).map_err(invalid)?;
+
+        Ok(Rule {
+            name: name.to_string(),
+            pattern,
+            replacement,
+        })
+    }
+
+    /// Return the rule in the syntax read by [`parse_rules`].
+    pub fn to_sexp(&self) -> String {
+        format!("(rule {} {} {})", self.name, self.pattern, self.replacement)
+    }
+}
+
+/// The kinds of IR type which patterns distinguish.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum PatternKind {
+    Int,
+    Bool,
+}
+
+impl fmt::Display for PatternKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternKind::Int => write!(f, "an integer"),
+            PatternKind::Bool => write!(f, "a bool"),
+        }
+    }
+}
+
+/// Infers the kinds of the names bound by a pattern, and checks a replacement against them.
+struct KindChecker<'a> {
+    kinds: FxHashMap<&'a str, PatternKind>,
+    /// Whether names may still be given a kind, i.e. a pattern rather than a replacement is
+    /// being checked.
+    binding: bool,
+}
+
+impl<'a> KindChecker<'a> {
+    /// Check that `pattern` is of the `expected` kind, if any, returning its kind if known.
+    fn check(
+        &mut self,
+        pattern: &'a Pattern,
+        expected: Option<PatternKind>,
+    ) -> Result<Option<PatternKind>, String> {
+        let unify = |what: &dyn fmt::Display,
+                     actual: Option<PatternKind>,
+                     expected: Option<PatternKind>| match (actual, expected) {
+            (Some(actual), Some(expected)) if actual != expected => {
+                Err(format!("{what} is {actual} where {expected} is required"))
+            }
+            _ => Ok(actual.or(expected)),
+        };
+        match pattern {
+            Pattern::Value(name) | Pattern::Constant(name) => {
+                let what = format!("'{name}'");
+                let known = self.kinds.get(name.as_str()).copied();
+                let kind = match pattern {
+                    Pattern::Constant(_) => unify(&what, Some(PatternKind::Int), known)?,
+                    _ => known,
+                };
+                let kind = unify(&what, kind, expected)?;
+                if let Some(kind) = kind {
+                    if self.binding {
+                        self.kinds.insert(name, kind);
+                    } else if known.is_none() {
+                        return Err(format!("the type of {what} is not known to be {kind}"));
+                    }
+                }
+                Ok(kind)
+            }
+            Pattern::Uint(n) => unify(&n, Some(PatternKind::Int), expected),
+            Pattern::Bool(b) => unify(&b, Some(PatternKind::Bool), expected),
+            Pattern::Op { op, lhs, rhs, .. } => match op {
+                PatternOp::Binary(_) => {
+                    self.check(lhs, Some(PatternKind::Int))?;
+                    self.check(rhs, Some(PatternKind::Int))?;
+                    unify(
+                        &format!("'{}'", op.name()),
+                        Some(PatternKind::Int),
+                        expected,
+                    )
+                }
+                PatternOp::Cmp(Predicate::Equal) => {
+                    // Either operand may decide the kind of the other.
+                    let lhs_kind = self.check(lhs, None)?;
+                    let rhs_kind = self.check(rhs, lhs_kind)?;
+                    if lhs_kind.is_none() && rhs_kind.is_some() {
+                        self.check(lhs, rhs_kind)?;
+                    }
+                    unify(
+                        &format!("'{}'", op.name()),
+                        Some(PatternKind::Bool),
+                        expected,
+                    )
+                }
+                PatternOp::Cmp(_) => {
+                    self.check(lhs, Some(PatternKind::Int))?;
+                    self.check(rhs, Some(PatternKind::Int))?;
+                    unify(
+                        &format!("'{}'", op.name()),
+                        Some(PatternKind::Bool),
+                        expected,
+                    )
+                }
+            },
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} => {}", self.name, self.pattern, self.replacement)
+    }
+}
+
+/// Count the uses of each value by the instructions of `function`.
+pub fn count_uses(context: &Context, function: Function) -> UseCounts {
+    let mut uses = UseCounts::default();
+    for (_block, ins) in function.instruction_iter(context) {
+        if let Some(instruction) = ins.get_instruction(context) {
+            for operand in instruction.get_operands() {
+                *uses.entry(operand).or_default() += 1;
+            }
+        }
+    }
+    uses
+}
+
+/// Repeatedly rewrite the first instruction of `function` matching any of `rules`, until none
+/// match, returning whether any were rewritten.
+///
+/// The rules are tried in order.  Each rewrite must make progress, e.g. by leaving fewer
+/// instructions, or this will never finish.
+pub fn apply_rules(context: &mut Context, function: Function, rules: &[Rule]) -> bool {
+    let mut modified = false;
+    loop {
+        let uses = count_uses(context, function);
+        let candidate = function.instruction_iter(context).find_map(|(block, ins)| {
+            rules.iter().find_map(|rule| {
+                rule.pattern
+                    .matches(context, &uses, ins)
+                    .map(|captures| (block, ins, rule, captures))
+            })
+        });
+        let (block, ins, rule, captures) = match candidate {
+            Some(candidate) => candidate,
+            None => break,
+        };
+        if !context.consume_fuel() {
+            break;
+        }
+
+        let ty = ins
+            .get_type(context)
+            .expect("matched instructions are typed");
+        let mut inserter = PatternInserter::before(context, block, ins);
+        let replacement = rule
+            .replacement
+            .build(context, &captures, ty, &mut inserter);
+
+        function.replace_value(context, ins, replacement, None);
+        block.remove_instruction(context, ins);
+        modified = true;
+    }
+    modified
+}
+
+/// Parse the rules in `source`, in the order they're written.
+///
+/// Each rule is written as `(rule <name> <pattern> <replacement>)`, where the pattern and
+/// replacement use the s-expression syntax of the [`pattern!`](crate::pattern!) macro, and
+/// comments run from a `;` to the end of the line.  Every rule is validated by
+/// [`Rule::try_new`].
+///
+/// ```
+/// let rules = sway_ir::parse_rules(
+///     "; Multiplication by two can't overflow any more than the addition.
+///      (rule mul_two (mul x 2) (add x x))",
+/// )
+/// .unwrap();
+/// assert_eq!(rules[0], sway_ir::rule!(mul_two: (mul x 2) => (add x x)));
+/// ```
+pub fn parse_rules(source: &str) -> Result<Vec<Rule>, IrError> {
+    read_sexps(source)?
+        .iter()
+        .map(|sexp| match sexp {
+            Sexp::List(items, line) => match items.as_slice() {
+                [Sexp::Atom(keyword, _), Sexp::Atom(name, _), pattern, replacement]
+                    if keyword == "rule" =>
+                {
+                    Rule::try_new(
+                        name,
+                        sexp_to_pattern(pattern)?,
+                        sexp_to_pattern(replacement)?,
+                    )
+                }
+                _ => Err(sexp_error(
+                    *line,
+                    "expected `(rule <name> <pattern> <replacement>)`",
+                )),
+            },
+            Sexp::Atom(atom, line) => Err(sexp_error(*line, &format!("unexpected '{atom}'"))),
+        })
+        .collect()
+}
+
+/// An s-expression, with the line it starts on.
+enum Sexp {
+    Atom(String, usize),
+    List(Vec<Sexp>, usize),
+}
+
+fn sexp_error(line: usize, msg: &str) -> IrError {
+    IrError::InvalidRewriteRule(format!("line {line}: {msg}"))
+}
+
+fn read_sexps(source: &str) -> Result<Vec<Sexp>, IrError> {
+    // The lists being read, innermost last, with the lines they start on.
+    let mut open: Vec<(Vec<Sexp>, usize)> = vec![(Vec::new(), 1)];
+    let mut atom = String::new();
+    let mut chars = source.chars().peekable();
+    let mut line = 1;
+    while let Some(c) = chars.next() {
+        if !atom.is_empty() && (c.is_whitespace() || matches!(c, '(' | ')' | ';')) {
+            let sexp = Sexp::Atom(std::mem::take(&mut atom), line);
+            open.last_mut().unwrap().0.push(sexp);
+        }
+        match c {
+            '\n' => line += 1,
+            ';' => while chars.next_if(|c| *c != '\n').is_some() {},
+            '(' => open.push((Vec::new(), line)),
+            ')' => {
+                if open.len() == 1 {
+                    return Err(sexp_error(line, "unexpected ')'"));
+                }
+                let (items, start) = open.pop().unwrap();
+                open.last_mut().unwrap().0.push(Sexp::List(items, start));
+            }
+            c if c.is_whitespace() => (),
+            c => atom.push(c),
+        }
+    }
+    if !atom.is_empty() {
+        open.last_mut().unwrap().0.push(Sexp::Atom(atom, line));
+    }
+    match open.pop() {
+        Some((sexps, _)) if open.is_empty() => Ok(sexps),
+        Some((_, start)) => Err(sexp_error(start, "unclosed '('")),
+        None => unreachable!("the top level is never closed"),
+    }
+}
+
+fn sexp_to_pattern(sexp: &Sexp) -> Result<Pattern, IrError> {
+    match sexp {
+        Sexp::Atom(atom, line) => {
+            if let Ok(n) = atom.parse::<u64>() {
+                Ok(Pattern::Uint(n))
+            } else if let Ok(b) = atom.parse::<bool>() {
+                Ok(Pattern::Bool(b))
+            } else if is_name(atom) {
+                Ok(Pattern::Value(atom.clone()))
+            } else {
+                Err(sexp_error(*line, &format!("invalid name '{atom}'")))
+            }
+        }
+        Sexp::List(items, line) => match items.as_slice() {
+            [Sexp::Atom(keyword, _), Sexp::Atom(name, _)]
+                if keyword == "const" && is_name(name) =>
+            {
+                Ok(Pattern::Constant(name.clone()))
+            }
+            [Sexp::Atom(keyword, _), rest @ ..] if keyword == "one_use" => {
+                op_to_pattern(rest, *line).map(Pattern::one_use)
+            }
+            items => op_to_pattern(items, *line),
+        },
+    }
+}
+
+fn op_to_pattern(items: &[Sexp], line: usize) -> Result<Pattern, IrError> {
+    match items {
+        [Sexp::Atom(op_name, _), lhs, rhs] => match PatternOp::from_name(op_name) {
+            Some(op) => Ok(Pattern::op(
+                op,
+                sexp_to_pattern(lhs)?,
+                sexp_to_pattern(rhs)?,
+            )),
+            None => Err(sexp_error(line, &format!("unknown operation '{op_name}'"))),
+        },
+        _ => Err(sexp_error(
+            line,
+            "expected `(const <name>)` or an operation with two operands",
+        )),
+    }
+}
+
+fn is_name(atom: &str) -> bool {
+    let mut chars = atom.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Build a [`Pattern`] from its s-expression syntax.  See the [pattern module](crate::pattern)
+/// for details.
+#[macro_export]
+macro_rules! pattern {
+    (true) => {
+        $crate::Pattern::Bool(true)
+    };
+    (false) => {
+        $crate::Pattern::Bool(false)
+    };
+    ($name:ident) => {
+        $crate::Pattern::Value(stringify!($name).to_string())
+    };
+    ($n:literal) => {
+        $crate::Pattern::Uint($n)
+    };
+    ((const $name:ident)) => {
+        $crate::Pattern::Constant(stringify!($name).to_string())
+    };
+    ((one_use $($op:tt)+)) => {
+        $crate::pattern!(($($op)+)).one_use()
+    };
+    (($op:ident $lhs:tt $rhs:tt)) => {
+        $crate::Pattern::op(
+            $crate::pattern!(@op $op),
+            $crate::pattern!($lhs),
+            $crate::pattern!($rhs),
+        )
+    };
+    (@op add) => { $crate::PatternOp::Binary($crate::BinaryOpKind::Add) };
+    (@op sub) => { $crate::PatternOp::Binary($crate::BinaryOpKind::Sub) };
+    (@op mul) => { $crate::PatternOp::Binary($crate::BinaryOpKind::Mul) };
+    (@op div) => { $crate::PatternOp::Binary($crate::BinaryOpKind::Div) };
+    (@op and) => { $crate::PatternOp::Binary($crate::BinaryOpKind::And) };
+    (@op or) => { $crate::PatternOp::Binary($crate::BinaryOpKind::Or) };
+    (@op xor) => { $crate::PatternOp::Binary($crate::BinaryOpKind::Xor) };
+    (@op eq) => { $crate::PatternOp::Cmp($crate::Predicate::Equal) };
+    (@op lt) => { $crate::PatternOp::Cmp($crate::Predicate::LessThan) };
+    (@op gt) => { $crate::PatternOp::Cmp($crate::Predicate::GreaterThan) };
+}
+
+/// Build a [`Rule`] from the s-expression syntax of its pattern and replacement, as in
+/// `rule!(add_zero: (add x 0) => x)`.
+#[macro_export]
+macro_rules! rule {
+    ($name:ident: $pattern:tt => $replacement:tt) => {
+        $crate::Rule::new(
+            stringify!($name),
+            $crate::pattern!($pattern),
+            $crate::pattern!($replacement),
+        )
+    };
+}
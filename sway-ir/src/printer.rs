@@ -4,7 +4,7 @@
 //! crates were assessed but didn't seem to work as well as this simple version, which is quite
 //! effective.
 
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use crate::{
     asm::*,
@@ -13,7 +13,7 @@ use crate::{
     context::Context,
     function::{Function, FunctionContent},
     instruction::{FuelVmInstruction, Instruction, Predicate, Register},
-    metadata::{MetadataIndex, Metadatum},
+    metadata::{MetadataIndex, Metadatum, SPAN_TAG},
     module::{Kind, ModuleContent},
     value::{Value, ValueContent, ValueDatum},
     AnalysisResult, AnalysisResultT, AnalysisResults, BinaryOpKind, BlockArgument, IrError, Module,
@@ -84,21 +84,191 @@ impl Doc {
     }
 }
 
+/// Options for [`to_string_with_options`] which choose what is printed.
+#[derive(Clone, Debug)]
+pub struct PrinterOptions {
+    /// Print the metadata of functions, arguments and values.
+    pub metadata: bool,
+    /// Print the `span` metadata.  Has no effect unless `metadata` is set.
+    pub spans: bool,
+    /// Print only the functions with these names.  The output may then refer to functions which
+    /// are not printed.
+    pub functions: Option<Vec<String>>,
+    /// Print the functions of each module sorted by name rather than in the order they were
+    /// added, so that passes which add or remove functions don't move the others in a diff.
+    pub stable_order: bool,
+    /// Follow each named instruction with a comment giving the type of its value.
+    pub annotate_types: bool,
+}
+
+impl Default for PrinterOptions {
+    fn default() -> Self {
+        PrinterOptions {
+            metadata: true,
+            spans: true,
+            functions: None,
+            stable_order: false,
+            annotate_types: false,
+        }
+    }
+}
+
 /// Pretty-print a whole [`Context`] to a string.
 ///
 /// The ouput from this function must always be suitable for [`crate::parser::parse`].
 pub fn to_string(context: &Context) -> String {
-    let mut md_namer = MetadataNamer::default();
+    to_string_with_options(context, &PrinterOptions::default())
+}
+
+/// Pretty-print a whole [`Context`] to a string, with `options` choosing what is printed.
+///
+/// The output is suitable for [`crate::parser::parse`] unless some functions were left out, and is
+/// the same as that of [`to_string`] with the default options.
+pub fn to_string_with_options(context: &Context, options: &PrinterOptions) -> String {
+    let mut md_namer = MetadataNamer::new(options);
     context
         .modules
         .iter()
         .fold(Doc::Empty, |doc, (_, module)| {
-            doc.append(module_to_doc(context, &mut md_namer, module))
+            doc.append(module_to_doc(context, &mut md_namer, module, options))
         })
         .append(md_namer.to_doc(context))
         .build()
 }
 
+/// Pretty-print a single [`Function`] to a string, followed by the metadata it refers to.
+///
+/// The output is the same for functions with the same content, regardless of their handles in the
+/// [`Context`], so it may be used to detect changes.  It is not suitable for
+/// [`crate::parser::parse`] since the enclosing module and its configurables are not included, but
+/// may be given to [`crate::parser::parse_function_into`].
+pub fn function_to_string(context: &Context, function: Function) -> String {
+    let mut md_namer = MetadataNamer::default();
+    function_to_doc(
+        context,
+        &mut md_namer,
+        &mut Namer::new(function, GlobalNamer::new()),
+        &context.functions[function.0],
+        &PrinterOptions::default(),
+    )
+    .append(md_namer.to_doc(context))
+    .build()
+}
+
+/// A [`Block`] pretty-printed one instruction at a time by [`function_to_blocks`].
+pub struct PrintedBlock {
+    pub block: Block,
+    /// The block label along with its arguments.
+    pub header: String,
+    /// Each instruction in the block along with its text, which may span several lines if it is
+    /// preceded by the constants it uses.
+    pub instructions: Vec<(Value, String)>,
+}
+
+/// Pretty-print each block of a [`Function`] separately, one instruction at a time.
+///
+/// Values are named exactly as they are by [`function_to_string`].  Metadata indices are included
+/// but the metadata they refer to is not.
+pub fn function_to_blocks(context: &Context, function: Function) -> Vec<PrintedBlock> {
+    function_to_blocks_and_names(context, function).0
+}
+
+/// Like [`function_to_blocks`] but also return the names given to the values of the function.
+pub(crate) fn function_to_blocks_and_names(
+    context: &Context,
+    function: Function,
+) -> (Vec<PrintedBlock>, HashMap<Value, String>) {
+    let mut md_namer = MetadataNamer::default();
+    let mut namer = Namer::new(function, GlobalNamer::new());
+    let blocks = function
+        .block_iter(context)
+        .map(|block| {
+            let header = Doc::text(block.get_label(context).to_owned())
+                .append(Doc::in_parens_comma_sep(
+                    block
+                        .arg_iter(context)
+                        .map(|arg_val| {
+                            block_arg_to_doc(context, &mut md_namer, &mut namer, arg_val)
+                        })
+                        .collect(),
+                ))
+                .append(Doc::text(":"))
+                .build();
+            let instructions = block
+                .instruction_iter(context)
+                .map(|ins| {
+                    let text = instruction_to_doc(context, &mut md_namer, &mut namer, &block, &ins)
+                        .build();
+                    (ins, text.trim_end().to_string())
+                })
+                .collect();
+            PrintedBlock {
+                block,
+                header,
+                instructions,
+            }
+        })
+        .collect();
+
+    let mut names = namer.global_namer.names;
+    names.extend(namer.names);
+    names.extend(
+        context.functions[function.0]
+            .arguments
+            .iter()
+            .map(|(name, arg)| (*arg, name.clone())),
+    );
+    (blocks, names)
+}
+
+/// Pretty-print just the configurables of a [`Module`] to a string, followed by the metadata they
+/// refer to.
+pub(crate) fn configurables_to_string(context: &Context, module: Module) -> String {
+    let mut md_namer = MetadataNamer::default();
+    let mut global_namer = GlobalNamer::for_module(context, &context.modules[module.0]);
+    Doc::List(
+        context.modules[module.0]
+            .global_configurable
+            .values()
+            .map(|value| config_to_doc(context, &mut md_namer, &mut global_namer, value))
+            .collect(),
+    )
+    .append(md_namer.to_doc(context))
+    .build()
+}
+
+/// Name the arguments, instructions and configurables of a [`Module`] exactly as [`to_string`]
+/// does.
+pub(crate) fn module_value_names(context: &Context, module: Module) -> HashMap<Value, String> {
+    let module = &context.modules[module.0];
+    let mut md_namer = MetadataNamer::default();
+    let mut global_namer = GlobalNamer::for_module(context, module);
+    for value in module.global_configurable.values() {
+        global_namer.name(context, value);
+    }
+
+    let mut names = global_namer.names.clone();
+    for function in &module.functions {
+        let function_content = &context.functions[function.0];
+        let mut namer = Namer::new(*function, global_namer.clone());
+        function_to_doc(
+            context,
+            &mut md_namer,
+            &mut namer,
+            function_content,
+            &PrinterOptions::default(),
+        );
+        names.extend(namer.names);
+        names.extend(
+            function_content
+                .arguments
+                .iter()
+                .map(|(name, arg)| (*arg, name.clone())),
+        );
+    }
+    names
+}
+
 pub struct ModulePrinterResult;
 impl AnalysisResultT for ModulePrinterResult {}
 
@@ -114,7 +284,8 @@ pub fn module_printer(
         module_to_doc(
             context,
             &mut md_namer,
-            context.modules.get(module.0).unwrap()
+            context.modules.get(module.0).unwrap(),
+            &PrinterOptions::default(),
         )
         .append(md_namer.to_doc(context))
         .build()
@@ -129,6 +300,8 @@ pub fn create_module_printer_pass() -> Pass {
         name: MODULEPRINTER_NAME,
         descr: "Print module to stdout",
         deps: vec![],
+        preserves: vec![],
+        prune_unreachable: false,
         runner: ScopedPass::ModulePass(PassMutability::Analysis(module_printer)),
     }
 }
@@ -137,8 +310,20 @@ fn module_to_doc<'a>(
     context: &'a Context,
     md_namer: &mut MetadataNamer,
     module: &'a ModuleContent,
+    options: &PrinterOptions,
 ) -> Doc {
-    let mut global_namer = GlobalNamer::new();
+    let mut global_namer = GlobalNamer::for_module(context, module);
+    let mut functions = module
+        .functions
+        .iter()
+        .filter(|function| match &options.functions {
+            Some(names) => names.iter().any(|name| name == function.get_name(context)),
+            None => true,
+        })
+        .collect::<Vec<_>>();
+    if options.stable_order {
+        functions.sort_by_key(|function| function.get_name(context));
+    }
     Doc::line(Doc::Text(format!(
         "{} {{",
         match module.kind {
@@ -166,15 +351,15 @@ fn module_to_doc<'a>(
     .append(Doc::indent(
         4,
         Doc::list_sep(
-            module
-                .functions
-                .iter()
+            functions
+                .into_iter()
                 .map(|function| {
                     function_to_doc(
                         context,
                         md_namer,
                         &mut Namer::new(*function, global_namer.clone()),
                         &context.functions[function.0],
+                        options,
                     )
                 })
                 .collect(),
@@ -189,55 +374,74 @@ fn function_to_doc<'a>(
     md_namer: &mut MetadataNamer,
     namer: &mut Namer,
     function: &'a FunctionContent,
+    options: &PrinterOptions,
 ) -> Doc {
+    let mut attributes = function.attributes.iter().collect::<Vec<_>>();
+    attributes.sort();
+    let attributes = if attributes.is_empty() {
+        String::new()
+    } else {
+        format!(
+            " [{}]",
+            attributes
+                .iter()
+                .map(|attribute| attribute.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    };
     let public = if function.is_public { "pub " } else { "" };
     let entry = if function.is_entry { "entry " } else { "" };
     Doc::line(
-        Doc::text(format!("{}{}fn {}", public, entry, function.name))
-            .append(
-                function
-                    .selector
-                    .map(|bytes| {
-                        Doc::text(format!(
-                            "<{:02x}{:02x}{:02x}{:02x}>",
-                            bytes[0], bytes[1], bytes[2], bytes[3]
-                        ))
-                    })
-                    .unwrap_or(Doc::Empty),
-            )
-            .append(Doc::in_parens_comma_sep(
-                function
-                    .arguments
-                    .iter()
-                    .map(|(name, arg_val)| {
-                        if let ValueContent {
-                            value: ValueDatum::Argument(BlockArgument { ty, by_ref, .. }),
-                            metadata,
-                            ..
-                        } = &context.values[arg_val.0]
-                        {
-                            if *by_ref {
-                                Doc::text("inout ")
-                            } else {
-                                Doc::Empty
-                            }
-                            .append(Doc::text(name))
-                            .append(
-                                Doc::Space.and(md_namer.md_idx_to_doc_no_comma(context, metadata)),
-                            )
-                            .append(Doc::text(format!(": {}", ty.as_string(context))))
+        Doc::text(format!(
+            "{}{}fn {}",
+            public,
+            entry,
+            function.name.as_str(context)
+        ))
+        .append(
+            function
+                .selector
+                .map(|bytes| {
+                    Doc::text(format!(
+                        "<{:02x}{:02x}{:02x}{:02x}>",
+                        bytes[0], bytes[1], bytes[2], bytes[3]
+                    ))
+                })
+                .unwrap_or(Doc::Empty),
+        )
+        .append(Doc::in_parens_comma_sep(
+            function
+                .arguments
+                .iter()
+                .map(|(name, arg_val)| {
+                    if let ValueContent {
+                        value: ValueDatum::Argument(BlockArgument { ty, by_ref, .. }),
+                        metadata,
+                        ..
+                    } = &context.values[arg_val.0]
+                    {
+                        if *by_ref {
+                            Doc::text("inout ")
                         } else {
-                            unreachable!("Unexpected non argument value for function arguments.")
+                            Doc::Empty
                         }
-                    })
-                    .collect(),
-            ))
-            .append(Doc::text(format!(
-                " -> {}",
-                function.return_type.as_string(context)
-            )))
-            .append(md_namer.md_idx_to_doc(context, &function.metadata))
-            .append(Doc::text(" {")),
+                        .append(Doc::text(name))
+                        .append(Doc::Space.and(md_namer.md_idx_to_doc_no_comma(context, metadata)))
+                        .append(Doc::text(format!(": {}", ty.as_string(context))))
+                    } else {
+                        unreachable!("Unexpected non argument value for function arguments.")
+                    }
+                })
+                .collect(),
+        ))
+        .append(Doc::text(format!(
+            " -> {}{}",
+            function.return_type.as_string(context),
+            attributes
+        )))
+        .append(md_namer.md_idx_to_doc(context, &function.metadata))
+        .append(Doc::text(" {")),
     )
     .append(Doc::indent(
         4,
@@ -270,7 +474,7 @@ fn function_to_doc<'a>(
                     function
                         .blocks
                         .iter()
-                        .map(|block| block_to_doc(context, md_namer, namer, block))
+                        .map(|block| block_to_doc(context, md_namer, namer, block, options))
                         .collect(),
                     Doc::line(Doc::Empty),
                 ),
@@ -286,19 +490,15 @@ fn block_to_doc(
     md_namer: &mut MetadataNamer,
     namer: &mut Namer,
     block: &Block,
+    options: &PrinterOptions,
 ) -> Doc {
     let block_content = &context.blocks[block.0];
     Doc::line(
-        Doc::text(block_content.label.to_string()).append(
+        Doc::text(block_content.label.as_str(context).to_owned()).append(
             Doc::in_parens_comma_sep(
                 block
                     .arg_iter(context)
-                    .map(|arg_val| {
-                        Doc::text(namer.name(context, arg_val)).append(Doc::text(format!(
-                            ": {}",
-                            arg_val.get_type(context).unwrap().as_string(context)
-                        )))
-                    })
+                    .map(|arg_val| block_arg_to_doc(context, md_namer, namer, arg_val))
                     .collect(),
             )
             .append(Doc::Text(":".to_string())),
@@ -308,11 +508,62 @@ fn block_to_doc(
         block_content
             .instructions
             .iter()
-            .map(|ins| instruction_to_doc(context, md_namer, namer, block, ins))
+            .map(|ins| {
+                let doc = instruction_to_doc(context, md_namer, namer, block, ins);
+                match ins.get_type(context) {
+                    Some(ty) if options.annotate_types && namer.is_known(ins) => {
+                        annotate_type(doc, &namer.name(context, ins), &ty.as_string(context))
+                    }
+                    _otherwise => doc,
+                }
+            })
             .collect(),
     ))
 }
 
+fn block_arg_to_doc(
+    context: &Context,
+    md_namer: &mut MetadataNamer,
+    namer: &mut Namer,
+    arg_val: &Value,
+) -> Doc {
+    if let ValueContent {
+        value: ValueDatum::Argument(BlockArgument { ty, by_ref, .. }),
+        metadata,
+        ..
+    } = &context.values[arg_val.0]
+    {
+        if *by_ref {
+            Doc::text("inout ")
+        } else {
+            Doc::Empty
+        }
+        .append(Doc::text(namer.name(context, arg_val)))
+        .append(Doc::Space.and(md_namer.md_idx_to_doc_no_comma(context, metadata)))
+        .append(Doc::text(format!(": {}", ty.as_string(context))))
+    } else {
+        unreachable!("Unexpected non argument value for block arguments.")
+    }
+}
+
+// Append a comment with the type to the line of an instruction, which is usually the last but not
+// for an `asm` block.  The doc is rebuilt from its lines so it may still be indented.
+fn annotate_type(doc: Doc, name: &str, ty: &str) -> Doc {
+    let assignment = format!("{name} = ");
+    Doc::List(
+        doc.build()
+            .lines()
+            .map(|line| {
+                if line.starts_with(&assignment) {
+                    Doc::text_line(format!("{line} // {ty}"))
+                } else {
+                    Doc::text_line(line)
+                }
+            })
+            .collect(),
+    )
+}
+
 fn config_to_doc(
     context: &Context,
     md_namer: &mut MetadataNamer,
@@ -322,6 +573,7 @@ fn config_to_doc(
     if let ValueContent {
         value: ValueDatum::Configurable(configurable),
         metadata,
+        ..
     } = &context.values[const_val.0]
     {
         Doc::line(
@@ -346,6 +598,7 @@ fn constant_to_doc(
     if let ValueContent {
         value: ValueDatum::Constant(constant),
         metadata,
+        ..
     } = &context.values[const_val.0]
     {
         Doc::line(
@@ -385,6 +638,7 @@ fn instruction_to_doc<'a>(
     if let ValueContent {
         value: ValueDatum::Instruction(instruction),
         metadata,
+        ..
     } = &context.values[ins_value.0]
     {
         match instruction {
@@ -443,17 +697,16 @@ fn instruction_to_doc<'a>(
                         doc.append(maybe_constant_to_doc(context, md_namer, namer, param))
                     })
                     .append(Doc::line(
-                        Doc::text(format!("br {}", context.blocks[to_block.block.0].label,))
-                            .append(
-                                Doc::in_parens_comma_sep(
-                                    to_block
-                                        .args
-                                        .iter()
-                                        .map(|arg_val| Doc::text(namer.name(context, arg_val)))
-                                        .collect(),
-                                )
-                                .append(md_namer.md_idx_to_doc(context, metadata)),
-                            ),
+                        Doc::text(format!("br {}", to_block.block.get_label(context),)).append(
+                            Doc::in_parens_comma_sep(
+                                to_block
+                                    .args
+                                    .iter()
+                                    .map(|arg_val| Doc::text(namer.name(context, arg_val)))
+                                    .collect(),
+                            )
+                            .append(md_namer.md_idx_to_doc(context, metadata)),
+                        ),
                     ))
             }
             Instruction::Call(func, args) => args
@@ -465,7 +718,7 @@ fn instruction_to_doc<'a>(
                     Doc::text(format!(
                         "{} = call {}",
                         namer.name(context, ins_value),
-                        context.functions[func.0].name
+                        func.get_name(context)
                     ))
                     .append(Doc::in_parens_comma_sep(
                         args.iter()
@@ -506,8 +759,8 @@ fn instruction_to_doc<'a>(
                 true_block,
                 false_block,
             } => {
-                let true_label = &context.blocks[true_block.block.0].label;
-                let false_label = &context.blocks[false_block.block.0].label;
+                let true_label = true_block.block.get_label(context);
+                let false_label = false_block.block.get_label(context);
                 // Handle possibly constant block parameters
                 let doc = true_block.args.iter().fold(
                     maybe_constant_to_doc(context, md_namer, namer, cond_value),
@@ -600,6 +853,12 @@ fn instruction_to_doc<'a>(
                 .append(md_namer.md_idx_to_doc(context, metadata)),
             )),
             Instruction::FuelVm(fuel_vm_instr) => match fuel_vm_instr {
+                FuelVmInstruction::GasCheckpoint(id) => {
+                    maybe_constant_to_doc(context, md_namer, namer, id).append(Doc::line(
+                        Doc::text(format!("gas_checkpoint {}", namer.name(context, id)))
+                            .append(md_namer.md_idx_to_doc(context, metadata)),
+                    ))
+                }
                 FuelVmInstruction::GetStorageKey => Doc::line(
                     Doc::text(format!(
                         "{} = get_storage_key",
@@ -1018,6 +1277,44 @@ impl GlobalNamer {
         }
     }
 
+    /// Name the configurables of `module` by their call paths, unless a path couldn't be parsed
+    /// back as a name or would be mistaken for an argument or another value.
+    fn for_module(context: &Context, module: &ModuleContent) -> Self {
+        let arg_names = module
+            .functions
+            .iter()
+            .flat_map(|function| &context.functions[function.0].arguments)
+            .map(|(name, _)| name.as_str())
+            .collect::<HashSet<_>>();
+        let is_segment = |segment: &String| {
+            segment
+                .chars()
+                .next()
+                .map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_')
+                && !["unit", "bool", "u64", "b256", "string"].contains(&segment.as_str())
+        };
+        let is_value_name = |name: &str| {
+            name.strip_prefix('v').map_or(false, |idx| {
+                !idx.is_empty() && idx.chars().all(|c| c.is_ascii_digit())
+            })
+        };
+
+        let mut namer = GlobalNamer::new();
+        for (call_path, value) in &module.global_configurable {
+            let name = call_path.join("::");
+            if call_path.iter().all(is_segment)
+                && !is_value_name(&name)
+                && !arg_names.contains(name.as_str())
+            {
+                namer.names.insert(*value, name);
+            }
+        }
+        namer
+    }
+
     fn name(&mut self, context: &Context, value: &Value) -> String {
         match &context.values[value.0].value {
             ValueDatum::Configurable(_) => self.default_configurable_name(value),
@@ -1027,8 +1324,14 @@ impl GlobalNamer {
 
     fn default_configurable_name(&mut self, value: &Value) -> String {
         self.names.get(value).cloned().unwrap_or_else(|| {
-            let new_name = format!("c{}", self.next_configurable_idx);
-            self.next_configurable_idx += 1;
+            // Skip any names already taken from call paths.
+            let new_name = loop {
+                let new_name = format!("c{}", self.next_configurable_idx);
+                self.next_configurable_idx += 1;
+                if !self.names.values().any(|name| *name == new_name) {
+                    break new_name;
+                }
+            };
             self.names.insert(*value, new_name.clone());
             new_name
         })
@@ -1082,13 +1385,33 @@ impl Namer {
     }
 }
 
-#[derive(Default)]
 struct MetadataNamer {
     md_map: BTreeMap<MetadataIndex, u64>,
+    // Lists which were filtered by the options, and so aren't in the context.
+    list_map: BTreeMap<Vec<MetadataIndex>, u64>,
     next_md_idx: u64,
+
+    show_metadata: bool,
+    show_spans: bool,
+}
+
+impl Default for MetadataNamer {
+    fn default() -> Self {
+        MetadataNamer::new(&PrinterOptions::default())
+    }
 }
 
 impl MetadataNamer {
+    fn new(options: &PrinterOptions) -> Self {
+        MetadataNamer {
+            md_map: BTreeMap::new(),
+            list_map: BTreeMap::new(),
+            next_md_idx: 0,
+            show_metadata: options.metadata,
+            show_spans: options.spans,
+        }
+    }
+
     fn values_sorted(&self) -> impl Iterator<Item = (u64, MetadataIndex)> {
         let mut items = self
             .md_map
@@ -1114,7 +1437,8 @@ impl MetadataNamer {
     // elements, struct members, etc. It's done in `add_md_idx()` below.
     fn md_idx_to_doc_no_comma(&mut self, context: &Context, md_idx: &Option<MetadataIndex>) -> Doc {
         md_idx
-            .map(|md_idx| Doc::text(format!("!{}", self.add_md_idx(context, &md_idx))))
+            .and_then(|md_idx| self.add_shown_md_idx(context, &md_idx))
+            .map(|idx| Doc::text(format!("!{idx}")))
             .unwrap_or(Doc::Empty)
     }
 
@@ -1122,6 +1446,50 @@ impl MetadataNamer {
         Doc::Comma.and(self.md_idx_to_doc_no_comma(context, md_idx))
     }
 
+    // Like `add_md_idx()` but leaving out the metadata hidden by the options, if any remain.
+    fn add_shown_md_idx(&mut self, context: &Context, md_idx: &MetadataIndex) -> Option<u64> {
+        if !self.show_metadata {
+            return None;
+        }
+        if self.show_spans {
+            return Some(self.add_md_idx(context, md_idx));
+        }
+
+        let is_span = |md_idx: &MetadataIndex| match &context.metadata[md_idx.0] {
+            Metadatum::Struct(tag, _) => tag == SPAN_TAG,
+            _otherwise => false,
+        };
+        match &context.metadata[md_idx.0] {
+            Metadatum::List(idcs) => {
+                let shown = idcs
+                    .iter()
+                    .filter(|idx| !is_span(idx))
+                    .copied()
+                    .collect::<Vec<_>>();
+                match shown.len() {
+                    0 => None,
+                    1 => Some(self.add_md_idx(context, &shown[0])),
+                    len if len == idcs.len() => Some(self.add_md_idx(context, md_idx)),
+                    _ => Some(self.add_md_list(context, shown)),
+                }
+            }
+            _ if is_span(md_idx) => None,
+            _ => Some(self.add_md_idx(context, md_idx)),
+        }
+    }
+
+    fn add_md_list(&mut self, context: &Context, idcs: Vec<MetadataIndex>) -> u64 {
+        self.list_map.get(&idcs).copied().unwrap_or_else(|| {
+            for idx in &idcs {
+                self.add_md_idx(context, idx);
+            }
+            let new_idx = self.next_md_idx;
+            self.next_md_idx += 1;
+            self.list_map.insert(idcs, new_idx);
+            new_idx
+        })
+    }
+
     fn add_md_idx(&mut self, context: &Context, md_idx: &MetadataIndex) -> u64 {
         self.md_map.get(md_idx).copied().unwrap_or_else(|| {
             // Recurse for all sub-metadata here first to be sure they can be referenced later.
@@ -1191,14 +1559,17 @@ impl MetadataNamer {
             }
         }
 
-        let md_lines = self
+        let mut mds = self
             .values_sorted()
-            .map(|(ref_idx, md_idx)| {
-                Doc::text_line(format!(
-                    "!{ref_idx} = {}",
-                    md_to_string(self, &context.metadata[md_idx.0])
-                ))
-            })
+            .map(|(ref_idx, md_idx)| (ref_idx, md_to_string(self, &context.metadata[md_idx.0])))
+            .chain(self.list_map.iter().map(|(idcs, ref_idx)| {
+                (*ref_idx, md_to_string(self, &Metadatum::List(idcs.clone())))
+            }))
+            .collect::<Vec<_>>();
+        mds.sort_unstable();
+        let md_lines = mds
+            .into_iter()
+            .map(|(ref_idx, md)| Doc::text_line(format!("!{ref_idx} = {md}")))
             .collect::<Vec<_>>();
 
         // We want to add an empty line only when there are metadata.
@@ -0,0 +1,437 @@
+//! A superoptimizer for short sequences of arithmetic.
+//!
+//! For each value computed by the arithmetic of a block, [`superoptimize`] enumerates the
+//! expressions over the same operands and a few small constants, cheapest first, looking for one
+//! which is cheaper according to the VM cost model.  Expressions are compared by [evaluating](
+//! evaluate) them on sample inputs, including whether they panic, and only the first expression
+//! with each distinct set of results is kept to be combined further.  A candidate which agrees
+//! with the original is checked again on many more inputs before it's reported as a [`Rule`].
+//!
+//! Since the inputs are only sampled, the rules found are candidates to be reviewed before being
+//! added to `instcombine`.  The `superopt` tool writes them in the format read by
+//! [`parse_rules`](crate::parse_rules), so they can also be tried out with the `rewrite` pass.
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    arithmetic_roots, block_arithmetic, count_uses, instcombine_rules, pattern::PatternKind,
+    pattern_op_cost, BinaryOpKind, Constant, ConstantValue, Context, Function, Pattern, PatternOp,
+    Predicate, Rule, Value,
+};
+
+/// Limits on the search made by [`superoptimize`].
+#[derive(Clone, Debug)]
+pub struct SuperoptConfig {
+    /// The greatest cost of the expressions enumerated.
+    pub max_cost: u64,
+    /// Values computed from more distinct operands than this are skipped.
+    pub max_operands: usize,
+    /// The number of inputs on which expressions are compared while enumerating.
+    pub samples: usize,
+    /// The number of further random inputs on which a candidate is checked.
+    pub verify_samples: usize,
+    /// The seed for the random inputs.
+    pub seed: u64,
+}
+
+impl Default for SuperoptConfig {
+    fn default() -> Self {
+        SuperoptConfig {
+            max_cost: 3,
+            max_operands: 3,
+            samples: 64,
+            verify_samples: 10_000,
+            seed: 0x5eed,
+        }
+    }
+}
+
+/// A cheaper equivalent of a value, as a rule rewriting its expression.
+#[derive(Clone, Debug)]
+pub struct SuperoptCandidate {
+    pub value: Value,
+    pub original_cost: u64,
+    pub cost: u64,
+    pub rule: Rule,
+}
+
+/// The result of evaluating an expression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Eval {
+    Uint(u64),
+    Bool(bool),
+}
+
+/// Evaluate `pattern` with the values of its names in `env`, returning `None` if it panics, e.g.
+/// on overflow.  Integers are 64 bits wide.
+pub fn evaluate(pattern: &Pattern, env: &FxHashMap<String, Eval>) -> Option<Eval> {
+    match pattern {
+        Pattern::Value(name) | Pattern::Constant(name) => env.get(name).copied(),
+        Pattern::Uint(n) => Some(Eval::Uint(*n)),
+        Pattern::Bool(b) => Some(Eval::Bool(*b)),
+        Pattern::Op { op, lhs, rhs, .. } => apply(op, evaluate(lhs, env)?, evaluate(rhs, env)?),
+    }
+}
+
+fn apply(op: &PatternOp, lhs: Eval, rhs: Eval) -> Option<Eval> {
+    match (lhs, rhs) {
+        (Eval::Uint(lhs), Eval::Uint(rhs)) => match op.fold(64, lhs, rhs)? {
+            ConstantValue::Uint(n) => Some(Eval::Uint(n)),
+            ConstantValue::Bool(b) => Some(Eval::Bool(b)),
+            _ => None,
+        },
+        (Eval::Bool(lhs), Eval::Bool(rhs)) if *op == PatternOp::Cmp(Predicate::Equal) => {
+            Some(Eval::Bool(lhs == rhs))
+        }
+        _ => None,
+    }
+}
+
+/// Return the cost of the instructions in `pattern`, counting shared operands once per use.
+pub fn pattern_cost(pattern: &Pattern) -> u64 {
+    match pattern {
+        Pattern::Op { op, lhs, rhs, .. } => {
+            pattern_op_cost(op) + pattern_cost(lhs) + pattern_cost(rhs)
+        }
+        _ => 0,
+    }
+}
+
+/// Search for a cheaper equivalent of each value computed by the arithmetic of a block of
+/// `function` for use elsewhere.  Values which `instcombine` already simplifies are skipped.
+pub fn superoptimize(
+    context: &Context,
+    function: Function,
+    config: &SuperoptConfig,
+) -> Vec<SuperoptCandidate> {
+    let instcombine = instcombine_rules();
+    let uses = count_uses(context, function);
+    let mut rng = Rng::new(config.seed);
+    let mut candidates = Vec::new();
+    for block in function.block_iter(context) {
+        let arithmetic = block_arithmetic(context, block);
+        let roots = arithmetic_roots(context, function, block, &arithmetic);
+        for root in block.instruction_iter(context) {
+            if !roots.contains(&root)
+                || instcombine
+                    .iter()
+                    .any(|rule| rule.pattern.matches(context, &uses, root).is_some())
+            {
+                continue;
+            }
+            let mut operands = Vec::new();
+            let target = match expression(context, function, &arithmetic, root, &mut operands) {
+                Some(target) if operands.len() <= config.max_operands => target,
+                _ => continue,
+            };
+            let operands = operands
+                .into_iter()
+                .map(|(name, value)| match value.get_type(context) {
+                    Some(ty) if ty.is_bool(context) => (name, PatternKind::Bool),
+                    _ => (name, PatternKind::Int),
+                })
+                .collect::<Vec<_>>();
+
+            let original_cost = pattern_cost(&target);
+            if let Some(replacement) = search(&target, &operands, config, &mut rng) {
+                let cost = pattern_cost(&replacement);
+                let name = format!("{}_{}", function.get_name(context), candidates.len());
+                if let Ok(rule) = Rule::try_new(&name, target, replacement) {
+                    candidates.push(SuperoptCandidate {
+                        value: root,
+                        original_cost,
+                        cost,
+                        rule,
+                    });
+                }
+            }
+        }
+    }
+    candidates
+}
+
+/// Return the expression computing `value` from the `arithmetic` of its block.  Its other
+/// operands are named after the arguments of `function`, or otherwise `x0`, `x1`, etc., and are
+/// added to `operands`.  Only 64-bit integers and bools are supported.
+fn expression(
+    context: &Context,
+    function: Function,
+    arithmetic: &FxHashMap<Value, (PatternOp, Value, Value)>,
+    value: Value,
+    operands: &mut Vec<(String, Value)>,
+) -> Option<Pattern> {
+    if let Some((op, arg1, arg2)) = arithmetic.get(&value) {
+        let lhs = expression(context, function, arithmetic, *arg1, operands)?;
+        let rhs = expression(context, function, arithmetic, *arg2, operands)?;
+        return Some(Pattern::op(*op, lhs, rhs));
+    }
+    match value.get_constant(context) {
+        Some(Constant {
+            ty,
+            value: ConstantValue::Uint(n),
+        }) if ty.is_uint64(context) => return Some(Pattern::Uint(*n)),
+        Some(Constant {
+            value: ConstantValue::Bool(b),
+            ..
+        }) => return Some(Pattern::Bool(*b)),
+        Some(_) => return None,
+        None => (),
+    }
+    if !matches!(value.get_type(context), Some(ty) if ty.is_uint64(context) || ty.is_bool(context))
+    {
+        return None;
+    }
+
+    let name = match operands.iter().find(|(_, operand)| *operand == value) {
+        Some((name, _)) => name.clone(),
+        None => {
+            let name = function
+                .lookup_arg_name(context, &value)
+                .cloned()
+                .unwrap_or_else(|| format!("x{}", operands.len()));
+            operands.push((name.clone(), value));
+            name
+        }
+    };
+    Some(Pattern::Value(name))
+}
+
+/// The most expressions enumerated for a single value.
+const MAX_EXPRESSIONS: usize = 200_000;
+
+/// Integers at which arithmetic tends to behave differently.
+const EDGE_VALUES: [u64; 8] = [0, 1, 2, 3, 1 << 32, 1 << 63, u64::MAX - 1, u64::MAX];
+
+/// An enumerated expression with its results on the sample inputs.
+struct Enumerated {
+    pattern: Pattern,
+    kind: PatternKind,
+    results: Vec<Option<Eval>>,
+}
+
+/// Return the cheapest expression over `operands` and small constants which is cheaper than
+/// `target` and agrees with it, if there is one within the limits of `config`.
+fn search(
+    target: &Pattern,
+    operands: &[(String, PatternKind)],
+    config: &SuperoptConfig,
+    rng: &mut Rng,
+) -> Option<Pattern> {
+    let samples = sample_inputs(operands, config.samples, rng);
+    let results_of = |pattern: &Pattern| {
+        samples
+            .iter()
+            .map(|env| evaluate(pattern, env))
+            .collect::<Vec<_>>()
+    };
+    let target_results = results_of(target);
+    if target_results.iter().all(Option::is_none) {
+        return None;
+    }
+    let mut verify = |candidate: &Pattern| {
+        let mut inputs = sample_inputs(operands, config.verify_samples, rng);
+        inputs.extend(edge_inputs(operands));
+        inputs
+            .iter()
+            .all(|env| evaluate(candidate, env) == evaluate(target, env))
+    };
+
+    // The expressions of each cost, with the operands and constants costing nothing.
+    let mut constants = vec![Pattern::Uint(0), Pattern::Uint(1), Pattern::Uint(2)];
+    collect_literals(target, &mut constants);
+    let mut leaves = operands
+        .iter()
+        .map(|(name, kind)| (Pattern::Value(name.clone()), *kind))
+        .collect::<Vec<_>>();
+    leaves.extend(
+        constants
+            .into_iter()
+            .map(|pattern| (pattern, PatternKind::Int)),
+    );
+    leaves.extend([
+        (Pattern::Bool(false), PatternKind::Bool),
+        (Pattern::Bool(true), PatternKind::Bool),
+    ]);
+
+    let mut seen = FxHashSet::default();
+    let mut by_cost: Vec<Vec<Enumerated>> = vec![Vec::new()];
+    for (pattern, kind) in leaves {
+        let results = results_of(&pattern);
+        if results == target_results {
+            if verify(&pattern) {
+                return Some(pattern);
+            }
+        } else if seen.insert(results.clone()) {
+            by_cost[0].push(Enumerated {
+                pattern,
+                kind,
+                results,
+            });
+        }
+    }
+
+    let max_cost = config.max_cost.min(pattern_cost(target).saturating_sub(1));
+    let mut count = 0;
+    for cost in 1..=max_cost {
+        let mut enumerated = Vec::new();
+        for op in all_ops() {
+            let op_cost = pattern_op_cost(&op);
+            if op_cost > cost {
+                continue;
+            }
+            for lhs_cost in 0..=(cost - op_cost) {
+                let rhs_cost = cost - op_cost - lhs_cost;
+                if op.is_commutative() && lhs_cost > rhs_cost {
+                    continue;
+                }
+                for (lhs_idx, lhs) in by_cost[lhs_cost as usize].iter().enumerate() {
+                    for (rhs_idx, rhs) in by_cost[rhs_cost as usize].iter().enumerate() {
+                        if op.is_commutative() && lhs_cost == rhs_cost && rhs_idx < lhs_idx {
+                            continue;
+                        }
+                        let kind = match (op, lhs.kind, rhs.kind) {
+                            (PatternOp::Binary(_), PatternKind::Int, PatternKind::Int) => {
+                                PatternKind::Int
+                            }
+                            (PatternOp::Cmp(Predicate::Equal), lhs_kind, rhs_kind)
+                                if lhs_kind == rhs_kind =>
+                            {
+                                PatternKind::Bool
+                            }
+                            (PatternOp::Cmp(_), PatternKind::Int, PatternKind::Int) => {
+                                PatternKind::Bool
+                            }
+                            _ => continue,
+                        };
+                        let results = lhs
+                            .results
+                            .iter()
+                            .zip(&rhs.results)
+                            .map(|(lhs, rhs)| apply(&op, (*lhs)?, (*rhs)?))
+                            .collect::<Vec<_>>();
+                        let pattern = Pattern::op(op, lhs.pattern.clone(), rhs.pattern.clone());
+                        if results == target_results {
+                            if verify(&pattern) {
+                                return Some(pattern);
+                            }
+                        } else if seen.insert(results.clone()) {
+                            enumerated.push(Enumerated {
+                                pattern,
+                                kind,
+                                results,
+                            });
+                        }
+                        count += 1;
+                        if count > MAX_EXPRESSIONS {
+                            return None;
+                        }
+                    }
+                }
+            }
+        }
+        by_cost.push(enumerated);
+    }
+    None
+}
+
+fn all_ops() -> impl Iterator<Item = PatternOp> {
+    [
+        BinaryOpKind::Add,
+        BinaryOpKind::Sub,
+        BinaryOpKind::Mul,
+        BinaryOpKind::Div,
+        BinaryOpKind::And,
+        BinaryOpKind::Or,
+        BinaryOpKind::Xor,
+    ]
+    .into_iter()
+    .map(PatternOp::Binary)
+    .chain(
+        [
+            Predicate::Equal,
+            Predicate::LessThan,
+            Predicate::GreaterThan,
+        ]
+        .into_iter()
+        .map(PatternOp::Cmp),
+    )
+}
+
+/// Add the integer literals of `pattern` to `literals`, if they aren't there already.
+fn collect_literals(pattern: &Pattern, literals: &mut Vec<Pattern>) {
+    match pattern {
+        Pattern::Uint(_) if !literals.contains(pattern) => literals.push(pattern.clone()),
+        Pattern::Op { lhs, rhs, .. } => {
+            collect_literals(lhs, literals);
+            collect_literals(rhs, literals);
+        }
+        _ => (),
+    }
+}
+
+/// Return `count` random inputs for `operands`, mixing edge cases, small numbers and numbers of
+/// every size.
+fn sample_inputs(
+    operands: &[(String, PatternKind)],
+    count: usize,
+    rng: &mut Rng,
+) -> Vec<FxHashMap<String, Eval>> {
+    (0..count)
+        .map(|_| {
+            operands
+                .iter()
+                .map(|(name, kind)| {
+                    let value = match kind {
+                        PatternKind::Bool => Eval::Bool(rng.next() & 1 == 1),
+                        PatternKind::Int => Eval::Uint(match rng.next() % 4 {
+                            0 => EDGE_VALUES[(rng.next() % EDGE_VALUES.len() as u64) as usize],
+                            1 => rng.next() % 16,
+                            2 => rng.next() >> 32,
+                            _ => rng.next(),
+                        }),
+                    };
+                    (name.clone(), value)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Return every combination of edge cases for `operands`.
+fn edge_inputs(operands: &[(String, PatternKind)]) -> Vec<FxHashMap<String, Eval>> {
+    operands
+        .iter()
+        .fold(vec![FxHashMap::default()], |inputs, (name, kind)| {
+            let values = match kind {
+                PatternKind::Bool => vec![Eval::Bool(false), Eval::Bool(true)],
+                PatternKind::Int => EDGE_VALUES.iter().map(|n| Eval::Uint(*n)).collect(),
+            };
+            inputs
+                .iter()
+                .flat_map(|input| {
+                    values.iter().map(move |value| {
+                        let mut input = input.clone();
+                        input.insert(name.clone(), *value);
+                        input
+                    })
+                })
+                .collect()
+        })
+}
+
+/// A xorshift generator, which is plenty for sampling inputs.
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Rng {
+        // The state must never be zero.
+        Rng(seed | 1)
+    }
+
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
@@ -0,0 +1,74 @@
+//! Interned strings for the names of functions and the labels of blocks.
+//!
+//! Each distinct string is stored once in the [`Context`] and referred to by a [`Symbol`], which
+//! is `Copy` and compared and hashed in constant time.  Symbols are only meaningful within the
+//! context which interned them; comparisons across contexts must use the strings.
+
+use std::sync::Arc;
+
+use rustc_hash::FxHashMap;
+
+use crate::context::Context;
+
+/// A string interned in a [`Context`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// Return the string this symbol was interned from.
+    pub fn as_str<'a>(&self, context: &'a Context) -> &'a str {
+        &context.symbols.strings[self.0 as usize]
+    }
+}
+
+/// The strings interned in a [`Context`].
+#[derive(Default)]
+pub(crate) struct Interner {
+    strings: Vec<Arc<str>>,
+    symbols: FxHashMap<Arc<str>, Symbol>,
+}
+
+impl Interner {
+    pub(crate) fn intern(&mut self, string: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(string) {
+            return *symbol;
+        }
+        let symbol = Symbol(
+            u32::try_from(self.strings.len()).expect("Too many interned strings for a context."),
+        );
+        let string = Arc::<str>::from(string);
+        self.strings.push(string.clone());
+        self.symbols.insert(string, symbol);
+        symbol
+    }
+
+    pub(crate) fn get(&self, string: &str) -> Option<Symbol> {
+        self.symbols.get(string).copied()
+    }
+
+    /// The number of interned strings and the bytes they take, for the memory report.
+    pub(crate) fn usage(&self) -> (usize, usize) {
+        let bytes = self
+            .strings
+            .iter()
+            .map(|string| string.len())
+            .sum::<usize>()
+            + self.strings.capacity() * std::mem::size_of::<Arc<str>>()
+            + self.symbols.capacity()
+                * (std::mem::size_of::<Arc<str>>() + std::mem::size_of::<Symbol>());
+        (self.strings.len(), bytes)
+    }
+}
+
+impl Context {
+    /// Return the symbol for `string`, interning it if it's new.
+    pub fn intern(&mut self, string: &str) -> Symbol {
+        self.symbols.intern(string)
+    }
+
+    /// Return the symbol for `string` if it has been interned, e.g. to look up a function by name
+    /// without interning the name.
+    pub fn get_symbol(&self, string: &str) -> Option<Symbol> {
+        self.symbols.get(string)
+    }
+}
@@ -13,9 +13,12 @@ use crate::{
     context::Context,
     instruction::{FuelVmInstruction, Instruction},
     irtype::Type,
-    metadata::{combine, MetadataIndex},
+    metadata::{
+        self, combine, Attachment, AttachmentPolicy, DebugVariable, InlinedFrom, MetadataIndex,
+        MetadataKind, Metadatum, SourceSpan,
+    },
     pretty::DebugWithContext,
-    BlockArgument,
+    Block, BlockArgument,
 };
 
 /// A wrapper around an [ECS](https://github.com/fitzgen/generational-arena) handle into the
@@ -28,6 +31,8 @@ pub struct Value(#[in_context(values)] pub generational_arena::Index);
 pub struct ValueContent {
     pub value: ValueDatum,
     pub metadata: Option<MetadataIndex>,
+    /// The block containing this instruction, if it's in one.  See [`Value::get_block`].
+    pub block: Option<Block>,
 }
 
 #[doc(hidden)]
@@ -45,6 +50,7 @@ impl Value {
         let content = ValueContent {
             value: ValueDatum::Argument(arg),
             metadata: None,
+            block: None,
         };
         Value(context.values.insert(content))
     }
@@ -54,6 +60,7 @@ impl Value {
         let content = ValueContent {
             value: ValueDatum::Configurable(constant),
             metadata: None,
+            block: None,
         };
         Value(context.values.insert(content))
     }
@@ -63,6 +70,7 @@ impl Value {
         let content = ValueContent {
             value: ValueDatum::Constant(constant),
             metadata: None,
+            block: None,
         };
         Value(context.values.insert(content))
     }
@@ -72,8 +80,11 @@ impl Value {
         let content = ValueContent {
             value: ValueDatum::Instruction(instruction),
             metadata: None,
+            block: None,
         };
-        Value(context.values.insert(content))
+        let value = Value(context.values.insert(content));
+        value.update_uses(context);
+        value
     }
 
     /// Add some metadata to this value.
@@ -97,6 +108,138 @@ impl Value {
         context.values[self.0].metadata
     }
 
+    /// Attach `attachment` to this value, replacing any attachment with the same namespace and
+    /// key.
+    pub fn add_attachment(self, context: &mut Context, attachment: &Attachment) -> Self {
+        let md_idx = context.values[self.0].metadata;
+        context.values[self.0].metadata = metadata::add_attachment(context, md_idx, attachment);
+        self
+    }
+
+    /// Return the source span of this value, if it has one.
+    pub fn get_span(&self, context: &Context) -> Option<SourceSpan> {
+        metadata::get_span(context, context.values[self.0].metadata)
+    }
+
+    /// Set the source span of this value, replacing any it already has.
+    pub fn set_span(self, context: &mut Context, span: SourceSpan) -> Self {
+        let md_idx = context.values[self.0].metadata;
+        context.values[self.0].metadata = metadata::set_span(context, md_idx, span);
+        self
+    }
+
+    /// Return the fields of this value's metadatum of `kind`, if it has one.
+    pub fn get_metadatum<'a>(
+        &self,
+        context: &'a Context,
+        kind: &MetadataKind,
+    ) -> Option<&'a [Metadatum]> {
+        metadata::get_metadatum(context, context.values[self.0].metadata, kind)
+    }
+
+    /// Set this value's metadatum of `kind` to one with `fields`, replacing any it already has.
+    pub fn set_metadatum(
+        self,
+        context: &mut Context,
+        kind: &MetadataKind,
+        fields: Vec<Metadatum>,
+    ) -> Self {
+        let md_idx = context.values[self.0].metadata;
+        context.values[self.0].metadata = metadata::set_metadatum(context, md_idx, kind, fields);
+        self
+    }
+
+    /// Remove this value's metadatum of `kind`, if it has one.
+    pub fn remove_metadatum(&self, context: &mut Context, kind: &MetadataKind) {
+        let md_idx = context.values[self.0].metadata;
+        context.values[self.0].metadata = metadata::remove_metadatum(context, md_idx, kind);
+    }
+
+    /// Return the storage key this value accesses, if it's known.
+    pub fn get_storage_key(&self, context: &Context) -> Option<u64> {
+        match self.get_metadatum(context, &MetadataKind::StorageKey)? {
+            [Metadatum::Integer(key)] => Some(*key),
+            _otherwise => None,
+        }
+    }
+
+    /// Set the storage key this value accesses, replacing any it already has.
+    pub fn set_storage_key(self, context: &mut Context, key: u64) -> Self {
+        self.set_metadatum(
+            context,
+            &MetadataKind::StorageKey,
+            vec![Metadatum::Integer(key)],
+        )
+    }
+
+    /// Return the function this instruction was inlined from, if it was.
+    pub fn get_inlined_from(&self, context: &Context) -> Option<InlinedFrom> {
+        metadata::get_inlined_from(context, context.values[self.0].metadata)
+    }
+
+    /// Set the function this instruction was inlined from, replacing any it already has.
+    pub fn set_inlined_from(self, context: &mut Context, inlined_from: &InlinedFrom) -> Self {
+        let md_idx = context.values[self.0].metadata;
+        context.values[self.0].metadata = metadata::set_inlined_from(context, md_idx, inlined_from);
+        self
+    }
+
+    /// Return the user variables this value holds.
+    pub fn get_debug_variables(&self, context: &Context) -> Vec<DebugVariable> {
+        metadata::get_debug_variables(context, context.values[self.0].metadata)
+    }
+
+    /// Record that this value holds the user variable `variable`, as well as any others it holds.
+    pub fn add_debug_variable(self, context: &mut Context, variable: &DebugVariable) -> Self {
+        let md_idx = context.values[self.0].metadata;
+        context.values[self.0].metadata = metadata::add_debug_variable(context, md_idx, variable);
+        self
+    }
+
+    /// Remove the user variables of this value and return them.
+    pub fn take_debug_variables(&self, context: &mut Context) -> Vec<DebugVariable> {
+        let md_idx = context.values[self.0].metadata;
+        let variables = metadata::get_debug_variables(context, md_idx);
+        if !variables.is_empty() {
+            context.values[self.0].metadata = metadata::remove_debug_variables(context, md_idx);
+        }
+        variables
+    }
+
+    /// Return the attachments of this value.
+    pub fn get_attachments(&self, context: &Context) -> Vec<Attachment> {
+        metadata::get_attachments(context, context.values[self.0].metadata)
+    }
+
+    /// Return the value attached to this value for `key` in `namespace`, if any.
+    pub fn get_attachment(&self, context: &Context, namespace: &str, key: &str) -> Option<String> {
+        self.get_attachments(context)
+            .into_iter()
+            .find(|attachment| attachment.namespace == namespace && attachment.key == key)
+            .map(|attachment| attachment.value)
+    }
+
+    /// Remove the attachments of this value for which `remove` is true.
+    pub fn remove_attachments<F: Fn(&Attachment) -> bool>(&self, context: &mut Context, remove: F) {
+        let md_idx = context.values[self.0].metadata;
+        context.values[self.0].metadata = metadata::remove_attachments(context, md_idx, remove);
+    }
+
+    /// Copy the attachments of this value to `replacement` for the namespaces whose policy is
+    /// [`AttachmentPolicy::Propagate`].  [`Function::replace_values`](crate::Function::replace_values)
+    /// does this for the values it replaces, so it's only needed by passes which replace the uses
+    /// of this value by other means.
+    pub fn propagate_attachments(&self, context: &mut Context, replacement: Value) {
+        if *self == replacement {
+            return;
+        }
+        for attachment in self.get_attachments(context) {
+            if context.get_attachment_policy(&attachment.namespace) == AttachmentPolicy::Propagate {
+                replacement.add_attachment(context, &attachment);
+            }
+        }
+    }
+
     /// Return whether this is a constant value.
     pub fn is_configurable(&self, context: &Context) -> bool {
         matches!(context.values[self.0].value, ValueDatum::Configurable(_))
@@ -146,16 +289,65 @@ impl Value {
         context: &mut Context,
         replace_map: &FxHashMap<Value, Value>,
     ) {
-        if let ValueDatum::Instruction(instruction) =
-            &mut context.values.get_mut(self.0).unwrap().value
+        let Some(instruction) = self.get_instruction(context) else {
+            return;
+        };
+        if !instruction
+            .get_operands()
+            .iter()
+            .any(|operand| replace_map.contains_key(operand))
         {
-            instruction.replace_values(replace_map);
+            return;
         }
+        self.remove_uses(context);
+        self.get_instruction_mut(context)
+            .unwrap()
+            .replace_values(replace_map);
+        self.update_uses(context);
     }
 
     /// Replace this value with another one, in-place.
+    ///
+    /// The attachments in namespaces whose policy is [`AttachmentPolicy::Drop`] are removed.
     pub fn replace(&self, context: &mut Context, other: ValueDatum) {
+        self.remove_uses(context);
         context.values[self.0].value = other;
+        self.update_uses(context);
+        self.drop_attachments(context);
+    }
+
+    /// Remove the attachments in namespaces whose policy is [`AttachmentPolicy::Drop`].  To be
+    /// called by passes which rewrite or move this value.
+    pub fn drop_attachments(&self, context: &mut Context) {
+        let dropped = self
+            .get_attachments(context)
+            .into_iter()
+            .filter(|attachment| {
+                context.get_attachment_policy(&attachment.namespace) == AttachmentPolicy::Drop
+            })
+            .collect::<Vec<_>>();
+        if !dropped.is_empty() {
+            self.remove_attachments(context, |attachment| dropped.contains(attachment));
+        }
+    }
+
+    /// Return the block containing this value, if it's a block argument or an instruction in a
+    /// block.
+    ///
+    /// The block of an instruction is kept up to date by the APIs which insert, move and remove
+    /// instructions, so finding it doesn't need a search of the function.
+    pub fn get_block(&self, context: &Context) -> Option<Block> {
+        let content = &context.values[self.0];
+        match &content.value {
+            ValueDatum::Argument(BlockArgument { block, .. }) => Some(*block),
+            ValueDatum::Instruction(_) => content.block,
+            ValueDatum::Configurable(_) | ValueDatum::Constant(_) => None,
+        }
+    }
+
+    /// Record that this instruction is now in `block`, or in no block.
+    pub(crate) fn set_block(&self, context: &mut Context, block: Option<Block>) {
+        context.values[self.0].block = block;
     }
 
     /// Get a reference to this value as an instruction, iff it is one.
@@ -168,6 +360,8 @@ impl Value {
     }
 
     /// Get a mutable reference to this value as an instruction, iff it is one.
+    ///
+    /// Any operand added through the reference must be recorded with [`Value::update_uses`].
     pub fn get_instruction_mut<'a>(&self, context: &'a mut Context) -> Option<&'a mut Instruction> {
         if let ValueDatum::Instruction(instruction) =
             &mut context.values.get_mut(self.0).unwrap().value
@@ -178,6 +372,65 @@ impl Value {
         }
     }
 
+    /// Return the instructions which use this value as an operand, in the order they started to.
+    ///
+    /// These are kept up to date as instructions are created, removed from their blocks and have
+    /// their operands replaced, so no scan of the function is needed.
+    pub fn users(&self, context: &Context) -> Vec<Value> {
+        context.users.get(self).map_or(Vec::new(), |users| {
+            // An operand may have been removed through a mutable reference, so check that each
+            // user still uses this value.
+            users
+                .iter()
+                .filter(|user| {
+                    context.values.get(user.0).map_or(false, |content| {
+                        matches!(&content.value, ValueDatum::Instruction(ins)
+                            if ins.get_operands().contains(self))
+                    })
+                })
+                .copied()
+                .collect()
+        })
+    }
+
+    /// Record this instruction as a user of each of its operands.  Only needed after adding an
+    /// operand through a mutable reference, such as from [`Value::get_instruction_mut`].
+    pub fn update_uses(&self, context: &mut Context) {
+        let Some(instruction) = self.get_instruction(context) else {
+            return;
+        };
+        for operand in instruction.get_operands() {
+            let users = context.users.entry(operand).or_default();
+            if !users.contains(self) {
+                users.push(*self);
+            }
+        }
+    }
+
+    /// Stop recording this instruction as a user of its operands, e.g., once it has been removed
+    /// from its block.
+    pub(crate) fn remove_uses(&self, context: &mut Context) {
+        let Some(instruction) = self.get_instruction(context) else {
+            return;
+        };
+        for operand in instruction.get_operands() {
+            if let Some(users) = context.users.get_mut(&operand) {
+                users.retain(|user| user != self);
+                if users.is_empty() {
+                    context.users.remove(&operand);
+                }
+            }
+        }
+    }
+
+    /// Remove this value from the context, along with the record of its uses.  Any handle to it is
+    /// then invalid.
+    pub(crate) fn free(&self, context: &mut Context) {
+        self.remove_uses(context);
+        context.users.remove(self);
+        context.values.remove(self.0);
+    }
+
     /// Get a reference to this value as a constant, iff it is one.
     pub fn get_configurable<'a>(&self, context: &'a Context) -> Option<&'a Constant> {
         if let ValueDatum::Configurable(cn) = &context.values.get(self.0).unwrap().value {
@@ -3,19 +3,25 @@
 //! During creation, deserialization and optimization the IR should be verified to be in a
 //! consistent valid state, using the functions in this module.
 
+use rustc_hash::FxHashMap;
+
 use crate::{
+    block::Block,
     block::BlockContent,
+    compute_dom_tree, compute_post_order,
     context::Context,
+    dominates,
     error::IrError,
     function::{Function, FunctionContent},
     instruction::{FuelVmInstruction, Instruction, Predicate},
     irtype::Type,
     local_var::LocalVar,
     metadata::{MetadataIndex, Metadatum},
-    module::ModuleContent,
+    module::{selector_collision, selector_collisions, ModuleContent},
+    printer::function_to_blocks,
     value::{Value, ValueDatum},
     AnalysisResult, AnalysisResultT, AnalysisResults, BinaryOpKind, BlockArgument,
-    BranchToWithArgs, Module, Pass, PassMutability, ScopedPass, TypeOption,
+    BranchToWithArgs, DomTree, Module, Pass, PassMutability, ScopedPass, TypeOption,
 };
 
 pub struct ModuleVerifierResult;
@@ -38,6 +44,8 @@ pub fn create_module_verifier_pass() -> Pass {
         name: MODULEVERIFIER_NAME,
         descr: "Verify module",
         deps: vec![],
+        preserves: vec![],
+        prune_unreachable: false,
         runner: ScopedPass::ModulePass(PassMutability::Analysis(module_verifier)),
     }
 }
@@ -51,9 +59,18 @@ impl Context {
         Ok(self)
     }
 
-    fn verify_module(&self, module: &ModuleContent) -> Result<(), IrError> {
+    pub(crate) fn verify_module(&self, module: &ModuleContent) -> Result<(), IrError> {
         for function in &module.functions {
-            self.verify_function(module, &self.functions[function.0])?;
+            self.verify_function(module, *function)?;
+        }
+
+        // Selectors identify the ABI methods of a contract so must be unique.
+        let selectors = module
+            .functions
+            .iter()
+            .filter_map(|function| Some((*function, function.get_selector(self)?)));
+        if let Some((selector, functions)) = selector_collisions(selectors).into_iter().next() {
+            return Err(selector_collision(self, selector, &functions));
         }
         Ok(())
     }
@@ -61,21 +78,78 @@ impl Context {
     fn verify_function(
         &self,
         cur_module: &ModuleContent,
-        function: &FunctionContent,
+        function: Function,
     ) -> Result<(), IrError> {
-        for block in &function.blocks {
-            self.verify_block(cur_module, function, &self.blocks[block.0])?;
+        let cur_function = &self.functions[function.0];
+
+        // The entry block takes exactly the function arguments, in order.
+        let entry_block = &self.blocks[function.get_entry_block(self).0];
+        if entry_block.args.len() != cur_function.arguments.len()
+            || entry_block
+                .args
+                .iter()
+                .zip(&cur_function.arguments)
+                .any(|(block_arg, (_, fn_arg))| block_arg != fn_arg)
+        {
+            return Err(self.located_error(
+                function,
+                Some(entry_block),
+                None,
+                IrError::VerifyEntryArgsMismatch(cur_function.name.as_str(self).to_owned()),
+            ));
         }
-        self.verify_metadata(function.metadata)?;
-        Ok(())
+
+        // Where each instruction is, to check that it's defined before each of its uses.
+        let dom_tree = compute_dom_tree(self, &function, &compute_post_order(self, &function));
+        let definitions = function
+            .block_iter(self)
+            .flat_map(|block| {
+                block
+                    .instruction_iter(self)
+                    .enumerate()
+                    .map(move |(idx, ins)| (ins, (block, idx)))
+            })
+            .collect::<FxHashMap<_, _>>();
+
+        for block in &cur_function.blocks {
+            self.verify_block(cur_module, function, *block, &dom_tree, &definitions)?;
+        }
+        self.verify_metadata(cur_function.metadata)
+            .map_err(|error| self.located_error(function, None, None, error))
     }
 
     fn verify_block(
         &self,
         cur_module: &ModuleContent,
-        cur_function: &FunctionContent,
-        block: &BlockContent,
+        function: Function,
+        block_handle: Block,
+        dom_tree: &DomTree,
+        definitions: &FxHashMap<Value, (Block, usize)>,
     ) -> Result<(), IrError> {
+        let block = &self.blocks[block_handle.0];
+
+        // Each block records its function and each instruction its block.
+        if block.function != function {
+            return Err(self.located_error(
+                function,
+                Some(block),
+                None,
+                IrError::VerifyInconsistentParent,
+            ));
+        }
+        if let Some(ins) = block
+            .instructions
+            .iter()
+            .find(|ins| ins.get_block(self) != Some(block_handle))
+        {
+            return Err(self.located_error(
+                function,
+                Some(block),
+                Some(*ins),
+                IrError::VerifyInconsistentParent,
+            ));
+        }
+
         if block.instructions.len() <= 1 && block.preds.is_empty() {
             // Empty unreferenced blocks are a harmless artefact.
             return Ok(());
@@ -83,16 +157,28 @@ impl Context {
 
         for (arg_idx, arg_val) in block.args.iter().enumerate() {
             match self.values[arg_val.0].value {
-                ValueDatum::Argument(BlockArgument { idx, .. }) if idx == arg_idx => (),
-                _ => return Err(IrError::VerifyBlockArgMalformed),
+                ValueDatum::Argument(BlockArgument { block, idx, .. })
+                    if block == block_handle && idx == arg_idx => {}
+                _ => {
+                    return Err(self.located_error(
+                        function,
+                        Some(block),
+                        None,
+                        IrError::VerifyBlockArgMalformed,
+                    ))
+                }
             }
         }
 
         InstructionVerifier {
             context: self,
             cur_module,
-            cur_function,
+            function,
+            cur_function: &self.functions[function.0],
+            block: block_handle,
             cur_block: block,
+            dom_tree,
+            definitions,
         }
         .verify_instructions()?;
 
@@ -105,12 +191,39 @@ impl Context {
                 }
             });
         if !last_is_term {
-            Err(IrError::MissingTerminator(block.label.clone()))
+            Err(IrError::MissingTerminator(
+                block.label.as_str(self).to_owned(),
+            ))
         } else if num_terms != 1 {
-            Err(IrError::MisplacedTerminator(block.label.clone()))
+            Err(IrError::MisplacedTerminator(
+                block.label.as_str(self).to_owned(),
+            ))
         } else {
             Ok(())
         }
+        .map_err(|error| self.located_error(function, Some(block), None, error))
+    }
+
+    /// Wrap `error` with where it was found, naming the instruction as it's printed.
+    fn located_error(
+        &self,
+        function: Function,
+        block: Option<&BlockContent>,
+        instruction: Option<Value>,
+        error: IrError,
+    ) -> IrError {
+        let instruction = instruction.and_then(|ins| {
+            function_to_blocks(self, function)
+                .into_iter()
+                .flat_map(|printed| printed.instructions)
+                .find_map(|(value, text)| (value == ins).then(|| text.trim().to_string()))
+        });
+        IrError::VerifyFailed {
+            function: function.get_name(self).to_string(),
+            block: block.map(|block| block.label.as_str(self).to_owned()),
+            instruction,
+            error: Box::new(error),
+        }
     }
 
     fn verify_metadata(&self, md_idx: Option<MetadataIndex>) -> Result<(), IrError> {
@@ -150,130 +263,31 @@ impl Context {
 struct InstructionVerifier<'a> {
     context: &'a Context,
     cur_module: &'a ModuleContent,
+    function: Function,
     cur_function: &'a FunctionContent,
+    block: Block,
     cur_block: &'a BlockContent,
+    dom_tree: &'a DomTree,
+    definitions: &'a FxHashMap<Value, (Block, usize)>,
 }
 
 impl<'a> InstructionVerifier<'a> {
     fn verify_instructions(&self) -> Result<(), IrError> {
-        for ins in &self.cur_block.instructions {
+        for (idx, ins) in self.cur_block.instructions.iter().enumerate() {
             let value_content = &self.context.values[ins.0];
             if let ValueDatum::Instruction(instruction) = &value_content.value {
-                match instruction {
-                    Instruction::AddrOf(arg) => self.verify_addr_of(arg)?,
-                    Instruction::AsmBlock(..) => (),
-                    Instruction::BitCast(value, ty) => self.verify_bitcast(value, ty)?,
-                    Instruction::BinaryOp { op, arg1, arg2 } => {
-                        self.verify_binary_op(op, arg1, arg2)?
-                    }
-                    Instruction::Branch(block) => self.verify_br(block)?,
-                    Instruction::Call(func, args) => self.verify_call(func, args)?,
-                    Instruction::CastPtr(val, ty, _offs) => self.verify_cast_ptr(val, ty)?,
-                    Instruction::Cmp(pred, lhs_value, rhs_value) => {
-                        self.verify_cmp(pred, lhs_value, rhs_value)?
-                    }
-                    Instruction::ConditionalBranch {
-                        cond_value,
-                        true_block,
-                        false_block,
-                    } => self.verify_cbr(cond_value, true_block, false_block)?,
-                    Instruction::ContractCall {
-                        params,
-                        coins,
-                        asset_id,
-                        gas,
-                        ..
-                    } => self.verify_contract_call(params, coins, asset_id, gas)?,
-                    Instruction::ExtractElement {
-                        array,
-                        ty,
-                        index_val,
-                    } => self.verify_extract_element(array, ty, index_val)?,
-                    Instruction::ExtractValue {
-                        aggregate,
-                        ty,
-                        indices,
-                    } => self.verify_extract_value(aggregate, ty, indices)?,
-                    Instruction::FuelVm(fuel_vm_instr) => match fuel_vm_instr {
-                        FuelVmInstruction::GetStorageKey => (),
-                        FuelVmInstruction::Gtf { index, tx_field_id } => {
-                            self.verify_gtf(index, tx_field_id)?
-                        }
-                        FuelVmInstruction::Log {
-                            log_val,
-                            log_ty,
-                            log_id,
-                        } => self.verify_log(log_val, log_ty, log_id)?,
-                        FuelVmInstruction::ReadRegister(_) => (),
-                        FuelVmInstruction::Revert(val) => self.verify_revert(val)?,
-                        FuelVmInstruction::Smo {
-                            recipient_and_message,
-                            message_size,
-                            output_index,
-                            coins,
-                        } => self.verify_smo(
-                            recipient_and_message,
-                            message_size,
-                            output_index,
-                            coins,
-                        )?,
-                        FuelVmInstruction::StateClear {
-                            key,
-                            number_of_slots,
-                        } => self.verify_state_clear(key, number_of_slots)?,
-                        FuelVmInstruction::StateLoadWord(key) => {
-                            self.verify_state_load_word(key)?
-                        }
-                        FuelVmInstruction::StateLoadQuadWord {
-                            load_val: dst_val,
-                            key,
-                            number_of_slots,
-                        }
-                        | FuelVmInstruction::StateStoreQuadWord {
-                            stored_val: dst_val,
-                            key,
-                            number_of_slots,
-                        } => self.verify_state_load_store(
-                            dst_val,
-                            Type::get_b256(self.context),
-                            key,
-                            number_of_slots,
-                        )?,
-                        FuelVmInstruction::StateStoreWord {
-                            stored_val: dst_val,
-                            key,
-                        } => self.verify_state_store_word(dst_val, key)?,
-                    },
-                    Instruction::GetLocal(local_var) => self.verify_get_local(local_var)?,
-                    Instruction::InsertElement {
-                        array,
-                        ty,
-                        value,
-                        index_val,
-                    } => self.verify_insert_element(array, ty, value, index_val)?,
-                    Instruction::InsertValue {
-                        aggregate,
-                        ty,
-                        value,
-                        indices,
-                    } => self.verify_insert_value(aggregate, ty, value, indices)?,
-                    Instruction::IntToPtr(value, ty) => self.verify_int_to_ptr(value, ty)?,
-                    Instruction::Load(ptr) => self.verify_load(ptr)?,
-                    Instruction::MemCopy {
-                        dst_val,
-                        src_val,
-                        byte_len,
-                    } => self.verify_mem_copy(dst_val, src_val, byte_len)?,
-                    Instruction::Nop => (),
-                    Instruction::Ret(val, ty) => self.verify_ret(val, ty)?,
-                    Instruction::Store {
-                        dst_val,
-                        stored_val,
-                    } => self.verify_store(dst_val, stored_val)?,
-                };
-
-                // Verify the instruction metadata too.
-                self.context.verify_metadata(value_content.metadata)?;
+                self.verify_instruction(instruction)
+                    .and_then(|_| self.verify_operands_dominate(idx, instruction))
+                    // Verify the instruction metadata too.
+                    .and_then(|_| self.context.verify_metadata(value_content.metadata))
+                    .map_err(|error| {
+                        self.context.located_error(
+                            self.function,
+                            Some(self.cur_block),
+                            Some(*ins),
+                            error,
+                        )
+                    })?;
             } else {
                 unreachable!("Verify instruction is not an instruction.");
             }
@@ -281,6 +295,142 @@ impl<'a> InstructionVerifier<'a> {
         Ok(())
     }
 
+    /// Check that the operands of the instruction at `idx` in the current block are defined before
+    /// it, either earlier in the block or in a block which dominates it.
+    fn verify_operands_dominate(
+        &self,
+        idx: usize,
+        instruction: &Instruction,
+    ) -> Result<(), IrError> {
+        // An unreachable block is never run, so it doesn't matter what it uses.
+        if !self.dom_tree.contains_key(&self.block) {
+            return Ok(());
+        }
+        for operand in instruction.get_operands() {
+            let is_defined = match &self.context.values[operand.0].value {
+                ValueDatum::Argument(arg) => dominates(self.dom_tree, arg.block, self.block),
+                ValueDatum::Instruction(_) => match self.definitions.get(&operand) {
+                    Some((def_block, def_idx)) if *def_block == self.block => *def_idx < idx,
+                    Some((def_block, _)) => dominates(self.dom_tree, *def_block, self.block),
+                    None => false,
+                },
+                ValueDatum::Configurable(_) | ValueDatum::Constant(_) => true,
+            };
+            if !is_defined {
+                return Err(IrError::VerifyOperandNotDominated);
+            }
+        }
+        Ok(())
+    }
+
+    fn verify_instruction(&self, instruction: &Instruction) -> Result<(), IrError> {
+        match instruction {
+            Instruction::AddrOf(arg) => self.verify_addr_of(arg)?,
+            Instruction::AsmBlock(..) => (),
+            Instruction::BitCast(value, ty) => self.verify_bitcast(value, ty)?,
+            Instruction::BinaryOp { op, arg1, arg2 } => self.verify_binary_op(op, arg1, arg2)?,
+            Instruction::Branch(block) => self.verify_br(block)?,
+            Instruction::Call(func, args) => self.verify_call(func, args)?,
+            Instruction::CastPtr(val, ty, _offs) => self.verify_cast_ptr(val, ty)?,
+            Instruction::Cmp(pred, lhs_value, rhs_value) => {
+                self.verify_cmp(pred, lhs_value, rhs_value)?
+            }
+            Instruction::ConditionalBranch {
+                cond_value,
+                true_block,
+                false_block,
+            } => self.verify_cbr(cond_value, true_block, false_block)?,
+            Instruction::ContractCall {
+                params,
+                coins,
+                asset_id,
+                gas,
+                ..
+            } => self.verify_contract_call(params, coins, asset_id, gas)?,
+            Instruction::ExtractElement {
+                array,
+                ty,
+                index_val,
+            } => self.verify_extract_element(array, ty, index_val)?,
+            Instruction::ExtractValue {
+                aggregate,
+                ty,
+                indices,
+            } => self.verify_extract_value(aggregate, ty, indices)?,
+            Instruction::FuelVm(fuel_vm_instr) => match fuel_vm_instr {
+                FuelVmInstruction::GasCheckpoint(id) => self.verify_gas_checkpoint(id)?,
+                FuelVmInstruction::GetStorageKey => (),
+                FuelVmInstruction::Gtf { index, tx_field_id } => {
+                    self.verify_gtf(index, tx_field_id)?
+                }
+                FuelVmInstruction::Log {
+                    log_val,
+                    log_ty,
+                    log_id,
+                } => self.verify_log(log_val, log_ty, log_id)?,
+                FuelVmInstruction::ReadRegister(_) => (),
+                FuelVmInstruction::Revert(val) => self.verify_revert(val)?,
+                FuelVmInstruction::Smo {
+                    recipient_and_message,
+                    message_size,
+                    output_index,
+                    coins,
+                } => self.verify_smo(recipient_and_message, message_size, output_index, coins)?,
+                FuelVmInstruction::StateClear {
+                    key,
+                    number_of_slots,
+                } => self.verify_state_clear(key, number_of_slots)?,
+                FuelVmInstruction::StateLoadWord(key) => self.verify_state_load_word(key)?,
+                FuelVmInstruction::StateLoadQuadWord {
+                    load_val: dst_val,
+                    key,
+                    number_of_slots,
+                }
+                | FuelVmInstruction::StateStoreQuadWord {
+                    stored_val: dst_val,
+                    key,
+                    number_of_slots,
+                } => self.verify_state_load_store(
+                    dst_val,
+                    Type::get_b256(self.context),
+                    key,
+                    number_of_slots,
+                )?,
+                FuelVmInstruction::StateStoreWord {
+                    stored_val: dst_val,
+                    key,
+                } => self.verify_state_store_word(dst_val, key)?,
+            },
+            Instruction::GetLocal(local_var) => self.verify_get_local(local_var)?,
+            Instruction::InsertElement {
+                array,
+                ty,
+                value,
+                index_val,
+            } => self.verify_insert_element(array, ty, value, index_val)?,
+            Instruction::InsertValue {
+                aggregate,
+                ty,
+                value,
+                indices,
+            } => self.verify_insert_value(aggregate, ty, value, indices)?,
+            Instruction::IntToPtr(value, ty) => self.verify_int_to_ptr(value, ty)?,
+            Instruction::Load(ptr) => self.verify_load(ptr)?,
+            Instruction::MemCopy {
+                dst_val,
+                src_val,
+                byte_len,
+            } => self.verify_mem_copy(dst_val, src_val, byte_len)?,
+            Instruction::Nop => (),
+            Instruction::Ret(val, ty) => self.verify_ret(val, ty)?,
+            Instruction::Store {
+                dst_val,
+                stored_val,
+            } => self.verify_store(dst_val, stored_val)?,
+        }
+        Ok(())
+    }
+
     fn verify_addr_of(&self, value: &Value) -> Result<(), IrError> {
         // `addr_of` is weird and will be replaced by `ptr_to_int` when we reintroduce pointers.
         let val_ty = value
@@ -333,7 +483,10 @@ impl<'a> InstructionVerifier<'a> {
     fn verify_br(&self, dest_block: &BranchToWithArgs) -> Result<(), IrError> {
         if !self.cur_function.blocks.contains(&dest_block.block) {
             Err(IrError::VerifyBranchToMissingBlock(
-                self.context.blocks[dest_block.block.0].label.clone(),
+                self.context.blocks[dest_block.block.0]
+                    .label
+                    .as_str(self.context)
+                    .to_owned(),
             ))
         } else {
             self.verify_dest_args(dest_block)
@@ -344,7 +497,7 @@ impl<'a> InstructionVerifier<'a> {
         let callee_content = &self.context.functions[callee.0];
         if !self.cur_module.functions.contains(callee) {
             return Err(IrError::VerifyCallToMissingFunction(
-                callee_content.name.clone(),
+                callee_content.name.as_str(self.context).to_owned(),
             ));
         }
 
@@ -358,7 +511,7 @@ impl<'a> InstructionVerifier<'a> {
                     Ok(*ty)
                 } else {
                     Err(IrError::VerifyArgumentValueIsNotArgument(
-                        callee_content.name.clone(),
+                        callee_content.name.as_str(self.context).to_owned(),
                     ))
                 }
             })
@@ -376,7 +529,7 @@ impl<'a> InstructionVerifier<'a> {
             let caller_arg_type = opt_caller_arg_type.as_ref().unwrap();
             if !caller_arg_type.eq(self.context, callee_arg_type) {
                 return Err(IrError::VerifyCallArgTypeMismatch(
-                    callee_content.name.clone(),
+                    callee_content.name.as_str(self.context).to_owned(),
                 ));
             }
         }
@@ -408,19 +561,22 @@ impl<'a> InstructionVerifier<'a> {
 
     fn verify_dest_args(&self, dest: &BranchToWithArgs) -> Result<(), IrError> {
         if dest.block.num_args(self.context) != dest.args.len() {
-            return Err(IrError::VerifyBranchParamsMismatch);
-        }
-        for (arg_idx, dest_param) in dest.block.arg_iter(self.context).enumerate() {
-            match dest.args.get(arg_idx) {
-                Some(actual)
-                    if dest_param
-                        .get_type(self.context)
-                        .unwrap()
-                        .eq(self.context, &actual.get_type(self.context).unwrap()) => {}
-                _ =>
-                // TODO: https://github.com/FuelLabs/sway/pull/2880
-                {
-                    // return Err(IrError::VerifyBranchParamsMismatch)
+            return Err(IrError::VerifyBranchParamsMismatch(
+                self.cur_block.label.as_str(self.context).to_owned(),
+                dest.block.get_label(self.context).to_owned(),
+            ));
+        }
+        for (dest_param, actual) in dest.block.arg_iter(self.context).zip(&dest.args) {
+            match (
+                dest_param.get_type(self.context),
+                actual.get_type(self.context),
+            ) {
+                (Some(param_ty), Some(actual_ty)) if param_ty.eq(self.context, &actual_ty) => {}
+                _ => {
+                    return Err(IrError::VerifyBranchParamsMismatch(
+                        self.cur_block.label.as_str(self.context).to_owned(),
+                        dest.block.get_label(self.context).to_owned(),
+                    ))
                 }
             }
         }
@@ -440,11 +596,17 @@ impl<'a> InstructionVerifier<'a> {
             Err(IrError::VerifyConditionExprNotABool)
         } else if !self.cur_function.blocks.contains(&true_block.block) {
             Err(IrError::VerifyBranchToMissingBlock(
-                self.context.blocks[true_block.block.0].label.clone(),
+                self.context.blocks[true_block.block.0]
+                    .label
+                    .as_str(self.context)
+                    .to_owned(),
             ))
         } else if !self.cur_function.blocks.contains(&false_block.block) {
             Err(IrError::VerifyBranchToMissingBlock(
-                self.context.blocks[false_block.block.0].label.clone(),
+                self.context.blocks[false_block.block.0]
+                    .label
+                    .as_str(self.context)
+                    .to_owned(),
             ))
         } else {
             self.verify_dest_args(true_block)
@@ -590,6 +752,14 @@ impl<'a> InstructionVerifier<'a> {
         }
     }
 
+    fn verify_gas_checkpoint(&self, id: &Value) -> Result<(), IrError> {
+        if !id.get_type(self.context).is(Type::is_uint64, self.context) {
+            Err(IrError::VerifyGasCheckpointIdBadType)
+        } else {
+            Ok(())
+        }
+    }
+
     fn verify_gtf(&self, index: &Value, _tx_field_id: &u64) -> Result<(), IrError> {
         // We should perhaps verify that _tx_field_id fits in a twelve bit immediate
         if !index.get_type(self.context).is(Type::is_uint, self.context) {
@@ -743,7 +913,7 @@ impl<'a> InstructionVerifier<'a> {
                 && self.opt_ty_not_eq(&val.get_type(self.context), &Some(*ty)))
         {
             Err(IrError::VerifyMismatchedReturnTypes(
-                self.cur_function.name.clone(),
+                self.cur_function.name.as_str(self.context).to_owned(),
             ))
         } else {
             Ok(())
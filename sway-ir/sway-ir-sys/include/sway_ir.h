@@ -0,0 +1,71 @@
+/*
+ * C API for Sway-IR.
+ *
+ * Contexts are created by sway_ir_parse(), or empty by sway_ir_context_new() to be filled in with
+ * sway_ir_add_module() and sway_ir_add_function(), and must be released with
+ * sway_ir_context_free().  Every string returned by this API is owned by the caller and must be
+ * released with sway_ir_string_free().  Functions which may fail take an optional `error` out
+ * parameter, which on failure is set to a string describing the error.
+ *
+ * A panic while using a context may leave its IR part way through a change, so the context is
+ * then poisoned: every later call using it fails, and it may only be released.
+ */
+
+#ifndef SWAY_IR_H
+#define SWAY_IR_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* An opaque handle to an IR context. */
+typedef struct SwayIrContext SwayIrContext;
+
+/* Parse `source` as textual IR, returning a new context or NULL on failure. */
+SwayIrContext *sway_ir_parse(const char *source, char **error);
+
+/* Return a new context without any modules. */
+SwayIrContext *sway_ir_context_new(void);
+
+/*
+ * Add an empty module of the given `kind` to `context`, one of "script", "predicate", "contract"
+ * or "library".  Returns 0 on success and -1 on failure.
+ */
+int sway_ir_add_module(SwayIrContext *context, const char *kind, char **error);
+
+/*
+ * Parse `source` as a function in its textual form and add it to the last module added to
+ * `context`.  Its calls are resolved by name to the functions in that module, including itself.
+ * Returns 0 on success and -1 on failure.
+ */
+int sway_ir_add_function(SwayIrContext *context, const char *source, char **error);
+
+/*
+ * Release a context created by sway_ir_parse() or sway_ir_context_new(), even if it is poisoned.
+ * NULL is ignored.
+ */
+void sway_ir_context_free(SwayIrContext *context);
+
+/* Print the IR of `context` in its textual form.  Returns NULL if `context` is NULL or poisoned. */
+char *sway_ir_print(const SwayIrContext *context);
+
+/* Verify the IR of `context`.  Returns 0 if it is valid and -1 otherwise. */
+int sway_ir_verify(SwayIrContext *context, char **error);
+
+/*
+ * Run the comma separated list of `passes` over `context`, e.g. "inline_main, mem2reg, dce".
+ * Returns 1 if the IR was modified, 0 if it wasn't and -1 on failure.
+ */
+int sway_ir_run_passes(SwayIrContext *context, const char *passes, char **error);
+
+/* Return the names of the available passes, separated by commas, or NULL on failure. */
+char *sway_ir_pass_names(void);
+
+/* Release a string returned by this API.  NULL is ignored. */
+void sway_ir_string_free(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SWAY_IR_H */
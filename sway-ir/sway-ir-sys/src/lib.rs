@@ -0,0 +1,317 @@
+//! A C API for Sway-IR.
+//!
+//! This allows tools written in other languages to parse, verify, optimize and print IR in its
+//! textual form.  The declarations are in `include/sway_ir.h`.
+//!
+//! Contexts are created by [`sway_ir_parse`], or empty by [`sway_ir_context_new`] to be filled in
+//! with [`sway_ir_add_module`] and [`sway_ir_add_function`], and must be released with
+//! [`sway_ir_context_free`].  Every string returned by this API is owned by the caller and must be
+//! released with [`sway_ir_string_free`].  Functions which may fail take an optional `error` out
+//! parameter, which on failure is set to a string describing the error.
+//!
+//! Panics are caught at the boundary and reported as errors, so they never unwind into C.  A panic
+//! while using a context may leave its IR part way through a change, so the context is then
+//! poisoned: every later call using it fails, and it may only be released.
+
+use std::{
+    ffi::{c_char, c_int, CStr, CString},
+    panic::{catch_unwind, resume_unwind, AssertUnwindSafe},
+    ptr,
+};
+
+use sway_ir::{
+    register_known_passes, Context, Kind, Module, PassGroup, PassManager, MODULEVERIFIER_NAME,
+};
+
+/// The error of every call using a context after a call using it panicked.
+const POISONED_ERROR: &str = "context is poisoned by an earlier panic";
+
+/// An opaque handle to an IR context.
+pub struct SwayIrContext {
+    context: Context,
+    /// Set once a call using the context panicked, possibly leaving its IR inconsistent.
+    poisoned: bool,
+}
+
+impl SwayIrContext {
+    fn new(context: Context) -> *mut SwayIrContext {
+        Box::into_raw(Box::new(SwayIrContext {
+            context,
+            poisoned: false,
+        }))
+    }
+}
+
+/// Parse `source` as textual IR, returning a new context or null on failure.
+///
+/// # Safety
+///
+/// `source` must be a valid null-terminated string, and `error` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sway_ir_parse(
+    source: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut SwayIrContext {
+    guard(error, ptr::null_mut(), || {
+        let source = str_arg(source, "source")?;
+        let context = sway_ir::parser::parse(source).map_err(|err| err.to_string())?;
+        Ok(SwayIrContext::new(context))
+    })
+}
+
+/// Return a new context without any modules.
+#[no_mangle]
+pub extern "C" fn sway_ir_context_new() -> *mut SwayIrContext {
+    SwayIrContext::new(Context::default())
+}
+
+/// Add an empty module of the given `kind` to `context`, one of `"script"`, `"predicate"`,
+/// `"contract"` or `"library"`.  Returns 0 on success and -1 on failure.
+///
+/// # Safety
+///
+/// `context` must be null or a valid context, `kind` must be a valid null-terminated string and
+/// `error` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sway_ir_add_module(
+    context: *mut SwayIrContext,
+    kind: *const c_char,
+    error: *mut *mut c_char,
+) -> c_int {
+    guard_context(context, error, -1, |context| {
+        let kind = match str_arg(kind, "kind")? {
+            "script" => Kind::Script,
+            "predicate" => Kind::Predicate,
+            "contract" => Kind::Contract,
+            "library" => Kind::Library,
+            kind => return Err(format!("unknown module kind '{kind}'")),
+        };
+        Module::new(context, kind);
+        Ok(0)
+    })
+}
+
+/// Parse `source` as a function in its textual form and add it to the last module added to
+/// `context`.  Its calls are resolved by name to the functions in that module, including itself.
+/// Returns 0 on success and -1 on failure.
+///
+/// # Safety
+///
+/// `context` must be null or a valid context, `source` must be a valid null-terminated string and
+/// `error` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sway_ir_add_function(
+    context: *mut SwayIrContext,
+    source: *const c_char,
+    error: *mut *mut c_char,
+) -> c_int {
+    guard_context(context, error, -1, |context| {
+        let source = str_arg(source, "source")?;
+        let module = context
+            .module_iter()
+            .last()
+            .ok_or("context has no modules")?;
+        sway_ir::parse_function_into_module(context, module, source)
+            .map_err(|err| err.to_string())?;
+        Ok(0)
+    })
+}
+
+/// Release a context created by [`sway_ir_parse`] or [`sway_ir_context_new`], even if it is
+/// poisoned.  Null is ignored.
+///
+/// # Safety
+///
+/// `context` must be null or a context which has not been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn sway_ir_context_free(context: *mut SwayIrContext) {
+    if !context.is_null() {
+        drop(Box::from_raw(context));
+    }
+}
+
+/// Print the IR of `context` in its textual form.  Returns null if `context` is null or poisoned.
+///
+/// # Safety
+///
+/// `context` must be null or a valid context.
+#[no_mangle]
+pub unsafe extern "C" fn sway_ir_print(context: *const SwayIrContext) -> *mut c_char {
+    guard(ptr::null_mut(), ptr::null_mut(), || {
+        let context = context.as_ref().ok_or("context is null")?;
+        if context.poisoned {
+            return Err(POISONED_ERROR.to_string());
+        }
+        Ok(into_c_string(sway_ir::printer::to_string(&context.context)))
+    })
+}
+
+/// Verify the IR of `context`.  Returns 0 if it is valid and -1 otherwise.
+///
+/// # Safety
+///
+/// `context` must be null or a valid context, and `error` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sway_ir_verify(
+    context: *mut SwayIrContext,
+    error: *mut *mut c_char,
+) -> c_int {
+    guard_context(context, error, -1, |context| {
+        let mut passes = PassGroup::default();
+        passes.append_pass(MODULEVERIFIER_NAME);
+        run(context, &passes).map(|_| 0)
+    })
+}
+
+/// Run the comma separated list of `passes` over `context`, e.g. `"inline_main, mem2reg, dce"`.
+/// Returns 1 if the IR was modified, 0 if it wasn't and -1 on failure.
+///
+/// The names of the available passes are returned by [`sway_ir_pass_names`].
+///
+/// # Safety
+///
+/// `context` must be null or a valid context, `passes` must be a valid null-terminated string and
+/// `error` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sway_ir_run_passes(
+    context: *mut SwayIrContext,
+    passes: *const c_char,
+    error: *mut *mut c_char,
+) -> c_int {
+    guard_context(context, error, -1, |context| {
+        let pass_mgr = pass_manager();
+        let mut pass_group = PassGroup::default();
+        for name in str_arg(passes, "passes")?
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            let pass = pass_mgr
+                .lookup_registered_pass(name)
+                .ok_or_else(|| format!("unknown pass '{name}'"))?;
+            pass_group.append_pass(pass.name);
+        }
+        run(context, &pass_group).map(c_int::from)
+    })
+}
+
+/// Return the names of the available passes, separated by commas, or null on failure.
+#[no_mangle]
+pub extern "C" fn sway_ir_pass_names() -> *mut c_char {
+    // There is no error out parameter for `guard` to write to.
+    unsafe {
+        guard(ptr::null_mut(), ptr::null_mut(), || {
+            let pass_mgr = pass_manager();
+            let mut names = pass_mgr.pass_names().collect::<Vec<_>>();
+            names.sort_unstable();
+            Ok(into_c_string(names.join(",")))
+        })
+    }
+}
+
+/// Release a string returned by this API.  Null is ignored.
+///
+/// # Safety
+///
+/// `string` must be null or a string returned by this API which has not been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn sway_ir_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+fn pass_manager() -> PassManager {
+    let mut pass_mgr = PassManager::default();
+    register_known_passes(&mut pass_mgr);
+    pass_mgr
+}
+
+fn run(context: &mut Context, passes: &PassGroup) -> Result<bool, String> {
+    pass_manager()
+        .run(context, passes)
+        .map_err(|err| err.to_string())
+}
+
+/// Borrow the string argument `arg`, failing if it is null or not UTF-8.
+unsafe fn str_arg<'a>(arg: *const c_char, name: &str) -> Result<&'a str, String> {
+    if arg.is_null() {
+        return Err(format!("{name} is null"));
+    }
+    CStr::from_ptr(arg)
+        .to_str()
+        .map_err(|_| format!("{name} is not valid UTF-8"))
+}
+
+/// Return `string` as a C string owned by the caller.  Interior nulls are dropped.
+fn into_c_string(string: String) -> *mut c_char {
+    let bytes = string.into_bytes().into_iter().filter(|byte| *byte != 0);
+    CString::new(bytes.collect::<Vec<_>>())
+        .expect("nulls were removed")
+        .into_raw()
+}
+
+/// Run `f`, returning `default` and setting `error` if it fails or panics.
+unsafe fn guard<T>(
+    error: *mut *mut c_char,
+    default: T,
+    f: impl FnOnce() -> Result<T, String>,
+) -> T {
+    let message = match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => return value,
+        Ok(Err(message)) => message,
+        Err(panic) => panic
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "panicked".to_string()),
+    };
+    if !error.is_null() {
+        *error = into_c_string(message);
+    }
+    default
+}
+
+/// Run `f` on the IR of `context` as [`guard`] does, failing if `context` is null or poisoned.  A
+/// panic in `f` poisons `context`.
+unsafe fn guard_context<T>(
+    context: *mut SwayIrContext,
+    error: *mut *mut c_char,
+    default: T,
+    f: impl FnOnce(&mut Context) -> Result<T, String>,
+) -> T {
+    guard(error, default, || {
+        let context = context.as_mut().ok_or("context is null")?;
+        if context.poisoned {
+            return Err(POISONED_ERROR.to_string());
+        }
+        catch_unwind(AssertUnwindSafe(|| f(&mut context.context))).unwrap_or_else(|panic| {
+            context.poisoned = true;
+            resume_unwind(panic)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panics_poison_the_context() {
+        unsafe {
+            let context = sway_ir_context_new();
+            let mut error = ptr::null_mut();
+            let result = guard_context(context, &mut error, -1, |_| -> Result<c_int, String> {
+                panic!("pass failed")
+            });
+            assert_eq!(result, -1);
+            assert_eq!(CString::from_raw(error).to_str().unwrap(), "pass failed");
+
+            let mut error = ptr::null_mut();
+            assert_eq!(sway_ir_verify(context, &mut error), -1);
+            assert_eq!(CString::from_raw(error).to_str().unwrap(), POISONED_ERROR);
+            assert!(sway_ir_print(context).is_null());
+            sway_ir_context_free(context);
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use std::{
+    ffi::{c_char, CStr, CString},
+    ptr,
+};
+
+use sway_ir_sys::*;
+
+const IR: &str = r#"
+script {
+    fn main() -> u64 {
+        entry():
+        v0 = const u64 1
+        v1 = call id(v0)
+        ret u64 v1
+    }
+
+    fn id(x: u64) -> u64 {
+        entry(x: u64):
+        ret u64 x
+    }
+}
+"#;
+
+/// Take ownership of a string returned by the API.
+unsafe fn take_string(string: *mut c_char) -> String {
+    assert!(!string.is_null());
+    let owned = CStr::from_ptr(string).to_str().unwrap().to_string();
+    sway_ir_string_free(string);
+    owned
+}
+
+#[test]
+fn parse_optimize_and_print() {
+    unsafe {
+        let source = CString::new(IR).unwrap();
+        let mut error = ptr::null_mut();
+        let context = sway_ir_parse(source.as_ptr(), &mut error);
+        assert!(!context.is_null());
+        assert!(error.is_null());
+
+        assert_eq!(sway_ir_verify(context, &mut error), 0);
+
+        let passes = CString::new("inline_main, dce").unwrap();
+        assert_eq!(sway_ir_run_passes(context, passes.as_ptr(), &mut error), 1);
+        assert!(error.is_null());
+
+        let printed = take_string(sway_ir_print(context));
+        assert!(!printed.contains("call id"), "{printed}");
+
+        sway_ir_context_free(context);
+    }
+}
+
+#[test]
+fn build_from_an_empty_context() {
+    unsafe {
+        let context = sway_ir_context_new();
+        let mut error = ptr::null_mut();
+        let kind = CString::new("script").unwrap();
+        assert_eq!(sway_ir_add_module(context, kind.as_ptr(), &mut error), 0);
+
+        let main = CString::new(
+            r#"
+            entry fn main() -> u64 {
+                entry():
+                v0 = const u64 1
+                v1 = call id(v0)
+                ret u64 v1
+            }
+            "#,
+        )
+        .unwrap();
+        let id = CString::new(
+            r#"
+            fn id(x: u64) -> u64 {
+                entry(x: u64):
+                ret u64 x
+            }
+            "#,
+        )
+        .unwrap();
+        // `main` calls `id`, so it can only be added once `id` is.
+        assert_eq!(sway_ir_add_function(context, main.as_ptr(), &mut error), -1);
+        assert!(!take_string(error).is_empty());
+        let mut error = ptr::null_mut();
+        assert_eq!(sway_ir_add_function(context, id.as_ptr(), &mut error), 0);
+        assert_eq!(sway_ir_add_function(context, main.as_ptr(), &mut error), 0);
+        assert_eq!(sway_ir_verify(context, &mut error), 0);
+        assert!(error.is_null());
+
+        let printed = take_string(sway_ir_print(context));
+        assert!(printed.contains("script {"), "{printed}");
+        assert!(printed.contains("entry fn main() -> u64"), "{printed}");
+        assert!(printed.contains("call id(v0)"), "{printed}");
+
+        sway_ir_context_free(context);
+    }
+}
+
+#[test]
+fn building_errors_are_reported() {
+    unsafe {
+        let context = sway_ir_context_new();
+        let id = CString::new("fn id(x: u64) -> u64 {\n entry(x: u64):\n ret u64 x\n}").unwrap();
+        let mut error = ptr::null_mut();
+        assert_eq!(sway_ir_add_function(context, id.as_ptr(), &mut error), -1);
+        assert_eq!(take_string(error), "context has no modules");
+
+        let kind = CString::new("module").unwrap();
+        let mut error = ptr::null_mut();
+        assert_eq!(sway_ir_add_module(context, kind.as_ptr(), &mut error), -1);
+        assert_eq!(take_string(error), "unknown module kind 'module'");
+        sway_ir_context_free(context);
+    }
+}
+
+#[test]
+fn errors_are_reported() {
+    unsafe {
+        let source = CString::new("script {").unwrap();
+        let mut error = ptr::null_mut();
+        assert!(sway_ir_parse(source.as_ptr(), &mut error).is_null());
+        assert!(!take_string(error).is_empty());
+
+        let source = CString::new(IR).unwrap();
+        let context = sway_ir_parse(source.as_ptr(), ptr::null_mut());
+        let passes = CString::new("no_such_pass").unwrap();
+        let mut error = ptr::null_mut();
+        assert_eq!(sway_ir_run_passes(context, passes.as_ptr(), &mut error), -1);
+        assert_eq!(take_string(error), "unknown pass 'no_such_pass'");
+        sway_ir_context_free(context);
+    }
+}
+
+#[test]
+fn pass_names() {
+    let names = unsafe { take_string(sway_ir_pass_names()) };
+    assert!(names.split(',').any(|name| name == "dce"), "{names}");
+}
@@ -1,9 +1,25 @@
-use std::path::PathBuf;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    path::PathBuf,
+};
+
+use rustc_hash::FxHashMap;
 
 use sway_ir::{
-    create_const_combine_pass, create_dce_pass, create_dom_fronts_pass, create_dominators_pass,
-    create_mem2reg_pass, create_postorder_pass, create_simplify_cfg_pass, optimize as opt, Context,
-    PassGroup, PassManager,
+    compute_module_effects, compute_module_storage_access, create_const_combine_pass,
+    create_coverage_pass, create_dce_pass, create_dom_fronts_pass, create_dominators_pass,
+    create_dse_pass, create_egraph_pass, create_gas_checkpoints_pass, create_instcombine_pass,
+    create_mem2reg_pass, create_o1_pass_group, create_o2_pass_group, create_os_pass_group,
+    create_oz_pass_group, create_packed_abi_pass, create_postorder_pass, create_rewrite_pass,
+    create_simplify_cfg_pass, create_split_critical_edges_pass, create_tco_pass,
+    decode_function_into, freeze_configurables, insert_after_each, optimize as opt, parse_rules,
+    register_known_passes, shuffle_pass_group, AliasAnalysis, AliasResult, BasicAliasAnalysis,
+    BinaryOpKind, Block, Constant, Context, DebugInfo, DebugVariable, DotCfgOptions, Function,
+    FunctionCache, FunctionEffects, Inline, InlineCost, InlinePolicy, InlinedFrom, InsertionPoint,
+    Instruction, InterpretArg, IrError, MemoryAccess, MemoryLocation, MemoryUser, MetadataKind,
+    Metadatum, PassGroup, PassManager, PrinterOptions, SourceSpan, StorageKey, Value, ADCE_NAME,
+    BINARY_VERSION, CSE_NAME, DCE_NAME, INLINE_NONPREDICATE_NAME, LICM_NAME, MARK_PURE_NAME,
+    MEM2REG_NAME, RLE_NAME, UNROLL_NAME,
 };
 
 // -------------------------------------------------------------------------------------------------
@@ -131,6 +147,120 @@ fn constants() {
 
 // -------------------------------------------------------------------------------------------------
 
+#[test]
+fn freeze() {
+    run_tests("freeze", |_first_line, ir: &mut Context| {
+        let module = ir.module_iter().next().unwrap();
+        let values = BTreeMap::from([("LIMIT".to_owned(), "0x0".to_owned())]);
+        assert_eq!(
+            freeze_configurables(ir, &module, &values).unwrap(),
+            vec!["LIMIT".to_owned()]
+        );
+        let mut pass_mgr = PassManager::default();
+        let mut pass_group = PassGroup::default();
+        let pass = pass_mgr.register(create_const_combine_pass());
+        pass_group.append_pass(pass);
+        pass_mgr.run(ir, &pass_group).unwrap()
+    })
+}
+
+#[test]
+fn freeze_errors() {
+    let error = |name: &str, value: &str| {
+        let mut ir = sway_ir::parser::parse(
+            "script {\n\
+                 ENABLED = config bool true\n\
+                 LIMIT = config u64 5\n\
+             }",
+        )
+        .unwrap();
+        let module = ir.module_iter().next().unwrap();
+        let values = BTreeMap::from([(name.to_owned(), value.to_owned())]);
+        freeze_configurables(&mut ir, &module, &values)
+            .unwrap_err()
+            .to_string()
+    };
+    assert_eq!(
+        error("ENABLED", "yes"),
+        "Invalid configurable value: configurable 'ENABLED': 'yes' is not a bool"
+    );
+    assert_eq!(
+        error("LIMIT", "five"),
+        "Invalid configurable value: configurable 'LIMIT': 'five' is not an integer"
+    );
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn instcombine() {
+    run_tests("instcombine", |_first_line, ir: &mut Context| {
+        let mut pass_mgr = PassManager::default();
+        let mut pass_group = PassGroup::default();
+        let pass = pass_mgr.register(create_instcombine_pass());
+        pass_group.append_pass(pass);
+        pass_mgr.run(ir, &pass_group).unwrap()
+    })
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn pass_fuel() {
+    let source = r#"
+script {
+    fn main(a: u64) -> u64 {
+        entry(a: u64):
+        v0 = const u64 0
+        v1 = add a, v0
+        v2 = add v1, v0
+        v3 = add v2, v0
+        v4 = add v3, v0
+        ret u64 v4
+    }
+}
+"#;
+    let run = |fuel| {
+        let mut ir = sway_ir::parser::parse(source).unwrap();
+        ir.set_pass_fuel(fuel);
+        let mut pass_mgr = PassManager::default();
+        let mut pass_group = PassGroup::default();
+        let pass = pass_mgr.register(create_instcombine_pass());
+        pass_group.append_pass(pass);
+        pass_mgr.run(&mut ir, &pass_group).unwrap();
+        let adds = sway_ir::printer::to_string(&ir).matches(" add ").count();
+        let exhausted = pass_mgr
+            .fuel_exhausted()
+            .iter()
+            .map(|exhausted| (exhausted.pass, exhausted.function.clone()))
+            .collect::<Vec<_>>();
+        ir.verify().unwrap();
+        (adds, exhausted)
+    };
+
+    // Each rewrite costs a unit of fuel, and running out is reported but leaves valid IR.
+    assert_eq!(run(None), (0, vec![]));
+    assert_eq!(run(Some(4)), (0, vec![]));
+    assert_eq!(run(Some(2)), (2, vec![("instcombine", "main".to_owned())]));
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn egraph() {
+    run_tests("egraph", |_first_line, ir: &mut Context| {
+        let mut pass_mgr = PassManager::default();
+        let mut pass_group = PassGroup::default();
+        let egraph = pass_mgr.register(create_egraph_pass());
+        let dce = pass_mgr.register(create_dce_pass());
+        pass_group.append_pass(egraph);
+        pass_group.append_pass(dce);
+        pass_mgr.run(ir, &pass_group).unwrap()
+    })
+}
+
+// -------------------------------------------------------------------------------------------------
+
 #[allow(clippy::needless_collect)]
 #[test]
 fn simplify_cfg() {
@@ -145,6 +275,262 @@ fn simplify_cfg() {
 
 // -------------------------------------------------------------------------------------------------
 
+#[allow(clippy::needless_collect)]
+#[test]
+fn cse() {
+    run_tests("cse", |_first_line, ir: &mut Context| {
+        let mut pass_mgr = PassManager::default();
+        let mut pass_group = PassGroup::default();
+        register_known_passes(&mut pass_mgr);
+        pass_group.append_pass(CSE_NAME);
+        pass_mgr.run(ir, &pass_group).unwrap()
+    })
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn mark_pure() {
+    run_tests("mark_pure", |_first_line, ir: &mut Context| {
+        let mut pass_mgr = PassManager::default();
+        register_known_passes(&mut pass_mgr);
+        let mut pass_group = PassGroup::default();
+        pass_group.append_pass(MARK_PURE_NAME);
+        pass_mgr.run(ir, &pass_group).unwrap()
+    });
+
+    // The effects behind each function's purity.
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let source =
+        std::fs::read_to_string(format!("{manifest_dir}/tests/mark_pure/effects.ir")).unwrap();
+    let ir = sway_ir::parser::parse(&source).unwrap();
+    let module = ir.module_iter().next().unwrap();
+    let effects = compute_module_effects(&ir, module);
+    let effects_of = |name: &str| {
+        module
+            .function_iter(&ir)
+            .find(|function| function.get_name(&ir) == name)
+            .map(|function| effects[&function])
+            .unwrap()
+    };
+    assert_eq!(effects_of("square"), FunctionEffects::default());
+    assert_eq!(
+        effects_of("read_storage"),
+        FunctionEffects {
+            reads_storage: true,
+            ..FunctionEffects::default()
+        }
+    );
+    let write_arg = effects_of("write_arg");
+    assert!(write_arg.writes_memory && !write_arg.writes_storage);
+    assert!(effects_of("log_it").other_side_effects);
+    assert_eq!(effects_of("calls_log_it"), effects_of("log_it"));
+    for name in ["reverts", "spin", "recurse"] {
+        assert!(effects_of(name).may_not_return, "{name}");
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn storage_access() {
+    let ir = sway_ir::parser::parse(
+        r#"
+contract {
+    fn field() -> u64 {
+        local b256 key
+
+        entry():
+        v0 = get_local b256 key
+        v1 = state_load_word key v0, !1
+        ret u64 v1
+    }
+
+    fn two_slots() -> () {
+        local b256 key = const b256 0x0000000000000000000000000000000000000000000000000000000000000001
+        local b256 val
+
+        entry():
+        v0 = get_local b256 key
+        v1 = get_local b256 val
+        v2 = const u64 2
+        state_store_quad_word v1, key v0, v2
+        v3 = const unit ()
+        ret () v3
+    }
+
+    fn store_some(n: u64) -> () {
+        local b256 key = const b256 0x0000000000000000000000000000000000000000000000000000000000000001
+        local b256 val
+
+        entry(n: u64):
+        v0 = get_local b256 key
+        v1 = get_local b256 val
+        state_store_quad_word v1, key v0, n
+        v2 = const unit ()
+        ret () v2
+    }
+
+    fn asm_read(key: u64) -> u64 {
+        entry(key: u64):
+        v0 = asm(r1: key, r2) -> u64 r2 {
+            srw    r2 r1
+        }
+        ret u64 v0
+    }
+
+    fn reader() -> u64 {
+        entry():
+        v0 = call field()
+        v1 = call asm_read(v0)
+        ret u64 v1
+    }
+
+    fn abi_method() -> u64 {
+        entry():
+        v0 = call two_slots()
+        v1 = call reader()
+        ret u64 v1
+    }
+}
+
+!1 = state_index 3
+"#,
+    )
+    .unwrap();
+    let module = ir.module_iter().next().unwrap();
+    let access = compute_module_storage_access(&ir, module);
+    let access_of = |name: &str| {
+        module
+            .function_iter(&ir)
+            .find(|function| function.get_name(&ir) == name)
+            .map(|function| access[&function].clone())
+            .unwrap()
+    };
+    let slot = |n: u8| {
+        let mut key = [0; 32];
+        key[31] = n;
+        StorageKey::Slot(key)
+    };
+
+    // Keys come from the storage key metadata, or a constant key local.
+    assert_eq!(
+        access_of("field").reads,
+        BTreeSet::from([StorageKey::Index(3)])
+    );
+    let two_slots = access_of("two_slots");
+    assert!(two_slots.reads.is_empty());
+    assert_eq!(two_slots.writes, BTreeSet::from([slot(1), slot(2)]));
+
+    // An unknown number of slots or a storage opcode in ASM may access anything.
+    assert_eq!(
+        access_of("store_some").writes,
+        BTreeSet::from([StorageKey::Unknown])
+    );
+    assert_eq!(
+        access_of("asm_read").reads,
+        BTreeSet::from([StorageKey::Unknown])
+    );
+
+    // Callees' accesses are included.
+    let reader = access_of("reader");
+    assert!(reader.is_read_only());
+    assert_eq!(
+        reader.reads,
+        BTreeSet::from([StorageKey::Index(3), StorageKey::Unknown])
+    );
+    let abi_method = access_of("abi_method");
+    assert!(!abi_method.is_read_only());
+    assert_eq!(abi_method.reads, reader.reads);
+    assert_eq!(abi_method.writes, two_slots.writes);
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[allow(clippy::needless_collect)]
+#[test]
+fn rle() {
+    run_tests("rle", |_first_line, ir: &mut Context| {
+        let mut pass_mgr = PassManager::default();
+        register_known_passes(&mut pass_mgr);
+        let mut pass_group = PassGroup::default();
+        pass_group.append_pass(RLE_NAME);
+        pass_mgr.run(ir, &pass_group).unwrap()
+    })
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[allow(clippy::needless_collect)]
+#[test]
+fn dse() {
+    run_tests("dse", |_first_line, ir: &mut Context| {
+        let mut pass_mgr = PassManager::default();
+        let mut pass_group = PassGroup::default();
+        let pass = pass_mgr.register(create_dse_pass());
+        pass_group.append_pass(pass);
+        pass_mgr.run(ir, &pass_group).unwrap()
+    })
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[allow(clippy::needless_collect)]
+#[test]
+fn licm() {
+    run_tests("licm", |_first_line, ir: &mut Context| {
+        let mut pass_mgr = PassManager::default();
+        let mut pass_group = PassGroup::default();
+        register_known_passes(&mut pass_mgr);
+        pass_group.append_pass(LICM_NAME);
+        pass_mgr.run(ir, &pass_group).unwrap()
+    })
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[allow(clippy::needless_collect)]
+#[test]
+fn tco() {
+    run_tests("tco", |_first_line, ir: &mut Context| {
+        let mut pass_mgr = PassManager::default();
+        let mut pass_group = PassGroup::default();
+        let pass = pass_mgr.register(create_tco_pass());
+        pass_group.append_pass(pass);
+        pass_mgr.run(ir, &pass_group).unwrap()
+    })
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[allow(clippy::needless_collect)]
+#[test]
+fn unroll() {
+    run_tests("unroll", |_first_line, ir: &mut Context| {
+        let mut pass_mgr = PassManager::default();
+        register_known_passes(&mut pass_mgr);
+        let mut pass_group = PassGroup::default();
+        pass_group.append_pass(UNROLL_NAME);
+        pass_mgr.run(ir, &pass_group).unwrap()
+    })
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[allow(clippy::needless_collect)]
+#[test]
+fn critical_edges() {
+    run_tests("critical_edges", |_first_line, ir: &mut Context| {
+        let mut pass_mgr = PassManager::default();
+        let mut pass_group = PassGroup::default();
+        let pass = pass_mgr.register(create_split_critical_edges_pass());
+        pass_group.append_pass(pass);
+        pass_mgr.run(ir, &pass_group).unwrap()
+    })
+}
+
+// -------------------------------------------------------------------------------------------------
+
 #[allow(clippy::needless_collect)]
 #[test]
 fn dce() {
@@ -159,6 +545,20 @@ fn dce() {
 
 // -------------------------------------------------------------------------------------------------
 
+#[allow(clippy::needless_collect)]
+#[test]
+fn adce() {
+    run_tests("adce", |_first_line, ir: &mut Context| {
+        let mut pass_mgr = PassManager::default();
+        let mut pass_group = PassGroup::default();
+        register_known_passes(&mut pass_mgr);
+        pass_group.append_pass(ADCE_NAME);
+        pass_mgr.run(ir, &pass_group).unwrap()
+    })
+}
+
+// -------------------------------------------------------------------------------------------------
+
 #[allow(clippy::needless_collect)]
 #[test]
 fn mem2reg() {
@@ -176,6 +576,145 @@ fn mem2reg() {
 
 // -------------------------------------------------------------------------------------------------
 
+#[test]
+fn coverage() {
+    run_tests("coverage", |_first_line, ir: &mut Context| {
+        let mut pass_mgr = PassManager::default();
+        let mut pass_group = PassGroup::default();
+        let pass = pass_mgr.register(create_coverage_pass());
+        pass_group.append_pass(pass);
+        pass_mgr.run(ir, &pass_group).unwrap()
+    })
+}
+
+#[test]
+fn coverage_across_modules() {
+    // The counters and branches of each module are numbered after those of the modules
+    // instrumented before it, so that they're unique across the context.
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let input =
+        std::fs::read_to_string(format!("{manifest_dir}/tests/coverage/branches.ir")).unwrap();
+    let mut ir = sway_ir::parser::parse(&input).unwrap();
+    let first = ir.module_iter().next().unwrap();
+
+    // Give a second module the same branching function.
+    let second = sway_ir::Module::new(&mut ir, sway_ir::Kind::Library);
+    let (bool_ty, u64_ty) = (sway_ir::Type::get_bool(&ir), sway_ir::Type::get_uint64(&ir));
+    let main = sway_ir::Function::new(
+        &mut ir,
+        second,
+        "main".to_string(),
+        vec![("b".to_string(), bool_ty, false, None)],
+        u64_ty,
+        None,
+        false,
+        false,
+        None,
+    );
+    let entry = main.get_entry_block(&ir);
+    let (block0, block1, block2) = (
+        main.create_block(&mut ir, None),
+        main.create_block(&mut ir, None),
+        main.create_block(&mut ir, None),
+    );
+    let cond = main.get_arg(&ir, "b").unwrap();
+    entry
+        .ins(&mut ir)
+        .conditional_branch(cond, block0, block1, vec![], vec![]);
+    for (block, n) in [(block0, 1), (block1, 2)] {
+        let value = sway_ir::Constant::get_uint(&mut ir, 64, n);
+        block.ins(&mut ir).branch(block2, vec![value]);
+    }
+    let result_idx = block2.new_arg(&mut ir, u64_ty, false);
+    let result = block2.get_arg(&ir, result_idx).unwrap();
+    block2.ins(&mut ir).ret(result, u64_ty);
+
+    let points = [first, second]
+        .into_iter()
+        .flat_map(|module| sway_ir::instrument_coverage(&mut ir, module))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        points.iter().map(|point| point.id).collect::<Vec<_>>(),
+        (0..8).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        points
+            .iter()
+            .filter_map(|point| point.branch.as_ref().map(|branch| branch.id))
+            .collect::<Vec<_>>(),
+        [0, 0, 1, 1]
+    );
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn gas_checkpoints() {
+    run_tests("gas_checkpoints", |_first_line, ir: &mut Context| {
+        let mut pass_mgr = PassManager::default();
+        let mut pass_group = PassGroup::default();
+        let pass = pass_mgr.register(create_gas_checkpoints_pass());
+        pass_group.append_pass(pass);
+        pass_mgr.run(ir, &pass_group).unwrap()
+    })
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn packed_abi() {
+    run_tests("packed_abi", |_first_line, ir: &mut Context| {
+        let mut pass_mgr = PassManager::default();
+        let mut pass_group = PassGroup::default();
+        let pass = pass_mgr.register(create_packed_abi_pass());
+        pass_group.append_pass(pass);
+        pass_mgr.run(ir, &pass_group).unwrap()
+    })
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn rewrite() {
+    run_tests("rewrite", |_first_line, ir: &mut Context| {
+        let rules = "; Additions are cheaper than multiplications.\n\
+                     (rule mul_two (mul x 2) (add x x))";
+        ir.set_rewrite_rules(parse_rules(rules).unwrap());
+        let mut pass_mgr = PassManager::default();
+        let mut pass_group = PassGroup::default();
+        let pass = pass_mgr.register(create_rewrite_pass());
+        pass_group.append_pass(pass);
+        pass_mgr.run(ir, &pass_group).unwrap()
+    })
+}
+
+#[test]
+fn rewrite_rule_errors() {
+    let error = |source: &str| parse_rules(source).unwrap_err().to_string();
+    assert_eq!(
+        error("(rule swap (sub x y) (sub y z))"),
+        "Invalid rewrite rule: rule 'swap': 'z' is not bound by the pattern"
+    );
+    assert_eq!(
+        error("(rule bad_cmp (add x y) (eq x y))"),
+        "Invalid rewrite rule: rule 'bad_cmp': 'eq' is a bool where an integer is required"
+    );
+    assert_eq!(
+        error("(rule bad_add (add (eq x y) 1) x)"),
+        "Invalid rewrite rule: rule 'bad_add': 'eq' is a bool where an integer is required"
+    );
+    assert_eq!(
+        error("\n(rule nop (nop x 1) x)"),
+        "Invalid rewrite rule: line 2: unknown operation 'nop'"
+    );
+    assert_eq!(
+        error("(rule open (add x 0) x"),
+        "Invalid rewrite rule: line 1: unclosed '('"
+    );
+}
+
+// -------------------------------------------------------------------------------------------------
+
 #[test]
 fn serialize() {
     // This isn't running a pass, it's just confirming that the IR can be loaded and printed, and
@@ -184,3 +723,3600 @@ fn serialize() {
 }
 
 // -------------------------------------------------------------------------------------------------
+
+#[test]
+fn content_hash() {
+    // The content hashes must survive a round trip through the printer and parser, as the arena
+    // handles will differ.
+    let module_hashes = |ir: &Context| {
+        ir.module_iter()
+            .map(|module| module.content_hash(ir))
+            .collect::<Vec<_>>()
+    };
+
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let dir: PathBuf = format!("{manifest_dir}/tests/serialize").into();
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let input = std::fs::read_to_string(&path).unwrap();
+        let ir = sway_ir::parser::parse(&input).unwrap();
+        let round_tripped = sway_ir::parser::parse(&sway_ir::printer::to_string(&ir)).unwrap();
+        assert_eq!(
+            module_hashes(&ir),
+            module_hashes(&round_tripped),
+            "content hash changed for {}",
+            path.display()
+        );
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+// Every .ir file under the tests directory.
+fn corpus_files() -> Vec<PathBuf> {
+    fn ir_files(dir: PathBuf, files: &mut Vec<PathBuf>) {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                ir_files(path, files);
+            } else if path.extension().map_or(false, |ext| ext == "ir") {
+                files.push(path);
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+    ir_files(
+        format!("{}/tests", env!("CARGO_MANIFEST_DIR")).into(),
+        &mut files,
+    );
+    files
+}
+
+#[test]
+fn round_trip() {
+    // Every test module must parse back from its printed form to an equivalent context.
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    for path in corpus_files() {
+        let ir = sway_ir::parser::parse(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let printed = sway_ir::printer::to_string(&ir);
+        let round_tripped = sway_ir::parser::parse(&printed).unwrap();
+        assert!(
+            ir.eq_modulo_handles(&round_tripped),
+            "round trip changed {}:\n{printed}",
+            path.display()
+        );
+    }
+
+    // Whereas any change to what's held is noticed, even where it isn't otherwise visible.
+    let source =
+        std::fs::read_to_string(format!("{manifest_dir}/tests/serialize/round_trip.ir")).unwrap();
+    let ir = sway_ir::parser::parse(&source).unwrap();
+    for (from, to) in [
+        ("!1 = 1", "!1 = 2"),
+        ("exit(inout q: u64)", "exit(q: u64)"),
+        ("get<01020304>", "get<01020305>"),
+        ("const u64 3", "const u64 4"),
+        (", cold]", "]"),
+        ("lib::limits::LIMIT", "lib::LIMIT"),
+    ] {
+        let changed = sway_ir::parser::parse(&source.replace(from, to)).unwrap();
+        assert!(!ir.eq_modulo_handles(&changed), "{from} -> {to}");
+    }
+}
+
+#[test]
+fn binary_round_trip() {
+    // Every test module must decode from its binary encoding to an equivalent context.
+    for path in corpus_files() {
+        let ir = sway_ir::parser::parse(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let bytes = ir.to_bytes().unwrap();
+        let decoded = Context::from_bytes(&bytes).unwrap();
+        assert!(
+            ir.eq_modulo_handles(&decoded),
+            "binary round trip changed {}",
+            path.display()
+        );
+        decoded.verify().unwrap();
+
+        // A single module is decoded into a context of its own.
+        let module = ir.module_iter().next().unwrap();
+        let decoded = Context::from_bytes(&module.to_bytes(&ir).unwrap()).unwrap();
+        assert!(ir.eq_modulo_handles(&decoded), "{}", path.display());
+
+        // A single function is decoded over the same function of another context.
+        let mut other = sway_ir::parser::parse(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let other_module = other.module_iter().next().unwrap();
+        for (function, other_function) in module
+            .function_iter(&ir)
+            .zip(other_module.function_iter(&other))
+        {
+            let bytes = function.to_bytes(&ir).unwrap();
+            decode_function_into(&mut other, other_function, &bytes).unwrap();
+            assert!(matches!(
+                decode_function_into(&mut other, other_function, &bytes[..bytes.len() - 1]),
+                Err(IrError::BinaryDecodeFailed(_))
+            ));
+        }
+        assert!(ir.eq_modulo_handles(&other), "{}", path.display());
+        other.verify().unwrap();
+    }
+
+    // Anything which isn't a whole encoding of the current version is rejected.
+    let source = std::fs::read_to_string(format!(
+        "{}/tests/serialize/round_trip.ir",
+        env!("CARGO_MANIFEST_DIR")
+    ))
+    .unwrap();
+    let bytes = sway_ir::parser::parse(&source).unwrap().to_bytes().unwrap();
+
+    for not_ir in [source.as_bytes(), &bytes[..2]] {
+        assert!(matches!(
+            Context::from_bytes(not_ir),
+            Err(IrError::BinaryNotIr)
+        ));
+    }
+
+    let mut future = bytes.clone();
+    future[4..8].copy_from_slice(&(BINARY_VERSION + 1).to_le_bytes());
+    assert!(matches!(
+        Context::from_bytes(&future),
+        Err(IrError::BinaryVersionMismatch(found, expected))
+            if found == BINARY_VERSION + 1 && expected == BINARY_VERSION
+    ));
+
+    for len in [6, 8, bytes.len() / 2, bytes.len() - 1] {
+        assert!(matches!(
+            Context::from_bytes(&bytes[..len]),
+            Err(IrError::BinaryDecodeFailed(_))
+        ));
+    }
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(matches!(
+        Context::from_bytes(&trailing),
+        Err(IrError::BinaryDecodeFailed(_))
+    ));
+}
+
+#[test]
+fn module_to_json() {
+    let ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main(a !1: u64) -> u64 {
+        local u64 x = const u64 0
+
+        entry(a !1: u64):
+        v0 = get_local u64 x
+        store a to v0
+        v1 = const u64 5
+        v2 = cmp eq a v1
+        cbr v2, yes(a), no()
+
+        yes(b: u64):
+        ret u64 b
+
+        no():
+        v3 = load v0, !2
+        ret u64 v3
+    }
+}
+
+!1 = "arg"
+!2 = span !1 10 20
+"#,
+    )
+    .unwrap();
+    let json = ir.module_iter().next().unwrap().to_json(&ir);
+
+    assert_eq!(json["kind"], "script");
+    assert_eq!(
+        json["types"]["u64"],
+        serde_json::json!({ "kind": "uint", "width": 64 })
+    );
+
+    let main = &json["functions"][0];
+    assert_eq!(main["name"], "main");
+    assert_eq!(main["args"][0]["name"], "a");
+    assert_eq!(
+        json["metadata"][main["args"][0]["metadata"].as_u64().unwrap() as usize]["string"],
+        "arg"
+    );
+    assert_eq!(main["locals"][0]["initializer"]["value"], 0);
+
+    let entry = &main["blocks"][0]["instructions"];
+    assert_eq!(entry[1]["op"], "store");
+    assert_eq!(entry[1]["name"], serde_json::Value::Null);
+    assert_eq!(entry[2]["op"], "cmp");
+    assert_eq!(entry[2]["rhs"]["constant"]["value"], 5);
+    assert_eq!(
+        entry[3]["true_target"],
+        serde_json::json!({ "block": "yes", "args": [{ "value": "a" }] })
+    );
+
+    let no = &main["blocks"][2];
+    assert_eq!(no["preds"], serde_json::json!(["entry"]));
+    let span = &json["metadata"][no["instructions"][0]["metadata"].as_u64().unwrap() as usize];
+    assert_eq!(span["struct"], "span");
+    assert_eq!(span["fields"][2]["integer"], 20);
+
+    // Every named instruction is named as it's printed.
+    for path in corpus_files() {
+        let ir = sway_ir::parser::parse(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let printed = sway_ir::printer::to_string(&ir);
+        for module in ir.module_iter() {
+            let json = module.to_json(&ir);
+            for function in json["functions"].as_array().unwrap() {
+                for block in function["blocks"].as_array().unwrap() {
+                    for ins in block["instructions"].as_array().unwrap() {
+                        if let Some(name) = ins["name"].as_str() {
+                            let op = ins["op"].as_str().unwrap();
+                            assert!(
+                                printed.contains(&format!("{name} = {op}")),
+                                "{name} = {op} in {}",
+                                path.display()
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn taint() {
+    let ir = sway_ir::parser::parse(
+        r#"
+contract {
+    fn set<01020304>(key: b256, value: u64) -> () {
+        entry(key: b256, value: u64):
+        v0 = call store(key, value)
+        ret () v0
+    }
+
+    fn store(key: b256, value: u64) -> () {
+        local b256 slot
+
+        entry(key: b256, value: u64):
+        v0 = get_local b256 slot
+        store key to v0
+        state_store_word value, key v0
+        v1 = const unit ()
+        ret () v1
+    }
+
+    fn insert<05060708>(key: b256, value: u64) -> () {
+        local b256 slot
+
+        entry(key: b256, value: u64):
+        v0 = get_local b256 slot
+        v1 = const u64 32
+        v2 = asm(dst: v0, src: key, len: v1) -> b256 dst {
+            s256   dst src len
+        }
+        state_store_word value, key v2
+        v3 = const unit ()
+        ret () v3
+    }
+
+    fn set_owner<090a0b0c>(owner: u64) -> () {
+        local b256 slot = const b256 0x0000000000000000000000000000000000000000000000000000000000000001
+
+        entry(owner: u64):
+        v0 = get_local b256 slot
+        state_store_word owner, key v0
+        v1 = const unit ()
+        ret () v1
+    }
+}
+"#,
+    )
+    .unwrap();
+    let module = ir.module_iter().next().unwrap();
+    let taint = sway_ir::compute_module_taint(&ir, module);
+    let writer = |inst: &sway_ir::Value| {
+        module
+            .function_iter(&ir)
+            .find(|function| function.instruction_iter(&ir).any(|(_, ins)| ins == *inst))
+            .map(|function| function.get_name(&ir).to_string())
+            .unwrap()
+    };
+
+    // The key `set` passes to `store` is the caller's, so the caller may write to any slot.  The
+    // key `insert` writes to is hashed from the caller's and the key `set_owner` writes to is
+    // constant, so the caller only chooses the values they write.
+    assert_eq!(
+        taint
+            .tainted_storage_writes
+            .iter()
+            .map(writer)
+            .collect::<Vec<_>>(),
+        ["store"]
+    );
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn function_cache() {
+    // `b` is the only function to change between builds, so `a` should be served from the cache
+    // while `b` and its caller `main` are optimized again.
+    let source = |b_addend: u64| {
+        format!(
+            r#"
+script {{
+    entry fn main() -> u64 {{
+        entry():
+        v0 = const u64 1
+        v1 = call a(v0)
+        v2 = call b(v1)
+        ret u64 v2
+    }}
+
+    fn a(x: u64) -> u64, !0 {{
+        local u64 t
+        entry(x: u64):
+        v0 = get_local u64 t
+        store x to v0
+        v1 = load v0
+        v2 = const u64 2
+        v3 = add v1, v2
+        ret u64 v3
+    }}
+
+    fn b(x: u64) -> u64, !1 {{
+        local u64 t
+        entry(x: u64):
+        v0 = get_local u64 t
+        store x to v0
+        v1 = load v0
+        v2 = const u64 {b_addend}
+        v3 = add v1, v2
+        ret u64 v3
+    }}
+}}
+
+!0 = inline "never"
+!1 = inline "never"
+"#
+        )
+    };
+    let optimize = |ir: &mut Context| {
+        let mut pass_mgr = PassManager::default();
+        register_known_passes(&mut pass_mgr);
+        pass_mgr.run(ir, &create_o2_pass_group(false)).unwrap();
+    };
+
+    let dir = std::env::temp_dir().join(format!("sway-ir-function-cache-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let cache = FunctionCache::new(dir.clone(), String::new());
+    // Build with the cache, returning the optimized IR and the names of the cached functions.
+    let build = |input: &str| {
+        let mut ir = sway_ir::parser::parse(input).unwrap();
+        let keys = cache.load(&mut ir);
+        let cached = keys
+            .keys()
+            .filter(|function| function.is_optimized(&ir))
+            .map(|function| function.get_name(&ir).to_string())
+            .collect::<Vec<_>>();
+        optimize(&mut ir);
+        cache.store(&ir, &keys).unwrap();
+        (sway_ir::printer::to_string(&ir), cached)
+    };
+
+    let (_, cached) = build(&source(3));
+    assert!(cached.is_empty());
+
+    let edited = source(4);
+    let (incremental, cached) = build(&edited);
+    assert_eq!(cached, ["a"]);
+
+    // The cached `a` was optimized, and the result matches a clean build.
+    let mut clean = sway_ir::parser::parse(&edited).unwrap();
+    optimize(&mut clean);
+    assert!(!incremental.contains("get_local"));
+    assert_eq!(incremental, sway_ir::printer::to_string(&clean));
+
+    // Nothing has changed since, so everything is served from the cache.
+    let (_, cached) = build(&edited);
+    assert_eq!(cached, ["main", "a", "b"]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn estimate_gas() {
+    let ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main() -> u64 {
+        local b256 slot
+
+        entry():
+        v0 = get_local b256 slot
+        v1 = state_load_word key v0
+        v2 = call double(v1)
+        v3 = call double(v2)
+        ret u64 v3
+    }
+
+    fn double(x: u64) -> u64 {
+        entry(x: u64):
+        v0 = add x, x
+        ret u64 v0
+    }
+}
+"#,
+    )
+    .unwrap();
+    let estimates = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .map(|function| {
+            let estimate = sway_ir::estimate_gas(&ir, &function);
+            (function.get_name(&ir).to_string(), estimate)
+        })
+        .collect::<Vec<_>>();
+    // Storage access dominates, and each call adds the callee along with its own overhead.
+    assert_eq!(
+        estimates,
+        vec![("main".to_string(), 276), ("double".to_string(), 2)]
+    );
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn inline_policy() {
+    let source = r#"
+script {
+    fn main(a: u64) -> u64 {
+        entry(a: u64):
+        v0 = call heavy(a)
+        v1 = call light(v0)
+        ret u64 v1
+    }
+
+    fn heavy(x: u64) -> u64 {
+        local b256 slot
+
+        entry(x: u64):
+        v0 = get_local b256 slot
+        v1 = state_load_word key v0
+        v2 = add x, v1
+        v3 = mul v2, v2
+        v4 = call light(v3)
+        ret u64 v4
+    }
+
+    fn light(y: u64) -> u64 {
+        entry(y: u64):
+        v0 = add y, y
+        ret u64 v0
+    }
+}
+"#;
+    let callees = |ir: &Context| {
+        ir.module_iter()
+            .flat_map(|module| module.function_iter(ir))
+            .flat_map(|function| function.instruction_iter(ir))
+            .filter_map(|(_block, inst)| match inst.get_instruction(ir) {
+                Some(Instruction::Call(callee, _)) => Some(callee.get_name(ir).to_string()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+    let inline = |ir: &mut Context| {
+        let mut pass_mgr = PassManager::default();
+        register_known_passes(&mut pass_mgr);
+        let mut pass_group = PassGroup::default();
+        pass_group.append_pass(INLINE_NONPREDICATE_NAME);
+        pass_mgr.run(ir, &pass_group).unwrap();
+    };
+
+    let ir = sway_ir::parser::parse(source).unwrap();
+    let heavy = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .find(|function| function.get_name(&ir) == "heavy")
+        .unwrap();
+    assert_eq!(
+        heavy.inline_cost(&ir),
+        InlineCost {
+            weight: 6,
+            num_calls: 1,
+            num_blocks: 1,
+            accesses_storage: true,
+        }
+    );
+
+    // By default functions called once and small functions are both inlined.
+    let mut ir = sway_ir::parser::parse(source).unwrap();
+    inline(&mut ir);
+    assert!(callees(&ir).is_empty());
+
+    // Capping the weight keeps the heavy function out of line, though it's called only once.
+    let mut ir = sway_ir::parser::parse(source).unwrap();
+    ir.set_inline_policy(InlinePolicy {
+        max_weight: Some(4),
+        ..Default::default()
+    });
+    inline(&mut ir);
+    assert_eq!(callees(&ir), vec!["heavy".to_string()]);
+
+    // As does refusing to inline storage accesses.
+    let mut ir = sway_ir::parser::parse(source).unwrap();
+    ir.set_inline_policy(InlinePolicy {
+        inline_storage_access: false,
+        ..Default::default()
+    });
+    inline(&mut ir);
+    assert_eq!(callees(&ir), vec!["heavy".to_string()]);
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn inline_attributes() {
+    let mut ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main(a: u64) -> u64 {
+        entry(a: u64):
+        v0 = call tiny(a)
+        v1 = call tiny_cold(v0)
+        v2 = call big(v1)
+        v3 = call big(v2)
+        ret u64 v3
+    }
+
+    fn tiny(x: u64) -> u64 [inline_never] {
+        entry(x: u64):
+        ret u64 x
+    }
+
+    fn tiny_cold(x: u64) -> u64 [cold] {
+        entry(x: u64):
+        ret u64 x
+    }
+
+    fn big(x: u64) -> u64 [inline_always] {
+        entry(x: u64):
+        v0 = add x, x
+        v1 = mul v0, v0
+        v2 = sub v1, x
+        v3 = div v2, x
+        ret u64 v3
+    }
+}
+"#,
+    )
+    .unwrap();
+
+    let mut pass_mgr = PassManager::default();
+    register_known_passes(&mut pass_mgr);
+    let mut pass_group = PassGroup::default();
+    pass_group.append_pass(INLINE_NONPREDICATE_NAME);
+    pass_mgr.run(&mut ir, &pass_group).unwrap();
+
+    // The tiny functions would otherwise be inlined, and the big one, called twice, wouldn't.
+    let main = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .find(|function| function.get_name(&ir) == "main")
+        .unwrap();
+    let callees = main
+        .instruction_iter(&ir)
+        .filter_map(|(_block, inst)| match inst.get_instruction(&ir) {
+            Some(Instruction::Call(callee, _)) => Some(callee.get_name(&ir).to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(callees, vec!["tiny".to_string(), "tiny_cold".to_string()]);
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn memory_report() {
+    let ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main(a: u64) -> u64 {
+        local u64 x
+
+        entry(a: u64):
+        v0 = const u64 1
+        v1 = add a, v0
+        br exit(v1)
+
+        exit(v2: u64):
+        ret u64 v2
+    }
+}
+"#,
+    )
+    .unwrap();
+    let report = ir.memory_report();
+    let counts = ["functions", "blocks", "values", "constants", "locals"]
+        .map(|name| report.get(name).unwrap().count);
+    // The values are the two arguments and three instructions, besides the constant.
+    assert_eq!(counts, [1, 2, 5, 1, 1]);
+    assert!(report.total_bytes() >= report.get("values").unwrap().bytes);
+    assert_eq!(report.to_string().lines().count(), report.usages.len() + 1);
+}
+
+#[test]
+fn remove_function() {
+    let mut ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main() -> u64 {
+        entry():
+        v0 = const u64 1
+        ret u64 v0
+    }
+
+    fn dead(a: u64) -> u64 {
+        local u64 x
+
+        entry(a: u64):
+        v0 = get_local u64 x
+        store a to v0
+        br exit(a)
+
+        exit(v1: u64):
+        ret u64 v1
+    }
+}
+"#,
+    )
+    .unwrap();
+    let counts = |ir: &Context| {
+        let report = ir.memory_report();
+        ["functions", "blocks", "values", "constants", "locals"]
+            .map(|name| report.get(name).unwrap().count)
+    };
+    assert_eq!(counts(&ir), [2, 3, 7, 1, 1]);
+
+    // Everything belonging to `dead` is freed, and only `main` is left.
+    let module = ir.module_iter().next().unwrap();
+    let dead = module.function_iter(&ir).nth(1).unwrap();
+    module.remove_function(&mut ir, &dead);
+    assert_eq!(counts(&ir), [1, 1, 1, 1, 0]);
+    assert_eq!(
+        module
+            .function_iter(&ir)
+            .map(|function| function.get_name(&ir).to_owned())
+            .collect::<Vec<_>>(),
+        vec!["main".to_owned()]
+    );
+    ir.verify().unwrap();
+}
+
+#[test]
+fn selectors() {
+    let source = r#"
+contract {
+    pub fn get<01020304>() -> u64 {
+        entry():
+        v0 = call helper()
+        ret u64 v0
+    }
+
+    pub fn set<05060708>() -> u64 {
+        entry():
+        v0 = call helper()
+        ret u64 v0
+    }
+
+    fn helper() -> u64 {
+        entry():
+        v0 = const u64 1
+        ret u64 v0
+    }
+}
+"#;
+    let mut ir = sway_ir::parser::parse(source).unwrap();
+    let module = ir.module_iter().next().unwrap();
+    let [get, set, helper] = module
+        .function_iter(&ir)
+        .collect::<Vec<_>>()
+        .try_into()
+        .unwrap();
+    assert_eq!(
+        module.selector_iter(&ir).collect::<Vec<_>>(),
+        vec![(get, [1, 2, 3, 4]), (set, [5, 6, 7, 8])]
+    );
+    assert!(module.selector_collisions(&ir).is_empty());
+    module.check_selectors(&ir).unwrap();
+
+    // A shared selector is reported with every function sharing it, and fails verification.
+    set.set_selector(&mut ir, Some([1, 2, 3, 4]));
+    assert_eq!(
+        module.selector_collisions(&ir),
+        vec![([1, 2, 3, 4], vec![get, set])]
+    );
+    let error = module.check_selectors(&ir).unwrap_err();
+    assert!(matches!(
+        &error,
+        IrError::SelectorCollision { selector: [1, 2, 3, 4], functions }
+            if functions == &["get", "set"]
+    ));
+    assert_eq!(
+        error.to_string(),
+        "Functions get, set share the selector 01020304."
+    );
+    assert!(matches!(
+        ir.verify(),
+        Err(IrError::SelectorCollision { .. })
+    ));
+    assert!(matches!(
+        sway_ir::parser::parse(&source.replace("05060708", "01020304")),
+        Err(IrError::SelectorCollision { .. })
+    ));
+
+    // Recomputed selectors replace only existing ones, and aren't applied if they collide.
+    let mut ir = sway_ir::parser::parse(source).unwrap();
+    let module = ir.module_iter().next().unwrap();
+    assert!(matches!(
+        module.recompute_selectors(&mut ir, |_, _| [0; 4]),
+        Err(IrError::SelectorCollision { .. })
+    ));
+    assert_eq!(get.get_selector(&ir), Some([1, 2, 3, 4]));
+    module
+        .recompute_selectors(&mut ir, |ir, function| {
+            let mut selector = [0; 4];
+            selector[..3].copy_from_slice(&function.get_name(ir).as_bytes()[..3]);
+            selector
+        })
+        .unwrap();
+    assert_eq!(get.get_selector(&ir), Some(*b"get\0"));
+    assert_eq!(set.get_selector(&ir), Some(*b"set\0"));
+    assert_eq!(helper.get_selector(&ir), None);
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn ir_builder() {
+    // Build `max(a, b)` with a branch, a block parameter and a local, and check it survives a round
+    // trip through the printer and parser.
+    let mut builder = sway_ir::IrBuilder::new(sway_ir::Kind::Script);
+    let u64_ty = builder.u64_type();
+    let max = builder.declare_function("max", &[("a", u64_ty), ("b", u64_ty)], u64_ty);
+    let main = builder.declare_entry_function("main", &[], u64_ty);
+
+    let mut max_builder = builder.function_builder(max);
+    let (a, b) = (max_builder.param(0), max_builder.param(1));
+    let is_greater = max_builder.cmp(sway_ir::Predicate::GreaterThan, a, b);
+    let exit = max_builder.create_block("exit");
+    let result = max_builder.add_block_param(exit, u64_ty);
+    max_builder.conditional_branch(is_greater, exit, &[a], exit, &[b]);
+    max_builder.switch_to_block(exit);
+    max_builder.ret(result);
+
+    let mut main_builder = builder.function_builder(main);
+    let local = main_builder.local("x", u64_ty);
+    let forty_two = main_builder.const_u64(42);
+    main_builder.store(local, forty_two);
+    let x = main_builder.load(local);
+    let seven = main_builder.const_u64(7);
+    let max_x = main_builder.call(max, &[x, seven]);
+    main_builder.ret(max_x);
+
+    let ir = builder.finish().unwrap();
+    let printed = sway_ir::printer::to_string(&ir);
+    assert!(printed.contains("cbr v0, exit(a), exit(b)"), "{printed}");
+    let round_tripped = sway_ir::parser::parse(&printed).unwrap();
+    assert_eq!(printed, sway_ir::printer::to_string(&round_tripped));
+}
+
+#[test]
+fn instruction_inserter() {
+    let mut ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main(a: u64) -> u64 {
+        entry(a: u64):
+        v0 = add a, a
+        ret u64 v0
+    }
+}
+"#,
+    )
+    .unwrap();
+    let main = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .next()
+        .unwrap();
+    let entry = main.get_entry_block(&ir);
+    let add = entry.instruction_iter(&ir).next().unwrap();
+    let ret = entry.instruction_iter(&ir).nth(1).unwrap();
+    let md = sway_ir::MetadataIndex::new_string(&mut ir, "inserted");
+
+    let a = main.get_arg(&ir, "a").unwrap();
+    let one = sway_ir::Constant::get_uint(&mut ir, 64, 1);
+
+    // Each new instruction goes where it's asked, with the metadata it's given.
+    let first = entry
+        .ins(&mut ir)
+        .position(InsertionPoint::Start)
+        .binary_op(BinaryOpKind::Sub, a, one);
+    let before = entry
+        .ins(&mut ir)
+        .position(InsertionPoint::Before(add))
+        .binary_op(BinaryOpKind::Mul, first, one);
+    let after = entry
+        .ins(&mut ir)
+        .position(InsertionPoint::After(add))
+        .metadata(Some(md))
+        .binary_op(BinaryOpKind::Add, add, before);
+    assert_eq!(
+        entry.instruction_iter(&ir).collect::<Vec<_>>(),
+        vec![first, before, add, after, ret]
+    );
+    assert_eq!(after.get_metadata(&ir), Some(md));
+    assert_eq!(first.get_metadata(&ir), None);
+    ir.verify().unwrap();
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn block_args() {
+    let mut ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main(c: bool, a: u64, b: bool) -> u64 {
+        entry(c: bool, a: u64, b: bool):
+        cbr c, left(), right()
+
+        left():
+        br exit(a)
+
+        right():
+        br exit(a)
+
+        exit(x: u64):
+        ret u64 x
+    }
+}
+"#,
+    )
+    .unwrap();
+    let main = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .next()
+        .unwrap();
+    let exit = main.block_iter(&ir).last().unwrap();
+    assert!(sway_ir::check_block_args(&ir, main).is_empty());
+
+    // The verifier wouldn't accept this, so pass `b` from `right` only after parsing.
+    let b = main.get_arg(&ir, "b").unwrap();
+    let right = main.block_iter(&ir).nth(2).unwrap();
+    right.get_succ_params_mut(&mut ir, &exit).unwrap()[0] = b;
+
+    // Only the edge from `right` disagrees with the parameter of `exit`.
+    let mismatches = sway_ir::check_block_args(&ir, main);
+    assert_eq!(mismatches.len(), 1);
+    assert_eq!(
+        mismatches[0].as_string(&ir),
+        "Branch from block 'right' to block 'exit' passes argument 0 of type bool \
+        where u64 is required."
+    );
+    assert!(sway_ir::infer_block_arg_types(&ir, exit).is_err());
+    assert!(sway_ir::propagate_block_arg_types(&mut ir, main).is_err());
+
+    // Once both edges pass a `bool`, the parameter type may be changed to follow them.
+    let c = main.get_entry_block(&ir).get_arg(&ir, 0).unwrap();
+    let left = main.block_iter(&ir).nth(1).unwrap();
+    left.get_succ_params_mut(&mut ir, &exit).unwrap()[0] = c;
+    assert_eq!(sway_ir::propagate_block_arg_types(&mut ir, main), Ok(true));
+    assert!(sway_ir::check_block_args(&ir, main).is_empty());
+    let bool_ty = sway_ir::Type::get_bool(&ir);
+    assert_eq!(sway_ir::infer_block_arg_types(&ir, exit), Ok(vec![bool_ty]));
+}
+
+#[test]
+fn verify_block_args() {
+    use sway_ir::IrError;
+
+    // Branches must pass exactly the number and types of the destination parameters.
+    for (left_args, right_args, from_block) in [("a, a", "a", "left"), ("a", "b", "right")] {
+        let source = format!(
+            r#"
+script {{
+    fn main(c: bool, a: u64, b: bool) -> u64 {{
+        entry(c: bool, a: u64, b: bool):
+        cbr c, left(), right()
+
+        left():
+        br exit({left_args})
+
+        right():
+        br exit({right_args})
+
+        exit(x: u64):
+        ret u64 x
+    }}
+}}
+"#,
+        );
+        let Err(error) = sway_ir::parser::parse(&source) else {
+            panic!("The branch from '{from_block}' should fail to verify.");
+        };
+        let IrError::VerifyFailed { error: inner, .. } = &error else {
+            panic!("{error}");
+        };
+        assert!(
+            matches!(&**inner, IrError::VerifyBranchParamsMismatch(from, to)
+                if from == from_block && to == "exit"),
+            "{inner}"
+        );
+    }
+
+    // The entry block must take exactly the function arguments.
+    let mut ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main(a: u64) -> u64 {
+        entry(a: u64):
+        ret u64 a
+    }
+}
+"#,
+    )
+    .unwrap();
+    let main = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .next()
+        .unwrap();
+    let u64_ty = sway_ir::Type::get_uint64(&ir);
+    main.get_entry_block(&ir).new_arg(&mut ir, u64_ty, false);
+    let Err(error) = ir.verify() else {
+        panic!("An extra entry block argument should fail to verify.");
+    };
+    let IrError::VerifyFailed { error: inner, .. } = &error else {
+        panic!("{error}");
+    };
+    assert!(
+        matches!(&**inner, IrError::VerifyEntryArgsMismatch(name) if name == "main"),
+        "{inner}"
+    );
+}
+
+#[test]
+fn unreachable_blocks() {
+    let mut ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main(a: u64) -> u64 {
+        entry(a: u64):
+        v0 = const bool true
+        cbr v0, left(), right()
+
+        left():
+        ret u64 a
+
+        right():
+        v1 = add a, a
+        ret u64 v1
+
+        orphan():
+        ret u64 a
+    }
+}
+"#,
+    )
+    .unwrap();
+    let main = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .next()
+        .unwrap();
+    let labels = |ir: &Context, blocks: Vec<sway_ir::Block>| {
+        blocks
+            .into_iter()
+            .map(|block| block.get_label(ir).to_owned())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        labels(&ir, sway_ir::find_unreachable_blocks(&ir, main)),
+        vec!["orphan"]
+    );
+
+    // Folding the `cbr` orphans `right` too, and `constcombine` asks for orphans to be pruned.
+    let mut pass_mgr = PassManager::default();
+    register_known_passes(&mut pass_mgr);
+    let mut pass_group = PassGroup::default();
+    pass_group.append_pass(sway_ir::CONSTCOMBINE_NAME);
+    assert!(pass_mgr.run(&mut ir, &pass_group).unwrap());
+    assert!(sway_ir::find_unreachable_blocks(&ir, main).is_empty());
+    assert_eq!(
+        labels(&ir, main.block_iter(&ir).collect()),
+        vec!["entry", "left"]
+    );
+}
+
+#[test]
+fn instruction_blocks() {
+    let mut ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main(a: u64) -> u64 {
+        entry(a: u64):
+        v0 = const u64 1
+        v1 = add a, v0
+        v2 = mul v1, v1
+        ret u64 v2
+    }
+}
+"#,
+    )
+    .unwrap();
+    let main = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .next()
+        .unwrap();
+    let entry = main.get_entry_block(&ir);
+    assert_eq!(entry.get_function(&ir), main);
+    let [add, mul, ret] = entry
+        .instruction_iter(&ir)
+        .collect::<Vec<_>>()
+        .try_into()
+        .unwrap();
+    let a = entry.get_arg(&ir, 0).unwrap();
+    assert_eq!(a.get_block(&ir), Some(entry));
+    assert_eq!(add.get_block(&ir), Some(entry));
+    let one = add.get_instruction(&ir).unwrap().get_operands()[1];
+    assert_eq!(one.get_block(&ir), None);
+
+    // Inserted instructions are in their block, and removed ones in none.
+    let sub = entry
+        .ins(&mut ir)
+        .position(InsertionPoint::Before(ret))
+        .binary_op(BinaryOpKind::Sub, mul, a);
+    assert_eq!(sub.get_block(&ir), Some(entry));
+    entry.remove_instruction(&mut ir, sub);
+    assert_eq!(sub.get_block(&ir), None);
+
+    // Moved instructions are in their new block.
+    let (head, tail) = entry.split_after(&mut ir, add).unwrap();
+    assert_eq!(head, entry);
+    assert_eq!(tail.get_function(&ir), main);
+    assert_eq!(add.get_block(&ir), Some(head));
+    assert_eq!(mul.get_block(&ir), Some(tail));
+    assert_eq!(ret.get_block(&ir), Some(tail));
+    ir.verify().unwrap();
+}
+
+#[test]
+fn replace_values() {
+    let mut ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main(a: u64, b: u64) -> u64 {
+        entry(a: u64, b: u64):
+        v0 = add a, b
+        br next(v0)
+
+        next(x: u64):
+        v1 = mul x, a
+        ret u64 v1
+    }
+}
+"#,
+    )
+    .unwrap();
+    let main = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .next()
+        .unwrap();
+    let [entry, next] = main.block_iter(&ir).collect::<Vec<_>>().try_into().unwrap();
+    let (a, b) = (
+        entry.get_arg(&ir, 0).unwrap(),
+        entry.get_arg(&ir, 1).unwrap(),
+    );
+    let add = entry.instruction_iter(&ir).next().unwrap();
+    let mul = next.instruction_iter(&ir).next().unwrap();
+    let operands = |ir: &Context, ins: Value| ins.get_instruction(ir).unwrap().get_operands();
+
+    // Only the uses from the starting block on are replaced.
+    let two = Constant::new_uint(&mut ir, 64, 2);
+    let two = Value::new_constant(&mut ir, two);
+    main.replace_value(&mut ir, a, two, Some(next));
+    assert_eq!(operands(&ir, add), vec![a, b]);
+    assert_eq!(operands(&ir, mul)[1], two);
+    assert_eq!(a.users(&ir), vec![add]);
+
+    // Chains of replacements are followed.
+    let chain = FxHashMap::from_iter([(a, b), (b, two)]);
+    main.replace_values(&mut ir, &chain, None);
+    assert_eq!(operands(&ir, add), vec![two, two]);
+    assert_eq!(two.users(&ir), vec![mul, add]);
+    assert!(a.users(&ir).is_empty() && b.users(&ir).is_empty());
+    ir.verify().unwrap();
+}
+
+#[test]
+fn symbols() {
+    let mut ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main() -> u64 {
+        entry():
+        v0 = call exit()
+        ret u64 v0
+    }
+
+    fn exit() -> u64 {
+        entry():
+        v0 = const u64 0
+        ret u64 v0
+    }
+}
+"#,
+    )
+    .unwrap();
+    let [main, exit] = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .collect::<Vec<_>>()
+        .try_into()
+        .unwrap();
+
+    // Equal strings share a symbol, whether they name a function or label a block.
+    let main_entry = main.get_entry_block(&ir);
+    let exit_entry = exit.get_entry_block(&ir);
+    assert_eq!(
+        main_entry.get_label_symbol(&ir),
+        exit_entry.get_label_symbol(&ir)
+    );
+    assert_eq!(ir.intern("exit"), exit.get_name_symbol(&ir));
+    assert_eq!(exit.get_name_symbol(&ir).as_str(&ir), "exit");
+    assert_ne!(main.get_name_symbol(&ir), exit.get_name_symbol(&ir));
+    assert_eq!(ir.get_symbol("unknown"), None);
+
+    // Labels are still unique within their function only.
+    let block = main.create_block(&mut ir, Some("exit".to_owned()));
+    assert_eq!(block.get_label(&ir), "exit");
+    assert_eq!(block.get_label_symbol(&ir), exit.get_name_symbol(&ir));
+    let block = main.create_block(&mut ir, Some("exit".to_owned()));
+    assert_eq!(block.get_label(&ir), "exit0");
+
+    // Each string is counted once.
+    assert_eq!(
+        ir.memory_report().get("symbols").unwrap().count,
+        ["main", "entry", "exit", "exit0"].len()
+    );
+}
+
+#[test]
+fn split_block() {
+    let source = r#"
+script {
+    fn main(a: u64, b: u64) -> u64 {
+        entry(a: u64, b: u64):
+        v0 = add a, b
+        v1 = mul v0, a
+        v2 = cmp lt v1 b
+        cbr v2, exit(v0), exit(v1)
+
+        exit(x: u64):
+        ret u64 x
+    }
+}
+"#;
+    let before = sway_ir::parser::parse(source).unwrap();
+    let mut ir = sway_ir::parser::parse(source).unwrap();
+    let main = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .next()
+        .unwrap();
+    let entry = main.get_entry_block(&ir);
+    let exit = main.block_iter(&ir).nth(1).unwrap();
+    let add = entry.instruction_iter(&ir).next().unwrap();
+    let cbr = entry.instruction_iter(&ir).next_back().unwrap();
+
+    // The terminator can't be split after, nor an instruction from another block.
+    assert!(entry.split_after(&mut ir, cbr).is_err());
+    let ret = exit.instruction_iter(&ir).next().unwrap();
+    assert!(entry.split_after(&mut ir, ret).is_err());
+
+    let (head, tail) = entry.split_after(&mut ir, add).unwrap();
+    assert_eq!(head, entry);
+    assert_eq!(head.num_instructions(&ir), 2);
+    assert_eq!(tail.num_instructions(&ir), 3);
+    assert_eq!(head.successors(&ir).len(), 1);
+    assert_eq!(exit.pred_iter(&ir).copied().collect::<Vec<_>>(), vec![tail]);
+    let ir = ir.verify().unwrap();
+
+    let mut failures = Vec::new();
+    compare_functions("split_block", "splitting", &before, &ir, &mut failures);
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn attachments() {
+    let mut ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main() -> bool {
+        local u64 x
+
+        entry():
+        v0 = get_local u64 x
+        v1 = const u64 42
+        store v1 to v0
+        v2 = load v0, !0
+        v3 = const u64 42
+        v4 = cmp eq v2 v3, !1
+        ret bool v4
+    }
+}
+
+!0 = attr "tooling" "generated-by" "abi-decode"
+!1 = attr "debug" "note" "compare"
+"#,
+    )
+    .unwrap();
+
+    // Attachments survive a round trip through the printer and parser.
+    let printed = sway_ir::printer::to_string(&ir);
+    assert!(printed.contains(r#"attr "tooling" "generated-by" "abi-decode""#));
+    assert_eq!(
+        printed,
+        sway_ir::printer::to_string(&sway_ir::parser::parse(&printed).unwrap())
+    );
+
+    ir.set_attachment_policy("tooling", sway_ir::AttachmentPolicy::Propagate);
+    ir.set_attachment_policy("debug", sway_ir::AttachmentPolicy::Drop);
+    let mut pass_mgr = PassManager::default();
+    sway_ir::register_known_passes(&mut pass_mgr);
+    let mut run_pass = |ir: &mut Context, name| {
+        let mut pass_group = PassGroup::default();
+        pass_group.append_pass(name);
+        assert!(pass_mgr.run(ir, &pass_group).unwrap());
+    };
+    let main = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .next()
+        .unwrap();
+    let terminator_operand = |ir: &Context| {
+        let exit = main.block_iter(ir).last().unwrap();
+        let terminator = exit.instruction_iter(ir).last().unwrap();
+        terminator.get_instruction(ir).unwrap().get_operands()[0]
+    };
+    let cmp = terminator_operand(&ir);
+
+    // The `load` is replaced by the stored constant, which then has its attachments.
+    run_pass(&mut ir, sway_ir::MEM2REG_NAME);
+    let stored = cmp.get_instruction(&ir).unwrap().get_operands()[0];
+    assert!(stored.is_constant(&ir));
+    assert_eq!(
+        stored.get_attachment(&ir, "tooling", "generated-by"),
+        Some("abi-decode".to_string())
+    );
+
+    // The `cmp` is folded in place, which drops its attachments.
+    assert!(cmp.get_attachment(&ir, "debug", "note").is_some());
+    run_pass(&mut ir, sway_ir::CONSTCOMBINE_NAME);
+    assert_eq!(terminator_operand(&ir), cmp);
+    assert!(cmp.is_constant(&ir));
+    assert!(cmp.get_attachments(&ir).is_empty());
+
+    // Attaching the same key again replaces the previous value.
+    stored.add_attachment(
+        &mut ir,
+        &sway_ir::Attachment::new("tooling", "generated-by", "mem2reg"),
+    );
+    assert_eq!(stored.get_attachments(&ir).len(), 1);
+    stored.remove_attachments(&mut ir, |attachment| attachment.namespace == "tooling");
+    assert!(stored.get_attachments(&ir).is_empty());
+}
+
+#[test]
+fn attachments_follow_replacements() {
+    // Each program returns a value which the pass replaces with another.
+    let programs = [
+        (
+            sway_ir::INSTCOMBINE_NAME,
+            "
+            fn main(a: u64) -> u64 {
+                entry(a: u64):
+                v0 = const u64 0
+                v1 = add a, v0
+                ret u64 v1
+            }",
+        ),
+        (
+            CSE_NAME,
+            "
+            fn main(a: u64) -> u64 {
+                entry(a: u64):
+                v0 = mul a, a
+                v1 = mul a, a
+                ret u64 v1
+            }",
+        ),
+        (
+            RLE_NAME,
+            "
+            fn main(a: u64) -> u64 {
+                local u64 x
+
+                entry(a: u64):
+                v0 = get_local u64 x
+                store a to v0
+                v1 = load v0
+                ret u64 v1
+            }",
+        ),
+        (
+            sway_ir::SIMPLIFYCFG_NAME,
+            "
+            fn main(a: u64) -> u64 {
+                entry(a: u64):
+                br next(a)
+
+                next(x: u64):
+                ret u64 x
+            }",
+        ),
+        (
+            sway_ir::INLINE_MAIN_NAME,
+            "
+            fn id(x: u64) -> u64 {
+                entry(x: u64):
+                ret u64 x
+            }
+
+            fn main(a: u64) -> u64 {
+                entry(a: u64):
+                v0 = call id(a)
+                ret u64 v0
+            }",
+        ),
+    ];
+    for (pass, program) in programs {
+        let mut ir = sway_ir::parser::parse(&format!("script {{{program}\n}}")).unwrap();
+        ir.set_attachment_policy("tooling", sway_ir::AttachmentPolicy::Propagate);
+        let main = ir
+            .module_iter()
+            .flat_map(|module| module.function_iter(&ir))
+            .find(|function| function.get_name(&ir) == "main")
+            .unwrap();
+        let returned = |ir: &Context| {
+            main.instruction_iter(ir)
+                .find_map(|(_, ins)| match ins.get_instruction(ir) {
+                    Some(Instruction::Ret(value, _)) => Some(*value),
+                    _ => None,
+                })
+                .unwrap()
+        };
+        let replaced = returned(&ir);
+        replaced.add_attachment(
+            &mut ir,
+            &sway_ir::Attachment::new("tooling", "origin", pass),
+        );
+
+        let mut pass_mgr = PassManager::default();
+        register_known_passes(&mut pass_mgr);
+        let mut pass_group = PassGroup::default();
+        pass_group.append_pass(pass);
+        assert!(pass_mgr.run(&mut ir, &pass_group).unwrap(), "{pass}");
+
+        let replacement = returned(&ir);
+        assert_ne!(replacement, replaced, "{pass}");
+        assert_eq!(
+            replacement.get_attachment(&ir, "tooling", "origin"),
+            Some(pass.to_string()),
+            "{pass}"
+        );
+    }
+
+    // Hoisting an instruction out of a loop drops the attachments which don't survive rewrites.
+    let mut ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main(n: u64, a: u64) -> u64 {
+        entry(n: u64, a: u64):
+        v0 = const u64 0
+        br header(v0)
+
+        header(i: u64):
+        v1 = and a, a, !0
+        v2 = cmp lt i n
+        cbr v2, body(), exit()
+
+        body():
+        v3 = const u64 1
+        v4 = add i, v3
+        br header(v4)
+
+        exit():
+        ret u64 v1
+    }
+}
+
+!0 = attr "debug" "note" "invariant"
+"#,
+    )
+    .unwrap();
+    ir.set_attachment_policy("debug", sway_ir::AttachmentPolicy::Drop);
+    let main = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .next()
+        .unwrap();
+    let and = main
+        .instruction_iter(&ir)
+        .map(|(_, ins)| ins)
+        .find(|ins| {
+            matches!(
+                ins.get_instruction(&ir),
+                Some(Instruction::BinaryOp {
+                    op: BinaryOpKind::And,
+                    ..
+                })
+            )
+        })
+        .unwrap();
+    let header = and.get_block(&ir);
+    let mut pass_mgr = PassManager::default();
+    register_known_passes(&mut pass_mgr);
+    let mut pass_group = PassGroup::default();
+    pass_group.append_pass(LICM_NAME);
+    assert!(pass_mgr.run(&mut ir, &pass_group).unwrap());
+    assert_ne!(and.get_block(&ir), header);
+    assert!(and.get_attachments(&ir).is_empty());
+}
+
+#[test]
+fn debug_info() {
+    let mut ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main(a: u64) -> u64, !2 {
+        entry(a: u64):
+        v0 = add a, a, !3
+        v1 = mul v0, v0
+        ret u64 v1, !4
+    }
+}
+
+!1 = "src/main.sw"
+!2 = span !1 0 41
+!3 = span !1 24 29
+!4 = attr "tooling" "origin" "user"
+"#,
+    )
+    .unwrap();
+    let main = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .next()
+        .unwrap();
+    let ins = main
+        .instruction_iter(&ir)
+        .map(|(_, ins)| ins)
+        .collect::<Vec<_>>();
+    let (add, mul, ret) = (ins[0], ins[1], ins[2]);
+
+    let main_span = main.get_span(&ir).unwrap();
+    let add_span = add.get_span(&ir).unwrap();
+    assert_eq!((add_span.start, add_span.end), (24, 29));
+    assert_eq!(add_span.file, main_span.file);
+    assert_eq!(mul.get_span(&ir), None);
+    let nowhere = SourceSpan::new(SourceSpan::new_file(&mut ir, "nowhere.sw"), 0, 1);
+
+    {
+        let mut debug_info = DebugInfo::new(&ir);
+        debug_info.add_source(
+            "src/main.sw",
+            "fn main() {\n    let x = a + a;\n    x * x\n}\n",
+        );
+
+        // Instructions without a span of their own inherit the span before them.
+        assert_eq!(debug_info.span(mul), None);
+        assert_eq!(debug_info.nearest_span(mul), Some(add_span));
+        assert_eq!(debug_info.nearest_span(ret), Some(add_span));
+
+        assert_eq!(debug_info.path(&add_span), Some("src/main.sw"));
+        assert_eq!(debug_info.source_text(&add_span), Some("a + a"));
+        assert_eq!(debug_info.line_col(&add_span), Some((2, 13)));
+        assert_eq!(debug_info.line_col(&main_span), Some((1, 1)));
+
+        // A span outside the source or in a file which can't be read has no text.
+        let past_end = SourceSpan::new(add_span.file, 40, 50);
+        assert_eq!(debug_info.source_text(&past_end), None);
+        assert_eq!(debug_info.path(&nowhere), Some("nowhere.sw"));
+        assert_eq!(debug_info.source_text(&nowhere), None);
+    }
+
+    // Setting a span replaces the old one but keeps any other metadata.
+    let file = SourceSpan::new_file(&mut ir, "src/lib.sw");
+    let lib_span = SourceSpan::new(file, 1, 2);
+    ret.set_span(&mut ir, lib_span);
+    assert_eq!(ret.get_span(&ir), Some(lib_span));
+    add.set_span(&mut ir, lib_span);
+    assert_eq!(add.get_span(&ir), Some(lib_span));
+    assert_eq!(
+        ret.get_attachment(&ir, "tooling", "origin"),
+        Some("user".to_owned())
+    );
+    let debug_info = DebugInfo::new(&ir);
+    assert_eq!(debug_info.nearest_span(mul), Some(lib_span));
+    assert_eq!(debug_info.path(&lib_span), Some("src/lib.sw"));
+}
+
+#[test]
+fn debug_variables() {
+    let ir_text = r#"
+script {
+    fn main() -> u64, !2 {
+        local u64 x
+
+        entry():
+        v0 = const u64 5
+        v1 = call id(v0), !7
+        v2 = get_local u64 x, !4
+        store v1 to v2
+        v3 = get_local u64 x, !4
+        v4 = load v3
+        ret u64 v4
+    }
+
+    fn id(a !8: u64) -> u64, !3 {
+        entry(a: u64):
+        v0 = add a, a, !9
+        v1 = mul a, a, !10
+        ret u64 v0
+    }
+}
+
+!1 = "src/main.sw"
+!2 = span !1 0 100
+!3 = span !1 100 200
+!4 = dbg_var "x" "u64" !2
+!5 = span !1 30 35
+!6 = dbg_var "y" "u64" !2
+!7 = (!5 !6)
+!8 = dbg_var "a" "u64" !3
+!9 = dbg_var "twice" "u64" !3
+!10 = dbg_var "square" "u64" !3
+"#;
+    let mut ir = sway_ir::parser::parse(ir_text).unwrap();
+    let functions = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .collect::<Vec<_>>();
+    let (main, id) = (functions[0], functions[1]);
+    let main_scope = main.get_span(&ir).unwrap();
+    let id_scope = id.get_span(&ir).unwrap();
+    let call_span = SourceSpan::new(main_scope.file, 30, 35);
+    let variable = |name, scope, inlined_at| DebugVariable {
+        inlined_at,
+        ..DebugVariable::new(name, "u64", scope)
+    };
+
+    // The variables survive printing and parsing.
+    let (call, get_local) = (
+        main.instruction_iter(&ir).next().unwrap().1,
+        main.instruction_iter(&ir).nth(1).unwrap().1,
+    );
+    assert_eq!(
+        get_local.get_debug_variables(&ir),
+        vec![variable("x", main_scope, None)]
+    );
+    let printed = sway_ir::printer::to_string(&ir);
+    let reparsed = sway_ir::parser::parse(&printed).unwrap();
+    assert_eq!(sway_ir::printer::to_string(&reparsed), printed);
+
+    // Inlining marks the variables of the callee with the call, and the result of the call holds
+    // the caller's variable rather than the inlined instructions.
+    let Some(Instruction::Call(_, args)) = call.get_instruction(&ir) else {
+        panic!("expected a call");
+    };
+    let five = args[0];
+    opt::inline_all_function_calls(&mut ir, &main).unwrap();
+    let insts = main
+        .instruction_iter(&ir)
+        .map(|(_, inst)| inst)
+        .collect::<Vec<_>>();
+    let (add, mul) = (insts[0], insts[1]);
+    assert_eq!(
+        five.get_debug_variables(&ir),
+        vec![variable("a", id_scope, Some(call_span))]
+    );
+    assert_eq!(
+        add.get_debug_variables(&ir),
+        vec![variable("twice", id_scope, Some(call_span))]
+    );
+    assert_eq!(add.get_span(&ir), Some(call_span));
+
+    // Promoting `x` moves it to the returned value which is stored in it, and the dead `mul` is
+    // recorded as optimized out.
+    let mut pass_mgr = PassManager::default();
+    register_known_passes(&mut pass_mgr);
+    let mut pass_group = PassGroup::default();
+    pass_group.append_pass(MEM2REG_NAME);
+    pass_group.append_pass(DCE_NAME);
+    pass_mgr.run(&mut ir, &pass_group).unwrap();
+
+    let ret_val = main
+        .instruction_iter(&ir)
+        .find_map(|(_, inst)| match inst.get_instruction(&ir) {
+            Some(Instruction::Ret(val, _)) => Some(*val),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(
+        ret_val.get_debug_variables(&ir),
+        vec![
+            variable("y", main_scope, None),
+            variable("x", main_scope, None)
+        ]
+    );
+    assert!(!main.instruction_iter(&ir).any(|(_, inst)| inst == mul));
+    assert_eq!(
+        main.get_debug_variables(&ir),
+        vec![variable("square", id_scope, Some(call_span))]
+    );
+}
+
+#[test]
+fn inlined_from() {
+    let ir_text = r#"
+script {
+    fn main() -> u64, !2 {
+        entry():
+        v0 = const u64 5
+        v1 = call outer(v0), !5
+        ret u64 v1
+    }
+
+    fn outer(a: u64) -> u64, !3 {
+        entry(a: u64):
+        v0 = call inner(a), !6
+        v1 = add v0, a
+        ret u64 v1
+    }
+
+    fn inner(b: u64) -> u64, !4 {
+        entry(b: u64):
+        v0 = mul b, b
+        ret u64 v0
+    }
+}
+
+!1 = "src/main.sw"
+!2 = span !1 0 100
+!3 = span !1 100 200
+!4 = span !1 200 300
+!5 = span !1 10 20
+!6 = span !1 110 120
+"#;
+    let mut ir = sway_ir::parser::parse(ir_text).unwrap();
+    let functions = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .collect::<Vec<_>>();
+    let (main, outer, inner) = (functions[0], functions[1], functions[2]);
+    let inlined_from = |function: Function| InlinedFrom {
+        function: function.get_name(&ir).to_owned(),
+        span: function.get_span(&ir),
+    };
+    let (from_outer, from_inner) = (inlined_from(outer), inlined_from(inner));
+    let find = |ir: &Context, pred: fn(&Instruction) -> bool| {
+        main.instruction_iter(ir)
+            .map(|(_, ins)| ins)
+            .find(|ins| ins.get_instruction(ir).map_or(false, pred))
+            .unwrap()
+    };
+
+    // Inlining `outer` into `main` marks its instructions, including the call to `inner`.
+    opt::inline_all_function_calls(&mut ir, &main).unwrap();
+    let call = find(&ir, |ins| matches!(ins, Instruction::Call(..)));
+    assert_eq!(call.get_inlined_from(&ir), Some(from_outer.clone()));
+    let ret = find(&ir, |ins| matches!(ins, Instruction::Ret(..)));
+    assert_eq!(ret.get_inlined_from(&ir), None);
+
+    // Inlining `inner` in turn marks its instructions with `inner` rather than with `outer`.
+    opt::inline_all_function_calls(&mut ir, &main).unwrap();
+    let add = find(&ir, |ins| {
+        matches!(
+            ins,
+            Instruction::BinaryOp {
+                op: BinaryOpKind::Add,
+                ..
+            }
+        )
+    });
+    let mul = find(&ir, |ins| {
+        matches!(
+            ins,
+            Instruction::BinaryOp {
+                op: BinaryOpKind::Mul,
+                ..
+            }
+        )
+    });
+    assert_eq!(add.get_inlined_from(&ir), Some(from_outer));
+    assert_eq!(mul.get_inlined_from(&ir), Some(from_inner));
+
+    // The marks survive printing and parsing.
+    let printed = sway_ir::printer::to_string(&ir);
+    assert!(printed.contains("inlined_from \"inner\""));
+    let reparsed = sway_ir::parser::parse(&printed).unwrap();
+    assert_eq!(sway_ir::printer::to_string(&reparsed), printed);
+}
+
+#[test]
+fn metadata_kinds() {
+    let mut ir = sway_ir::parser::parse(
+        r#"
+contract {
+    fn get() -> u64, !4 {
+        local b256 key
+
+        entry():
+        v0 = get_local b256 key
+        v1 = state_load_word key v0, !7
+        v2 = add v1, v1, !8
+        ret u64 v2
+    }
+}
+
+!1 = "src/main.sw"
+!2 = inline "never"
+!3 = span !1 0 10
+!4 = (!2 !3)
+!5 = state_index 2
+!6 = span !1 5 6
+!7 = (!5 !6)
+!8 = lint "shadowed" 1
+"#,
+    )
+    .unwrap();
+    let get = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .next()
+        .unwrap();
+    let insts = get
+        .instruction_iter(&ir)
+        .map(|(_, ins)| ins)
+        .collect::<Vec<_>>();
+    let (load, add) = (insts[1], insts[2]);
+
+    assert_eq!(MetadataKind::new("span"), MetadataKind::Span);
+    assert_eq!(MetadataKind::new("lint").tag(), "lint");
+
+    // The known kinds have typed accessors.
+    assert_eq!(get.get_inline(&ir), Some(Inline::Never));
+    assert_eq!(load.get_storage_key(&ir), Some(2));
+    assert_eq!(add.get_storage_key(&ir), None);
+    get.set_inline(&mut ir, Inline::Always);
+    load.set_storage_key(&mut ir, 3);
+    assert_eq!(get.get_inline(&ir), Some(Inline::Always));
+    assert_eq!(load.get_storage_key(&ir), Some(3));
+    assert_eq!(get.get_span(&ir).map(|span| span.end), Some(10));
+    assert_eq!(load.get_span(&ir).map(|span| span.start), Some(5));
+
+    // Others are accessed by their fields.
+    let lint = MetadataKind::Custom("lint".to_owned());
+    assert_eq!(
+        add.get_metadatum(&ir, &lint),
+        Some(
+            [
+                Metadatum::String("shadowed".to_owned()),
+                Metadatum::Integer(1)
+            ]
+            .as_slice()
+        )
+    );
+    add.set_metadatum(&mut ir, &lint, vec![Metadatum::String("unused".to_owned())]);
+    get.set_metadatum(&mut ir, &lint, vec![]);
+    let printed = sway_ir::printer::to_string(&ir);
+    assert!(printed.contains(r#"lint "unused""#));
+    assert!(!printed.contains("shadowed"));
+    assert_eq!(get.get_metadatum(&ir, &lint), Some([].as_slice()));
+
+    add.remove_metadatum(&mut ir, &lint);
+    get.remove_metadatum(&mut ir, &MetadataKind::Inline);
+    assert_eq!(add.get_metadata(&ir), None);
+    assert_eq!(get.get_inline(&ir), None);
+    assert_eq!(get.get_span(&ir).map(|span| span.end), Some(10));
+}
+
+#[test]
+fn metadata_string_escapes() {
+    // Metadata strings are printed with Rust's escapes, which the parser must decode.
+    let mut ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main() -> () {
+        entry():
+        v0 = const unit ()
+        ret () v0
+    }
+}
+"#,
+    )
+    .unwrap();
+    let main = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .next()
+        .unwrap();
+
+    let doc_comment = MetadataKind::Custom("doc_comment".to_owned());
+    let fields = [
+        "",
+        "a \"quoted\" \\ string",
+        "tab\there\r\nnew line",
+        "nul\0 bell\u{7} 'apostrophes' \u{200b}",
+    ]
+    .into_iter()
+    .map(|s| Metadatum::String(s.to_owned()))
+    .collect::<Vec<_>>();
+    main.set_metadatum(&mut ir, &doc_comment, fields.clone());
+
+    let printed = sway_ir::printer::to_string(&ir);
+    assert!(printed.contains(r#""tab\there\r\nnew line""#));
+    let round_tripped = sway_ir::parser::parse(&printed).unwrap();
+    let main = round_tripped
+        .module_iter()
+        .flat_map(|module| module.function_iter(&round_tripped))
+        .next()
+        .unwrap();
+    assert_eq!(
+        main.get_metadatum(&round_tripped, &doc_comment),
+        Some(fields.as_slice())
+    );
+}
+
+#[test]
+fn gc_metadata() {
+    let parse = || {
+        sway_ir::parser::parse(
+            r#"
+script {
+    fn main(a !3: u64) -> u64, !2 {
+        entry(a: u64):
+        v0 = add a, a, !4
+        v1 = mul a, a, !5
+        ret u64 v0, !7
+    }
+}
+
+!1 = "src/main.sw"
+!2 = span !1 0 100
+!3 = span !1 10 20
+!4 = span !1 30 40
+!5 = span !1 50 60
+!6 = attr "tooling" "origin" "user"
+!7 = (!4 !6)
+"#,
+        )
+        .unwrap()
+    };
+    let dce = |ir: &mut Context, gc: bool| {
+        let mut pass_mgr = PassManager::default();
+        register_known_passes(&mut pass_mgr);
+        pass_mgr.set_gc_metadata(gc);
+        let mut pass_group = PassGroup::default();
+        pass_group.append_pass(DCE_NAME);
+        pass_mgr.run(ir, &pass_group).unwrap();
+    };
+
+    // Everything is referred to after parsing, but the span of the dead `mul` isn't after DCE.
+    let mut ir = parse();
+    assert_eq!(ir.gc_metadata(), 0);
+    dce(&mut ir, false);
+    let printed = sway_ir::printer::to_string(&ir);
+    assert_eq!(ir.gc_metadata(), 1);
+    assert_eq!(sway_ir::printer::to_string(&ir), printed);
+
+    // Replacing the span of `add` leaves the old one in use by the list of `ret`, but replacing
+    // the span of `ret` leaves both the old span and list unused.
+    let main = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .next()
+        .unwrap();
+    let insts = main
+        .instruction_iter(&ir)
+        .map(|(_, ins)| ins)
+        .collect::<Vec<_>>();
+    let (add, ret) = (insts[0], insts[1]);
+    let span = SourceSpan::new(main.get_span(&ir).unwrap().file, 70, 80);
+    add.set_span(&mut ir, span);
+    assert_eq!(ir.gc_metadata(), 0);
+    ret.set_span(&mut ir, span);
+    assert_eq!(ir.gc_metadata(), 2);
+    assert_eq!(ret.get_span(&ir), Some(span));
+    assert_eq!(
+        ret.get_attachment(&ir, "tooling", "origin"),
+        Some("user".to_owned())
+    );
+    sway_ir::printer::to_string(&ir);
+
+    // The pass manager can collect them once its passes have run.
+    let mut ir = parse();
+    dce(&mut ir, true);
+    assert_eq!(ir.gc_metadata(), 0);
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn printer_options() {
+    let ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main() -> u64, !1 {
+        entry():
+        v0 = const u64 1
+        v1 = call succ(v0), !3
+        ret u64 v1
+    }
+
+    fn succ(x: u64) -> u64 {
+        entry(x: u64):
+        v0 = const u64 1
+        v1 = add x, v0, !4
+        ret u64 v1
+    }
+}
+
+!0 = "main.sw"
+!1 = span !0 0 10
+!2 = inline "never"
+!3 = (!1 !2)
+!4 = attr "tooling" "generated-by" "test"
+"#,
+    )
+    .unwrap();
+    let print = |options: &PrinterOptions| sway_ir::printer::to_string_with_options(&ir, options);
+
+    // The default options print everything, as `to_string()` does.
+    assert_eq!(
+        print(&PrinterOptions::default()),
+        sway_ir::printer::to_string(&ir)
+    );
+
+    let no_metadata = print(&PrinterOptions {
+        metadata: false,
+        ..PrinterOptions::default()
+    });
+    assert!(!no_metadata.contains('!'), "{no_metadata}");
+
+    // Without spans the call refers to the `inline` metadatum alone.
+    let no_spans = print(&PrinterOptions {
+        spans: false,
+        ..PrinterOptions::default()
+    });
+    assert!(no_spans.contains("fn main() -> u64 {"), "{no_spans}");
+    assert!(no_spans.contains("v1 = call succ(v0), !0"), "{no_spans}");
+    assert!(!no_spans.contains("span"), "{no_spans}");
+    sway_ir::parser::parse(&no_spans).unwrap();
+
+    let stable = print(&PrinterOptions {
+        functions: Some(vec!["succ".to_string()]),
+        stable_order: true,
+        annotate_types: true,
+        ..PrinterOptions::default()
+    });
+    assert!(!stable.contains("fn main"), "{stable}");
+    assert!(stable.contains("v1 = add x, v0, !0 // u64"), "{stable}");
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn dot_cfg_loops() {
+    let ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main(c: bool) -> () {
+        entry(c: bool):
+        br outer()
+
+        outer():
+        cbr c, inner(), exit()
+
+        inner():
+        cbr c, inner(), outer_latch()
+
+        outer_latch():
+        br outer()
+
+        exit():
+        v0 = const unit ()
+        ret () v0
+    }
+}
+"#,
+    )
+    .unwrap();
+    let main = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .next()
+        .unwrap();
+    assert_eq!(
+        main.dot_cfg(&ir),
+        "digraph main {
+\tentry
+\texit
+\tsubgraph cluster_0 {
+\t\tlabel = \"loop outer\";
+\t\touter
+\t\touter_latch
+\t\tsubgraph cluster_1 {
+\t\t\tlabel = \"loop inner\";
+\t\t\tinner
+\t\t}
+\t}
+\tentry -> outer
+\touter -> inner
+\touter -> exit
+\tinner -> inner [color=red]
+\tinner -> outer_latch
+\touter_latch -> outer [color=red]
+}
+"
+    );
+}
+
+#[test]
+fn dot_cfg_instructions() {
+    let ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main(c: bool, x: u64) -> u64 {
+        entry(c: bool, x: u64):
+        cbr c, done(x), fail()
+
+        done(r: u64):
+        ret u64 r
+
+        fail():
+        v0 = const u64 42
+        revert v0
+    }
+}
+"#,
+    )
+    .unwrap();
+    let main = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .next()
+        .unwrap();
+    assert_eq!(
+        main.dot_cfg_with_options(
+            &ir,
+            &DotCfgOptions {
+                instructions: true,
+                edge_args: true,
+            }
+        ),
+        "digraph main {
+\tentry [shape=record, label=\"{entry(c: bool, x: u64):|cbr c, done(x), fail()\\l}\"]
+\tdone [shape=record, color=blue, label=\"{done(v0: u64):|ret u64 v0\\l}\"]
+\tfail [shape=record, color=orange, label=\"{fail():|v1 = const u64 42\\lrevert v1\\l}\"]
+\tentry -> done [label=\"x\"]
+\tentry -> fail
+}
+"
+    );
+}
+
+#[test]
+fn dot_domtree() {
+    let ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main(c: bool) -> () {
+        entry(c: bool):
+        cbr c, left(), right()
+
+        left():
+        br join()
+
+        right():
+        br join()
+
+        dead():
+        br join()
+
+        join():
+        v0 = const unit ()
+        ret () v0
+    }
+}
+"#,
+    )
+    .unwrap();
+    let main = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .next()
+        .unwrap();
+    assert_eq!(
+        main.dot_domtree(&ir),
+        "digraph main {
+\tentry
+\tleft
+\tright
+\tjoin
+\tentry -> left
+\tentry -> right
+\tentry -> join
+}
+"
+    );
+
+    // Blocks are drawn just as they are in the CFG.
+    let options = DotCfgOptions {
+        instructions: true,
+        ..DotCfgOptions::default()
+    };
+    assert!(main
+        .dot_domtree_with_options(&ir, &options)
+        .contains("\tjoin [shape=record, color=blue, label=\"{join():|v0 = const unit ()\\lret () v0\\l}\"]\n"));
+}
+
+#[test]
+fn dot_callgraph() {
+    let ir = sway_ir::parser::parse(
+        r#"
+contract {
+    pub entry fn get<01020304>() -> u64 {
+        entry():
+        v0 = call read()
+        v1 = call read()
+        v2 = call double(v0)
+        ret u64 v2
+    }
+
+    fn read() -> u64 {
+        local b256 slot
+
+        entry():
+        v0 = get_local b256 slot
+        v1 = state_load_word key v0
+        ret u64 v1
+    }
+
+    fn double(x: u64) -> u64 {
+        entry(x: u64):
+        v0 = add x, x
+        ret u64 v0
+    }
+}
+"#,
+    )
+    .unwrap();
+    let module = ir.module_iter().next().unwrap();
+    assert_eq!(
+        module.dot_callgraph(&ir),
+        "digraph contract {
+\t\"get\" [style=bold, color=blue, label=\"get\\n<01020304>\"]
+\t\"read\" [shape=box]
+\t\"double\"
+\t\"get\" -> \"read\" [label=\"2\"]
+\t\"get\" -> \"double\" [label=\"1\"]
+}
+"
+    );
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn loop_info() {
+    let mut ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main(c: bool, x: u64) -> u64 {
+        entry(c: bool, x: u64):
+        br outer()
+
+        outer():
+        cbr c, inner(x), exit()
+
+        inner(v0: u64):
+        cbr c, inner(v0), outer_latch()
+
+        outer_latch():
+        br outer()
+
+        exit():
+        ret u64 x
+    }
+}
+"#,
+    )
+    .unwrap();
+    let main = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .next()
+        .unwrap();
+    let labels = |ir: &Context, blocks: Vec<sway_ir::Block>| {
+        blocks
+            .into_iter()
+            .map(|block| block.get_label(ir).to_owned())
+            .collect::<Vec<_>>()
+    };
+
+    let mut loop_info = sway_ir::compute_function_loop_info(&ir, &main);
+    assert_eq!(loop_info.loops.len(), 2);
+    let (outer, inner) = (&loop_info.loops[0], &loop_info.loops[1]);
+    assert_eq!(outer.header.get_label(&ir), "outer");
+    assert_eq!(inner.header.get_label(&ir), "inner");
+    assert_eq!(inner.latch(), Some(inner.header));
+    assert_eq!(inner.parent, Some(0));
+    assert_eq!((loop_info.depth(0), loop_info.depth(1)), (1, 2));
+    assert_eq!(
+        labels(&ir, outer.blocks_iter(&ir).collect()),
+        ["outer", "inner", "outer_latch"]
+    );
+    assert_eq!(labels(&ir, outer.exits(&ir)), ["exit"]);
+    assert_eq!(labels(&ir, inner.exits(&ir)), ["outer_latch"]);
+    assert_eq!(
+        labels(&ir, outer.preheader(&ir).into_iter().collect()),
+        ["entry"]
+    );
+
+    // The inner loop is entered from a conditional branch, so has no preheader until one is made.
+    assert_eq!(inner.preheader(&ir), None);
+    let preheader = loop_info.get_or_create_preheader(&mut ir, 1).unwrap();
+    assert_eq!(loop_info.loops[1].preheader(&ir), Some(preheader));
+    assert!(loop_info.loops[0].blocks.contains(&preheader));
+    assert_eq!(
+        loop_info.get_or_create_preheader(&mut ir, 1),
+        Some(preheader)
+    );
+    let ir = ir.verify().unwrap();
+
+    let recomputed = sway_ir::compute_function_loop_info(&ir, &main);
+    assert_eq!(recomputed.loops[0].blocks, loop_info.loops[0].blocks);
+    assert_eq!(recomputed.loops[1].preheader(&ir), Some(preheader));
+    assert_eq!(
+        labels(&ir, main.block_iter(&ir).collect()),
+        [
+            "entry",
+            "outer",
+            "inner_preheader",
+            "inner",
+            "outer_latch",
+            "exit"
+        ]
+    );
+    assert!(sway_ir::printer::to_string(&ir).contains("cbr c, inner_preheader(x), exit()"));
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn pipeline_spec() {
+    let mut pass_mgr = PassManager::default();
+    register_known_passes(&mut pass_mgr);
+
+    let pipeline = pass_mgr
+        .parse_pipeline("mem2reg, simplifycfg,,dce", false)
+        .unwrap();
+    assert_eq!(
+        pipeline.flatten_pass_group(),
+        [
+            sway_ir::MEM2REG_NAME,
+            sway_ir::SIMPLIFYCFG_NAME,
+            sway_ir::DCE_NAME
+        ]
+    );
+
+    // A level stands for its passes, and a printed pipeline parses back to the same passes.
+    let o2 = create_o2_pass_group(true);
+    for spec in ["O2", "-O2"] {
+        let pipeline = pass_mgr.parse_pipeline(spec, true).unwrap();
+        assert_eq!(pipeline.flatten_pass_group(), o2.flatten_pass_group());
+    }
+    let pipeline = pass_mgr.parse_pipeline(&o2.to_string(), true).unwrap();
+    assert_eq!(pipeline.flatten_pass_group(), o2.flatten_pass_group());
+    assert_ne!(
+        pass_mgr.parse_pipeline("O2", false).unwrap().to_string(),
+        o2.to_string()
+    );
+
+    assert!(matches!(
+        pass_mgr.parse_pipeline("mem2reg,sccp", false),
+        Err(sway_ir::IrError::UnknownPass(name)) if name == "sccp"
+    ));
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn size_pass_groups() {
+    // Os simplifies further than O1, but leaves out the passes which only pay off in gas.
+    let os = create_os_pass_group(false).flatten_pass_group();
+    assert_ne!(os, create_o1_pass_group(false).flatten_pass_group());
+    assert!(os.contains(&CSE_NAME));
+    assert!(!os.contains(&LICM_NAME));
+
+    // Oz does everything Os does, and more.
+    let oz = create_oz_pass_group(false).flatten_pass_group();
+    assert_eq!(oz[..os.len()], os[..]);
+    assert!(oz.contains(&ADCE_NAME));
+
+    // And the common subexpression is only computed once.
+    let ir_size = |group: PassGroup| {
+        let mut ir = sway_ir::parser::parse(
+            r#"
+script {
+    fn main(a: u64, b: u64) -> u64 {
+        entry(a: u64, b: u64):
+        v0 = add a, b
+        v1 = add a, b
+        v2 = mul v0, v1
+        ret u64 v2
+    }
+}
+"#,
+        )
+        .unwrap();
+        let mut pass_mgr = PassManager::default();
+        register_known_passes(&mut pass_mgr);
+        pass_mgr.run(&mut ir, &group).unwrap();
+        ir.module_iter()
+            .flat_map(|module| module.function_iter(&ir))
+            .map(|function| function.instruction_iter(&ir).count())
+            .sum::<usize>()
+    };
+    assert_eq!(ir_size(create_o1_pass_group(false)), 4);
+    assert_eq!(ir_size(create_os_pass_group(false)), 3);
+    assert_eq!(ir_size(create_oz_pass_group(false)), 3);
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn pass_statistics() {
+    let mut ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main(a: u64) -> u64 {
+        entry(a: u64):
+        v0 = call double(a)
+        v1 = xor a, a
+        v2 = xor v1, a
+        ret u64 v0
+    }
+
+    fn double(x: u64) -> u64 {
+        entry(x: u64):
+        v0 = xor x, x
+        ret u64 x
+    }
+}
+"#,
+    )
+    .unwrap();
+    let mut pass_mgr = PassManager::default();
+    register_known_passes(&mut pass_mgr);
+    let pipeline = pass_mgr
+        .parse_pipeline("inline_main,dce,dce,dominators", false)
+        .unwrap();
+    pass_mgr.run(&mut ir, &pipeline).unwrap();
+
+    let stats = |name| {
+        pass_mgr
+            .statistics()
+            .iter()
+            .find(|stats| stats.pass == name)
+            .unwrap()
+    };
+    let inline = stats("inline_main");
+    assert_eq!((inline.runs, inline.changes), (1, 1));
+    assert_eq!(inline.counters["calls inlined"], 1);
+
+    // Only the first run of DCE changes anything, removing both `xor`s from `main` and the one
+    // inlined from `double` along with the one in `double` itself.
+    let dce = stats(sway_ir::DCE_NAME);
+    assert_eq!((dce.runs, dce.changes), (2, 2));
+    assert_eq!(dce.counters["instructions removed"], 4);
+
+    // Both functions are analysed, and are unchanged since.
+    let dominators = stats("dominators");
+    assert_eq!((dominators.runs, dominators.changes), (1, 2));
+    assert!(dominators.counters.is_empty());
+
+    let report = pass_mgr.statistics_report();
+    assert!(report.contains("calls inlined"), "{report}");
+    assert!(
+        report.lines().last().unwrap().starts_with("total"),
+        "{report}"
+    );
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn verify_after_each() {
+    use sway_ir::{AnalysisResults, IrError, Pass};
+
+    // Make the condition of the first `cbr` a `u64`, which is invalid.
+    fn break_cbr(ir: &mut Context, _: &AnalysisResults, f: Function) -> Result<bool, IrError> {
+        let (_, cbr) = f
+            .instruction_iter(ir)
+            .find(|(_, ins)| {
+                matches!(
+                    ins.get_instruction(ir),
+                    Some(Instruction::ConditionalBranch { .. })
+                )
+            })
+            .unwrap();
+        let Some(Instruction::ConditionalBranch {
+            true_block,
+            false_block,
+            ..
+        }) = cbr.get_instruction(ir).cloned()
+        else {
+            unreachable!()
+        };
+        let cond_value = sway_ir::Constant::get_uint(ir, 64, 0);
+        cbr.replace(
+            ir,
+            sway_ir::ValueDatum::Instruction(Instruction::ConditionalBranch {
+                cond_value,
+                true_block,
+                false_block,
+            }),
+        );
+        Ok(true)
+    }
+
+    let mut pass_mgr = PassManager::default();
+    register_known_passes(&mut pass_mgr);
+    pass_mgr.register(Pass {
+        name: "break_cbr",
+        descr: "",
+        deps: vec![],
+        preserves: vec![],
+        prune_unreachable: false,
+        runner: sway_ir::ScopedPass::FunctionPass(sway_ir::PassMutability::Transform(break_cbr)),
+    });
+    pass_mgr.set_verify_after_each(true);
+
+    let mut ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main(c: bool) -> u64 {
+        local u64 x
+
+        entry(c: bool):
+        v0 = get_local u64 x
+        cbr c, a(), b()
+
+        a():
+        v0 = const u64 1
+        ret u64 v0
+
+        b():
+        v1 = const u64 2
+        ret u64 v1
+    }
+}
+"#,
+    )
+    .unwrap();
+    let pipeline = pass_mgr
+        .parse_pipeline("simplifycfg,break_cbr,dce", false)
+        .unwrap();
+    let error = pass_mgr.run(&mut ir, &pipeline).unwrap_err();
+    let IrError::VerifyAfterPass(pass, error) = &error else {
+        panic!("{error}");
+    };
+    assert_eq!(pass, "break_cbr");
+    let IrError::VerifyFailed {
+        function,
+        block,
+        instruction,
+        error: inner,
+    } = error.as_ref()
+    else {
+        panic!("{error}");
+    };
+    assert_eq!(function, "main");
+    assert_eq!(block.as_deref(), Some("entry"));
+    assert_eq!(
+        instruction.as_deref(),
+        Some("v1 = const u64 0\ncbr v1, a(), b()")
+    );
+    assert!(matches!(**inner, IrError::VerifyConditionExprNotABool));
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn operands_dominate_uses() {
+    use sway_ir::IrError;
+
+    // `v1` is defined in `a` but used in `c`, which may be reached from `b` instead.
+    let Err(error) = sway_ir::parser::parse(
+        r#"
+script {
+    fn main(c: bool) -> u64 {
+        entry(c: bool):
+        v0 = const u64 0
+        cbr c, a(), b()
+
+        a():
+        v1 = add v0, v0
+        br c(v0)
+
+        b():
+        br c(v0)
+
+        c(v2: u64):
+        v3 = add v1, v2
+        ret u64 v3
+    }
+}
+"#,
+    ) else {
+        panic!("IR with an undominated use should fail to verify.");
+    };
+    let IrError::VerifyFailed {
+        function,
+        block,
+        error: inner,
+        ..
+    } = &error
+    else {
+        panic!("{error}");
+    };
+    assert_eq!(function, "main");
+    assert_eq!(block.as_deref(), Some("c"));
+    assert!(matches!(**inner, IrError::VerifyOperandNotDominated));
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn preserved_analyses() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use sway_ir::{AnalysisResult, AnalysisResultT, AnalysisResults, IrError, Pass};
+
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counted;
+    impl AnalysisResultT for Counted {}
+    fn count(_: &Context, _: &AnalysisResults, _: Function) -> Result<AnalysisResult, IrError> {
+        RUNS.fetch_add(1, Ordering::Relaxed);
+        Ok(Box::new(Counted))
+    }
+    // Claims to modify the function without changing anything.
+    fn touch(_: &mut Context, analyses: &AnalysisResults, f: Function) -> Result<bool, IrError> {
+        analyses.get_analysis_result::<Counted, _>(f);
+        Ok(true)
+    }
+    let pass = |name, deps, preserves, runner| Pass {
+        name,
+        descr: "",
+        deps,
+        preserves,
+        prune_unreachable: false,
+        runner: sway_ir::ScopedPass::FunctionPass(runner),
+    };
+
+    let mut pass_mgr = PassManager::default();
+    pass_mgr.register(pass(
+        "counted",
+        vec![],
+        vec![],
+        sway_ir::PassMutability::Analysis(count),
+    ));
+    pass_mgr.register(pass(
+        "preserving",
+        vec!["counted"],
+        vec!["counted"],
+        sway_ir::PassMutability::Transform(touch),
+    ));
+    pass_mgr.register(pass(
+        "invalidating",
+        vec!["counted"],
+        vec![],
+        sway_ir::PassMutability::Transform(touch),
+    ));
+
+    let mut ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main() -> () {
+        entry():
+        v0 = const unit ()
+        ret () v0
+    }
+}
+"#,
+    )
+    .unwrap();
+    let mut run = |name| {
+        let mut pass_group = PassGroup::default();
+        pass_group.append_pass(name);
+        pass_mgr.run(&mut ir, &pass_group).unwrap();
+        RUNS.load(Ordering::Relaxed)
+    };
+
+    // The result is computed once and kept for as long as every pass preserves it.
+    assert_eq!(run("preserving"), 1);
+    assert_eq!(run("preserving"), 1);
+    assert_eq!(run("invalidating"), 1);
+    assert_eq!(run("invalidating"), 2);
+    assert_eq!(run("preserving"), 3);
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn alias_analysis() {
+    let ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main(p: { b256, b256 }, key: b256) -> u64 {
+        local { b256, b256 } a
+        local u64 b
+        local b256 c
+
+        entry(p: { b256, b256 }, key: b256):
+        v0 = get_local { b256, b256 } a
+        v1 = cast_ptr v0, b256, 0
+        v2 = cast_ptr v0, b256, 1
+        v3 = get_local { b256, b256 } a
+        v4 = cast_ptr v3, b256, 1
+        v5 = get_local u64 b
+        v6 = get_local b256 c
+        v7 = get_local b256 c
+        v8 = addr_of v6
+        v9 = load v5
+        store v9 to v5
+        v10 = state_load_word key key
+        ret u64 v10
+    }
+}
+"#,
+    )
+    .unwrap();
+    let main = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .next()
+        .unwrap();
+    let values = main
+        .instruction_iter(&ir)
+        .map(|(_, ins)| ins)
+        .collect::<Vec<_>>();
+    let p = main.get_arg(&ir, "p").unwrap();
+    let key = main.get_arg(&ir, "key").unwrap();
+
+    let aa = BasicAliasAnalysis::new(&ir, &main);
+    let alias = |v1: Value, v2: Value| aa.may_alias(&ir, v1, v2);
+
+    // Different fields of the same local, reached through different `get_local`s.
+    assert_eq!(alias(values[1], values[2]), AliasResult::NoAlias);
+    assert_eq!(alias(values[2], values[4]), AliasResult::MustAlias);
+    assert_eq!(alias(values[0], values[4]), AliasResult::MayAlias);
+
+    // Different locals, and locals against an argument unless they escape.
+    assert_eq!(alias(values[0], values[5]), AliasResult::NoAlias);
+    assert_eq!(alias(values[5], p), AliasResult::NoAlias);
+    assert_eq!(alias(values[6], p), AliasResult::MayAlias);
+    assert_eq!(alias(values[6], values[7]), AliasResult::MustAlias);
+    assert_eq!(alias(values[7], values[1]), AliasResult::NoAlias);
+
+    // Storage is never memory.
+    assert_eq!(
+        aa.may_alias_locations(
+            &ir,
+            MemoryLocation::Storage(key),
+            MemoryLocation::Memory(key)
+        ),
+        AliasResult::NoAlias
+    );
+    assert_eq!(
+        aa.may_alias_locations(
+            &ir,
+            MemoryLocation::Storage(key),
+            MemoryLocation::Storage(key)
+        ),
+        AliasResult::MayAlias
+    );
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn memory_ssa() {
+    let ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn main(c: bool) -> u64 {
+        local u64 a
+        local u64 b
+
+        entry(c: bool):
+        v0 = get_local u64 a
+        v1 = get_local u64 b
+        v2 = const u64 1
+        store v2 to v0
+        store v2 to v1
+        cbr c, then(), join()
+
+        then():
+        store v2 to v0
+        br join()
+
+        join():
+        v3 = load v0
+        v4 = load v1
+        br header()
+
+        header():
+        v5 = load v1
+        cbr c, body(), exit()
+
+        body():
+        store v5 to v0
+        br header()
+
+        exit():
+        ret u64 v3
+    }
+}
+"#,
+    )
+    .unwrap();
+    let main = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .next()
+        .unwrap();
+    let blocks = main.block_iter(&ir).collect::<Vec<_>>();
+    let insts = |block: Block| block.instruction_iter(&ir).collect::<Vec<_>>();
+    let (entry, then, join, header, body) = (
+        insts(blocks[0]),
+        insts(blocks[1]),
+        insts(blocks[2]),
+        insts(blocks[3]),
+        insts(blocks[4]),
+    );
+    let (store_a, store_b, store_then) = (entry[2], entry[3], then[0]);
+    let (load_a, load_b, load_loop, store_loop) = (join[0], join[1], header[0], body[0]);
+
+    let mssa = sway_ir::compute_function_memory_ssa(&ir, &main);
+    assert_eq!(mssa.defining_access(entry[0]), None);
+    assert_eq!(
+        mssa.defining_access(store_a),
+        Some(MemoryAccess::LiveOnEntry)
+    );
+    assert_eq!(
+        mssa.defining_access(store_b),
+        Some(MemoryAccess::Def(store_a))
+    );
+    assert_eq!(
+        mssa.defining_access(store_then),
+        Some(MemoryAccess::Def(store_b))
+    );
+    assert_eq!(
+        mssa.phi_incoming(blocks[2]),
+        Some(
+            [
+                (blocks[0], MemoryAccess::Def(store_b)),
+                (blocks[1], MemoryAccess::Def(store_then))
+            ]
+            .as_slice()
+        )
+    );
+    assert_eq!(
+        mssa.defining_access(load_a),
+        Some(MemoryAccess::Phi(blocks[2]))
+    );
+    assert_eq!(
+        mssa.defining_access(store_loop),
+        Some(MemoryAccess::Phi(blocks[3]))
+    );
+    assert_eq!(
+        mssa.users(MemoryAccess::Def(store_loop)),
+        [MemoryUser::Phi(blocks[3])]
+    );
+
+    // Each load is linked to the stores it may read from, past those to other locals.
+    let aa = BasicAliasAnalysis::new(&ir, &main);
+    assert_eq!(
+        mssa.clobbering_access(&ir, &aa, load_a),
+        Some(MemoryAccess::Phi(blocks[2]))
+    );
+    assert_eq!(
+        mssa.clobbering_access(&ir, &aa, load_b),
+        Some(MemoryAccess::Def(store_b))
+    );
+    assert_eq!(
+        mssa.clobbering_access(&ir, &aa, load_loop),
+        Some(MemoryAccess::Def(store_b))
+    );
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn post_dominators() {
+    let ir = sway_ir::parser::parse(
+        r#"
+script {
+    fn exits(c: bool) -> u64 {
+        entry(c: bool):
+        cbr c, then(), else()
+
+        then():
+        br join()
+
+        else():
+        cbr c, join(), fail()
+
+        fail():
+        v0 = const u64 1
+        revert v0
+
+        join():
+        v1 = const u64 0
+        ret u64 v1
+    }
+
+    fn spin(c: bool) -> () {
+        entry(c: bool):
+        cbr c, header(), done()
+
+        header():
+        br latch()
+
+        latch():
+        br header()
+
+        done():
+        v0 = const unit ()
+        ret () v0
+    }
+}
+"#,
+    )
+    .unwrap();
+    let mut functions = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .collect::<Vec<_>>();
+    functions.sort_by_key(|function| function.get_name(&ir).to_owned());
+
+    // Each block with its immediate post-dominator and its post-dominance frontier.
+    let summary = |function: &Function| {
+        let tree = sway_ir::compute_post_dom_tree(&ir, function);
+        let fronts = sway_ir::compute_post_dom_fronts(&ir, &tree);
+        function
+            .block_iter(&ir)
+            .map(|block| {
+                let ipdom = tree.nodes[&block]
+                    .parent
+                    .map(|parent| parent.get_label(&ir).to_owned());
+                let mut front = fronts.0[&block]
+                    .iter()
+                    .map(|block| block.get_label(&ir).to_owned())
+                    .collect::<Vec<_>>();
+                front.sort();
+                (block.get_label(&ir).to_owned(), ipdom, front)
+            })
+            .collect::<Vec<_>>()
+    };
+    let s = |label: &str| label.to_owned();
+
+    // The two exits are only joined by the virtual exit.
+    assert_eq!(
+        summary(&functions[0]),
+        vec![
+            (s("entry"), None, vec![]),
+            (s("then"), Some(s("join")), vec![s("entry")]),
+            (s("else"), None, vec![s("entry")]),
+            (s("fail"), None, vec![s("else")]),
+            (s("join"), None, vec![s("else"), s("entry")]),
+        ]
+    );
+    // The infinite loop is treated as if it could be left from its latch.
+    assert_eq!(
+        summary(&functions[1]),
+        vec![
+            (s("entry"), None, vec![]),
+            (s("header"), Some(s("latch")), vec![s("entry")]),
+            (s("latch"), None, vec![s("entry")]),
+            (s("done"), None, vec![s("entry")]),
+        ]
+    );
+}
+
+// -------------------------------------------------------------------------------------------------
+
+#[test]
+fn superoptimize() {
+    let ir = sway_ir::parser::parse(
+        "script {
+            fn and_or(a: u64, b: u64) -> u64 {
+                entry(a: u64, b: u64):
+                v0 = and a, b
+                v1 = or a, b
+                v2 = xor v0, v1
+                ret u64 v2
+            }
+
+            fn sub_add(a: u64, b: u64) -> u64 {
+                entry(a: u64, b: u64):
+                v0 = add a, b
+                v1 = sub v0, b
+                ret u64 v1
+            }
+        }",
+    )
+    .unwrap();
+    let candidates = ir
+        .module_iter()
+        .flat_map(|module| module.function_iter(&ir))
+        .map(|function| {
+            sway_ir::superoptimize(&ir, function, &sway_ir::SuperoptConfig::default())
+                .into_iter()
+                .map(|candidate| candidate.rule.to_sexp())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        candidates,
+        vec![
+            vec!["(rule and_or_0 (xor (and a b) (or a b)) (xor a b))".to_string()],
+            // Unlike `a`, the addition may overflow.
+            vec![],
+        ]
+    );
+}
+
+// -------------------------------------------------------------------------------------------------
+// Differential testing of the transform passes.  Every function of every module in the test corpus,
+// along with some generated modules, is interpreted on random inputs before and after each
+// registered transform, and must behave the same.
+
+/// Transforms which change what a module does by design, adding logs or changing the arguments of
+/// entry functions.
+const INSTRUMENTING_PASSES: [&str; 3] = [
+    sway_ir::COVERAGE_NAME,
+    sway_ir::GAS_CHECKPOINTS_NAME,
+    sway_ir::PACKED_ABI_NAME,
+];
+
+/// The number of inputs each function is interpreted on.
+const DIFFERENTIAL_SAMPLES: usize = 16;
+
+/// A xorshift generator for the inputs and generated modules.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+/// Random arguments for `function`, or `None` if it takes arguments the interpreter can't be
+/// given.
+fn differential_args(ir: &Context, function: Function, rng: &mut Rng) -> Option<Vec<InterpretArg>> {
+    const EDGES: [u64; 5] = [0, 1, 2, u64::MAX - 1, u64::MAX];
+    function
+        .args_iter(ir)
+        .map(|(_, arg)| {
+            let ty = arg.get_type(ir)?;
+            if let Some(width) = ty.get_uint_width(ir) {
+                let n = match rng.below(3) {
+                    0 => EDGES[rng.below(EDGES.len() as u64) as usize],
+                    1 => rng.below(16),
+                    _ => rng.next(),
+                };
+                let mask = if width >= 64 {
+                    u64::MAX
+                } else {
+                    (1 << width) - 1
+                };
+                Some(InterpretArg::Uint(n & mask))
+            } else if ty.is_bool(ir) {
+                Some(InterpretArg::Bool(rng.below(2) == 1))
+            } else if ty.is_b256(ir) {
+                let mut bytes = [0; 32];
+                if rng.below(2) == 1 {
+                    bytes.iter_mut().for_each(|byte| *byte = rng.next() as u8);
+                }
+                Some(InterpretArg::B256(bytes))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Check that each transform preserves the behaviour of the functions in `source`, describing
+/// any which don't in `failures`.
+fn check_transforms(name: &str, source: &str, failures: &mut Vec<String>) {
+    let mut pass_mgr = PassManager::default();
+    register_known_passes(&mut pass_mgr);
+    let transforms = pass_mgr
+        .pass_names()
+        .filter(|pass_name| {
+            pass_mgr
+                .lookup_registered_pass(pass_name)
+                .unwrap()
+                .is_transform()
+                && !INSTRUMENTING_PASSES.contains(pass_name)
+        })
+        .collect::<Vec<_>>();
+
+    let before = sway_ir::parser::parse(source).unwrap_or_else(|err| panic!("{name}: {err}"));
+    for pass_name in transforms {
+        let mut after = sway_ir::parser::parse(source).unwrap();
+        let mut pass_group = PassGroup::default();
+        pass_group.append_pass(pass_name);
+        pass_mgr.run(&mut after, &pass_group).unwrap();
+
+        compare_functions(name, &format!("`{pass_name}`"), &before, &after, failures);
+    }
+}
+
+/// Check that each function of `before` which survives into `after` behaves the same in both,
+/// describing any which don't in `failures`.
+fn compare_functions(
+    name: &str,
+    transform: &str,
+    before: &Context,
+    after: &Context,
+    failures: &mut Vec<String>,
+) {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for function in before
+        .module_iter()
+        .flat_map(|module| module.function_iter(before))
+    {
+        // Functions may be removed, e.g. once they've been inlined.
+        let Some(transformed) = after
+            .module_iter()
+            .flat_map(|module| module.function_iter(after))
+            .find(|transformed| transformed.get_name(after) == function.get_name(before))
+        else {
+            continue;
+        };
+        for _ in 0..DIFFERENTIAL_SAMPLES {
+            let Some(args) = differential_args(before, function, &mut rng) else {
+                break;
+            };
+            // Only inputs which the interpreter can run to the end are compared.
+            let Ok(expected) = sway_ir::interpret(before, function, &args) else {
+                continue;
+            };
+            let actual = sway_ir::interpret(after, transformed, &args);
+            if actual.as_ref() != Ok(&expected) {
+                failures.push(format!(
+                    "{name}: {transform} changed `{}` on {args:?}: expected {expected:?}, \
+                    found {actual:?}",
+                    function.get_name(before)
+                ));
+            }
+        }
+    }
+}
+
+/// Generate a script whose `main` function computes a random mix of arithmetic on its arguments
+/// and some constants, branching on a comparison of the results.
+fn generate_module(rng: &mut Rng) -> String {
+    const OPS: [&str; 7] = ["add", "sub", "mul", "div", "and", "or", "xor"];
+    const CONSTANTS: [u64; 8] = [0, 1, 2, 3, 7, 255, u64::MAX - 1, u64::MAX];
+    let mut values = vec!["a".to_owned(), "b".to_owned(), "c".to_owned()];
+    let mut body = String::new();
+    let mut next_value = 0;
+    let mut operand = |rng: &mut Rng, values: &mut Vec<String>, body: &mut String| {
+        if rng.below(4) == 0 {
+            let name = format!("v{next_value}");
+            next_value += 1;
+            let n = CONSTANTS[rng.below(CONSTANTS.len() as u64) as usize];
+            body.push_str(&format!("        {name} = const u64 {n}\n"));
+            values.push(name.clone());
+            name
+        } else {
+            values[rng.below(values.len() as u64) as usize].clone()
+        }
+    };
+    let mut next_result = 0;
+    let mut op = |rng: &mut Rng, values: &mut Vec<String>, body: &mut String| {
+        let lhs = operand(rng, values, body);
+        let rhs = operand(rng, values, body);
+        let name = format!("r{next_result}");
+        next_result += 1;
+        let op = OPS[rng.below(OPS.len() as u64) as usize];
+        body.push_str(&format!("        {name} = {op} {lhs}, {rhs}\n"));
+        values.push(name.clone());
+        name
+    };
+
+    for _ in 0..4 + rng.below(8) {
+        op(rng, &mut values, &mut body);
+    }
+    let pred = ["eq", "lt", "gt"][rng.below(3) as usize];
+    let lhs = op(rng, &mut values, &mut body);
+    let rhs = op(rng, &mut values, &mut body);
+    body.push_str(&format!(
+        "        cond = cmp {pred} {lhs} {rhs}\n        cbr cond, block0(), block1()\n"
+    ));
+    // Every result is folded into the return value, otherwise DCE may legitimately remove an
+    // overflowing operation and turn a panic into a return.
+    let entry_results: Vec<String> = values
+        .iter()
+        .filter(|v| v.starts_with('r'))
+        .cloned()
+        .collect();
+    // Neither block dominates the other, so each may use only what's defined in the entry.
+    let entry_values = values.clone();
+    for (idx, block) in ["block0", "block1"].into_iter().enumerate() {
+        values.clone_from(&entry_values);
+        body.push_str(&format!("\n        {block}():\n"));
+        let mut result = op(rng, &mut values, &mut body);
+        for (i, value) in entry_results.iter().enumerate() {
+            let name = format!("s{idx}x{i}");
+            body.push_str(&format!("        {name} = xor {result}, {value}\n"));
+            result = name;
+        }
+        body.push_str(&format!("        ret u64 {result}\n"));
+    }
+    format!(
+        "script {{\n    fn main(a: u64, b: u64, c: u64) -> u64 {{\n        \
+        entry(a: u64, b: u64, c: u64):\n{body}    }}\n}}\n"
+    )
+}
+
+/// The name and source of every IR file under `tests`.
+fn test_corpus() -> Vec<(String, String)> {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let tests_dir: PathBuf = format!("{manifest_dir}/tests").into();
+    let mut paths = std::fs::read_dir(tests_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_dir())
+        .flat_map(|dir| std::fs::read_dir(dir).unwrap())
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "ir"))
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+        .into_iter()
+        .map(|path| {
+            let source = std::fs::read_to_string(&path).unwrap();
+            (path.display().to_string(), source)
+        })
+        .collect()
+}
+
+#[test]
+fn differential() {
+    let mut failures = Vec::new();
+
+    for (name, source) in test_corpus() {
+        check_transforms(&name, &source, &mut failures);
+    }
+
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for idx in 0..64 {
+        let source = generate_module(&mut rng);
+        check_transforms(&format!("generated module {idx}"), &source, &mut failures);
+        if failures.last().map_or(false, |failure| {
+            failure.starts_with(&format!("generated module {idx}:"))
+        }) {
+            println!("generated module {idx}:\n{source}");
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+// -------------------------------------------------------------------------------------------------
+// Pass ordering robustness.  The O2 pipeline is run in random orders, with some passes repeated, and
+// the IR must verify after every pass and behave as it did before.
+
+/// The number of shuffled pipelines each module is run through.
+const PASS_ORDERINGS: u64 = 8;
+
+#[test]
+fn pass_orderings() {
+    let mut failures = Vec::new();
+
+    let mut rng = Rng(0x6a09_e667_f3bc_c909);
+    let mut corpus = test_corpus();
+    corpus
+        .extend((0..16).map(|idx| (format!("generated module {idx}"), generate_module(&mut rng))));
+
+    for (name, source) in corpus {
+        let before = sway_ir::parser::parse(&source).unwrap_or_else(|err| panic!("{name}: {err}"));
+        for seed in 0..PASS_ORDERINGS {
+            let passes = shuffle_pass_group(&create_o2_pass_group(false), seed);
+            let ordering = format!("passes `{}`", passes.flatten_pass_group().join(" "));
+            let passes = insert_after_each(passes, sway_ir::MODULEVERIFIER_NAME);
+
+            // Analyses are cached by handle, so a pass manager mustn't be reused for another
+            // context once it has modified one.
+            let mut pass_mgr = PassManager::default();
+            register_known_passes(&mut pass_mgr);
+            let mut after = sway_ir::parser::parse(&source).unwrap();
+            match pass_mgr.run(&mut after, &passes) {
+                Ok(_) => compare_functions(&name, &ordering, &before, &after, &mut failures),
+                Err(err) => failures.push(format!("{name}: {ordering} failed: {err}")),
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn clone_function() {
+    let mut failures = Vec::new();
+
+    let mut rng = Rng(0xbb67_ae85_84ca_a73b);
+    for (name, source) in test_corpus() {
+        let mut ir = sway_ir::parser::parse(&source).unwrap_or_else(|err| panic!("{name}: {err}"));
+        let functions = ir
+            .module_iter()
+            .flat_map(|module| module.function_iter(&ir))
+            .collect::<Vec<_>>();
+        for function in functions {
+            let clone_name = format!("{}_clone", function.get_name(&ir));
+            let clone = function.clone_function(&mut ir, clone_name);
+
+            // Apart from the signature, the clone must print just like the original.
+            let body = |ir: &Context, function: Function| {
+                sway_ir::printer::function_to_string(ir, function)
+                    .lines()
+                    .skip(1)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+            if body(&ir, clone) != body(&ir, function) {
+                failures.push(format!(
+                    "{name}: clone of `{}` differs:\n{}",
+                    function.get_name(&ir),
+                    sway_ir::printer::function_to_string(&ir, clone)
+                ));
+            }
+
+            for _ in 0..DIFFERENTIAL_SAMPLES {
+                let Some(args) = differential_args(&ir, function, &mut rng) else {
+                    break;
+                };
+                let expected = sway_ir::interpret(&ir, function, &args);
+                let actual = sway_ir::interpret(&ir, clone, &args);
+                if actual != expected {
+                    failures.push(format!(
+                        "{name}: clone of `{}` on {args:?}: expected {expected:?}, found {actual:?}",
+                        function.get_name(&ir)
+                    ));
+                }
+            }
+        }
+        if let Err(err) = ir.verify() {
+            failures.push(format!("{name}: clones don't verify: {err}"));
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+// -------------------------------------------------------------------------------------------------
+// The users of each value are kept up to date by the passes, so must match those found by scanning
+// every block.
+
+/// Describe each value of `ir` whose users differ from those found by a scan.
+fn users_mismatches(ir: &Context) -> Vec<String> {
+    let mut scanned = HashMap::<Value, HashSet<Value>>::new();
+    for function in ir.module_iter().flat_map(|module| module.function_iter(ir)) {
+        for block in function.block_iter(ir) {
+            for arg in block.arg_iter(ir) {
+                scanned.entry(*arg).or_default();
+            }
+        }
+        for (_, ins) in function.instruction_iter(ir) {
+            scanned.entry(ins).or_default();
+            for operand in ins.get_instruction(ir).unwrap().get_operands() {
+                scanned.entry(operand).or_default().insert(ins);
+            }
+        }
+    }
+
+    let printed = |value: &Value| match value.get_instruction(ir) {
+        Some(ins) => format!("{value:?} = {ins:?}"),
+        None => format!("{value:?}"),
+    };
+    scanned
+        .iter()
+        .filter_map(|(value, scanned_users)| {
+            let users = value.users(ir);
+            (users.len() != scanned_users.len()
+                || !users.iter().all(|user| scanned_users.contains(user)))
+            .then(|| {
+                format!(
+                    "`{}` is used by [{}] but recorded as used by [{}]",
+                    printed(value),
+                    scanned_users
+                        .iter()
+                        .map(printed)
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    users.iter().map(printed).collect::<Vec<_>>().join(", ")
+                )
+            })
+        })
+        .collect()
+}
+
+fn parent_mismatches(ir: &Context) -> Vec<String> {
+    let mut mismatches = Vec::new();
+    for function in ir.module_iter().flat_map(|module| module.function_iter(ir)) {
+        for block in function.block_iter(ir) {
+            if block.get_function(ir) != function {
+                mismatches.push(format!(
+                    "block `{}` isn't recorded as in `{}`",
+                    block.get_label(ir),
+                    function.get_name(ir)
+                ));
+            }
+            for ins in block.instruction_iter(ir) {
+                if ins.get_block(ir) != Some(block) {
+                    mismatches.push(format!(
+                        "`{ins:?} = {:?}` isn't recorded as in block `{}`",
+                        ins.get_instruction(ir).unwrap(),
+                        block.get_label(ir)
+                    ));
+                }
+            }
+        }
+    }
+    mismatches
+}
+
+#[test]
+fn value_users() {
+    let mut failures = Vec::new();
+
+    let mut pass_mgr = PassManager::default();
+    register_known_passes(&mut pass_mgr);
+    let transforms = pass_mgr
+        .pass_names()
+        .filter(|pass_name| {
+            pass_mgr
+                .lookup_registered_pass(pass_name)
+                .unwrap()
+                .is_transform()
+        })
+        .map(|pass_name| vec![pass_name])
+        .chain([create_o2_pass_group(false).flatten_pass_group()])
+        .collect::<Vec<_>>();
+
+    let mut rng = Rng(0x3c6e_f372_fe94_f82b);
+    let mut corpus = test_corpus();
+    corpus
+        .extend((0..16).map(|idx| (format!("generated module {idx}"), generate_module(&mut rng))));
+    for (name, source) in corpus {
+        let ir = sway_ir::parser::parse(&source).unwrap_or_else(|err| panic!("{name}: {err}"));
+        for mismatch in users_mismatches(&ir)
+            .into_iter()
+            .chain(parent_mismatches(&ir))
+        {
+            failures.push(format!("{name}: parsed: {mismatch}"));
+        }
+        for passes in &transforms {
+            let mut pass_mgr = PassManager::default();
+            register_known_passes(&mut pass_mgr);
+            let mut ir = sway_ir::parser::parse(&source).unwrap();
+            let mut pass_group = PassGroup::default();
+            for pass in passes {
+                pass_group.append_pass(pass);
+            }
+            pass_mgr.run(&mut ir, &pass_group).unwrap();
+            for mismatch in users_mismatches(&ir)
+                .into_iter()
+                .chain(parent_mismatches(&ir))
+            {
+                failures.push(format!("{name}: after `{}`: {mismatch}", passes.join(" ")));
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+// -------------------------------------------------------------------------------------------------
+// Compiling the same module twice must give byte-identical IR and bytecode, whatever order the
+// hash maps used along the way iterate in.
+
+/// The number of times each module is compiled and compared with the first compilation.
+const DETERMINISM_RUNS: usize = 3;
+
+#[test]
+fn deterministic_output() {
+    let mut failures = Vec::new();
+
+    let mut pass_mgr = PassManager::default();
+    register_known_passes(&mut pass_mgr);
+    let pipelines = pass_mgr
+        .pass_names()
+        .filter(|pass_name| {
+            pass_mgr
+                .lookup_registered_pass(pass_name)
+                .unwrap()
+                .is_transform()
+        })
+        .map(|pass_name| vec![pass_name])
+        .chain([create_o2_pass_group(false).flatten_pass_group()])
+        .collect::<Vec<_>>();
+
+    let mut rng = Rng(0xbb67_ae85_84ca_a73b);
+    let mut corpus = test_corpus();
+    corpus
+        .extend((0..16).map(|idx| (format!("generated module {idx}"), generate_module(&mut rng))));
+    for (name, source) in corpus {
+        for passes in &pipelines {
+            let compile = || {
+                let mut pass_mgr = PassManager::default();
+                register_known_passes(&mut pass_mgr);
+                let mut ir = sway_ir::parser::parse(&source).unwrap();
+                let mut pass_group = PassGroup::default();
+                for pass in passes {
+                    pass_group.append_pass(pass);
+                }
+                pass_mgr.run(&mut ir, &pass_group).unwrap();
+                (sway_ir::printer::to_string(&ir), ir.to_bytes().unwrap())
+            };
+            let (text, bytes) = compile();
+            for _ in 1..DETERMINISM_RUNS {
+                let (other_text, other_bytes) = compile();
+                if other_text != text {
+                    failures.push(format!(
+                        "{name}: after `{}` the printed IR differs:\n{text}\n{other_text}",
+                        passes.join(" ")
+                    ));
+                    break;
+                }
+                if other_bytes != bytes {
+                    failures.push(format!(
+                        "{name}: after `{}` the bytecode differs",
+                        passes.join(" ")
+                    ));
+                    break;
+                }
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+// -------------------------------------------------------------------------------------------------
+// The `opt` tool.
+
+const OPT_IR: &str = r#"
+script {
+    fn main() -> u64 {
+        entry():
+        v0 = const u64 1
+        v1 = call id(v0)
+        ret u64 v1
+    }
+
+    fn id(x: u64) -> u64 {
+        entry(x: u64):
+        ret u64 x
+    }
+}
+"#;
+
+/// Run `opt` with `args`, returning what it printed to stdout and to stderr.
+fn run_opt(args: &[&str]) -> (String, String) {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_opt"))
+        .args(args)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(output.status.success(), "{stderr}");
+    (String::from_utf8(output.stdout).unwrap(), stderr)
+}
+
+#[test]
+fn opt_binary_input() {
+    let dir = std::env::temp_dir().join(format!("sway-ir-opt-binary-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let text_path = dir.join("main.ir");
+    let binary_path = dir.join("main.bin");
+    std::fs::write(&text_path, OPT_IR).unwrap();
+    let bytes = sway_ir::parser::parse(OPT_IR).unwrap().to_bytes().unwrap();
+    std::fs::write(&binary_path, bytes).unwrap();
+
+    // The binary IR is optimized just as the text it was encoded from.
+    let from_text = run_opt(&["inline_main", "dce", "-i", text_path.to_str().unwrap()]).0;
+    let from_binary = run_opt(&["inline_main", "dce", "-i", binary_path.to_str().unwrap()]).0;
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(!from_text.contains("call id"), "{from_text}");
+    assert_eq!(from_binary, from_text);
+}
+
+#[test]
+fn opt_print_before_and_after_passes() {
+    let dir = std::env::temp_dir().join(format!("sway-ir-opt-print-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("main.ir");
+    std::fs::write(&path, OPT_IR).unwrap();
+    let path = path.to_str().unwrap();
+
+    // Only the chosen passes print, and only the chosen functions.
+    let printed = run_opt(&[
+        "inline_main",
+        "dce",
+        "-print-before",
+        "inline_main",
+        "-print-after",
+        "dce",
+        "-function",
+        "main",
+        "-i",
+        path,
+    ])
+    .1;
+    let (before_inline, after_dce) = printed
+        .split_once("// IR after dce:")
+        .expect("IR after dce");
+    assert!(
+        before_inline.starts_with("// IR before inline_main:"),
+        "{printed}"
+    );
+    assert!(before_inline.contains("call id"), "{printed}");
+    assert!(!after_dce.contains("call id"), "{printed}");
+    assert!(!printed.contains("// IR after inline_main:"), "{printed}");
+    assert!(!printed.contains("// IR before dce:"), "{printed}");
+    assert!(!printed.contains("fn id("), "{printed}");
+
+    // Every transform pass prints when asked for all of them.
+    let printed = run_opt(&["inline_main", "dce", "-print-after-all", "-i", path]).1;
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(printed.contains("// IR after inline_main:"), "{printed}");
+    assert!(printed.contains("// IR after dce:"), "{printed}");
+    assert!(!printed.contains("// IR before"), "{printed}");
+}
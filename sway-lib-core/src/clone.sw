@@ -0,0 +1,49 @@
+library;
+
+/// A value that can be explicitly duplicated.
+pub trait Clone {
+    /// Returns a copy of `self`.
+    fn clone(self) -> Self;
+}
+
+impl Clone for bool {
+    fn clone(self) -> Self {
+        self
+    }
+}
+
+impl Clone for u64 {
+    fn clone(self) -> Self {
+        self
+    }
+}
+
+impl Clone for u32 {
+    fn clone(self) -> Self {
+        self
+    }
+}
+
+impl Clone for u16 {
+    fn clone(self) -> Self {
+        self
+    }
+}
+
+impl Clone for u8 {
+    fn clone(self) -> Self {
+        self
+    }
+}
+
+impl Clone for b256 {
+    fn clone(self) -> Self {
+        self
+    }
+}
+
+impl Clone for raw_ptr {
+    fn clone(self) -> Self {
+        self
+    }
+}
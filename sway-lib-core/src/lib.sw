@@ -4,5 +4,6 @@ mod primitives;
 mod raw_ptr;
 mod raw_slice;
 mod ops;
+mod clone;
 mod never;
 mod prelude;
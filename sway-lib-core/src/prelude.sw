@@ -8,3 +8,4 @@ use ::raw_ptr::*;
 use ::raw_slice::*;
 use ::never::*;
 use ::ops::*;
+use ::clone::*;
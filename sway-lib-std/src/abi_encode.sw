@@ -0,0 +1,77 @@
+library;
+
+use ::assert::assert;
+use ::bytes::Bytes;
+use ::convert::From;
+use ::option::Option;
+
+/// A value that can be encoded as specified by the Fuel ABI.
+///
+/// Every value is encoded as the words which hold it in memory: integers and booleans take a
+/// single big-endian word, a `b256` takes four words, structs and tuples are their fields in
+/// order and enums are their variant index followed by the value of the variant, left-padded to
+/// the size of the largest variant.
+pub trait AbiEncode {
+    /// Appends the encoding of `self` to `buffer`.
+    fn abi_encode(self, ref mut buffer: Bytes);
+}
+
+impl AbiEncode for bool {
+    fn abi_encode(self, ref mut buffer: Bytes) {
+        write_word(buffer, asm(value: self) { value: u64 });
+    }
+}
+
+impl AbiEncode for u64 {
+    fn abi_encode(self, ref mut buffer: Bytes) {
+        write_word(buffer, self);
+    }
+}
+
+impl AbiEncode for u32 {
+    fn abi_encode(self, ref mut buffer: Bytes) {
+        write_word(buffer, asm(value: self) { value: u64 });
+    }
+}
+
+impl AbiEncode for u16 {
+    fn abi_encode(self, ref mut buffer: Bytes) {
+        write_word(buffer, asm(value: self) { value: u64 });
+    }
+}
+
+impl AbiEncode for u8 {
+    fn abi_encode(self, ref mut buffer: Bytes) {
+        write_word(buffer, asm(value: self) { value: u64 });
+    }
+}
+
+impl AbiEncode for b256 {
+    fn abi_encode(self, ref mut buffer: Bytes) {
+        let mut value = Bytes::from(self);
+        buffer.append(value);
+    }
+}
+
+fn write_word(ref mut buffer: Bytes, word: u64) {
+    let mut shift = 64;
+    while shift > 0 {
+        shift -= 8;
+        buffer.push(asm(byte: (word >> shift) & 0xff) { byte: u8 });
+    }
+}
+
+#[test]
+fn test_abi_encode_words() {
+    let mut buffer = Bytes::new();
+    true.abi_encode(buffer);
+    258.abi_encode(buffer);
+    assert(buffer.len() == 16);
+    assert(buffer.get(7).unwrap() == 1u8);
+    assert(buffer.get(14).unwrap() == 1u8);
+    assert(buffer.get(15).unwrap() == 2u8);
+
+    0x0000000000000000000000000000000000000000000000000000000000000003.abi_encode(buffer);
+    assert(buffer.len() == 48);
+    assert(buffer.get(47).unwrap() == 3u8);
+}
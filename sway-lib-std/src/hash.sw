@@ -41,3 +41,84 @@ pub fn keccak256<T>(param: T) -> b256 {
         }
     }
 }
+
+/// Accumulates the values written by `Hash` implementations into a single `SHA-2-256` hash.
+///
+/// ### Examples
+///
+/// ```sway
+/// use std::hash::{Hash, Hasher};
+///
+/// let mut hasher = Hasher::new();
+/// 42.hash(hasher);
+/// let hash = hasher.finish();
+/// ```
+pub struct Hasher {
+    state: b256,
+}
+
+impl Hasher {
+    /// Creates a `Hasher` which has not been written to.
+    pub fn new() -> Self {
+        Self {
+            state: b256::min(),
+        }
+    }
+
+    /// Feeds `word` into the hash.
+    pub fn write(ref mut self, word: u64) {
+        self.state = sha256((self.state, word));
+    }
+
+    /// Feeds `value` into the hash.
+    pub fn write_b256(ref mut self, value: b256) {
+        self.state = sha256((self.state, value));
+    }
+
+    /// Returns the hash of the values written so far.
+    pub fn finish(self) -> b256 {
+        self.state
+    }
+}
+
+/// A value that can be fed into a `Hasher`.
+pub trait Hash {
+    /// Feeds `self` into `state`.
+    fn hash(self, ref mut state: Hasher);
+}
+
+impl Hash for bool {
+    fn hash(self, ref mut state: Hasher) {
+        state.write(asm(value: self) { value: u64 });
+    }
+}
+
+impl Hash for u64 {
+    fn hash(self, ref mut state: Hasher) {
+        state.write(self);
+    }
+}
+
+impl Hash for u32 {
+    fn hash(self, ref mut state: Hasher) {
+        state.write(asm(value: self) { value: u64 });
+    }
+}
+
+impl Hash for u16 {
+    fn hash(self, ref mut state: Hasher) {
+        state.write(asm(value: self) { value: u64 });
+    }
+}
+
+impl Hash for u8 {
+    fn hash(self, ref mut state: Hasher) {
+        state.write(asm(value: self) { value: u64 });
+    }
+}
+
+impl Hash for b256 {
+    fn hash(self, ref mut state: Hasher) {
+        state.write_b256(self);
+    }
+}
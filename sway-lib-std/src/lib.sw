@@ -24,6 +24,7 @@ mod address;
 mod identity;
 mod vec;
 mod bytes;
+mod abi_encode;
 mod r#storage;
 mod b256;
 mod tx;
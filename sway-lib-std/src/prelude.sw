@@ -11,6 +11,7 @@ use ::identity::Identity;
 // Collections
 use ::storage::StorageMap;
 use ::vec::Vec;
+use ::bytes::Bytes;
 
 // Error handling
 use ::assert::assert;
@@ -21,5 +22,9 @@ use ::revert::{require, revert};
 // Convert
 use ::convert::From;
 
+// Derivable traits
+use ::abi_encode::AbiEncode;
+use ::hash::{Hash, Hasher};
+
 // Logging
 use ::logging::log;
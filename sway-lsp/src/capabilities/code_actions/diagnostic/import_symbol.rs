@@ -0,0 +1,53 @@
+use sway_ast::ItemKind;
+use sway_types::Spanned;
+use tower_lsp::lsp_types::{Range, TextEdit};
+
+use crate::core::token::get_range_from_span;
+
+use super::{QuickFix, QuickFixContext};
+
+/// Returns a fix importing each of the public declarations named after the unresolved symbol.
+pub(crate) fn quick_fixes(ctx: &QuickFixContext, name: &str) -> Vec<QuickFix> {
+    // Insert the import after the last `use` statement, or else after the module declarations or
+    // the program kind.
+    let items = &ctx.module.items;
+    let last_use = items
+        .iter()
+        .filter(|item| matches!(item.value, ItemKind::Use(_)))
+        .last();
+    let last_submodule = items
+        .iter()
+        .filter(|item| matches!(item.value, ItemKind::Submodule(_)))
+        .last();
+    let (anchor, separator) = match (last_use, last_submodule) {
+        (Some(item), _) => (item.span(), "\n"),
+        (None, Some(item)) => (item.span(), "\n\n"),
+        (None, None) => (ctx.module.semicolon_token.span(), "\n\n"),
+    };
+    let position = get_range_from_span(&anchor).end;
+
+    ctx.declarations_named(name)
+        .into_iter()
+        .filter(|(path, decl)| !path.is_empty() && ctx.is_public(decl))
+        .map(|(path, _)| {
+            // Imports are relative to the current module. Dependencies are reachable from every
+            // module, but the other modules of the package are only reachable from the root.
+            let is_external = ctx
+                .program
+                .root
+                .namespace
+                .submodules()
+                .get(&path[0])
+                .map_or(false, |module| module.is_external);
+            let root = if ctx.is_root || is_external { "" } else { "::" };
+            let import = format!("{root}{}::{name}", path.join("::"));
+            QuickFix {
+                title: format!("Import `{import}`"),
+                edits: vec![TextEdit::new(
+                    Range::new(position, position),
+                    format!("{separator}use {import};"),
+                )],
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,119 @@
+use std::collections::HashSet;
+use sway_ast::ItemKind;
+use sway_core::{
+    language::ty::{self, TyFunctionParameter, TyTraitFn},
+    transform::AttributeKind,
+};
+use sway_types::{Span, Spanned};
+use tower_lsp::lsp_types::{Position, Range, TextEdit};
+
+use crate::{capabilities::code_actions::TAB, core::token::get_range_from_span};
+
+use super::{QuickFix, QuickFixContext};
+
+/// Returns a fix inserting a skeleton of each method missing from the trait implementation.
+pub(crate) fn quick_fix(
+    ctx: &QuickFixContext,
+    impl_span: &Span,
+    missing_functions: &str,
+) -> Option<QuickFix> {
+    let item_impl = ctx.module.items.iter().find_map(|item| match &item.value {
+        ItemKind::Impl(item_impl) if item_impl.span() == *impl_span => Some(item_impl),
+        _ => None,
+    })?;
+    let (trait_path, _) = item_impl.trait_opt.as_ref()?;
+    let trait_name = trait_path.last_segment().name.as_str();
+    let decl_engine = ctx.engines.de();
+    let interface_surface =
+        ctx.declarations_named(trait_name)
+            .into_iter()
+            .find_map(|(_, decl)| match decl {
+                ty::TyDeclaration::TraitDeclaration { decl_id, .. } => {
+                    Some(decl_engine.get_trait(&decl_id).interface_surface)
+                }
+                ty::TyDeclaration::AbiDeclaration { decl_id, .. } => {
+                    Some(decl_engine.get_abi(&decl_id).interface_surface)
+                }
+                _ => None,
+            })?;
+
+    let missing_functions: HashSet<&str> = missing_functions.lines().collect();
+    let impl_start = get_range_from_span(&item_impl.impl_token.span()).start;
+    let indent = " ".repeat(impl_start.character as usize);
+    let signatures = interface_surface
+        .iter()
+        .map(|item| match item {
+            ty::TyTraitInterfaceItem::TraitFn(decl_ref) => decl_engine.get_trait_fn(decl_ref.id()),
+        })
+        .filter(|trait_fn| missing_functions.contains(trait_fn.name.as_str()))
+        .map(|trait_fn| fn_skeleton(ctx, &trait_fn, &format!("{indent}{TAB}")))
+        .collect::<Vec<_>>();
+    if signatures.is_empty() {
+        return None;
+    }
+    let signatures = signatures.join("\n");
+
+    // Insert the methods on their own lines before the closing brace, separated from any existing
+    // ones by an empty line.
+    let braces = get_range_from_span(&item_impl.contents.span());
+    let last_line = Position::new(braces.end.line, 0);
+    let (position, new_text) = if braces.start.line == braces.end.line {
+        let close_brace = Position::new(braces.end.line, braces.end.character - 1);
+        (close_brace, format!("\n{signatures}\n{indent}"))
+    } else if item_impl.contents.get().is_empty() {
+        (last_line, format!("{signatures}\n"))
+    } else {
+        (last_line, format!("\n{signatures}\n"))
+    };
+    Some(QuickFix {
+        title: format!("Implement missing methods of `{trait_name}`"),
+        edits: vec![TextEdit::new(Range::new(position, position), new_text)],
+    })
+}
+
+/// Returns the declaration of a method matching the signature of the trait function along with
+/// its attributes. The body is empty if the method returns unit, and reverts otherwise so that it
+/// type checks until it's implemented.
+fn fn_skeleton(ctx: &QuickFixContext, trait_fn: &TyTraitFn, indent: &str) -> String {
+    let attributes = trait_fn
+        .attributes
+        .iter()
+        .filter(|(kind, _)| !matches!(kind, AttributeKind::DocComment { .. }))
+        .flat_map(|(_, attrs)| attrs.iter())
+        .map(|attr| format!("{indent}{}\n", attr.span.as_str()))
+        .collect::<String>();
+    let params = trait_fn
+        .parameters
+        .iter()
+        .map(param_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+    // Unit is the implicit return type.
+    let (return_type, body) = if ctx.engines.te().get(trait_fn.return_type).is_unit() {
+        (String::new(), "{}".to_string())
+    } else {
+        (
+            format!(" -> {}", trait_fn.return_type_span.as_str()),
+            format!("{{\n{indent}{TAB}__revert(0)\n{indent}}}"),
+        )
+    };
+    let name = &trait_fn.name;
+    format!("{attributes}{indent}fn {name}({params}){return_type} {body}")
+}
+
+fn param_string(param: &TyFunctionParameter) -> String {
+    let modifiers = match (param.is_reference, param.is_mutable) {
+        (true, true) => "ref mut ",
+        (false, true) => "mut ",
+        _ => "",
+    };
+    if param.is_self() {
+        format!("{modifiers}self")
+    } else {
+        format!(
+            "{modifiers}{}: {}",
+            param.name,
+            param.type_argument.span.as_str()
+        )
+    }
+}
@@ -0,0 +1,222 @@
+pub(crate) mod import_symbol;
+pub(crate) mod missing_methods;
+pub(crate) mod storage_attribute;
+pub(crate) mod unused_variable;
+
+use crate::{
+    capabilities::{
+        diagnostic::{get_error_diagnostic, get_warning_diagnostic},
+        document_symbol::lexed_modules,
+    },
+    core::{session::Session, token::get_range_from_span},
+};
+use serde_json::Value;
+use std::{collections::HashMap, path::Path};
+use sway_ast::Module;
+use sway_core::{language::ty, namespace, Engines};
+use sway_error::{error::CompileError, warning::Warning};
+use sway_types::{Ident, Span, Spanned};
+use tower_lsp::lsp_types::{
+    CodeAction as LspCodeAction, CodeActionKind, CodeActionOrCommand, Diagnostic, Range, TextEdit,
+    Url, WorkspaceEdit,
+};
+
+/// The state shared by the quick fixes offered for the diagnostics of a single file.
+pub(crate) struct QuickFixContext<'a> {
+    engines: Engines<'a>,
+    session: &'a Session,
+    program: &'a ty::TyProgram,
+    /// The syntax tree of the file, against which the edits are computed.
+    module: &'a Module,
+    /// Whether the file holds the root module of the program.
+    is_root: bool,
+    temp_uri: &'a Url,
+}
+
+/// A fix for a single diagnostic, made of the title of the code action and the edits it applies.
+pub(crate) struct QuickFix {
+    title: String,
+    edits: Vec<TextEdit>,
+}
+
+/// Returns the quick fixes for the warnings and errors of the last compilation which lie within
+/// the given range.
+pub(crate) fn code_actions(
+    session: &Session,
+    range: &Range,
+    temp_uri: &Url,
+    uri: &Url,
+) -> Vec<CodeActionOrCommand> {
+    let compiled_program = session.compiled_program.read();
+    let (lexed, program) = match (&compiled_program.lexed, &compiled_program.typed) {
+        (Some(lexed), Some(program)) => (lexed, program),
+        _ => return vec![],
+    };
+    let path = match temp_uri.to_file_path() {
+        Ok(path) => path,
+        Err(_) => return vec![],
+    };
+    let modules = lexed_modules(&lexed.root);
+    let index = match modules
+        .iter()
+        .position(|module| is_in_file(&module.kind.span(), &path))
+    {
+        Some(index) => index,
+        None => return vec![],
+    };
+    let type_engine = session.type_engine.read();
+    let decl_engine = session.decl_engine.read();
+    let ctx = QuickFixContext {
+        engines: Engines::new(&type_engine, &decl_engine),
+        session,
+        program,
+        module: modules[index],
+        is_root: index == 0,
+        temp_uri,
+    };
+    let in_range = |span: &Span| {
+        let span_range = get_range_from_span(span);
+        is_in_file(span, &path) && span_range.start <= range.end && range.start <= span_range.end
+    };
+
+    let mut actions = vec![];
+    for error in session.compile_errors.read().iter() {
+        if !in_range(&error.span()) {
+            continue;
+        }
+        let fixes = match error {
+            CompileError::MissingInterfaceSurfaceMethods {
+                missing_functions,
+                span,
+            } => missing_methods::quick_fix(&ctx, span, missing_functions)
+                .into_iter()
+                .collect(),
+            CompileError::SymbolNotFound { name, .. } => {
+                import_symbol::quick_fixes(&ctx, name.as_str())
+            }
+            CompileError::UnknownTypeName { name, .. }
+            | CompileError::TraitNotFound { name, .. } => import_symbol::quick_fixes(&ctx, name),
+            CompileError::StorageAccessMismatch { attrs, span }
+            | CompileError::ImpureInPureContext { attrs, span, .. } => {
+                storage_attribute::quick_fix(&ctx, attrs, span)
+                    .into_iter()
+                    .collect()
+            }
+            _ => vec![],
+        };
+        let diagnostic = get_error_diagnostic(error);
+        actions.extend(
+            fixes
+                .into_iter()
+                .map(|fix| fix.code_action(uri, diagnostic.clone())),
+        );
+    }
+    for warning in session.compile_warnings.read().iter() {
+        if !in_range(&warning.span()) {
+            continue;
+        }
+        let fix = match warning.warning_content {
+            Warning::DeadDeclaration => unused_variable::quick_fix(&ctx, &warning.span()),
+            _ => None,
+        };
+        let diagnostic = get_warning_diagnostic(warning);
+        actions.extend(fix.map(|fix| fix.code_action(uri, diagnostic)));
+    }
+    actions
+}
+
+impl QuickFix {
+    fn code_action(self, uri: &Url, diagnostic: Diagnostic) -> CodeActionOrCommand {
+        let changes = HashMap::from([(uri.clone(), self.edits)]);
+        CodeActionOrCommand::CodeAction(LspCodeAction {
+            title: self.title,
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(vec![diagnostic]),
+            edit: Some(WorkspaceEdit {
+                changes: Some(changes),
+                ..Default::default()
+            }),
+            data: Some(Value::String(uri.to_string())),
+            ..Default::default()
+        })
+    }
+}
+
+impl QuickFixContext<'_> {
+    /// Returns the declarations of the given name found in any module of the program or of its
+    /// dependencies, along with the shortest path of the module they can be imported from.
+    fn declarations_named(&self, name: &str) -> Vec<(Vec<String>, ty::TyDeclaration)> {
+        let mut declarations: Vec<(Vec<String>, Ident, ty::TyDeclaration)> = vec![];
+        collect_declarations(
+            &self.program.root.namespace,
+            &mut vec![],
+            name,
+            &mut declarations,
+        );
+        declarations.sort_by_key(|(path, ..)| path.len());
+        let mut unique: Vec<(Vec<String>, Ident, ty::TyDeclaration)> = vec![];
+        for declaration in declarations {
+            if !unique
+                .iter()
+                .any(|(_, ident, _)| ident.span() == declaration.1.span())
+            {
+                unique.push(declaration);
+            }
+        }
+        unique
+            .into_iter()
+            .map(|(path, _, decl)| (path, decl))
+            .collect()
+    }
+
+    fn is_public(&self, decl: &ty::TyDeclaration) -> bool {
+        let decl_engine = self.engines.de();
+        match decl {
+            ty::TyDeclaration::ConstantDeclaration { decl_id, .. } => {
+                decl_engine.get_constant(decl_id).visibility.is_public()
+            }
+            ty::TyDeclaration::FunctionDeclaration { decl_id, .. } => {
+                decl_engine.get_function(decl_id).visibility.is_public()
+            }
+            ty::TyDeclaration::TraitDeclaration { decl_id, .. } => {
+                decl_engine.get_trait(decl_id).visibility.is_public()
+            }
+            ty::TyDeclaration::StructDeclaration { decl_id, .. } => {
+                decl_engine.get_struct(decl_id).visibility.is_public()
+            }
+            ty::TyDeclaration::EnumDeclaration { decl_id, .. } => {
+                decl_engine.get_enum(decl_id).visibility.is_public()
+            }
+            ty::TyDeclaration::AbiDeclaration { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+fn collect_declarations(
+    module: &namespace::Module,
+    path: &mut Vec<String>,
+    name: &str,
+    declarations: &mut Vec<(Vec<String>, Ident, ty::TyDeclaration)>,
+) {
+    for (ident, decl) in module.symbols().iter() {
+        if ident.as_str() == name {
+            declarations.push((path.clone(), ident.clone(), decl.clone()));
+        }
+    }
+    for (submodule_name, submodule) in module.submodules().iter() {
+        path.push(submodule_name.clone());
+        collect_declarations(submodule, path, name, declarations);
+        path.pop();
+    }
+}
+
+fn is_in_file(span: &Span, path: &Path) -> bool {
+    span.path()
+        .map_or(false, |span_path| span_path.as_path() == path)
+}
+
+/// Returns true if the `outer` span encloses the `inner` one.
+fn contains(outer: &Span, inner: &Span) -> bool {
+    outer.path() == inner.path() && outer.start() <= inner.start() && inner.end() <= outer.end()
+}
@@ -0,0 +1,71 @@
+use sway_ast::{AttributeDecl, ItemFn, ItemImplItem, ItemKind, Module};
+use sway_types::{constants::STORAGE_PURITY_ATTRIBUTE_NAME, Span, Spanned};
+use tower_lsp::lsp_types::{Range, TextEdit};
+
+use crate::core::token::get_range_from_span;
+
+use super::{contains, QuickFix, QuickFixContext};
+
+/// Returns a fix giving the function which encloses the storage access the storage attribute
+/// reported by the compiler, either by extending its existing attribute or by adding a new one.
+pub(crate) fn quick_fix(ctx: &QuickFixContext, attrs: &str, span: &Span) -> Option<QuickFix> {
+    let (attribute_list, item_fn) = functions(ctx.module)
+        .into_iter()
+        .find(|(_, item_fn)| contains(&item_fn.span(), span))?;
+    let attribute = format!("{STORAGE_PURITY_ATTRIBUTE_NAME}({attrs})");
+    let existing = attribute_list
+        .iter()
+        .flat_map(|attr_decl| attr_decl.attribute.get())
+        .find(|attr| attr.name.as_str() == STORAGE_PURITY_ATTRIBUTE_NAME);
+    let edit = match existing {
+        Some(attr) => TextEdit::new(get_range_from_span(&attr.span()), attribute.clone()),
+        None => {
+            let start = get_range_from_span(&item_fn.fn_signature.span()).start;
+            let indent = " ".repeat(start.character as usize);
+            TextEdit::new(
+                Range::new(start, start),
+                format!("#[{attribute}]\n{indent}"),
+            )
+        }
+    };
+    Some(QuickFix {
+        title: format!("Add `#[{attribute}]` to `{}`", item_fn.fn_signature.name),
+        edits: vec![edit],
+    })
+}
+
+/// Returns the functions of the module along with their attributes, including the methods of
+/// its impl blocks and the provided methods of its traits and ABIs.
+fn functions(module: &Module) -> Vec<(&Vec<AttributeDecl>, &ItemFn)> {
+    let mut functions = vec![];
+    for item in &module.items {
+        match &item.value {
+            ItemKind::Fn(item_fn) => functions.push((&item.attribute_list, item_fn)),
+            ItemKind::Impl(item_impl) => {
+                for annotated in item_impl.contents.get() {
+                    match &annotated.value {
+                        ItemImplItem::Fn(item_fn) => {
+                            functions.push((&annotated.attribute_list, item_fn))
+                        }
+                    }
+                }
+            }
+            ItemKind::Trait(item_trait) => {
+                if let Some(defs) = &item_trait.trait_defs_opt {
+                    for annotated in defs.get() {
+                        functions.push((&annotated.attribute_list, &annotated.value));
+                    }
+                }
+            }
+            ItemKind::Abi(item_abi) => {
+                if let Some(defs) = &item_abi.abi_defs_opt {
+                    for annotated in defs.get() {
+                        functions.push((&annotated.attribute_list, &annotated.value));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    functions
+}
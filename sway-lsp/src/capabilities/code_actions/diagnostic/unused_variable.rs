@@ -0,0 +1,30 @@
+use sway_core::language::ty;
+use sway_types::{Span, Spanned};
+use tower_lsp::lsp_types::{Range, TextEdit};
+
+use crate::core::token::{get_range_from_span, TypedAstToken};
+
+use super::{QuickFix, QuickFixContext};
+
+/// Returns a fix prefixing the name of an unused variable with an underscore, which silences the
+/// warning.
+pub(crate) fn quick_fix(ctx: &QuickFixContext, span: &Span) -> Option<QuickFix> {
+    let start = get_range_from_span(span).start;
+    let (ident, token) = ctx
+        .session
+        .token_map()
+        .token_at_position(ctx.temp_uri, start)?;
+    let is_variable = matches!(
+        token.typed,
+        Some(TypedAstToken::TypedDeclaration(
+            ty::TyDeclaration::VariableDeclaration(_)
+        ))
+    );
+    if !is_variable || ident.span() != *span || ident.as_str().starts_with('_') {
+        return None;
+    }
+    Some(QuickFix {
+        title: format!("Prefix `{ident}` with an underscore"),
+        edits: vec![TextEdit::new(Range::new(start, start), "_".to_string())],
+    })
+}
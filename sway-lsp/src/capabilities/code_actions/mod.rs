@@ -1,4 +1,5 @@
 pub mod abi_decl;
+pub mod diagnostic;
 pub mod struct_decl;
 
 use crate::core::{
@@ -38,6 +39,24 @@ pub(crate) fn code_actions(
     range: &Range,
     text_document: TextDocumentIdentifier,
     temp_uri: &Url,
+) -> Option<CodeActionResponse> {
+    let mut actions = diagnostic::code_actions(&session, range, temp_uri, &text_document.uri);
+    actions.extend(
+        refactor_code_actions(&session, range, &text_document, temp_uri).unwrap_or_default(),
+    );
+    if actions.is_empty() {
+        None
+    } else {
+        Some(actions)
+    }
+}
+
+/// Returns the code actions generating code for the declaration at the start of the range.
+fn refactor_code_actions(
+    session: &Session,
+    range: &Range,
+    text_document: &TextDocumentIdentifier,
+    temp_uri: &Url,
 ) -> Option<CodeActionResponse> {
     let (_, token) = session
         .token_map()
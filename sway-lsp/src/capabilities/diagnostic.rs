@@ -1,32 +1,45 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, DiagnosticTag, Position, Range};
 
 use sway_error::warning::CompileWarning;
 use sway_error::{error::CompileError, warning::Warning};
 use sway_types::{LineCol, Spanned};
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct Diagnostics {
     pub warnings: Vec<Diagnostic>,
     pub errors: Vec<Diagnostic>,
 }
 
-fn get_error_diagnostics(errors: &[CompileError]) -> Vec<Diagnostic> {
-    Vec::from_iter(errors.iter().map(|error| Diagnostic {
+pub(crate) fn get_error_diagnostic(error: &CompileError) -> Diagnostic {
+    Diagnostic {
         range: get_range(error.span().line_col()),
         severity: Some(DiagnosticSeverity::ERROR),
         message: format!("{error}"),
         ..Default::default()
-    }))
+    }
 }
 
-fn get_warning_diagnostics(warnings: &[CompileWarning]) -> Vec<Diagnostic> {
-    Vec::from_iter(warnings.iter().map(|warning| Diagnostic {
+pub(crate) fn get_warning_diagnostic(warning: &CompileWarning) -> Diagnostic {
+    Diagnostic {
         range: get_range(warning.span().line_col()),
         severity: Some(DiagnosticSeverity::WARNING),
         message: warning.to_friendly_warning_string(),
         tags: get_warning_diagnostic_tags(&warning.warning_content),
         ..Default::default()
-    }))
+    }
+}
+
+fn get_error_diagnostics(errors: &[CompileError]) -> Vec<Diagnostic> {
+    Vec::from_iter(errors.iter().map(get_error_diagnostic))
+}
+
+fn get_warning_diagnostics(warnings: &[CompileWarning]) -> Vec<Diagnostic> {
+    Vec::from_iter(warnings.iter().map(get_warning_diagnostic))
 }
 
 pub fn get_diagnostics(warnings: &[CompileWarning], errors: &[CompileError]) -> Diagnostics {
@@ -36,6 +49,34 @@ pub fn get_diagnostics(warnings: &[CompileWarning], errors: &[CompileError]) ->
     }
 }
 
+/// Returns the diagnostics of each file which has any. Those which aren't attributed to a file
+/// are attributed to `default_path`.
+pub fn get_diagnostics_by_file(
+    warnings: &[CompileWarning],
+    errors: &[CompileError],
+    default_path: &Path,
+) -> HashMap<PathBuf, Diagnostics> {
+    let mut diagnostics: HashMap<PathBuf, Diagnostics> = HashMap::new();
+    let file_of = |path: Option<&Arc<PathBuf>>| {
+        path.map_or_else(|| default_path.to_path_buf(), |path| path.to_path_buf())
+    };
+    for warning in warnings {
+        diagnostics
+            .entry(file_of(warning.span().path()))
+            .or_default()
+            .warnings
+            .push(get_warning_diagnostic(warning));
+    }
+    for error in errors {
+        diagnostics
+            .entry(file_of(error.span().path()))
+            .or_default()
+            .errors
+            .push(get_error_diagnostic(error));
+    }
+    diagnostics
+}
+
 fn get_range((start, end): (LineCol, LineCol)) -> Range {
     let pos = |lc: LineCol| Position::new(lc.line as u32 - 1, lc.col as u32 - 1);
     let start = pos(start);
@@ -58,6 +99,7 @@ fn get_warning_diagnostic_tags(warning: &Warning) -> Option<Vec<DiagnosticTag>>
         | Warning::MatchExpressionUnreachableArm
         | Warning::UnreachableCode
         | Warning::UnusedReturnValue { .. } => Some(vec![DiagnosticTag::UNNECESSARY]),
+        Warning::UsingDeprecated { .. } => Some(vec![DiagnosticTag::DEPRECATED]),
         _ => None,
     }
 }
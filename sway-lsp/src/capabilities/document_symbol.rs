@@ -1,55 +1,195 @@
-use crate::core::token::{get_range_from_span, SymbolKind, Token};
-use sway_types::{Ident, Spanned};
-use tower_lsp::lsp_types::{self, Location, SymbolInformation, Url};
-
-pub fn to_symbol_information<I>(tokens: I, url: Url) -> Vec<SymbolInformation>
-where
-    I: Iterator<Item = (Ident, Token)>,
-{
-    let mut symbols: Vec<SymbolInformation> = vec![];
-
-    for (ident, token) in tokens {
-        let symbol = symbol_info(&ident, &token, url.clone());
-        symbols.push(symbol)
+use crate::core::token::get_range_from_span;
+use std::path::Path;
+use sway_ast::{
+    attribute::Annotated, keywords::SemicolonToken, ItemFn, ItemImpl, ItemImplItem, ItemKind,
+    ItemTraitItem, Module,
+};
+use sway_core::language::lexed::LexedModule;
+use sway_types::{Ident, Span, Spanned};
+use tower_lsp::lsp_types::{DocumentSymbol, SymbolKind};
+
+/// Returns the outline of a module, with the members of its structs, enums, traits, ABIs, impl
+/// blocks, storage and configurable blocks nested within them.
+pub fn to_document_symbols(module: &Module) -> Vec<DocumentSymbol> {
+    module
+        .items
+        .iter()
+        .filter_map(|item| item_symbol(&item.value))
+        .collect()
+}
+
+/// Returns the syntax trees of the program's root module followed by all of its submodules.
+pub(crate) fn lexed_modules(root: &LexedModule) -> Vec<&Module> {
+    let mut modules = vec![&root.tree];
+    for (_, submodule) in &root.submodules {
+        modules.extend(lexed_modules(&submodule.module));
     }
+    modules
+}
+
+/// Returns the syntax tree of the module of the program held by the file at the given path.
+pub(crate) fn lexed_module_in_file<'a>(root: &'a LexedModule, path: &Path) -> Option<&'a Module> {
+    lexed_modules(root).into_iter().find(|module| {
+        module
+            .kind
+            .span()
+            .path()
+            .map_or(false, |module_path| module_path.as_path() == path)
+    })
+}
 
-    symbols
-}
-
-/// Given a `token::SymbolKind`, return the `lsp_types::SymbolKind` that corresponds to it.
-pub(crate) fn symbol_kind(symbol_kind: &SymbolKind) -> lsp_types::SymbolKind {
-    match symbol_kind {
-        SymbolKind::Field => lsp_types::SymbolKind::FIELD,
-        SymbolKind::BuiltinType => lsp_types::SymbolKind::TYPE_PARAMETER,
-        SymbolKind::Function | SymbolKind::DeriveHelper => lsp_types::SymbolKind::FUNCTION,
-        SymbolKind::Const => lsp_types::SymbolKind::CONSTANT,
-        SymbolKind::Struct => lsp_types::SymbolKind::STRUCT,
-        SymbolKind::Trait | SymbolKind::Storage => lsp_types::SymbolKind::INTERFACE,
-        SymbolKind::Module => lsp_types::SymbolKind::MODULE,
-        SymbolKind::Enum => lsp_types::SymbolKind::ENUM,
-        SymbolKind::Variant => lsp_types::SymbolKind::ENUM_MEMBER,
-        SymbolKind::BoolLiteral => lsp_types::SymbolKind::BOOLEAN,
-        SymbolKind::StringLiteral => lsp_types::SymbolKind::STRING,
-        SymbolKind::NumericLiteral => lsp_types::SymbolKind::NUMBER,
-        SymbolKind::TypeParameter => lsp_types::SymbolKind::TYPE_PARAMETER,
-        SymbolKind::ValueParam
-        | SymbolKind::ByteLiteral
-        | SymbolKind::Variable
-        | SymbolKind::Keyword
-        | SymbolKind::Unknown => lsp_types::SymbolKind::VARIABLE,
+fn item_symbol(item: &ItemKind) -> Option<DocumentSymbol> {
+    match item {
+        ItemKind::Submodule(submodule) => symbol(&submodule.name, SymbolKind::MODULE, item.span()),
+        ItemKind::Use(_) | ItemKind::Error(_) => None,
+        ItemKind::Struct(item_struct) => {
+            let fields = item_struct.fields.get().into_iter().filter_map(|field| {
+                let field = &field.value;
+                symbol(&field.name, SymbolKind::FIELD, field.span())
+                    .map(|symbol| with_detail(symbol, field.ty.span()))
+            });
+            symbol(&item_struct.name, SymbolKind::STRUCT, item.span())
+                .map(|symbol| with_children(symbol, fields))
+        }
+        ItemKind::Enum(item_enum) => {
+            let variants = item_enum.fields.get().into_iter().filter_map(|variant| {
+                let variant = &variant.value;
+                symbol(&variant.name, SymbolKind::ENUM_MEMBER, variant.span())
+                    .map(|symbol| with_detail(symbol, variant.ty.span()))
+            });
+            symbol(&item_enum.name, SymbolKind::ENUM, item.span())
+                .map(|symbol| with_children(symbol, variants))
+        }
+        ItemKind::Fn(item_fn) => fn_symbol(item_fn, SymbolKind::FUNCTION),
+        ItemKind::Trait(item_trait) => interface_symbol(
+            &item_trait.name,
+            item.span(),
+            item_trait.trait_items.get(),
+            item_trait.trait_defs_opt.as_ref().map(|defs| defs.get()),
+        ),
+        ItemKind::Impl(item_impl) => impl_symbol(item_impl),
+        ItemKind::Abi(item_abi) => interface_symbol(
+            &item_abi.name,
+            item.span(),
+            item_abi.abi_items.get(),
+            item_abi.abi_defs_opt.as_ref().map(|defs| defs.get()),
+        ),
+        ItemKind::Const(item_const) => symbol(&item_const.name, SymbolKind::CONSTANT, item.span())
+            .map(|symbol| match &item_const.ty_opt {
+                Some((_, ty)) => with_detail(symbol, ty.span()),
+                None => symbol,
+            }),
+        ItemKind::Storage(item_storage) => {
+            let fields = item_storage.fields.get().into_iter().filter_map(|field| {
+                let field = &field.value;
+                symbol(&field.name, SymbolKind::FIELD, field.span())
+                    .map(|symbol| with_detail(symbol, field.ty.span()))
+            });
+            let storage_ident = Ident::new(item_storage.storage_token.span());
+            symbol(&storage_ident, SymbolKind::NAMESPACE, item.span())
+                .map(|symbol| with_children(symbol, fields))
+        }
+        ItemKind::Configurable(item_configurable) => {
+            let fields = item_configurable
+                .fields
+                .get()
+                .into_iter()
+                .filter_map(|field| {
+                    let field = &field.value;
+                    symbol(&field.name, SymbolKind::CONSTANT, field.span())
+                        .map(|symbol| with_detail(symbol, field.ty.span()))
+                });
+            let configurable_ident = Ident::new(item_configurable.configurable_token.span());
+            symbol(&configurable_ident, SymbolKind::NAMESPACE, item.span())
+                .map(|symbol| with_children(symbol, fields))
+        }
     }
 }
 
-#[allow(warnings)]
+/// Returns the symbol of a trait or an ABI, with its required and provided methods as children.
+fn interface_symbol(
+    name: &Ident,
+    span: Span,
+    items: &[(Annotated<ItemTraitItem>, SemicolonToken)],
+    defs: Option<&Vec<Annotated<ItemFn>>>,
+) -> Option<DocumentSymbol> {
+    let items = items.iter().filter_map(|(item, _)| match &item.value {
+        ItemTraitItem::Fn(fn_signature) => {
+            symbol(&fn_signature.name, SymbolKind::METHOD, fn_signature.span())
+        }
+    });
+    let defs = defs
+        .into_iter()
+        .flatten()
+        .filter_map(|item_fn| fn_symbol(&item_fn.value, SymbolKind::METHOD));
+    symbol(name, SymbolKind::INTERFACE, span).map(|symbol| with_children(symbol, items.chain(defs)))
+}
+
+/// Returns the symbol of an impl block, named after the implemented trait and type as in
+/// `impl Trait for Type`, with its methods as children.
+#[allow(deprecated)]
+fn impl_symbol(item_impl: &ItemImpl) -> Option<DocumentSymbol> {
+    let ty = item_impl.ty.span();
+    let name = match &item_impl.trait_opt {
+        Some((path_type, _)) => format!("impl {} for {}", path_type.span().as_str(), ty.as_str()),
+        None => format!("impl {}", ty.as_str()),
+    };
+    let methods = item_impl
+        .contents
+        .get()
+        .iter()
+        .filter_map(|item| match &item.value {
+            ItemImplItem::Fn(item_fn) => fn_symbol(item_fn, SymbolKind::METHOD),
+        });
+    Some(DocumentSymbol {
+        name,
+        detail: None,
+        kind: SymbolKind::OBJECT,
+        tags: None,
+        deprecated: None,
+        range: get_range_from_span(&item_impl.span()),
+        selection_range: get_range_from_span(&ty),
+        children: Some(methods.collect()),
+    })
+}
+
+fn fn_symbol(item_fn: &ItemFn, kind: SymbolKind) -> Option<DocumentSymbol> {
+    symbol(&item_fn.fn_signature.name, kind, item_fn.span())
+}
+
+/// Returns a symbol spanning `span` which is named by `ident`. Symbols without a name, such as
+/// those recovered from parse errors, are skipped since clients reject them.
+#[allow(deprecated)]
 // TODO: the "deprecated: None" field is deprecated according to this library
-fn symbol_info(ident: &Ident, token: &Token, url: Url) -> SymbolInformation {
-    let range = get_range_from_span(&ident.span());
-    SymbolInformation {
+fn symbol(ident: &Ident, kind: SymbolKind, span: Span) -> Option<DocumentSymbol> {
+    if ident.as_str().is_empty() {
+        return None;
+    }
+    Some(DocumentSymbol {
         name: ident.as_str().to_string(),
-        kind: symbol_kind(&token.kind),
-        location: Location::new(url, range),
+        detail: None,
+        kind,
         tags: None,
-        container_name: None,
         deprecated: None,
+        range: get_range_from_span(&span),
+        selection_range: get_range_from_span(&ident.span()),
+        children: None,
+    })
+}
+
+fn with_detail(symbol: DocumentSymbol, ty_span: Span) -> DocumentSymbol {
+    DocumentSymbol {
+        detail: Some(ty_span.as_str().to_string()),
+        ..symbol
+    }
+}
+
+fn with_children(
+    symbol: DocumentSymbol,
+    children: impl Iterator<Item = DocumentSymbol>,
+) -> DocumentSymbol {
+    DocumentSymbol {
+        children: Some(children.collect()),
+        ..symbol
     }
 }
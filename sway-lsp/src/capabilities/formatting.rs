@@ -1,23 +1,61 @@
-use crate::error::LanguageServerError;
+use crate::{core::token::get_range_from_span, error::LanguageServerError};
 use std::sync::Arc;
-use swayfmt::Formatter;
+use swayfmt::{FormatEdits, Formatter};
 use tower_lsp::lsp_types::{Position, Range, TextEdit};
 
-pub fn get_page_text_edit(
+/// Returns the edits which format the page.
+///
+/// When formatting only changes whitespace and comments, which is the common case, the edits
+/// replace just the text between the tokens where they changed. Otherwise the whole page is
+/// replaced.
+pub fn get_format_text_edits(
     text: Arc<str>,
     formatter: &mut Formatter,
-) -> Result<TextEdit, LanguageServerError> {
+) -> Result<Vec<TextEdit>, LanguageServerError> {
     // we only format if code is correct
-    let formatted_code = formatter
-        .format(text.clone(), None)
+    let edits = formatter
+        .format_edits(text.clone(), None)
         .map_err(LanguageServerError::FormatError)?;
+    match edits {
+        FormatEdits::Trivia(edits) => Ok(edits
+            .into_iter()
+            .map(|edit| TextEdit::new(get_range_from_span(&edit.span), edit.new_text))
+            .collect()),
+        FormatEdits::Replace(formatted_code) => Ok(vec![page_text_edit(&text, formatted_code)]),
+    }
+}
 
+fn page_text_edit(text: &str, formatted_code: String) -> TextEdit {
     let text_lines_count = text.split('\n').count();
     let num_of_lines = formatted_code.split('\n').count();
     let line_end = std::cmp::max(num_of_lines, text_lines_count) as u32;
 
-    Ok(TextEdit {
+    TextEdit {
         range: Range::new(Position::new(0, 0), Position::new(line_end, 0)),
         new_text: formatted_code,
-    })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_edits_only_touch_changed_whitespace() {
+        let text = "script;\n\nfn main() {\n  let x  = 1;\n}\n";
+        let edits = get_format_text_edits(Arc::from(text), &mut Formatter::default()).unwrap();
+        assert_eq!(
+            edits,
+            [
+                TextEdit::new(
+                    Range::new(Position::new(2, 11), Position::new(3, 2)),
+                    "\n    ".to_string()
+                ),
+                TextEdit::new(
+                    Range::new(Position::new(3, 7), Position::new(3, 9)),
+                    " ".to_string()
+                ),
+            ]
+        );
+    }
 }
@@ -7,7 +7,12 @@ use crate::{
         attributes::doc_comment_attributes, keyword_docs::KeywordDocs, markdown, markup::Markup,
     },
 };
-use std::sync::Arc;
+use forc_pkg::{manifest::PackageManifestFile, ArtifactPaths, BuildProfile, FunctionCost};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use sway_core::{
     language::{ty, Visibility},
     Engines, TypeId,
@@ -55,10 +60,15 @@ pub fn hover_data(
         None => (ident, token),
     };
 
+    let costs = match &decl_token.typed {
+        Some(TypedAstToken::TypedFunctionDeclaration(func)) => function_costs(&session, func),
+        _ => None,
+    };
     let contents = hover_format(
         Engines::new(&session.type_engine.read(), &session.decl_engine.read()),
         &decl_token,
         &decl_ident,
+        costs,
     );
     Some(lsp_types::Hover {
         contents,
@@ -66,6 +76,59 @@ pub fn hover_data(
     })
 }
 
+/// Returns the bytecode size and estimated gas of the function as of the last build of the
+/// package, if it was built since its source file was last saved.
+///
+/// Generic functions have an entry per instantiation, whose sizes are summed and the most
+/// expensive of which is shown.  Functions which were inlined into all of their callers have none.
+fn function_costs(session: &Session, func: &ty::TyFunctionDeclaration) -> Option<String> {
+    let name_span = func.name.span();
+    let source_path = Url::from_file_path(name_span.path()?.as_path())
+        .ok()
+        .and_then(|url| session.sync.to_workspace_url(url))?
+        .to_file_path()
+        .ok()?;
+    let manifest = PackageManifestFile::from_dir(&session.sync.manifest_dir().ok()?).ok()?;
+
+    // Use the artifact of whichever profile was built most recently.
+    let (built_at, artifact_path) = [BuildProfile::DEBUG, BuildProfile::RELEASE]
+        .into_iter()
+        .map(|profile| ArtifactPaths::from_manifest(&manifest, profile).function_costs())
+        .filter_map(|path| Some((modified_at(&path)?, path)))
+        .max()?;
+    // The offsets of the declarations may have changed since.
+    if modified_at(&source_path)? > built_at {
+        return None;
+    }
+    let costs: Vec<FunctionCost> = serde_json::from_slice(&fs::read(artifact_path).ok()?).ok()?;
+
+    let source_path = canonicalize(&source_path);
+    let offset = name_span.start();
+    let instances = costs
+        .iter()
+        .filter(|cost| {
+            cost.path.as_deref().map(canonicalize).as_ref() == Some(&source_path)
+                && cost.span_start <= offset
+                && offset < cost.span_end
+        })
+        .collect::<Vec<_>>();
+    let estimated_gas = instances.iter().map(|cost| cost.estimated_gas).max()?;
+    let size_in_bytes: u64 = instances.iter().map(|cost| cost.size_in_bytes).sum();
+    Some(format!(
+        "Last build: {size_in_bytes} bytes, estimated gas {estimated_gas}"
+    ))
+}
+
+fn modified_at(path: &Path) -> Option<std::time::SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+fn canonicalize(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
 fn visibility_as_str(visibility: &Visibility) -> &'static str {
     match visibility {
         Visibility::Private => "",
@@ -116,7 +179,12 @@ fn markup_content(markup: Markup) -> lsp_types::MarkupContent {
     lsp_types::MarkupContent { kind, value }
 }
 
-fn hover_format(engines: Engines<'_>, token: &Token, ident: &Ident) -> lsp_types::HoverContents {
+fn hover_format(
+    engines: Engines<'_>,
+    token: &Token,
+    ident: &Ident,
+    costs: Option<String>,
+) -> lsp_types::HoverContents {
     let decl_engine = engines.de();
 
     let token_name: String = ident.as_str().into();
@@ -190,7 +258,13 @@ fn hover_format(engines: Engines<'_>, token: &Token, ident: &Ident) -> lsp_types
             _ => None,
         });
 
-    let content = Markup::new().maybe_add_sway_block(value).text(&doc_comment);
+    let mut content = Markup::new().maybe_add_sway_block(value).text(&doc_comment);
+    if let Some(costs) = costs {
+        if !doc_comment.is_empty() {
+            content = content.line_sperator();
+        }
+        content = content.text(&costs);
+    }
 
     lsp_types::HoverContents::Markup(markup_content(content))
 }
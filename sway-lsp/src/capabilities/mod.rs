@@ -7,9 +7,12 @@ pub mod highlight;
 pub mod hover;
 pub mod inlay_hints;
 pub mod on_enter;
+pub mod references;
 pub mod rename;
 pub mod runnable;
 pub mod semantic_tokens;
+pub mod show_ir;
+pub mod workspace_symbol;
 
 pub(crate) use code_actions::code_actions;
 pub(crate) use on_enter::on_enter;
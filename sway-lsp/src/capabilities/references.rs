@@ -0,0 +1,206 @@
+use crate::core::{session::Session, token::get_range_from_span};
+use std::sync::Arc;
+use sway_core::{
+    decl_engine::{DeclEngine, InterfaceDeclId},
+    language::ty,
+};
+use sway_types::{Ident, Span, Spanned};
+use tower_lsp::lsp_types::{Location, Position, Url};
+
+pub fn references(
+    session: Arc<Session>,
+    url: Url,
+    position: Position,
+    include_declaration: bool,
+) -> Option<Vec<Location>> {
+    let (_, token) = session.token_map().token_at_position(&url, position)?;
+    let decl_ident =
+        token.declared_token_ident(&session.type_engine.read(), &session.decl_engine.read())?;
+    let decl_spans: Vec<Span> = related_declarations(&session, &decl_ident)
+        .iter()
+        .map(|ident| ident.span())
+        .collect();
+
+    let mut locations = references_of_declarations(&session, &decl_spans)
+        .into_iter()
+        .filter(|ident| include_declaration || !decl_spans.contains(&ident.span()))
+        .filter_map(|ident| {
+            let url = ident
+                .span()
+                .path()
+                .and_then(|path| Url::from_file_path(path.as_ref()).ok())
+                .and_then(|url| session.sync.to_workspace_url(url))?;
+            Some(Location::new(url, get_range_from_span(&ident.span())))
+        })
+        .collect::<Vec<_>>();
+    locations.sort_by(|a, b| (a.uri.as_str(), a.range.start).cmp(&(b.uri.as_str(), b.range.start)));
+    Some(locations)
+}
+
+/// Returns the idents of every token which refers to one of the given declarations, including
+/// the declarations themselves.
+pub(crate) fn references_of_declarations(session: &Session, decl_spans: &[Span]) -> Vec<Ident> {
+    let type_engine = session.type_engine.read();
+    let decl_engine = session.decl_engine.read();
+    session
+        .token_map()
+        .iter()
+        .filter(|item| {
+            item.value()
+                .declared_token_span(&type_engine, &decl_engine)
+                .map_or(false, |decl_span| decl_spans.contains(&decl_span))
+        })
+        .map(|item| item.key().0.clone())
+        .collect()
+}
+
+/// Returns the declarations which share their references with the given one.
+///
+/// For a method of a trait or ABI, these are the methods of the same name in the trait or ABI and
+/// in each of its implementations, as a call may refer to any of them. Otherwise, it is just the
+/// given declaration.
+pub(crate) fn related_declarations(session: &Session, decl_ident: &Ident) -> Vec<Ident> {
+    let interfaces = {
+        let compiled_program = session.compiled_program.read();
+        match &compiled_program.typed {
+            Some(program) => interfaces(&session.decl_engine.read(), program),
+            None => vec![],
+        }
+    };
+    let interface_span = interfaces
+        .iter()
+        .find(|interface| {
+            interface
+                .methods
+                .iter()
+                .any(|method| method.span() == decl_ident.span())
+        })
+        .map(|interface| interface.name.span());
+    let interface_span = match interface_span {
+        Some(span) => span,
+        None => return vec![decl_ident.clone()],
+    };
+    let mut decl_idents: Vec<Ident> = vec![];
+    let methods = interfaces
+        .iter()
+        .filter(|interface| interface.name.span() == interface_span)
+        .flat_map(|interface| &interface.methods)
+        .filter(|method| method.as_str() == decl_ident.as_str());
+    for method in methods {
+        if !decl_idents
+            .iter()
+            .any(|ident| ident.span() == method.span())
+        {
+            decl_idents.push(method.clone());
+        }
+    }
+    decl_idents
+}
+
+/// The methods of a trait or ABI, or of an implementation of one, along with the name of the
+/// trait or ABI they belong to.
+struct Interface {
+    name: Ident,
+    methods: Vec<Ident>,
+}
+
+/// Returns the traits and ABIs declared by the program, along with its implementations of them.
+///
+/// Each implementation also lists the methods of the trait or ABI it implements, as these may be
+/// declared by a dependency.
+fn interfaces(decl_engine: &DeclEngine, program: &ty::TyProgram) -> Vec<Interface> {
+    let mut interfaces = vec![];
+    for decl in declarations(program) {
+        match decl {
+            ty::TyDeclaration::TraitDeclaration { decl_id, .. } => {
+                let decl = decl_engine.get_trait(decl_id);
+                interfaces.push(Interface {
+                    methods: interface_methods(decl_engine, &decl.interface_surface, &decl.items),
+                    name: decl.name,
+                });
+            }
+            ty::TyDeclaration::AbiDeclaration { decl_id, .. } => {
+                let decl = decl_engine.get_abi(decl_id);
+                interfaces.push(Interface {
+                    methods: interface_methods(decl_engine, &decl.interface_surface, &decl.items),
+                    name: decl.name,
+                });
+            }
+            ty::TyDeclaration::ImplTrait { decl_id, .. } => {
+                let decl = decl_engine.get_impl_trait(decl_id);
+                let mut methods = item_methods(decl_engine, &decl.items);
+                let name = match decl.trait_decl_ref.as_ref().map(|decl_ref| decl_ref.id()) {
+                    Some(InterfaceDeclId::Trait(id)) => {
+                        let decl = decl_engine.get_trait(id);
+                        methods.extend(interface_methods(
+                            decl_engine,
+                            &decl.interface_surface,
+                            &decl.items,
+                        ));
+                        decl.name
+                    }
+                    Some(InterfaceDeclId::Abi(id)) => {
+                        let decl = decl_engine.get_abi(id);
+                        methods.extend(interface_methods(
+                            decl_engine,
+                            &decl.interface_surface,
+                            &decl.items,
+                        ));
+                        decl.name
+                    }
+                    None => continue,
+                };
+                interfaces.push(Interface { name, methods });
+            }
+            _ => {}
+        }
+    }
+    interfaces
+}
+
+/// Returns the program's root module followed by all of its submodules.
+pub(crate) fn modules(program: &ty::TyProgram) -> impl Iterator<Item = &ty::TyModule> {
+    std::iter::once(&program.root).chain(
+        program
+            .root
+            .submodules_recursive()
+            .map(|(_, submodule)| &submodule.module),
+    )
+}
+
+fn declarations(program: &ty::TyProgram) -> impl Iterator<Item = &ty::TyDeclaration> {
+    modules(program)
+        .flat_map(|module| &module.all_nodes)
+        .filter_map(|node| match &node.content {
+            ty::TyAstNodeContent::Declaration(decl) => Some(decl),
+            _ => None,
+        })
+}
+
+/// Returns the names of the methods required and provided by a trait or ABI.
+pub(crate) fn interface_methods(
+    decl_engine: &DeclEngine,
+    interface_surface: &[ty::TyTraitInterfaceItem],
+    items: &[ty::TyTraitItem],
+) -> Vec<Ident> {
+    let mut methods: Vec<Ident> = interface_surface
+        .iter()
+        .map(|item| match item {
+            ty::TyTraitInterfaceItem::TraitFn(decl_ref) => {
+                decl_engine.get_trait_fn(decl_ref.id()).name
+            }
+        })
+        .collect();
+    methods.extend(item_methods(decl_engine, items));
+    methods
+}
+
+/// Returns the names of the methods of a trait, ABI or impl block.
+pub(crate) fn item_methods(decl_engine: &DeclEngine, items: &[ty::TyTraitItem]) -> Vec<Ident> {
+    items
+        .iter()
+        .map(|item| match item {
+            ty::TyTraitItem::Fn(decl_ref) => decl_engine.get_function(decl_ref.id()).name,
+        })
+        .collect()
+}
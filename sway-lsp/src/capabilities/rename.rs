@@ -1,7 +1,21 @@
-use crate::core::{session::Session, token::get_range_from_span};
+use crate::{
+    capabilities::references::{self, interface_methods, item_methods, modules},
+    core::{
+        session::Session,
+        token::{get_range_from_span, SymbolKind},
+    },
+    error::{LanguageServerError, RenameError},
+};
 use std::collections::HashMap;
-use std::sync::Arc;
-use sway_types::Spanned;
+use std::{path::PathBuf, sync::Arc};
+use sway_ast::{
+    syntax_tree::{SyntaxElement, SyntaxKind, SyntaxNode},
+    token::Delimiter,
+};
+use sway_core::{decl_engine::DeclEngine, language::ty};
+use sway_error::handler::Handler;
+use sway_parse::RESERVED_KEYWORDS;
+use sway_types::{Ident, Span, Spanned};
 use tower_lsp::lsp_types::{Position, PrepareRenameResponse, TextEdit, Url, WorkspaceEdit};
 
 pub fn rename(
@@ -9,35 +23,336 @@ pub fn rename(
     new_name: String,
     url: Url,
     position: Position,
-) -> Option<WorkspaceEdit> {
-    let (_, token) = session.token_map().token_at_position(&url, position)?;
-    let mut edits = Vec::new();
-
-    // todo: currently only supports single file rename
-    for (ident, _) in session.token_map().all_references_of_token(
-        &token,
-        &session.type_engine.read(),
-        &session.decl_engine.read(),
-    ) {
-        let range = get_range_from_span(&ident.span());
-        edits.push(TextEdit::new(range, new_name.clone()));
-    }
-
-    let mut map_of_changes = HashMap::new();
-    session.sync.to_workspace_url(url).map(|url| {
-        map_of_changes.insert(url, edits);
-        WorkspaceEdit::new(map_of_changes)
-    })
+) -> Result<WorkspaceEdit, LanguageServerError> {
+    if !is_valid_identifier(&new_name) {
+        return Err(RenameError::InvalidName { name: new_name }.into());
+    }
+    let (_, decl_ident) = renamable_token(&session, &url, position)?;
+
+    // Renaming a method of a trait or ABI also renames it within each of its implementations.
+    let decl_idents = references::related_declarations(&session, &decl_ident);
+    let scopes = {
+        let compiled_program = session.compiled_program.read();
+        match &compiled_program.typed {
+            Some(program) => scopes(&session.decl_engine.read(), program),
+            None => vec![],
+        }
+    };
+    for ident in &decl_idents {
+        if !is_in_workspace(&session, &ident.span()) {
+            return Err(RenameError::TokenNotPartOfWorkspace.into());
+        }
+        let conflicts = scopes
+            .iter()
+            .filter(|names| names.iter().any(|name| name.span() == ident.span()))
+            .flatten()
+            .any(|name| name.as_str() == new_name && name.span() != ident.span());
+        if conflicts {
+            return Err(RenameError::NameConflict { name: new_name }.into());
+        }
+    }
+    let decl_spans: Vec<Span> = decl_idents.iter().map(|ident| ident.span()).collect();
+    let references = references::references_of_declarations(&session, &decl_spans);
+    let mut trees = HashMap::new();
+    if conflicts_with_locals(&session, &mut trees, &decl_spans, &references, &new_name) {
+        return Err(RenameError::NameConflict { name: new_name }.into());
+    }
+
+    let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+    for ident in references {
+        // Only rename the tokens which spell out the name of the declaration, leaving aliases
+        // introduced by `use` statements and the `Self` type intact.
+        if ident.as_str() != decl_ident.as_str() {
+            continue;
+        }
+        let span = match name_token_span(&mut trees, &ident) {
+            Some(span) => span,
+            None => continue,
+        };
+        let url = span
+            .path()
+            .and_then(|path| Url::from_file_path(path.as_ref()).ok())
+            .and_then(|url| session.sync.to_workspace_url(url));
+        if let Some(url) = url {
+            let edit = TextEdit::new(get_range_from_span(&span), new_name.clone());
+            changes.entry(url).or_default().push(edit);
+        }
+    }
+    Ok(WorkspaceEdit::new(changes))
 }
 
 pub fn prepare_rename(
     session: Arc<Session>,
     url: Url,
     position: Position,
-) -> Option<PrepareRenameResponse> {
-    let (ident, ..) = session.token_map().token_at_position(&url, position)?;
-    Some(PrepareRenameResponse::RangeWithPlaceholder {
+) -> Result<PrepareRenameResponse, LanguageServerError> {
+    let (ident, _) = renamable_token(&session, &url, position)?;
+    Ok(PrepareRenameResponse::RangeWithPlaceholder {
         range: get_range_from_span(&ident.span()),
         placeholder: ident.as_str().to_string(),
     })
 }
+
+/// Returns the [Ident] of the token at the given position, along with the [Ident] of its
+/// declaration, if the token is one that can be renamed.
+fn renamable_token(
+    session: &Session,
+    url: &Url,
+    position: Position,
+) -> Result<(Ident, Ident), RenameError> {
+    let (ident, token) = session
+        .token_map()
+        .token_at_position(url, position)
+        .ok_or(RenameError::TokenNotFound)?;
+    let renamable_kind = matches!(
+        token.kind,
+        SymbolKind::Field
+            | SymbolKind::ValueParam
+            | SymbolKind::Function
+            | SymbolKind::Const
+            | SymbolKind::Struct
+            | SymbolKind::Trait
+            | SymbolKind::Enum
+            | SymbolKind::Variant
+            | SymbolKind::Variable
+            | SymbolKind::TypeParameter
+    );
+    if !renamable_kind || RESERVED_KEYWORDS.contains(ident.as_str()) {
+        return Err(RenameError::SymbolKindNotAllowed);
+    }
+    let decl_ident = token
+        .declared_token_ident(&session.type_engine.read(), &session.decl_engine.read())
+        .ok_or(RenameError::TokenNotFound)?;
+    if !is_in_workspace(session, &decl_ident.span()) {
+        return Err(RenameError::TokenNotPartOfWorkspace);
+    }
+    Ok((ident, decl_ident))
+}
+
+/// Returns the span of the identifier token in the source text at the start of `ident`, if that
+/// token spells out its name. References whose spans point elsewhere, such as into code the
+/// compiler generated, are left alone.
+///
+/// The syntax tree of each source file is built once and kept in `trees`.
+fn name_token_span(
+    trees: &mut HashMap<Option<Arc<PathBuf>>, Option<SyntaxNode>>,
+    ident: &Ident,
+) -> Option<Span> {
+    let span = ident.span();
+    let token = syntax_tree(trees, &span)?.token_at_offset(span.start())?;
+    let spells_name = token.kind == SyntaxKind::Ident && token.span.as_str() == ident.as_str();
+    spells_name.then(|| token.span.clone())
+}
+
+/// Returns the syntax tree of the source file `span` belongs to, building it if it isn't in
+/// `trees` yet.
+fn syntax_tree<'a>(
+    trees: &'a mut HashMap<Option<Arc<PathBuf>>, Option<SyntaxNode>>,
+    span: &Span,
+) -> Option<&'a SyntaxNode> {
+    trees
+        .entry(span.path().cloned())
+        .or_insert_with(|| {
+            sway_parse::parse_syntax_tree(
+                &Handler::default(),
+                span.src().clone(),
+                span.path().cloned(),
+            )
+            .ok()
+        })
+        .as_ref()
+}
+
+/// Returns true if renaming the declarations to `new_name` would change what a name refers to
+/// within a function: if a local variable or parameter called `new_name` is in scope at one of
+/// their `references`, or if one of them is a local in whose scope another declaration called
+/// `new_name` is referred to.
+fn conflicts_with_locals(
+    session: &Session,
+    trees: &mut HashMap<Option<Arc<PathBuf>>, Option<SyntaxNode>>,
+    decl_spans: &[Span],
+    references: &[Ident],
+    new_name: &str,
+) -> bool {
+    let type_engine = session.type_engine.read();
+    let decl_engine = session.decl_engine.read();
+    let is_local =
+        |kind: &SymbolKind| matches!(kind, SymbolKind::Variable | SymbolKind::ValueParam);
+    let tokens: Vec<(Ident, SymbolKind, Option<Ident>)> = session
+        .token_map()
+        .iter()
+        .map(|item| {
+            let token = item.value();
+            let decl_ident = token.declared_token_ident(&type_engine, &decl_engine);
+            (item.key().0.clone(), token.kind.clone(), decl_ident)
+        })
+        .collect();
+
+    let shadowed_by_local = tokens
+        .iter()
+        .filter(|(ident, kind, _)| ident.as_str() == new_name && is_local(kind))
+        .map(|(ident, kind, decl_ident)| (decl_ident.as_ref().unwrap_or(ident), kind))
+        .filter(|(decl_ident, _)| !decl_spans.contains(&decl_ident.span()))
+        .filter_map(|(decl_ident, kind)| local_scope(trees, decl_ident, kind))
+        .any(|scope| {
+            references
+                .iter()
+                .any(|ident| span_contains(&scope, &ident.span()))
+        });
+    if shadowed_by_local {
+        return true;
+    }
+
+    tokens
+        .iter()
+        .filter(|(ident, kind, _)| decl_spans.contains(&ident.span()) && is_local(kind))
+        .filter_map(|(ident, kind, _)| local_scope(trees, ident, kind))
+        .any(|scope| {
+            tokens.iter().any(|(ident, kind, _)| {
+                ident.as_str() == new_name
+                    && matches!(
+                        kind,
+                        SymbolKind::Function
+                            | SymbolKind::Const
+                            | SymbolKind::Variable
+                            | SymbolKind::ValueParam
+                    )
+                    && span_contains(&scope, &ident.span())
+            })
+        })
+}
+
+/// Returns the span from the declaration of a local variable or parameter to the end of its
+/// scope: the block the variable is declared in, or the function the parameter belongs to.
+fn local_scope(
+    trees: &mut HashMap<Option<Arc<PathBuf>>, Option<SyntaxNode>>,
+    ident: &Ident,
+    kind: &SymbolKind,
+) -> Option<Span> {
+    let span = ident.span();
+    let scope_kind = match kind {
+        SymbolKind::ValueParam => SyntaxKind::Item,
+        _ => SyntaxKind::Group(Delimiter::Brace),
+    };
+    let mut node = syntax_tree(trees, &span)?;
+    let mut scope = None;
+    while let Some(child) = node.children.iter().find_map(|child| match child {
+        SyntaxElement::Node(child)
+            if child.span.start() <= span.start() && span.start() < child.span.end() =>
+        {
+            Some(child)
+        }
+        _ => None,
+    }) {
+        if child.kind == scope_kind {
+            scope = Some(child);
+        }
+        node = child;
+    }
+    let scope_end = scope?.span.end();
+    Span::new(
+        span.src().clone(),
+        span.start(),
+        scope_end,
+        span.path().cloned(),
+    )
+}
+
+fn span_contains(outer: &Span, inner: &Span) -> bool {
+    outer.path() == inner.path() && outer.start() <= inner.start() && inner.end() <= outer.end()
+}
+
+/// Returns true if the span belongs to a file within the user's workspace, rather than to a
+/// dependency such as the standard library.
+fn is_in_workspace(session: &Session, span: &Span) -> bool {
+    span.path()
+        .and_then(|path| Url::from_file_path(path.as_ref()).ok())
+        .map_or(false, |url| session.sync.is_path_in_workspace(&url))
+}
+
+fn is_valid_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    let starts_well = chars
+        .next()
+        .map_or(false, |c| c.is_ascii_alphabetic() || c == '_');
+    starts_well
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && name != "_"
+        && !RESERVED_KEYWORDS.contains(name)
+}
+
+/// Returns the names declared within each of the program's scopes in which names must be unique:
+/// its modules, and the fields, variants and methods of its declarations.
+fn scopes(decl_engine: &DeclEngine, program: &ty::TyProgram) -> Vec<Vec<Ident>> {
+    let mut scopes = vec![];
+    for module in modules(program) {
+        let mut items = vec![];
+        for node in &module.all_nodes {
+            let decl = match &node.content {
+                ty::TyAstNodeContent::Declaration(decl) => decl,
+                _ => continue,
+            };
+            match decl {
+                ty::TyDeclaration::ConstantDeclaration { name, .. }
+                | ty::TyDeclaration::FunctionDeclaration { name, .. } => items.push(name.clone()),
+                ty::TyDeclaration::StructDeclaration { name, decl_id, .. } => {
+                    items.push(name.clone());
+                    let decl = decl_engine.get_struct(decl_id);
+                    scopes.push(decl.fields.into_iter().map(|field| field.name).collect());
+                }
+                ty::TyDeclaration::EnumDeclaration { name, decl_id, .. } => {
+                    items.push(name.clone());
+                    let decl = decl_engine.get_enum(decl_id);
+                    scopes.push(
+                        decl.variants
+                            .into_iter()
+                            .map(|variant| variant.name)
+                            .collect(),
+                    );
+                }
+                ty::TyDeclaration::TraitDeclaration { name, decl_id, .. } => {
+                    items.push(name.clone());
+                    let decl = decl_engine.get_trait(decl_id);
+                    scopes.push(interface_methods(
+                        decl_engine,
+                        &decl.interface_surface,
+                        &decl.items,
+                    ));
+                }
+                ty::TyDeclaration::AbiDeclaration { name, decl_id, .. } => {
+                    items.push(name.clone());
+                    let decl = decl_engine.get_abi(decl_id);
+                    scopes.push(interface_methods(
+                        decl_engine,
+                        &decl.interface_surface,
+                        &decl.items,
+                    ));
+                }
+                ty::TyDeclaration::ImplTrait { decl_id, .. } => {
+                    let decl = decl_engine.get_impl_trait(decl_id);
+                    scopes.push(item_methods(decl_engine, &decl.items));
+                }
+                ty::TyDeclaration::StorageDeclaration { decl_id, .. } => {
+                    let decl = decl_engine.get_storage(decl_id);
+                    scopes.push(decl.fields.into_iter().map(|field| field.name).collect());
+                }
+                _ => {}
+            }
+        }
+        scopes.push(items);
+    }
+    scopes
+}
+
+#[test]
+fn test_is_valid_identifier() {
+    assert!(is_valid_identifier("foo"));
+    assert!(is_valid_identifier("_foo_2"));
+    assert!(is_valid_identifier("Foo"));
+    assert!(!is_valid_identifier(""));
+    assert!(!is_valid_identifier("_"));
+    assert!(!is_valid_identifier("2foo"));
+    assert!(!is_valid_identifier("foo-bar"));
+    assert!(!is_valid_identifier("fn"));
+    assert!(!is_valid_identifier("storage"));
+}
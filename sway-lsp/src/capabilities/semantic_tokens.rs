@@ -1,12 +1,17 @@
 use crate::core::{
     session::Session,
-    token::{get_range_from_span, SymbolKind, Token},
+    token::{get_range_from_span, to_ident_key, SymbolKind, Token, TypeDefinition, TypedAstToken},
 };
 use std::sync::{
     atomic::{AtomicU32, Ordering},
     Arc,
 };
-use sway_types::{Span, Spanned};
+use sway_core::{
+    decl_engine::DeclEngine,
+    language::ty::{self, GetDeclIdent, TyConstantDeclaration, TyFunctionDeclaration},
+    Engines, TypeId, TypeInfo,
+};
+use sway_types::{Ident, Span, Spanned};
 use tower_lsp::lsp_types::{
     Range, SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokens,
     SemanticTokensResult, Url,
@@ -26,7 +31,7 @@ pub fn semantic_tokens_full(session: Arc<Session>, url: &Url) -> Option<Semantic
         a.cmp(&b)
     });
 
-    let semantic_tokens = semantic_tokens(&tokens_sorted);
+    let semantic_tokens = semantic_tokens(&session, &tokens_sorted);
 
     Some(semantic_tokens.into())
 }
@@ -89,25 +94,252 @@ impl SemanticTokensBuilder {
     }
 }
 
-pub fn semantic_tokens(tokens_sorted: &[(Span, Token)]) -> SemanticTokens {
+pub fn semantic_tokens(session: &Session, tokens_sorted: &[(Span, Token)]) -> SemanticTokens {
     static TOKEN_RESULT_COUNTER: AtomicU32 = AtomicU32::new(1);
     let id = TOKEN_RESULT_COUNTER
         .fetch_add(1, Ordering::SeqCst)
         .to_string();
     let mut builder = SemanticTokensBuilder::new(id);
 
+    let type_engine = session.type_engine.read();
+    let decl_engine = session.decl_engine.read();
+    let engines = Engines::new(&type_engine, &decl_engine);
     for (span, token) in tokens_sorted.iter() {
-        let ty = semantic_token_type(&token.kind);
+        let mut modifiers = Modifiers::default();
+        let ty = typed_semantic_token_type(session, engines, span, token, &mut modifiers)
+            .unwrap_or_else(|| semantic_token_type(&token.kind));
         let token_index = type_index(ty);
-        // TODO - improve with modifiers
-        let modifier_bitset = 0;
         let range = get_range_from_span(span);
 
-        builder.push(range, token_index, modifier_bitset);
+        builder.push(range, token_index, modifiers.0);
     }
     builder.build()
 }
 
+/// The bitset of the [SemanticTokenModifier]s of a token, indexed by [SUPPORTED_MODIFIERS].
+#[derive(Default)]
+struct Modifiers(u32);
+
+impl Modifiers {
+    fn insert(&mut self, modifier: SemanticTokenModifier) {
+        let index = SUPPORTED_MODIFIERS
+            .iter()
+            .position(|it| *it == modifier)
+            .unwrap();
+        self.0 |= 1 << index;
+    }
+}
+
+/// Returns the semantic token type of a token based on what the typed AST knows about the
+/// declaration it refers to, and collects its modifiers. Returns `None` if the typed AST doesn't
+/// tell more than the [SymbolKind] of the token.
+fn typed_semantic_token_type(
+    session: &Session,
+    engines: Engines<'_>,
+    span: &Span,
+    token: &Token,
+    modifiers: &mut Modifiers,
+) -> Option<SemanticTokenType> {
+    let (type_engine, decl_engine) = (engines.te(), engines.de());
+    let is_dependency = token
+        .declared_token_span(type_engine, decl_engine)
+        .and_then(|decl_span| decl_span.path().cloned())
+        .and_then(|path| Url::from_file_path(path.as_ref()).ok())
+        .map_or(false, |url| session.is_dependency_url(&url));
+    if is_dependency {
+        modifiers.insert(SemanticTokenModifier::DEFAULT_LIBRARY);
+    }
+
+    // A token which refers to a declaration by name points at it through an [Ident]. Any other
+    // token is described by its own typed AST node.
+    let resolved = match &token.type_def {
+        Some(TypeDefinition::Ident(decl_ident)) => session
+            .token_map()
+            .try_get(&to_ident_key(decl_ident))
+            .try_unwrap()
+            .map(|item| (decl_ident.span(), item.value().clone())),
+        _ => None,
+    };
+    let (decl_span, decl_token) = match &resolved {
+        Some((decl_span, decl_token)) => (decl_span, decl_token),
+        None => (span, token),
+    };
+
+    // Tokens within a declaration, such as the types of its parameters, share its typed AST node
+    // and only the token of its name is described by it.
+    let names_declaration = decl_token
+        .typed
+        .as_ref()
+        .and_then(declared_ident)
+        .map_or(false, |ident| ident.span() == *decl_span);
+    if names_declaration {
+        if decl_span == span {
+            modifiers.insert(SemanticTokenModifier::DECLARATION);
+        }
+        if let Some(ty) = declaration_type(decl_engine, decl_token, modifiers) {
+            return Some(ty);
+        }
+    }
+
+    // Types, such as those of type arguments and struct expressions, are described by the type
+    // they resolve to.
+    match (&decl_token.type_def, &decl_token.kind) {
+        (
+            Some(TypeDefinition::TypeId(type_id)),
+            SymbolKind::Struct | SymbolKind::Enum | SymbolKind::TypeParameter | SymbolKind::Unknown,
+        ) => type_id_type(engines, *type_id),
+        _ => None,
+    }
+}
+
+/// Returns the name of the declaration described by the typed AST node, if it is one.
+fn declared_ident(typed: &TypedAstToken) -> Option<Ident> {
+    match typed {
+        TypedAstToken::TypedDeclaration(decl) => decl.get_decl_ident(),
+        TypedAstToken::TypedFunctionDeclaration(func_decl) => Some(func_decl.name.clone()),
+        TypedAstToken::TypedConstantDeclaration(const_decl) => {
+            Some(const_decl.call_path.suffix.clone())
+        }
+        TypedAstToken::TypedFunctionParameter(param) => Some(param.name.clone()),
+        TypedAstToken::TypedStructField(field) => Some(field.name.clone()),
+        TypedAstToken::TypedEnumVariant(variant) => Some(variant.name.clone()),
+        TypedAstToken::TypedTraitFn(trait_fn) => Some(trait_fn.name.clone()),
+        TypedAstToken::TypedStorageField(field) => Some(field.name.clone()),
+        TypedAstToken::TypedParameter(type_param) => Some(type_param.name_ident.clone()),
+        _ => None,
+    }
+}
+
+/// Returns the semantic token type of the declaration described by the token's typed AST node.
+fn declaration_type(
+    decl_engine: &DeclEngine,
+    token: &Token,
+    modifiers: &mut Modifiers,
+) -> Option<SemanticTokenType> {
+    let ty = match token.typed.as_ref()? {
+        TypedAstToken::TypedFunctionDeclaration(func_decl) => {
+            function_type(decl_engine, func_decl, modifiers)
+        }
+        TypedAstToken::TypedTraitFn(_) => {
+            modifiers.insert(SemanticTokenModifier::new("trait"));
+            SemanticTokenType::METHOD
+        }
+        TypedAstToken::TypedConstantDeclaration(const_decl) => constant_type(const_decl, modifiers),
+        TypedAstToken::TypedFunctionParameter(param) => {
+            if param.is_mutable {
+                modifiers.insert(SemanticTokenModifier::new("mutable"));
+            }
+            SemanticTokenType::PARAMETER
+        }
+        TypedAstToken::TypedStructField(_) => SemanticTokenType::PROPERTY,
+        TypedAstToken::TypedEnumVariant(_) => SemanticTokenType::ENUM_MEMBER,
+        // Storage fields are told apart from struct fields by the `static` modifier.
+        TypedAstToken::TypedStorageField(_) => {
+            modifiers.insert(SemanticTokenModifier::STATIC);
+            SemanticTokenType::PROPERTY
+        }
+        TypedAstToken::TypedParameter(_) => SemanticTokenType::TYPE_PARAMETER,
+        TypedAstToken::TypedDeclaration(decl) => match decl {
+            ty::TyDeclaration::VariableDeclaration(var_decl) => {
+                if var_decl.mutability.is_mutable() {
+                    modifiers.insert(SemanticTokenModifier::new("mutable"));
+                }
+                SemanticTokenType::VARIABLE
+            }
+            ty::TyDeclaration::ConstantDeclaration { decl_id, .. } => {
+                constant_type(&decl_engine.get_constant(decl_id), modifiers)
+            }
+            ty::TyDeclaration::FunctionDeclaration { decl_id, .. } => {
+                function_type(decl_engine, &decl_engine.get_function(decl_id), modifiers)
+            }
+            ty::TyDeclaration::StructDeclaration { decl_id, .. } => {
+                if decl_engine.get_struct(decl_id).visibility.is_public() {
+                    modifiers.insert(SemanticTokenModifier::new("public"));
+                }
+                SemanticTokenType::STRUCT
+            }
+            ty::TyDeclaration::EnumDeclaration { decl_id, .. } => {
+                if decl_engine.get_enum(decl_id).visibility.is_public() {
+                    modifiers.insert(SemanticTokenModifier::new("public"));
+                }
+                SemanticTokenType::ENUM
+            }
+            ty::TyDeclaration::TraitDeclaration { decl_id, .. } => {
+                if decl_engine.get_trait(decl_id).visibility.is_public() {
+                    modifiers.insert(SemanticTokenModifier::new("public"));
+                }
+                SemanticTokenType::INTERFACE
+            }
+            ty::TyDeclaration::AbiDeclaration { .. } => SemanticTokenType::INTERFACE,
+            ty::TyDeclaration::GenericTypeForFunctionScope { .. } => {
+                SemanticTokenType::TYPE_PARAMETER
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(ty)
+}
+
+/// Functions declared within a trait, an ABI or an impl block are methods. Those which belong to
+/// a trait or an ABI are given the `trait` modifier.
+fn function_type(
+    decl_engine: &DeclEngine,
+    func_decl: &TyFunctionDeclaration,
+    modifiers: &mut Modifiers,
+) -> SemanticTokenType {
+    if func_decl.visibility.is_public() {
+        modifiers.insert(SemanticTokenModifier::new("public"));
+    }
+    let is_trait_method = match &func_decl.implementing_type {
+        Some(ty::TyDeclaration::ImplTrait { decl_id, .. }) => {
+            decl_engine.get_impl_trait(decl_id).trait_decl_ref.is_some()
+        }
+        Some(
+            ty::TyDeclaration::TraitDeclaration { .. } | ty::TyDeclaration::AbiDeclaration { .. },
+        ) => true,
+        _ => false,
+    };
+    if is_trait_method {
+        modifiers.insert(SemanticTokenModifier::new("trait"));
+    }
+    match func_decl.implementing_type {
+        Some(_) => SemanticTokenType::METHOD,
+        None => SemanticTokenType::FUNCTION,
+    }
+}
+
+/// Constants are read-only variables. Configurable constants are also given the `static`
+/// modifier, as their value is set when the contract is deployed.
+fn constant_type(
+    const_decl: &TyConstantDeclaration,
+    modifiers: &mut Modifiers,
+) -> SemanticTokenType {
+    modifiers.insert(SemanticTokenModifier::READONLY);
+    if const_decl.is_configurable {
+        modifiers.insert(SemanticTokenModifier::STATIC);
+    }
+    if const_decl.visibility.is_public() {
+        modifiers.insert(SemanticTokenModifier::new("public"));
+    }
+    SemanticTokenType::VARIABLE
+}
+
+/// Returns the semantic token type of a type, if it is one that can be told apart.
+fn type_id_type(engines: Engines<'_>, type_id: TypeId) -> Option<SemanticTokenType> {
+    match engines.te().get(type_id) {
+        TypeInfo::Struct(_) => Some(SemanticTokenType::STRUCT),
+        TypeInfo::Enum(_) => Some(SemanticTokenType::ENUM),
+        TypeInfo::UnknownGeneric { .. } | TypeInfo::Placeholder(_) => {
+            Some(SemanticTokenType::TYPE_PARAMETER)
+        }
+        TypeInfo::UnsignedInteger(_) | TypeInfo::Boolean | TypeInfo::B256 | TypeInfo::Str(_) => {
+            Some(SemanticTokenType::new("builtinType"))
+        }
+        _ => None,
+    }
+}
+
 pub(crate) const SUPPORTED_TYPES: &[SemanticTokenType] = &[
     SemanticTokenType::STRING,
     SemanticTokenType::NUMBER,
@@ -143,6 +375,12 @@ pub(crate) const SUPPORTED_MODIFIERS: &[SemanticTokenModifier] = &[
     SemanticTokenModifier::DOCUMENTATION,
     // for symbols that are part of stdlib
     SemanticTokenModifier::DEFAULT_LIBRARY,
+    // for mutable variables and parameters
+    SemanticTokenModifier::new("mutable"),
+    // for public declarations
+    SemanticTokenModifier::new("public"),
+    // for methods of traits and ABIs
+    SemanticTokenModifier::new("trait"),
 ];
 
 /// Get the semantic token type from the symbol kind.
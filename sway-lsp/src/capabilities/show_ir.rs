@@ -0,0 +1,94 @@
+use crate::core::{
+    session::Session,
+    token::{get_range_from_span, TypedAstToken},
+};
+use forc_pkg::manifest::PackageManifestFile;
+use std::{fmt::Write, sync::Arc};
+use sway_core::{language::ty, BuildConfig, BuildTarget, CompileResult, EmitConfig, Engines};
+use sway_error::error::CompileError;
+use tower_lsp::lsp_types::{Position, Url};
+
+/// Compiles the program and returns the final IR and ASM generated for the function enclosing the
+/// given position. If `show_passes` is set, the IR of the function after each pass which changed
+/// it is included as well.
+pub(crate) fn show_ir(
+    session: Arc<Session>,
+    url: &Url,
+    position: Position,
+    show_passes: bool,
+) -> Option<String> {
+    let func_decl = session
+        .token_map()
+        .tokens_for_file(url)
+        .filter_map(|(_, token)| match token.typed {
+            Some(TypedAstToken::TypedFunctionDeclaration(func_decl)) => Some(func_decl),
+            _ => None,
+        })
+        .filter(|func_decl| {
+            let range = get_range_from_span(&func_decl.span);
+            range.start <= position && position <= range.end
+        })
+        .min_by_key(|func_decl| func_decl.span.end() - func_decl.span.start())?;
+
+    let artifacts = {
+        let compiled_program = session.compiled_program.read();
+        let program = compiled_program.typed.as_ref()?;
+        let manifest_dir = session.sync.temp_dir().ok()?;
+        let manifest = PackageManifestFile::from_dir(&manifest_dir).ok()?;
+        let build_config = BuildConfig::root_from_file_name_and_manifest_path(
+            manifest.entry_path(),
+            manifest_dir,
+            BuildTarget::default(),
+        )
+        .emit(EmitConfig {
+            function: Some(func_decl.span.clone()),
+            ..Default::default()
+        });
+        let type_engine = session.type_engine.read();
+        let decl_engine = session.decl_engine.read();
+        let engines = Engines::new(&type_engine, &decl_engine);
+        let ast_res = CompileResult::new(Some(program.clone()), vec![], vec![]);
+        let asm_res = sway_core::ast_to_asm(engines, &ast_res, &build_config);
+        match asm_res.value {
+            Some(asm) => asm.0.emitted,
+            None => return Some(compile_errors(&func_decl, &asm_res.errors)),
+        }
+    };
+
+    let mut contents = String::new();
+    for artifact in &artifacts {
+        let name = artifact.path.file_name()?.to_string_lossy();
+        let heading = match name.as_ref() {
+            "final.ir" => "Final IR".to_string(),
+            "final.asm" => "Final ASM".to_string(),
+            "initial.ir" if show_passes => "Initial IR".to_string(),
+            // The IR after each pass is named `{index}-after-{pass}.ir`.
+            _ if show_passes => match name
+                .strip_suffix(".ir")
+                .and_then(|name| name.split_once("-after-"))
+            {
+                Some((_, pass)) => format!("IR after {pass}"),
+                None => continue,
+            },
+            _ => continue,
+        };
+        let _ = write!(
+            contents,
+            "// {heading}\n\n{}\n\n",
+            artifact.contents.trim_end()
+        );
+    }
+    Some(format!(
+        "// fn {}\n\n{}",
+        func_decl.name,
+        contents.trim_end()
+    ))
+}
+
+fn compile_errors(func_decl: &ty::TyFunctionDeclaration, errors: &[CompileError]) -> String {
+    let mut contents = format!("// Failed to compile fn {}\n", func_decl.name);
+    for error in errors {
+        let _ = writeln!(contents, "// {error}");
+    }
+    contents
+}
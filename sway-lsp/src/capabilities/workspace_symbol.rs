@@ -0,0 +1,97 @@
+use crate::{
+    capabilities::document_symbol::{lexed_modules, to_document_symbols},
+    core::session::Session,
+};
+use std::sync::Arc;
+use sway_types::Spanned;
+use tower_lsp::lsp_types::{DocumentSymbol, Location, SymbolInformation, Url};
+
+/// The most symbols returned for a single query, since clients will ask again as the query is
+/// refined.
+const MAX_SYMBOLS: usize = 128;
+
+/// Returns the symbols declared in any module of the given projects whose name fuzzy matches the
+/// query, best matches first.
+pub fn workspace_symbols(
+    sessions: impl Iterator<Item = Arc<Session>>,
+    query: &str,
+) -> Vec<SymbolInformation> {
+    let mut matches = vec![];
+    for session in sessions {
+        let compiled_program = session.compiled_program.read();
+        let lexed = match &compiled_program.lexed {
+            Some(lexed) => lexed,
+            None => continue,
+        };
+        for module in lexed_modules(&lexed.root) {
+            let url = match module
+                .kind
+                .span()
+                .path()
+                .and_then(|path| Url::from_file_path(path.as_path()).ok())
+                .and_then(|url| session.sync.to_workspace_url(url))
+            {
+                Some(url) => url,
+                None => continue,
+            };
+            collect_matches(to_document_symbols(module), None, &url, query, &mut matches);
+        }
+    }
+    matches.sort_by_key(|(score, symbol)| (*score, symbol.name.len()));
+    matches
+        .into_iter()
+        .take(MAX_SYMBOLS)
+        .map(|(_, symbol)| symbol)
+        .collect()
+}
+
+#[allow(deprecated)]
+// TODO: the "deprecated: None" field is deprecated according to this library
+fn collect_matches(
+    symbols: Vec<DocumentSymbol>,
+    container_name: Option<&str>,
+    url: &Url,
+    query: &str,
+    matches: &mut Vec<(usize, SymbolInformation)>,
+) {
+    for symbol in symbols {
+        if let Some(score) = fuzzy_score(&symbol.name, query) {
+            matches.push((
+                score,
+                SymbolInformation {
+                    name: symbol.name.clone(),
+                    kind: symbol.kind,
+                    tags: None,
+                    deprecated: None,
+                    location: Location::new(url.clone(), symbol.selection_range),
+                    container_name: container_name.map(str::to_string),
+                },
+            ));
+        }
+        if let Some(children) = symbol.children {
+            collect_matches(children, Some(&symbol.name), url, query, matches);
+        }
+    }
+}
+
+/// Returns how well the name matches the query, lower being better, if the characters of the
+/// query appear in the name in order, ignoring case. Matches at the start of the name and of each
+/// of its words are preferred, as are consecutive characters.
+fn fuzzy_score(name: &str, query: &str) -> Option<usize> {
+    let name: Vec<char> = name.chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    for query_char in query.chars().filter(|c| !c.is_whitespace()) {
+        let index =
+            (position..name.len()).find(|index| name[*index].eq_ignore_ascii_case(&query_char))?;
+        let is_word_start = index == 0
+            || !name[index - 1].is_alphanumeric()
+            || (name[index].is_uppercase() && name[index - 1].is_lowercase());
+        let is_consecutive = index == position && position > 0;
+        if !is_word_start && !is_consecutive {
+            score += index - position + 1;
+        }
+        position = index + 1;
+    }
+    Some(score)
+}
@@ -1,45 +1,79 @@
 use crate::{
     capabilities::{
         self,
-        diagnostic::{get_diagnostics, Diagnostics},
-        formatting::get_page_text_edit,
+        diagnostic::{get_diagnostics_by_file, Diagnostics},
+        formatting::get_format_text_edits,
         runnable::{Runnable, RunnableMainFn, RunnableTestFn},
     },
     core::{
         document::TextDocument,
         sync::SyncWorkspace,
-        token::{get_range_from_span, TypedAstToken},
+        token::{get_range_from_span, Token, TypedAstToken},
         token_map::TokenMap,
     },
     error::{DocumentError, LanguageServerError},
-    traverse::{
-        dependency, lexed_tree, parsed_tree::ParsedTree, typed_tree::TypedTree, ParseContext,
-    },
+    traverse::{lexed_tree, parsed_tree::ParsedTree, typed_tree::TypedTree, ParseContext},
 };
 use dashmap::DashMap;
 use forc_pkg as pkg;
-use parking_lot::RwLock;
-use pkg::{manifest::ManifestFile, Programs};
-use std::{fs::File, io::Write, path::PathBuf, sync::Arc, vec};
+use parking_lot::{Mutex, RwLock};
+use pkg::{manifest::ManifestFile, source, CheckCache, Programs};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::Write,
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    vec,
+};
 use sway_core::{
     decl_engine::DeclEngine,
     language::{
         lexed::LexedProgram,
-        parsed::{AstNode, ParseProgram},
+        parsed::{AstNode, ParseModule, ParseProgram},
         ty,
     },
     BuildTarget, CompileResult, Engines, TypeEngine,
 };
-use sway_types::{Span, Spanned};
+use sway_error::{error::CompileError, warning::CompileWarning};
+use sway_types::{Ident, Span, Spanned};
 use sway_utils::helpers::get_sway_files;
 use tower_lsp::lsp_types::{
-    CompletionItem, GotoDefinitionResponse, Location, Position, Range, SymbolInformation,
+    CompletionItem, DocumentSymbol, GotoDefinitionResponse, Location, Position, Range,
     TextDocumentContentChangeEvent, TextEdit, Url,
 };
 
 pub type Documents = DashMap<String, TextDocument>;
 pub type ProjectDirectory = PathBuf;
 
+/// The number of compilations which share the same type and declaration engines. The engines
+/// grow with every compilation, so they are started afresh now and then, along with the cache of
+/// checked packages which refer to them.
+const COMPILATIONS_PER_ENGINES: usize = 32;
+
+/// The packages checked by previous compilations of the project, which are reused as long as
+/// their sources are unchanged.
+#[derive(Debug, Default)]
+struct CheckState {
+    cache: CheckCache,
+    /// The tokens of each dependency, which are collected again only once it has been checked
+    /// again.
+    dependency_tokens: HashMap<pkg::PinnedId, CachedTokens>,
+    /// The number of compilations since the engines were last cleared.
+    compilations: usize,
+}
+
+/// The tokens collected from the check of a dependency with the given fingerprint.
+#[derive(Debug)]
+struct CachedTokens {
+    fingerprint: u64,
+    tokens: Vec<((Ident, Span), Token)>,
+}
+
 #[derive(Default, Debug)]
 pub struct CompiledProgram {
     pub lexed: Option<LexedProgram>,
@@ -60,6 +94,22 @@ pub struct Session {
     pub type_engine: RwLock<TypeEngine>,
     pub decl_engine: RwLock<DeclEngine>,
     pub sync: SyncWorkspace,
+    /// The source directories of the dependencies fetched by forc, such as the standard library.
+    dependency_dirs: RwLock<Vec<PathBuf>>,
+    /// The source directories of the path dependencies of the project.
+    path_dependency_dirs: RwLock<Vec<PathBuf>>,
+    /// The warnings and errors of the last compilation of the project, from which quick fixes
+    /// are offered.
+    pub compile_warnings: RwLock<Vec<CompileWarning>>,
+    pub compile_errors: RwLock<Vec<CompileError>>,
+    /// Incremented as each compilation starts, so that those still running know that they have
+    /// been superseded.
+    compile_generation: AtomicUsize,
+    /// Held for the duration of each compilation, so that only one runs at a time.
+    check_state: Mutex<CheckState>,
+    /// The files which diagnostics have been published for, so that they can be cleared once
+    /// they have none.
+    pub diagnostic_files: Mutex<HashSet<Url>>,
 }
 
 impl Session {
@@ -72,6 +122,13 @@ impl Session {
             type_engine: <_>::default(),
             decl_engine: <_>::default(),
             sync: SyncWorkspace::new(),
+            dependency_dirs: RwLock::new(vec![]),
+            path_dependency_dirs: RwLock::new(vec![]),
+            compile_warnings: RwLock::new(vec![]),
+            compile_errors: RwLock::new(vec![]),
+            compile_generation: AtomicUsize::new(0),
+            check_state: Mutex::new(CheckState::default()),
+            diagnostic_files: Mutex::new(HashSet::new()),
         }
     }
 
@@ -108,9 +165,56 @@ impl Session {
         &self.token_map
     }
 
-    pub fn parse_project(&self, uri: &Url) -> Result<Diagnostics, LanguageServerError> {
-        self.token_map.clear();
-        self.runnables.clear();
+    /// Returns true if the file belongs to one of the dependencies fetched by forc for this
+    /// session's project, rather than to the project itself.
+    pub fn is_dependency_url(&self, uri: &Url) -> bool {
+        let path = Path::new(uri.path());
+        self.dependency_dirs
+            .read()
+            .iter()
+            .any(|dir| path.starts_with(dir))
+    }
+
+    /// Returns true if the file belongs to one of the path dependencies of this session's
+    /// project.
+    pub fn is_path_dependency_url(&self, uri: &Url) -> bool {
+        let path = Path::new(uri.path());
+        self.path_dependency_dirs
+            .read()
+            .iter()
+            .any(|dir| path.starts_with(dir))
+    }
+
+    /// Marks the start of a new compilation, superseding any which are still running, and
+    /// returns its generation.
+    pub fn start_compilation(&self) -> usize {
+        self.compile_generation.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Returns true if a compilation was started after the one of the given generation.
+    pub fn is_superseded(&self, generation: usize) -> bool {
+        self.compile_generation.load(Ordering::SeqCst) != generation
+    }
+
+    /// Compiles the project, reusing the packages which haven't changed since the last
+    /// compilation, and collects its tokens.
+    ///
+    /// The diagnostics of each package, by file, are passed to `on_diagnostics` as soon as the
+    /// package has been checked. Compilation stops with [LanguageServerError::CompilationCancelled]
+    /// once it has been superseded by a newer one, leaving the results of the last one in place,
+    /// unless the engines were reset for this compilation since those results refer to the old
+    /// ones. A compilation which has started collecting its tokens is never cancelled.
+    pub fn parse_project(
+        &self,
+        uri: &Url,
+        generation: usize,
+        mut on_diagnostics: impl FnMut(HashMap<PathBuf, Diagnostics>),
+    ) -> Result<(), LanguageServerError> {
+        // Wait for the previous compilation, which stops early if it was superseded by this one.
+        let mut check_state = self.check_state.lock();
+        if self.is_superseded(generation) {
+            return Err(LanguageServerError::CompilationCancelled);
+        }
 
         let manifest_dir = PathBuf::from(uri.path());
         let locked = false;
@@ -139,20 +243,59 @@ impl Session {
         let plan =
             pkg::BuildPlan::from_lock_and_manifests(&lock_path, &member_manifests, locked, offline)
                 .map_err(LanguageServerError::BuildPlanFailed)?;
+        let (dependency_dirs, path_dependency_dirs) = dependency_dirs(&plan);
+        *self.dependency_dirs.write() = dependency_dirs;
+        *self.path_dependency_dirs.write() = path_dependency_dirs;
+
+        check_state.compilations += 1;
+        if check_state.compilations > COMPILATIONS_PER_ENGINES {
+            check_state.compilations = 1;
+            check_state.cache.clear();
+            check_state.dependency_tokens.clear();
+            // Everything collected so far refers to the old engines.
+            self.token_map.clear();
+            *self.compiled_program.write() = Default::default();
+            *self.type_engine.write() = <_>::default();
+            *self.decl_engine.write() = <_>::default();
+        }
 
-        let mut diagnostics = Diagnostics {
-            warnings: vec![],
-            errors: vec![],
-        };
-
-        *self.type_engine.write() = <_>::default();
-        *self.decl_engine.write() = <_>::default();
         let type_engine = &*self.type_engine.read();
         let decl_engine = &*self.decl_engine.read();
         let engines = Engines::new(type_engine, decl_engine);
         let tests_enabled = true;
-        let results = pkg::check(&plan, BuildTarget::default(), true, tests_enabled, engines)
-            .map_err(LanguageServerError::FailedToCompile)?;
+        let results = pkg::check_incremental(
+            &plan,
+            BuildTarget::default(),
+            true,
+            tests_enabled,
+            engines,
+            &mut check_state.cache,
+            |_, res| {
+                // Diagnostics which aren't attributed to a file are shown in the one being edited.
+                on_diagnostics(get_diagnostics_by_file(
+                    &res.warnings,
+                    &res.errors,
+                    Path::new(uri.path()),
+                ));
+                match self.is_superseded(generation) {
+                    true => ControlFlow::Break(()),
+                    false => ControlFlow::Continue(()),
+                }
+            },
+        )
+        .map_err(LanguageServerError::FailedToCompile)?;
+
+        // The tokens of the last compilation remain in use until those of this one are collected.
+        // Once they're cleared the new ones are always collected in full, so that no request sees
+        // a partial token map.
+        if self.is_superseded(generation) {
+            return Err(LanguageServerError::CompilationCancelled);
+        }
+        self.token_map.clear();
+        self.runnables.clear();
+        self.compile_warnings.write().clear();
+        self.compile_errors.write().clear();
+
         let results_len = results.len();
         for (i, res) in results.into_iter().enumerate() {
             // We can convert these destructured elements to a Vec<Diagnostic> later on.
@@ -173,10 +316,11 @@ impl Session {
 
             let ast_res = CompileResult::new(typed, warnings, errors);
             let typed_program = self.compile_res_to_typed_program(&ast_res)?;
-            let ctx = ParseContext::new(&self.token_map, engines);
 
             // The final element in the results is the main program.
             if i == results_len - 1 {
+                let ctx = ParseContext::new(&self.token_map, engines);
+
                 // First, populate our token_map with sway keywords.
                 lexed_tree::parse(&lexed, &ctx);
 
@@ -198,19 +342,56 @@ impl Session {
                 self.save_parsed_program(parsed.to_owned().clone());
                 self.save_typed_program(typed_program.to_owned().clone());
 
-                diagnostics = get_diagnostics(&ast_res.warnings, &ast_res.errors);
+                *self.compile_warnings.write() = ast_res.warnings.clone();
+                *self.compile_errors.write() = ast_res.errors.clone();
             } else {
-                // Collect tokens from dependencies and the standard library prelude.
-                self.parse_ast_to_tokens(&parsed, &ctx, |an, ctx| {
-                    dependency::collect_parsed_declaration(an, ctx)
-                });
-
-                self.parse_ast_to_typed_tokens(typed_program, &ctx, |node, ctx| {
-                    dependency::collect_typed_declaration(node, ctx)
-                });
+                // Populate our token_map with the tokens of dependencies, including the standard
+                // library, so that definitions resolve both into and from within their sources.
+                // These are only traversed again once the dependency has been checked again.
+                let pkg_id = plan.graph()[plan.compilation_order()[i]].id();
+                let fingerprint = check_state.cache.fingerprint(&pkg_id);
+                match check_state.dependency_tokens.get(&pkg_id) {
+                    Some(cached) if Some(cached.fingerprint) == fingerprint => {
+                        for (key, token) in &cached.tokens {
+                            self.token_map.insert(key.clone(), token.clone());
+                        }
+                    }
+                    _ => {
+                        let dependency_tokens = TokenMap::new();
+                        let ctx = ParseContext::new(&dependency_tokens, engines);
+                        let parsed_tree = ParsedTree::new(&ctx);
+                        parsed_tree.collect_module_spans(&parsed);
+                        self.parse_ast_to_tokens(&parsed, &ctx, |an, _ctx| {
+                            parsed_tree.traverse_node(an)
+                        });
+
+                        let typed_tree = TypedTree::new(&ctx, &typed_program.root.namespace);
+                        typed_tree.collect_module_spans(typed_program);
+                        self.parse_ast_to_typed_tokens(typed_program, &ctx, |node, _ctx| {
+                            typed_tree.traverse_node(node)
+                        });
+
+                        let tokens: Vec<_> = dependency_tokens
+                            .iter()
+                            .map(|item| (item.key().clone(), item.value().clone()))
+                            .collect();
+                        for (key, token) in &tokens {
+                            self.token_map.insert(key.clone(), token.clone());
+                        }
+                        if let Some(fingerprint) = fingerprint {
+                            check_state.dependency_tokens.insert(
+                                pkg_id,
+                                CachedTokens {
+                                    fingerprint,
+                                    tokens,
+                                },
+                            );
+                        }
+                    }
+                }
             }
         }
-        Ok(diagnostics)
+        Ok(())
     }
 
     pub fn token_ranges(&self, url: &Url, position: Position) -> Option<Vec<Range>> {
@@ -277,11 +458,12 @@ impl Session {
         None
     }
 
-    pub fn symbol_information(&self, url: &Url) -> Option<Vec<SymbolInformation>> {
-        let tokens = self.token_map.tokens_for_file(url);
-        self.sync
-            .to_workspace_url(url.clone())
-            .map(|url| capabilities::document_symbol::to_symbol_information(tokens, url))
+    pub fn document_symbols(&self, url: &Url) -> Option<Vec<DocumentSymbol>> {
+        let path = url.to_file_path().ok()?;
+        let compiled_program = self.compiled_program.read();
+        let lexed = compiled_program.lexed.as_ref()?;
+        capabilities::document_symbol::lexed_module_in_file(&lexed.root, &path)
+            .map(capabilities::document_symbol::to_document_symbols)
     }
 
     pub fn format_text(&self, url: &Url) -> Result<Vec<TextEdit>, LanguageServerError> {
@@ -293,8 +475,7 @@ impl Session {
                 path: url.path().to_string(),
             })?;
 
-        get_page_text_edit(Arc::from(document.get_text()), &mut <_>::default())
-            .map(|page_text_edit| vec![page_text_edit])
+        get_format_text_edits(Arc::from(document.get_text()), &mut <_>::default())
     }
 
     pub fn handle_open_file(&self, uri: &Url) {
@@ -383,14 +564,7 @@ impl Session {
         ctx: &ParseContext,
         f: impl Fn(&AstNode, &ParseContext),
     ) {
-        let root_nodes = parse_program.root.tree.root_nodes.iter();
-        let sub_nodes = parse_program
-            .root
-            .submodules
-            .iter()
-            .flat_map(|(_, submodule)| &submodule.module.tree.root_nodes);
-
-        root_nodes.chain(sub_nodes).for_each(|n| f(n, ctx));
+        parse_module_to_tokens(&parse_program.root, ctx, &f);
     }
 
     /// Parse the [ty::TyProgram] AST to populate the [TokenMap] with typed AST nodes.
@@ -403,8 +577,7 @@ impl Session {
         let root_nodes = typed_program.root.all_nodes.iter();
         let sub_nodes = typed_program
             .root
-            .submodules
-            .iter()
+            .submodules_recursive()
             .flat_map(|(_, submodule)| submodule.module.all_nodes.iter());
 
         root_nodes.chain(sub_nodes).for_each(|n| f(n, ctx));
@@ -418,9 +591,7 @@ impl Session {
         ast_res
             .value
             .as_ref()
-            .ok_or(LanguageServerError::FailedToParse {
-                diagnostics: get_diagnostics(&ast_res.warnings, &ast_res.errors),
-            })
+            .ok_or(LanguageServerError::FailedToParse)
     }
 
     /// Create runnables if the `TyProgramKind` of the `TyProgram` is a script.
@@ -484,6 +655,45 @@ impl Session {
     }
 }
 
+/// Parse the nodes of the [ParseModule] and all of its submodules, recursively.
+fn parse_module_to_tokens(
+    module: &ParseModule,
+    ctx: &ParseContext,
+    f: &impl Fn(&AstNode, &ParseContext),
+) {
+    module.tree.root_nodes.iter().for_each(|n| f(n, ctx));
+    for (_, submodule) in &module.submodules {
+        parse_module_to_tokens(&submodule.module, ctx, f);
+    }
+}
+
+/// The source directories of the dependencies of the project, split into those that forc
+/// fetches into the user's forc directory and the path dependencies of the project's members.
+///
+/// The fetched dependencies are the git and registry dependencies, along with any path
+/// dependencies found within their sources.
+fn dependency_dirs(plan: &pkg::BuildPlan) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let graph = plan.graph();
+    let member_ids: HashSet<_> = plan
+        .member_pinned_pkgs()
+        .map(|pinned| pinned.id())
+        .collect();
+    let mut fetched = vec![];
+    let mut path = vec![];
+    for pinned in plan.compilation_order().iter().map(|&node| &graph[node]) {
+        let dirs = match &pinned.source {
+            source::Pinned::Git(_) | source::Pinned::Registry(_) => &mut fetched,
+            source::Pinned::Path(source) if member_ids.contains(&source.path_root) => &mut path,
+            source::Pinned::Path(_) => &mut fetched,
+            source::Pinned::Member(_) => continue,
+        };
+        if let Some(manifest) = plan.manifest_map().get(&pinned.id()) {
+            dirs.push(manifest.dir().to_path_buf());
+        }
+    }
+    (fetched, path)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -515,8 +725,9 @@ mod tests {
         let session = Session::new();
         let dir = get_absolute_path("sway-lsp/tests/fixtures");
         let uri = get_url(&dir);
-        let result =
-            Session::parse_project(&session, &uri).expect_err("expected ManifestFileNotFound");
+        let generation = session.start_compilation();
+        let result = Session::parse_project(&session, &uri, generation, |_| {})
+            .expect_err("expected ManifestFileNotFound");
         assert!(matches!(
             result,
             LanguageServerError::DocumentError(
@@ -525,4 +736,16 @@ mod tests {
             if test_dir == dir
         ));
     }
+
+    #[test]
+    fn parse_project_returns_compilation_cancelled() {
+        let session = Session::new();
+        let dir = get_absolute_path("sway-lsp/tests/fixtures");
+        let uri = get_url(&dir);
+        let generation = session.start_compilation();
+        session.start_compilation();
+        let result = Session::parse_project(&session, &uri, generation, |_| {})
+            .expect_err("expected CompilationCancelled");
+        assert!(matches!(result, LanguageServerError::CompilationCancelled));
+    }
 }
@@ -1,8 +1,6 @@
 use swayfmt::FormatterError;
 use thiserror::Error;
 
-use crate::capabilities::diagnostic::Diagnostics;
-
 #[derive(Debug, Error)]
 pub enum LanguageServerError {
     // Inherited errors
@@ -10,6 +8,8 @@ pub enum LanguageServerError {
     DocumentError(#[from] DocumentError),
     #[error(transparent)]
     DirectoryError(#[from] DirectoryError),
+    #[error(transparent)]
+    RenameError(#[from] RenameError),
 
     // Top level errors
     #[error("Failed to create build plan. {0}")]
@@ -17,7 +17,9 @@ pub enum LanguageServerError {
     #[error("Failed to compile. {0}")]
     FailedToCompile(anyhow::Error),
     #[error("Failed to parse document")]
-    FailedToParse { diagnostics: Diagnostics },
+    FailedToParse,
+    #[error("Compilation was superseded by a newer one")]
+    CompilationCancelled,
     #[error("Error formatting document: {0}")]
     FormatError(FormatterError),
 }
@@ -59,3 +61,17 @@ pub enum DirectoryError {
     #[error("Unable to create Url from path {:?}", path)]
     UrlFromPathFailed { path: String },
 }
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RenameError {
+    #[error("No token was found at the cursor position")]
+    TokenNotFound,
+    #[error("Only tokens declared within the workspace can be renamed")]
+    TokenNotPartOfWorkspace,
+    #[error("Keywords, modules and built-in types cannot be renamed")]
+    SymbolKindNotAllowed,
+    #[error("{:?} is not a valid identifier", name)]
+    InvalidName { name: String },
+    #[error("{:?} is already declared within the same scope", name)]
+    NameConflict { name: String },
+}
@@ -17,6 +17,7 @@ pub async fn start() {
 
     let (service, socket) = LspService::build(Backend::new)
         .custom_method("sway/show_ast", Backend::show_ast)
+        .custom_method("sway/show_ir", Backend::show_ir)
         .custom_method("textDocument/inlayHint", Backend::inlay_hints)
         .finish();
     Server::new(stdin, stdout, socket).serve(service).await;
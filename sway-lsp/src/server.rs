@@ -12,6 +12,7 @@ use forc_tracing::{init_tracing_subscriber, TracingSubscriberOptions, TracingWri
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
     fs::File,
     io::Write,
     ops::Deref,
@@ -19,6 +20,7 @@ use std::{
     sync::Arc,
 };
 use sway_types::{Ident, Spanned};
+use tokio::sync::mpsc;
 use tower_lsp::lsp_types::*;
 use tower_lsp::{jsonrpc, Client, LanguageServer};
 use tracing::metadata::LevelFilter;
@@ -56,29 +58,98 @@ impl Backend {
         &self,
         workspace_uri: &Url,
     ) -> Result<(Url, Arc<Session>), LanguageServerError> {
+        // Files within path dependencies which haven't been opened as projects of their own are
+        // served by the session of a project that depends on them, which holds their tokens.
+        if !self.has_own_session(workspace_uri) {
+            if let Some(session) = self.path_dependency_session(workspace_uri) {
+                return Ok((workspace_uri.clone(), session));
+            }
+        }
+        self.get_uri_and_own_session(workspace_uri)
+    }
+
+    /// Like [Backend::get_uri_and_session], but files within path dependencies are always served
+    /// by the session of their own project, which is created if need be so that they're compiled
+    /// as they are opened and edited.
+    fn get_uri_and_own_session(
+        &self,
+        workspace_uri: &Url,
+    ) -> Result<(Url, Arc<Session>), LanguageServerError> {
+        // Files within fetched dependencies, such as those reached by going to a definition in the
+        // standard library, are served by the session of the project that depends on them.
+        if let Some(session) = self.dependency_session(workspace_uri) {
+            return Ok((workspace_uri.clone(), session));
+        }
         let session = self.url_to_session(workspace_uri)?;
         let uri = session.sync.workspace_to_temp_url(workspace_uri)?;
         Ok((uri, session))
     }
 
-    async fn parse_project(&self, uri: Url, workspace_uri: Url, session: Arc<Session>) {
-        // pass in the temp Url into parse_project, we can now get the updated AST's back.
-        let diagnostics = match session.parse_project(&uri) {
-            Ok(diagnostics) => diagnostics,
-            Err(err) => {
-                tracing::error!("{}", err.to_string().as_str());
-                if let LanguageServerError::FailedToParse { diagnostics } = err {
-                    diagnostics
-                } else {
-                    Diagnostics {
-                        warnings: vec![],
-                        errors: vec![],
-                    }
-                }
+    async fn parse_project(&self, uri: Url, session: Arc<Session>) {
+        // Compile on a blocking thread so that requests are answered from the results of the last
+        // compilation in the meantime. Starting this compilation supersedes any which is still
+        // running for an earlier edit.
+        let generation = session.start_compilation();
+        let (diagnostics_tx, mut diagnostics_rx) = mpsc::unbounded_channel();
+        let compilation = tokio::task::spawn_blocking({
+            let session = session.clone();
+            let uri = uri.clone();
+            move || {
+                // pass in the temp Url into parse_project, we can now get the updated AST's back.
+                session.parse_project(&uri, generation, |diagnostics| {
+                    let _ = diagnostics_tx.send(diagnostics);
+                })
             }
+        });
+
+        // Publish the diagnostics of each package as soon as it has been checked.
+        let mut published = HashMap::new();
+        while let Some(diagnostics) = diagnostics_rx.recv().await {
+            if session.is_superseded(generation) {
+                continue;
+            }
+            for (path, diagnostics) in diagnostics {
+                // Diagnostics are only shown for the files of the project.
+                let file_uri = match Url::from_file_path(path) {
+                    Ok(file_uri) if session.sync.is_path_in_workspace(&file_uri) => file_uri,
+                    _ => continue,
+                };
+                session.diagnostic_files.lock().insert(file_uri.clone());
+                self.publish_diagnostics(&file_uri, &session, diagnostics.clone())
+                    .await;
+                published.insert(file_uri, diagnostics);
+            }
+        }
+
+        match compilation.await {
+            Ok(Ok(())) | Ok(Err(LanguageServerError::FailedToParse)) => {}
+            Ok(Err(LanguageServerError::CompilationCancelled)) => return,
+            Ok(Err(err)) => tracing::error!("{}", err.to_string().as_str()),
+            Err(err) => tracing::error!("{}", err.to_string().as_str()),
+        }
+
+        // The tokens of the edited file, which may be shown as warnings for debugging, are only
+        // collected once the whole project has been checked.
+        if self.config.read().debug.show_collected_tokens_as_warnings != Warnings::Default {
+            let diagnostics = published.get(&uri).cloned().unwrap_or_default();
+            self.publish_diagnostics(&uri, &session, diagnostics).await;
+        }
+
+        // Clear the diagnostics of the files which no longer have any.
+        let stale = {
+            let mut diagnostic_files = session.diagnostic_files.lock();
+            let stale: Vec<Url> = diagnostic_files
+                .iter()
+                .filter(|file_uri| !published.contains_key(*file_uri))
+                .cloned()
+                .collect();
+            *diagnostic_files = published.into_keys().collect();
+            stale
         };
-        self.publish_diagnostics(&uri, &workspace_uri, session, diagnostics)
-            .await;
+        for file_uri in stale {
+            self.publish_diagnostics(&file_uri, &session, Diagnostics::default())
+                .await;
+        }
     }
 }
 
@@ -102,24 +173,58 @@ pub fn capabilities() -> ServerCapabilities {
             .into(),
         ),
         document_symbol_provider: Some(OneOf::Left(true)),
+        workspace_symbol_provider: Some(OneOf::Left(true)),
         completion_provider: Some(CompletionOptions {
             trigger_characters: Some(vec![".".to_string()]),
             ..Default::default()
         }),
         document_formatting_provider: Some(OneOf::Left(true)),
         definition_provider: Some(OneOf::Left(true)),
+        references_provider: Some(OneOf::Left(true)),
         inlay_hint_provider: Some(OneOf::Left(true)),
         code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
         code_lens_provider: Some(CodeLensOptions {
             resolve_provider: Some(false),
         }),
         hover_provider: Some(HoverProviderCapability::Simple(true)),
+        rename_provider: Some(OneOf::Right(RenameOptions {
+            prepare_provider: Some(true),
+            work_done_progress_options: WorkDoneProgressOptions {
+                work_done_progress: None,
+            },
+        })),
         ..ServerCapabilities::default()
     }
 }
 
 impl Backend {
-    fn url_to_session(&self, uri: &Url) -> Result<Arc<Session>, LanguageServerError> {
+    /// Returns the session of a project which the file at the given [Url] is a fetched
+    /// dependency of, if any.
+    fn dependency_session(&self, uri: &Url) -> Option<Arc<Session>> {
+        self.sessions
+            .iter()
+            .find(|item| item.value().is_dependency_url(uri))
+            .map(|item| item.value().clone())
+    }
+
+    /// Returns the session of a project which the file at the given [Url] is a path
+    /// dependency of, if any.
+    fn path_dependency_session(&self, uri: &Url) -> Option<Arc<Session>> {
+        self.sessions
+            .iter()
+            .find(|item| item.value().is_path_dependency_url(uri))
+            .map(|item| item.value().clone())
+    }
+
+    /// Returns true if a session has been created for the project of the file at the given [Url].
+    fn has_own_session(&self, uri: &Url) -> bool {
+        self.url_to_manifest_dir(uri)
+            .map(|manifest_dir| self.sessions.contains_key(&manifest_dir))
+            .unwrap_or(false)
+    }
+
+    /// Returns the directory of the manifest of the project the file at the given [Url] belongs to.
+    fn url_to_manifest_dir(&self, uri: &Url) -> Result<PathBuf, LanguageServerError> {
         let path = PathBuf::from(uri.path());
         let manifest = PackageManifestFile::from_dir(&path).map_err(|_| {
             DocumentError::ManifestFileNotFound {
@@ -133,7 +238,11 @@ impl Backend {
             .parent()
             .ok_or(DirectoryError::ManifestDirNotFound)?
             .to_path_buf();
+        Ok(manifest_dir)
+    }
 
+    fn url_to_session(&self, uri: &Url) -> Result<Arc<Session>, LanguageServerError> {
+        let manifest_dir = self.url_to_manifest_dir(uri)?;
         let session = match self.sessions.try_get(&manifest_dir).try_unwrap() {
             Some(item) => item.value().clone(),
             None => {
@@ -150,13 +259,13 @@ impl Backend {
         Ok(session)
     }
 
-    async fn publish_diagnostics(
-        &self,
-        uri: &Url,
-        workspace_uri: &Url,
-        session: Arc<Session>,
-        diagnostics: Diagnostics,
-    ) {
+    /// Publishes the diagnostics of the file at the given temp [Url], replacing any published
+    /// before.
+    async fn publish_diagnostics(&self, uri: &Url, session: &Session, diagnostics: Diagnostics) {
+        let workspace_uri = match session.sync.to_workspace_url(uri.clone()) {
+            Some(workspace_uri) => workspace_uri,
+            None => return,
+        };
         let diagnostics_res = {
             let mut diagnostics_to_publish = vec![];
             let config = &self.config.read();
@@ -185,7 +294,7 @@ impl Backend {
         // Note: Even if the computed diagnostics vec is empty, we still have to push the empty Vec
         // in order to clear former diagnostics. Newly pushed diagnostics always replace previously pushed diagnostics.
         self.client
-            .publish_diagnostics(workspace_uri.clone(), diagnostics_res, None)
+            .publish_diagnostics(workspace_uri, diagnostics_res, None)
             .await;
     }
 }
@@ -238,11 +347,13 @@ impl LanguageServer for Backend {
 
     // Document Handlers
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
-        match self.get_uri_and_session(&params.text_document.uri) {
+        match self.get_uri_and_own_session(&params.text_document.uri) {
             Ok((uri, session)) => {
                 session.handle_open_file(&uri);
-                self.parse_project(uri, params.text_document.uri, session.clone())
-                    .await;
+                // Dependencies are compiled along with the project that depends on them.
+                if !session.is_dependency_url(&uri) {
+                    self.parse_project(uri, session.clone()).await;
+                }
             }
             Err(err) => tracing::error!("{}", err.to_string()),
         }
@@ -250,7 +361,9 @@ impl LanguageServer for Backend {
 
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
         let config = self.config.read().on_enter.clone();
-        match self.get_uri_and_session(&params.text_document.uri) {
+        match self.get_uri_and_own_session(&params.text_document.uri) {
+            // Changes to fetched dependencies are not written to disk nor compiled.
+            Ok((uri, session)) if session.is_dependency_url(&uri) => {}
             Ok((uri, session)) => {
                 // handle on_enter capabilities if they are enabled
                 capabilities::on_enter(&config, &self.client, &session, &uri.clone(), &params)
@@ -259,8 +372,7 @@ impl LanguageServer for Backend {
                 // update this file with the new changes and write to disk
                 match session.write_changes_to_file(&uri, params.content_changes) {
                     Ok(_) => {
-                        self.parse_project(uri, params.text_document.uri.clone(), session.clone())
-                            .await;
+                        self.parse_project(uri, session.clone()).await;
                     }
                     Err(err) => tracing::error!("{}", err.to_string()),
                 }
@@ -270,7 +382,8 @@ impl LanguageServer for Backend {
     }
 
     async fn did_save(&self, params: DidSaveTextDocumentParams) {
-        match self.get_uri_and_session(&params.text_document.uri) {
+        match self.get_uri_and_own_session(&params.text_document.uri) {
+            Ok((uri, session)) if session.is_dependency_url(&uri) => {}
             Ok((uri, session)) => {
                 // overwrite the contents of the tmp/folder with everything in
                 // the current workspace. (resync)
@@ -287,8 +400,7 @@ impl LanguageServer for Backend {
                             sync::edit_manifest_dependency_paths(&manifest, temp_manifest_path)
                         }
                     });
-                self.parse_project(uri, params.text_document.uri, session)
-                    .await;
+                self.parse_project(uri, session).await;
             }
             Err(err) => tracing::error!("{}", err.to_string()),
         }
@@ -297,7 +409,7 @@ impl LanguageServer for Backend {
     async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
         for event in params.changes {
             if event.typ == FileChangeType::DELETED {
-                match self.get_uri_and_session(&event.uri) {
+                match self.get_uri_and_own_session(&event.uri) {
                     Ok((uri, session)) => {
                         let _ = session.remove_document(&uri);
                     }
@@ -392,8 +504,8 @@ impl LanguageServer for Backend {
     ) -> jsonrpc::Result<Option<DocumentSymbolResponse>> {
         match self.get_uri_and_session(&params.text_document.uri) {
             Ok((uri, session)) => Ok(session
-                .symbol_information(&uri)
-                .map(DocumentSymbolResponse::Flat)),
+                .document_symbols(&uri)
+                .map(DocumentSymbolResponse::Nested)),
             Err(err) => {
                 tracing::error!("{}", err.to_string());
                 Ok(None)
@@ -401,6 +513,17 @@ impl LanguageServer for Backend {
         }
     }
 
+    async fn symbol(
+        &self,
+        params: WorkspaceSymbolParams,
+    ) -> jsonrpc::Result<Option<Vec<SymbolInformation>>> {
+        let sessions = self.sessions.iter().map(|item| item.value().clone());
+        Ok(Some(capabilities::workspace_symbol::workspace_symbols(
+            sessions,
+            &params.query,
+        )))
+    }
+
     async fn semantic_tokens_full(
         &self,
         params: SemanticTokensParams,
@@ -462,14 +585,33 @@ impl LanguageServer for Backend {
             })
     }
 
+    async fn references(&self, params: ReferenceParams) -> jsonrpc::Result<Option<Vec<Location>>> {
+        match self.get_uri_and_session(&params.text_document_position.text_document.uri) {
+            Ok((uri, session)) => {
+                let position = params.text_document_position.position;
+                Ok(capabilities::references::references(
+                    session,
+                    uri,
+                    position,
+                    params.context.include_declaration,
+                ))
+            }
+            Err(err) => {
+                tracing::error!("{}", err.to_string());
+                Ok(None)
+            }
+        }
+    }
+
     async fn rename(&self, params: RenameParams) -> jsonrpc::Result<Option<WorkspaceEdit>> {
         match self.get_uri_and_session(&params.text_document_position.text_document.uri) {
             Ok((uri, session)) => {
                 let new_name = params.new_name;
                 let position = params.text_document_position.position;
-                Ok(capabilities::rename::rename(
-                    session, new_name, uri, position,
-                ))
+                match capabilities::rename::rename(session, new_name, uri, position) {
+                    Ok(edit) => Ok(Some(edit)),
+                    Err(err) => Err(jsonrpc::Error::invalid_params(err.to_string())),
+                }
             }
             Err(err) => {
                 tracing::error!("{}", err.to_string());
@@ -485,7 +627,10 @@ impl LanguageServer for Backend {
         match self.get_uri_and_session(&params.text_document.uri) {
             Ok((uri, session)) => {
                 let position = params.position;
-                Ok(capabilities::rename::prepare_rename(session, uri, position))
+                match capabilities::rename::prepare_rename(session, uri, position) {
+                    Ok(res) => Ok(Some(res)),
+                    Err(err) => Err(jsonrpc::Error::invalid_params(err.to_string())),
+                }
             }
             Err(err) => {
                 tracing::error!("{}", err.to_string());
@@ -503,6 +648,15 @@ pub struct ShowAstParams {
     pub save_path: Url,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShowIrParams {
+    pub text_document: TextDocumentIdentifier,
+    pub position: Position,
+    pub show_passes: bool,
+    pub save_path: Url,
+}
+
 // Custom LSP-Server Methods
 impl Backend {
     pub async fn inlay_hints(
@@ -634,4 +788,46 @@ impl Backend {
             }
         }
     }
+
+    /// This method is triggered by the "show IR and ASM" command in VScode.
+    ///
+    /// The function expects the URI of the current open file and the position of the cursor.
+    /// The function enclosing the cursor is compiled and its final IR and ASM, optionally
+    /// preceded by its IR after each optimisation pass which changed it, are written to a
+    /// temporary file. The URI of the file is returned to the client so it can be opened and
+    /// displayed in a seperate side panel.
+    pub async fn show_ir(
+        &self,
+        params: ShowIrParams,
+    ) -> jsonrpc::Result<Option<TextDocumentIdentifier>> {
+        match self.get_uri_and_session(&params.text_document.uri) {
+            Ok((uri, session)) => {
+                let contents = match capabilities::show_ir::show_ir(
+                    session,
+                    &uri,
+                    params.position,
+                    params.show_passes,
+                ) {
+                    Some(contents) => contents,
+                    None => return Ok(None),
+                };
+                let path = PathBuf::from(params.save_path.path()).join("function.ir");
+                let mut file = match File::create(&path) {
+                    Ok(file) => file,
+                    Err(err) => {
+                        tracing::error!("{}", err.to_string());
+                        return Ok(None);
+                    }
+                };
+                let _ = writeln!(&mut file, "{contents}");
+                Ok(Url::from_file_path(path)
+                    .ok()
+                    .map(TextDocumentIdentifier::new))
+            }
+            Err(err) => {
+                tracing::error!("{}", err.to_string());
+                Ok(None)
+            }
+        }
+    }
 }
@@ -86,6 +86,7 @@ impl Parse for ItemKind {
             ItemKind::Configurable(item_configurable) => {
                 item_configurable.parse(ctx);
             }
+            ItemKind::Error(_) => {}
         }
     }
 }
@@ -524,6 +525,7 @@ impl Parse for Statement {
             Statement::Item(item) => {
                 item.value.parse(ctx);
             }
+            Statement::Error(_) => {}
         }
     }
 }
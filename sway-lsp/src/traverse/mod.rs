@@ -1,7 +1,6 @@
 use crate::core::token_map::TokenMap;
 use sway_core::Engines;
 
-pub(crate) mod dependency;
 pub(crate) mod lexed_tree;
 pub(crate) mod parsed_tree;
 pub(crate) mod typed_tree;
@@ -724,7 +724,8 @@ impl<'a> TypedTree<'a> {
                 self.handle_expression(address);
             }
             ty::TyExpressionVariant::StorageAccess(storage_access) => {
-                for field in &storage_access.fields {
+                let decl_engine = self.ctx.engines.de();
+                for (i, field) in storage_access.fields.iter().enumerate() {
                     if let Some(mut token) = self
                         .ctx
                         .tokens
@@ -732,6 +733,22 @@ impl<'a> TypedTree<'a> {
                         .try_unwrap()
                     {
                         token.typed = Some(TypedAstToken::TyStorageAccessDescriptor(field.clone()));
+
+                        // the first ident is a field of the storage definition
+                        if i == 0 {
+                            if let Some(storage_field) = self
+                                .namespace
+                                .get_declared_storage(decl_engine)
+                                .and_then(|storage| {
+                                    storage
+                                        .fields
+                                        .into_iter()
+                                        .find(|f| f.name.as_str() == field.name.as_str())
+                                })
+                            {
+                                token.type_def = Some(TypeDefinition::Ident(storage_field.name));
+                            }
+                        }
                     }
                 }
             }
@@ -0,0 +1,11 @@
+contract;
+
+abi Counter {
+    fn get(amount: u64) -> u64;
+}
+
+impl Counter for Contract {
+    fn get(amount: u64) -> u64 {
+        amount * 2
+    }
+}
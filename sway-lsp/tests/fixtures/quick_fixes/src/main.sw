@@ -0,0 +1,40 @@
+contract;
+
+mod shapes;
+
+use shapes::{Area, Square};
+
+abi Shapes {
+    fn unused_variable() -> u64;
+    fn missing_import() -> u64;
+}
+
+storage {
+    counter: u64 = 0,
+}
+
+impl Shapes for Contract {
+    fn unused_variable() -> u64 {
+        let side = 2;
+        0
+    }
+
+    fn missing_import() -> u64 {
+        perimeter(Square { side: 2 })
+    }
+}
+
+impl Area for Square {
+    fn area(self) -> u64 {
+        self.side * self.side
+    }
+}
+
+#[storage(read)]
+fn counter() -> u64 {
+    storage.counter
+}
+
+fn double_counter() -> u64 {
+    counter() * 2
+}
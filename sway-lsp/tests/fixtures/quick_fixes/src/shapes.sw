@@ -0,0 +1,14 @@
+library;
+
+pub struct Square {
+    side: u64,
+}
+
+pub trait Area {
+    fn area(self) -> u64;
+    fn scale(self, factor: u64) -> Self;
+}
+
+pub fn perimeter(square: Square) -> u64 {
+    square.side * 4
+}
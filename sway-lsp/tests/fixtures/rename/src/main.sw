@@ -0,0 +1,39 @@
+library;
+
+trait Shape {
+    fn area(self) -> u64;
+}
+
+struct Square {
+    side: u64,
+    offset: u64,
+}
+
+impl Shape for Square {
+    fn area(self) -> u64 {
+        self.side * self.side + self.offset
+    }
+}
+
+struct Circle {
+    radius: u64,
+}
+
+impl Shape for Circle {
+    fn area(self) -> u64 {
+        3 * self.radius * self.radius
+    }
+}
+
+fn total(square: Square, circle: Circle) -> u64 {
+    square.area() + circle.area()
+}
+
+fn perimeter(side: u64) -> u64 {
+    4 * side
+}
+
+fn perimeters() -> u64 {
+    let side = 2;
+    perimeter(1) + perimeter(side)
+}
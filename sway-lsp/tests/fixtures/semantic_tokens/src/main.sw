@@ -0,0 +1,38 @@
+contract;
+
+configurable {
+    OWNER: u64 = 1,
+}
+
+storage {
+    counter: u64 = 0,
+}
+
+pub trait Double {
+    fn double(self) -> Self;
+}
+
+struct Pair<T> {
+    first: T,
+}
+
+impl Double for u64 {
+    fn double(self) -> Self {
+        self * 2
+    }
+}
+
+abi Counter {
+    #[storage(read)]
+    fn get(amount: u64) -> u64;
+}
+
+impl Counter for Contract {
+    #[storage(read)]
+    fn get(amount: u64) -> u64 {
+        let mut total = storage.counter + OWNER;
+        total = total.double();
+        let pair = Pair { first: amount };
+        total + pair.first
+    }
+}
@@ -10,3 +10,7 @@ pub enum DeepEnum {
 pub struct DeepStruct<T> {
     field: T,
 }
+
+pub fn deep_struct() -> DeepStruct<u64> {
+    DeepStruct { field: 0 }
+}
@@ -320,3 +320,48 @@ pub(crate) async fn code_action_struct_existing_impl_request(
     assert_json_eq!(expected, response.ok().unwrap());
     code_action
 }
+
+pub(crate) async fn quick_fix_check(
+    service: &mut LspService<Backend>,
+    uri: &Url,
+    position: Position,
+    title: &str,
+    expected: TextEdit,
+    id: i64,
+) -> Request {
+    let params = json!({
+        "textDocument": {
+            "uri": uri,
+        },
+        "range" : {
+            "start": position,
+            "end": position,
+        },
+        "context": {
+            "diagnostics": [],
+            "triggerKind": 1
+        }
+    });
+    let code_action = build_request_with_id("textDocument/codeAction", params, id);
+    let response = call_request(service, code_action.clone())
+        .await
+        .unwrap()
+        .unwrap();
+    let actions: CodeActionResponse =
+        serde_json::from_value(response.result().unwrap().clone()).unwrap();
+    let action = actions
+        .into_iter()
+        .find_map(|action| match action {
+            CodeActionOrCommand::CodeAction(action) if action.title == title => Some(action),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("no code action titled {title:?}"));
+    assert_eq!(action.kind, Some(CodeActionKind::QUICKFIX));
+    assert_eq!(
+        action.diagnostics.map(|diagnostics| diagnostics.len()),
+        Some(1)
+    );
+    let changes = action.edit.unwrap().changes.unwrap();
+    assert_eq!(changes[uri], vec![expected]);
+    code_action
+}
@@ -146,6 +146,31 @@ pub(crate) async fn show_ast_request(
     show_ast
 }
 
+pub(crate) async fn show_ir_request(
+    service: &mut LspService<Backend>,
+    uri: &Url,
+    position: Position,
+    show_passes: bool,
+) -> String {
+    let save_path = Url::from_file_path(Path::new("/tmp")).unwrap();
+    let params = json!({
+        "textDocument": {
+            "uri": uri
+        },
+        "position": position,
+        "showPasses": show_passes,
+        "savePath": save_path,
+    });
+    let show_ir = build_request_with_id("sway/show_ir", params, 1);
+    let response = call_request(service, show_ir).await;
+    let expected = Response::from_ok(
+        1.into(),
+        json!({ "uri": format!("{save_path}/function.ir") }),
+    );
+    assert_json_eq!(expected, response.ok().unwrap());
+    std::fs::read_to_string("/tmp/function.ir").unwrap()
+}
+
 pub(crate) async fn semantic_tokens_request(
     service: &mut LspService<Backend>,
     uri: &Url,
@@ -160,6 +185,51 @@ pub(crate) async fn semantic_tokens_request(
     semantic_tokens
 }
 
+/// Requests the semantic tokens of the file and checks the type and modifiers of the token
+/// starting at each of the given `(line, character, token_type, token_modifiers)`.
+pub(crate) async fn semantic_tokens_check(
+    service: &mut LspService<Backend>,
+    uri: &Url,
+    expected: &[(u32, u32, u32, u32)],
+) {
+    let request = semantic_tokens_request(service, uri).await;
+    let response = call_request(service, request).await.unwrap().unwrap();
+    let value = response.result().unwrap();
+    let result: SemanticTokensResult = serde_json::from_value(value.clone()).unwrap();
+    let tokens = match result {
+        SemanticTokensResult::Tokens(tokens) => tokens.data,
+        SemanticTokensResult::Partial(partial) => partial.data,
+    };
+
+    // Each token is positioned relative to the previous one.
+    let (mut line, mut character) = (0, 0);
+    let mut decoded = vec![];
+    for token in tokens {
+        if token.delta_line > 0 {
+            character = 0;
+        }
+        line += token.delta_line;
+        character += token.delta_start;
+        decoded.push((
+            line,
+            character,
+            token.token_type,
+            token.token_modifiers_bitset,
+        ));
+    }
+    for (line, character, token_type, token_modifiers) in expected {
+        let actual = decoded
+            .iter()
+            .find(|token| token.0 == *line && token.1 == *character)
+            .unwrap_or_else(|| panic!("No semantic token at {line}:{character}"));
+        assert_eq!(
+            (actual.2, actual.3),
+            (*token_type, *token_modifiers),
+            "Unexpected semantic token at {line}:{character}"
+        );
+    }
+}
+
 pub(crate) async fn document_symbol_request(
     service: &mut LspService<Backend>,
     uri: &Url,
@@ -174,6 +244,52 @@ pub(crate) async fn document_symbol_request(
     document_symbol
 }
 
+/// Requests the document symbols of the file and checks the names of the top level symbols, along
+/// with the names of the children of each of them.
+pub(crate) async fn document_symbol_check(
+    service: &mut LspService<Backend>,
+    uri: &Url,
+    expected: &[(&str, &[&str])],
+) {
+    let request = document_symbol_request(service, uri).await;
+    let response = call_request(service, request).await.unwrap().unwrap();
+    let value = response.result().unwrap();
+    let symbols = match serde_json::from_value(value.clone()).unwrap() {
+        DocumentSymbolResponse::Nested(symbols) => symbols,
+        DocumentSymbolResponse::Flat(_) => panic!("Expected nested document symbols"),
+    };
+    let actual: Vec<(&str, Vec<&str>)> = symbols
+        .iter()
+        .map(|symbol| {
+            let children = symbol
+                .children
+                .iter()
+                .flatten()
+                .map(|child| child.name.as_str())
+                .collect();
+            (symbol.name.as_str(), children)
+        })
+        .collect();
+    let expected: Vec<(&str, Vec<&str>)> = expected
+        .iter()
+        .map(|(name, children)| (*name, children.to_vec()))
+        .collect();
+    assert_eq!(actual, expected);
+}
+
+pub(crate) async fn workspace_symbol_request(
+    service: &mut LspService<Backend>,
+    query: &str,
+) -> Vec<SymbolInformation> {
+    let params = json!({ "query": query });
+    let workspace_symbol = build_request_with_id("workspace/symbol", params, 1);
+    let response = call_request(service, workspace_symbol)
+        .await
+        .unwrap()
+        .unwrap();
+    serde_json::from_value(response.result().unwrap().clone()).unwrap()
+}
+
 pub(crate) fn definition_request(uri: &Url, token_line: i32, token_char: i32, id: i64) -> Request {
     let params = json!({
         "textDocument": {
@@ -424,6 +540,123 @@ pub(crate) async fn definition_check<'a>(
     definition
 }
 
+/// Finds the references to the token at the given position and checks that exactly the
+/// `expected` ranges of the file are returned, each given as its line followed by its start and
+/// end characters.
+pub(crate) async fn references_check(
+    service: &mut LspService<Backend>,
+    uri: &Url,
+    token_line: i32,
+    token_char: i32,
+    include_declaration: bool,
+    expected: &[(u32, u32, u32)],
+    ids: &mut impl Iterator<Item = i64>,
+) -> Request {
+    let params = json!({
+        "textDocument": {
+            "uri": uri,
+        },
+        "position": {
+            "line": token_line,
+            "character": token_char,
+        },
+        "context": {
+            "includeDeclaration": include_declaration,
+        },
+    });
+    let references = build_request_with_id("textDocument/references", params, ids.next().unwrap());
+    let response = call_request(service, references.clone())
+        .await
+        .unwrap()
+        .unwrap();
+    let value = response.result().unwrap();
+    let locations: Vec<Location> = serde_json::from_value(value.clone()).unwrap_or_else(|error| {
+        panic!(
+            "Failed to deserialize response: {:?} input: {:#?} error: {}",
+            value.clone(),
+            references.clone(),
+            error
+        );
+    });
+    let ranges = locations
+        .iter()
+        .map(|location| {
+            assert_eq!(&location.uri, uri);
+            (
+                location.range.start.line,
+                location.range.start.character,
+                location.range.end.character,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(ranges, expected);
+    references
+}
+
+pub(crate) fn rename_request(
+    uri: &Url,
+    token_line: i32,
+    token_char: i32,
+    new_name: &str,
+    id: i64,
+) -> Request {
+    let params = json!({
+        "textDocument": {
+            "uri": uri,
+        },
+        "position": {
+            "line": token_line,
+            "character": token_char,
+        },
+        "newName": new_name,
+    });
+    build_request_with_id("textDocument/rename", params, id)
+}
+
+/// Renames the token at the given position and checks that exactly the `expected` ranges of the
+/// file are edited, each given as its line followed by its start and end characters.
+pub(crate) async fn rename_check(
+    service: &mut LspService<Backend>,
+    uri: &Url,
+    token_line: i32,
+    token_char: i32,
+    new_name: &str,
+    expected: &[(u32, u32, u32)],
+    ids: &mut impl Iterator<Item = i64>,
+) -> Request {
+    let rename = rename_request(uri, token_line, token_char, new_name, ids.next().unwrap());
+    let response = call_request(service, rename.clone())
+        .await
+        .unwrap()
+        .unwrap();
+    let value = response.result().unwrap();
+    let workspace_edit: WorkspaceEdit =
+        serde_json::from_value(value.clone()).unwrap_or_else(|error| {
+            panic!(
+                "Failed to deserialize response: {:?} input: {:#?} error: {}",
+                value.clone(),
+                rename.clone(),
+                error
+            );
+        });
+    let changes = workspace_edit.changes.unwrap_or_default();
+    assert_eq!(changes.len(), 1, "expected edits to one file: {changes:?}");
+    let mut ranges = changes[uri]
+        .iter()
+        .map(|edit| {
+            assert_eq!(edit.new_text, new_name);
+            (
+                edit.range.start.line,
+                edit.range.start.character,
+                edit.range.end.character,
+            )
+        })
+        .collect::<Vec<_>>();
+    ranges.sort();
+    assert_eq!(ranges, expected);
+    rename
+}
+
 pub(crate) async fn hover_request<'a>(
     service: &mut LspService<Backend>,
     hover_docs: &'a HoverDocumentation<'a>,
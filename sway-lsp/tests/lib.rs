@@ -199,6 +199,31 @@ async fn show_ast() {
     shutdown_and_exit(&mut service).await;
 }
 
+#[tokio::test]
+async fn show_ir() {
+    let (mut service, _) = LspService::build(Backend::new)
+        .custom_method("sway/show_ir", Backend::show_ir)
+        .finish();
+
+    let uri = init_and_open(
+        &mut service,
+        test_fixtures_dir().join("semantic_tokens/src/main.sw"),
+    )
+    .await;
+    // total = total.double();
+    let position = Position::new(33, 10);
+    let contents = lsp::show_ir_request(&mut service, &uri, position, false).await;
+    assert!(contents.starts_with("// fn get"));
+    assert!(contents.contains("// Final IR"));
+    assert!(contents.contains("// Final ASM"));
+    assert!(!contents.contains("// Initial IR"));
+
+    let contents = lsp::show_ir_request(&mut service, &uri, position, true).await;
+    assert!(contents.contains("// Initial IR"));
+    assert!(contents.contains("// IR after "));
+    shutdown_and_exit(&mut service).await;
+}
+
 //------------------- GO TO DEFINITION -------------------//
 
 #[tokio::test]
@@ -866,6 +891,62 @@ async fn go_to_definition_for_paths() {
     shutdown_and_exit(&mut service).await;
 }
 
+#[tokio::test]
+async fn go_to_definition_inside_nested_modules() {
+    let (mut service, _) = LspService::new(Backend::new);
+    let _ = init_and_open(
+        &mut service,
+        test_fixtures_dir().join("tokens/paths/src/main.sw"),
+    )
+    .await;
+    let uri =
+        Url::from_file_path(test_fixtures_dir().join("tokens/paths/src/deep_mod/deeper_mod.sw"))
+            .unwrap();
+    let mut i = 0..;
+
+    let mut go_to = GotoDefinition {
+        req_uri: &uri,
+        req_line: 13,
+        req_char: 24,
+        def_line: 9,
+        def_start_char: 11,
+        def_end_char: 21,
+        def_path: "sway-lsp/tests/fixtures/tokens/paths/src/deep_mod/deeper_mod.sw",
+    };
+    // DeepStruct
+    let _ = lsp::definition_check(&mut service, &go_to, &mut i).await;
+    definition_check_with_req_offset(&mut service, &mut go_to, 14, 7, &mut i).await;
+
+    shutdown_and_exit(&mut service).await;
+}
+
+#[tokio::test]
+async fn go_to_definition_inside_dependencies() {
+    let (mut service, _) = LspService::new(Backend::new);
+    let _ = init_and_open(
+        &mut service,
+        test_fixtures_dir().join("tokens/paths/src/main.sw"),
+    )
+    .await;
+    // The standard library isn't opened, so it's served by the session of the project above.
+    let uri = Url::from_file_path(sway_workspace_dir().join("sway-lib-std/src/option.sw")).unwrap();
+    let mut i = 0..;
+
+    let go_to = GotoDefinition {
+        req_uri: &uri,
+        req_line: 158,
+        req_char: 18,
+        def_line: 31,
+        def_start_char: 7,
+        def_end_char: 13,
+        def_path: "sway-lib-std/src/revert.sw",
+    };
+    // revert
+    let _ = lsp::definition_check(&mut service, &go_to, &mut i).await;
+
+    shutdown_and_exit(&mut service).await;
+}
+
 #[tokio::test]
 async fn go_to_definition_for_traits() {
     let (mut service, _) = LspService::new(Backend::new);
@@ -1483,6 +1564,53 @@ async fn hover_docs_for_functions() {
     let _ = lsp::hover_request(&mut service, &hover, &mut i).await;
 }
 
+#[tokio::test]
+async fn hover_costs_for_functions() {
+    let (mut service, _) = LspService::new(Backend::new);
+    // The fixture is only used here since the build artifacts are written into it.
+    let fixture_dir = test_fixtures_dir().join("function_costs");
+    let entry_point = fixture_dir.join("src/main.sw");
+    let out_dir = fixture_dir.join("out");
+    let _ = fs::remove_dir_all(&out_dir);
+    let _out_dir = RemoveOnDrop(out_dir.clone());
+
+    // Write the artifact `forc build` would have for the contract's `get` method.
+    let source = fs::read_to_string(&entry_point).unwrap();
+    let costs = serde_json::json!([{
+        "name": "get_1",
+        "path": entry_point,
+        "span_start": source.rfind("fn get").unwrap(),
+        "span_end": source.len(),
+        "size_in_bytes": 96,
+        "estimated_gas": 291,
+    }]);
+    let artifact_path =
+        forc_pkg::ArtifactPaths::new(&out_dir.join("debug"), "function_costs").function_costs();
+    fs::create_dir_all(artifact_path.parent().unwrap()).unwrap();
+    fs::write(&artifact_path, costs.to_string()).unwrap();
+
+    let uri = init_and_open(&mut service, entry_point).await;
+    let hover = HoverDocumentation {
+        req_uri: &uri,
+        req_line: 7,
+        req_char: 7,
+        documentation:
+            "```sway\nfn get(amount: u64) -> u64\n```\n---\nLast build: 96 bytes, estimated gas 291",
+    };
+    let mut i = 0..;
+    let _ = lsp::hover_request(&mut service, &hover, &mut i).await;
+    shutdown_and_exit(&mut service).await;
+}
+
+/// Removes a directory when dropped, so it's cleaned up even if the test using it fails.
+struct RemoveOnDrop(PathBuf);
+
+impl Drop for RemoveOnDrop {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
 #[tokio::test]
 async fn hover_docs_for_structs() {
     let (mut service, _) = LspService::new(Backend::new);
@@ -1582,6 +1710,298 @@ async fn hover_docs_with_code_examples() {
     let _ = lsp::hover_request(&mut service, &hover, &mut i).await;
 }
 
+//------------------- REFERENCES -------------------//
+
+#[tokio::test]
+async fn references_to_trait_method() {
+    let (mut service, _) = LspService::new(Backend::new);
+    let uri = init_and_open(&mut service, test_fixtures_dir().join("rename/src/main.sw")).await;
+    let mut i = 0..;
+
+    // square.area()
+    let declarations: [(u32, u32, u32); 3] = [(3, 7, 11), (12, 7, 11), (22, 7, 11)];
+    let calls = [(28, 11, 15), (28, 25, 29)];
+    let _ = lsp::references_check(&mut service, &uri, 28, 12, false, &calls, &mut i).await;
+    let all = [declarations.as_slice(), calls.as_slice()].concat();
+    let _ = lsp::references_check(&mut service, &uri, 28, 12, true, &all, &mut i).await;
+
+    shutdown_and_exit(&mut service).await;
+}
+
+#[tokio::test]
+async fn references_to_storage_field() {
+    let (mut service, _) = LspService::new(Backend::new);
+    let uri = init_and_open(
+        &mut service,
+        test_fixtures_dir().join("tokens/storage/src/main.sw"),
+    )
+    .await;
+    let mut i = 0..;
+
+    // var1: Type1 = ...
+    let expected = [(13, 4, 8), (24, 16, 20), (25, 16, 20), (26, 16, 20)];
+    let _ = lsp::references_check(&mut service, &uri, 13, 5, true, &expected, &mut i).await;
+
+    shutdown_and_exit(&mut service).await;
+}
+
+//------------------- RENAME -------------------//
+
+#[tokio::test]
+async fn rename_trait_method() {
+    let (mut service, _) = LspService::new(Backend::new);
+    let uri = init_and_open(&mut service, test_fixtures_dir().join("rename/src/main.sw")).await;
+    let mut i = 0..;
+
+    // The declaration in the trait, both implementations and the call sites.
+    let expected = [
+        (3, 7, 11),
+        (12, 7, 11),
+        (22, 7, 11),
+        (28, 11, 15),
+        (28, 25, 29),
+    ];
+    // fn area(self) -> u64;
+    let _ = lsp::rename_check(&mut service, &uri, 3, 8, "size", &expected, &mut i).await;
+    // square.area()
+    let _ = lsp::rename_check(&mut service, &uri, 28, 12, "size", &expected, &mut i).await;
+
+    shutdown_and_exit(&mut service).await;
+}
+
+#[tokio::test]
+async fn rename_struct_field() {
+    let (mut service, _) = LspService::new(Backend::new);
+    let uri = init_and_open(&mut service, test_fixtures_dir().join("rename/src/main.sw")).await;
+    let mut i = 0..;
+
+    let expected = [(7, 4, 8), (13, 13, 17), (13, 25, 29)];
+    // side: u64,
+    let _ = lsp::rename_check(&mut service, &uri, 7, 5, "length", &expected, &mut i).await;
+
+    shutdown_and_exit(&mut service).await;
+}
+
+#[tokio::test]
+async fn rename_rejects_conflicts_and_invalid_names() {
+    let (mut service, _) = LspService::new(Backend::new);
+    let uri = init_and_open(&mut service, test_fixtures_dir().join("rename/src/main.sw")).await;
+
+    // side: u64,
+    let rename = lsp::rename_request(&uri, 7, 5, "offset", 1);
+    let response = lsp::call_request(&mut service, rename).await;
+    let err = Response::from_error(
+        1.into(),
+        jsonrpc::Error::invalid_params("\"offset\" is already declared within the same scope"),
+    );
+    assert_eq!(response, Ok(Some(err)));
+
+    // struct Circle
+    let rename = lsp::rename_request(&uri, 17, 8, "Square", 2);
+    let response = lsp::call_request(&mut service, rename).await;
+    let err = Response::from_error(
+        2.into(),
+        jsonrpc::Error::invalid_params("\"Square\" is already declared within the same scope"),
+    );
+    assert_eq!(response, Ok(Some(err)));
+
+    let rename = lsp::rename_request(&uri, 7, 5, "impl", 3);
+    let response = lsp::call_request(&mut service, rename).await;
+    let err = Response::from_error(
+        3.into(),
+        jsonrpc::Error::invalid_params("\"impl\" is not a valid identifier"),
+    );
+    assert_eq!(response, Ok(Some(err)));
+
+    // fn perimeter, which is called with the local `side` in `perimeters`
+    let rename = lsp::rename_request(&uri, 31, 4, "side", 4);
+    let response = lsp::call_request(&mut service, rename).await;
+    let err = Response::from_error(
+        4.into(),
+        jsonrpc::Error::invalid_params("\"side\" is already declared within the same scope"),
+    );
+    assert_eq!(response, Ok(Some(err)));
+
+    // let side = 2;
+    let rename = lsp::rename_request(&uri, 36, 9, "perimeter", 5);
+    let response = lsp::call_request(&mut service, rename).await;
+    let err = Response::from_error(
+        5.into(),
+        jsonrpc::Error::invalid_params("\"perimeter\" is already declared within the same scope"),
+    );
+    assert_eq!(response, Ok(Some(err)));
+
+    shutdown_and_exit(&mut service).await;
+}
+
+//------------------- SEMANTIC TOKENS -------------------//
+
+#[tokio::test]
+async fn semantic_tokens_from_typed_ast() {
+    // Indices of the token types and bits of the token modifiers in the server's legend.
+    const INTERFACE: u32 = 6;
+    const STRUCT: u32 = 4;
+    const TYPE_PARAMETER: u32 = 9;
+    const METHOD: u32 = 11;
+    const PROPERTY: u32 = 12;
+    const VARIABLE: u32 = 13;
+    const PARAMETER: u32 = 14;
+    const DECLARATION: u32 = 1;
+    const READONLY: u32 = 1 << 2;
+    const STATIC: u32 = 1 << 3;
+    const MUTABLE: u32 = 1 << 7;
+    const PUBLIC: u32 = 1 << 8;
+    const TRAIT: u32 = 1 << 9;
+
+    let (mut service, _) = LspService::new(Backend::new);
+    let uri = init_and_open(
+        &mut service,
+        test_fixtures_dir().join("semantic_tokens/src/main.sw"),
+    )
+    .await;
+    let expected = [
+        // OWNER: u64 = 1,
+        (3, 4, VARIABLE, DECLARATION | READONLY | STATIC | PUBLIC),
+        // counter: u64 = 0,
+        (7, 4, PROPERTY, DECLARATION | STATIC),
+        // pub trait Double {
+        (10, 10, INTERFACE, DECLARATION | PUBLIC),
+        // fn double(self) -> Self;
+        (11, 7, METHOD, DECLARATION | TRAIT),
+        // struct Pair<T> {
+        (14, 7, STRUCT, DECLARATION),
+        (14, 12, TYPE_PARAMETER, DECLARATION),
+        // fn get(amount: u64) -> u64 {
+        (31, 11, PARAMETER, DECLARATION),
+        // let mut total = storage.counter + OWNER;
+        (32, 16, VARIABLE, DECLARATION | MUTABLE),
+        (32, 32, PROPERTY, STATIC),
+    ];
+    lsp::semantic_tokens_check(&mut service, &uri, &expected).await;
+    shutdown_and_exit(&mut service).await;
+}
+
+//------------------- SYMBOLS -------------------//
+
+#[tokio::test]
+async fn document_symbols() {
+    let (mut service, _) = LspService::new(Backend::new);
+    let uri = init_and_open(
+        &mut service,
+        test_fixtures_dir().join("semantic_tokens/src/main.sw"),
+    )
+    .await;
+    let expected: &[(&str, &[&str])] = &[
+        ("configurable", &["OWNER"]),
+        ("storage", &["counter"]),
+        ("Double", &["double"]),
+        ("Pair", &["first"]),
+        ("impl Double for u64", &["double"]),
+        ("Counter", &["get"]),
+        ("impl Counter for Contract", &["get"]),
+    ];
+    lsp::document_symbol_check(&mut service, &uri, expected).await;
+    shutdown_and_exit(&mut service).await;
+}
+
+#[tokio::test]
+async fn workspace_symbols() {
+    let (mut service, _) = LspService::new(Backend::new);
+    let _ = init_and_open(
+        &mut service,
+        test_fixtures_dir().join("semantic_tokens/src/main.sw"),
+    )
+    .await;
+
+    let symbols = lsp::workspace_symbol_request(&mut service, "dbl").await;
+    let mut names: Vec<_> = symbols
+        .iter()
+        .map(|symbol| (symbol.name.as_str(), symbol.container_name.as_deref()))
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        vec![
+            ("Double", None),
+            ("double", Some("Double")),
+            ("double", Some("impl Double for u64")),
+            ("impl Double for u64", None),
+        ]
+    );
+
+    // The best matches come first.
+    let symbols = lsp::workspace_symbol_request(&mut service, "count").await;
+    let mut best: Vec<_> = symbols[..2]
+        .iter()
+        .map(|symbol| symbol.name.as_str())
+        .collect();
+    best.sort();
+    assert_eq!(best, vec!["Counter", "counter"]);
+    shutdown_and_exit(&mut service).await;
+}
+
+//------------------- QUICK FIXES -------------------//
+
+#[tokio::test]
+async fn quick_fixes() {
+    let (mut service, _) = LspService::new(Backend::new);
+    let uri = init_and_open(
+        &mut service,
+        test_fixtures_dir().join("quick_fixes/src/main.sw"),
+    )
+    .await;
+    let insert = |line, character, text: &str| {
+        let position = Position::new(line, character);
+        TextEdit::new(Range::new(position, position), text.to_string())
+    };
+
+    // let side = 2;
+    let expected = insert(17, 12, "_");
+    let title = "Prefix `side` with an underscore";
+    let _ = code_actions::quick_fix_check(
+        &mut service,
+        &uri,
+        Position::new(17, 13),
+        title,
+        expected,
+        1,
+    )
+    .await;
+
+    // perimeter(Square { side: 2 })
+    let expected = insert(4, 27, "\nuse shapes::perimeter;");
+    let title = "Import `shapes::perimeter`";
+    let _ = code_actions::quick_fix_check(
+        &mut service,
+        &uri,
+        Position::new(22, 10),
+        title,
+        expected,
+        2,
+    )
+    .await;
+
+    // impl Area for Square {
+    let expected = insert(
+        30,
+        0,
+        "\n    fn scale(self, factor: u64) -> Self {\n        __revert(0)\n    }\n",
+    );
+    let title = "Implement missing methods of `Area`";
+    let _ =
+        code_actions::quick_fix_check(&mut service, &uri, Position::new(26, 6), title, expected, 3)
+            .await;
+
+    // counter() * 2
+    let expected = insert(37, 0, "#[storage(read)]\n");
+    let title = "Add `#[storage(read)]` to `double_counter`";
+    let _ =
+        code_actions::quick_fix_check(&mut service, &uri, Position::new(38, 5), title, expected, 4)
+            .await;
+
+    shutdown_and_exit(&mut service).await;
+}
+
 #[tokio::test]
 async fn publish_diagnostics_dead_code_warning() {
     let (mut service, socket) = LspService::new(Backend::new);
@@ -1,9 +1,9 @@
 use crate::priv_prelude::{Peek, Peeker};
 use crate::{Parse, ParseBracket, ParseResult, ParseToEnd, Parser, ParserConsumed};
 
-use sway_ast::attribute::{Annotated, Attribute, AttributeDecl, AttributeHashKind};
+use sway_ast::attribute::{Annotated, Attribute, AttributeArg, AttributeDecl, AttributeHashKind};
 use sway_ast::brackets::{Parens, SquareBrackets};
-use sway_ast::keywords::{HashBangToken, HashToken, StorageToken, Token};
+use sway_ast::keywords::{EqToken, HashBangToken, HashToken, StorageToken, Token};
 use sway_ast::punctuated::Punctuated;
 use sway_ast::token::{DocComment, DocStyle};
 use sway_error::parser_error::ParseErrorKind;
@@ -47,7 +47,10 @@ impl<T: Parse> Parse for Annotated<T> {
                             doc_comment.span.clone(),
                         ),
                         args: Some(Parens::new(
-                            Punctuated::single(value),
+                            Punctuated::single(AttributeArg {
+                                name: value,
+                                value: None,
+                            }),
                             doc_comment.content_span,
                         )),
                     }),
@@ -102,6 +105,17 @@ impl Parse for Attribute {
     }
 }
 
+impl Parse for AttributeArg {
+    fn parse(parser: &mut Parser) -> ParseResult<Self> {
+        let name = parser.parse()?;
+        let value = match parser.take::<EqToken>() {
+            Some(eq_token) => Some((eq_token, parser.parse()?)),
+            None => None,
+        };
+        Ok(AttributeArg { name, value })
+    }
+}
+
 impl ParseToEnd for Attribute {
     fn parse_to_end<'a, 'e>(mut parser: Parser<'a, '_>) -> ParseResult<(Self, ParserConsumed<'a>)> {
         let attrib = parser.parse()?;
@@ -147,9 +161,12 @@ mod tests {
                     args: Some(Parens(
                       inner: Punctuated(
                         value_separator_pairs: [],
-                        final_value_opt: Some(Ident(
-                          to_string: " This is a doc comment.",
-                          span: (85, 108),
+                        final_value_opt: Some(AttributeArg(
+                          name: Ident(
+                            to_string: " This is a doc comment.",
+                            span: (85, 108),
+                          ),
+                          value: None,
                         )),
                       ),
                       span: (85, 108),
@@ -174,9 +191,12 @@ mod tests {
                     args: Some(Parens(
                       inner: Punctuated(
                         value_separator_pairs: [],
-                        final_value_opt: Some(Ident(
-                          to_string: "read",
-                          span: (131, 135),
+                        final_value_opt: Some(AttributeArg(
+                          name: Ident(
+                            to_string: "read",
+                            span: (131, 135),
+                          ),
+                          value: None,
                         )),
                       ),
                       span: (130, 136),
@@ -1,4 +1,7 @@
-use crate::{Parse, ParseBracket, ParseResult, ParseToEnd, Parser, ParserConsumed, Peek};
+use crate::{
+    item::peek_item_start, Parse, ParseBracket, ParseResult, ParseToEnd, Parser, ParserConsumed,
+    Peek,
+};
 
 use sway_ast::brackets::{Braces, Parens, SquareBrackets};
 use sway_ast::expr::{LoopControlFlow, ReassignmentOp, ReassignmentOpVariant};
@@ -132,9 +135,24 @@ impl ParseToEnd for CodeBlockContents {
             if let Some(consumed) = parser.check_empty() {
                 break (None, consumed);
             }
-            match parse_stmt(&mut parser)? {
-                StmtOrTail::Stmt(s) => statements.push(s),
-                StmtOrTail::Tail(e, c) => break (Some(e), c),
+            match parser.parse_or_rewind(parse_stmt) {
+                Ok(StmtOrTail::Stmt(s)) => statements.push(s),
+                Ok(StmtOrTail::Tail(e, c)) => break (Some(e), c),
+                // Recover by skipping to the end of the statement, or to the start of the next
+                // one if its `;` is missing.
+                Err(_) => {
+                    let mut spans = parser
+                        .skip_until(|parser| {
+                            parser.peek::<SemicolonToken>().is_some()
+                                || parser.peek::<LetToken>().is_some()
+                                || peek_item_start(parser)
+                        })
+                        .into_vec();
+                    if let Some(semicolon_token) = parser.take::<SemicolonToken>() {
+                        spans.push(semicolon_token.span());
+                    }
+                    statements.push(Statement::Error(spans.into()));
+                }
             }
         };
         let code_block_contents = CodeBlockContents {
@@ -1,10 +1,11 @@
 use crate::{Parse, ParseResult, ParseToEnd, Parser, ParserConsumed};
 
 use sway_ast::keywords::{
-    AbiToken, ClassToken, ConfigurableToken, ConstToken, EnumToken, FnToken, ImplToken, ModToken,
-    MutToken, OpenAngleBracketToken, RefToken, SelfToken, SemicolonToken, StorageToken,
-    StructToken, TraitToken, UseToken, WhereToken,
+    AbiToken, ClassToken, ConfigurableToken, ConstToken, EnumToken, FnToken, HashToken, ImplToken,
+    ModToken, MutToken, OpenAngleBracketToken, PubToken, RefToken, SelfToken, SemicolonToken,
+    StorageToken, StructToken, TraitToken, UseToken, WhereToken,
 };
+use sway_ast::token::DocComment;
 use sway_ast::{
     FnArg, FnArgs, FnSignature, ItemConst, ItemEnum, ItemFn, ItemKind, ItemStruct, ItemTrait,
     ItemUse, Submodule, TypeField,
@@ -73,6 +74,26 @@ impl Parse for ItemKind {
     }
 }
 
+/// Returns whether the next tokens could start an item, which is where parsing resumes after an
+/// item that failed to parse.
+pub(crate) fn peek_item_start(parser: &Parser) -> bool {
+    parser.peek::<ModToken>().is_some()
+        || parser.peek::<UseToken>().is_some()
+        || parser.peek::<ClassToken>().is_some()
+        || parser.peek::<StructToken>().is_some()
+        || parser.peek::<EnumToken>().is_some()
+        || parser.peek::<FnToken>().is_some()
+        || parser.peek::<TraitToken>().is_some()
+        || parser.peek::<ImplToken>().is_some()
+        || parser.peek::<AbiToken>().is_some()
+        || parser.peek::<ConstToken>().is_some()
+        || parser.peek::<StorageToken>().is_some()
+        || parser.peek::<ConfigurableToken>().is_some()
+        || parser.peek::<PubToken>().is_some()
+        || parser.peek::<HashToken>().is_some()
+        || parser.peek::<DocComment>().is_some()
+}
+
 impl Parse for TypeField {
     fn parse(parser: &mut Parser) -> ParseResult<TypeField> {
         Ok(TypeField {
@@ -184,9 +205,9 @@ mod tests {
                     .map(|att| {
                         (
                             att.name.as_str(),
-                            att.args
-                                .as_ref()
-                                .map(|arg| arg.get().into_iter().map(|a| a.as_str()).collect()),
+                            att.args.as_ref().map(|arg| {
+                                arg.get().into_iter().map(|a| a.name.as_str()).collect()
+                            }),
                         )
                     })
                     .collect()
@@ -29,8 +29,9 @@ pub use crate::{
 
 use sway_ast::{
     attribute::Annotated,
+    syntax_tree::{SyntaxKind, SyntaxNode},
     token::{DocComment, DocStyle},
-    Module, ModuleKind,
+    Item, ItemKind, Module, ModuleKind,
 };
 use sway_error::handler::{ErrorEmitted, Handler};
 
@@ -45,6 +46,60 @@ pub fn parse_file(
     Parser::new(handler, &ts).parse_to_end().map(|(m, _)| m)
 }
 
+/// Parses the source into a lossless syntax tree, which keeps the whitespace and comments that
+/// the AST drops.
+///
+/// Only lexing errors are reported. The items are found by parsing the module too, and if that
+/// fails the tree just holds the tokens.
+pub fn parse_syntax_tree(
+    handler: &Handler,
+    src: Arc<str>,
+    path: Option<Arc<PathBuf>>,
+) -> Result<SyntaxNode, ErrorEmitted> {
+    let ts = lex_commented(handler, &src, 0, src.len(), &path)?;
+    let mut tree = SyntaxNode::from_token_stream(&ts);
+    if let Ok(module) = parse_file(&Handler::default(), src, path) {
+        let module = module.value;
+        tree.wrap(
+            SyntaxKind::ModuleKind,
+            &Span::join(module.kind.span(), module.semicolon_token.span()),
+        );
+        for item in &module.items {
+            wrap_item(&mut tree, item);
+        }
+    }
+    Ok(tree)
+}
+
+fn annotated_span<T: Spanned>(annotated: &Annotated<T>) -> Span {
+    match annotated.attribute_list.first() {
+        Some(attribute) => Span::join(attribute.span(), annotated.value.span()),
+        None => annotated.value.span(),
+    }
+}
+
+fn wrap_item(tree: &mut SyntaxNode, item: &Item) {
+    tree.wrap(SyntaxKind::Item, &annotated_span(item));
+    let (trait_items, fns) = match &item.value {
+        ItemKind::Impl(item_impl) => {
+            for impl_item in item_impl.contents.get() {
+                tree.wrap(SyntaxKind::Item, &annotated_span(impl_item));
+            }
+            return;
+        }
+        ItemKind::Abi(item_abi) => (item_abi.abi_items.get(), &item_abi.abi_defs_opt),
+        ItemKind::Trait(item_trait) => (item_trait.trait_items.get(), &item_trait.trait_defs_opt),
+        _ => return,
+    };
+    for (trait_item, semicolon_token) in trait_items {
+        let span = Span::join(annotated_span(trait_item), semicolon_token.span());
+        tree.wrap(SyntaxKind::Item, &span);
+    }
+    for item_fn in fns.iter().flat_map(|fns| fns.get()) {
+        tree.wrap(SyntaxKind::Item, &annotated_span(item_fn));
+    }
+}
+
 pub fn parse_module_kind(
     handler: &Handler,
     src: Arc<str>,
@@ -1,11 +1,11 @@
-use crate::{Parse, ParseResult, ParseToEnd, Parser, ParserConsumed};
+use crate::{item::peek_item_start, Parse, ParseResult, ParseToEnd, Parser, ParserConsumed};
 
 use sway_ast::{
-    attribute::{Annotated, Attribute, AttributeHashKind},
+    attribute::{Annotated, Attribute, AttributeArg, AttributeHashKind},
     brackets::SquareBrackets,
     keywords::{HashBangToken, Token},
     token::{DocComment, DocStyle},
-    AttributeDecl, Module, ModuleKind, Parens, Punctuated,
+    AttributeDecl, ItemKind, Module, ModuleKind, Parens, Punctuated,
 };
 use sway_error::parser_error::ParseErrorKind;
 use sway_types::{constants::DOC_COMMENT_ATTRIBUTE_NAME, Ident};
@@ -30,7 +30,14 @@ impl ParseToEnd for Annotated<Module> {
     fn parse_to_end<'a, 'e>(mut parser: Parser<'a, '_>) -> ParseResult<(Self, ParserConsumed<'a>)> {
         // Parse the attribute list.
         let mut attribute_list = Vec::new();
-        while let Some(DocComment { .. }) = parser.peek() {
+        loop {
+            if let Some(attr) = parser.guarded_parse::<HashBangToken, _>()? {
+                attribute_list.push(attr);
+                continue;
+            }
+            if parser.peek::<DocComment>().is_none() {
+                break;
+            }
             let doc_comment = parser.parse::<DocComment>()?;
             // TODO: Use a Literal instead of an Ident when Attribute args
             // start supporting them and remove `Ident::new_no_trim`.
@@ -47,7 +54,10 @@ impl ParseToEnd for Annotated<Module> {
                                 doc_comment.span.clone(),
                             ),
                             args: Some(Parens::new(
-                                Punctuated::single(value),
+                                Punctuated::single(AttributeArg {
+                                    name: value,
+                                    value: None,
+                                }),
                                 doc_comment.content_span,
                             )),
                         }),
@@ -61,7 +71,21 @@ impl ParseToEnd for Annotated<Module> {
         }
         let (kind, semicolon_token) = parser.parse()?;
 
-        let (items, consumed) = parser.parse_to_end()?;
+        // Recover from items that fail to parse by skipping to the start of the next one, so that
+        // a syntax error doesn't lose the rest of the module.
+        let mut items = Vec::new();
+        let consumed = loop {
+            if let Some(consumed) = parser.check_empty() {
+                break consumed;
+            }
+            match parser.parse_or_rewind(|parser| parser.parse::<Annotated<ItemKind>>()) {
+                Ok(item) => items.push(item),
+                Err(_) => items.push(Annotated {
+                    attribute_list: Vec::new(),
+                    value: ItemKind::Error(parser.skip_until(peek_item_start)),
+                }),
+            }
+        };
 
         let module = Annotated {
             attribute_list,
@@ -80,6 +104,284 @@ mod tests {
     use super::*;
     use crate::test_utils::parse_to_end;
     use insta::*;
+    use std::{collections::BTreeSet, sync::Arc};
+    use sway_ast::Statement;
+    use sway_error::handler::Handler;
+
+    #[test]
+    fn recover_from_items_and_statements_that_fail_to_parse() {
+        let handler = Handler::default();
+        let module = crate::parse_file(
+            &handler,
+            Arc::from(
+                r#"
+            script;
+
+            fn broken() -> {}
+
+            fn main() {
+                let x = ;
+                foo(1 2);
+                let y = 2;
+            }
+        "#,
+            ),
+            None,
+        )
+        .expect("the module to be recovered");
+        assert!(!handler.consume().0.is_empty());
+
+        let items = &module.value.items;
+        assert_eq!(items.len(), 2);
+        assert!(matches!(items[0].value, ItemKind::Error(_)));
+        let statements = match &items[1].value {
+            ItemKind::Fn(item_fn) => &item_fn.body.get().statements,
+            item => panic!("expected `main` to be recovered, found {item:?}"),
+        };
+        assert!(matches!(
+            statements.as_slice(),
+            [Statement::Let(_), Statement::Error(_), Statement::Let(_)]
+        ));
+    }
+
+    fn parse_module(src: &str) -> Module {
+        let handler = Handler::default();
+        let module = crate::parse_file(&handler, Arc::from(src), None).unwrap();
+        assert!(handler.consume().0.is_empty());
+        module.value
+    }
+
+    #[test]
+    fn visit_reaches_every_node_kind() {
+        use sway_ast::{visit::*, *};
+
+        #[derive(Default)]
+        struct Recorder {
+            visited: BTreeSet<&'static str>,
+        }
+
+        macro_rules! record {
+            ($($method:ident($node:ty) $(=> $walk:ident)?,)*) => {
+                impl Visit for Recorder {
+                    $(
+                        fn $method(&mut self, _node: &$node) {
+                            self.visited.insert(stringify!($method));
+                            $($walk(self, _node);)?
+                        }
+                    )*
+                }
+
+                const ALL_METHODS: &[&str] = &[$(stringify!($method)),*];
+            };
+        }
+
+        record! {
+            visit_module(Module) => walk_module,
+            visit_item(Item) => walk_item,
+            visit_attribute_decl(AttributeDecl) => walk_attribute_decl,
+            visit_submodule(Submodule) => walk_submodule,
+            visit_item_use(ItemUse) => walk_item_use,
+            visit_use_tree(UseTree) => walk_use_tree,
+            visit_item_struct(ItemStruct) => walk_item_struct,
+            visit_item_enum(ItemEnum) => walk_item_enum,
+            visit_type_field(TypeField) => walk_type_field,
+            visit_item_fn(ItemFn) => walk_item_fn,
+            visit_fn_signature(FnSignature) => walk_fn_signature,
+            visit_fn_arg(FnArg) => walk_fn_arg,
+            visit_item_trait(ItemTrait) => walk_item_trait,
+            visit_traits(Traits) => walk_traits,
+            visit_item_impl(ItemImpl) => walk_item_impl,
+            visit_item_abi(ItemAbi) => walk_item_abi,
+            visit_item_const(ItemConst) => walk_item_const,
+            visit_item_storage(ItemStorage) => walk_item_storage,
+            visit_storage_field(StorageField) => walk_storage_field,
+            visit_item_configurable(ItemConfigurable) => walk_item_configurable,
+            visit_configurable_field(ConfigurableField) => walk_configurable_field,
+            visit_generic_params(GenericParams) => walk_generic_params,
+            visit_generic_args(GenericArgs) => walk_generic_args,
+            visit_where_clause(WhereClause) => walk_where_clause,
+            visit_ty(Ty) => walk_ty,
+            visit_path_type(PathType) => walk_path_type,
+            visit_path_expr(PathExpr) => walk_path_expr,
+            visit_code_block_contents(CodeBlockContents) => walk_code_block_contents,
+            visit_statement(Statement) => walk_statement,
+            visit_statement_let(StatementLet) => walk_statement_let,
+            visit_expr(Expr) => walk_expr,
+            visit_if_expr(IfExpr) => walk_if_expr,
+            visit_match_branch(MatchBranch) => walk_match_branch,
+            visit_asm_block(AsmBlock) => walk_asm_block,
+            visit_assignable(Assignable) => walk_assignable,
+            visit_pattern(Pattern) => walk_pattern,
+            visit_literal(Literal),
+            visit_ident(Ident),
+        }
+
+        let module = parse_module(
+            r#"
+            contract;
+
+            mod other;
+
+            use std::{hash::sha256, storage::*};
+
+            struct Wrapper<T> {
+                value: T,
+            }
+
+            enum Choice {
+                First: u64,
+                Second: (),
+            }
+
+            trait Eq {
+                fn eq(self, other: Self) -> bool;
+            } {
+                fn neq(self, other: Self) -> bool {
+                    !self.eq(other)
+                }
+            }
+
+            abi Counter {
+                #[storage(read, write)]
+                fn increment(amount: u64) -> u64;
+            }
+
+            const LIMIT: u64 = 10;
+
+            storage {
+                counter: u64 = 0,
+            }
+
+            configurable {
+                OWNER: b256 = 0x0000000000000000000000000000000000000000000000000000000000000000,
+            }
+
+            impl Counter for Contract {
+                #[storage(read, write)]
+                fn increment(amount: u64) -> u64 {
+                    let mut total: u64 = amount + 1;
+                    total = match amount {
+                        0 => total,
+                        _ => {
+                            total * 2
+                        },
+                    };
+                    if total > LIMIT { total } else { LIMIT }
+                }
+            }
+
+            fn wrap<T>(value: T) -> Wrapper<T> where T: Eq {
+                let word = asm(r1: value) { r1: u64 };
+                Wrapper { value }
+            }
+        "#,
+        );
+        let mut recorder = Recorder::default();
+        recorder.visit_module(&module);
+        let missed: Vec<_> = ALL_METHODS
+            .iter()
+            .filter(|method| !recorder.visited.contains(*method))
+            .collect();
+        assert!(missed.is_empty(), "never called: {missed:?}");
+    }
+
+    #[test]
+    fn visit_mut_edits_nodes_in_place() {
+        use sway_ast::{visit::Visit, visit_mut::VisitMut};
+        use sway_types::Spanned;
+
+        struct Rename;
+
+        impl VisitMut for Rename {
+            fn visit_ident(&mut self, ident: &mut Ident) {
+                if ident.as_str() == "x" {
+                    *ident = Ident::new_with_override("y".to_string(), ident.span());
+                }
+            }
+        }
+
+        #[derive(Default)]
+        struct Names(Vec<String>);
+
+        impl Visit for Names {
+            fn visit_ident(&mut self, ident: &Ident) {
+                self.0.push(ident.as_str().to_string());
+            }
+        }
+
+        let mut module = parse_module(
+            r#"
+            script;
+
+            fn main(x: u64) -> u64 {
+                let z = x * 2;
+                x + z
+            }
+        "#,
+        );
+        Rename.visit_module(&mut module);
+        let mut names = Names::default();
+        names.visit_module(&module);
+        assert_eq!(names.0, ["main", "y", "u64", "u64", "z", "y", "y", "z"]);
+    }
+
+    #[test]
+    fn fold_rewrites_nested_nodes() {
+        use sway_ast::{
+            fold::{self, Fold},
+            Expr,
+        };
+        use sway_types::Spanned;
+
+        /// Swaps the operands of every addition.
+        struct Commute;
+
+        impl Fold for Commute {
+            fn fold_expr(&mut self, expr: Expr) -> Expr {
+                match fold::walk_expr(self, expr) {
+                    Expr::Add {
+                        lhs,
+                        add_token,
+                        rhs,
+                    } => Expr::Add {
+                        lhs: rhs,
+                        add_token,
+                        rhs: lhs,
+                    },
+                    expr => expr,
+                }
+            }
+        }
+
+        let module = Commute.fold_module(parse_module(
+            r#"
+            script;
+
+            fn main() -> u64 {
+                a + (b + c)
+            }
+        "#,
+        ));
+        let body = match &module.items[0].value {
+            ItemKind::Fn(item_fn) => item_fn.body.get(),
+            item => panic!("expected `main`, found {item:?}"),
+        };
+        let (lhs, rhs) = match body.final_expr_opt.as_deref() {
+            Some(Expr::Add { lhs, rhs, .. }) => (lhs, rhs),
+            expr => panic!("expected an addition, found {expr:?}"),
+        };
+        assert_eq!(rhs.span().as_str(), "a");
+        match &**lhs {
+            Expr::Parens(parens) => match &*parens.inner {
+                Expr::Add { lhs, rhs, .. } => {
+                    assert_eq!(lhs.span().as_str(), "c");
+                    assert_eq!(rhs.span().as_str(), "b");
+                }
+                expr => panic!("expected an addition, found {expr:?}"),
+            },
+            expr => panic!("expected parentheses, found {expr:?}"),
+        }
+    }
 
     #[test]
     fn parse_noop_script_module() {
@@ -85,6 +85,39 @@ impl<'a, 'e> Parser<'a, 'e> {
         self.peek::<G>().map(|_| self.parse()).transpose()
     }
 
+    /// Runs `parse`, rewinding the parser to where it was if that fails.
+    ///
+    /// Used together with [`Parser::skip_until`] to recover from a syntax error by skipping the
+    /// offending tokens and carrying on with the rest of the input.
+    pub fn parse_or_rewind<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Parser<'a, 'e>) -> ParseResult<T>,
+    ) -> ParseResult<T> {
+        let token_trees = self.token_trees;
+        let result = parse(self);
+        if result.is_err() {
+            self.token_trees = token_trees;
+        }
+        result
+    }
+
+    /// Skips the next token tree along with those following it, until `is_recovery_point` holds or
+    /// the end is reached, and returns the spans of the skipped token trees.
+    pub fn skip_until(
+        &mut self,
+        is_recovery_point: impl Fn(&Parser<'a, 'e>) -> bool,
+    ) -> Box<[Span]> {
+        let mut spans = Vec::new();
+        while let [token_tree, rest @ ..] = self.token_trees {
+            spans.push(token_tree.span());
+            self.token_trees = rest;
+            if is_recovery_point(self) {
+                break;
+            }
+        }
+        spans.into()
+    }
+
     pub fn parse_to_end<T: ParseToEnd>(self) -> ParseResult<(T, ParserConsumed<'a>)> {
         T::parse_to_end(self)
     }
@@ -997,4 +997,114 @@ mod tests {
         );
         assert_eq!(tts.next(), None);
     }
+
+    #[test]
+    fn syntax_tree_is_lossless() {
+        use sway_ast::syntax_tree::SyntaxKind;
+
+        let input = r#"
+        //! Module docs.
+        script;
+
+        /* block /* nested */ comment */
+        fn r#main() -> u64 {
+            let x = [1, 2]; // trailing
+            x[0]  	+ "str".len()
+        }
+        "#;
+        let handler = Handler::default();
+        let tree = crate::parse_syntax_tree(&handler, Arc::from(input), None).unwrap();
+        assert!(handler.consume().0.is_empty());
+        assert_eq!(tree.text(), input);
+        let text: String = tree
+            .tokens()
+            .iter()
+            .map(|token| token.span.as_str())
+            .collect();
+        assert_eq!(text, input);
+
+        let ident = tree.token_at_offset(input.find("main").unwrap()).unwrap();
+        assert_eq!(ident.kind, SyntaxKind::Ident);
+        assert_eq!(ident.span.as_str(), "r#main");
+        let comment = tree
+            .token_at_offset(input.find("trailing").unwrap())
+            .unwrap();
+        assert_eq!(comment.kind, SyntaxKind::Comment);
+        assert_eq!(comment.span.as_str(), "// trailing");
+    }
+
+    #[test]
+    fn syntax_tree_trivia_edits() {
+        let parse = |input: &str| {
+            crate::parse_syntax_tree(&Handler::default(), Arc::from(input), None).unwrap()
+        };
+        let before = parse("fn  main( ) {\n// comment\nfoo( 1,2 )\n}");
+        let after = parse("fn main() {\n    // comment\n    foo(1, 2)\n}\n");
+        let edits = before.trivia_edits(&after).unwrap();
+        let edits: Vec<_> = edits
+            .iter()
+            .map(|edit| {
+                (
+                    edit.span.start(),
+                    edit.span.as_str(),
+                    edit.new_text.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            edits,
+            [
+                (2, "  ", " "),
+                (9, " ", ""),
+                (13, "\n// comment\n", "\n    // comment\n    "),
+                (29, " ", ""),
+                (32, "", " "),
+                (33, " ", ""),
+                (37, "", "\n"),
+            ]
+        );
+
+        assert!(before.trivia_edits(&parse("fn main() {}")).is_none());
+    }
+
+    #[test]
+    fn syntax_tree_groups_items() {
+        use sway_ast::syntax_tree::SyntaxKind;
+
+        let input = "library;\n\n/// Docs.\npub fn f() {}\n\nimpl Foo {\n    fn g() {}\n}\n\n\
+                     abi A {\n    fn h();\n} {\n    fn i() {}\n}\n";
+        let tree = crate::parse_syntax_tree(&Handler::default(), Arc::from(input), None).unwrap();
+        let text: String = tree
+            .tokens()
+            .iter()
+            .map(|token| token.span.as_str())
+            .collect();
+        assert_eq!(text, input);
+
+        let module_kind = tree.descendants(SyntaxKind::ModuleKind);
+        assert_eq!(module_kind.len(), 1);
+        assert_eq!(module_kind[0].text(), "library;");
+        let items: Vec<_> = tree
+            .descendants(SyntaxKind::Item)
+            .iter()
+            .map(|item| item.text())
+            .collect();
+        assert_eq!(
+            items,
+            [
+                "/// Docs.\npub fn f() {}",
+                "impl Foo {\n    fn g() {}\n}",
+                "fn g() {}",
+                "abi A {\n    fn h();\n} {\n    fn i() {}\n}",
+                "fn h();",
+                "fn i() {}",
+            ]
+        );
+
+        // Code which doesn't parse keeps its tokens, just without the items.
+        let invalid = "library;\n\nfn f( {}\n";
+        let tree = crate::parse_syntax_tree(&Handler::default(), Arc::from(invalid), None).unwrap();
+        assert_eq!(tree.text(), invalid);
+        assert!(tree.descendants(SyntaxKind::Item).is_empty());
+    }
 }
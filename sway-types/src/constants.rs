@@ -46,12 +46,36 @@ pub const DOC_COMMENT_ATTRIBUTE_NAME: &str = "doc-comment";
 /// The attribute used for Sway in-language unit tests.
 pub const TEST_ATTRIBUTE_NAME: &str = "test";
 
+/// The attribute used for Sway in-language benchmarks.
+pub const BENCH_ATTRIBUTE_NAME: &str = "bench";
+
 /// The valid attribute string used for payable functions.
 pub const PAYABLE_ATTRIBUTE_NAME: &str = "payable";
 
+/// The attribute used to give an ABI method an explicit selector, as in
+/// `#[selector(value = "0x12345678")]`.
+pub const SELECTOR_ATTRIBUTE_NAME: &str = "selector";
+pub const SELECTOR_VALUE_NAME: &str = "value";
+
 /// The valid attribute strings related to allow.
 pub const ALLOW_ATTRIBUTE_NAME: &str = "allow";
 pub const ALLOW_DEAD_CODE_NAME: &str = "dead_code";
+pub const ALLOW_DEPRECATED_NAME: &str = "deprecated";
+
+/// The attributes which, along with `allow`, set the level of lints.
+pub const WARN_ATTRIBUTE_NAME: &str = "warn";
+pub const DENY_ATTRIBUTE_NAME: &str = "deny";
+
+/// The valid attribute strings related to deprecation.
+pub const DEPRECATED_ATTRIBUTE_NAME: &str = "deprecated";
+pub const DEPRECATED_NOTE_NAME: &str = "note";
+
+/// The attribute used to derive trait implementations for structs and enums.
+pub const DERIVE_ATTRIBUTE_NAME: &str = "derive";
+pub const DERIVE_EQ_NAME: &str = "Eq";
+pub const DERIVE_CLONE_NAME: &str = "Clone";
+pub const DERIVE_HASH_NAME: &str = "Hash";
+pub const DERIVE_ABI_ENCODE_NAME: &str = "AbiEncode";
 
 /// The list of valid attributes.
 pub const VALID_ATTRIBUTE_NAMES: &[&str] = &[
@@ -59,7 +83,13 @@ pub const VALID_ATTRIBUTE_NAMES: &[&str] = &[
     DOC_ATTRIBUTE_NAME,
     DOC_COMMENT_ATTRIBUTE_NAME,
     TEST_ATTRIBUTE_NAME,
+    BENCH_ATTRIBUTE_NAME,
     INLINE_ATTRIBUTE_NAME,
     PAYABLE_ATTRIBUTE_NAME,
+    SELECTOR_ATTRIBUTE_NAME,
     ALLOW_ATTRIBUTE_NAME,
+    WARN_ATTRIBUTE_NAME,
+    DENY_ATTRIBUTE_NAME,
+    DERIVE_ATTRIBUTE_NAME,
+    DEPRECATED_ATTRIBUTE_NAME,
 ];
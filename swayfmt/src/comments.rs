@@ -1,11 +1,14 @@
 use ropey::Rope;
-use std::{fmt::Write, ops::Range};
-use sway_ast::token::{Comment, CommentKind};
+use std::{collections::HashMap, fmt::Write, ops::Range, path::PathBuf, sync::Arc};
+use sway_ast::{
+    token::{Comment, CommentKind},
+    Module,
+};
 use sway_types::{Span, Spanned};
 
 use crate::{
     formatter::FormattedCode,
-    parse::parse_snippet,
+    parse::{parse_file, parse_snippet},
     utils::map::{
         byte_span::{ByteSpan, LeafSpans},
         comments::CommentMap,
@@ -100,7 +103,7 @@ pub fn write_comments(
             let newlines = collect_newlines_after_comment(&formatter.comments_context, comment);
 
             match comment.comment_kind {
-                CommentKind::Newlined => {
+                CommentKind::Newlined | CommentKind::Multilined => {
                     write!(
                         formatted_code,
                         "{}{}{}",
@@ -118,7 +121,6 @@ pub fn write_comments(
                     formatted_code.truncate(formatted_code.trim_end().len());
                     write!(formatted_code, " {} ", comment.span().as_str(),)?;
                 }
-                CommentKind::Multilined => {}
             }
         }
     }
@@ -203,6 +205,112 @@ pub fn rewrite_with_comments<T: sway_parse::Parse + Format + LeafSpans>(
     Ok(())
 }
 
+/// Finds the comments of the unformatted code that did not make it into the formatted code.
+///
+/// Comments are compared by their text, since formatting moves them around.
+fn lost_comments(
+    unformatted_comments: &CommentMap,
+    formatted_code: &str,
+) -> Result<Vec<(ByteSpan, Comment)>, FormatterError> {
+    let mut formatted_comments: HashMap<&str, usize> = HashMap::new();
+    let formatted_map = CommentMap::from_src(Arc::from(formatted_code))?;
+    for comment in formatted_map.values() {
+        *formatted_comments
+            .entry(comment.span.as_str().trim_end())
+            .or_default() += 1;
+    }
+    let mut lost_comments = vec![];
+    for (byte_span, comment) in unformatted_comments.iter() {
+        let text = comment.span.as_str().trim_end();
+        match formatted_comments.get_mut(text) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => lost_comments.push((byte_span.clone(), comment.clone())),
+        }
+    }
+    Ok(lost_comments)
+}
+
+/// Reattaches the comments the formatter failed to place to the nearest node, that is the leaf
+/// that followed the comment in the unformatted code. A comment with no leaf after it is appended
+/// to the end of the formatted code.
+///
+/// Line comments are written on their own line above the leaf, block comments right before it.
+pub fn reattach_lost_comments(
+    unformatted_comments: &CommentMap,
+    unformatted_module: &Module,
+    formatted_code: &mut FormattedCode,
+    path: Option<Arc<PathBuf>>,
+) -> Result<(), FormatterError> {
+    let lost_comments = lost_comments(unformatted_comments, formatted_code)?;
+    if lost_comments.is_empty() {
+        return Ok(());
+    }
+    // Leaves line up between both modules, in the same way `handle_newlines` relies on.
+    let formatted_module = parse_file(Arc::from(formatted_code.as_str()), path)?.value;
+    let unformatted_leaf_spans = unformatted_module.leaf_spans();
+    let formatted_leaf_spans = formatted_module.leaf_spans();
+
+    let mut insertions = vec![];
+    for (byte_span, comment) in lost_comments {
+        let text = comment.span.as_str().trim_end();
+        let anchor = unformatted_leaf_spans
+            .iter()
+            .position(|leaf_span| leaf_span.start >= byte_span.end)
+            .and_then(|index| formatted_leaf_spans.get(index));
+        let insertion = match anchor {
+            Some(leaf_span) if text.starts_with("/*") => (leaf_span.start, format!("{text} ")),
+            Some(leaf_span) => {
+                let line_start = formatted_code[..leaf_span.start]
+                    .rfind('\n')
+                    .map_or(0, |newline| newline + 1);
+                let indent: String = formatted_code[line_start..]
+                    .chars()
+                    .take_while(|c| *c == ' ' || *c == '\t')
+                    .collect();
+                (line_start, format!("{indent}{text}\n"))
+            }
+            None => {
+                let separator = if formatted_code.ends_with('\n') {
+                    ""
+                } else {
+                    "\n"
+                };
+                (formatted_code.len(), format!("{separator}{text}\n"))
+            }
+        };
+        insertions.push(insertion);
+    }
+    // Insert back to front so that earlier positions stay valid, keeping comments that share a
+    // position in their original order.
+    for (position, text) in insertions.into_iter().rev() {
+        formatted_code.insert_str(position, &text);
+    }
+    Ok(())
+}
+
+/// Checks that every comment of the unformatted code made it into the formatted code, so that a
+/// comment the formatter failed to place results in an error rather than silently disappearing.
+pub fn check_comments_preserved(
+    unformatted_comments: &CommentMap,
+    unformatted_code: &str,
+    formatted_code: &str,
+) -> Result<(), FormatterError> {
+    let lost_comments = lost_comments(unformatted_comments, formatted_code)?;
+    if lost_comments.is_empty() {
+        Ok(())
+    } else {
+        Err(FormatterError::LostComments(
+            lost_comments
+                .iter()
+                .map(|(byte_span, comment)| {
+                    let line = unformatted_code[..byte_span.start].matches('\n').count() + 1;
+                    format!("{line}: {}", comment.span.as_str().trim_end())
+                })
+                .collect(),
+        ))
+    }
+}
+
 /// Collect extra newline before comment(s). The main purpose of this function is to maintain
 /// newlines between comments when inserting multiple comments at once.
 fn collect_extra_newlines(unformatted_span: Span, comments_found: &Vec<Comment>) -> Vec<usize> {
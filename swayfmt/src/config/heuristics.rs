@@ -36,6 +36,16 @@ impl Heuristics {
                 .unwrap_or(default.use_small_heuristics),
         }
     }
+    /// The width settings to format with. Turning off `use_small_heuristics` takes precedence
+    /// over `heuristics_pref`, so that lines are only wrapped at `max_width` and small structure
+    /// literals and if-else expressions are no longer kept on a single line.
+    pub fn to_width_heuristics(&self, max_width: usize) -> WidthHeuristics {
+        if self.use_small_heuristics {
+            self.heuristics_pref.to_width_heuristics(max_width)
+        } else {
+            WidthHeuristics::off()
+        }
+    }
 }
 
 /// Heuristic settings that can be used to simplify
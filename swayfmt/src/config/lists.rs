@@ -1,5 +1,8 @@
 //! Configuration options related to rewriting a list.
-use crate::config::{user_opts::ListsOptions, whitespace::IndentStyle};
+use crate::{
+    config::{user_opts::ListsOptions, whitespace::IndentStyle},
+    formatter::shape::LineStyle,
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Copy, Clone)]
@@ -74,6 +77,14 @@ impl SeparatorTactic {
             SeparatorTactic::Never
         }
     }
+    /// Whether a list written in the given `LineStyle` should end with a separator.
+    pub(crate) fn has_trailing_separator(self, line_style: LineStyle) -> bool {
+        match self {
+            SeparatorTactic::Always => true,
+            SeparatorTactic::Never => false,
+            SeparatorTactic::Vertical => line_style == LineStyle::Multiline,
+        }
+    }
 }
 
 /// Where to put separator.
@@ -3,7 +3,7 @@ use crate::config::user_opts::OrderingOptions;
 
 #[derive(Debug, Clone)]
 pub struct Ordering {
-    /// Reorder import and extern crate statements alphabetically.
+    /// Sort the imports within a `use` group alphabetically.
     pub reorder_imports: bool,
     /// Reorder module statements alphabetically in group.
     pub reorder_modules: bool,
@@ -14,10 +14,10 @@ pub enum FormatterError {
     FormatError(#[from] std::fmt::Error),
     #[error("Error while adding comments")]
     CommentError,
+    #[error("Formatting would remove the comments on the following lines:\n{}", .0.join("\n"))]
+    LostComments(Vec<String>),
     #[error("Error while formatting newline sequences")]
     NewlineSequenceError,
-    #[error("Cannot format raw hashbang attribute,\nIf this is intended to be a doc comment try using the `//!` syntax instead")]
-    HashBangAttributeError,
 }
 
 #[derive(Debug, Error)]
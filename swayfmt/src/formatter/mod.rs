@@ -1,5 +1,8 @@
 use self::shape::Shape;
-use crate::comments::{write_comments, CommentsContext};
+use crate::comments::{
+    check_comments_preserved, reattach_lost_comments, write_comments, CommentsContext,
+};
+use crate::items::item_use::regroup_imports;
 use crate::parse::parse_file;
 use crate::utils::map::comments::CommentMap;
 use crate::utils::map::{newline::handle_newlines, newline_style::apply_newline_style};
@@ -8,7 +11,10 @@ pub use crate::{
     error::{ConfigError, FormatterError},
 };
 use std::{fmt::Write, path::Path, sync::Arc};
+use sway_ast::syntax_tree::SyntaxEdit;
 use sway_core::BuildConfig;
+use sway_error::handler::Handler;
+use sway_parse::parse_syntax_tree;
 use sway_types::Spanned;
 
 pub(crate) mod shape;
@@ -22,6 +28,16 @@ pub struct Formatter {
 
 pub type FormattedCode = String;
 
+/// The changes which format a source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatEdits {
+    /// Formatting only changes whitespace and comments, so these edits replace the trivia
+    /// between the tokens where it changed.
+    Trivia(Vec<SyntaxEdit>),
+    /// Formatting changes the tokens too, so the whole source is replaced.
+    Replace(FormattedCode),
+}
+
 pub trait Format {
     fn format(
         &self,
@@ -52,7 +68,6 @@ impl Formatter {
         self.shape.apply_width_heuristics(
             self.config
                 .heuristics
-                .heuristics_pref
                 .to_width_heuristics(self.config.whitespace.max_width),
         );
         let src = src.trim();
@@ -65,8 +80,8 @@ impl Formatter {
         let mut raw_formatted_code = String::with_capacity(src.len());
 
         // Collect Span -> Comment mapping from unformatted input.
-        self.comments_context =
-            CommentsContext::new(CommentMap::from_src(Arc::from(src))?, src.to_string());
+        let comment_map = CommentMap::from_src(Arc::from(src))?;
+        self.comments_context = CommentsContext::new(comment_map.clone(), src.to_string());
 
         let module = parse_file(Arc::from(src), path.clone())?.value;
         module.format(&mut raw_formatted_code, self)?;
@@ -85,10 +100,20 @@ impl Formatter {
             Arc::from(src),
             &module,
             Arc::from(formatted_code.clone()),
-            path,
+            path.clone(),
             &mut formatted_code,
             self,
         )?;
+        // Place the comments the formatter failed to write next to the nearest node, and make
+        // sure none remain unaccounted for.
+        reattach_lost_comments(&comment_map, &module, &mut formatted_code, path.clone())?;
+        regroup_imports(
+            &mut formatted_code,
+            self.config.imports.group_imports,
+            self.config.ordering.reorder_imports,
+            path,
+        )?;
+        check_comments_preserved(&comment_map, src, &formatted_code)?;
         // Replace newlines with specified `NewlineStyle`
         apply_newline_style(
             self.config.whitespace.newline_style,
@@ -101,6 +126,24 @@ impl Formatter {
 
         Ok(formatted_code)
     }
+    /// Formats the source like [Formatter::format], but returns the smallest edits that turn the
+    /// source into the formatted code, so editors can keep the cursor and unchanged text intact.
+    pub fn format_edits(
+        &mut self,
+        src: Arc<str>,
+        build_config: Option<&BuildConfig>,
+    ) -> Result<FormatEdits, FormatterError> {
+        let formatted_code = self.format(src.clone(), build_config)?;
+        let handler = Handler::default();
+        let trivia_edits = parse_syntax_tree(&handler, src, None)
+            .ok()
+            .zip(parse_syntax_tree(&handler, Arc::from(formatted_code.as_str()), None).ok())
+            .and_then(|(tree, formatted_tree)| tree.trivia_edits(&formatted_tree));
+        Ok(match trivia_edits {
+            Some(edits) => FormatEdits::Trivia(edits),
+            None => FormatEdits::Replace(formatted_code),
+        })
+    }
     pub(crate) fn with_shape<F, O>(&mut self, new_shape: Shape, f: F) -> O
     where
         F: FnOnce(&mut Self) -> O,
@@ -1,17 +1,19 @@
 use crate::{
+    config::imports::GroupImports,
     formatter::{
         shape::{ExprKind, LineStyle},
         *,
     },
+    parse::parse_file,
     utils::{
         map::byte_span::{ByteSpan, LeafSpans},
         CurlyBrace,
     },
 };
-use std::fmt::Write;
+use std::{fmt::Write, path::PathBuf, sync::Arc};
 use sway_ast::{
     token::{Delimiter, PunctKind},
-    ItemUse, UseTree,
+    Item, ItemKind, ItemUse, UseTree,
 };
 use sway_types::Spanned;
 
@@ -55,6 +57,92 @@ impl Format for ItemUse {
     }
 }
 
+/// Regroups each run of top-level `use` statements in the formatted code as `group_imports`
+/// says, sorting the statements within each new group if `reorder_imports` is set. A run is
+/// ended by any other item or comment, which stay where they are.
+pub(crate) fn regroup_imports(
+    formatted_code: &mut FormattedCode,
+    group_imports: GroupImports,
+    reorder_imports: bool,
+    path: Option<Arc<PathBuf>>,
+) -> Result<(), FormatterError> {
+    if let GroupImports::Preserve = group_imports {
+        return Ok(());
+    }
+    let module = parse_file(Arc::from(formatted_code.as_str()), path)?.value;
+    let mut runs: Vec<Vec<(&Item, &ItemUse)>> = vec![];
+    let mut prev_end = None;
+    for item in &module.items {
+        let item_use = match &item.value {
+            ItemKind::Use(item_use) => item_use,
+            _ => {
+                prev_end = None;
+                continue;
+            }
+        };
+        let contiguous = prev_end.map_or(false, |end| {
+            formatted_code[end..item.span().start()].trim().is_empty()
+        });
+        match runs.last_mut() {
+            Some(run) if contiguous => run.push((item, item_use)),
+            _ => runs.push(vec![(item, item_use)]),
+        }
+        prev_end = Some(item.span().end());
+    }
+
+    let mut edits = vec![];
+    for run in runs.iter().filter(|run| run.len() > 1) {
+        let mut groups: [Vec<(&Item, &ItemUse)>; 3] = Default::default();
+        for &(item, item_use) in run {
+            let group = match group_imports {
+                GroupImports::StdExternalCrate => import_group(item_use),
+                _ => 0,
+            };
+            groups[group].push((item, item_use));
+        }
+        if reorder_imports {
+            for group in &mut groups {
+                group.sort_by_key(|(_, item_use)| item_use.tree.span().as_str().to_lowercase());
+            }
+        }
+        let text = groups
+            .iter()
+            .filter(|group| !group.is_empty())
+            .map(|group| {
+                group
+                    .iter()
+                    .map(|(item, _)| item.span().as_str().to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let (first, _) = run[0];
+        let (last, _) = run[run.len() - 1];
+        edits.push((first.span().start()..last.span().end(), text));
+    }
+    for (range, text) in edits.into_iter().rev() {
+        formatted_code.replace_range(range, &text);
+    }
+
+    Ok(())
+}
+
+/// Returns the group of `StdExternalCrate` that the import belongs to: `std` and `core` imports
+/// first, then other imports, and `self`, `crate` and `super` imports last.
+fn import_group(item_use: &ItemUse) -> usize {
+    let root = match &item_use.tree {
+        UseTree::Path { prefix, .. } => prefix.as_str(),
+        UseTree::Name { name } | UseTree::Rename { name, .. } => name.as_str(),
+        _ => "",
+    };
+    match root {
+        "std" | "core" => 0,
+        "self" | "crate" | "super" => 2,
+        _ => 1,
+    }
+}
+
 impl Format for UseTree {
     fn format(
         &self,
@@ -86,21 +174,34 @@ impl Format for UseTree {
 
                     ord_vec.push(buf);
                 }
-                ord_vec.sort_by_key(|x| x.to_lowercase());
+                if formatter.config.ordering.reorder_imports {
+                    ord_vec.sort_by_key(|x| x.to_lowercase());
+                }
+                let trailing_comma = formatter
+                    .config
+                    .lists
+                    .trailing_comma
+                    .has_trailing_separator(formatter.shape.code_line.line_style);
 
                 match formatter.shape.code_line.line_style {
-                    LineStyle::Multiline => writeln!(
-                        formatted_code,
-                        "{}{}",
-                        formatter.shape.indent.to_string(&formatter.config)?,
-                        ord_vec.join(&format!(
+                    LineStyle::Multiline => {
+                        let mut import_str = ord_vec.join(&format!(
                             "\n{}",
                             formatter.shape.indent.to_string(&formatter.config)?
-                        ))
-                    )?,
+                        ));
+                        if !trailing_comma && import_str.ends_with(PunctKind::Comma.as_char()) {
+                            import_str.pop();
+                        }
+                        writeln!(
+                            formatted_code,
+                            "{}{}",
+                            formatter.shape.indent.to_string(&formatter.config)?,
+                            import_str
+                        )?
+                    }
                     _ => {
                         let mut import_str = ord_vec.join(" ");
-                        if import_str.ends_with(PunctKind::Comma.as_char()) {
+                        if !trailing_comma && import_str.ends_with(PunctKind::Comma.as_char()) {
                             import_str.pop();
                         }
                         write!(formatted_code, "{import_str}")?;
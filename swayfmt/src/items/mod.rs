@@ -7,4 +7,4 @@ mod item_impl;
 mod item_storage;
 mod item_struct;
 mod item_trait;
-mod item_use;
+pub(crate) mod item_use;
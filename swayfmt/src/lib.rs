@@ -14,5 +14,5 @@ mod module;
 pub mod parse;
 mod utils;
 
-pub use crate::formatter::{Format, Formatter};
+pub use crate::formatter::{Format, FormatEdits, Formatter};
 pub use error::FormatterError;
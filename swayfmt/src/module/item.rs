@@ -3,6 +3,7 @@ use crate::{
     utils::map::byte_span::{ByteSpan, LeafSpans},
 };
 use sway_ast::ItemKind::{self, *};
+use sway_types::Spanned;
 
 impl Format for ItemKind {
     fn format(
@@ -22,6 +23,7 @@ impl Format for ItemKind {
             Const(item_const) => item_const.format(formatted_code, formatter),
             Storage(item_storage) => item_storage.format(formatted_code, formatter),
             Configurable(item_configurable) => item_configurable.format(formatted_code, formatter),
+            Error(_) => Ok(()),
         }
     }
 }
@@ -40,6 +42,7 @@ impl LeafSpans for ItemKind {
             Impl(item_impl) => item_impl.leaf_spans(),
             Use(item_use) => item_use.leaf_spans(),
             Configurable(item_configurable) => item_configurable.leaf_spans(),
+            Error(_) => vec![self.span().into()],
         }
     }
 }
@@ -7,7 +7,7 @@ use crate::{
 };
 use std::fmt::Write;
 use sway_ast::{
-    attribute::{Annotated, Attribute, AttributeDecl, AttributeHashKind},
+    attribute::{Annotated, Attribute, AttributeArg, AttributeDecl, AttributeHashKind},
     token::{Delimiter, PunctKind},
 };
 use sway_types::{constants::DOC_COMMENT_ATTRIBUTE_NAME, Spanned};
@@ -54,13 +54,10 @@ impl Format for AttributeDecl {
                 .as_ref()
                 .map(|args| args.inner.final_value_opt.as_ref())
             {
+                let doc_comment = doc_comment.name.as_str().trim_end();
                 match self.hash_kind {
-                    AttributeHashKind::Inner(_) => {
-                        writeln!(formatted_code, "//!{}", doc_comment.as_str().trim_end())?
-                    }
-                    AttributeHashKind::Outer(_) => {
-                        writeln!(formatted_code, "///{}", doc_comment.as_str().trim_end())?
-                    }
+                    AttributeHashKind::Inner(_) => writeln!(formatted_code, "//!{doc_comment}")?,
+                    AttributeHashKind::Outer(_) => writeln!(formatted_code, "///{doc_comment}")?,
                 }
             }
             return Ok(());
@@ -69,10 +66,10 @@ impl Format for AttributeDecl {
         // invariant: attribute lists cannot be empty
         // `#`
         let hash_type_token_span = match &self.hash_kind {
-            AttributeHashKind::Inner(_) => Err(FormatterError::HashBangAttributeError),
-            AttributeHashKind::Outer(hash_token) => Ok(hash_token.span()),
+            AttributeHashKind::Inner(hash_bang_token) => hash_bang_token.span(),
+            AttributeHashKind::Outer(hash_token) => hash_token.span(),
         };
-        write!(formatted_code, "{}", hash_type_token_span?.as_str())?;
+        write!(formatted_code, "{}", hash_type_token_span.as_str())?;
         // `[`
         Self::open_square_bracket(formatted_code, formatter)?;
         let mut regular_attrs = regular_attrs.iter().peekable();
@@ -104,6 +101,21 @@ impl Format for AttributeDecl {
     }
 }
 
+impl Format for AttributeArg {
+    fn format(
+        &self,
+        formatted_code: &mut FormattedCode,
+        formatter: &mut Formatter,
+    ) -> Result<(), FormatterError> {
+        self.name.format(formatted_code, formatter)?;
+        if let Some((eq_token, value)) = &self.value {
+            write!(formatted_code, " {} ", eq_token.span().as_str())?;
+            value.format(formatted_code, formatter)?;
+        }
+        Ok(())
+    }
+}
+
 impl SquareBracket for AttributeDecl {
     fn open_square_bracket(
         line: &mut String,
@@ -157,3 +169,13 @@ impl LeafSpans for Attribute {
         collected_spans
     }
 }
+impl LeafSpans for AttributeArg {
+    fn leaf_spans(&self) -> Vec<ByteSpan> {
+        let mut collected_spans = vec![ByteSpan::from(self.name.span())];
+        if let Some((eq_token, value)) = &self.value {
+            collected_spans.push(ByteSpan::from(eq_token.span()));
+            collected_spans.append(&mut value.leaf_spans());
+        }
+        collected_spans
+    }
+}
@@ -21,6 +21,11 @@ where
         formatter: &mut Formatter,
     ) -> Result<(), FormatterError> {
         if !self.value_separator_pairs.is_empty() || self.final_value_opt.is_some() {
+            let trailing_comma = formatter
+                .config
+                .lists
+                .trailing_comma
+                .has_trailing_separator(formatter.shape.code_line.line_style);
             match formatter.shape.code_line.line_style {
                 LineStyle::Normal => {
                     let value_pairs = &self.value_separator_pairs;
@@ -32,9 +37,14 @@ where
 
                     if let Some(final_value) = &self.final_value_opt {
                         final_value.format(formatted_code, formatter)?;
+                        if trailing_comma {
+                            write!(formatted_code, "{}", PunctKind::Comma.as_char())?;
+                        }
                     } else {
                         formatted_code.pop();
-                        formatted_code.pop();
+                        if !trailing_comma {
+                            formatted_code.pop();
+                        }
                     }
                 }
                 LineStyle::Inline => {
@@ -48,9 +58,14 @@ where
                     }
                     if let Some(final_value) = &self.final_value_opt {
                         final_value.format(formatted_code, formatter)?;
+                        if trailing_comma {
+                            write!(formatted_code, "{}", PunctKind::Comma.as_char())?;
+                        }
                     } else {
                         formatted_code.pop();
-                        formatted_code.pop();
+                        if !trailing_comma {
+                            formatted_code.pop();
+                        }
                     }
                     write!(formatted_code, " ")?;
                 }
@@ -59,7 +74,13 @@ where
                         writeln!(formatted_code)?;
                     }
                     let value_pairs_iter = self.value_separator_pairs.iter();
-                    for (type_field, comma_token) in value_pairs_iter.clone() {
+                    let last_pair_index = match self.final_value_opt {
+                        Some(_) => None,
+                        None => self.value_separator_pairs.len().checked_sub(1),
+                    };
+                    for (pair_index, (type_field, comma_token)) in
+                        value_pairs_iter.clone().enumerate()
+                    {
                         write!(
                             formatted_code,
                             "{}",
@@ -67,7 +88,9 @@ where
                         )?;
                         type_field.format(formatted_code, formatter)?;
 
-                        comma_token.format(formatted_code, formatter)?;
+                        if trailing_comma || Some(pair_index) != last_pair_index {
+                            comma_token.format(formatted_code, formatter)?;
+                        }
                         writeln!(formatted_code)?;
                     }
                     if let Some(final_value) = &self.final_value_opt {
@@ -77,7 +100,10 @@ where
                             &formatter.shape.indent.to_string(&formatter.config)?
                         )?;
                         final_value.format(formatted_code, formatter)?;
-                        writeln!(formatted_code, "{}", PunctKind::Comma.as_char())?;
+                        if trailing_comma {
+                            write!(formatted_code, "{}", PunctKind::Comma.as_char())?;
+                        }
+                        writeln!(formatted_code)?;
                     }
                 }
             }
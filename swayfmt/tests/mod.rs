@@ -1,5 +1,8 @@
 use std::sync::Arc;
-use swayfmt::{config::user_def::FieldAlignment, Formatter};
+use swayfmt::{
+    config::{imports::GroupImports, lists::SeparatorTactic, user_def::FieldAlignment},
+    FormatEdits, Formatter,
+};
 use test_macros::assert_eq_pretty;
 
 /// Takes a configured formatter as input and formats a given input and checks the actual output against an
@@ -71,6 +74,137 @@ pub struct Foo {
     );
 }
 
+#[test]
+fn struct_without_ending_comma() {
+    let mut formatter = Formatter::default();
+    formatter.config.lists.trailing_comma = SeparatorTactic::Never;
+
+    check_with_formatter(
+        r#"contract;
+pub struct Foo {
+    bar: u64,
+    baz: bool,
+}
+"#,
+        r#"contract;
+pub struct Foo {
+    bar: u64,
+    baz: bool
+}
+"#,
+        &mut formatter,
+    );
+}
+
+#[test]
+fn single_line_import_ending_comma() {
+    let mut formatter = Formatter::default();
+    formatter.config.lists.trailing_comma = SeparatorTactic::Always;
+
+    check_with_formatter(
+        r#"contract;
+use foo::{bar, baz};
+"#,
+        r#"contract;
+use foo::{bar, baz,};
+"#,
+        &mut formatter,
+    );
+}
+
+#[test]
+fn import_group_without_reordering() {
+    let mut formatter = Formatter::default();
+    formatter.config.ordering.reorder_imports = false;
+
+    check_with_formatter(
+        r#"contract;
+use foo::{baz, bar};
+"#,
+        r#"contract;
+use foo::{baz, bar};
+"#,
+        &mut formatter,
+    );
+}
+
+#[test]
+fn group_imports_std_external_crate() {
+    let mut formatter = Formatter::default();
+    formatter.config.imports.group_imports = GroupImports::StdExternalCrate;
+
+    check_with_formatter(
+        r#"contract;
+use foo::bar;
+use self::baz;
+use std::hash::sha256;
+
+use ::core::ops::Eq;
+use alpha::beta;
+// keeps the import below in place
+use std::auth::msg_sender;
+"#,
+        r#"contract;
+use ::core::ops::Eq;
+use std::hash::sha256;
+
+use alpha::beta;
+use foo::bar;
+
+use self::baz;
+// keeps the import below in place
+use std::auth::msg_sender;
+"#,
+        &mut formatter,
+    );
+}
+
+#[test]
+fn group_imports_one() {
+    let mut formatter = Formatter::default();
+    formatter.config.imports.group_imports = GroupImports::One;
+    formatter.config.ordering.reorder_imports = false;
+
+    check_with_formatter(
+        r#"contract;
+use std::hash::sha256;
+
+use foo::bar;
+
+use self::baz;
+"#,
+        r#"contract;
+use std::hash::sha256;
+use foo::bar;
+use self::baz;
+"#,
+        &mut formatter,
+    );
+}
+
+#[test]
+fn without_small_heuristics() {
+    let mut formatter = Formatter::default();
+    formatter.config.heuristics.use_small_heuristics = false;
+
+    check_with_formatter(
+        r#"library;
+fn main() {
+    let point = Point { x: 0, y: 0 };
+}
+"#,
+        r#"library;
+fn main() {
+    let point = Point {
+        x: 0,
+        y: 0,
+    };
+}
+"#,
+        &mut formatter,
+    );
+}
+
 #[test]
 fn enum_without_variant_alignment() {
     check(
@@ -1264,6 +1398,40 @@ fn foo() {}
     );
 }
 
+#[test]
+fn attribute_args_with_values() {
+    check(
+        r#"library;
+
+#[deprecated(note="use `bar` instead"), allow(dead_code)]
+fn foo() {}
+"#,
+        r#"library;
+
+#[deprecated(note = "use `bar` instead"), allow(dead_code)]
+fn foo() {}
+"#,
+    );
+}
+
+#[test]
+fn module_lint_attributes() {
+    check(
+        r#"#![allow(dead_code)]
+#![deny( deprecated )]
+library;
+
+fn foo() {}
+"#,
+        r#"#![allow(dead_code)]
+#![deny(deprecated)]
+library;
+
+fn foo() {}
+"#,
+    );
+}
+
 #[test]
 fn stack_of_comma_separated_attributes1() {
     check(
@@ -1486,3 +1654,134 @@ impl MyAbi for Contract {
 "#,
     );
 }
+
+/// Inserts a comment at every point between two tokens of a program, formats it and checks that
+/// the comment is never dropped. The formatter may refuse to format a program rather than lose
+/// one of its comments, but whenever it succeeds the comment must still be there.
+#[test]
+fn comments_are_never_dropped() {
+    let programs = [
+        r#"contract;
+
+struct Foo {
+    bar: u64,
+    baz: bool,
+}
+
+enum Color {
+    Blue: (),
+    Green: u64,
+}
+
+storage {
+    counter: u64 = 0,
+    owner: b256 = 0x0000000000000000000000000000000000000000000000000000000000000000,
+}
+
+configurable {
+    LIMIT: u64 = 10,
+}
+
+abi Counter {
+    #[storage(read, write)]
+    fn increment(amount: u64) -> u64;
+}
+
+impl Counter for Contract {
+    #[storage(read, write)]
+    fn increment(amount: u64) -> u64 {
+        let incremented = storage.counter + amount;
+        storage.counter = incremented;
+        incremented
+    }
+}
+"#,
+        r#"library;
+
+trait Eq {
+    fn eq(self, other: Self) -> bool;
+}
+
+fn pick<T>(value: T, flag: bool) -> u64
+where
+    T: Eq,
+{
+    let result = match flag {
+        true => 1,
+        false => {
+            let fallback = 2;
+            fallback
+        },
+    };
+    if result > 1 { result } else { 0 }
+}
+"#,
+    ];
+
+    for program in programs {
+        Formatter::default()
+            .format(Arc::from(program), None)
+            .expect("the program without any comments to format");
+
+        for (position, character) in program.char_indices() {
+            if !character.is_whitespace() {
+                continue;
+            }
+            let mut comments = vec![format!("/* comment at {position} */")];
+            if character == '\n' {
+                comments.push(format!("// comment at {position}"));
+            }
+            for comment in comments {
+                let commented = format!(
+                    "{} {}{}",
+                    &program[..position],
+                    comment,
+                    &program[position..]
+                );
+                // Comments the formatter fails to place are reattached to the nearest node, so
+                // formatting must succeed and keep the comment.
+                let formatted = Formatter::default()
+                    .format(Arc::from(commented.as_str()), None)
+                    .unwrap_or_else(|error| {
+                        panic!("failed to format `{comment}` in:\n{commented}\n{error:?}")
+                    });
+                assert!(
+                    formatted.contains(&comment),
+                    "`{comment}` was dropped while formatting:\n{commented}\ninto:\n{formatted}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn format_edits_replace_only_trivia() {
+    let src = "script;\n\nfn main() {\n  let x  = 1;\n}\n";
+    let edits = Formatter::default()
+        .format_edits(Arc::from(src), None)
+        .unwrap();
+    let edits = match edits {
+        FormatEdits::Trivia(edits) => edits,
+        FormatEdits::Replace(formatted) => panic!("expected trivia edits, got:\n{formatted}"),
+    };
+    let texts: Vec<_> = edits
+        .iter()
+        .map(|edit| (edit.span.as_str(), edit.new_text.as_str()))
+        .collect();
+    assert_eq!(texts, [("\n  ", "\n    "), ("  ", " ")]);
+
+    // Applying the edits gives the formatted code.
+    let mut edited = src.to_string();
+    for edit in edits.iter().rev() {
+        edited.replace_range(edit.span.start()..edit.span.end(), &edit.new_text);
+    }
+    let formatted = Formatter::default().format(Arc::from(src), None).unwrap();
+    assert_eq!(edited, formatted);
+
+    // Formatting that changes the tokens, e.g. by adding a trailing comma, replaces the source.
+    let src = "script;\n\nstruct Foo {\n    x: u64\n}\n";
+    let edits = Formatter::default()
+        .format_edits(Arc::from(src), None)
+        .unwrap();
+    assert!(matches!(edits, FormatEdits::Replace(_)));
+}
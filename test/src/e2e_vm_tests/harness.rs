@@ -278,6 +278,37 @@ pub(crate) async fn compile_and_run_unit_tests(
     .await
 }
 
+pub(crate) async fn compile_and_run_benches(
+    file_name: &str,
+    run_config: &RunConfig,
+    capture_output: bool,
+) -> (Result<Vec<forc_test::BenchedPackage>>, String) {
+    run_and_capture_output(|| async {
+        tracing::info!("Compiling {} ...", file_name.bold());
+        let manifest_dir = env!("CARGO_MANIFEST_DIR");
+        let path: PathBuf = [
+            manifest_dir,
+            "src",
+            "e2e_vm_tests",
+            "test_programs",
+            file_name,
+        ]
+        .iter()
+        .collect();
+        let built_tests = forc_test::build(forc_test::Opts {
+            pkg: forc_pkg::PkgOpts {
+                path: Some(path.to_string_lossy().into_owned()),
+                locked: run_config.locked,
+                terse: !(capture_output || run_config.verbose),
+                ..Default::default()
+            },
+            ..Default::default()
+        })?;
+        built_tests.run_benches(3)
+    })
+    .await
+}
+
 pub(crate) fn test_json_abi(file_name: &str, built_package: &BuiltPackage) -> Result<()> {
     emit_json_abi(file_name, built_package)?;
     let manifest_dir = env!("CARGO_MANIFEST_DIR");
@@ -34,6 +34,7 @@ enum TestCategory {
     Runs,
     RunsWithContract,
     UnitTestsPass,
+    BenchesPass,
     Disabled,
 }
 
@@ -67,6 +68,7 @@ struct TestDescription {
     validate_storage_slots: bool,
     supported_targets: HashSet<BuildTarget>,
     checker: filecheck::Checker,
+    expected_gas_checkpoints: Option<Vec<u64>>,
 }
 
 #[derive(Clone)]
@@ -101,6 +103,7 @@ impl TestContext {
             validate_abi,
             validate_storage_slots,
             checker,
+            expected_gas_checkpoints,
             ..
         } = test;
 
@@ -142,6 +145,9 @@ impl TestContext {
                 let result = harness::runs_in_vm(compiled.clone(), script_data)?;
                 let result = match result {
                     harness::VMExecutionResult::Fuel(state, receipts) => {
+                        if let Some(expected_gas_checkpoints) = &expected_gas_checkpoints {
+                            check_gas_checkpoints(expected_gas_checkpoints, &receipts)?;
+                        }
                         match state {
                             ProgramState::Return(v) => TestResult::Return(v),
                             ProgramState::ReturnData(digest) => {
@@ -155,6 +161,11 @@ impl TestContext {
                                 TestResult::ReturnData(data)
                             }
                             ProgramState::Revert(v) => TestResult::Revert(v),
+                            // The VM only stops at a debug event when single stepping or at a
+                            // breakpoint, neither of which the tests set.
+                            ProgramState::RunProgram(_) | ProgramState::VerifyPredicate(_) => {
+                                unreachable!("the VM stopped at a debug event")
+                            }
                         }
                     }
                     harness::VMExecutionResult::Evm(state) => match state.exit_reason {
@@ -343,6 +354,52 @@ impl TestContext {
                 Ok(())
             }
 
+            TestCategory::BenchesPass => {
+                let (result, out) =
+                    harness::compile_and_run_benches(&name, &context.run_config, true).await;
+                *output = out;
+
+                let benched_pkgs = result.expect("failed to compile and run benchmarks");
+                let benches = benched_pkgs
+                    .iter()
+                    .flat_map(|benched_pkg| benched_pkg.benches.iter())
+                    .collect::<Vec<_>>();
+                if benches.is_empty() {
+                    panic!("For {name}\nno benchmarks were run");
+                }
+                let failed: Vec<String> = benches
+                    .iter()
+                    .filter(|bench| bench.reverted() || bench.gas_used == 0)
+                    .map(|bench| {
+                        format!(
+                            "Benchmark '{}' failed with state {:?} using {} gas\n",
+                            bench.name, bench.state, bench.gas_used,
+                        )
+                    })
+                    .collect();
+                if !failed.is_empty() {
+                    panic!(
+                        "For {name}\n{} benchmarks failed:\n{}",
+                        failed.len(),
+                        failed.into_iter().collect::<String>()
+                    );
+                }
+
+                // Gas usage is deterministic, so the benchmarks never regress against themselves.
+                let baseline = forc_test::BenchBaseline::from_benched(&benched_pkgs);
+                for benched_pkg in &benched_pkgs {
+                    for bench in &benched_pkg.benches {
+                        let comparison = baseline.compare(benched_pkg, bench);
+                        assert!(
+                            comparison.map_or(false, |comparison| !comparison.is_regression()),
+                            "For {name}\nbenchmark '{}' regressed against itself: {comparison:?}",
+                            bench.name
+                        );
+                    }
+                }
+                Ok(())
+            }
+
             category => Err(anyhow::Error::msg(format!(
                 "Unexpected test category: {category:?}",
             ))),
@@ -584,6 +641,7 @@ fn parse_test_toml(path: &Path) -> Result<TestDescription> {
             Some("compile") => Ok(TestCategory::Compiles),
             Some("disabled") => Ok(TestCategory::Disabled),
             Some("unit_tests_pass") => Ok(TestCategory::UnitTestsPass),
+            Some("benches_pass") => Ok(TestCategory::BenchesPass),
             None => Err(anyhow!(
                 "Malformed category '{category_val}', should be a string."
             )),
@@ -612,6 +670,7 @@ fn parse_test_toml(path: &Path) -> Result<TestDescription> {
         TestCategory::Compiles
         | TestCategory::FailsToCompile
         | TestCategory::UnitTestsPass
+        | TestCategory::BenchesPass
         | TestCategory::Disabled => None,
     };
 
@@ -622,6 +681,7 @@ fn parse_test_toml(path: &Path) -> Result<TestDescription> {
         TestCategory::Compiles
         | TestCategory::FailsToCompile
         | TestCategory::UnitTestsPass
+        | TestCategory::BenchesPass
         | TestCategory::Disabled => None,
     };
 
@@ -691,6 +751,23 @@ fn parse_test_toml(path: &Path) -> Result<TestDescription> {
         supported_targets
     });
 
+    let expected_gas_checkpoints = match toml_content.get("expected_gas_checkpoints") {
+        None => None,
+        Some(ids) => Some(
+            ids.as_array()
+                .ok_or_else(|| anyhow!("Gas checkpoints must be an array of integers."))
+                .and_then(|vals| {
+                    vals.iter()
+                        .map(|val| {
+                            val.as_integer()
+                                .and_then(|id| u64::try_from(id).ok())
+                                .ok_or_else(|| anyhow!("Gas checkpoints must be u64 ids."))
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                })?,
+        ),
+    };
+
     Ok(TestDescription {
         name,
         category,
@@ -702,9 +779,33 @@ fn parse_test_toml(path: &Path) -> Result<TestDescription> {
         validate_storage_slots,
         supported_targets,
         checker,
+        expected_gas_checkpoints,
     })
 }
 
+/// Check that the gas checkpoint logs within `receipts` are for the `expected` checkpoint ids, in
+/// order, and that the gas they record never increases.
+fn check_gas_checkpoints(expected: &[u64], receipts: &[fuel_tx::Receipt]) -> Result<()> {
+    let checkpoints = receipts
+        .iter()
+        .filter_map(|receipt| match receipt {
+            fuel_tx::Receipt::Log { ra, rb, rc, .. } if *rb == sway_ir::GAS_CHECKPOINT_LOG_ID => {
+                Some((*rc, *ra))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let ids = checkpoints.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+    if ids != expected {
+        bail!("expected gas checkpoints: {expected:?}\nactual: {ids:?}");
+    }
+    if checkpoints.windows(2).any(|pair| pair[1].1 > pair[0].1) {
+        bail!("gas checkpoints record increasing gas: {checkpoints:?}");
+    }
+    Ok(())
+}
+
 fn get_test_abi_from_value(value: &toml::Value) -> Result<BuildTarget> {
     match value.as_str() {
         Some(target) => match target {
@@ -0,0 +1,12 @@
+script;
+
+#[derive(Debug)]
+struct Point {
+    x: u64,
+    y: u64,
+}
+
+#[derive(Eq)]
+fn f() {}
+
+fn main() {}
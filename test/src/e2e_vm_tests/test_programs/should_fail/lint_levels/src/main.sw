@@ -0,0 +1,24 @@
+#![deny(dead_code)]
+script;
+
+fn denied() {}
+
+#[allow(dead_code)]
+fn allowed() {}
+
+#[warn(dead_code)]
+fn warned() {}
+
+#[deny(deprecated)]
+fn uses_deprecated() -> u64 {
+    old()
+}
+
+#[deprecated]
+fn old() -> u64 {
+    0
+}
+
+fn main() -> u64 {
+    uses_deprecated()
+}
@@ -0,0 +1,8 @@
+script;
+
+#[deny(shadowing)]
+fn main() -> u64 {
+    asm(zero: 5) {
+        zero: u64
+    }
+}
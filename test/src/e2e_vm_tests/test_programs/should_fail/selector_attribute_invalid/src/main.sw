@@ -0,0 +1,13 @@
+contract;
+
+abi MyContract {
+    #[selector(value = "0x1234")]
+    fn foo() -> u64;
+}
+
+impl MyContract for Contract {
+    #[selector(value = "0x1234")]
+    fn foo() -> u64 {
+        1
+    }
+}
@@ -0,0 +1,12 @@
+contract;
+
+abi MyContract {
+    #[selector(value = "0x12345678")]
+    fn foo() -> u64;
+}
+
+impl MyContract for Contract {
+    fn foo() -> u64 {
+        1
+    }
+}
@@ -0,0 +1,20 @@
+contract;
+
+abi MyContract {
+    #[selector(value = "0x12345678")]
+    fn foo() -> u64;
+    #[selector(value = "0x12345678")]
+    fn bar() -> u64;
+}
+
+impl MyContract for Contract {
+    #[selector(value = "0x12345678")]
+    fn foo() -> u64 {
+        1
+    }
+
+    #[selector(value = "0x12345678")]
+    fn bar() -> u64 {
+        2
+    }
+}
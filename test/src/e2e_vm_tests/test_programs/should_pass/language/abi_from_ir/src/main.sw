@@ -0,0 +1,16 @@
+script;
+
+// `VERBOSE` is frozen to `false` in `Forc.toml`, so it's compiled as a constant and the log in the
+// branch it guards is removed by the optimizer.  Neither the configurable nor the `bool` log type
+// should be in the JSON ABI, while the `u64` log which remains in the bytecode is.
+configurable {
+    VERBOSE: bool = true,
+}
+
+fn main() -> u64 {
+    if VERBOSE {
+        __log(true);
+    }
+    __log(42);
+    42
+}
@@ -0,0 +1,51 @@
+script;
+
+#[deprecated(note = "use `new_function` instead")]
+fn old_function() -> u64 {
+    1
+}
+
+#[deprecated]
+struct OldStruct {
+    x: u64,
+}
+
+#[deprecated(note = "use `NewEnum` instead")]
+enum OldEnum {
+    A: (),
+}
+
+struct S {}
+
+impl S {
+    #[deprecated]
+    fn old_method(self) -> u64 {
+        2
+    }
+}
+
+#[allow(deprecated)]
+fn allowed() -> u64 {
+    let s = OldStruct { x: 3 };
+    old_function() + s.x
+}
+
+fn takes_old(s: OldStruct) -> u64 {
+    s.x
+}
+
+fn make_enum() -> OldEnum {
+    OldEnum::A
+}
+
+fn main() -> u64 {
+    let s = OldStruct { x: 4 };
+    let e = OldEnum::A;
+    let v = match e {
+        OldEnum::A => 5,
+    };
+    let t = S {};
+    let u: (u64, OldStruct) = (6, OldStruct { x: 7 });
+    let _ = make_enum();
+    old_function() + t.old_method() + allowed() + s.x + v + takes_old(u.1)
+}
@@ -0,0 +1,44 @@
+script;
+
+#[derive(Eq)]
+struct Point {
+    x: u64,
+    y: u64,
+}
+
+#[derive(Eq)]
+struct Wrapper<T> {
+    inner: T,
+}
+
+#[derive(Eq)]
+struct Empty {}
+
+#[derive(Eq)]
+enum Shape {
+    Dot: (),
+    Line: Point,
+    Boxed: Wrapper<Point>,
+}
+
+fn main() -> bool {
+    let a = Point { x: 1, y: 2 };
+    let b = Point { x: 1, y: 2 };
+    let c = Point { x: 2, y: 1 };
+    assert(a == b);
+    assert(a != c);
+
+    assert(Wrapper { inner: 42 } == Wrapper { inner: 42 });
+    assert(Wrapper { inner: a } != Wrapper { inner: c });
+
+    assert(Empty {} == Empty {});
+
+    assert(Shape::Dot == Shape::Dot);
+    assert(Shape::Line(a) == Shape::Line(b));
+    assert(Shape::Line(a) != Shape::Line(c));
+    assert(Shape::Dot != Shape::Line(a));
+    assert(Shape::Boxed(Wrapper { inner: a }) == Shape::Boxed(Wrapper { inner: b }));
+    assert(Shape::Boxed(Wrapper { inner: a }) != Shape::Line(a));
+
+    true
+}
@@ -0,0 +1,71 @@
+script;
+
+#[derive(Eq, Clone, Hash, AbiEncode)]
+struct Point {
+    x: u64,
+    y: u64,
+}
+
+#[derive(Eq, Clone, Hash)]
+struct Wrapper<T> {
+    inner: T,
+}
+
+#[derive(Eq, Clone, Hash, AbiEncode)]
+enum Shape {
+    Dot: (),
+    Line: Point,
+}
+
+fn hash_of<T>(value: T) -> b256 where T: Hash {
+    let mut hasher = Hasher::new();
+    value.hash(hasher);
+    hasher.finish()
+}
+
+fn main() -> bool {
+    let a = Point { x: 1, y: 2 };
+    let c = Point { x: 2, y: 1 };
+
+    // Clone
+    assert(a.clone() == a);
+    let w = Wrapper { inner: a };
+    assert(w.clone() == w);
+    assert(Shape::Dot.clone() == Shape::Dot);
+    assert(Shape::Line(a).clone() == Shape::Line(a));
+
+    // Hash
+    assert(hash_of(a) == hash_of(Point { x: 1, y: 2 }));
+    assert(hash_of(a) != hash_of(c));
+    assert(hash_of(Wrapper { inner: a }) == hash_of(Wrapper { inner: a }));
+    assert(hash_of(Wrapper { inner: a }) != hash_of(Wrapper { inner: c }));
+    assert(hash_of(Shape::Dot) == hash_of(Shape::Dot));
+    assert(hash_of(Shape::Dot) != hash_of(Shape::Line(a)));
+    assert(hash_of(Shape::Line(a)) != hash_of(Shape::Line(c)));
+
+    // AbiEncode
+    let mut buffer = Bytes::new();
+    a.abi_encode(buffer);
+    assert(buffer.len() == 16);
+    assert(buffer.get(7).unwrap() == 1u8);
+    assert(buffer.get(15).unwrap() == 2u8);
+
+    // Enums are padded to their largest variant.
+    let mut buffer = Bytes::new();
+    Shape::Dot.abi_encode(buffer);
+    assert(buffer.len() == 24);
+    let mut i = 0;
+    while i < 24 {
+        assert(buffer.get(i).unwrap() == 0u8);
+        i += 1;
+    }
+
+    let mut buffer = Bytes::new();
+    Shape::Line(a).abi_encode(buffer);
+    assert(buffer.len() == 24);
+    assert(buffer.get(7).unwrap() == 1u8);
+    assert(buffer.get(15).unwrap() == 1u8);
+    assert(buffer.get(23).unwrap() == 2u8);
+
+    true
+}
@@ -0,0 +1,15 @@
+script;
+
+fn main() -> u64 {
+    __gas_checkpoint(1);
+
+    let mut i = 0;
+    let mut sum = 0;
+    while i < 10 {
+        sum = sum + i;
+        i = i + 1;
+    }
+
+    __gas_checkpoint(2);
+    sum
+}
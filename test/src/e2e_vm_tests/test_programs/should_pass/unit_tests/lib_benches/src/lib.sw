@@ -0,0 +1,21 @@
+library;
+
+fn sum_to(n: u64) -> u64 {
+    let mut sum = 0;
+    let mut i = 0;
+    while i < n {
+        i += 1;
+        sum += i;
+    }
+    sum
+}
+
+#[bench]
+fn bench_sum_to_ten() {
+    assert(sum_to(10) == 55);
+}
+
+#[bench]
+fn bench_sum_to_hundred() {
+    assert(sum_to(100) == 5050);
+}
@@ -10,7 +10,7 @@ fn main() -> u64 {
 
 // ::check-ir::
 
-// regex: CONFIG=c\d+
+// regex: CONFIG=[[:alpha:]_][[:alnum:]_:]*
 
 // check: script {
 // check: $(c0=$CONFIG) = config u64 42, $(config_mds=$MD)
@@ -11,7 +11,7 @@ fn main() -> (u64, u64) {
 
 // ::check-ir::
 
-// regex: CONFIG=c\d+
+// regex: CONFIG=[[:alpha:]_][[:alnum:]_:]*
 
 // check: script {
 // check: $(c0=$CONFIG) = config u64 42, $(config0_mds=$MD)
@@ -0,0 +1,22 @@
+contract;
+
+abi Documented {
+    /// Returns the answer.
+    ///
+    /// It is always `42`.
+    fn answer() -> u64;
+}
+
+impl Documented for Contract {
+    fn answer() -> u64 {
+        42
+    }
+}
+
+// The implementation has no doc comment of its own, so it inherits the one in the ABI.
+
+// regex: SEL=[0-9a-f]{8}
+
+// check: fn answer<$SEL>() -> u64, $(answer_md=$MD) {
+// check: $(doc_md=$MD) = doc_comment "Returns the answer." "" "It is always `42`."
+// check: $answer_md = ($MD $doc_md)